@@ -32,9 +32,12 @@ use openidconnect::{
     reqwest::async_http_client,
     ClientId, ClientSecret, IssuerUrl, RedirectUrl,
 };
-use router_resolver::{config_handler, graphql_playground, integration_webhook, upload_file};
+use router_resolver::{
+    config_handler, csv_export, graphql_playground, integration_webhook, shared_collection,
+    stored_image, upload_file,
+};
 use sea_orm::DatabaseConnection;
-use statistics_resolver::StatisticsQuery;
+use statistics_resolver::{StatisticsMutation, StatisticsQuery};
 use statistics_service::StatisticsService;
 use supporting_service::SupportingService;
 use tower_http::{
@@ -69,6 +72,7 @@ pub async fn create_app_services(
     let file_storage_service = Arc::new(FileStorageService::new(
         s3_client,
         config.file_storage.s3_bucket_name.clone(),
+        config.file_storage.image_proxy_url.clone(),
     ));
     let cache_service = CacheService::new(&db);
     let supporting_service = Arc::new(
@@ -107,6 +111,7 @@ pub async fn create_app_services(
     .data(exercise_service.clone())
     .data(statistics_service.clone())
     .data(collection_service.clone())
+    .data(integration_service.clone())
     .data(file_storage_service.clone())
     .data(miscellaneous_service.clone())
     .finish();
@@ -136,7 +141,12 @@ pub async fn create_app_services(
         .route("/config", get(config_handler))
         .route("/graphql", gql)
         .route("/upload", post(upload_file))
+        .route("/images/*key", get(stored_image))
+        .route("/shared/collection/:token", get(shared_collection))
+        .route("/export/csv", get(csv_export))
         .layer(Extension(config.clone()))
+        .layer(Extension(exporter_service.clone()))
+        .layer(Extension(collection_service.clone()))
         .layer(Extension(integration_service.clone()))
         .layer(Extension(schema))
         .layer(TowerTraceLayer::new_for_http())
@@ -206,6 +216,7 @@ pub struct MutationRoot(
     ExporterMutation,
     ExerciseMutation,
     FileStorageMutation,
+    StatisticsMutation,
     CollectionMutation,
     UserMutation,
 );
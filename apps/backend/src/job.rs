@@ -1,7 +1,11 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use apalis::prelude::*;
-use background::{ApplicationJob, CoreApplicationJob, ScheduledJob};
+use background::{record_job_outcome, ApplicationJob, CoreApplicationJob, ScheduledJob};
+use chrono::Utc;
 use common_utils::ryot_log;
 use exporter_service::ExporterService;
 use fitness_service::ExerciseService;
@@ -10,12 +14,29 @@ use integration_service::IntegrationService;
 use media_models::CommitMediaInput;
 use miscellaneous_service::MiscellaneousService;
 use statistics_service::StatisticsService;
+use tokio::time::sleep;
+
+// DEV: Replicas start their cron streams at the same time, the jitter spreads
+// them out so that they do not all hit the providers at once
+async fn sleep_for_jitter(max_seconds: u64) {
+    if max_seconds == 0 {
+        return;
+    }
+    let seconds = u64::from(Utc::now().timestamp_subsec_nanos()) % (max_seconds + 1);
+    ryot_log!(
+        debug,
+        "Waiting {} seconds before running scheduled job",
+        seconds
+    );
+    sleep(Duration::from_secs(seconds)).await;
+}
 
 pub async fn background_jobs(
     information: ScheduledJob,
     misc_service: Data<Arc<MiscellaneousService>>,
 ) -> Result<(), Error> {
     ryot_log!(debug, "Running job at {:#?}", information.0);
+    sleep_for_jitter(misc_service.0.config.scheduler.jitter_seconds).await;
     misc_service.perform_background_jobs().await.unwrap();
     Ok(())
 }
@@ -23,8 +44,52 @@ pub async fn background_jobs(
 pub async fn sync_integrations_data(
     _information: ScheduledJob,
     integration_service: Data<Arc<IntegrationService>>,
+    misc_service: Data<Arc<MiscellaneousService>>,
 ) -> Result<(), Error> {
+    sleep_for_jitter(integration_service.0.config.scheduler.jitter_seconds).await;
     integration_service.yank_integrations_data().await.unwrap();
+    integration_service
+        .alert_stale_integrations()
+        .await
+        .unwrap();
+    integration_service
+        .requeue_stuck_webhook_deliveries()
+        .await
+        .unwrap();
+    integration_service
+        .retry_outbound_webhook_deliveries()
+        .await
+        .unwrap();
+    misc_service
+        .process_notification_deliveries()
+        .await
+        .unwrap();
+    misc_service
+        .requeue_postponed_metadata_updates()
+        .await
+        .unwrap();
+    Ok(())
+}
+
+pub async fn refresh_in_progress_media(
+    _information: ScheduledJob,
+    misc_service: Data<Arc<MiscellaneousService>>,
+) -> Result<(), Error> {
+    sleep_for_jitter(misc_service.0.config.scheduler.jitter_seconds).await;
+    misc_service.refresh_in_progress_media().await.unwrap();
+    Ok(())
+}
+
+pub async fn scheduled_exports(
+    _information: ScheduledJob,
+    exporter_service: Data<Arc<ExporterService>>,
+) -> Result<(), Error> {
+    sleep_for_jitter(exporter_service.0.config.scheduler.jitter_seconds).await;
+    // DEV: Failures of single users are part of the status, this only fails when the
+    // users could not be listed or the status could not be saved
+    if let Err(e) = exporter_service.perform_scheduled_exports().await {
+        ryot_log!(error, "Scheduled exports failed: {:?}", e);
+    }
     Ok(())
 }
 
@@ -50,7 +115,18 @@ pub async fn perform_core_application_job(
             .bulk_progress_update(user_id, input)
             .await
             .is_ok(),
+        CoreApplicationJob::ProcessIntegrationWebhookDeliveries(integration_id) => {
+            integration_service
+                .process_webhook_deliveries(integration_id)
+                .await
+                .is_ok()
+        }
+        CoreApplicationJob::ProcessOutboundWebhookDeliveries(integration_id) => integration_service
+            .process_outbound_webhook_deliveries(integration_id)
+            .await
+            .is_ok(),
     };
+    record_job_outcome(status);
     ryot_log!(
         trace,
         "Job: {:#?}, Time Taken: {}ms, Successful = {}",
@@ -111,6 +187,10 @@ pub async fn perform_application_job(
         ApplicationJob::RecalculateCalendarEvents => {
             misc_service.recalculate_calendar_events().await.is_ok()
         }
+        ApplicationJob::RecalculateSortTitles(recalculate_all) => misc_service
+            .recalculate_sort_titles(recalculate_all)
+            .await
+            .is_ok(),
         ApplicationJob::PerformBackgroundTasks => {
             misc_service.perform_background_jobs().await.is_ok()
         }
@@ -144,6 +224,7 @@ pub async fn perform_application_job(
             .await
             .is_ok(),
     };
+    record_job_outcome(status);
     ryot_log!(
         trace,
         "Job: {:#?}, Time Taken: {}ms, Successful = {}",
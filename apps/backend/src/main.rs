@@ -2,14 +2,13 @@ use std::{
     env,
     fs::{self, create_dir_all},
     path::PathBuf,
-    str::FromStr,
     sync::{Arc, Mutex},
     time::Duration,
 };
 
 use anyhow::{bail, Result};
 use apalis::{
-    cron::{CronStream, Schedule},
+    cron::CronStream,
     layers::{
         limit::RateLimitLayer as ApalisRateLimitLayer, tracing::TraceLayer as ApalisTraceLayer,
     },
@@ -17,14 +16,20 @@ use apalis::{
     utils::TokioExecutor,
 };
 use aws_sdk_s3::config::Region;
-use background::ApplicationJob;
+use background::{ApplicationJob, ScheduledJobKind};
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use common_utils::{convert_naive_to_utc, ryot_log, COMPILATION_TIMESTAMP, PROJECT_NAME, TEMP_DIR};
-use database_models::prelude::Exercise;
+use database_models::{
+    metadata,
+    prelude::{Exercise, Metadata},
+};
 use env_utils::{APP_VERSION, UNKEY_API_ID};
 use logs_wheel::LogFileInitializer;
 use migrations::Migrator;
-use sea_orm::{ConnectionTrait, Database, DatabaseConnection, EntityTrait, PaginatorTrait};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, Database, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter,
+};
 use sea_orm_migration::MigratorTrait;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -41,7 +46,7 @@ use crate::{
     common::create_app_services,
     job::{
         background_jobs, perform_application_job, perform_core_application_job,
-        sync_integrations_data,
+        refresh_in_progress_media, scheduled_exports, sync_integrations_data,
     },
 };
 
@@ -68,6 +73,18 @@ async fn main() -> Result<()> {
     ryot_log!(info, "Running version: {}", APP_VERSION);
 
     let config = Arc::new(config::load_app_config()?);
+    let config_report = config.validate();
+    for warning in config_report.warnings.iter() {
+        ryot_log!(warn, "Config: {}", warning);
+    }
+    if !config_report.errors.is_empty() {
+        let errors = config_report.errors.iter().map(|e| format!("  - {e}"));
+        bail!(
+            "The configuration has {} problem(s):\n{}",
+            config_report.errors.len(),
+            errors.collect::<Vec<_>>().join("\n")
+        );
+    }
     if config.server.sleep_before_startup_seconds > 0 {
         let duration = TokioDuration::from_secs(config.server.sleep_before_startup_seconds);
         ryot_log!(info, "Sleeping for {:?} before starting up...", duration);
@@ -75,7 +92,24 @@ async fn main() -> Result<()> {
     }
 
     let rate_limit_count = config.scheduler.rate_limit_num;
-    let sync_every_minutes = config.integration.sync_every_minutes;
+    let background_jobs_schedule = match ScheduledJobKind::BackgroundJobs.schedule(&config) {
+        Ok(schedule) => schedule,
+        Err(e) => bail!(e),
+    };
+    let sync_integrations_data_schedule =
+        match ScheduledJobKind::SyncIntegrationsData.schedule(&config) {
+            Ok(schedule) => schedule,
+            Err(e) => bail!(e),
+        };
+    let refresh_in_progress_media_schedule =
+        match ScheduledJobKind::RefreshInProgressMedia.schedule(&config) {
+            Ok(schedule) => schedule,
+            Err(e) => bail!(e),
+        };
+    let scheduled_exports_schedule = match ScheduledJobKind::ScheduledExports.schedule(&config) {
+        Ok(schedule) => schedule,
+        Err(e) => bail!(e),
+    };
     let disable_background_jobs = config.server.disable_background_jobs;
 
     let compile_timestamp = Utc.timestamp_opt(COMPILATION_TIMESTAMP, 0).unwrap();
@@ -145,6 +179,18 @@ async fn main() -> Result<()> {
             .unwrap();
     }
 
+    if Metadata::find()
+        .filter(metadata::Column::SortTitle.is_null())
+        .count(&db)
+        .await?
+        > 0
+    {
+        perform_application_job_storage
+            .enqueue(ApplicationJob::RecalculateSortTitles(false))
+            .await
+            .unwrap();
+    }
+
     let app_services = create_app_services(
         is_pro,
         db,
@@ -211,11 +257,7 @@ async fn main() -> Result<()> {
         .register_with_count(
             1,
             WorkerBuilder::new("background_jobs")
-                .stream(
-                    // every day
-                    CronStream::new_with_timezone(Schedule::from_str("0 0 0 * * *").unwrap(), tz)
-                        .into_stream(),
-                )
+                .stream(CronStream::new_with_timezone(background_jobs_schedule, tz).into_stream())
                 .layer(ApalisTraceLayer::new())
                 .data(miscellaneous_service_1.clone())
                 .build_fn(background_jobs),
@@ -224,16 +266,33 @@ async fn main() -> Result<()> {
             1,
             WorkerBuilder::new("sync_integrations_data")
                 .stream(
-                    CronStream::new_with_timezone(
-                        Schedule::from_str(&format!("0 */{} * * * *", sync_every_minutes)).unwrap(),
-                        tz,
-                    )
-                    .into_stream(),
+                    CronStream::new_with_timezone(sync_integrations_data_schedule, tz)
+                        .into_stream(),
                 )
                 .layer(ApalisTraceLayer::new())
                 .data(integration_service_1.clone())
+                .data(miscellaneous_service_1.clone())
                 .build_fn(sync_integrations_data),
         )
+        .register_with_count(
+            1,
+            WorkerBuilder::new("refresh_in_progress_media")
+                .stream(
+                    CronStream::new_with_timezone(refresh_in_progress_media_schedule, tz)
+                        .into_stream(),
+                )
+                .layer(ApalisTraceLayer::new())
+                .data(miscellaneous_service_1.clone())
+                .build_fn(refresh_in_progress_media),
+        )
+        .register_with_count(
+            1,
+            WorkerBuilder::new("scheduled_exports")
+                .stream(CronStream::new_with_timezone(scheduled_exports_schedule, tz).into_stream())
+                .layer(ApalisTraceLayer::new())
+                .data(exporter_service_1.clone())
+                .build_fn(scheduled_exports),
+        )
         // application jobs
         .register_with_count(
             1,
@@ -583,9 +583,9 @@ export const ReviewItemDisplay = (props: {
 											/>
 											<Text className={classes.text} fw="bold">
 												{props.review.rating}
-												{reviewScale === UserReviewScale.OutOfFive
-													? undefined
-													: "%"}
+												{reviewScale === UserReviewScale.OutOfHundred
+													? "%"
+													: undefined}
 											</Text>
 										</Flex>
 									))
@@ -202,9 +202,9 @@ export const MetadataDisplayItem = (props: {
 										? Math.round(Number(averageRating)).toString()
 										: Number(averageRating).toFixed(1)}
 									{userPreferences.general.reviewScale ===
-									UserReviewScale.OutOfFive
-										? null
-										: " %"}
+									UserReviewScale.OutOfHundred
+										? " %"
+										: null}
 								</Text>
 							</Group>
 						))
@@ -442,7 +442,22 @@ export default function Page() {
 										.exhaustive()}`}
 								/>
 								<Text fz="sm">
-									{Number(loaderData.metadataDetails.providerRating).toFixed(1)}
+									{(
+										(Number(loaderData.metadataDetails.providerRating) *
+											match(loaderData.metadataDetails.source)
+												.with(
+													MediaSource.Audible,
+													MediaSource.GoogleBooks,
+													() => 5,
+												)
+												.with(
+													MediaSource.Mal,
+													MediaSource.MangaUpdates,
+													() => 10,
+												)
+												.otherwise(() => 100)) /
+										100
+									).toFixed(1)}
 									{match(loaderData.metadataDetails.source)
 										.with(
 											MediaSource.Anilist,
@@ -499,9 +514,9 @@ export default function Page() {
 													loaderData.userMetadataDetails.averageRating,
 												).toFixed(1)}
 												{userPreferences.general.reviewScale ===
-												UserReviewScale.OutOfFive
-													? undefined
-													: "%"}
+												UserReviewScale.OutOfHundred
+													? "%"
+													: undefined}
 											</Text>
 										</Paper>
 									))
@@ -614,12 +629,9 @@ export default function Page() {
 										: null}
 								</SimpleGrid>
 								{loaderData.metadataDetails.description ? (
-									<div
-										// biome-ignore lint/security/noDangerouslySetInnerHtml: generated by the backend securely
-										dangerouslySetInnerHTML={{
-											__html: loaderData.metadataDetails.description,
-										}}
-									/>
+									<Text style={{ whiteSpace: "pre-line" }}>
+										{loaderData.metadataDetails.description}
+									</Text>
 								) : null}
 								{userPreferences.featuresEnabled.media.people ? (
 									<Stack>
@@ -91,9 +91,15 @@ export const action = async ({ request }: ActionFunctionArgs) => {
 				.with(ImportSource.Trakt, () => ({
 					trakt: processSubmission(formData, usernameImportFormSchema),
 				}))
+				.with(ImportSource.Anilist, () => ({
+					anilist: processSubmission(formData, usernameImportFormSchema),
+				}))
 				.with(ImportSource.Movary, async () => ({
 					movary: processSubmission(formData, movaryImportFormSchema),
 				}))
+				.with(ImportSource.Letterboxd, async () => ({
+					letterboxd: processSubmission(formData, letterboxdImportFormSchema),
+				}))
 				.with(ImportSource.Mal, async () => ({
 					mal: processSubmission(formData, malImportFormSchema),
 				}))
@@ -176,6 +182,14 @@ const movaryImportFormSchema = z.object({
 	watchlist: z.string(),
 });
 
+const letterboxdImportFormSchema = z.object({
+	export: z.string().optional(),
+	watched: z.string().optional(),
+	ratings: z.string().optional(),
+	reviews: z.string().optional(),
+	watchlist: z.string().optional(),
+});
+
 const strongAppImportFormSchema = z.object({
 	exportPath: z.string(),
 	mapping: z.string(),
@@ -227,9 +241,11 @@ export default function Page() {
 												.with(ImportSource.Mal, () => "myanimelist")
 												.with(ImportSource.MediaTracker, () => "mediatracker")
 												.with(ImportSource.Movary, () => "movary")
+												.with(ImportSource.Letterboxd, () => "letterboxd")
 												.with(ImportSource.StoryGraph, () => "storygraph")
 												.with(ImportSource.StrongApp, () => "strong-app")
 												.with(ImportSource.Trakt, () => "trakt")
+												.with(ImportSource.Anilist, () => "anilist")
 												.with(
 													ImportSource.Audiobookshelf,
 													() => "audiobookshelf",
@@ -297,7 +313,7 @@ export default function Page() {
 													</>
 												),
 											)
-											.with(ImportSource.Trakt, () => (
+											.with(ImportSource.Trakt, ImportSource.Anilist, () => (
 												<>
 													<TextInput
 														label="Username"
@@ -348,6 +364,36 @@ export default function Page() {
 													/>
 												</>
 											))
+											.with(ImportSource.Letterboxd, () => (
+												<>
+													<FileInput
+														label="Export zip file"
+														accept=".zip"
+														name="export"
+														description="The CSV files below are used instead of the ones in the zip."
+													/>
+													<FileInput
+														label="Watched CSV file"
+														accept=".csv"
+														name="watched"
+													/>
+													<FileInput
+														label="Ratings CSV file"
+														accept=".csv"
+														name="ratings"
+													/>
+													<FileInput
+														label="Reviews CSV file"
+														accept=".csv"
+														name="reviews"
+													/>
+													<FileInput
+														label="Watchlist CSV file"
+														accept=".csv"
+														name="watchlist"
+													/>
+												</>
+											))
 											.with(ImportSource.Igdb, () => (
 												<>
 													<Select
@@ -1370,6 +1370,23 @@ const ReviewEntityForm = ({
 								/>
 							</Flex>
 						))
+						.with(UserReviewScale.OutOfTen, () => (
+							<NumberInput
+								label="Rating"
+								name="rating"
+								min={0}
+								max={10}
+								step={1}
+								w="40%"
+								hideControls
+								rightSection={<Text size="xs">/10</Text>}
+								defaultValue={
+									entityToReview.existingReview?.rating
+										? Number(entityToReview.existingReview.rating)
+										: undefined
+								}
+							/>
+						))
 						.with(UserReviewScale.OutOfHundred, () => (
 							<NumberInput
 								label="Rating"
@@ -1,12 +1,22 @@
-use apalis::prelude::{Job, Message};
-use chrono::DateTime;
+use std::{
+    collections::VecDeque,
+    str::FromStr,
+    sync::{LazyLock, Mutex},
+};
+
+use apalis::{
+    cron::Schedule,
+    prelude::{Job, Message},
+};
+use chrono::{DateTime, Duration, Utc};
 use chrono_tz::Tz;
+use config::AppConfig;
 use database_models::seen;
 use enums::{MediaLot, MediaSource};
 use fitness_models::GithubExercise;
 use media_models::{DeployImportJobInput, ProgressUpdateInput, ReviewPostedEvent};
 use serde::{Deserialize, Serialize};
-use strum::Display;
+use strum::{Display, EnumIter};
 use uuid::Uuid;
 
 // The background jobs which cannot be throttled.
@@ -15,6 +25,8 @@ pub enum CoreApplicationJob {
     SyncIntegrationsData(String),
     ReviewPosted(ReviewPostedEvent),
     BulkProgressUpdate(String, Vec<ProgressUpdateInput>),
+    ProcessIntegrationWebhookDeliveries(String),
+    ProcessOutboundWebhookDeliveries(String),
 }
 
 impl Message for CoreApplicationJob {
@@ -31,6 +43,7 @@ pub enum ApplicationJob {
     UpdatePerson(String),
     UpdateMetadataGroup(String),
     RecalculateCalendarEvents,
+    RecalculateSortTitles(bool),
     AssociateGroupWithMetadata(MediaLot, MediaSource, String),
     PerformExport(String),
     RecalculateUserActivitiesAndSummary(String, bool),
@@ -46,7 +59,90 @@ impl Message for ApplicationJob {
     const NAME: &'static str = "apalis::ApplicationJob";
 }
 
+// DEV: Jobs are kept in memory, so their outcomes are only known since the last restart
+static JOB_OUTCOMES: LazyLock<Mutex<VecDeque<(DateTime<Utc>, bool)>>> =
+    LazyLock::new(Default::default);
+
+const JOB_OUTCOMES_RETENTION_DAYS: i64 = 7;
+
+fn push_job_outcome(
+    outcomes: &mut VecDeque<(DateTime<Utc>, bool)>,
+    now: DateTime<Utc>,
+    successful: bool,
+) {
+    while outcomes.front().map_or(false, |(at, _)| {
+        *at < now - Duration::days(JOB_OUTCOMES_RETENTION_DAYS)
+    }) {
+        outcomes.pop_front();
+    }
+    outcomes.push_back((now, successful));
+}
+
+fn count_job_outcomes(
+    outcomes: &VecDeque<(DateTime<Utc>, bool)>,
+    since: DateTime<Utc>,
+) -> (usize, usize) {
+    outcomes
+        .iter()
+        .filter(|(at, _)| *at >= since)
+        .fold((0, 0), |(total, failed), (_, successful)| {
+            (total + 1, failed + usize::from(!successful))
+        })
+}
+
+pub fn record_job_outcome(successful: bool) {
+    push_job_outcome(&mut JOB_OUTCOMES.lock().unwrap(), Utc::now(), successful);
+}
+
+/// The number of application jobs that ran since the given instant and how many
+/// of them failed.
+pub fn job_outcomes_since(since: DateTime<Utc>) -> (usize, usize) {
+    count_job_outcomes(&JOB_OUTCOMES.lock().unwrap(), since)
+}
+
 // Cron Jobs
+#[derive(Debug, Clone, Copy, Display, EnumIter, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum ScheduledJobKind {
+    BackgroundJobs,
+    SyncIntegrationsData,
+    RefreshInProgressMedia,
+    ScheduledExports,
+}
+
+impl ScheduledJobKind {
+    pub fn cron_expression(&self, config: &AppConfig) -> String {
+        match self {
+            Self::BackgroundJobs => config.scheduler.background_jobs_cron.clone(),
+            Self::SyncIntegrationsData => config.sync_integrations_data_cron(),
+            Self::RefreshInProgressMedia => config.scheduler.refresh_in_progress_media_cron.clone(),
+            Self::ScheduledExports => config.scheduler.scheduled_exports_cron.clone(),
+        }
+    }
+
+    /// The schedule of the job. The server refuses to start if this fails so that
+    /// a typo does not silently stop the job from ever running.
+    pub fn schedule(&self, config: &AppConfig) -> Result<Schedule, String> {
+        let expression = self.cron_expression(config);
+        Schedule::from_str(&expression).map_err(|e| {
+            format!(
+                "Invalid cron expression {:?} for the {} job: {}",
+                expression, self, e
+            )
+        })
+    }
+}
+
+/// The next times at which a schedule fires, evaluated in the given timezone so
+/// that jobs stay at the same wall clock time across daylight saving changes.
+pub fn next_scheduled_runs(schedule: &Schedule, timezone: &Tz, count: usize) -> Vec<DateTime<Utc>> {
+    schedule
+        .upcoming(*timezone)
+        .take(count)
+        .map(|d| d.with_timezone(&Utc))
+        .collect()
+}
+
 pub struct ScheduledJob(pub DateTime<Tz>);
 
 impl From<DateTime<Tz>> for ScheduledJob {
@@ -58,3 +154,63 @@ impl From<DateTime<Tz>> for ScheduledJob {
 impl Job for ScheduledJob {
     const NAME: &'static str = "apalis::ScheduledJob";
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+
+    use super::*;
+
+    #[test]
+    fn next_scheduled_runs_keep_the_local_time() {
+        let schedule = Schedule::from_str("0 30 3 * * *").unwrap();
+        let timezone = chrono_tz::Europe::Berlin;
+        let runs = next_scheduled_runs(&schedule, &timezone, 400);
+        assert_eq!(runs.len(), 400);
+        assert!(runs.windows(2).all(|w| w[0] < w[1]));
+        // DEV: A year of runs always crosses a daylight saving change in Berlin
+        for run in runs {
+            let local = run.with_timezone(&timezone);
+            assert_eq!((local.hour(), local.minute()), (3, 30));
+        }
+    }
+
+    #[test]
+    fn next_scheduled_runs_depend_on_the_timezone() {
+        let schedule = Schedule::from_str("0 0 12 * * *").unwrap();
+        let utc = next_scheduled_runs(&schedule, &chrono_tz::UTC, 1);
+        let tokyo = next_scheduled_runs(&schedule, &chrono_tz::Asia::Tokyo, 1);
+        assert_eq!(utc[0].hour(), 12);
+        assert_eq!(tokyo[0].hour(), 3);
+    }
+
+    #[test]
+    fn invalid_cron_expressions_are_reported() {
+        let mut config = AppConfig::default();
+        config.scheduler.background_jobs_cron = "every day".to_owned();
+        let error = ScheduledJobKind::BackgroundJobs
+            .schedule(&config)
+            .unwrap_err();
+        assert!(error.contains("every day"));
+        config.scheduler.background_jobs_cron = "0 0 0 * * *".to_owned();
+        assert!(ScheduledJobKind::BackgroundJobs.schedule(&config).is_ok());
+    }
+
+    #[test]
+    fn job_failure_rate_only_counts_the_last_week() {
+        let start = DateTime::from_timestamp(1_730_000_000, 0).unwrap();
+        let mut outcomes = VecDeque::new();
+        push_job_outcome(&mut outcomes, start, false);
+        push_job_outcome(&mut outcomes, start + Duration::days(3), true);
+        push_job_outcome(&mut outcomes, start + Duration::days(6), false);
+        push_job_outcome(&mut outcomes, start + Duration::days(8), true);
+        // DEV: The first outcome is older than the retention period by now
+        assert_eq!(outcomes.len(), 3);
+        let week_ago = start + Duration::days(1);
+        assert_eq!(count_job_outcomes(&outcomes, week_ago), (3, 1));
+        assert_eq!(
+            count_job_outcomes(&outcomes, start + Duration::days(7)),
+            (1, 0)
+        );
+    }
+}
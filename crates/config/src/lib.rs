@@ -141,6 +141,29 @@ pub struct MediaConfig {
     /// Number of days after which a media should be removed from the Monitoring collection.
     #[setting(default = 30)]
     pub monitoring_remove_after_days: i64,
+    /// Number of consecutive failures after which calls to a provider are
+    /// paused.
+    #[setting(default = 5)]
+    pub provider_failure_threshold: i32,
+    /// Number of seconds for which calls to a failing provider are paused
+    /// before it is probed again.
+    #[setting(default = 300)]
+    pub provider_cooldown_seconds: i64,
+    /// Number of suggestions from the provider that are kept for a media item.
+    #[setting(default = 10)]
+    pub max_suggestions: usize,
+    /// Languages whose leading articles are ignored when sorting by title. The
+    /// supported ones are `de`, `en`, `es`, `fr`, `it`, `nl` and `pt`.
+    #[setting(default = vec!["en".to_owned()], parse_env = schematic::env::split_comma)]
+    pub sort_title_languages: Vec<String>,
+    /// Number of hours after which shows and podcasts that users have in
+    /// progress or in their Watchlist are refreshed again by the background job.
+    #[setting(default = 12)]
+    pub in_progress_refresh_after_hours: i64,
+    /// Number of seconds to wait between two refreshes from the same provider
+    /// when refreshing media that users have in progress.
+    #[setting(default = 2)]
+    pub in_progress_refresh_delay_seconds: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
@@ -162,7 +185,11 @@ pub struct MovieAndShowConfig {
     pub tmdb: TmdbConfig,
 }
 
-impl IsFeatureEnabled for MovieAndShowConfig {}
+impl IsFeatureEnabled for MovieAndShowConfig {
+    fn is_enabled(&self) -> bool {
+        !self.tmdb.access_token.is_empty()
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
 #[config(rename_all = "snake_case", env_prefix = "PODCASTS_LISTENNOTES_")]
@@ -246,9 +273,34 @@ pub struct VisualNovelConfig {}
 
 impl IsFeatureEnabled for VisualNovelConfig {}
 
+#[derive(Debug, Serialize, Deserialize, Clone, Config)]
+#[config(rename_all = "snake_case", env_prefix = "EXPORTER_")]
+pub struct ExporterConfig {
+    /// Write scheduled exports of every user to this directory, in a sub-directory
+    /// named after the user id.
+    pub scheduled_directory: String,
+    /// Upload scheduled exports to the file storage too, where they are listed
+    /// along with the exports the user started.
+    #[setting(default = false)]
+    pub scheduled_to_file_storage: bool,
+    /// The number of scheduled exports kept for every user in each destination.
+    /// Older ones are deleted.
+    #[setting(default = 7)]
+    pub scheduled_keep_last: usize,
+}
+
+impl IsFeatureEnabled for ExporterConfig {
+    fn is_enabled(&self) -> bool {
+        !self.scheduled_directory.is_empty() || self.scheduled_to_file_storage
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
 #[config(rename_all = "snake_case", env_prefix = "FILE_STORAGE_")]
 pub struct FileStorageConfig {
+    /// The URL of an image proxy. Images served over plain HTTP by hosts that do
+    /// not support HTTPS are requested as `<image_proxy_url>?url=<original_url>`.
+    pub image_proxy_url: String,
     /// The access key ID for the S3 compatible file storage. **Required** to
     /// enable file storage.
     pub s3_access_key_id: String,
@@ -310,6 +362,31 @@ pub struct IntegrationConfig {
     /// Sync data from push and yank based integrations every `n` minutes.
     #[setting(default = 5)]
     pub sync_every_minutes: i32,
+    /// Process webhooks before responding instead of queueing them. Some media
+    /// servers give up if the response takes more than a couple of seconds.
+    #[setting(default = false)]
+    pub process_webhooks_synchronously: bool,
+    /// A game session is closed when no heartbeat is received for this many
+    /// minutes. The next heartbeat starts a new session.
+    #[setting(default = 15)]
+    pub game_session_idle_minutes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Config)]
+#[config(rename_all = "snake_case", env_prefix = "NOTIFICATIONS_")]
+pub struct NotificationsConfig {
+    /// Notifications queued for the same platform within this many seconds of
+    /// each other are sent together as a single message.
+    #[setting(default = 60)]
+    pub coalesce_window_seconds: i64,
+    /// The maximum number of notifications sent at the same time to a single
+    /// host, for example an ntfy or Gotify server.
+    #[setting(default = 2)]
+    pub max_concurrent_per_host: usize,
+    /// The maximum number of due notifications picked up each time they are
+    /// sent. The rest stay queued until the next run.
+    #[setting(default = 200)]
+    pub max_deliveries_per_run: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
@@ -319,6 +396,25 @@ pub struct SchedulerConfig {
     /// the background.
     #[setting(default = 5)]
     pub rate_limit_num: u64,
+    /// The cron expression (with seconds) for the daily background jobs. It is
+    /// evaluated in the timezone set by the `TZ` environment variable.
+    #[setting(default = "0 0 0 * * *")]
+    pub background_jobs_cron: String,
+    /// The cron expression (with seconds) for refreshing shows and podcasts that
+    /// users have in progress.
+    #[setting(default = "0 0 */6 * * *")]
+    pub refresh_in_progress_media_cron: String,
+    /// The cron expression (with seconds) for exporting the data of every user.
+    /// Only used when a destination is set in `exporter`.
+    #[setting(default = "0 0 3 * * *")]
+    pub scheduled_exports_cron: String,
+    /// The cron expression (with seconds) for syncing data from integrations.
+    /// Uses `integration.sync_every_minutes` when empty.
+    pub sync_integrations_data_cron: String,
+    /// Wait a random number of seconds up to this before running a scheduled job
+    /// so that replicas do not all run it at the same time.
+    #[setting(default = 0)]
+    pub jitter_seconds: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
@@ -332,12 +428,9 @@ pub struct SmtpConfig {
 }
 
 impl IsFeatureEnabled for SmtpConfig {
+    // DEV: Relays on the local network often do not need a login
     fn is_enabled(&self) -> bool {
-        let mut enabled = false;
-        if !self.server.is_empty() && !self.user.is_empty() && !self.password.is_empty() {
-            enabled = true;
-        }
-        enabled
+        !self.server.is_empty()
     }
 }
 
@@ -368,6 +461,10 @@ pub struct ServerConfig {
     /// it has been already marked as seen in the last `n` hours.
     #[setting(default = 2)]
     pub progress_update_threshold: i64,
+    /// The minutes during which an update with the same progress as the one in
+    /// progress is ignored, even if it comes from a different provider.
+    #[setting(default = 15)]
+    pub progress_update_dedupe_minutes: i64,
     /// The maximum file size in MB for user uploads.
     #[setting(default = 70)]
     pub max_file_size: usize,
@@ -423,6 +520,9 @@ pub struct AppConfig {
     /// Settings related to exercises.
     #[setting(nested)]
     pub exercise: ExerciseConfig,
+    /// Settings related to exports.
+    #[setting(nested)]
+    pub exporter: ExporterConfig,
     /// Settings related to file storage.
     #[setting(nested)]
     pub file_storage: FileStorageConfig,
@@ -438,6 +538,9 @@ pub struct AppConfig {
     /// Settings related to movies and shows.
     #[setting(nested)]
     pub movies_and_shows: MovieAndShowConfig,
+    /// Settings related to sending notifications.
+    #[setting(nested)]
+    pub notifications: NotificationsConfig,
     /// Settings related to podcasts.
     #[setting(nested)]
     pub podcasts: PodcastConfig,
@@ -463,7 +566,151 @@ pub struct AppConfig {
     pub disable_telemetry: bool,
 }
 
+/// A problem with a single setting, `path` is where it is set in the config file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// The result of checking the config. The server refuses to start when there
+/// are errors, warnings only mean that a feature is turned off.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConfigValidationReport {
+    pub errors: Vec<ConfigIssue>,
+    pub warnings: Vec<ConfigIssue>,
+}
+
+impl ConfigValidationReport {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.errors.push(ConfigIssue {
+            path: path.to_owned(),
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, path: &str, message: impl Into<String>) {
+        self.warnings.push(ConfigIssue {
+            path: path.to_owned(),
+            message: message.into(),
+        });
+    }
+
+    /// Settings that are either all empty or all set, setting only some of them
+    /// is almost always a mistake.
+    fn all_or_none(&mut self, feature: &str, settings: &[(&str, &str)]) {
+        if settings.iter().all(|(_, v)| v.is_empty()) {
+            return;
+        }
+        for (path, _) in settings.iter().filter(|(_, v)| v.is_empty()) {
+            self.error(path, format!("is required when {feature} is configured"));
+        }
+    }
+
+    fn url(&mut self, path: &str, value: &str, schemes: &[&str]) {
+        if value.is_empty() {
+            return;
+        }
+        let host = schemes
+            .iter()
+            .find_map(|s| value.strip_prefix(s)?.strip_prefix("://"));
+        if host.map_or(true, |h| {
+            h.is_empty() || h.starts_with('/') || h.contains(' ')
+        }) {
+            self.error(
+                path,
+                format!(
+                    "{value:?} is not a valid URL, it must start with {}",
+                    schemes
+                        .iter()
+                        .map(|s| format!("{s}://"))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                ),
+            );
+        }
+    }
+
+    fn positive(&mut self, path: &str, value: i64) {
+        if value <= 0 {
+            self.error(path, format!("must be greater than 0, got {value}"));
+        }
+    }
+
+    fn not_negative(&mut self, path: &str, value: i64) {
+        if value < 0 {
+            self.error(path, format!("can not be negative, got {value}"));
+        }
+    }
+}
+
 impl AppConfig {
+    /// The cron expression used to sync data from integrations.
+    pub fn sync_integrations_data_cron(&self) -> String {
+        match self.scheduler.sync_integrations_data_cron.is_empty() {
+            true => format!("0 */{} * * * *", self.integration.sync_every_minutes),
+            false => self.scheduler.sync_integrations_data_cron.clone(),
+        }
+    }
+
+    /// The providers that can be used for each kind of media, along with the setting
+    /// that enables the provider that needs credentials.
+    pub fn media_providers(&self) -> Vec<(&'static str, &'static str, Vec<&'static str>)> {
+        let configured = |is_set: bool, provider| is_set.then_some(provider);
+        vec![
+            (
+                "movies and shows",
+                "movies_and_shows.tmdb.access_token",
+                vec![configured(self.movies_and_shows.is_enabled(), "TMDB")],
+            ),
+            (
+                "video games",
+                "video_games.twitch.client_id",
+                vec![configured(self.video_games.is_enabled(), "IGDB")],
+            ),
+            (
+                "podcasts",
+                "podcasts.listennotes.api_token",
+                vec![
+                    Some("iTunes"),
+                    configured(
+                        !self.podcasts.listennotes.api_token.is_empty(),
+                        "Listennotes",
+                    ),
+                ],
+            ),
+            (
+                "anime and manga",
+                "anime_and_manga.mal.client_id",
+                vec![
+                    Some("Anilist"),
+                    Some("MangaUpdates"),
+                    configured(!self.anime_and_manga.mal.client_id.is_empty(), "MAL"),
+                ],
+            ),
+            (
+                "books",
+                "books.google_books.api_key",
+                vec![Some("Openlibrary"), Some("Google Books")],
+            ),
+            (
+                "audio books",
+                "audio_books.audible.locale",
+                vec![Some("Audible")],
+            ),
+            ("visual novels", "visual_novels", vec![Some("VNDB")]),
+        ]
+        .into_iter()
+        .map(|(media, path, providers)| (media, path, providers.into_iter().flatten().collect()))
+        .collect()
+    }
+
     pub fn masked_value(&self) -> Self {
         let gt = || "****".to_owned();
         let mut cl = self.clone();
@@ -492,6 +739,213 @@ impl AppConfig {
         cl.server.admin_access_token = gt();
         cl
     }
+
+    /// Check the settings that would otherwise only fail when they are first used,
+    /// every problem is reported instead of stopping at the first one.
+    pub fn validate(&self) -> ConfigValidationReport {
+        let mut report = ConfigValidationReport::default();
+        let http = ["http", "https"];
+
+        report.url(
+            "database.url",
+            &self.database.url,
+            &["postgres", "postgresql"],
+        );
+        report.url("frontend.url", &self.frontend.url, &http);
+        report.url(
+            "frontend.umami.script_url",
+            &self.frontend.umami.script_url,
+            &http,
+        );
+        report.url(
+            "file_storage.image_proxy_url",
+            &self.file_storage.image_proxy_url,
+            &http,
+        );
+        report.url("file_storage.s3_url", &self.file_storage.s3_url, &http);
+        report.url(
+            "server.oidc.issuer_url",
+            &self.server.oidc.issuer_url,
+            &http,
+        );
+        for (idx, origin) in self.server.cors_origins.iter().enumerate() {
+            report.url(&format!("server.cors_origins[{idx}]"), origin, &http);
+        }
+
+        report.all_or_none(
+            "file storage",
+            &[
+                (
+                    "file_storage.s3_access_key_id",
+                    self.file_storage.s3_access_key_id.as_str(),
+                ),
+                (
+                    "file_storage.s3_secret_access_key",
+                    self.file_storage.s3_secret_access_key.as_str(),
+                ),
+                (
+                    "file_storage.s3_bucket_name",
+                    self.file_storage.s3_bucket_name.as_str(),
+                ),
+            ],
+        );
+        report.all_or_none(
+            "SMTP authentication",
+            &[
+                ("server.smtp.user", self.server.smtp.user.as_str()),
+                ("server.smtp.password", self.server.smtp.password.as_str()),
+            ],
+        );
+        if !self.server.smtp.is_enabled() && !self.server.smtp.user.is_empty() {
+            report.error(
+                "server.smtp.server",
+                "is required when SMTP authentication is configured",
+            );
+        }
+        if self.server.smtp.is_enabled() && !self.server.smtp.mailbox.contains('@') {
+            report.error(
+                "server.smtp.mailbox",
+                format!("{:?} is not a valid mailbox", self.server.smtp.mailbox),
+            );
+        }
+        report.all_or_none(
+            "OIDC",
+            &[
+                ("server.oidc.client_id", self.server.oidc.client_id.as_str()),
+                (
+                    "server.oidc.client_secret",
+                    self.server.oidc.client_secret.as_str(),
+                ),
+                (
+                    "server.oidc.issuer_url",
+                    self.server.oidc.issuer_url.as_str(),
+                ),
+            ],
+        );
+        if self.users.disable_local_auth && self.server.oidc.client_id.is_empty() {
+            report.error(
+                "users.disable_local_auth",
+                "can not be set when OIDC is not configured since nobody would be able to log in",
+            );
+        }
+        report.all_or_none(
+            "Twitch",
+            &[
+                (
+                    "video_games.twitch.client_id",
+                    self.video_games.twitch.client_id.as_str(),
+                ),
+                (
+                    "video_games.twitch.client_secret",
+                    self.video_games.twitch.client_secret.as_str(),
+                ),
+            ],
+        );
+
+        report.positive(
+            "users.token_valid_for_days",
+            self.users.token_valid_for_days.into(),
+        );
+        report.positive(
+            "media.provider_failure_threshold",
+            self.media.provider_failure_threshold.into(),
+        );
+        report.positive(
+            "media.provider_cooldown_seconds",
+            self.media.provider_cooldown_seconds,
+        );
+        report.positive(
+            "integration.game_session_idle_minutes",
+            self.integration.game_session_idle_minutes,
+        );
+        report.positive(
+            "notifications.max_concurrent_per_host",
+            self.notifications
+                .max_concurrent_per_host
+                .try_into()
+                .unwrap_or(i64::MAX),
+        );
+        report.positive(
+            "notifications.max_deliveries_per_run",
+            self.notifications
+                .max_deliveries_per_run
+                .try_into()
+                .unwrap_or(i64::MAX),
+        );
+        report.not_negative(
+            "media.in_progress_refresh_after_hours",
+            self.media.in_progress_refresh_after_hours,
+        );
+        report.not_negative(
+            "server.progress_update_dedupe_minutes",
+            self.server.progress_update_dedupe_minutes,
+        );
+        report.positive(
+            "server.max_file_size",
+            self.server.max_file_size.try_into().unwrap_or(i64::MAX),
+        );
+        report.positive(
+            "exporter.scheduled_keep_last",
+            self.exporter
+                .scheduled_keep_last
+                .try_into()
+                .unwrap_or(i64::MAX),
+        );
+        if self.exporter.scheduled_to_file_storage && !self.file_storage.is_enabled() {
+            report.error(
+                "exporter.scheduled_to_file_storage",
+                "can not be set when file storage is not configured",
+            );
+        }
+        if self.scheduler.sync_integrations_data_cron.is_empty()
+            && !(1..60).contains(&self.integration.sync_every_minutes)
+        {
+            report.error(
+                "integration.sync_every_minutes",
+                format!(
+                    "must be between 1 and 59, got {}",
+                    self.integration.sync_every_minutes
+                ),
+            );
+        }
+        if self.users.jwt_secret == PROJECT_NAME {
+            report.warning(
+                "users.jwt_secret",
+                "is the default value, anyone who knows it can create login tokens",
+            );
+        }
+
+        for (media, path, providers) in self.media_providers() {
+            if providers.is_empty() {
+                report.warning(
+                    path,
+                    format!("is empty, no provider is configured so {media} are disabled"),
+                );
+            }
+        }
+        if !self.file_storage.is_enabled() {
+            report.warning(
+                "file_storage.s3_bucket_name",
+                "is empty, file storage is disabled",
+            );
+        }
+        if !self.server.smtp.is_enabled() {
+            report.warning("server.smtp.server", "is empty, emails are disabled");
+        }
+        if self.books.google_books.api_key.is_empty() {
+            report.warning(
+                "books.google_books.api_key",
+                "is empty, Google Books is rate limited",
+            );
+        }
+        if self.podcasts.listennotes.api_token.is_empty() {
+            report.warning(
+                "podcasts.listennotes.api_token",
+                "is empty, podcasts can only be found on iTunes",
+            );
+        }
+        report
+    }
 }
 
 pub fn load_app_config() -> Result<AppConfig> {
@@ -507,3 +961,116 @@ pub fn load_app_config() -> Result<AppConfig> {
 
     Ok(result.config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue_paths(issues: &[ConfigIssue]) -> Vec<&str> {
+        issues.iter().map(|i| i.path.as_str()).collect()
+    }
+
+    #[test]
+    fn smtp_only_needs_a_server() {
+        let mut config = AppConfig::default();
+        config.server.smtp.server = "smtp.example.com".to_owned();
+        assert!(config.server.smtp.is_enabled());
+        assert!(!issue_paths(&config.validate().errors).contains(&"server.smtp.user"));
+
+        config.server.smtp.user = "ryot".to_owned();
+        assert!(issue_paths(&config.validate().errors).contains(&"server.smtp.password"));
+
+        config.server.smtp.server = String::new();
+        config.server.smtp.password = "secret".to_owned();
+        let errors = config.validate().errors;
+        assert_eq!(issue_paths(&errors), vec!["server.smtp.server"]);
+    }
+
+    #[test]
+    fn media_without_providers_are_reported() {
+        let mut config = AppConfig::default();
+        config.movies_and_shows.tmdb.access_token = String::new();
+        let warnings = config.validate().warnings;
+        let paths = issue_paths(&warnings);
+        assert!(paths.contains(&"movies_and_shows.tmdb.access_token"));
+        assert!(paths.contains(&"video_games.twitch.client_id"));
+        assert!(!paths.contains(&"podcasts.listennotes.api_token"));
+
+        config.video_games.twitch.client_id = "id".to_owned();
+        config.video_games.twitch.client_secret = "secret".to_owned();
+        let warnings = config.validate().warnings;
+        assert!(!issue_paths(&warnings).contains(&"video_games.twitch.client_id"));
+    }
+
+    #[test]
+    fn zero_durations_are_allowed_where_they_disable_a_feature() {
+        let mut config = AppConfig::default();
+        config.server.progress_update_dedupe_minutes = 0;
+        config.media.in_progress_refresh_after_hours = 0;
+        assert!(config.validate().errors.is_empty());
+
+        config.server.progress_update_dedupe_minutes = -1;
+        config.media.in_progress_refresh_after_hours = -1;
+        let errors = config.validate().errors;
+        assert_eq!(
+            issue_paths(&errors),
+            vec![
+                "media.in_progress_refresh_after_hours",
+                "server.progress_update_dedupe_minutes"
+            ]
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported_with_its_path() {
+        let mut config = AppConfig::default();
+        config.frontend.url = "localhost:3000".to_owned();
+        config.file_storage.s3_access_key_id = "key".to_owned();
+        config.users.disable_local_auth = true;
+        config.users.token_valid_for_days = 0;
+        let errors = config.validate().errors;
+        assert_eq!(
+            issue_paths(&errors),
+            vec![
+                "frontend.url",
+                "file_storage.s3_secret_access_key",
+                "file_storage.s3_bucket_name",
+                "users.disable_local_auth",
+                "users.token_valid_for_days",
+            ]
+        );
+        assert_eq!(
+            errors[0].message,
+            r#""localhost:3000" is not a valid URL, it must start with http:// or https://"#
+        );
+        assert_eq!(
+            errors[1].message,
+            "is required when file storage is configured"
+        );
+    }
+
+    #[test]
+    fn a_missing_tmdb_token_disables_movies_and_shows() {
+        let mut config = AppConfig::default();
+        config.movies_and_shows.tmdb.access_token = String::new();
+        assert!(!config.movies_and_shows.is_enabled());
+        let (_, _, providers) = config
+            .media_providers()
+            .into_iter()
+            .find(|(media, _, _)| *media == "movies and shows")
+            .unwrap();
+        assert!(providers.is_empty());
+        // DEV: Only a warning, the server still starts without movies and shows
+        let report = config.validate();
+        assert!(report.errors.is_empty());
+        let warning = report
+            .warnings
+            .iter()
+            .find(|w| w.path == "movies_and_shows.tmdb.access_token")
+            .unwrap();
+        assert_eq!(
+            warning.message,
+            "is empty, no provider is configured so movies and shows are disabled"
+        );
+    }
+}
@@ -78,6 +78,44 @@ pub enum MediaSource {
     Vndb,
 }
 
+/// The kinds of ids that other services use to refer to a media. Providers that
+/// keep movies and shows (or anime and manga) in separate id spaces have one
+/// kind for each.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Deserialize,
+    Serialize,
+    Enum,
+    Hash,
+    Display,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "String(StringLen::None)",
+    rename_all = "snake_case"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalIdType {
+    Imdb,
+    Tvdb,
+    TmdbMovie,
+    TmdbShow,
+    Anilist,
+    MalAnime,
+    MalManga,
+    Igdb,
+    Steam,
+    /// The source that a media item used before it was swapped for another one,
+    /// see `previous_source_external_id`.
+    PreviousSource,
+}
+
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize, Enum,
 )]
@@ -124,6 +162,20 @@ pub enum EntityLot {
     WorkoutTemplate,
 }
 
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize, Enum,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "String(StringLen::None)",
+    rename_all = "snake_case"
+)]
+pub enum AnnouncementSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
 // The different possible states of a seen item.
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize, Enum,
@@ -138,6 +190,9 @@ pub enum SeenState {
     Dropped,
     InProgress,
     OnAHold,
+    /// The episode was deliberately not watched. It counts towards finishing the
+    /// media but not towards the statistics.
+    Skipped,
 }
 
 #[derive(
@@ -184,12 +239,15 @@ pub enum Visibility {
     rename_all = "snake_case"
 )]
 pub enum ImportSource {
+    Anilist,
     Audiobookshelf,
+    GenericCsv,
     GenericJson,
     Goodreads,
     Igdb,
     Imdb,
     Jellyfin,
+    Letterboxd,
     Mal,
     Movary,
     MediaTracker,
@@ -463,6 +521,146 @@ pub enum IntegrationProvider {
     JellyfinPush,
     Komga,
     GenericJson,
+    Webhook,
+    GameSession,
+}
+
+/// Whether an integration is receiving data as often as it is expected to.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Enum,
+    PartialEq,
+    Eq,
+    DeriveActiveEnum,
+    EnumIter,
+    Serialize,
+    Deserialize,
+    Hash,
+    Display,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "String(StringLen::None)",
+    rename_all = "snake_case"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrationHealthState {
+    Healthy,
+    Stale,
+    Erroring,
+}
+
+/// Where a webhook request sent to an integration is in the processing queue.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Enum,
+    PartialEq,
+    Eq,
+    DeriveActiveEnum,
+    EnumIter,
+    Serialize,
+    Deserialize,
+    Hash,
+    Display,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "String(StringLen::None)",
+    rename_all = "snake_case"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrationWebhookDeliveryStatus {
+    Queued,
+    Processed,
+    Failed,
+}
+
+/// The events that can be sent to a webhook integration.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Enum,
+    PartialEq,
+    Eq,
+    DeriveActiveEnum,
+    EnumIter,
+    Serialize,
+    Deserialize,
+    Hash,
+    Display,
+    ConfigEnum,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "String(StringLen::None)",
+    rename_all = "snake_case"
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum OutboundWebhookEvent {
+    SeenCompleted,
+    ReviewPosted,
+    ItemAddedToCollection,
+    NewEpisodeNotification,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Enum,
+    PartialEq,
+    Eq,
+    DeriveActiveEnum,
+    EnumIter,
+    Serialize,
+    Deserialize,
+    Hash,
+    Display,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "String(StringLen::None)",
+    rename_all = "snake_case"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboundWebhookDeliveryStatus {
+    Queued,
+    Delivered,
+    /// Every attempt failed, the delivery will not be retried.
+    DeadLetter,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Enum,
+    PartialEq,
+    Eq,
+    DeriveActiveEnum,
+    EnumIter,
+    Serialize,
+    Deserialize,
+    Hash,
+    Display,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "String(StringLen::None)",
+    rename_all = "snake_case"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationDeliveryStatus {
+    Queued,
+    Delivered,
+    /// Every attempt failed, the delivery will not be retried.
+    DeadLetter,
 }
 
 #[derive(
@@ -47,6 +47,43 @@ mod m20241013_changes_for_issue_1052;
 mod m20241019_changes_for_issue_929;
 mod m20241019_changes_for_issue_964;
 mod m20241025_changes_for_issue_1084;
+mod m20241026_create_csv_import_preset;
+mod m20241027_changes_for_episode_level_statistics;
+mod m20241028_create_default_user_preferences;
+mod m20241029_add_edition_column_to_seen;
+mod m20241030_changes_for_webhook_debugging;
+mod m20241031_create_announcement;
+mod m20241101_add_value_column_to_application_cache;
+mod m20241102_add_images_unreachable_column_to_metadata;
+mod m20241103_add_quick_log_to_general_preferences;
+mod m20241104_add_provider_rating_columns_to_metadata;
+mod m20241105_add_activity_alert_columns_to_integration;
+mod m20241105_normalize_runtimes_stored_in_seconds;
+mod m20241106_create_integration_item_mapping;
+mod m20241107_add_review_comment_posted_to_notification_preferences;
+mod m20241107_create_integration_webhook_delivery;
+mod m20241108_create_safety_snapshot;
+mod m20241109_add_description_preview_to_metadata;
+mod m20241109_create_tag;
+mod m20241110_add_disable_activity_sharing_to_general_preferences;
+mod m20241110_create_outbound_webhook_delivery;
+mod m20241111_create_metadata_external_id;
+mod m20241112_add_default_collection_to_integration;
+mod m20241113_create_video_game_session;
+mod m20241114_add_sort_title_to_metadata;
+mod m20241115_add_index_to_metadata_to_metadata;
+mod m20241116_create_notification_delivery;
+mod m20241117_add_amount_consumed_columns_to_seen;
+mod m20241118_add_last_refreshed_on_to_metadata;
+mod m20241119_add_created_by_user_id_to_metadata;
+mod m20241120_add_rank_to_collection_to_entity;
+mod m20241121_add_smart_filter_to_collection;
+mod m20241122_add_share_token_to_collection;
+mod m20241123_add_contents_to_safety_snapshot;
+mod m20241124_recalculate_tag_keys;
+mod m20241125_add_next_attempt_on_to_integration_webhook_delivery;
+mod m20241126_rename_previous_source_external_ids;
+mod m20241127_add_update_retry_at_to_metadata;
 
 pub use m20230410_create_metadata::Metadata as AliasedMetadata;
 pub use m20230413_create_person::Person as AliasedPerson;
@@ -115,6 +152,43 @@ impl MigratorTrait for Migrator {
             Box::new(m20241019_changes_for_issue_929::Migration),
             Box::new(m20241019_changes_for_issue_964::Migration),
             Box::new(m20241025_changes_for_issue_1084::Migration),
+            Box::new(m20241026_create_csv_import_preset::Migration),
+            Box::new(m20241027_changes_for_episode_level_statistics::Migration),
+            Box::new(m20241028_create_default_user_preferences::Migration),
+            Box::new(m20241029_add_edition_column_to_seen::Migration),
+            Box::new(m20241030_changes_for_webhook_debugging::Migration),
+            Box::new(m20241031_create_announcement::Migration),
+            Box::new(m20241101_add_value_column_to_application_cache::Migration),
+            Box::new(m20241102_add_images_unreachable_column_to_metadata::Migration),
+            Box::new(m20241103_add_quick_log_to_general_preferences::Migration),
+            Box::new(m20241104_add_provider_rating_columns_to_metadata::Migration),
+            Box::new(m20241105_add_activity_alert_columns_to_integration::Migration),
+            Box::new(m20241105_normalize_runtimes_stored_in_seconds::Migration),
+            Box::new(m20241106_create_integration_item_mapping::Migration),
+            Box::new(m20241107_create_integration_webhook_delivery::Migration),
+            Box::new(m20241107_add_review_comment_posted_to_notification_preferences::Migration),
+            Box::new(m20241108_create_safety_snapshot::Migration),
+            Box::new(m20241109_add_description_preview_to_metadata::Migration),
+            Box::new(m20241109_create_tag::Migration),
+            Box::new(m20241110_add_disable_activity_sharing_to_general_preferences::Migration),
+            Box::new(m20241110_create_outbound_webhook_delivery::Migration),
+            Box::new(m20241111_create_metadata_external_id::Migration),
+            Box::new(m20241112_add_default_collection_to_integration::Migration),
+            Box::new(m20241113_create_video_game_session::Migration),
+            Box::new(m20241114_add_sort_title_to_metadata::Migration),
+            Box::new(m20241115_add_index_to_metadata_to_metadata::Migration),
+            Box::new(m20241116_create_notification_delivery::Migration),
+            Box::new(m20241117_add_amount_consumed_columns_to_seen::Migration),
+            Box::new(m20241118_add_last_refreshed_on_to_metadata::Migration),
+            Box::new(m20241119_add_created_by_user_id_to_metadata::Migration),
+            Box::new(m20241120_add_rank_to_collection_to_entity::Migration),
+            Box::new(m20241121_add_smart_filter_to_collection::Migration),
+            Box::new(m20241122_add_share_token_to_collection::Migration),
+            Box::new(m20241123_add_contents_to_safety_snapshot::Migration),
+            Box::new(m20241124_recalculate_tag_keys::Migration),
+            Box::new(m20241125_add_next_attempt_on_to_integration_webhook_delivery::Migration),
+            Box::new(m20241126_rename_previous_source_external_ids::Migration),
+            Box::new(m20241127_add_update_retry_at_to_metadata::Migration),
         ]
     }
 }
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230417_create_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+pub enum CsvImportPreset {
+    Table,
+    Id,
+    Name,
+    UserId,
+    CreatedOn,
+    // The mapping of CSV headers to the fields understood by the importer
+    Mapping,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CsvImportPreset::Table)
+                    .col(
+                        ColumnDef::new(CsvImportPreset::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CsvImportPreset::Name).text().not_null())
+                    .col(ColumnDef::new(CsvImportPreset::UserId).text().not_null())
+                    .col(
+                        ColumnDef::new(CsvImportPreset::CreatedOn)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(CsvImportPreset::Mapping)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("csv_import_preset_to_user_foreign_key")
+                            .from(CsvImportPreset::Table, CsvImportPreset::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .unique()
+                    .name("csv_import_preset-user_id-name__unique-index")
+                    .table(CsvImportPreset::Table)
+                    .col(CsvImportPreset::UserId)
+                    .col(CsvImportPreset::Name)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
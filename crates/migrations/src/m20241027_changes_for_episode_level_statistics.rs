@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        // DEV: The existing activities are kept and the next run of the summary job
+        // recalculates them from the beginning to fill in the episode counts
+        db.execute_unprepared(
+            r#"
+ALTER TABLE "seen" ADD COLUMN IF NOT EXISTS "show_season_number" INTEGER
+GENERATED ALWAYS AS (("show_extra_information" ->> 'season')::INTEGER) STORED;
+ALTER TABLE "seen" ADD COLUMN IF NOT EXISTS "show_episode_number" INTEGER
+GENERATED ALWAYS AS (("show_extra_information" ->> 'episode')::INTEGER) STORED;
+ALTER TABLE "seen" ADD COLUMN IF NOT EXISTS "podcast_episode_number" INTEGER
+GENERATED ALWAYS AS (("podcast_extra_information" ->> 'episode')::INTEGER) STORED;
+ALTER TABLE "daily_user_activity" ADD COLUMN IF NOT EXISTS "show_episode_count" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "daily_user_activity" ADD COLUMN IF NOT EXISTS "podcast_episode_count" INTEGER NOT NULL DEFAULT 0;
+INSERT INTO "application_cache" ("key", "expires_at")
+VALUES ('"PendingFullSummaryRecalculation"', NOW() + INTERVAL '1 year')
+ON CONFLICT ("key") DO NOTHING;
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
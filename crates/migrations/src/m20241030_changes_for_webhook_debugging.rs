@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240607_create_integration::Integration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+pub enum WebhookDebugCapture {
+    Table,
+    Id,
+    IntegrationId,
+    CapturedOn,
+    // The request headers, without the ones that can contain credentials
+    Headers,
+    Body,
+    // The integration that the payload was handed to
+    Parser,
+    Outcome,
+    WasSuccess,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+ALTER TABLE "integration" ADD COLUMN IF NOT EXISTS "debug_mode_until" TIMESTAMPTZ;
+"#,
+        )
+        .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDebugCapture::Table)
+                    .col(
+                        ColumnDef::new(WebhookDebugCapture::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDebugCapture::IntegrationId)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDebugCapture::CapturedOn)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDebugCapture::Headers)
+                            .array(ColumnType::Text)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDebugCapture::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(WebhookDebugCapture::Parser)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDebugCapture::Outcome)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDebugCapture::WasSuccess)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("webhook_debug_capture_to_integration_foreign_key")
+                            .from(
+                                WebhookDebugCapture::Table,
+                                WebhookDebugCapture::IntegrationId,
+                            )
+                            .to(Integration::Table, Integration::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("webhook_debug_capture__integration_id__index")
+                    .table(WebhookDebugCapture::Table)
+                    .col(WebhookDebugCapture::IntegrationId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
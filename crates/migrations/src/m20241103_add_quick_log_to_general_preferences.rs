@@ -0,0 +1,25 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for table in ["user", "default_user_preferences"] {
+            db.execute_unprepared(&format!(
+                r#"
+UPDATE "{table}" SET "preferences" = jsonb_set("preferences", '{{general,quick_log_action}}', '"Now"');
+UPDATE "{table}" SET "preferences" = jsonb_set("preferences", '{{general,quick_log_auto_advance}}', 'true');
+"#
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if !manager
+            .has_column("metadata", "provider_rating_scale")
+            .await?
+        {
+            db.execute_unprepared(
+                r#"
+ALTER TABLE "metadata" ADD COLUMN "provider_rating_scale" DECIMAL;
+ALTER TABLE "metadata" ADD COLUMN "provider_rating_count" INTEGER;
+
+UPDATE "metadata" SET "provider_rating_scale" = 5, "provider_rating" = "provider_rating" * 20
+WHERE "provider_rating" IS NOT NULL AND "source" IN ('audible', 'google_books');
+
+UPDATE "metadata" SET "provider_rating_scale" = 10, "provider_rating" = "provider_rating" * 10
+WHERE "provider_rating" IS NOT NULL AND "source" IN ('mal', 'manga_updates');
+
+UPDATE "metadata" SET "provider_rating_scale" = 10
+WHERE "provider_rating" IS NOT NULL AND "source" = 'tmdb';
+
+UPDATE "metadata" SET "provider_rating_scale" = 100
+WHERE "provider_rating" IS NOT NULL AND "source" IN ('anilist', 'igdb', 'listennotes', 'vndb');
+"#,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
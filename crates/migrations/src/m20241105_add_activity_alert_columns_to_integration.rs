@@ -0,0 +1,31 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if !manager
+            .has_column("integration", "expected_activity_days")
+            .await?
+        {
+            db.execute_unprepared(
+                r#"
+ALTER TABLE "integration" ADD COLUMN "expected_activity_days" INTEGER;
+ALTER TABLE "integration" ADD COLUMN "alerts_snoozed_until" TIMESTAMP WITH TIME ZONE;
+ALTER TABLE "integration" ADD COLUMN "stale_alert_sent_on" TIMESTAMP WITH TIME ZONE;
+ALTER TABLE "integration" ADD COLUMN "consecutive_failures" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "integration" ADD COLUMN "health_state" TEXT NOT NULL DEFAULT 'healthy';
+"#,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// DEV: Runtimes are stored in minutes. Values above these limits are not
+// plausible in minutes and can only have been stored in seconds:
+// movies over 24 hours, audiobooks over 100 hours and episodes over 10 hours.
+const MOVIE_LIMIT: i64 = 24 * 60;
+const AUDIO_BOOK_LIMIT: i64 = 100 * 60;
+const EPISODE_LIMIT: i64 = 10 * 60;
+
+/// The runtime in minutes of a stored value, this is what the statements below
+/// do in the database.
+fn normalized_runtime(runtime: i64, limit: i64) -> i64 {
+    if runtime > limit {
+        (runtime as f64 / 60.0).round() as i64
+    } else {
+        runtime
+    }
+}
+
+fn normalizations() -> [(&'static str, String); 4] {
+    [
+        (
+            "movie runtimes",
+            format!(
+                r#"
+UPDATE "metadata"
+SET "movie_specifics" = jsonb_set(
+  "movie_specifics", '{{runtime}}',
+  to_jsonb(round(("movie_specifics"->>'runtime')::numeric / 60)::int)
+)
+WHERE ("movie_specifics"->>'runtime')::numeric > {MOVIE_LIMIT};
+"#
+            ),
+        ),
+        (
+            "audiobook runtimes",
+            format!(
+                r#"
+UPDATE "metadata"
+SET "audio_book_specifics" = jsonb_set(
+  "audio_book_specifics", '{{runtime}}',
+  to_jsonb(round(("audio_book_specifics"->>'runtime')::numeric / 60)::int)
+)
+WHERE ("audio_book_specifics"->>'runtime')::numeric > {AUDIO_BOOK_LIMIT};
+"#
+            ),
+        ),
+        (
+            "podcast episode runtimes",
+            format!(
+                r#"
+UPDATE "metadata"
+SET "podcast_specifics" = jsonb_set("podcast_specifics", '{{episodes}}', (
+  SELECT jsonb_agg(
+    CASE WHEN (e->>'runtime')::numeric > {EPISODE_LIMIT}
+      THEN jsonb_set(e, '{{runtime}}', to_jsonb(round((e->>'runtime')::numeric / 60)::int))
+      ELSE e
+    END ORDER BY idx
+  )
+  FROM jsonb_array_elements("podcast_specifics"->'episodes') WITH ORDINALITY AS t(e, idx)
+))
+WHERE EXISTS (
+  SELECT 1 FROM jsonb_array_elements("podcast_specifics"->'episodes') AS t(e)
+  WHERE (e->>'runtime')::numeric > {EPISODE_LIMIT}
+);
+"#
+            ),
+        ),
+        (
+            "show episode runtimes",
+            format!(
+                r#"
+UPDATE "metadata"
+SET "show_specifics" = jsonb_set("show_specifics", '{{seasons}}', (
+  SELECT jsonb_agg(
+    jsonb_set(s, '{{episodes}}', COALESCE((
+      SELECT jsonb_agg(
+        CASE WHEN (e->>'runtime')::numeric > {EPISODE_LIMIT}
+          THEN jsonb_set(e, '{{runtime}}', to_jsonb(round((e->>'runtime')::numeric / 60)::int))
+          ELSE e
+        END ORDER BY eidx
+      )
+      FROM jsonb_array_elements(s->'episodes') WITH ORDINALITY AS et(e, eidx)
+    ), '[]'::jsonb)) ORDER BY sidx
+  )
+  FROM jsonb_array_elements("show_specifics"->'seasons') WITH ORDINALITY AS st(s, sidx)
+))
+WHERE EXISTS (
+  SELECT 1
+  FROM jsonb_array_elements("show_specifics"->'seasons') AS st(s),
+  jsonb_array_elements(s->'episodes') AS et(e)
+  WHERE (e->>'runtime')::numeric > {EPISODE_LIMIT}
+);
+"#
+            ),
+        ),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for (name, statement) in normalizations() {
+            let result = db.execute_unprepared(&statement).await?;
+            tracing::info!(
+                "Converted {} from seconds to minutes in {} metadata row(s)",
+                name,
+                result.rows_affected()
+            );
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized_runtime_only_converts_implausible_minutes() {
+        // DEV: A three hour movie stays, the same movie stored in seconds is converted
+        assert_eq!(normalized_runtime(180, MOVIE_LIMIT), 180);
+        assert_eq!(normalized_runtime(10_800, MOVIE_LIMIT), 180);
+        assert_eq!(normalized_runtime(MOVIE_LIMIT, MOVIE_LIMIT), MOVIE_LIMIT);
+        assert_eq!(normalized_runtime(1_441, MOVIE_LIMIT), 24);
+        assert_eq!(normalized_runtime(5_999, AUDIO_BOOK_LIMIT), 5_999);
+        assert_eq!(normalized_runtime(45_000, AUDIO_BOOK_LIMIT), 750);
+        assert_eq!(normalized_runtime(600, EPISODE_LIMIT), 600);
+        assert_eq!(normalized_runtime(2_730, EPISODE_LIMIT), 46);
+        // DEV: Postgres rounds halves away from zero as well
+        assert_eq!(normalized_runtime(2_670, EPISODE_LIMIT), 45);
+        assert_eq!(normalized_runtime(2_610, EPISODE_LIMIT), 44);
+    }
+
+    #[test]
+    fn normalizations_use_the_limit_of_their_lot() {
+        let statements = normalizations();
+        assert!(statements[0].1.contains(&format!("> {};", MOVIE_LIMIT)));
+        assert!(statements[1]
+            .1
+            .contains(&format!("> {};", AUDIO_BOOK_LIMIT)));
+        for (_, statement) in &statements[2..] {
+            assert_eq!(
+                statement.matches(&format!("> {}", EPISODE_LIMIT)).count(),
+                2
+            );
+            assert!(statement.contains("'{runtime}'"));
+        }
+        assert!(statements[0].1.contains("'{runtime}'"));
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "integration_item_mapping" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "integration_id" TEXT NOT NULL,
+    "metadata_id" TEXT NOT NULL,
+    "server_item_id" TEXT NOT NULL,
+    "server_id" TEXT,
+    "updated_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "integration_item_mapping_to_integration_foreign_key"
+        FOREIGN KEY ("integration_id") REFERENCES "integration" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "integration_item_mapping_to_metadata_foreign_key"
+        FOREIGN KEY ("metadata_id") REFERENCES "metadata" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "integration_item_mapping__integration_id__metadata_id__index"
+ON "integration_item_mapping" ("integration_id", "metadata_id");
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
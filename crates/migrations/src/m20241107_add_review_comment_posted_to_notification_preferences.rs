@@ -0,0 +1,29 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for table in ["user", "default_user_preferences"] {
+            db.execute_unprepared(&format!(
+                r#"
+UPDATE "{table}" SET "preferences" = jsonb_set(
+    "preferences",
+    '{{notifications,to_send}}',
+    ("preferences" -> 'notifications' -> 'to_send') || '"ReviewCommentPosted"'
+)
+WHERE NOT ("preferences" -> 'notifications' -> 'to_send' ? 'ReviewCommentPosted');
+"#
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
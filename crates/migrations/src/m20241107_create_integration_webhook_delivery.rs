@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "integration_webhook_delivery" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "integration_id" TEXT NOT NULL,
+    "received_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "processed_on" TIMESTAMP WITH TIME ZONE,
+    "headers" TEXT[] NOT NULL DEFAULT '{}',
+    "payload" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'queued',
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "error" TEXT,
+    CONSTRAINT "integration_webhook_delivery_to_integration_foreign_key"
+        FOREIGN KEY ("integration_id") REFERENCES "integration" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "integration_webhook_delivery__integration_id__received_on__index"
+ON "integration_webhook_delivery" ("integration_id", "received_on");
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
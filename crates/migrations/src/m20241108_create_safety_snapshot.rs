@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "safety_snapshot" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "created_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "user_id" TEXT,
+    "reason" TEXT NOT NULL,
+    "seen_count" INTEGER NOT NULL,
+    "review_count" INTEGER NOT NULL,
+    "restored_on" TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT "safety_snapshot_to_user_foreign_key"
+        FOREIGN KEY ("user_id") REFERENCES "user" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
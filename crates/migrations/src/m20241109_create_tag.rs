@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "tag" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "key" TEXT NOT NULL UNIQUE,
+    "created_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "is_hidden" BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS "metadata_to_tag" (
+    "metadata_id" TEXT NOT NULL,
+    "tag_id" TEXT NOT NULL,
+    "user_id" TEXT,
+    "created_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("metadata_id", "tag_id"),
+    CONSTRAINT "metadata_to_tag_to_metadata_foreign_key"
+        FOREIGN KEY ("metadata_id") REFERENCES "metadata" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "metadata_to_tag_to_tag_foreign_key"
+        FOREIGN KEY ("tag_id") REFERENCES "tag" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "metadata_to_tag_to_user_foreign_key"
+        FOREIGN KEY ("user_id") REFERENCES "user" ("id")
+        ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "metadata_to_tag__tag_id_idx" ON "metadata_to_tag" ("tag_id");
+"#,
+        )
+        .await?;
+        for table in ["user", "default_user_preferences"] {
+            db.execute_unprepared(&format!(
+                r#"
+UPDATE "{table}" SET "preferences" = jsonb_set("preferences", '{{general,content_warning_tags}}', '[]');
+"#
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "outbound_webhook_delivery" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "integration_id" TEXT NOT NULL,
+    "event" TEXT NOT NULL,
+    "payload" TEXT NOT NULL,
+    "created_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "next_attempt_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "delivered_on" TIMESTAMP WITH TIME ZONE,
+    "status" TEXT NOT NULL DEFAULT 'queued',
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "response_status" INTEGER,
+    "error" TEXT,
+    CONSTRAINT "outbound_webhook_delivery_to_integration_foreign_key"
+        FOREIGN KEY ("integration_id") REFERENCES "integration" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "outbound_webhook_delivery__integration_id__created_on__index"
+ON "outbound_webhook_delivery" ("integration_id", "created_on");
+
+CREATE INDEX IF NOT EXISTS "outbound_webhook_delivery__status__next_attempt_on__index"
+ON "outbound_webhook_delivery" ("status", "next_attempt_on");
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
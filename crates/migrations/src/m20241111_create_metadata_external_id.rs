@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "metadata_external_id" (
+    "id_type" TEXT NOT NULL,
+    "value" TEXT NOT NULL,
+    "metadata_id" TEXT NOT NULL,
+    PRIMARY KEY ("id_type", "value"),
+    CONSTRAINT "metadata_external_id_to_metadata_foreign_key"
+        FOREIGN KEY ("metadata_id") REFERENCES "metadata" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "metadata_external_id__metadata_id_idx"
+ON "metadata_external_id" ("metadata_id");
+
+INSERT INTO "metadata_external_id" ("id_type", "value", "metadata_id")
+SELECT
+    CASE
+        WHEN "source" = 'tmdb' AND "lot" = 'movie' THEN 'tmdb_movie'
+        WHEN "source" = 'tmdb' THEN 'tmdb_show'
+        WHEN "source" = 'anilist' THEN 'anilist'
+        WHEN "lot" = 'anime' THEN 'mal_anime'
+        ELSE 'mal_manga'
+    END,
+    "identifier",
+    "id"
+FROM "metadata"
+WHERE ("source" = 'tmdb' AND "lot" IN ('movie', 'show'))
+    OR "source" = 'anilist'
+    OR ("source" = 'mal' AND "lot" IN ('anime', 'manga'))
+ON CONFLICT DO NOTHING;
+
+INSERT INTO "metadata_external_id" ("id_type", "value", "metadata_id")
+SELECT 'tvdb', "external_identifiers" ->> 'tvdb_id', "id"
+FROM "metadata"
+WHERE "external_identifiers" ->> 'tvdb_id' IS NOT NULL
+ON CONFLICT DO NOTHING;
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
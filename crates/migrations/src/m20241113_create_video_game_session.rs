@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "video_game_session" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "user_id" TEXT NOT NULL,
+    "metadata_id" TEXT NOT NULL,
+    "integration_id" TEXT,
+    "started_on" TIMESTAMP WITH TIME ZONE NOT NULL,
+    "last_heartbeat_on" TIMESTAMP WITH TIME ZONE NOT NULL,
+    "duration" INTEGER NOT NULL DEFAULT 0,
+    CONSTRAINT "video_game_session_to_user_foreign_key"
+        FOREIGN KEY ("user_id") REFERENCES "user" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "video_game_session_to_metadata_foreign_key"
+        FOREIGN KEY ("metadata_id") REFERENCES "metadata" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "video_game_session_to_integration_foreign_key"
+        FOREIGN KEY ("integration_id") REFERENCES "integration" ("id")
+        ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "video_game_session__user_id__metadata_id__last_heartbeat_on__index"
+ON "video_game_session" ("user_id", "metadata_id", "last_heartbeat_on");
+
+CREATE INDEX IF NOT EXISTS "video_game_session__user_id__started_on__index"
+ON "video_game_session" ("user_id", "started_on");
+
+INSERT INTO "metadata_external_id" ("id_type", "value", "metadata_id")
+SELECT 'igdb', "identifier", "id" FROM "metadata" WHERE "source" = 'igdb'
+ON CONFLICT DO NOTHING;
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
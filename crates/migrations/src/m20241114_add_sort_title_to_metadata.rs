@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if !manager.has_column("metadata", "sort_title").await? {
+            // DEV: Existing rows are filled in properly by a job on startup
+            db.execute_unprepared(
+                r#"
+ALTER TABLE "metadata" ADD COLUMN "sort_title" TEXT;
+ALTER TABLE "metadata" ADD COLUMN "sort_title_override" TEXT;
+CREATE INDEX "metadata-sort_title__index" ON "metadata" ("sort_title");
+"#,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+CREATE TABLE IF NOT EXISTS "notification_delivery" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "notification_platform_id" TEXT NOT NULL,
+    "message" TEXT NOT NULL,
+    "created_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "next_attempt_on" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "delivered_on" TIMESTAMP WITH TIME ZONE,
+    "status" TEXT NOT NULL DEFAULT 'queued',
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "error" TEXT,
+    CONSTRAINT "notification_delivery_to_notification_platform_foreign_key"
+        FOREIGN KEY ("notification_platform_id") REFERENCES "notification_platform" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "notification_delivery__notification_platform_id__status__index"
+ON "notification_delivery" ("notification_platform_id", "status");
+
+CREATE INDEX IF NOT EXISTS "notification_delivery__status__next_attempt_on__index"
+ON "notification_delivery" ("status", "next_attempt_on");
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
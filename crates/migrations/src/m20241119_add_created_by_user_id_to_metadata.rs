@@ -0,0 +1,29 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+ALTER TABLE "metadata" ADD COLUMN IF NOT EXISTS "created_by_user_id" TEXT
+    REFERENCES "user" ("id") ON UPDATE CASCADE ON DELETE SET NULL;
+
+UPDATE "metadata" "m" SET "created_by_user_id" = "c"."user_id"
+FROM "collection_to_entity" "cte"
+JOIN "collection" "c" ON "c"."id" = "cte"."collection_id"
+WHERE "cte"."metadata_id" = "m"."id" AND "c"."name" = 'Custom'
+AND "m"."source" = 'custom' AND "m"."created_by_user_id" IS NULL;
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if !manager.has_column("collection_to_entity", "rank").await? {
+            db.execute_unprepared(
+                r#"
+ALTER TABLE "collection_to_entity" ADD COLUMN "rank" INTEGER NOT NULL DEFAULT 0;
+
+UPDATE "collection_to_entity" "cte" SET "rank" = "ranked"."rank"
+FROM (
+    SELECT "id", ROW_NUMBER() OVER (
+        PARTITION BY "collection_id" ORDER BY "created_on", "id"
+    ) AS "rank"
+    FROM "collection_to_entity"
+) "ranked"
+WHERE "cte"."id" = "ranked"."id";
+
+ALTER TABLE "collection_to_entity" ADD CONSTRAINT "collection_to_entity__collection_id-rank__key"
+    UNIQUE ("collection_id", "rank") DEFERRABLE INITIALLY DEFERRED;
+"#,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
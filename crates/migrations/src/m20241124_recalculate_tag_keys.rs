@@ -0,0 +1,26 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        // DEV: Tag keys are computed by the application, so the background job
+        // recalculates them after the rules for plurals changed
+        db.execute_unprepared(
+            r#"
+INSERT INTO "application_cache" ("key", "expires_at")
+VALUES ('"PendingTagKeyRecalculation"', NOW() + INTERVAL '1 year')
+ON CONFLICT ("key") DO NOTHING;
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+UPDATE "metadata_external_id"
+SET "value" =
+    LOWER(REGEXP_REPLACE(SPLIT_PART("value", ':', 1), '([a-z])([A-Z])', '\1_\2', 'g'))
+    || ':'
+    || LOWER(REGEXP_REPLACE(SPLIT_PART("value", ':', 2), '([a-z])([A-Z])', '\1_\2', 'g'))
+    || SUBSTR("value", LENGTH(SPLIT_PART("value", ':', 1)) + LENGTH(SPLIT_PART("value", ':', 2)) + 2)
+WHERE "id_type" = 'previous_source' AND "value" ~ '^[A-Z]';
+"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use async_graphql::{Enum, InputObject, SimpleObject};
 use educe::Educe;
 use enum_meta::{meta, Meta};
-use enums::EntityLot;
+use enums::{AnnouncementSeverity, EntityLot, MediaLot, MediaSource};
 use rust_decimal::Decimal;
 use schematic::{ConfigEnum, Schematic};
 use sea_orm::{prelude::DateTimeUtc, FromJsonQueryResult};
@@ -139,6 +139,7 @@ pub enum BackgroundJob {
     PerformBackgroundTasks,
     ReEvaluateUserWorkouts,
     RecalculateCalendarEvents,
+    RecalculateSortTitles,
     CalculateUserActivitiesAndSummary,
 }
 
@@ -150,6 +151,7 @@ pub enum BackendError {
     SessionExpired,
     AdminOnlyAction,
     MutationNotAllowed,
+    ValidationFailed,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, InputObject)]
@@ -191,6 +193,14 @@ pub enum MediaStateChanged {
     MetadataEpisodeImagesChanged,
     PersonMediaAssociated,
     ReviewPosted,
+    ReviewCommentPosted,
+}
+
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct ScheduledJobRuns {
+    pub job: String,
+    pub cron_expression: String,
+    pub next_runs: Vec<DateTimeUtc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
@@ -201,9 +211,79 @@ pub struct ExportJob {
     pub started_at: DateTimeUtc,
 }
 
+/// The outcome of the last run of the scheduled exports.
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct ScheduledExportStatus {
+    pub started_at: DateTimeUtc,
+    pub ended_at: DateTimeUtc,
+    pub exported_users: usize,
+    /// The users whose export failed along with the error.
+    pub failures: Vec<String>,
+}
+
+#[derive(Enum, Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProviderCircuitState {
+    /// Calls are going through to the provider.
+    Closed,
+    /// Calls fail fast until the cooldown is over.
+    Open,
+    /// The cooldown is over and a single probe call is allowed through.
+    HalfOpen,
+}
+
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct ProviderStatus {
+    pub source: MediaSource,
+    pub state: ProviderCircuitState,
+    pub consecutive_failures: i32,
+    pub opened_at: Option<DateTimeUtc>,
+    pub retry_at: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct CreateOrUpdateAnnouncementInput {
+    pub update_id: Option<String>,
+    pub message: String,
+    pub severity: AnnouncementSeverity,
+    /// Defaults to the current time.
+    pub starts_at: Option<DateTimeUtc>,
+    /// The announcement is shown indefinitely if not provided.
+    pub ends_at: Option<DateTimeUtc>,
+    #[graphql(default = true)]
+    pub is_dismissible: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum UserDashboardSection {
+    InProgress,
+    Upcoming,
+    Summary,
+    Recommendations,
+    PendingNotifications,
+    Favorites,
+}
+
+#[derive(Debug, Serialize, Deserialize, Enum, Clone, Copy, Eq, PartialEq)]
+pub enum InstancePopularWindow {
+    Week,
+    Month,
+    Quarter,
+}
+
 #[skip_serializing_none]
 #[derive(Clone, Debug, PartialEq, FromJsonQueryResult, Eq, Serialize, Deserialize)]
 pub enum ApplicationCacheKey {
+    AdminDashboardStatistics,
+    MetadataRefreshProgress {
+        metadata_id: String,
+    },
+    MetadataForcedRefresh {
+        metadata_id: String,
+    },
+    UserDashboardSection {
+        user_id: String,
+        section: UserDashboardSection,
+    },
     ProgressUpdateCache {
         user_id: String,
         metadata_id: String,
@@ -214,4 +294,16 @@ pub enum ApplicationCacheKey {
         manga_chapter_number: Option<Decimal>,
         manga_volume_number: Option<i32>,
     },
+    InstancePopular {
+        lot: MediaLot,
+        window: InstancePopularWindow,
+        include_nsfw: bool,
+    },
+    UserYearlySummary {
+        user_id: String,
+        year: i32,
+    },
+    LastScheduledExportStatus,
+    PendingFullSummaryRecalculation,
+    PendingTagKeyRecalculation,
 }
@@ -12,6 +12,7 @@ pub struct Model {
     #[sea_orm(column_type = "Json")]
     pub key: ApplicationCacheKey,
     pub expires_at: Option<DateTimeUtc>,
+    pub value: Option<serde_json::Value>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -3,6 +3,7 @@
 use async_graphql::SimpleObject;
 use async_trait::async_trait;
 use common_models::CollectionExtraInformation;
+use media_models::SmartCollectionFilter;
 use nanoid::nanoid;
 use schematic::Schematic;
 use sea_orm::entity::prelude::*;
@@ -24,6 +25,12 @@ pub struct Model {
     pub user_id: String,
     #[sea_orm(column_type = "Json")]
     pub information_template: Option<Vec<CollectionExtraInformation>>,
+    #[sea_orm(column_type = "Json")]
+    pub smart_filter: Option<SmartCollectionFilter>,
+    /// Anyone with this token can see the media in the collection.
+    #[graphql(skip)]
+    pub share_token: Option<String>,
+    pub share_token_expires_on: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -22,6 +22,8 @@ pub struct Model {
     pub workout_id: Option<String>,
     pub information: Option<serde_json::Value>,
     pub workout_template_id: Option<String>,
+    /// The position of the entity in the collection, lower ranks come first.
+    pub rank: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -25,11 +25,13 @@ pub struct Model {
     pub book_count: i32,
     pub book_pages: i32,
     pub podcast_count: i32,
+    pub podcast_episode_count: i32,
     pub podcast_duration: i32,
     pub manga_count: i32,
     pub movie_count: i32,
     pub movie_duration: i32,
     pub show_count: i32,
+    pub show_episode_count: i32,
     pub show_duration: i32,
     pub video_game_count: i32,
     pub video_game_duration: i32,
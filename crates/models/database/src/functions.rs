@@ -2,6 +2,7 @@ use async_graphql::Result;
 use chrono::Utc;
 use enums::EntityLot;
 use sea_orm::{
+    sea_query::{Expr, OnConflict},
     ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
 };
 
@@ -72,3 +73,175 @@ where
         }
     })
 }
+
+/// Does the same as `associate_user_with_entity` for many entities at once, using
+/// one query to create the missing associations and one to touch all of them.
+pub async fn associate_user_with_entities<C>(
+    db: &C,
+    user_id: &String,
+    entity_ids: Vec<String>,
+    entity_lot: EntityLot,
+) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    if entity_ids.is_empty() {
+        return Ok(());
+    }
+    let column = match entity_lot {
+        EntityLot::Metadata => user_to_entity::Column::MetadataId,
+        EntityLot::Person => user_to_entity::Column::PersonId,
+        EntityLot::Exercise => user_to_entity::Column::ExerciseId,
+        EntityLot::MetadataGroup => user_to_entity::Column::MetadataGroupId,
+        EntityLot::Collection | EntityLot::Workout | EntityLot::WorkoutTemplate => unreachable!(),
+    };
+    let now = Utc::now();
+    let inserts = entity_ids.iter().map(|entity_id| {
+        let mut user_to_meta = user_to_entity::ActiveModel {
+            user_id: ActiveValue::Set(user_id.to_owned()),
+            last_updated_on: ActiveValue::Set(now),
+            needs_to_be_updated: ActiveValue::Set(Some(true)),
+            ..Default::default()
+        };
+        user_to_meta.set(column, Some(entity_id.to_owned()).into());
+        user_to_meta
+    });
+    UserToEntity::insert_many(inserts)
+        .on_conflict(OnConflict::new().do_nothing().to_owned())
+        .exec_without_returning(db)
+        .await?;
+    UserToEntity::update_many()
+        .col_expr(user_to_entity::Column::LastUpdatedOn, Expr::value(now))
+        .col_expr(user_to_entity::Column::NeedsToBeUpdated, Expr::value(true))
+        .filter(user_to_entity::Column::UserId.eq(user_id))
+        .filter(column.is_in(entity_ids))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Statement, Transaction, Value};
+    use uuid::Uuid;
+
+    use super::*;
+
+    const USER_ID: &str = "usr_1";
+
+    fn association(metadata_id: &str) -> user_to_entity::Model {
+        user_to_entity::Model {
+            id: Uuid::new_v4(),
+            created_on: Utc::now(),
+            last_updated_on: Utc::now(),
+            user_id: USER_ID.to_owned(),
+            person_id: None,
+            metadata_id: Some(metadata_id.to_owned()),
+            exercise_id: None,
+            collection_id: None,
+            exercise_extra_information: None,
+            exercise_num_times_interacted: None,
+            media_reason: None,
+            needs_to_be_updated: Some(true),
+            metadata_group_id: None,
+        }
+    }
+
+    fn statements(log: Vec<Transaction>) -> Vec<Statement> {
+        log.into_iter()
+            .flat_map(|t| t.statements().to_vec())
+            .collect()
+    }
+
+    /// The metadata ids bound in the statements that start with the given keyword.
+    fn metadata_ids(statements: &[Statement], keyword: &str) -> BTreeSet<String> {
+        statements
+            .iter()
+            .filter(|s| s.sql.starts_with(keyword))
+            .flat_map(|s| s.values.iter().flat_map(|v| v.0.iter()))
+            .filter_map(|v| match v {
+                Value::String(Some(s)) if s.starts_with("meta_") => Some(s.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn batched_associations_match_the_per_item_ones() {
+        // DEV: The user already has an association with the first item
+        let fixture = ["meta_1", "meta_2", "meta_3"];
+        let user_id = USER_ID.to_owned();
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([
+                vec![association("meta_1")],
+                vec![association("meta_1")],
+                vec![],
+                vec![association("meta_2")],
+                vec![],
+                vec![association("meta_3")],
+            ])
+            .into_connection();
+        for metadata_id in fixture {
+            let associated = associate_user_with_entity(
+                &db,
+                &user_id,
+                metadata_id.to_owned(),
+                EntityLot::Metadata,
+            )
+            .await
+            .unwrap();
+            assert_eq!(associated.metadata_id.as_deref(), Some(metadata_id));
+            assert_eq!(associated.needs_to_be_updated, Some(true));
+        }
+        let per_item = statements(db.into_transaction_log());
+        assert_eq!(per_item.len(), 6);
+        assert_eq!(
+            metadata_ids(&per_item, "INSERT"),
+            BTreeSet::from(["meta_2".to_owned(), "meta_3".to_owned()])
+        );
+        assert_eq!(
+            per_item
+                .iter()
+                .filter(|s| s.sql.starts_with("UPDATE"))
+                .count(),
+            1
+        );
+
+        let exec = || MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 2,
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([exec(), exec()])
+            .into_connection();
+        associate_user_with_entities(
+            &db,
+            &user_id,
+            fixture.map(String::from).to_vec(),
+            EntityLot::Metadata,
+        )
+        .await
+        .unwrap();
+        let batched = statements(db.into_transaction_log());
+        assert_eq!(batched.len(), 2);
+        assert!(batched[0].sql.ends_with("ON CONFLICT DO NOTHING"));
+        // DEV: The existing association is skipped by the insert and touched by the
+        // update, which leaves the same rows as the per-item path
+        let all = BTreeSet::from(fixture.map(String::from));
+        assert_eq!(metadata_ids(&batched, "INSERT"), all);
+        assert_eq!(metadata_ids(&batched, "UPDATE"), all);
+        assert!(batched[1].sql.contains(r#""needs_to_be_updated" = $2"#));
+    }
+
+    #[tokio::test]
+    async fn nothing_is_batched_without_entities() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        associate_user_with_entities(&db, &USER_ID.to_owned(), vec![], EntityLot::Metadata)
+            .await
+            .unwrap();
+        assert!(db.into_transaction_log().is_empty());
+    }
+}
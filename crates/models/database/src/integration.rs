@@ -2,7 +2,7 @@
 
 use async_graphql::{InputObject, SimpleObject};
 use async_trait::async_trait;
-use enums::{IntegrationLot, IntegrationProvider};
+use enums::{IntegrationHealthState, IntegrationLot, IntegrationProvider};
 use media_models::IntegrationProviderSpecifics;
 use nanoid::nanoid;
 use sea_orm::{entity::prelude::*, ActiveValue};
@@ -21,11 +21,28 @@ pub struct Model {
     pub lot: IntegrationLot,
     pub provider: IntegrationProvider,
     pub sync_to_owned_collection: Option<bool>,
+    /// Media that the user gets for the first time through this integration is
+    /// also added to this collection.
+    pub default_collection: Option<String>,
     pub is_disabled: Option<bool>,
     #[graphql(skip_input)]
     pub created_on: DateTimeUtc,
     #[graphql(skip_input)]
     pub last_triggered_on: Option<DateTimeUtc>,
+    /// Requests to the webhook are captured for debugging until this time.
+    #[graphql(skip_input)]
+    pub debug_mode_until: Option<DateTimeUtc>,
+    /// The user is alerted if no data is received for this many days.
+    pub expected_activity_days: Option<i32>,
+    /// No stale alerts are sent for this integration until this time.
+    #[graphql(skip_input)]
+    pub alerts_snoozed_until: Option<DateTimeUtc>,
+    #[graphql(skip)]
+    pub stale_alert_sent_on: Option<DateTimeUtc>,
+    #[graphql(skip)]
+    pub consecutive_failures: i32,
+    #[graphql(skip_input)]
+    pub health_state: IntegrationHealthState,
     #[sea_orm(column_type = "Json")]
     #[graphql(skip)]
     pub provider_specifics: Option<IntegrationProviderSpecifics>,
@@ -41,6 +58,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     User,
+    #[sea_orm(has_many = "super::integration_item_mapping::Entity")]
+    IntegrationItemMapping,
+    #[sea_orm(has_many = "super::integration_webhook_delivery::Entity")]
+    IntegrationWebhookDelivery,
+    #[sea_orm(has_many = "super::outbound_webhook_delivery::Entity")]
+    OutboundWebhookDelivery,
+    #[sea_orm(has_many = "super::webhook_debug_capture::Entity")]
+    WebhookDebugCapture,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -49,6 +74,30 @@ impl Related<super::user::Entity> for Entity {
     }
 }
 
+impl Related<super::integration_item_mapping::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IntegrationItemMapping.def()
+    }
+}
+
+impl Related<super::integration_webhook_delivery::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IntegrationWebhookDelivery.def()
+    }
+}
+
+impl Related<super::outbound_webhook_delivery::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OutboundWebhookDelivery.def()
+    }
+}
+
+impl Related<super::webhook_debug_capture::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WebhookDebugCapture.def()
+    }
+}
+
 #[async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
@@ -3,30 +3,43 @@
 pub mod prelude;
 
 pub mod access_link;
+pub mod announcement;
 pub mod application_cache;
 pub mod calendar_event;
 pub mod collection;
 pub mod collection_to_entity;
+pub mod csv_import_preset;
 pub mod daily_user_activity;
+pub mod default_user_preferences;
 pub mod exercise;
 pub mod functions;
 pub mod genre;
 pub mod import_report;
 pub mod integration;
+pub mod integration_item_mapping;
+pub mod integration_webhook_delivery;
 pub mod metadata;
+pub mod metadata_external_id;
 pub mod metadata_group;
 pub mod metadata_to_genre;
 pub mod metadata_to_metadata;
 pub mod metadata_to_metadata_group;
 pub mod metadata_to_person;
+pub mod metadata_to_tag;
 pub mod monitored_entity;
+pub mod notification_delivery;
 pub mod notification_platform;
+pub mod outbound_webhook_delivery;
 pub mod person;
 pub mod queued_notification;
 pub mod review;
+pub mod safety_snapshot;
 pub mod seen;
+pub mod tag;
 pub mod user;
 pub mod user_measurement;
 pub mod user_to_entity;
+pub mod video_game_session;
+pub mod webhook_debug_capture;
 pub mod workout;
 pub mod workout_template;
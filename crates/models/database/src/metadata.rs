@@ -21,18 +21,34 @@ pub struct Model {
     pub created_on: DateTimeUtc,
     pub lot: MediaLot,
     pub last_updated_on: DateTimeUtc,
+    /// When the details were last fetched from the provider by a refresh.
+    pub last_refreshed_on: Option<DateTimeUtc>,
+    /// When an update that was postponed because the provider was unavailable
+    /// should be queued again.
+    pub update_retry_at: Option<DateTimeUtc>,
     pub title: String,
+    /// The title used when sorting by title, see `sort_title_override`.
+    pub sort_title: Option<String>,
+    /// Set by an admin when the computed sort title is wrong. It takes the
+    /// place of the computed one and is kept when the metadata is refreshed.
+    pub sort_title_override: Option<String>,
     pub identifier: String,
     pub source: MediaSource,
     pub is_nsfw: Option<bool>,
     pub is_partial: Option<bool>,
     pub is_recommendation: Option<bool>,
     pub description: Option<String>,
+    /// The first few sentences of the description, shown in lists.
+    pub description_preview: Option<String>,
     pub original_language: Option<String>,
     pub publish_year: Option<i32>,
     pub publish_date: Option<NaiveDate>,
     pub production_status: Option<String>,
+    /// Out of 100, whatever scale the provider uses.
     pub provider_rating: Option<Decimal>,
+    /// The best rating on the scale that the provider uses.
+    pub provider_rating_scale: Option<Decimal>,
+    pub provider_rating_count: Option<i32>,
     #[sea_orm(column_type = "Json")]
     pub images: Option<Vec<MetadataImage>>,
     #[sea_orm(column_type = "Json")]
@@ -53,6 +69,9 @@ pub struct Model {
     pub anime_specifics: Option<AnimeSpecifics>,
     pub manga_specifics: Option<MangaSpecifics>,
     pub state_changes: Option<MetadataStateChanges>,
+    pub images_unreachable: Option<bool>,
+    /// The user who created the media item when its source is custom.
+    pub created_by_user_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -61,16 +80,28 @@ pub enum Relation {
     CalendarEvent,
     #[sea_orm(has_many = "super::collection_to_entity::Entity")]
     CollectionToEntity,
+    #[sea_orm(has_many = "super::metadata_external_id::Entity")]
+    MetadataExternalId,
     #[sea_orm(has_many = "super::metadata_to_genre::Entity")]
     MetadataToGenre,
     #[sea_orm(has_many = "super::metadata_to_metadata_group::Entity")]
     MetadataToMetadataGroup,
     #[sea_orm(has_many = "super::metadata_to_person::Entity")]
     MetadataToPerson,
+    #[sea_orm(has_many = "super::metadata_to_tag::Entity")]
+    MetadataToTag,
     #[sea_orm(has_many = "super::review::Entity")]
     Review,
     #[sea_orm(has_many = "super::seen::Entity")]
     Seen,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::CreatedByUserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    User,
     #[sea_orm(has_many = "super::user_to_entity::Entity")]
     UserToEntity,
 }
@@ -87,6 +118,12 @@ impl Related<super::collection_to_entity::Entity> for Entity {
     }
 }
 
+impl Related<super::metadata_external_id::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MetadataExternalId.def()
+    }
+}
+
 impl Related<super::metadata_to_genre::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::MetadataToGenre.def()
@@ -105,6 +142,12 @@ impl Related<super::metadata_to_person::Entity> for Entity {
     }
 }
 
+impl Related<super::metadata_to_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MetadataToTag.def()
+    }
+}
+
 impl Related<super::review::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Review.def()
@@ -117,6 +160,12 @@ impl Related<super::seen::Entity> for Entity {
     }
 }
 
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
 impl Related<super::user_to_entity::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::UserToEntity.def()
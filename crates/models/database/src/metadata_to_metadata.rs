@@ -13,6 +13,8 @@ pub struct Model {
     pub from_metadata_id: String,
     pub relation: MetadataToMetadataRelation,
     pub to_metadata_id: String,
+    /// The position in the list that the provider returned, only set for suggestions.
+    pub index: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -33,6 +33,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     User,
+    #[sea_orm(has_many = "super::notification_delivery::Entity")]
+    NotificationDelivery,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -41,6 +43,12 @@ impl Related<super::user::Entity> for Entity {
     }
 }
 
+impl Related<super::notification_delivery::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NotificationDelivery.def()
+    }
+}
+
 #[async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
@@ -1,29 +1,42 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.1
 
 pub use super::access_link::Entity as AccessLink;
+pub use super::announcement::Entity as Announcement;
 pub use super::application_cache::Entity as ApplicationCache;
 pub use super::calendar_event::Entity as CalendarEvent;
 pub use super::collection::Entity as Collection;
 pub use super::collection_to_entity::Entity as CollectionToEntity;
+pub use super::csv_import_preset::Entity as CsvImportPreset;
 pub use super::daily_user_activity::Entity as DailyUserActivity;
+pub use super::default_user_preferences::Entity as DefaultUserPreferences;
 pub use super::exercise::Entity as Exercise;
 pub use super::genre::Entity as Genre;
 pub use super::import_report::Entity as ImportReport;
 pub use super::integration::Entity as Integration;
+pub use super::integration_item_mapping::Entity as IntegrationItemMapping;
+pub use super::integration_webhook_delivery::Entity as IntegrationWebhookDelivery;
 pub use super::metadata::Entity as Metadata;
+pub use super::metadata_external_id::Entity as MetadataExternalId;
 pub use super::metadata_group::Entity as MetadataGroup;
 pub use super::metadata_to_genre::Entity as MetadataToGenre;
 pub use super::metadata_to_metadata::Entity as MetadataToMetadata;
 pub use super::metadata_to_metadata_group::Entity as MetadataToMetadataGroup;
 pub use super::metadata_to_person::Entity as MetadataToPerson;
+pub use super::metadata_to_tag::Entity as MetadataToTag;
 pub use super::monitored_entity::Entity as MonitoredEntity;
+pub use super::notification_delivery::Entity as NotificationDelivery;
 pub use super::notification_platform::Entity as NotificationPlatform;
+pub use super::outbound_webhook_delivery::Entity as OutboundWebhookDelivery;
 pub use super::person::Entity as Person;
 pub use super::queued_notification::Entity as QueuedNotification;
 pub use super::review::Entity as Review;
+pub use super::safety_snapshot::Entity as SafetySnapshot;
 pub use super::seen::Entity as Seen;
+pub use super::tag::Entity as Tag;
 pub use super::user::Entity as User;
 pub use super::user_measurement::Entity as UserMeasurement;
 pub use super::user_to_entity::Entity as UserToEntity;
+pub use super::video_game_session::Entity as VideoGameSession;
+pub use super::webhook_debug_capture::Entity as WebhookDebugCapture;
 pub use super::workout::Entity as Workout;
 pub use super::workout_template::Entity as WorkoutTemplate;
@@ -6,7 +6,7 @@ use chrono::{NaiveDate, Utc};
 use educe::Educe;
 use enums::{EntityLot, SeenState};
 use media_models::{
-    SeenAnimeExtraInformation, SeenMangaExtraInformation, SeenPodcastExtraInformation,
+    SeenAnimeExtraInformation, SeenEdition, SeenMangaExtraInformation, SeenPodcastExtraInformation,
     SeenShowExtraInformation,
 };
 use nanoid::nanoid;
@@ -40,9 +40,20 @@ pub struct Model {
     pub anime_extra_information: Option<SeenAnimeExtraInformation>,
     pub manga_extra_information: Option<SeenMangaExtraInformation>,
     pub manual_time_spent: Option<Decimal>,
+    pub edition: Option<SeenEdition>,
+    /// The number of pages read when the progress was given in pages.
+    pub pages_read: Option<i32>,
+    /// The number of minutes consumed when the progress was given in minutes.
+    pub minutes_consumed: Option<i32>,
     // Generated columns
     pub last_updated_on: DateTimeUtc,
     pub num_times_updated: i32,
+    #[graphql(skip)]
+    pub show_season_number: Option<i32>,
+    #[graphql(skip)]
+    pub show_episode_number: Option<i32>,
+    #[graphql(skip)]
+    pub podcast_episode_number: Option<i32>,
     pub review_id: Option<String>,
 }
 
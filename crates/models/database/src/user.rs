@@ -37,6 +37,8 @@ pub enum Relation {
     AccessLink,
     #[sea_orm(has_many = "super::collection::Entity")]
     Collection,
+    #[sea_orm(has_many = "super::csv_import_preset::Entity")]
+    CsvImportPreset,
     #[sea_orm(has_many = "super::exercise::Entity")]
     Exercise,
     #[sea_orm(has_many = "super::import_report::Entity")]
@@ -67,6 +69,12 @@ impl Related<super::access_link::Entity> for Entity {
     }
 }
 
+impl Related<super::csv_import_preset::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CsvImportPreset.def()
+    }
+}
+
 impl Related<super::exercise::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Exercise.def()
@@ -1,18 +1,20 @@
 use async_graphql::{InputObject, OutputType, SimpleObject, Union};
-use common_models::{BackendError, SearchDetails};
+use chrono::NaiveDate;
+use common_models::{BackendError, ProviderStatus, SearchDetails};
 use config::FrontendConfig;
 use database_models::{
     collection, exercise, metadata, metadata_group, person, seen, user, user_measurement,
-    user_to_entity, workout, workout_template,
+    user_to_entity, video_game_session, workout, workout_template,
 };
-use enums::UserToMediaReason;
+use enums::{EntityLot, MediaLot, MediaSource, UserToMediaReason};
 use fitness_models::{UserToExerciseHistoryExtraInformation, UserWorkoutInput};
-use importer_models::ImportFailedItem;
+use importer_models::{ImportFailedItem, ImportWarningItem};
 use media_models::{
     CreateOrUpdateCollectionInput, DailyUserActivitiesResponseGroupedBy, DailyUserActivityItem,
-    EntityWithLot, GenreListItem, GraphqlMediaAssets, ImportOrExportExerciseItem,
-    ImportOrExportMediaGroupItem, ImportOrExportMediaItem, ImportOrExportPersonItem,
-    MetadataCreatorGroupedByRole, PersonDetailsGroupedByRole, ReviewItem, UserDetailsError,
+    EntityWithLot, GenreListItem, GraphqlCalendarEvent, GraphqlMediaAssets,
+    ImportOrExportExerciseItem, ImportOrExportMediaGroupItem, ImportOrExportMediaItem,
+    ImportOrExportPersonItem, MetadataCreatorGroupedByRole, PersonDetailsGroupedByRole,
+    PersonWorksLotCount, PlaybackLink, ReviewItem, SeenChangesCursor, UserDetailsError,
     UserMediaNextEntry, UserMetadataDetailsEpisodeProgress, UserMetadataDetailsShowSeasonProgress,
 };
 use rust_decimal::Decimal;
@@ -36,8 +38,19 @@ use serde_with::skip_serializing_none;
     params(media_models::MetadataGroupSearchItem)
 ))]
 #[graphql(concrete(name = "GenreListResults", params(media_models::GenreListItem)))]
+#[graphql(concrete(name = "MetadataListResults", params(media_models::MetadataListItem)))]
 #[graphql(concrete(name = "WorkoutListResults", params(workout::Model)))]
 #[graphql(concrete(name = "WorkoutTemplateListResults", params(workout_template::Model)))]
+#[graphql(concrete(
+    name = "VideoGameSessionListResults",
+    params(video_game_session::Model)
+))]
+#[graphql(concrete(name = "SeenHistoryResults", params(seen::Model)))]
+#[graphql(concrete(name = "ReviewListResults", params(media_models::ReviewItem)))]
+#[graphql(concrete(
+    name = "ReviewCommentListResults",
+    params(media_models::ImportOrExportItemReviewComment)
+))]
 #[graphql(concrete(name = "IdResults", params(String)))]
 pub struct SearchResults<T: OutputType> {
     pub details: SearchDetails,
@@ -61,11 +74,17 @@ pub struct ImportOrExportWorkoutTemplateItem {
     pub collections: Vec<String>,
 }
 
+/// The version of the format written by the exporter. It must be bumped when a
+/// change to the format can not be read by older importers.
+pub const COMPLETE_EXPORT_VERSION: i32 = 1;
+
 /// Complete export of the user.
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Schematic)]
 #[serde(rename_all = "snake_case")]
 pub struct CompleteExport {
+    /// Version of the export format. Missing in exports made before it was added.
+    pub version: Option<i32>,
     /// Data about user's media.
     pub media: Option<Vec<media_models::ImportOrExportMediaItem>>,
     /// Data about user's people.
@@ -125,6 +144,13 @@ pub struct PersonDetails {
     pub source_url: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct PersonWorks {
+    /// The number of works of every type, used to render a tab for each.
+    pub lots: Vec<PersonWorksLotCount>,
+    pub works: SearchResults<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
 pub struct MetadataGroupDetails {
     pub details: metadata_group::Model,
@@ -170,12 +196,45 @@ pub struct CoreDetails {
 pub struct UserPersonDetails {
     pub reviews: Vec<ReviewItem>,
     pub collections: Vec<collection::Model>,
+    /// The media this person has worked on along with how far the user has got
+    /// with each of them.
+    pub works: Vec<UserPersonWork>,
+}
+
+/// A media a person has worked on along with how far the user has got with it.
+#[derive(SimpleObject)]
+pub struct UserPersonWork {
+    pub metadata_id: String,
+    pub role: String,
+    pub has_interacted: bool,
+    pub is_finished: bool,
+}
+
+/// A media in a group along with how far the user has got with it.
+#[derive(SimpleObject)]
+pub struct UserMetadataGroupPart {
+    pub metadata_id: String,
+    pub part: i32,
+    pub publish_year: Option<i32>,
+    pub has_interacted: bool,
+    pub is_finished: bool,
 }
 
 #[derive(SimpleObject)]
 pub struct UserMetadataGroupDetails {
     pub reviews: Vec<ReviewItem>,
     pub collections: Vec<collection::Model>,
+    /// The media in the group in the order that they were released.
+    pub parts: Vec<UserMetadataGroupPart>,
+}
+
+/// An entry of a media that is underway. Shows and podcasts can have several
+/// of them at once, for example when two seasons are watched in parallel.
+#[derive(SimpleObject)]
+pub struct UserMediaInProgressThread {
+    pub seen: seen::Model,
+    /// The next episode after the one in this entry.
+    pub next_entry: Option<UserMediaNextEntry>,
 }
 
 #[derive(SimpleObject)]
@@ -187,15 +246,25 @@ pub struct UserMetadataDetails {
     /// The public reviews of this media.
     pub reviews: Vec<ReviewItem>,
     /// The seen history of this media.
+    #[graphql(deprecation = "Use `userMetadataSeenHistory`, which is paginated")]
     pub history: Vec<seen::Model>,
     /// The seen item if it is in progress.
     pub in_progress: Option<seen::Model>,
+    /// Every seen item that is in progress, each with its own next episode.
+    pub in_progress_threads: Vec<UserMediaInProgressThread>,
     /// The next episode/chapter of this media.
     pub next_entry: Option<UserMediaNextEntry>,
+    /// The tags of this media that the user wants to be warned about.
+    pub content_warnings: Vec<String>,
     /// The number of users who have seen this media.
     pub seen_by_all_count: usize,
     /// The number of times this user has seen this media.
     pub seen_by_user_count: usize,
+    /// The number of times this user has finished this media. Shows, podcasts and
+    /// anime only count once every episode has been seen.
+    pub times_consumed: usize,
+    /// The date on which each of these was finished, oldest first.
+    pub consumption_history: Vec<Option<NaiveDate>>,
     /// The average rating of this media in this service.
     pub average_rating: Option<Decimal>,
     /// The seen progress of this media if it is a show.
@@ -204,18 +273,35 @@ pub struct UserMetadataDetails {
     pub podcast_progress: Option<Vec<UserMetadataDetailsEpisodeProgress>>,
     /// Whether this media has been interacted with
     pub has_interacted: bool,
+    /// Links that open this media in the servers where the user plays it.
+    pub playback_links: Vec<PlaybackLink>,
+    /// The number of seconds the user has played this media if it is a video game,
+    /// summed over the sessions reported by game launchers.
+    pub video_game_playtime: Option<i64>,
+}
+
+/// The id that a media server uses for one of the items in an import.
+#[derive(Debug, Clone)]
+pub struct ImportServerItemId {
+    pub lot: MediaLot,
+    pub source: MediaSource,
+    pub identifier: String,
+    pub server_item_id: String,
+    pub server_id: Option<String>,
 }
 
 #[derive(Debug, Default)]
 pub struct ImportResult {
     pub workouts: Vec<UserWorkoutInput>,
     pub failed_items: Vec<ImportFailedItem>,
+    pub warnings: Vec<ImportWarningItem>,
     pub metadata: Vec<ImportOrExportMediaItem>,
     pub people: Vec<ImportOrExportPersonItem>,
     pub measurements: Vec<user_measurement::Model>,
     pub metadata_groups: Vec<ImportOrExportMediaGroupItem>,
     pub collections: Vec<CreateOrUpdateCollectionInput>,
     pub application_workouts: Vec<ImportOrExportWorkoutItem>,
+    pub server_item_ids: Vec<ImportServerItemId>,
 }
 
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
@@ -227,8 +313,92 @@ pub struct DailyUserActivitiesResponse {
     pub grouped_by: DailyUserActivitiesResponseGroupedBy,
 }
 
+#[derive(Debug, SimpleObject, Clone)]
+pub struct UserDashboard {
+    pub in_progress: Vec<UserDashboardInProgressItem>,
+    pub upcoming: Vec<GraphqlCalendarEvent>,
+    pub summary: DailyUserActivityItem,
+    pub recommendations: Vec<String>,
+    pub pending_notifications: u64,
+    pub favorites: Vec<EntityWithLot>,
+}
+
+/// An entry of the in progress section of the dashboard. Media with several
+/// episodes underway have one entry for each of them.
+#[derive(Debug, SimpleObject, Clone, Serialize, Deserialize)]
+pub struct UserDashboardInProgressItem {
+    pub entity_id: String,
+    pub entity_lot: EntityLot,
+    /// The in progress seen item of this entry, empty when nothing was seen yet.
+    pub seen_id: Option<String>,
+    /// The next episode after the one in this entry.
+    pub next_entry: Option<UserMediaNextEntry>,
+}
+
+#[derive(Debug, SimpleObject, Clone, Serialize, Deserialize)]
+pub struct AdminDashboardLotCount {
+    pub lot: MediaLot,
+    pub count: i64,
+}
+
+/// How much an account has stored, to find accounts that are growing out of hand.
+#[derive(Debug, SimpleObject, Clone, Serialize, Deserialize)]
+pub struct AdminDashboardUserContribution {
+    pub user_id: String,
+    pub name: String,
+    pub seen_count: i64,
+    pub review_count: i64,
+}
+
+/// The parts of the admin dashboard that come from the database. These are cached
+/// since they scan whole tables.
+#[derive(Debug, SimpleObject, Clone, Serialize, Deserialize)]
+pub struct AdminDashboardStatistics {
+    pub total_users: u64,
+    /// Users that logged in or recorded something in the last 30 days.
+    pub active_users: u64,
+    pub metadata_per_lot: Vec<AdminDashboardLotCount>,
+    pub database_size_bytes: i64,
+    pub user_contributions: Vec<AdminDashboardUserContribution>,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct AdminDashboard {
+    pub statistics: AdminDashboardStatistics,
+    /// Application jobs that ran in the last week, counted since the last restart.
+    pub jobs_last_week: usize,
+    pub failed_jobs_last_week: usize,
+    /// Notifications delivered, counted since the last restart.
+    pub delivered_notifications: u64,
+    /// Attempts to deliver a notification that failed, counted since the last restart.
+    pub failed_notification_attempts: u64,
+    /// Notifications waiting to be delivered, including the ones waiting for a retry.
+    pub queued_notification_deliveries: u64,
+    pub provider_statuses: Vec<ProviderStatus>,
+}
+
 #[derive(Debug, SimpleObject, Clone, Serialize, Deserialize, Schematic)]
 pub struct UserWorkoutTemplateDetails {
     pub details: workout_template::Model,
     pub collections: Vec<collection::Model>,
 }
+
+/// How far a user has got with a media item.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MetadataFinishedStatus {
+    /// Whether the media, or every episode of it, was finished.
+    pub is_finished: bool,
+    /// Whether the user has any seen entry for the media.
+    pub has_seen: bool,
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone)]
+pub struct SeenChangesResponse {
+    /// The changed seen items, least recently updated first.
+    pub items: Vec<seen::Model>,
+    /// The position to continue from. It stays the same when nothing changed so
+    /// that it can be polled.
+    pub next_cursor: Option<SeenChangesCursor>,
+    /// Whether more changes are available right away.
+    pub has_more: bool,
+}
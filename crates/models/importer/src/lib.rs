@@ -1,5 +1,6 @@
 use async_graphql::{Enum, SimpleObject};
-use enums::MediaLot;
+use enums::{ImportSource, MediaLot};
+use rust_decimal::Decimal;
 use sea_orm::FromJsonQueryResult;
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +29,16 @@ pub struct ImportFailedItem {
     pub error: Option<String>,
 }
 
+/// An item that was imported after some of its data was changed.
+#[derive(
+    Debug, SimpleObject, FromJsonQueryResult, Serialize, Deserialize, Eq, PartialEq, Clone,
+)]
+pub struct ImportWarningItem {
+    pub lot: Option<MediaLot>,
+    pub identifier: String,
+    pub warning: String,
+}
+
 #[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct ImportDetails {
     pub total: usize,
@@ -39,4 +50,14 @@ pub struct ImportDetails {
 pub struct ImportResultResponse {
     pub import: ImportDetails,
     pub failed_items: Vec<ImportFailedItem>,
+    #[serde(default)]
+    pub warnings: Vec<ImportWarningItem>,
+}
+
+/// A likely import source for an uploaded file.
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportFormatDetection {
+    pub source: ImportSource,
+    /// Between 0 and 1, higher means more likely.
+    pub confidence: Decimal,
 }
@@ -1,18 +1,21 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, iter::Sum, ops::Add};
 
-use async_graphql::{Enum, InputObject, InputType, OneofObject, SimpleObject, Union};
+use async_graphql::{
+    Enum, InputObject, InputType, MaybeUndefined, NewType, OneofObject, SimpleObject, Union,
+};
 use boilermates::boilermates;
 use chrono::{NaiveDate, NaiveDateTime};
 use common_models::{
-    CollectionExtraInformation, IdAndNamedObject, SearchInput, StoredUrl, StringIdObject,
+    CollectionExtraInformation, IdAndNamedObject, InstancePopularWindow, SearchInput, StoredUrl,
+    StringIdObject,
 };
 use common_utils::deserialize_date;
 use enums::{
-    EntityLot, ImportSource, IntegrationProvider, MediaLot, MediaSource, NotificationPlatformLot,
-    SeenState, UserLot, Visibility,
+    EntityLot, ExternalIdType, ImportSource, IntegrationProvider, MediaLot, MediaSource,
+    NotificationPlatformLot, OutboundWebhookEvent, SeenState, UserLot, Visibility,
 };
 use rust_decimal::Decimal;
-use schematic::Schematic;
+use schematic::{ConfigEnum, Schematic};
 use sea_orm::{
     prelude::{Date, DateTimeUtc},
     EnumIter, FromJsonQueryResult, FromQueryResult,
@@ -33,6 +36,85 @@ pub struct MetadataSearchItemResponse {
     /// Whether the user has interacted with this media item.
     pub has_interacted: bool,
     pub database_id: Option<String>,
+    /// The state of the most recent seen entry of the user for this media item.
+    pub seen_status: Option<SeenState>,
+}
+
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct MetadataListItem {
+    pub metadata_id: String,
+    /// The state of the most recent seen entry of the user for this media item.
+    pub seen_status: Option<SeenState>,
+    /// When the most recent seen entry of the user was last updated.
+    pub last_seen_on: Option<DateTimeUtc>,
+    /// The average rating of the user's reviews in their review scale.
+    pub average_rating: Option<Decimal>,
+}
+
+#[derive(Debug, InputObject, Clone)]
+pub struct ReorderCollectionEntityInput {
+    pub collection_name: String,
+    pub entity_id: String,
+    pub entity_lot: EntityLot,
+    /// The position to move the entity to, starting from 1.
+    pub new_position: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Enum, Clone, Copy, PartialEq, Eq, ConfigEnum)]
+pub enum SmartCollectionSeenStatus {
+    /// There is nothing in the seen history of the media.
+    NotStarted,
+    InProgress,
+    Completed,
+}
+
+/// The media of the owner's library that a smart collection contains. Every
+/// condition that is set has to match.
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    Default,
+    Serialize,
+    Deserialize,
+    SimpleObject,
+    InputObject,
+    FromJsonQueryResult,
+    Schematic,
+)]
+#[graphql(input_name = "SmartCollectionFilterInput")]
+pub struct SmartCollectionFilter {
+    pub lot: Option<MediaLot>,
+    pub genre_id: Option<String>,
+    pub publish_year_start: Option<i32>,
+    pub publish_year_end: Option<i32>,
+    pub seen_status: Option<SmartCollectionSeenStatus>,
+    /// The lowest average rating given by the owner, out of 100.
+    pub minimum_rating: Option<Decimal>,
+}
+
+#[derive(Debug, InputObject, Clone)]
+pub struct CreateCollectionShareTokenInput {
+    pub collection_id: String,
+    /// The token never expires when this is empty.
+    pub expires_in_days: Option<i64>,
+}
+
+/// A media item of a collection as shown to people the collection is shared with.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SharedCollectionItem {
+    pub lot: MediaLot,
+    pub title: String,
+    pub image: Option<String>,
+    pub publish_year: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SharedCollection {
+    pub name: String,
+    pub description: Option<String>,
+    pub items: Vec<SharedCollectionItem>,
 }
 
 #[derive(Debug, InputObject, Default, Clone)]
@@ -42,6 +124,10 @@ pub struct CreateOrUpdateCollectionInput {
     pub update_id: Option<String>,
     pub collaborators: Option<Vec<String>>,
     pub information_template: Option<Vec<CollectionExtraInformation>>,
+    /// Makes this a smart collection whose contents are computed from the filter
+    /// instead of the entities added to it. Leave it out to keep the current
+    /// filter and send `null` to turn it back into a regular collection.
+    pub smart_filter: MaybeUndefined<SmartCollectionFilter>,
 }
 
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone, FromQueryResult)]
@@ -51,6 +137,55 @@ pub struct GenreListItem {
     pub num_items: Option<i64>,
 }
 
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    NewType,
+)]
+#[serde(transparent)]
+pub struct MediaDuration(i32);
+
+impl MediaDuration {
+    pub fn from_minutes(minutes: i32) -> Self {
+        Self(minutes)
+    }
+
+    pub fn from_seconds(seconds: i64) -> Self {
+        Self::from_minutes(i32::try_from((seconds + 30) / 60).unwrap_or(i32::MAX))
+    }
+
+    pub fn from_milliseconds(milliseconds: i64) -> Self {
+        Self::from_seconds((milliseconds + 500) / 1000)
+    }
+
+    pub fn minutes(self) -> i32 {
+        self.0
+    }
+}
+
+impl Add for MediaDuration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sum for MediaDuration {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
 #[derive(
     Debug,
     Serialize,
@@ -65,7 +200,7 @@ pub struct GenreListItem {
 )]
 #[graphql(input_name = "AudioBookSpecificsInput")]
 pub struct AudioBookSpecifics {
-    pub runtime: Option<i32>,
+    pub runtime: Option<MediaDuration>,
 }
 
 #[derive(
@@ -99,7 +234,7 @@ pub struct BookSpecifics {
 )]
 #[graphql(input_name = "MovieSpecificsInput")]
 pub struct MovieSpecifics {
-    pub runtime: Option<i32>,
+    pub runtime: Option<MediaDuration>,
 }
 
 #[derive(
@@ -137,8 +272,7 @@ pub struct PodcastSpecifics {
 pub struct PodcastEpisode {
     pub number: i32,
     pub id: String,
-    #[serde(alias = "audio_length_sec")]
-    pub runtime: Option<i32>,
+    pub runtime: Option<MediaDuration>,
     #[serde(alias = "description")]
     pub overview: Option<String>,
     pub title: String,
@@ -162,7 +296,7 @@ pub struct PodcastEpisode {
 #[graphql(input_name = "ShowSpecificsInput")]
 pub struct ShowSpecifics {
     pub seasons: Vec<ShowSeason>,
-    pub runtime: Option<i32>,
+    pub runtime: Option<MediaDuration>,
     pub total_seasons: Option<usize>,
     pub total_episodes: Option<usize>,
 }
@@ -213,7 +347,7 @@ pub struct ShowEpisode {
     pub name: String,
     pub overview: Option<String>,
     pub poster_images: Vec<String>,
-    pub runtime: Option<i32>,
+    pub runtime: Option<MediaDuration>,
 }
 
 #[derive(
@@ -231,6 +365,8 @@ pub struct ShowEpisode {
 #[graphql(input_name = "VideoGameSpecificsInput")]
 pub struct VideoGameSpecifics {
     pub platforms: Vec<String>,
+    /// The name of the game in its IGDB page url.
+    pub slug: Option<String>,
 }
 
 #[derive(
@@ -247,7 +383,7 @@ pub struct VideoGameSpecifics {
 )]
 #[graphql(input_name = "VisualNovelSpecificsInput")]
 pub struct VisualNovelSpecifics {
-    pub length: Option<i32>,
+    pub length: Option<MediaDuration>,
 }
 
 #[derive(
@@ -353,6 +489,99 @@ pub struct ProgressUpdateInput {
     pub podcast_episode_number: Option<i32>,
     pub manga_chapter_number: Option<Decimal>,
     pub provider_watched_on: Option<String>,
+    pub edition: Option<SeenEdition>,
+    /// Used to calculate the progress of a book when `progress` is not given.
+    pub pages_read: Option<i32>,
+    /// Used to calculate the progress of a movie or audiobook when `progress` is
+    /// not given.
+    pub minutes_consumed: Option<i32>,
+    /// Allow the progress to be lower than the current one. Without it such updates
+    /// are rejected since they usually come from stale events.
+    pub allow_progress_decrease: Option<bool>,
+}
+
+/// What was logged by a quick log. The seen item can be deleted to undo it.
+#[derive(Debug, SimpleObject, Clone)]
+pub struct QuickLogResult {
+    pub seen_id: String,
+    pub date: Option<NaiveDate>,
+    pub show_season_number: Option<i32>,
+    pub show_episode_number: Option<i32>,
+    pub podcast_episode_number: Option<i32>,
+}
+
+#[derive(Debug, InputObject, Clone)]
+pub struct RandomMetadataInput {
+    pub lot: MediaLot,
+    /// Only pick media of this genre.
+    pub genre_id: Option<String>,
+    /// Only pick media in this collection.
+    pub collection_id: Option<String>,
+    /// Only pick media that the user has never seen.
+    pub unseen_only: Option<bool>,
+}
+
+#[derive(Debug, InputObject, Clone)]
+pub struct RandomEpisodeInput {
+    pub metadata_id: String,
+    /// Episodes seen within this many days are not picked.
+    pub avoid_recent_days: Option<i64>,
+    /// Only pick episodes that have never been seen.
+    pub unseen_only: Option<bool>,
+    /// Also pick episodes from special seasons of shows.
+    pub include_specials: Option<bool>,
+    /// Log the picked episode right away, the same way as a quick log.
+    pub and_mark_started: Option<bool>,
+}
+
+#[derive(Debug, Enum, Clone, Copy, PartialEq, Eq)]
+pub enum RandomEpisodeUnavailableReason {
+    NotShowOrPodcast,
+    NoAiredEpisodes,
+    AllSeenRecently,
+    AllSeen,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct RandomEpisode {
+    pub name: String,
+    pub show_season_number: Option<i32>,
+    pub show_episode_number: Option<i32>,
+    pub podcast_episode_number: Option<i32>,
+    pub publish_date: Option<NaiveDate>,
+    /// When the user last finished this episode.
+    pub last_seen_on: Option<NaiveDate>,
+    /// What was logged if the episode was asked to be marked right away.
+    pub logged: Option<QuickLogResult>,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct RandomEpisodeResult {
+    pub episode: Option<RandomEpisode>,
+    /// Why no episode could be picked.
+    pub unavailable_reason: Option<RandomEpisodeUnavailableReason>,
+}
+
+#[derive(Debug, Enum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataRefreshStage {
+    Queued,
+    FetchingDetails,
+    SavingChanges,
+    Finished,
+    Failed,
+}
+
+/// The state of the last refresh of a metadata that was deployed.
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone)]
+pub struct MetadataRefreshProgress {
+    pub stage: MetadataRefreshStage,
+    /// Pages (or seasons for shows) fetched from the provider so far.
+    pub pages_fetched: Option<usize>,
+    pub total_pages: Option<usize>,
+    /// What changed, available once the refresh has finished.
+    pub changes: Vec<String>,
+    pub error: Option<String>,
+    pub updated_at: DateTimeUtc,
 }
 
 #[derive(Enum, Clone, Debug, Copy, PartialEq, Eq)]
@@ -360,6 +589,11 @@ pub enum ProgressUpdateErrorVariant {
     AlreadySeen,
     NoSeenInProgress,
     UpdateWithoutProgressUpdate,
+    /// More than one episode is in progress and the input does not say which one
+    /// should be updated.
+    AmbiguousSeenInProgress,
+    /// The progress is lower than the current one and lowering it was not allowed.
+    RegressiveProgressUpdate,
 }
 
 #[derive(Debug, SimpleObject)]
@@ -448,6 +682,16 @@ pub struct WatchProvider {
 )]
 pub struct ExternalIdentifiers {
     pub tvdb_id: Option<i32>,
+    pub imdb_id: Option<String>,
+    pub mal_id: Option<i32>,
+    pub steam_app_id: Option<i32>,
+}
+
+/// An id by which another service knows a media item.
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone, PartialEq, Eq)]
+pub struct MetadataExternalIdentifier {
+    pub id_type: ExternalIdType,
+    pub value: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -455,6 +699,8 @@ pub struct MetadataDetails {
     pub identifier: String,
     pub is_nsfw: Option<bool>,
     pub title: String,
+    /// The title in Latin script when `title` is written in another script.
+    pub romanized_title: Option<String>,
     pub source: MediaSource,
     pub description: Option<String>,
     pub original_language: Option<String>,
@@ -463,6 +709,8 @@ pub struct MetadataDetails {
     pub creators: Vec<MetadataFreeCreator>,
     pub people: Vec<PartialMetadataPerson>,
     pub genres: Vec<String>,
+    /// Keywords and content tags, these are kept apart from the genres.
+    pub tags: Vec<String>,
     pub url_images: Vec<MetadataImageForMediaDetails>,
     pub s3_images: Vec<MetadataImageForMediaDetails>,
     pub videos: Vec<MetadataVideo>,
@@ -470,7 +718,9 @@ pub struct MetadataDetails {
     pub publish_date: Option<NaiveDate>,
     pub suggestions: Vec<PartialMetadataWithoutId>,
     pub group_identifiers: Vec<String>,
+    /// The rating on the scale that the provider uses.
     pub provider_rating: Option<Decimal>,
+    pub provider_rating_count: Option<i32>,
     pub watch_providers: Vec<WatchProvider>,
     pub audio_book_specifics: Option<AudioBookSpecifics>,
     pub book_specifics: Option<BookSpecifics>,
@@ -509,6 +759,12 @@ pub struct ImportOrExportMediaItemSeen {
     pub manga_volume_number: Option<i32>,
     /// The provider this item was watched on.
     pub provider_watched_on: Option<String>,
+    /// The specific edition or release that was consumed.
+    pub edition: Option<SeenEdition>,
+    /// If for a book, the number of pages read.
+    pub pages_read: Option<i32>,
+    /// If for a movie or audiobook, the number of minutes consumed.
+    pub minutes_consumed: Option<i32>,
 }
 
 /// Review data associated to a rating.
@@ -522,7 +778,7 @@ pub struct ImportOrExportItemReview {
     pub date: Option<DateTimeUtc>,
     /// Whether to mark the review as a spoiler. Defaults to false.
     pub spoiler: Option<bool>,
-    /// Actual text for the review.
+    /// Actual text for the review, in Markdown.
     pub text: Option<String>,
 }
 
@@ -626,6 +882,10 @@ pub struct ImportOrExportExerciseItem {
 )]
 pub struct MetadataImage {
     pub url: StoredUrl,
+    /// Whether the image was uploaded by a user. These are kept when the metadata
+    /// is refreshed from the provider.
+    #[serde(default)]
+    pub is_user_provided: bool,
 }
 
 #[derive(
@@ -679,6 +939,13 @@ pub struct ImportOrExportItemReviewComment {
     /// The user ids of all those who liked it.
     pub liked_by: HashSet<String>,
     pub created_on: DateTimeUtc,
+    /// The comment this one is a reply to. Replies can not be replied to.
+    #[serde(default)]
+    pub parent_comment_id: Option<String>,
+    /// Deleted comments are kept so that replies to them still make sense.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub deleted_on: Option<DateTimeUtc>,
 }
 
 #[derive(
@@ -729,6 +996,29 @@ pub struct SeenMangaExtraInformation {
     pub volume: Option<i32>,
 }
 
+/// The specific edition or release of a media item that was consumed.
+#[derive(
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Clone,
+    SimpleObject,
+    InputObject,
+    FromJsonQueryResult,
+    Schematic,
+)]
+#[graphql(input_name = "SeenEditionInput")]
+pub struct SeenEdition {
+    pub name: Option<String>,
+    pub year: Option<i32>,
+    /// The identifier of the edition with the provider, or an ISBN.
+    pub identifier: Option<String>,
+    pub pages: Option<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReviewPostedEvent {
     pub obj_id: String,
@@ -765,6 +1055,7 @@ pub struct MetadataPartialDetails {
     #[graphql(skip)]
     pub images: Option<Vec<MetadataImage>>,
     pub publish_year: Option<i32>,
+    pub description_preview: Option<String>,
 }
 
 #[derive(Debug, InputObject)]
@@ -803,6 +1094,15 @@ pub struct CommitMediaInput {
     pub force_update: Option<bool>,
 }
 
+#[derive(Debug, SimpleObject, Clone)]
+pub struct BulkCommitMetadataResult {
+    pub identifier: String,
+    /// Set when the media item was added to the library.
+    pub metadata_id: Option<String>,
+    /// Set when the media item could not be added.
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromJsonQueryResult, Eq, PartialEq, Default)]
 pub struct MetadataStateChanges {}
 
@@ -838,6 +1138,9 @@ pub struct PersonStateChanges {
 #[serde(rename_all = "snake_case")]
 pub struct IntegrationProviderSpecifics {
     pub plex_username: Option<String>,
+    pub plex_web_base_url: Option<String>,
+
+    pub jellyfin_sink_web_base_url: Option<String>,
 
     pub audiobookshelf_base_url: Option<String>,
     pub audiobookshelf_token: Option<String>,
@@ -862,6 +1165,36 @@ pub struct IntegrationProviderSpecifics {
     pub jellyfin_push_base_url: Option<String>,
     pub jellyfin_push_username: Option<String>,
     pub jellyfin_push_password: Option<String>,
+
+    pub webhook_url: Option<String>,
+    /// Requests are signed with this secret when it is provided.
+    pub webhook_secret: Option<String>,
+    /// All events are sent when this is not provided.
+    pub webhook_events: Option<Vec<OutboundWebhookEvent>>,
+}
+
+/// The media that an outbound webhook event is about.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboundWebhookMedia {
+    pub id: String,
+    pub lot: MediaLot,
+    pub title: String,
+    pub source: MediaSource,
+    pub identifier: String,
+}
+
+/// The body of the requests sent to webhook integrations. The version is bumped
+/// whenever a field is removed or changes meaning.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboundWebhookPayload {
+    pub version: i32,
+    pub event: OutboundWebhookEvent,
+    pub timestamp: DateTimeUtc,
+    pub user_id: String,
+    pub is_test: bool,
+    pub media: Option<OutboundWebhookMedia>,
+    pub details: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -872,10 +1205,15 @@ pub struct ReviewItem {
     pub visibility: Visibility,
     pub rating: Option<Decimal>,
     pub posted_by: IdAndNamedObject,
+    /// The review as it was written, in Markdown.
     pub text_original: Option<String>,
+    /// The review rendered to HTML. Raw HTML in the review is escaped and links with
+    /// unsafe protocols are removed, so this can be inserted into a page as is.
     pub text_rendered: Option<String>,
     pub seen_items_associated_with: Vec<String>,
+    /// The most recent comments on the review, oldest first.
     pub comments: Vec<ImportOrExportItemReviewComment>,
+    pub comments_count: usize,
     pub show_extra_information: Option<SeenShowExtraInformation>,
     pub podcast_extra_information: Option<SeenPodcastExtraInformation>,
     pub anime_extra_information: Option<SeenAnimeExtraInformation>,
@@ -888,10 +1226,60 @@ pub struct DeployGenericCsvImportInput {
     pub csv_path: String,
 }
 
+/// The CSV headers which hold the fields understood by the generic CSV importer.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    SimpleObject,
+    InputObject,
+    FromJsonQueryResult,
+)]
+#[graphql(input_name = "GenericCsvColumnMappingInput")]
+pub struct GenericCsvColumnMapping {
+    pub title: String,
+    pub identifier: Option<String>,
+    /// Values in this column can be `isbn`, `imdb` or empty when the identifier
+    /// is the one used by the provider itself.
+    pub identifier_type: Option<String>,
+    /// Ratings are expected to be out of 100.
+    pub rating: Option<String>,
+    pub date: Option<String>,
+    pub status: Option<String>,
+    /// Values in this column can contain multiple comma separated collections.
+    pub collection: Option<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployGenericCsvMappedImportInput {
+    // The file path of the uploaded CSV file.
+    pub csv_path: String,
+    pub lot: MediaLot,
+    pub source: MediaSource,
+    /// Either this or `preset_id` must be provided.
+    pub mapping: Option<GenericCsvColumnMapping>,
+    pub preset_id: Option<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct CreateOrUpdateCsvImportPresetInput {
+    pub update_id: Option<String>,
+    pub name: String,
+    pub mapping: GenericCsvColumnMapping,
+}
+
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployTraktImportInput {
-    // The public username in Trakt.
+    // The public username in Trakt. Can be left empty when an access token is provided.
     pub username: String,
+    // An OAuth access token, needed to import private profiles.
+    pub access_token: Option<String>,
+    // Plays watched before this are skipped. Set it to the last play that was
+    // imported to resume an import that stopped part way.
+    pub history_start_at: Option<DateTimeUtc>,
 }
 
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
@@ -904,6 +1292,23 @@ pub struct DeployMovaryImportInput {
     pub watchlist: String,
 }
 
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployAnilistImportInput {
+    // The public username in AniList.
+    pub username: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployLetterboxdImportInput {
+    // The file path of the uploaded export zip.
+    pub export: Option<String>,
+    // The file paths of the uploaded CSV files, used instead of the ones in the zip.
+    pub watched: Option<String>,
+    pub ratings: Option<String>,
+    pub reviews: Option<String>,
+    pub watchlist: Option<String>,
+}
+
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployMalImportInput {
     /// The anime export file path (uploaded via temporary upload).
@@ -952,20 +1357,100 @@ pub struct DeployUrlAndKeyAndUsernameImportInput {
     pub password: String,
 }
 
+/// What to do with dates in an import that can not be correct.
+#[derive(Debug, Enum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportInvalidDateAction {
+    /// Import the item without the date.
+    #[default]
+    Clear,
+    /// Replace the date with the closest valid one.
+    Clamp,
+}
+
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployImportJobInput {
     pub source: ImportSource,
+    /// Dates before January 1 of this year are considered invalid. Defaults to 1900.
+    pub earliest_valid_year: Option<i32>,
+    pub invalid_date_action: Option<ImportInvalidDateAction>,
+    /// If set, the source is detected from this uploaded file and overrides
+    /// `source` when the detection is confident enough.
+    pub auto_detect_path: Option<String>,
     pub mal: Option<DeployMalImportInput>,
     pub igdb: Option<DeployIgdbImportInput>,
     pub trakt: Option<DeployTraktImportInput>,
     pub movary: Option<DeployMovaryImportInput>,
+    pub anilist: Option<DeployAnilistImportInput>,
+    pub letterboxd: Option<DeployLetterboxdImportInput>,
     pub generic_json: Option<DeployJsonImportInput>,
     pub strong_app: Option<DeployStrongAppImportInput>,
     pub url_and_key: Option<DeployUrlAndKeyImportInput>,
     pub generic_csv: Option<DeployGenericCsvImportInput>,
+    pub generic_csv_mapped: Option<DeployGenericCsvMappedImportInput>,
     pub jellyfin: Option<DeployUrlAndKeyAndUsernameImportInput>,
 }
 
+#[derive(Debug, InputObject)]
+pub struct MarkEpisodeSkippedInput {
+    pub metadata_id: String,
+    pub season: i32,
+    /// When not provided, every episode of the season is marked.
+    pub episode: Option<i32>,
+    /// Set to false to remove the skipped marker.
+    #[graphql(default = true)]
+    pub is_skipped: bool,
+}
+
+#[derive(Debug, InputObject)]
+pub struct SwapMetadataSourceInput {
+    pub metadata_id: String,
+    pub new_source: MediaSource,
+    pub new_identifier: String,
+    /// Only return the changes without saving them.
+    #[graphql(default = false)]
+    pub dry_run: bool,
+}
+
+/// A field of a media item that changes when its source is swapped.
+#[derive(Debug, SimpleObject, Clone)]
+pub struct MetadataFieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct MarkShowSeasonSeenInput {
+    pub metadata_id: String,
+    pub season: i32,
+    /// The date on which the season was watched. Leave empty when it is not known.
+    pub date: Option<NaiveDate>,
+    /// Also mark episodes that have already been seen completely.
+    #[graphql(default = false)]
+    pub force: bool,
+}
+
+#[derive(Debug, InputObject)]
+pub struct AddMetadataImageInput {
+    pub metadata_id: String,
+    /// The path of the image as returned by the upload endpoint.
+    pub file_path: String,
+}
+
+#[derive(Debug, InputObject)]
+pub struct DeleteMetadataImageInput {
+    pub metadata_id: String,
+    /// The storage key of the image that was added by a user.
+    pub key: String,
+}
+
+#[derive(Debug, InputObject)]
+pub struct UpdateMetadataSortTitleInput {
+    pub metadata_id: String,
+    /// Leave empty to go back to the sort title computed from the title.
+    pub sort_title: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
 pub struct CreateCustomMetadataInput {
     pub title: String,
@@ -977,6 +1462,8 @@ pub struct CreateCustomMetadataInput {
     pub videos: Option<Vec<String>>,
     pub is_nsfw: Option<bool>,
     pub publish_year: Option<i32>,
+    /// The publish year is taken from this when it is not given.
+    pub publish_date: Option<NaiveDate>,
     pub audio_book_specifics: Option<AudioBookSpecifics>,
     pub book_specifics: Option<BookSpecifics>,
     pub movie_specifics: Option<MovieSpecifics>,
@@ -988,6 +1475,12 @@ pub struct CreateCustomMetadataInput {
     pub visual_novel_specifics: Option<VisualNovelSpecifics>,
 }
 
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct UpdateCustomMetadataInput {
+    pub existing_metadata_id: String,
+    pub update: CreateCustomMetadataInput,
+}
+
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
 pub struct CreateUserIntegrationInput {
     pub provider: IntegrationProvider,
@@ -995,6 +1488,9 @@ pub struct CreateUserIntegrationInput {
     pub minimum_progress: Option<Decimal>,
     pub maximum_progress: Option<Decimal>,
     pub sync_to_owned_collection: Option<bool>,
+    pub expected_activity_days: Option<i32>,
+    /// Add media that is new to the user to this collection.
+    pub default_collection: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
@@ -1004,6 +1500,28 @@ pub struct UpdateUserIntegrationInput {
     pub minimum_progress: Option<Decimal>,
     pub maximum_progress: Option<Decimal>,
     pub sync_to_owned_collection: Option<bool>,
+    /// Capture the requests made to the webhook for the next hour.
+    pub debug_mode: Option<bool>,
+    /// Alert if no data is received for this many days, zero turns alerts off.
+    pub expected_activity_days: Option<i32>,
+    /// Stop sending stale alerts until this time.
+    pub snooze_alerts_until: Option<DateTimeUtc>,
+    /// Add media that is new to the user to this collection, an empty value stops
+    /// adding media to it.
+    pub default_collection: Option<String>,
+}
+
+#[derive(Enum, Clone, Debug, Copy, PartialEq, Eq)]
+pub enum WebhookSamplePayloadKind {
+    Movie,
+    ShowEpisode,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct WebhookSimulationStep {
+    pub step: String,
+    pub was_success: bool,
+    pub message: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
@@ -1132,8 +1650,35 @@ pub struct GenreDetailsInput {
     pub page: Option<u64>,
 }
 
+#[derive(Debug, InputObject)]
+pub struct MetadataTagInput {
+    pub metadata_id: String,
+    pub tag: String,
+}
+
+#[derive(Debug, InputObject)]
+pub struct MediaByTagInput {
+    pub tag: String,
+    pub page: Option<u64>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct InstancePopularInput {
+    pub lot: MediaLot,
+    pub window: InstancePopularWindow,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct InstancePopularItem {
+    pub item: MetadataSearchItem,
+    pub database_id: String,
+    /// The number of users who have seen activity on this media in the window.
+    pub users_count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Enum, Clone, PartialEq, Eq, Copy, Default)]
 pub enum CollectionContentsSortBy {
+    Rank,
     Title,
     #[default]
     LastUpdatedOn,
@@ -1181,6 +1726,26 @@ pub struct MetadataCreatorGroupedByRole {
     pub items: Vec<MetadataCreator>,
 }
 
+#[derive(Debug, InputObject)]
+pub struct MediaReviewsInput {
+    pub metadata_id: String,
+    pub page: Option<u64>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct PersonWorksInput {
+    pub person_id: String,
+    /// Only return the works of this type.
+    pub lot: Option<MediaLot>,
+    pub page: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone, FromQueryResult)]
+pub struct PersonWorksLotCount {
+    pub lot: MediaLot,
+    pub count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
 pub struct PersonDetailsItemWithCharacter {
     pub metadata_id: String,
@@ -1225,14 +1790,25 @@ pub struct GraphqlMetadataDetails {
     pub source: MediaSource,
     pub is_nsfw: Option<bool>,
     pub is_partial: Option<bool>,
+    /// Whether none of the images of the media could be loaded when they were last
+    /// checked. A placeholder should be shown instead.
+    pub images_unreachable: Option<bool>,
     pub suggestions: Vec<String>,
     pub publish_year: Option<i32>,
     pub source_url: Option<String>,
+    /// The providers whose data is shown for this media item.
+    pub attribution: Vec<MediaSource>,
+    /// The tags from the providers and from the users of this instance.
+    pub tags: Vec<String>,
     pub genres: Vec<GenreListItem>,
     pub assets: GraphqlMediaAssets,
     pub description: Option<String>,
     pub publish_date: Option<NaiveDate>,
+    /// Out of 100, multiply by the scale and divide by 100 to get the rating that
+    /// the provider shows.
     pub provider_rating: Option<Decimal>,
+    pub provider_rating_scale: Option<Decimal>,
+    pub provider_rating_count: Option<i32>,
     pub original_language: Option<String>,
     pub production_status: Option<String>,
     pub group: Option<GraphqlMetadataGroup>,
@@ -1247,9 +1823,38 @@ pub struct GraphqlMetadataDetails {
     pub audio_book_specifics: Option<AudioBookSpecifics>,
     pub video_game_specifics: Option<VideoGameSpecifics>,
     pub external_identifiers: Option<ExternalIdentifiers>,
+    /// Every id that is used to match this media item with other services.
+    pub external_ids: Vec<MetadataExternalIdentifier>,
     pub visual_novel_specifics: Option<VisualNovelSpecifics>,
 }
 
+#[derive(Debug, Enum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataIdentifierType {
+    /// An ISBN-10 or ISBN-13, like the one encoded in a book's barcode.
+    Isbn,
+    /// An IMDb title id like `tt0137523`.
+    Imdb,
+    Tmdb,
+}
+
+/// A media that an identifier could refer to.
+#[derive(Debug, SimpleObject, Clone)]
+pub struct MetadataLookupCandidate {
+    pub lot: MediaLot,
+    pub source: MediaSource,
+    pub identifier: String,
+}
+
+/// When the identifier could refer to more than one media, nothing is committed
+/// and the candidates are returned instead.
+#[derive(Debug, SimpleObject, Clone)]
+pub struct MetadataLookupResult {
+    pub details: Option<GraphqlMetadataDetails>,
+    /// Whether the media was already in the user's library before the lookup.
+    pub already_in_library: bool,
+    pub candidates: Vec<MetadataLookupCandidate>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Enum, Clone, PartialEq, Eq, Copy, Default)]
 pub enum GraphqlSortOrder {
     Desc,
@@ -1266,6 +1871,7 @@ pub enum MediaSortBy {
     LastSeen,
     UserRating,
     ProviderRating,
+    TimesConsumed,
 }
 
 #[derive(Debug, Serialize, Deserialize, Enum, Clone, PartialEq, Eq, Copy, Default)]
@@ -1294,12 +1900,41 @@ pub enum MediaGeneralFilter {
     Dropped,
     OnAHold,
     Unfinished,
+    /// The user has finished the media.
+    Completed,
+    /// The user has started the media and has not reached the end yet.
+    InProgress,
+    /// There is nothing in the seen history of the media.
+    NotStarted,
+    OnWatchlist,
+}
+
+/// Whether a media item is out yet, based on its publish date. Items that only
+/// have a publish year are released once that year has passed.
+#[derive(Debug, Serialize, Deserialize, Enum, Clone, Copy, Eq, PartialEq)]
+pub enum MediaReleaseStatus {
+    Released,
+    Unreleased,
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct PublishYearRange {
+    pub start: Option<i32>,
+    pub end: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
 pub struct MediaFilter {
     pub general: Option<MediaGeneralFilter>,
     pub collections: Option<Vec<String>>,
+    pub publish_year_range: Option<PublishYearRange>,
+    /// The first year of a decade, for example 1990 for items published from 1990
+    /// to 1999.
+    pub decade: Option<i32>,
+    pub release_status: Option<MediaReleaseStatus>,
+    /// Leave out media with any of the tags the user wants to be warned about.
+    pub exclude_content_warnings: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
@@ -1312,6 +1947,49 @@ pub struct MetadataListInput {
     pub invert_collection: Option<bool>,
 }
 
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct SeenHistoryInput {
+    pub metadata_id: String,
+    pub page: Option<i32>,
+    /// The number of seen items on a page, the default page size when empty.
+    /// Capped at `MAX_PAGE_SIZE`.
+    pub take: Option<i32>,
+}
+
+/// A position in the seen changes of a user. Entries updated at the same instant
+/// are told apart by their ID, so no entry is skipped or returned twice.
+#[derive(
+    Debug, Serialize, Deserialize, SimpleObject, InputObject, Clone, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[graphql(input_name = "SeenChangesCursorInput")]
+pub struct SeenChangesCursor {
+    pub last_updated_on: DateTimeUtc,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct SeenChangesInput {
+    /// Only entries updated after this position are returned, all of them when
+    /// empty.
+    pub after: Option<SeenChangesCursor>,
+    /// The number of seen items to return, the default page size when empty.
+    /// Capped at `MAX_PAGE_SIZE`.
+    pub take: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct MetadataSuggestionsInput {
+    pub metadata_id: String,
+    pub page: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct VideoGameSessionsInput {
+    /// Only return the sessions of this game.
+    pub metadata_id: Option<String>,
+    pub page: Option<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
 pub struct PeopleListInput {
     pub take: Option<u64>,
@@ -1340,21 +2018,36 @@ pub struct MediaConsumedInput {
 pub struct UserMetadataDetailsEpisodeProgress {
     pub episode_number: i32,
     pub times_seen: usize,
+    pub times_skipped: usize,
+    /// The latest date on which this episode was seen.
+    pub last_seen_on: Option<NaiveDate>,
 }
 
 #[derive(SimpleObject)]
 pub struct UserMetadataDetailsShowSeasonProgress {
     pub season_number: i32,
     pub times_seen: usize,
+    pub aired_episodes: usize,
+    pub watched_episodes: usize,
+    pub skipped_episodes: usize,
     pub episodes: Vec<UserMetadataDetailsEpisodeProgress>,
 }
 
-#[derive(SimpleObject, Debug, Clone, Default)]
+/// A link that opens an item directly in a media server that it was played on.
+#[derive(SimpleObject, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackLink {
+    pub provider: IntegrationProvider,
+    pub url: String,
+}
+
+#[derive(SimpleObject, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct UserMediaNextEntry {
     pub season: Option<i32>,
     pub volume: Option<i32>,
     pub chapter: Option<Decimal>,
     pub episode: Option<i32>,
+    /// Links that open the media in the servers where the user plays it.
+    pub playback_links: Vec<PlaybackLink>,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
@@ -1365,6 +2058,11 @@ pub struct UpdateSeenItemInput {
     pub finished_on: Option<NaiveDate>,
     pub manual_time_spent: Option<Decimal>,
     pub provider_watched_on: Option<String>,
+    pub edition: Option<SeenEdition>,
+    /// Only allowed when the seen item belongs to a show.
+    pub show_season_number: Option<i32>,
+    /// Only allowed when the seen item belongs to a show.
+    pub show_episode_number: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
@@ -1373,12 +2071,36 @@ pub struct PresignedPutUrlResponse {
     pub key: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct PostReviewCommentInput {
+    pub review_id: String,
+    pub text: String,
+    /// The comment to reply to.
+    pub parent_comment_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct DeleteReviewCommentInput {
+    pub review_id: String,
+    pub comment_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+pub struct ReviewCommentsInput {
+    pub review_id: String,
+    pub page: Option<i32>,
+    /// The number of comments on a page, the default page size when empty.
+    pub take: Option<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
 pub struct CreateReviewCommentInput {
     /// The review this comment belongs to.
     pub review_id: String,
     pub comment_id: Option<String>,
     pub text: Option<String>,
+    /// The comment to reply to when creating a new comment.
+    pub parent_comment_id: Option<String>,
     pub increment_likes: Option<bool>,
     pub decrement_likes: Option<bool>,
     pub should_delete: Option<bool>,
@@ -1509,6 +2231,86 @@ pub struct DailyUserActivitiesInput {
     pub group_by: Option<DailyUserActivitiesResponseGroupedBy>,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize, InputObject, Clone)]
+pub struct ConsumptionHeatmapInput {
+    pub lot: Option<MediaLot>,
+    pub year: Option<i32>,
+    /// Count the entries that only have a date instead of leaving them out.
+    pub include_unknown_time: Option<bool>,
+}
+
+#[derive(Debug, Default, SimpleObject, Serialize, Deserialize, Clone)]
+pub struct ConsumptionHeatmap {
+    /// The number of seen entries for each day of the week starting from Monday,
+    /// and each hour of that day.
+    pub counts: Vec<Vec<i64>>,
+    /// The number of entries that only have a date, if they were requested.
+    pub unknown_time_count: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, InputObject, Clone)]
+pub struct ConsumptionStreaksInput {
+    /// The IANA timezone in which days start and end, like `Europe/Berlin`. The
+    /// timezone of the server is used when it is not given.
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone, FromQueryResult)]
+pub struct ConsumptionMonthCount {
+    /// The first day of the month.
+    pub month: NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone)]
+pub struct ConsumptionStreaks {
+    /// The consecutive days up to today with at least one seen entry. A streak that
+    /// ended yesterday is still current since today is not over yet.
+    pub current_streak_days: i64,
+    pub longest_streak_days: i64,
+    /// The number of seen entries for each day of the week starting from Monday.
+    pub weekday_counts: Vec<i64>,
+    /// The number of seen entries in each of the last 12 months, oldest first.
+    pub monthly_counts: Vec<ConsumptionMonthCount>,
+    /// The number of seen entries from the start of this year up to today.
+    pub year_to_date_count: i64,
+    /// The number of seen entries in the same part of last year.
+    pub previous_year_to_date_count: i64,
+}
+
+/// What a user finished of one type of media in a year.
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone, FromQueryResult)]
+pub struct UserYearlySummaryLot {
+    pub lot: MediaLot,
+    /// The number of seen entries, which is the number of episodes for shows
+    /// and podcasts.
+    pub seen_count: i64,
+    /// The number of different media items.
+    pub metadata_count: i64,
+    pub minutes: i64,
+    pub pages: i64,
+    /// The number of seen entries whose runtime or number of pages is not known.
+    /// They are not part of `minutes` and `pages`.
+    pub missing_data_count: i64,
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone, FromQueryResult)]
+pub struct UserYearlySummaryGenre {
+    pub name: String,
+    /// The number of media items of this genre that were finished.
+    pub count: i64,
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone)]
+pub struct UserYearlySummary {
+    pub year: i32,
+    pub lots: Vec<UserYearlySummaryLot>,
+    pub top_genres: Vec<UserYearlySummaryGenre>,
+    /// The most consecutive days on which something was finished.
+    pub longest_streak_days: i64,
+    pub calculated_at: DateTimeUtc,
+}
+
 #[derive(Debug, Default, SimpleObject, Serialize, Deserialize, Clone, FromQueryResult)]
 pub struct DailyUserActivityItem {
     pub day: Date,
@@ -1525,11 +2327,13 @@ pub struct DailyUserActivityItem {
     pub book_count: i64,
     pub total_book_pages: i64,
     pub podcast_count: i64,
+    pub podcast_episode_count: i64,
     pub total_podcast_duration: i64,
     pub manga_count: i64,
     pub movie_count: i64,
     pub total_movie_duration: i64,
     pub show_count: i64,
+    pub show_episode_count: i64,
     pub total_show_duration: i64,
     pub video_game_count: i64,
     pub total_video_game_duration: i64,
@@ -1545,3 +2349,50 @@ pub struct DailyUserActivityItem {
     pub total_count: i64,
     pub total_duration: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_duration_rounds_to_the_nearest_minute() {
+        assert_eq!(MediaDuration::from_seconds(29).minutes(), 0);
+        assert_eq!(MediaDuration::from_seconds(30).minutes(), 1);
+        assert_eq!(MediaDuration::from_seconds(5_400).minutes(), 90);
+        assert_eq!(MediaDuration::from_milliseconds(89_499).minutes(), 1);
+        assert_eq!(MediaDuration::from_milliseconds(89_500).minutes(), 2);
+    }
+
+    #[test]
+    fn media_duration_saturates_instead_of_overflowing() {
+        assert_eq!(
+            MediaDuration::from_seconds(i64::MAX / 2).minutes(),
+            i32::MAX
+        );
+        let total = MediaDuration::from_minutes(i32::MAX) + MediaDuration::from_minutes(1);
+        assert_eq!(total.minutes(), i32::MAX);
+    }
+
+    #[test]
+    fn media_duration_sums() {
+        let total: MediaDuration = [10, 20, 30]
+            .into_iter()
+            .map(MediaDuration::from_minutes)
+            .sum();
+        assert_eq!(total, MediaDuration::from_minutes(60));
+        assert_eq!(
+            std::iter::empty::<MediaDuration>().sum::<MediaDuration>(),
+            MediaDuration::default()
+        );
+    }
+
+    #[test]
+    fn media_duration_is_stored_as_minutes() {
+        let duration = MediaDuration::from_minutes(42);
+        assert_eq!(serde_json::to_string(&duration).unwrap(), "42");
+        assert_eq!(
+            serde_json::from_str::<MediaDuration>("42").unwrap(),
+            duration
+        );
+    }
+}
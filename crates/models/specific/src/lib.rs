@@ -18,6 +18,12 @@ pub mod audiobookshelf {
         pub progress: Decimal,
         pub is_finished: bool,
         pub ebook_progress: Option<Decimal>,
+        pub current_time: Option<Decimal>,
+        pub duration: Option<Decimal>,
+        /// Milliseconds since the epoch.
+        pub started_at: Option<i64>,
+        /// Milliseconds since the epoch.
+        pub finished_at: Option<i64>,
     }
 
     #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,6 +34,7 @@ pub mod audiobookshelf {
         pub asin: Option<String>,
         pub isbn: Option<String>,
         pub itunes_id: Option<String>,
+        pub author_name: Option<String>,
     }
 
     #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -73,4 +80,20 @@ pub mod audiobookshelf {
     pub struct ListResponse {
         pub results: Vec<Item>,
     }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ListeningSession {
+        pub library_item_id: String,
+        pub episode_id: Option<String>,
+        /// Milliseconds since the epoch.
+        pub updated_at: i64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ListeningSessionsResponse {
+        pub num_pages: i32,
+        pub sessions: Vec<ListeningSession>,
+    }
 }
@@ -1,8 +1,12 @@
-use async_graphql::{Enum, SimpleObject};
-use common_models::MediaStateChanged;
+use std::{collections::HashSet, str::FromStr};
+
+use async_graphql::{Enum, Error, Result as GraphqlResult, SimpleObject};
+use common_models::{MediaStateChanged, UpdateComplexJsonInput};
 use educe::Educe;
 use enums::MediaLot;
 use fitness_models::{SetRestTimersSettings, UserUnitSystem};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use sea_orm::{FromJsonQueryResult, Iterable};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -233,11 +237,68 @@ pub enum GridPacking {
 #[strum(ascii_case_insensitive, serialize_all = "SCREAMING_SNAKE_CASE")]
 pub enum UserReviewScale {
     OutOfFive,
+    OutOfTen,
     #[default]
     OutOfHundred,
     ThreePointSmiley,
 }
 
+impl UserReviewScale {
+    /// The highest rating that can be given in this scale.
+    pub fn maximum(&self) -> Decimal {
+        match self {
+            Self::OutOfFive => dec!(5),
+            Self::OutOfTen => dec!(10),
+            Self::OutOfHundred | Self::ThreePointSmiley => dec!(100),
+        }
+    }
+
+    /// What a rating in this scale has to be multiplied with to get the rating out
+    /// of 100 that is stored.
+    pub fn divisor(&self) -> Decimal {
+        dec!(100) / self.maximum()
+    }
+
+    /// Convert a rating given in this scale into a rating out of 100. Five star
+    /// ratings are rounded to the nearest half star and ten point ratings to the
+    /// nearest point.
+    pub fn normalize(&self, rating: Decimal) -> Decimal {
+        let rating = match self {
+            Self::OutOfFive => (rating * dec!(2)).round() / dec!(2),
+            Self::OutOfTen => rating.round(),
+            Self::OutOfHundred | Self::ThreePointSmiley => rating,
+        };
+        rating * self.divisor()
+    }
+
+    /// Convert a stored rating out of 100 into this scale for display.
+    pub fn denormalize(&self, rating: Decimal) -> Decimal {
+        (rating / self.divisor()).round_dp(1)
+    }
+}
+
+#[derive(
+    Debug,
+    Serialize,
+    Default,
+    Deserialize,
+    Enum,
+    Clone,
+    Eq,
+    PartialEq,
+    FromJsonQueryResult,
+    Copy,
+    EnumString,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum UserQuickLogAction {
+    /// Log the media as consumed today.
+    #[default]
+    Now,
+    /// Log the media as consumed on an unknown date in the past.
+    InThePast,
+}
+
 #[derive(
     Debug, Serialize, Deserialize, Enum, Clone, Eq, PartialEq, FromJsonQueryResult, Copy, Default,
 )]
@@ -280,6 +341,9 @@ pub struct UserGeneralWatchProvider {
 pub struct UserGeneralPreferences {
     #[educe(Default = true)]
     pub display_nsfw: bool,
+    /// Keep this user's activity out of the instance wide popular lists.
+    #[educe(Default = false)]
+    pub disable_activity_sharing: bool,
     #[educe(Default = false)]
     pub disable_videos: bool,
     #[educe(Default = false)]
@@ -304,6 +368,13 @@ pub struct UserGeneralPreferences {
     pub disable_integrations: bool,
     #[educe(Default = false)]
     pub disable_navigation_animation: bool,
+    #[educe(Default = UserQuickLogAction::Now)]
+    pub quick_log_action: UserQuickLogAction,
+    #[educe(Default = true)]
+    pub quick_log_auto_advance: bool,
+    /// Media with any of these tags is flagged before it is started.
+    #[educe(Default(expression = vec![]))]
+    pub content_warning_tags: Vec<String>,
     #[educe(Default(expression = vec![
         UserGeneralDashboardElement {
             num_elements: Some(8),
@@ -341,6 +412,11 @@ pub struct UserPreferences {
     pub general: UserGeneralPreferences,
     pub notifications: UserNotificationsPreferences,
     pub features_enabled: UserFeaturesEnabledPreferences,
+    /// The properties that the user has changed themselves. These are not
+    /// overwritten when an admin pushes the default preferences to all users.
+    #[graphql(skip)]
+    #[serde(default)]
+    pub customized_fields: HashSet<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, FromJsonQueryResult)]
@@ -382,3 +458,333 @@ pub enum NotificationPlatformSpecifics {
         chat_id: String,
     },
 }
+
+/// The message of the error returned when a property is not a known preference.
+pub const UNKNOWN_PREFERENCE_ERROR: &str = "Unknown preference";
+
+/// Change a single preference. The property is the dot delimited path to it and
+/// the value is parsed into the type of that preference.
+pub fn apply_preference_change(
+    preferences: &mut UserPreferences,
+    input: &UpdateComplexJsonInput,
+) -> GraphqlResult<()> {
+    let err = || Error::new(UNKNOWN_PREFERENCE_ERROR);
+    let invalid = || Error::new("Incorrect property value encountered");
+    let (left, right) = input.property.split_once('.').ok_or_else(err)?;
+    let value_bool = || input.value.parse::<bool>().map_err(|_| invalid());
+    match left {
+        "fitness" => {
+            let (left, right) = right.split_once('.').ok_or_else(err)?;
+            match left {
+                "measurements" => {
+                    let (left, right) = right.split_once('.').ok_or_else(err)?;
+                    match left {
+                        "custom" => {
+                            preferences.fitness.measurements.custom =
+                                serde_json::from_str(&input.value).map_err(|_| invalid())?
+                        }
+                        "inbuilt" => match right {
+                            "weight" => {
+                                preferences.fitness.measurements.inbuilt.weight = value_bool()?
+                            }
+                            "body_mass_index" => {
+                                preferences.fitness.measurements.inbuilt.body_mass_index =
+                                    value_bool()?;
+                            }
+                            "total_body_water" => {
+                                preferences.fitness.measurements.inbuilt.total_body_water =
+                                    value_bool()?;
+                            }
+                            "muscle" => {
+                                preferences.fitness.measurements.inbuilt.muscle = value_bool()?;
+                            }
+                            "lean_body_mass" => {
+                                preferences.fitness.measurements.inbuilt.lean_body_mass =
+                                    value_bool()?;
+                            }
+                            "body_fat" => {
+                                preferences.fitness.measurements.inbuilt.body_fat = value_bool()?;
+                            }
+                            "bone_mass" => {
+                                preferences.fitness.measurements.inbuilt.bone_mass = value_bool()?;
+                            }
+                            "visceral_fat" => {
+                                preferences.fitness.measurements.inbuilt.visceral_fat =
+                                    value_bool()?;
+                            }
+                            "waist_circumference" => {
+                                preferences.fitness.measurements.inbuilt.waist_circumference =
+                                    value_bool()?;
+                            }
+                            "waist_to_height_ratio" => {
+                                preferences
+                                    .fitness
+                                    .measurements
+                                    .inbuilt
+                                    .waist_to_height_ratio = value_bool()?;
+                            }
+                            "hip_circumference" => {
+                                preferences.fitness.measurements.inbuilt.hip_circumference =
+                                    value_bool()?;
+                            }
+                            "waist_to_hip_ratio" => {
+                                preferences.fitness.measurements.inbuilt.waist_to_hip_ratio =
+                                    value_bool()?;
+                            }
+                            "chest_circumference" => {
+                                preferences.fitness.measurements.inbuilt.chest_circumference =
+                                    value_bool()?;
+                            }
+                            "thigh_circumference" => {
+                                preferences.fitness.measurements.inbuilt.thigh_circumference =
+                                    value_bool()?;
+                            }
+                            "biceps_circumference" => {
+                                preferences
+                                    .fitness
+                                    .measurements
+                                    .inbuilt
+                                    .biceps_circumference = value_bool()?;
+                            }
+                            "neck_circumference" => {
+                                preferences.fitness.measurements.inbuilt.neck_circumference =
+                                    value_bool()?;
+                            }
+                            "body_fat_caliper" => {
+                                preferences.fitness.measurements.inbuilt.body_fat_caliper =
+                                    value_bool()?;
+                            }
+                            "chest_skinfold" => {
+                                preferences.fitness.measurements.inbuilt.chest_skinfold =
+                                    value_bool()?;
+                            }
+                            "abdominal_skinfold" => {
+                                preferences.fitness.measurements.inbuilt.abdominal_skinfold =
+                                    value_bool()?;
+                            }
+                            "thigh_skinfold" => {
+                                preferences.fitness.measurements.inbuilt.thigh_skinfold =
+                                    value_bool()?;
+                            }
+                            "basal_metabolic_rate" => {
+                                preferences
+                                    .fitness
+                                    .measurements
+                                    .inbuilt
+                                    .basal_metabolic_rate = value_bool()?;
+                            }
+                            "total_daily_energy_expenditure" => {
+                                preferences
+                                    .fitness
+                                    .measurements
+                                    .inbuilt
+                                    .total_daily_energy_expenditure = value_bool()?;
+                            }
+                            "calories" => {
+                                preferences.fitness.measurements.inbuilt.calories = value_bool()?;
+                            }
+                            _ => return Err(err()),
+                        },
+                        _ => return Err(err()),
+                    }
+                }
+                "exercises" => match right.split_once('.') {
+                    Some((left, right)) => match left {
+                        "set_rest_timers" => {
+                            let value = input.value.parse().ok();
+                            let set_rest_timers =
+                                &mut preferences.fitness.exercises.set_rest_timers;
+                            match right {
+                                "drop" => set_rest_timers.drop = value,
+                                "normal" => set_rest_timers.normal = value,
+                                "warmup" => set_rest_timers.warmup = value,
+                                "failure" => set_rest_timers.failure = value,
+                                _ => return Err(err()),
+                            }
+                        }
+                        _ => return Err(err()),
+                    },
+                    None => match right {
+                        "unit_system" => {
+                            preferences.fitness.exercises.unit_system =
+                                UserUnitSystem::from_str(&input.value).map_err(|_| invalid())?;
+                        }
+                        _ => return Err(err()),
+                    },
+                },
+                "logging" => match right {
+                    "show_details_while_editing" => {
+                        preferences.fitness.logging.show_details_while_editing = value_bool()?;
+                    }
+                    _ => return Err(err()),
+                },
+                _ => return Err(err()),
+            }
+        }
+        "features_enabled" => {
+            let (left, right) = right.split_once('.').ok_or_else(err)?;
+            match left {
+                "others" => match right {
+                    "collections" => {
+                        preferences.features_enabled.others.collections = value_bool()?
+                    }
+                    "calendar" => preferences.features_enabled.others.calendar = value_bool()?,
+                    _ => return Err(err()),
+                },
+                "fitness" => match right {
+                    "enabled" => preferences.features_enabled.fitness.enabled = value_bool()?,
+                    "measurements" => {
+                        preferences.features_enabled.fitness.measurements = value_bool()?
+                    }
+                    "workouts" => preferences.features_enabled.fitness.workouts = value_bool()?,
+                    "templates" => preferences.features_enabled.fitness.templates = value_bool()?,
+                    _ => return Err(err()),
+                },
+                "media" => {
+                    match right {
+                        "enabled" => preferences.features_enabled.media.enabled = value_bool()?,
+                        "audio_book" => {
+                            preferences.features_enabled.media.audio_book = value_bool()?
+                        }
+                        "book" => preferences.features_enabled.media.book = value_bool()?,
+                        "movie" => preferences.features_enabled.media.movie = value_bool()?,
+                        "podcast" => preferences.features_enabled.media.podcast = value_bool()?,
+                        "show" => preferences.features_enabled.media.show = value_bool()?,
+                        "video_game" => {
+                            preferences.features_enabled.media.video_game = value_bool()?
+                        }
+                        "visual_novel" => {
+                            preferences.features_enabled.media.visual_novel = value_bool()?
+                        }
+                        "manga" => preferences.features_enabled.media.manga = value_bool()?,
+                        "anime" => preferences.features_enabled.media.anime = value_bool()?,
+                        "people" => preferences.features_enabled.media.people = value_bool()?,
+                        "groups" => preferences.features_enabled.media.groups = value_bool()?,
+                        "genres" => preferences.features_enabled.media.genres = value_bool()?,
+                        _ => return Err(err()),
+                    };
+                }
+                _ => return Err(err()),
+            }
+        }
+        "notifications" => match right {
+            "to_send" => {
+                preferences.notifications.to_send =
+                    serde_json::from_str(&input.value).map_err(|_| invalid())?;
+            }
+            "enabled" => {
+                preferences.notifications.enabled = value_bool()?;
+            }
+            _ => return Err(err()),
+        },
+        "general" => match right {
+            "review_scale" => {
+                preferences.general.review_scale =
+                    UserReviewScale::from_str(&input.value).map_err(|_| invalid())?;
+            }
+            "grid_packing" => {
+                preferences.general.grid_packing =
+                    GridPacking::from_str(&input.value).map_err(|_| invalid())?;
+            }
+            "display_nsfw" => {
+                preferences.general.display_nsfw = value_bool()?;
+            }
+            "dashboard" => {
+                preferences.general.dashboard =
+                    serde_json::from_str(&input.value).map_err(|_| invalid())?;
+            }
+            "disable_activity_sharing" => {
+                preferences.general.disable_activity_sharing = value_bool()?;
+            }
+            "disable_integrations" => {
+                preferences.general.disable_integrations = value_bool()?;
+            }
+            "persist_queries" => {
+                preferences.general.persist_queries = value_bool()?;
+            }
+            "disable_navigation_animation" => {
+                preferences.general.disable_navigation_animation = value_bool()?;
+            }
+            "quick_log_action" => {
+                preferences.general.quick_log_action =
+                    UserQuickLogAction::from_str(&input.value)
+                        .map_err(|_| Error::new("Unknown quick log action"))?;
+            }
+            "quick_log_auto_advance" => {
+                preferences.general.quick_log_auto_advance = value_bool()?;
+            }
+            "content_warning_tags" => {
+                preferences.general.content_warning_tags =
+                    serde_json::from_str(&input.value).map_err(|_| invalid())?;
+            }
+            "disable_videos" => {
+                preferences.general.disable_videos = value_bool()?;
+            }
+            "disable_watch_providers" => {
+                preferences.general.disable_watch_providers = value_bool()?;
+            }
+            "watch_providers" => {
+                preferences.general.watch_providers =
+                    serde_json::from_str(&input.value).map_err(|_| invalid())?;
+            }
+            "disable_reviews" => {
+                preferences.general.disable_reviews = value_bool()?;
+            }
+            _ => return Err(err()),
+        },
+        _ => return Err(err()),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_rounds_to_the_steps_of_the_scale() {
+        assert_eq!(UserReviewScale::OutOfFive.normalize(dec!(3.5)), dec!(70));
+        assert_eq!(UserReviewScale::OutOfFive.normalize(dec!(3.7)), dec!(70));
+        assert_eq!(UserReviewScale::OutOfFive.normalize(dec!(3.8)), dec!(80));
+        assert_eq!(UserReviewScale::OutOfTen.normalize(dec!(7)), dec!(70));
+        assert_eq!(UserReviewScale::OutOfTen.normalize(dec!(7.4)), dec!(70));
+        assert_eq!(UserReviewScale::OutOfTen.normalize(dec!(7.6)), dec!(80));
+        assert_eq!(
+            UserReviewScale::OutOfHundred.normalize(dec!(73.5)),
+            dec!(73.5)
+        );
+        assert_eq!(
+            UserReviewScale::ThreePointSmiley.normalize(dec!(66.66)),
+            dec!(66.66)
+        );
+    }
+
+    #[test]
+    fn denormalize_rounds_to_one_decimal_place() {
+        assert_eq!(UserReviewScale::OutOfFive.denormalize(dec!(70)), dec!(3.5));
+        assert_eq!(UserReviewScale::OutOfFive.denormalize(dec!(74)), dec!(3.7));
+        assert_eq!(UserReviewScale::OutOfTen.denormalize(dec!(73)), dec!(7.3));
+        assert_eq!(
+            UserReviewScale::OutOfTen.denormalize(dec!(66.66)),
+            dec!(6.7)
+        );
+        assert_eq!(
+            UserReviewScale::OutOfHundred.denormalize(dec!(66.66)),
+            dec!(66.7)
+        );
+    }
+
+    #[test]
+    fn conversions_round_trip_for_every_scale() {
+        for scale in [
+            UserReviewScale::OutOfFive,
+            UserReviewScale::OutOfTen,
+            UserReviewScale::OutOfHundred,
+        ] {
+            let maximum = scale.maximum();
+            assert_eq!(scale.normalize(maximum), dec!(100));
+            assert_eq!(scale.denormalize(scale.normalize(maximum)), maximum);
+            assert_eq!(scale.normalize(dec!(0)), dec!(0));
+        }
+    }
+}
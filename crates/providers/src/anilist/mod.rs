@@ -10,16 +10,18 @@ use enums::{MediaLot, MediaSource};
 use graphql_client::{GraphQLQuery, Response};
 use itertools::Itertools;
 use media_models::{
-    AnimeAiringScheduleSpecifics, AnimeSpecifics, MangaSpecifics, MetadataDetails,
-    MetadataImageForMediaDetails, MetadataPerson, MetadataPersonRelated, MetadataSearchItem,
-    MetadataVideo, MetadataVideoSource, PartialMetadataPerson, PartialMetadataWithoutId,
-    PeopleSearchItem, PersonSourceSpecifics,
+    AnimeAiringScheduleSpecifics, AnimeSpecifics, ExternalIdentifiers, MangaSpecifics,
+    MetadataDetails, MetadataImageForMediaDetails, MetadataPerson, MetadataPersonRelated,
+    MetadataSearchItem, MetadataVideo, MetadataVideoSource, PartialMetadataPerson,
+    PartialMetadataWithoutId, PeopleSearchItem, PersonSourceSpecifics,
 };
 use reqwest::Client;
 use rust_decimal::Decimal;
 use sea_orm::prelude::DateTimeUtc;
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://graphql.anilist.co";
 static STUDIO_ROLE: &str = "Production Studio";
 
@@ -77,6 +79,15 @@ struct StaffQuery;
 )]
 struct StudioQuery;
 
+pub fn metadata_url(lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    let bw = match lot {
+        MediaLot::Anime => "anime",
+        MediaLot::Manga => "manga",
+        _ => return None,
+    };
+    Some(format!("https://anilist.co/{bw}/{identifier}/{slug}"))
+}
+
 #[derive(Debug, Clone)]
 pub struct AnilistService {
     client: Client,
@@ -159,7 +170,8 @@ impl MediaProvider for NonMediaAnilistService {
                 .json(&body)
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json::<Response<studio_search_query::ResponseData>>()
                 .await
                 .map_err(|e| anyhow!(e))?
@@ -202,7 +214,8 @@ impl MediaProvider for NonMediaAnilistService {
                 .json(&body)
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json::<Response<staff_search_query::ResponseData>>()
                 .await
                 .map_err(|e| anyhow!(e))?
@@ -264,7 +277,8 @@ impl MediaProvider for NonMediaAnilistService {
                 .json(&body)
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json::<Response<studio_query::ResponseData>>()
                 .await
                 .map_err(|e| anyhow!(e))?
@@ -324,7 +338,8 @@ impl MediaProvider for NonMediaAnilistService {
                 .json(&body)
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json::<Response<staff_query::ResponseData>>()
                 .await
                 .map_err(|e| anyhow!(e))?
@@ -550,7 +565,8 @@ async fn media_details(
         .json(&body)
         .send()
         .await
-        .map_err(|e| anyhow!(e))?
+        .map_err(|e| anyhow!(e))
+        .and_then(check_retry_after)?
         .json::<Response<media_details_query::ResponseData>>()
         .await
         .map_err(|e| anyhow!(e))?;
@@ -567,20 +583,19 @@ async fn media_details(
         .map(|i| MetadataImageForMediaDetails { image: i })
         .unique()
         .collect();
-    let mut genres = media
+    let genres = media
         .genres
         .into_iter()
         .flatten()
         .map(|t| t.unwrap())
         .collect_vec();
-    genres.extend(
-        media
-            .tags
-            .unwrap_or_default()
-            .into_iter()
-            .flatten()
-            .map(|t| t.name),
-    );
+    let tags = media
+        .tags
+        .unwrap_or_default()
+        .into_iter()
+        .flatten()
+        .map(|t| t.name)
+        .collect_vec();
     let mut people = Vec::from_iter(media.staff)
         .into_iter()
         .flat_map(|s| s.edges.unwrap())
@@ -697,6 +712,7 @@ async fn media_details(
         },
     }));
     let title = media.title.unwrap();
+    let romanized_title = title.romaji.clone();
     let title = get_in_preferred_language(
         title.native,
         title.english,
@@ -705,6 +721,7 @@ async fn media_details(
     );
     Ok(MetadataDetails {
         title,
+        romanized_title,
         identifier: media.id.to_string(),
         is_nsfw: media.is_adult,
         source: MediaSource::Anilist,
@@ -715,6 +732,7 @@ async fn media_details(
         url_images: images,
         videos,
         genres: genres.into_iter().unique().collect(),
+        tags: tags.into_iter().unique().collect(),
         publish_year: year,
         publish_date: None,
         anime_specifics,
@@ -725,6 +743,10 @@ async fn media_details(
         s3_images: vec![],
         production_status: media_status_string(media.status),
         original_language: None,
+        external_identifiers: Some(ExternalIdentifiers {
+            mal_id: media.id_mal.and_then(|i| i32::try_from(i).ok()),
+            ..Default::default()
+        }),
         ..Default::default()
     })
 }
@@ -751,7 +773,8 @@ async fn search(
         .json(&body)
         .send()
         .await
-        .map_err(|e| anyhow!(e))?
+        .map_err(|e| anyhow!(e))
+        .and_then(check_retry_after)?
         .json::<Response<media_search_query::ResponseData>>()
         .await
         .map_err(|e| anyhow!(e))?
@@ -10,9 +10,9 @@ use educe::Educe;
 use enums::{MediaLot, MediaSource};
 use itertools::Itertools;
 use media_models::{
-    AudioBookSpecifics, MetadataDetails, MetadataFreeCreator, MetadataImageForMediaDetails,
-    MetadataPerson, MetadataSearchItem, PartialMetadataPerson, PartialMetadataWithoutId,
-    PeopleSearchItem, PersonSourceSpecifics,
+    AudioBookSpecifics, MediaDuration, MetadataDetails, MetadataFreeCreator,
+    MetadataImageForMediaDetails, MetadataPerson, MetadataSearchItem, PartialMetadataPerson,
+    PartialMetadataWithoutId, PeopleSearchItem, PersonSourceSpecifics,
 };
 use paginate::Pages;
 use reqwest::Client;
@@ -22,6 +22,8 @@ use serde_json::json;
 use strum::{Display, EnumIter, IntoEnumIterator};
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static LOCALES: [&str; 10] = ["au", "ca", "de", "es", "fr", "in", "it", "jp", "gb", "us"];
 static AUDNEX_URL: &str = "https://api.audnex.us";
 
@@ -77,6 +79,7 @@ struct AudibleRelationshipItem {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AudibleRating {
     display_average_rating: Option<Decimal>,
+    num_ratings: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,6 +130,10 @@ struct AudibleItemSimResponse {
     similar_products: Vec<AudibleItem>,
 }
 
+pub fn metadata_url(_lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    Some(format!("https://www.audible.com/pd/{slug}/{identifier}"))
+}
+
 #[derive(Debug, Clone)]
 pub struct AudibleService {
     url: String,
@@ -190,7 +197,8 @@ impl MediaProvider for AudibleService {
             .query(&json!({ "region": self.locale, "name": query }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -232,7 +240,8 @@ impl MediaProvider for AudibleService {
             .query(&json!({ "region": self.locale }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -262,7 +271,8 @@ impl MediaProvider for AudibleService {
             .query(&PrimaryQuery::default())
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -282,7 +292,8 @@ impl MediaProvider for AudibleService {
                 .query(&PrimaryQuery::default())
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?;
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?;
             let data: AudibleItemResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
             collection_contents.push(PartialMetadataWithoutId {
                 title: data.product.title,
@@ -315,7 +326,8 @@ impl MediaProvider for AudibleService {
             .query(&PrimaryQuery::default())
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let data: AudibleItemResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let mut groups = vec![];
         for s in data.product.clone().series.unwrap_or_default() {
@@ -333,7 +345,8 @@ impl MediaProvider for AudibleService {
                 }))
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json()
                 .await
                 .map_err(|e| anyhow!(e))?;
@@ -377,7 +390,8 @@ impl MediaProvider for AudibleService {
             })
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: AudibleSearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .products
@@ -442,14 +456,12 @@ impl AudibleService {
             })
             .collect_vec();
         let description = item.publisher_summary.or(item.merchandising_summary);
-        let rating = if let Some(r) = item.rating {
-            if r.num_reviews > 0 {
-                r.overall_distribution.display_average_rating
-            } else {
-                None
-            }
-        } else {
-            None
+        let (rating, rating_count) = match item.rating {
+            Some(r) if r.num_reviews > 0 => (
+                r.overall_distribution.display_average_rating,
+                r.overall_distribution.num_ratings,
+            ),
+            _ => (None, None),
         };
         MetadataDetails {
             identifier: item.asin,
@@ -475,11 +487,43 @@ impl AudibleService {
             publish_year: convert_date_to_year(&release_date),
             publish_date: convert_string_to_date(&release_date),
             audio_book_specifics: Some(AudioBookSpecifics {
-                runtime: item.runtime_length_min,
+                runtime: item.runtime_length_min.map(MediaDuration::from_minutes),
             }),
             url_images: images,
             provider_rating: rating,
+            provider_rating_count: rating_count,
             ..Default::default()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn audiobook_runtime_is_stored_in_minutes() {
+        let service = AudibleService {
+            url: String::new(),
+            client: Client::new(),
+            locale: "us".to_owned(),
+        };
+        let item: AudibleItem = serde_json::from_value(json!({
+            "asin": "B0036I54I6",
+            "title": "Dune",
+            "product_images": {},
+            "runtime_length_min": 1283
+        }))
+        .unwrap();
+        let details = service.audible_response_to_search_response(item);
+        assert_eq!(
+            details
+                .audio_book_specifics
+                .and_then(|s| s.runtime)
+                .map(|r| r.minutes()),
+            Some(1283)
+        );
+    }
+}
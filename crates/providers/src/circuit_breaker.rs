@@ -0,0 +1,510 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    io,
+    sync::{LazyLock, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use common_models::{ProviderCircuitState, ProviderStatus};
+use common_utils::ryot_log;
+use database_models::metadata_group::MetadataGroupWithoutId;
+use dependent_models::SearchResults;
+use enums::MediaSource;
+use itertools::Itertools;
+use media_models::{
+    MetadataDetails, MetadataGroupSearchItem, MetadataPerson, MetadataSearchItem,
+    PartialMetadataWithoutId, PeopleSearchItem, PersonSourceSpecifics,
+};
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+use sea_orm::{prelude::DateTimeUtc, Iterable};
+use traits::MediaProvider;
+
+// DEV: Providers are constructed on every call, so the breaker state has to outlive them.
+static BREAKERS: LazyLock<Mutex<HashMap<MediaSource, Breaker>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, Default)]
+struct Breaker {
+    consecutive_failures: i32,
+    opened_at: Option<DateTimeUtc>,
+    retry_at: Option<DateTimeUtc>,
+    probe_started_at: Option<DateTimeUtc>,
+}
+
+impl Breaker {
+    fn state(&self) -> ProviderCircuitState {
+        self.state_at(Utc::now())
+    }
+
+    fn state_at(&self, now: DateTimeUtc) -> ProviderCircuitState {
+        match self.retry_at {
+            None => ProviderCircuitState::Closed,
+            Some(retry_at) if now < retry_at => ProviderCircuitState::Open,
+            Some(_) => ProviderCircuitState::HalfOpen,
+        }
+    }
+
+    /// Whether a call can be made. When the breaker is half open only one probe
+    /// is let through, unless the previous probe never reported back.
+    fn try_acquire(&mut self, now: DateTimeUtc, cooldown: Duration) -> bool {
+        match self.state_at(now) {
+            ProviderCircuitState::Closed => true,
+            ProviderCircuitState::HalfOpen
+                if self
+                    .probe_started_at
+                    .map_or(true, |started| now - started > cooldown) =>
+            {
+                self.probe_started_at = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Count a failed call and open the breaker once there were `threshold`
+    /// failures in a row. Returns the cooldown if the breaker was opened.
+    fn record_failure(
+        &mut self,
+        error: &anyhow::Error,
+        now: DateTimeUtc,
+        threshold: i32,
+        cooldown: Duration,
+    ) -> Option<Duration> {
+        self.probe_started_at = None;
+        if !is_provider_outage(error) {
+            return None;
+        }
+        self.consecutive_failures += 1;
+        let rate_limited = error.downcast_ref::<ProviderRateLimited>();
+        if rate_limited.is_none() && self.consecutive_failures < threshold {
+            return None;
+        }
+        // DEV: The provider knows best when it will accept calls again, so its
+        // `Retry-After` wins over the configured cooldown in both directions.
+        let cooldown = rate_limited
+            .map(|r| r.retry_after.max(Duration::zero()))
+            .unwrap_or(cooldown);
+        self.opened_at = Some(now);
+        self.retry_at = Some(now + cooldown);
+        Some(cooldown)
+    }
+}
+
+/// Whether an error means that the provider itself is having trouble. Errors
+/// like an unknown identifier or an unexpected response body say nothing about
+/// its health and are not counted by the breaker.
+fn is_provider_outage(error: &anyhow::Error) -> bool {
+    error.chain().any(|e| {
+        if e.is::<ProviderRateLimited>() {
+            return true;
+        }
+        if let Some(e) = e.downcast_ref::<io::Error>() {
+            return matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+            );
+        }
+        e.downcast_ref::<reqwest::Error>().is_some_and(|e| {
+            e.is_timeout()
+                || e.is_connect()
+                || e.is_request()
+                || e.status()
+                    .is_some_and(|s| s.is_server_error() || s == StatusCode::TOO_MANY_REQUESTS)
+        })
+    })
+}
+
+/// Returned instead of calling a provider whose circuit breaker is open.
+#[derive(Debug)]
+pub struct ProviderUnavailable {
+    pub source: MediaSource,
+    pub retry_at: DateTimeUtc,
+}
+
+impl fmt::Display for ProviderUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = (self.retry_at - Utc::now()).num_seconds().max(1);
+        write!(
+            f,
+            "{:?} is temporarily unavailable, please try again in {} seconds",
+            self.source, seconds
+        )
+    }
+}
+
+impl std::error::Error for ProviderUnavailable {}
+
+/// Returned when a provider asks us to back off via the `Retry-After` header.
+#[derive(Debug)]
+pub struct ProviderRateLimited {
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for ProviderRateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Provider asked to retry after {} seconds",
+            self.retry_after.num_seconds()
+        )
+    }
+}
+
+impl std::error::Error for ProviderRateLimited {}
+
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Some(Duration::seconds(seconds));
+    }
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| date.with_timezone(&Utc) - Utc::now())
+}
+
+/// Turn a rate limited response into a `ProviderRateLimited` error so that the
+/// circuit breaker can honor the delay requested by the provider. Other rate
+/// limited or server error responses are returned as errors so that they count
+/// as failures of the provider.
+pub fn check_retry_after(rsp: Response) -> Result<Response> {
+    let status = rsp.status();
+    let is_rate_limited = matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    );
+    let retry_after = rsp
+        .headers()
+        .get(RETRY_AFTER)
+        .filter(|_| is_rate_limited)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_retry_after);
+    if let Some(retry_after) = retry_after {
+        return Err(anyhow!(ProviderRateLimited { retry_after }));
+    }
+    match is_rate_limited || status.is_server_error() {
+        true => Ok(rsp.error_for_status()?),
+        false => Ok(rsp),
+    }
+}
+
+/// The time after which calls to the provider will be allowed again, if its
+/// circuit breaker is currently open.
+pub fn provider_retry_at(source: MediaSource) -> Option<DateTimeUtc> {
+    let breakers = BREAKERS.lock().unwrap();
+    let breaker = breakers.get(&source)?;
+    match breaker.state() {
+        ProviderCircuitState::Open => breaker.retry_at,
+        _ => None,
+    }
+}
+
+pub fn provider_statuses() -> Vec<ProviderStatus> {
+    let breakers = BREAKERS.lock().unwrap();
+    MediaSource::iter()
+        .filter(|source| *source != MediaSource::Custom)
+        .map(|source| {
+            let breaker = breakers.get(&source).cloned().unwrap_or_default();
+            ProviderStatus {
+                source,
+                state: breaker.state(),
+                retry_at: breaker.retry_at,
+                opened_at: breaker.opened_at,
+                consecutive_failures: breaker.consecutive_failures,
+            }
+        })
+        .collect_vec()
+}
+
+/// Wraps a provider and stops calling it after repeated failures. Once the
+/// cooldown is over, a single probe call is let through and its outcome
+/// decides whether the breaker closes or opens again.
+pub struct CircuitBreakingProvider {
+    source: MediaSource,
+    threshold: i32,
+    cooldown: Duration,
+    inner: Box<dyn MediaProvider + Send + Sync>,
+}
+
+impl CircuitBreakingProvider {
+    pub fn new(
+        source: MediaSource,
+        config: &config::MediaConfig,
+        inner: Box<dyn MediaProvider + Send + Sync>,
+    ) -> Self {
+        Self {
+            inner,
+            source,
+            threshold: config.provider_failure_threshold.max(1),
+            cooldown: Duration::seconds(config.provider_cooldown_seconds),
+        }
+    }
+
+    fn acquire(&self) -> Result<()> {
+        let mut breakers = BREAKERS.lock().unwrap();
+        let breaker = breakers.entry(self.source).or_default();
+        let now = Utc::now();
+        let is_probe = breaker.state_at(now) == ProviderCircuitState::HalfOpen;
+        if !breaker.try_acquire(now, self.cooldown) {
+            return Err(anyhow!(ProviderUnavailable {
+                source: self.source,
+                retry_at: breaker.retry_at.unwrap_or(now),
+            }));
+        }
+        if is_probe {
+            ryot_log!(debug, "Probing provider {:?}", self.source);
+        }
+        Ok(())
+    }
+
+    fn record_success(&self) {
+        let mut breakers = BREAKERS.lock().unwrap();
+        if let Some(breaker) = breakers.remove(&self.source) {
+            if breaker.retry_at.is_some() {
+                ryot_log!(info, "Provider {:?} has recovered", self.source);
+            }
+        }
+    }
+
+    fn record_failure(&self, error: &anyhow::Error) {
+        let mut breakers = BREAKERS.lock().unwrap();
+        let breaker = breakers.entry(self.source).or_default();
+        let Some(cooldown) =
+            breaker.record_failure(error, Utc::now(), self.threshold, self.cooldown)
+        else {
+            return;
+        };
+        ryot_log!(
+            warn,
+            "Pausing calls to provider {:?} for {} seconds after {} consecutive failures",
+            self.source,
+            cooldown.num_seconds(),
+            breaker.consecutive_failures
+        );
+    }
+
+    async fn call<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        self.acquire()?;
+        let result = future.await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(e) => self.record_failure(e),
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl MediaProvider for CircuitBreakingProvider {
+    async fn metadata_search(
+        &self,
+        query: &str,
+        page: Option<i32>,
+        display_nsfw: bool,
+    ) -> Result<SearchResults<MetadataSearchItem>> {
+        self.call(self.inner.metadata_search(query, page, display_nsfw))
+            .await
+    }
+
+    async fn metadata_details(&self, identifier: &str) -> Result<MetadataDetails> {
+        self.call(self.inner.metadata_details(identifier)).await
+    }
+
+    async fn metadata_updated_since(&self, identifier: &str, since: DateTimeUtc) -> Result<bool> {
+        self.call(self.inner.metadata_updated_since(identifier, since))
+            .await
+    }
+
+    async fn people_search(
+        &self,
+        query: &str,
+        page: Option<i32>,
+        source_specifics: &Option<PersonSourceSpecifics>,
+        display_nsfw: bool,
+    ) -> Result<SearchResults<PeopleSearchItem>> {
+        self.call(
+            self.inner
+                .people_search(query, page, source_specifics, display_nsfw),
+        )
+        .await
+    }
+
+    async fn person_details(
+        &self,
+        identity: &str,
+        source_specifics: &Option<PersonSourceSpecifics>,
+    ) -> Result<MetadataPerson> {
+        self.call(self.inner.person_details(identity, source_specifics))
+            .await
+    }
+
+    async fn metadata_group_search(
+        &self,
+        query: &str,
+        page: Option<i32>,
+        display_nsfw: bool,
+    ) -> Result<SearchResults<MetadataGroupSearchItem>> {
+        self.call(self.inner.metadata_group_search(query, page, display_nsfw))
+            .await
+    }
+
+    async fn metadata_suggestions(
+        &self,
+        identifier: &str,
+    ) -> Result<Vec<PartialMetadataWithoutId>> {
+        self.call(self.inner.metadata_suggestions(identifier)).await
+    }
+
+    async fn metadata_group_details(
+        &self,
+        identifier: &str,
+    ) -> Result<(MetadataGroupWithoutId, Vec<PartialMetadataWithoutId>)> {
+        self.call(self.inner.metadata_group_details(identifier))
+            .await
+    }
+
+    async fn get_recommendations_for_metadata(
+        &self,
+        identifier: &str,
+    ) -> Result<Vec<PartialMetadataWithoutId>> {
+        self.call(self.inner.get_recommendations_for_metadata(identifier))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: i32 = 3;
+
+    fn cooldown() -> Duration {
+        Duration::seconds(60)
+    }
+
+    fn start() -> DateTimeUtc {
+        DateTime::parse_from_rfc3339("2024-03-05T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn timeout() -> anyhow::Error {
+        anyhow!(io::Error::from(io::ErrorKind::TimedOut))
+    }
+
+    fn open_breaker(now: DateTimeUtc) -> Breaker {
+        let mut breaker = Breaker::default();
+        for _ in 1..THRESHOLD {
+            let opened = breaker.record_failure(&timeout(), now, THRESHOLD, cooldown());
+            assert_eq!(opened, None);
+            assert_eq!(breaker.state_at(now), ProviderCircuitState::Closed);
+        }
+        let opened = breaker.record_failure(&timeout(), now, THRESHOLD, cooldown());
+        assert_eq!(opened, Some(cooldown()));
+        breaker
+    }
+
+    #[test]
+    fn breaker_ignores_errors_that_are_not_outages() {
+        let now = start();
+        let mut breaker = Breaker::default();
+        for _ in 0..10 {
+            let opened = breaker.record_failure(
+                &anyhow!("No movie found with id 1"),
+                now,
+                THRESHOLD,
+                cooldown(),
+            );
+            assert_eq!(opened, None);
+        }
+        assert_eq!(breaker.consecutive_failures, 0);
+        assert_eq!(breaker.state_at(now), ProviderCircuitState::Closed);
+        assert!(breaker.try_acquire(now, cooldown()));
+
+        breaker.record_failure(&timeout(), now, THRESHOLD, cooldown());
+        breaker.record_failure(&anyhow!("Invalid JSON"), now, THRESHOLD, cooldown());
+        assert_eq!(breaker.consecutive_failures, 1);
+    }
+
+    #[test]
+    fn rate_limit_opens_the_breaker_for_the_requested_delay() {
+        let now = start();
+        let mut breaker = Breaker::default();
+        let error = anyhow!(ProviderRateLimited {
+            retry_after: Duration::seconds(10),
+        });
+        let opened = breaker.record_failure(&error, now, THRESHOLD, cooldown());
+        assert_eq!(opened, Some(Duration::seconds(10)));
+        assert_eq!(breaker.retry_at, Some(now + Duration::seconds(10)));
+        assert_eq!(breaker.state_at(now), ProviderCircuitState::Open);
+        assert!(!breaker.try_acquire(now, cooldown()));
+    }
+
+    #[test]
+    fn negative_retry_after_opens_without_delay() {
+        let now = start();
+        let mut breaker = Breaker::default();
+        let error = anyhow!(ProviderRateLimited {
+            retry_after: Duration::seconds(-5),
+        });
+        let opened = breaker.record_failure(&error, now, THRESHOLD, cooldown());
+        assert_eq!(opened, Some(Duration::zero()));
+        assert_eq!(breaker.state_at(now), ProviderCircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn breaker_goes_from_open_to_half_open_to_closed() {
+        let now = start();
+        let mut breaker = open_breaker(now);
+        assert_eq!(breaker.state_at(now), ProviderCircuitState::Open);
+        assert!(!breaker.try_acquire(now + Duration::seconds(30), cooldown()));
+
+        let later = now + cooldown();
+        assert_eq!(breaker.state_at(later), ProviderCircuitState::HalfOpen);
+        assert!(breaker.try_acquire(later, cooldown()));
+        // DEV: Only one probe is let through while it is in flight
+        assert!(!breaker.try_acquire(later + Duration::seconds(1), cooldown()));
+
+        // DEV: A successful probe removes the breaker
+        breaker = Breaker::default();
+        assert_eq!(breaker.state_at(later), ProviderCircuitState::Closed);
+        assert!(breaker.try_acquire(later, cooldown()));
+    }
+
+    #[test]
+    fn failed_probe_opens_the_breaker_again() {
+        let now = start();
+        let mut breaker = open_breaker(now);
+        let later = now + cooldown();
+        assert!(breaker.try_acquire(later, cooldown()));
+        let opened = breaker.record_failure(&timeout(), later, THRESHOLD, cooldown());
+        assert_eq!(opened, Some(cooldown()));
+        assert_eq!(breaker.probe_started_at, None);
+        assert_eq!(breaker.state_at(later), ProviderCircuitState::Open);
+        assert_eq!(
+            breaker.state_at(later + cooldown()),
+            ProviderCircuitState::HalfOpen
+        );
+    }
+
+    #[test]
+    fn abandoned_probe_is_replaced_after_the_cooldown() {
+        let now = start();
+        let mut breaker = open_breaker(now);
+        let later = now + cooldown();
+        assert!(breaker.try_acquire(later, cooldown()));
+        assert!(!breaker.try_acquire(later + cooldown(), cooldown()));
+        assert!(breaker.try_acquire(later + cooldown() + Duration::seconds(1), cooldown()));
+    }
+
+    #[test]
+    fn parse_retry_after_accepts_seconds() {
+        assert_eq!(parse_retry_after(" 120 "), Some(Duration::seconds(120)));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -19,8 +19,16 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://www.googleapis.com/books/v1/volumes";
 
+pub fn metadata_url(_lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    Some(format!(
+        "https://www.google.co.in/books/edition/{slug}/{identifier}"
+    ))
+}
+
 #[derive(Debug, Clone)]
 pub struct GoogleBooksService {
     client: Client,
@@ -66,6 +74,7 @@ struct ImageLinks {
 struct ItemVolumeInfo {
     title: String,
     average_rating: Option<Decimal>,
+    ratings_count: Option<i32>,
     published_date: Option<String>,
     image_links: Option<ImageLinks>,
     description: Option<String>,
@@ -98,7 +107,8 @@ impl MediaProvider for GoogleBooksService {
             .get(format!("{}/{}", URL, identifier))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let data: ItemResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let d = self.google_books_response_to_search_response(data.volume_info, data.id);
         Ok(d)
@@ -126,7 +136,8 @@ impl MediaProvider for GoogleBooksService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: SearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .items
@@ -235,6 +246,7 @@ impl GoogleBooksService {
             }),
             url_images: images.unique().collect(),
             provider_rating: item.average_rating,
+            provider_rating_count: item.ratings_count,
             ..Default::default()
         }
     }
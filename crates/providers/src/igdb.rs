@@ -11,8 +11,8 @@ use dependent_models::SearchResults;
 use enums::{MediaLot, MediaSource};
 use itertools::Itertools;
 use media_models::{
-    MetadataDetails, MetadataGroupSearchItem, MetadataImageForMediaDetails, MetadataPerson,
-    MetadataPersonRelated, MetadataSearchItem, MetadataVideo, MetadataVideoSource,
+    ExternalIdentifiers, MetadataDetails, MetadataGroupSearchItem, MetadataImageForMediaDetails,
+    MetadataPerson, MetadataPersonRelated, MetadataSearchItem, MetadataVideo, MetadataVideoSource,
     PartialMetadataPerson, PartialMetadataWithoutId, PeopleSearchItem, PersonSourceSpecifics,
     VideoGameSpecifics,
 };
@@ -28,6 +28,8 @@ use serde_json::{json, Value};
 use serde_with::{formats::Flexible, serde_as, TimestampSeconds};
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://api.igdb.com/v4";
 static IMAGE_URL: &str = "https://images.igdb.com/igdb/image/upload";
 static AUTH_URL: &str = "https://id.twitch.tv/oauth2/token";
@@ -42,6 +44,7 @@ static GAME_FIELDS: &str = "
 fields
     id,
     name,
+    slug,
     summary,
     cover.*,
     first_release_date,
@@ -50,12 +53,15 @@ fields
     involved_companies.*,
     artworks.*,
     rating,
+    rating_count,
     similar_games.id,
     similar_games.name,
     similar_games.cover.*,
     platforms.name,
     collection.id,
     videos.*,
+    external_games.category,
+    external_games.uid,
     genres.*;
 where version_parent = null;
 ";
@@ -119,6 +125,13 @@ struct IgdbVideo {
     video_id: String,
 }
 
+// DEV: The category of the stores that games are sold in, 1 is Steam
+#[derive(Serialize, Deserialize, Debug)]
+struct IgdbExternalGame {
+    category: Option<i32>,
+    uid: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct IgdbInvolvedCompany {
     id: i32,
@@ -139,7 +152,9 @@ struct IgdbImage {
 struct IgdbItemResponse {
     id: i32,
     name: Option<String>,
+    slug: Option<String>,
     rating: Option<Decimal>,
+    rating_count: Option<i32>,
     games: Option<Vec<IgdbItemResponse>>,
     summary: Option<String>,
     cover: Option<IgdbImage>,
@@ -153,10 +168,17 @@ struct IgdbItemResponse {
     similar_games: Option<Vec<IgdbItemResponse>>,
     version_parent: Option<i32>,
     collection: Option<IdObject>,
+    external_games: Option<Vec<IgdbExternalGame>>,
     #[serde(flatten)]
     rest_data: Option<HashMap<String, Value>>,
 }
 
+/// Game pages are found by the slug sent by IGDB, which can not be derived from
+/// the title.
+pub fn metadata_url(_lot: MediaLot, _identifier: &str, slug: &str) -> Option<String> {
+    Some(format!("https://www.igdb.com/games/{slug}"))
+}
+
 #[derive(Debug, Clone)]
 pub struct IgdbService {
     image_url: String,
@@ -211,7 +233,8 @@ offset: {offset};
             .body(req_body)
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let details: Vec<IgdbItemResponse> = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = details
             .into_iter()
@@ -249,7 +272,8 @@ where id = {id};
             .body(req_body)
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json::<Vec<_>>()
             .await
             .map_err(|e| anyhow!(e))?
@@ -314,7 +338,8 @@ offset: {offset};
             .body(req_body)
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let details: Vec<IgdbCompany> = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = details
             .into_iter()
@@ -356,7 +381,8 @@ where id = {id};
             .body(req_body)
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let mut details: Vec<IgdbInvolvedCompany> = rsp.json().await.map_err(|e| anyhow!(e))?;
         let detail = details
             .pop()
@@ -435,7 +461,8 @@ where id = {id};
             .body(req_body)
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         ryot_log!(debug, "Response = {:?}", rsp);
         let mut details: Vec<IgdbItemResponse> = rsp.json().await.map_err(|e| anyhow!(e))?;
         let detail = details.pop().unwrap();
@@ -463,7 +490,8 @@ where id = {id};
             .body(count_req_body)
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
 
         let search_count_resp: Vec<IgdbItemResponse> = rsp.json().await.map_err(|e| anyhow!(e))?;
 
@@ -485,7 +513,8 @@ offset: {offset};
             .body(req_body)
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
 
         let search: Vec<IgdbItemResponse> = rsp.json().await.map_err(|e| anyhow!(e))?;
 
@@ -610,6 +639,12 @@ impl IgdbService {
                 source: MetadataVideoSource::Youtube,
             })
             .collect_vec();
+        let steam_app_id = item
+            .external_games
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|g| g.category == Some(1))
+            .find_map(|g| g.uid?.parse().ok());
         MetadataDetails {
             identifier: item.id.to_string(),
             lot: MediaLot::VideoGame,
@@ -635,6 +670,7 @@ impl IgdbService {
                     .into_iter()
                     .map(|p| p.name)
                     .collect(),
+                slug: item.slug,
             }),
             suggestions: item
                 .similar_games
@@ -650,6 +686,11 @@ impl IgdbService {
                 })
                 .collect(),
             provider_rating: item.rating,
+            provider_rating_count: item.rating_count,
+            external_identifiers: Some(ExternalIdentifiers {
+                steam_app_id,
+                ..Default::default()
+            }),
             ..Default::default()
         }
     }
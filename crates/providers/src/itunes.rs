@@ -8,16 +8,24 @@ use dependent_models::SearchResults;
 use enums::{MediaLot, MediaSource};
 use itertools::Itertools;
 use media_models::{
-    MetadataDetails, MetadataFreeCreator, MetadataImageForMediaDetails, MetadataSearchItem,
-    PodcastEpisode, PodcastSpecifics,
+    MediaDuration, MetadataDetails, MetadataFreeCreator, MetadataImageForMediaDetails,
+    MetadataSearchItem, PodcastEpisode, PodcastSpecifics,
 };
 use reqwest::Client;
 use sea_orm::prelude::ChronoDateTimeUtc;
 use serde::{Deserialize, Serialize};
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://itunes.apple.com";
 
+pub fn metadata_url(_lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    Some(format!(
+        "https://podcasts.apple.com/us/podcast/{slug}/id{identifier}"
+    ))
+}
+
 #[derive(Debug, Clone)]
 pub struct ITunesService {
     client: Client,
@@ -90,7 +98,8 @@ impl MediaProvider for ITunesService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let details: SearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let ht = details.results.unwrap()[0].clone();
         let description = ht.description.clone();
@@ -126,7 +135,8 @@ impl MediaProvider for ITunesService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let url_images = details
             .image
             .into_iter()
@@ -138,21 +148,7 @@ impl MediaProvider for ITunesService {
             .last()
             .and_then(|e| e.release_date.to_owned())
             .map(|d| d.date_naive());
-        let mut episodes = episodes
-            .into_iter()
-            .enumerate()
-            .rev()
-            .map(|(idx, e)| PodcastEpisode {
-                number: i32::try_from(idx).unwrap() + 1,
-                id: e.track_id.unwrap().to_string(),
-                runtime: e.track_time_millis.map(|t| t / 1000 / 60),
-                overview: e.description,
-                title: e.track_name.unwrap(),
-                publish_date: e.release_date.map(|d| d.date_naive()).unwrap(),
-                thumbnail: e.artwork_url_60,
-            })
-            .collect_vec();
-        episodes.reverse();
+        let episodes = podcast_episodes(episodes);
         Ok(MetadataDetails {
             identifier: details.identifier,
             title: details.title,
@@ -190,7 +186,8 @@ impl MediaProvider for ITunesService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: SearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .results
@@ -244,3 +241,63 @@ fn get_search_response(item: ITunesItem) -> MetadataSearchItem {
         publish_year,
     }
 }
+
+/// The lengths of episodes are reported in milliseconds.
+fn podcast_episodes(results: Vec<ITunesItem>) -> Vec<PodcastEpisode> {
+    let mut episodes = results
+        .into_iter()
+        .enumerate()
+        .rev()
+        .map(|(idx, e)| PodcastEpisode {
+            number: i32::try_from(idx).unwrap() + 1,
+            id: e.track_id.unwrap().to_string(),
+            runtime: e
+                .track_time_millis
+                .map(|t| MediaDuration::from_milliseconds(t.into())),
+            overview: e.description,
+            title: e.track_name.unwrap(),
+            publish_date: e.release_date.map(|d| d.date_naive()).unwrap(),
+            thumbnail: e.artwork_url_60,
+        })
+        .collect_vec();
+    episodes.reverse();
+    episodes
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn podcast_episodes_convert_milliseconds_to_minutes() {
+        let results: Vec<ITunesItem> = serde_json::from_value(json!([
+            {
+                "collectionId": 1,
+                "collectionName": "Podcast",
+                "trackId": 10,
+                "trackName": "Newest",
+                "releaseDate": "2024-02-01T00:00:00Z",
+                "trackTimeMillis": 2_730_000
+            },
+            {
+                "collectionId": 1,
+                "collectionName": "Podcast",
+                "trackId": 11,
+                "trackName": "Oldest",
+                "releaseDate": "2024-01-01T00:00:00Z",
+                "trackTimeMillis": 29_499
+            }
+        ]))
+        .unwrap();
+        let episodes = podcast_episodes(results);
+        assert_eq!(
+            episodes
+                .iter()
+                .map(|e| (e.number, e.id.as_str(), e.runtime.map(|r| r.minutes())))
+                .collect_vec(),
+            vec![(1, "10", Some(46)), (2, "11", Some(0))]
+        );
+    }
+}
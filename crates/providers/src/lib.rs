@@ -1,5 +1,6 @@
 pub mod anilist;
 pub mod audible;
+pub mod circuit_breaker;
 pub mod google_books;
 pub mod igdb;
 pub mod itunes;
@@ -7,5 +8,268 @@ pub mod listennotes;
 pub mod mal;
 pub mod manga_updates;
 pub mod openlibrary;
+pub mod pagination;
+pub mod progress;
 pub mod tmdb;
 pub mod vndb;
+
+use enums::{MediaLot, MediaSource};
+
+/// The page of a media item on the website of the provider it was fetched from.
+/// Each provider keeps its own url template, `None` is returned when there is no
+/// page to link to. `provider_slug` is the slug stored from the provider, for
+/// the ones whose pages can not be found by a slug of the title.
+pub fn metadata_source_url(
+    source: MediaSource,
+    lot: MediaLot,
+    identifier: &str,
+    title: &str,
+    provider_slug: Option<&str>,
+) -> Option<String> {
+    let slug = slug::slugify(title);
+    match source {
+        MediaSource::Anilist => anilist::metadata_url(lot, identifier, &slug),
+        MediaSource::Audible => audible::metadata_url(lot, identifier, &slug),
+        MediaSource::GoogleBooks => google_books::metadata_url(lot, identifier, &slug),
+        MediaSource::Igdb => provider_slug.and_then(|s| igdb::metadata_url(lot, identifier, s)),
+        MediaSource::Itunes => itunes::metadata_url(lot, identifier, &slug),
+        MediaSource::Listennotes => listennotes::metadata_url(lot, identifier, &slug),
+        MediaSource::Mal => mal::metadata_url(lot, identifier, &slug),
+        MediaSource::Openlibrary => openlibrary::metadata_url(lot, identifier, &slug),
+        MediaSource::Tmdb => tmdb::metadata_url(lot, identifier, &slug),
+        MediaSource::Vndb => vndb::metadata_url(lot, identifier, &slug),
+        // DEV: The url is not derivable from the identifier, it is stored in the specifics
+        MediaSource::MangaUpdates | MediaSource::Custom => None,
+    }
+}
+
+/// The providers whose data is shown for a media item, the one it was fetched
+/// from first. Custom media is not attributed to anyone.
+pub fn attribution(
+    source: MediaSource,
+    contributors: impl IntoIterator<Item = MediaSource>,
+) -> Vec<MediaSource> {
+    let mut sources = vec![];
+    for source in std::iter::once(source).chain(contributors) {
+        if source != MediaSource::Custom && !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    sources
+}
+
+/// The page of a person on the website of the provider it was fetched from.
+pub fn person_source_url(source: MediaSource, identifier: &str, name: &str) -> Option<String> {
+    let slug = slug::slugify(name);
+    match source {
+        MediaSource::Audible => Some(format!(
+            "https://www.audible.com/author/{slug}/{identifier}"
+        )),
+        MediaSource::Openlibrary => Some(format!(
+            "https://openlibrary.org/authors/{identifier}/{slug}"
+        )),
+        MediaSource::Tmdb => Some(format!(
+            "https://www.themoviedb.org/person/{identifier}-{slug}"
+        )),
+        MediaSource::Igdb => Some(format!("https://www.igdb.com/companies/{slug}")),
+        MediaSource::Custom
+        | MediaSource::Anilist
+        | MediaSource::Listennotes
+        | MediaSource::Itunes
+        | MediaSource::MangaUpdates
+        | MediaSource::Mal
+        | MediaSource::Vndb
+        | MediaSource::GoogleBooks => None,
+    }
+}
+
+/// The page of a group of media, like a series or a collection of movies, on the
+/// website of the provider it was fetched from.
+pub fn metadata_group_source_url(
+    source: MediaSource,
+    identifier: &str,
+    title: &str,
+) -> Option<String> {
+    let slug = slug::slugify(title);
+    match source {
+        MediaSource::Audible => Some(format!(
+            "https://www.audible.com/series/{slug}/{identifier}"
+        )),
+        MediaSource::Tmdb => Some(format!(
+            "https://www.themoviedb.org/collections/{identifier}-{slug}"
+        )),
+        MediaSource::Igdb => Some(format!("https://www.igdb.com/collection/{slug}")),
+        MediaSource::Custom
+        | MediaSource::Anilist
+        | MediaSource::Listennotes
+        | MediaSource::Itunes
+        | MediaSource::MangaUpdates
+        | MediaSource::Mal
+        | MediaSource::Openlibrary
+        | MediaSource::Vndb
+        | MediaSource::GoogleBooks => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Iterable;
+
+    use super::*;
+
+    #[test]
+    fn metadata_source_url_uses_the_template_of_each_provider() {
+        let url = |source, lot, identifier| {
+            metadata_source_url(source, lot, identifier, "The Title", None)
+        };
+        assert_eq!(
+            url(MediaSource::Tmdb, MediaLot::Movie, "603"),
+            Some("https://www.themoviedb.org/movie/603-the-title".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Tmdb, MediaLot::Show, "1399"),
+            Some("https://www.themoviedb.org/tv/1399-the-title".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Anilist, MediaLot::Manga, "30013"),
+            Some("https://anilist.co/manga/30013/the-title".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Mal, MediaLot::Anime, "1"),
+            Some("https://myanimelist.net/anime/1/the-title".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Audible, MediaLot::AudioBook, "B0036I54I6"),
+            Some("https://www.audible.com/pd/the-title/B0036I54I6".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::GoogleBooks, MediaLot::Book, "abc"),
+            Some("https://www.google.co.in/books/edition/the-title/abc".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Openlibrary, MediaLot::Book, "OL1W"),
+            Some("https://openlibrary.org/works/OL1W/the-title".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Itunes, MediaLot::Podcast, "123"),
+            Some("https://podcasts.apple.com/us/podcast/the-title/id123".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Listennotes, MediaLot::Podcast, "abc"),
+            Some("https://www.listennotes.com/podcasts/the-title-abc".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Vndb, MediaLot::VisualNovel, "v17"),
+            Some("https://vndb.org/v17".to_owned())
+        );
+    }
+
+    #[test]
+    fn metadata_source_url_is_none_when_it_can_not_be_built() {
+        let url = |source, lot| metadata_source_url(source, lot, "1", "The Title", None);
+        assert_eq!(url(MediaSource::Tmdb, MediaLot::Book), None);
+        assert_eq!(url(MediaSource::Anilist, MediaLot::Movie), None);
+        assert_eq!(url(MediaSource::MangaUpdates, MediaLot::Manga), None);
+        assert_eq!(url(MediaSource::Custom, MediaLot::Movie), None);
+        assert_eq!(url(MediaSource::Igdb, MediaLot::VideoGame), None);
+    }
+
+    #[test]
+    fn metadata_source_url_uses_the_stored_igdb_slug() {
+        assert_eq!(
+            metadata_source_url(
+                MediaSource::Igdb,
+                MediaLot::VideoGame,
+                "1942",
+                "The Witcher 3: Wild Hunt",
+                Some("the-witcher-3-wild-hunt--1")
+            ),
+            Some("https://www.igdb.com/games/the-witcher-3-wild-hunt--1".to_owned())
+        );
+    }
+
+    #[test]
+    fn person_source_url_uses_the_template_of_each_provider() {
+        let url = |source| person_source_url(source, "42", "Jane Doe");
+        assert_eq!(
+            url(MediaSource::Audible),
+            Some("https://www.audible.com/author/jane-doe/42".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Openlibrary),
+            Some("https://openlibrary.org/authors/42/jane-doe".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Tmdb),
+            Some("https://www.themoviedb.org/person/42-jane-doe".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Igdb),
+            Some("https://www.igdb.com/companies/jane-doe".to_owned())
+        );
+        let linked = [
+            MediaSource::Audible,
+            MediaSource::Openlibrary,
+            MediaSource::Tmdb,
+            MediaSource::Igdb,
+        ];
+        for source in MediaSource::iter().filter(|s| !linked.contains(s)) {
+            assert_eq!(url(source), None, "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn metadata_group_source_url_uses_the_template_of_each_provider() {
+        let url = |source| metadata_group_source_url(source, "10", "Star Wars Collection");
+        assert_eq!(
+            url(MediaSource::Audible),
+            Some("https://www.audible.com/series/star-wars-collection/10".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Tmdb),
+            Some("https://www.themoviedb.org/collections/10-star-wars-collection".to_owned())
+        );
+        assert_eq!(
+            url(MediaSource::Igdb),
+            Some("https://www.igdb.com/collection/star-wars-collection".to_owned())
+        );
+        let linked = [MediaSource::Audible, MediaSource::Tmdb, MediaSource::Igdb];
+        for source in MediaSource::iter().filter(|s| !linked.contains(s)) {
+            assert_eq!(url(source), None, "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn source_urls_are_none_for_custom_media() {
+        assert_eq!(
+            metadata_source_url(MediaSource::Custom, MediaLot::Book, "1", "Title", None),
+            None
+        );
+        assert_eq!(person_source_url(MediaSource::Custom, "1", "Name"), None);
+        assert_eq!(
+            metadata_group_source_url(MediaSource::Custom, "1", "Title"),
+            None
+        );
+    }
+
+    #[test]
+    fn attribution_lists_each_source_once_with_the_main_one_first() {
+        assert_eq!(
+            attribution(
+                MediaSource::Audible,
+                [
+                    MediaSource::Openlibrary,
+                    MediaSource::Audible,
+                    MediaSource::Custom,
+                    MediaSource::Openlibrary
+                ]
+            ),
+            vec![MediaSource::Audible, MediaSource::Openlibrary]
+        );
+        assert_eq!(attribution(MediaSource::Custom, []), vec![]);
+        assert_eq!(
+            attribution(MediaSource::Custom, [MediaSource::Tmdb]),
+            vec![MediaSource::Tmdb]
+        );
+    }
+}
@@ -10,8 +10,8 @@ use dependent_models::SearchResults;
 use enums::{MediaLot, MediaSource};
 use itertools::Itertools;
 use media_models::{
-    MetadataDetails, MetadataFreeCreator, MetadataImageForMediaDetails, MetadataSearchItem,
-    PartialMetadataWithoutId, PodcastEpisode, PodcastSpecifics,
+    MediaDuration, MetadataDetails, MetadataFreeCreator, MetadataImageForMediaDetails,
+    MetadataSearchItem, PartialMetadataWithoutId, PodcastEpisode, PodcastSpecifics,
 };
 use reqwest::{
     header::{HeaderName, HeaderValue},
@@ -24,14 +24,26 @@ use serde_json::json;
 use serde_with::{formats::Flexible, serde_as, TimestampMilliSeconds};
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::{
+    circuit_breaker::check_retry_after, pagination::PaginationGuard, progress::report_page_progress,
+};
+
 static URL: &str = "https://listen-api.listennotes.com/api/v2";
 static FILE: &str = "listennotes.json";
+const MAX_EPISODE_PAGES: usize = 500;
+const MAX_EPISODES: usize = 10_000;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct Settings {
     genres: HashMap<i32, String>,
 }
 
+pub fn metadata_url(_lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    Some(format!(
+        "https://www.listennotes.com/podcasts/{slug}-{identifier}"
+    ))
+}
+
 #[derive(Debug, Clone)]
 pub struct ListennotesService {
     url: String,
@@ -66,10 +78,10 @@ impl ListennotesService {
 
 #[async_trait]
 impl MediaProvider for ListennotesService {
-    async fn metadata_details(&self, identifier: &str) -> Result<MetadataDetails> {
-        let mut details = self
-            .details_with_paginated_episodes(identifier, None, None)
-            .await?;
+    async fn metadata_suggestions(
+        &self,
+        identifier: &str,
+    ) -> Result<Vec<PartialMetadataWithoutId>> {
         #[derive(Serialize, Deserialize, Debug)]
         struct Recommendation {
             id: String,
@@ -88,11 +100,12 @@ impl MediaProvider for ListennotesService {
             ))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
-        details.suggestions = rec_data
+        Ok(rec_data
             .recommendations
             .into_iter()
             .map(|r| PartialMetadataWithoutId {
@@ -103,14 +116,43 @@ impl MediaProvider for ListennotesService {
                 source: MediaSource::Listennotes,
                 is_recommendation: None,
             })
-            .collect();
+            .collect())
+    }
+
+    async fn get_recommendations_for_metadata(
+        &self,
+        identifier: &str,
+    ) -> Result<Vec<PartialMetadataWithoutId>> {
+        self.metadata_suggestions(identifier).await
+    }
 
+    async fn metadata_details(&self, identifier: &str) -> Result<MetadataDetails> {
+        let mut details = self
+            .details_with_paginated_episodes(identifier, None, None)
+            .await?;
         if let Some(ref mut specifics) = details.podcast_specifics {
+            let page_size = specifics.episodes.len().max(1);
+            let total_pages = specifics.total_episodes.div_ceil(page_size).max(1);
+            let mut fetched_pages = 1;
+            report_page_progress(
+                MediaSource::Listennotes,
+                identifier,
+                fetched_pages,
+                Some(total_pages),
+            );
+            let mut guard = PaginationGuard::new(MAX_EPISODE_PAGES, MAX_EPISODES).keep_partial();
             loop {
                 if specifics.total_episodes > specifics.episodes.len() {
-                    let last_episode = specifics.episodes.last().unwrap();
+                    let Some(last_episode) = specifics.episodes.last() else {
+                        break;
+                    };
                     let next_pub_date = last_episode.publish_date;
                     let episode_number = last_episode.number;
+                    if !guard
+                        .next_page((next_pub_date, episode_number), specifics.episodes.len())?
+                    {
+                        break;
+                    }
                     let new_details = self
                         .details_with_paginated_episodes(
                             identifier,
@@ -121,6 +163,13 @@ impl MediaProvider for ListennotesService {
                     if let Some(p) = new_details.podcast_specifics {
                         specifics.episodes.extend(p.episodes);
                     }
+                    fetched_pages += 1;
+                    report_page_progress(
+                        MediaSource::Listennotes,
+                        identifier,
+                        fetched_pages,
+                        Some(total_pages.max(fetched_pages)),
+                    );
                 } else {
                     break;
                 }
@@ -162,7 +211,8 @@ impl MediaProvider for ListennotesService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
 
         let search: SearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let total = search.total;
@@ -185,6 +235,30 @@ impl MediaProvider for ListennotesService {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct ListennotesEpisode {
+    #[serde(flatten)]
+    episode: PodcastEpisode,
+    audio_length_sec: Option<i64>,
+}
+
+/// Number the episodes of a page that follows the episode `episode_number`. Their
+/// lengths are reported in seconds.
+fn podcast_episodes(
+    episodes: Vec<ListennotesEpisode>,
+    episode_number: Option<i32>,
+) -> Vec<PodcastEpisode> {
+    episodes
+        .into_iter()
+        .enumerate()
+        .map(|(idx, e)| PodcastEpisode {
+            number: (episode_number.unwrap_or_default() + idx as i32 + 1),
+            runtime: e.audio_length_sec.map(MediaDuration::from_seconds),
+            ..e.episode
+        })
+        .collect()
+}
+
 impl ListennotesService {
     // The API does not return all the episodes for a podcast, and instead needs to be
     // paginated through. It also does not return the episode number. So we have to
@@ -208,7 +282,7 @@ impl ListennotesService {
             publish_date: Option<DateTimeUtc>,
             publisher: Option<String>,
             image: Option<String>,
-            episodes: Vec<PodcastEpisode>,
+            episodes: Vec<ListennotesEpisode>,
             genre_ids: Vec<i32>,
             total_episodes: usize,
         }
@@ -221,7 +295,8 @@ impl ListennotesService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let podcast_data: Podcast = rsp.json().await.map_err(|e| anyhow!(e))?;
         Ok(MetadataDetails {
             identifier: podcast_data.id,
@@ -249,16 +324,7 @@ impl ListennotesService {
             publish_year: podcast_data.publish_date.map(|r| r.year()),
             publish_date: podcast_data.publish_date.map(|d| d.date_naive()),
             podcast_specifics: Some(PodcastSpecifics {
-                episodes: podcast_data
-                    .episodes
-                    .into_iter()
-                    .enumerate()
-                    .map(|(idx, episode)| PodcastEpisode {
-                        number: (episode_number.unwrap_or_default() + idx as i32 + 1),
-                        runtime: episode.runtime.map(|r| r / 60), // the api responds in seconds
-                        ..episode
-                    })
-                    .collect(),
+                episodes: podcast_episodes(podcast_data.episodes, episode_number),
                 total_episodes: podcast_data.total_episodes,
             }),
             provider_rating: podcast_data.listen_score,
@@ -300,3 +366,40 @@ async fn get_client_config(api_token: &str) -> (Client, Settings) {
     };
     (client, settings)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn podcast_episodes_convert_seconds_to_minutes() {
+        let episodes: Vec<ListennotesEpisode> = serde_json::from_value(json!([
+            {
+                "id": "a",
+                "title": "First",
+                "pub_date_ms": 1_700_000_000_000_u64,
+                "audio_length_sec": 3599
+            },
+            {
+                "id": "b",
+                "title": "Second",
+                "pub_date_ms": 1_700_100_000_000_u64,
+                "audio_length_sec": 89
+            },
+            {
+                "id": "c",
+                "title": "Third",
+                "pub_date_ms": 1_700_200_000_000_u64
+            }
+        ]))
+        .unwrap();
+        let episodes = podcast_episodes(episodes, Some(10));
+        assert_eq!(
+            episodes
+                .iter()
+                .map(|e| (e.number, e.runtime.map(|r| r.minutes())))
+                .collect_vec(),
+            vec![(11, Some(60)), (12, Some(1)), (13, None)]
+        );
+    }
+}
@@ -20,8 +20,19 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://api.myanimelist.net/v2";
 
+pub fn metadata_url(lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    let bw = match lot {
+        MediaLot::Anime => "anime",
+        MediaLot::Manga => "manga",
+        _ => return None,
+    };
+    Some(format!("https://myanimelist.net/{bw}/{identifier}/{slug}"))
+}
+
 #[derive(Debug, Clone)]
 pub struct MalService {
     client: Client,
@@ -148,7 +159,8 @@ async fn search(
         .query(&json!({ "q": q, "limit": PAGE_SIZE, "offset": offset, "fields": "start_date" }))
         .send()
         .await
-        .map_err(|e| anyhow!(e))?
+        .map_err(|e| anyhow!(e))
+        .and_then(check_retry_after)?
         .json()
         .await
         .map_err(|e| anyhow!(e))?;
@@ -181,6 +193,7 @@ struct ItemNode {
     studios: Option<Vec<NamedObject>>,
     start_date: Option<String>,
     mean: Option<Decimal>,
+    num_scoring_users: Option<i32>,
     status: Option<String>,
     num_episodes: Option<i32>,
     num_chapters: Option<i32>,
@@ -198,10 +211,11 @@ struct ItemData {
 async fn details(client: &Client, media_type: &str, id: &str) -> Result<MetadataDetails> {
     let details: ItemNode = client
         .get(format!("{}/{}/{}", URL, media_type, id))
-        .query(&json!({ "fields": "start_date,end_date,synopsis,genres,status,num_episodes,num_volumes,num_chapters,recommendations,related_manga,related_anime,mean,nsfw" }))
+        .query(&json!({ "fields": "start_date,end_date,synopsis,genres,status,num_episodes,num_volumes,num_chapters,recommendations,related_manga,related_anime,mean,num_scoring_users,nsfw" }))
         .send()
         .await
-        .map_err(|e| anyhow!(e))?
+        .map_err(|e| anyhow!(e))
+        .and_then(check_retry_after)?
         .json()
         .await
         .map_err(|e| anyhow!(e))?;
@@ -280,6 +294,7 @@ async fn details(client: &Client, media_type: &str, id: &str) -> Result<Metadata
         publish_date: details.start_date.and_then(|d| convert_string_to_date(&d)),
         suggestions,
         provider_rating: details.mean,
+        provider_rating_count: details.num_scoring_users,
         anime_specifics,
         manga_specifics,
         ..Default::default()
@@ -17,6 +17,8 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://api.mangaupdates.com/v1";
 
 #[derive(Debug, Clone)]
@@ -183,7 +185,8 @@ impl MediaProvider for MangaUpdatesService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -220,7 +223,8 @@ impl MediaProvider for MangaUpdatesService {
             .get(format!("{}/authors/{}", URL, identity))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -230,7 +234,8 @@ impl MediaProvider for MangaUpdatesService {
             .json(&serde_json::json!({ "orderby": "year" }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -279,7 +284,8 @@ impl MediaProvider for MangaUpdatesService {
             .get(format!("{}/series/{}", URL, identifier))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -316,7 +322,8 @@ impl MediaProvider for MangaUpdatesService {
                 .get(format!("{}/series/{}", URL, series_id))
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json::<MetadataItemRecord>()
                 .await
             {
@@ -386,7 +393,8 @@ impl MediaProvider for MangaUpdatesService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
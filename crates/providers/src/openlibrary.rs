@@ -11,7 +11,7 @@ use itertools::Itertools;
 use media_models::{
     BookSpecifics, MetadataDetails, MetadataImageForMediaDetails, MetadataPerson,
     MetadataPersonRelated, MetadataSearchItem, PartialMetadataPerson, PartialMetadataWithoutId,
-    PeopleSearchItem, PersonSourceSpecifics,
+    PeopleSearchItem, PersonSourceSpecifics, SeenEdition,
 };
 use reqwest::Client;
 use scraper::{Html, Selector};
@@ -19,6 +19,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://openlibrary.org";
 static IMAGE_BASE_URL: &str = "https://covers.openlibrary.org";
 
@@ -34,6 +36,7 @@ struct OpenlibraryEdition {
     publish_date: Option<String>,
     number_of_pages: Option<i32>,
     covers: Option<Vec<i64>>,
+    isbn_13: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -71,6 +74,10 @@ struct OpenlibraryKey {
     key: String,
 }
 
+pub fn metadata_url(_lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    Some(format!("https://openlibrary.org/works/{identifier}/{slug}"))
+}
+
 #[derive(Debug, Clone)]
 pub struct OpenlibraryService {
     image_url: String,
@@ -187,7 +194,8 @@ impl MediaProvider for OpenlibraryService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: OpenAuthorLibrarySearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .docs
@@ -223,7 +231,8 @@ impl MediaProvider for OpenlibraryService {
             .get(format!("{}/authors/{}.json", URL, identity))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let data: PersonDetailsOpenlibraryAuthor = rsp.json().await.map_err(|e| anyhow!(e))?;
         let identifier = get_key(&data.key);
         let description = data.bio.map(|d| match d {
@@ -244,7 +253,8 @@ impl MediaProvider for OpenlibraryService {
             .query(&serde_json::json!({ "limit": 600 }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -299,7 +309,8 @@ impl MediaProvider for OpenlibraryService {
             .get(format!("{}/works/{}.json", URL, identifier))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
 
         ryot_log!(debug, "Getting work details.");
         let data: MetadataDetailsOpenlibraryBook = rsp.json().await.map_err(|e| anyhow!(e))?;
@@ -311,7 +322,8 @@ impl MediaProvider for OpenlibraryService {
             .get(format!("{}/works/{}/editions.json", URL, identifier))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let editions: OpenlibraryEditionsResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
 
         let entries = editions.entries.unwrap_or_default();
@@ -398,7 +410,8 @@ impl MediaProvider for OpenlibraryService {
             .query(&json!({ "workid": identifier, "_component": "RelatedWorkCarousel" }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json::<OpenlibraryPartialResponse>()
             .await
             .map_err(|e| anyhow!(e))?
@@ -493,7 +506,8 @@ impl MediaProvider for OpenlibraryService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: OpenMediaLibrarySearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .docs
@@ -544,6 +558,26 @@ impl MediaProvider for OpenlibraryService {
 }
 
 impl OpenlibraryService {
+    /// All the editions of a work that the user can pick from when logging it.
+    pub async fn editions(&self, identifier: &str) -> Result<Vec<SeenEdition>> {
+        let rsp = self
+            .client
+            .get(format!("{}/works/{}/editions.json", URL, identifier))
+            .query(&json!({ "limit": 100 }))
+            .send()
+            .await
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
+        let editions: OpenlibraryEditionsResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
+        let editions = editions
+            .entries
+            .unwrap_or_default()
+            .into_iter()
+            .map(seen_edition)
+            .collect_vec();
+        Ok(editions)
+    }
+
     fn get_book_cover_image_url(&self, c: i64) -> String {
         self.get_cover_image_url("b", c)
     }
@@ -582,3 +616,84 @@ pub fn get_key(key: &str) -> String {
 fn parse_date(date_str: &str) -> Option<NaiveDate> {
     NaiveDate::parse_from_str(date_str, "%e %B %Y").ok()
 }
+
+fn seen_edition(edition: OpenlibraryEdition) -> SeenEdition {
+    // DEV: Most editions only have a year or a month and year as their publish date
+    let year = edition.publish_date.and_then(|d| {
+        OpenlibraryService::parse_date(&d)
+            .map(|d| d.year())
+            .or_else(|| {
+                d.split(|c: char| !c.is_ascii_digit())
+                    .find(|p| p.len() == 4)
+                    .and_then(|y| y.parse().ok())
+            })
+    });
+    SeenEdition {
+        year,
+        name: edition.title,
+        pages: edition.number_of_pages,
+        identifier: edition
+            .isbn_13
+            .and_then(|i| i.into_iter().next())
+            .or_else(|| Some(get_key(&edition.key))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editions_can_be_picked_from_the_provider() {
+        let response: OpenlibraryEditionsResponse = serde_json::from_value(json!({
+            "entries": [
+                {
+                    "key": "/books/OL1M",
+                    "title": "Dune",
+                    "publish_date": "1965",
+                    "number_of_pages": 412,
+                    "isbn_13": ["9780441013593"]
+                },
+                {
+                    "key": "/books/OL2M",
+                    "title": "Dune (Annotated)",
+                    "publish_date": "Oct 05, 2021"
+                },
+                {
+                    "key": "/books/OL3M",
+                    "publish_date": "March 1990"
+                }
+            ]
+        }))
+        .unwrap();
+        let editions = response
+            .entries
+            .unwrap()
+            .into_iter()
+            .map(seen_edition)
+            .collect_vec();
+        assert_eq!(
+            editions,
+            vec![
+                SeenEdition {
+                    name: Some("Dune".to_owned()),
+                    year: Some(1965),
+                    identifier: Some("9780441013593".to_owned()),
+                    pages: Some(412),
+                },
+                SeenEdition {
+                    name: Some("Dune (Annotated)".to_owned()),
+                    year: Some(2021),
+                    identifier: Some("OL2M".to_owned()),
+                    pages: None,
+                },
+                SeenEdition {
+                    name: None,
+                    year: Some(1990),
+                    identifier: Some("OL3M".to_owned()),
+                    pages: None,
+                },
+            ]
+        );
+    }
+}
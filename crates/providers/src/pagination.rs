@@ -0,0 +1,152 @@
+use std::fmt;
+
+use common_utils::ryot_log;
+
+/// Which limit of a `PaginationGuard` stopped a loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaginationLimit {
+    /// More pages were requested than allowed.
+    Pages,
+    /// The last item did not change from one page to the next, so the provider
+    /// keeps returning the same page.
+    StalledCursor,
+    /// More items were collected than allowed.
+    Items,
+}
+
+/// The error returned when a guard stops a loop, with how far it got.
+#[derive(Debug, Clone)]
+pub struct PaginationStopped {
+    pub limit: PaginationLimit,
+    pub pages: usize,
+    pub items: usize,
+}
+
+impl fmt::Display for PaginationStopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.limit {
+            PaginationLimit::Pages => "too many pages",
+            PaginationLimit::StalledCursor => "the provider returned the same page again",
+            PaginationLimit::Items => "too many items",
+        };
+        write!(
+            f,
+            "Stopped fetching after {} pages and {} items: {}",
+            self.pages, self.items, reason
+        )
+    }
+}
+
+impl std::error::Error for PaginationStopped {}
+
+/// Bounds a loop that fetches a provider response page by page, so that a
+/// provider that keeps returning pages can not keep it running forever.
+#[derive(Debug)]
+pub struct PaginationGuard<C> {
+    max_pages: usize,
+    max_items: usize,
+    keep_partial: bool,
+    pages: usize,
+    last_cursor: Option<C>,
+}
+
+impl<C: PartialEq + fmt::Debug> PaginationGuard<C> {
+    pub fn new(max_pages: usize, max_items: usize) -> Self {
+        Self {
+            max_pages,
+            max_items,
+            keep_partial: false,
+            pages: 1,
+            last_cursor: None,
+        }
+    }
+
+    /// Stop the loop without an error when a limit is hit, so that the caller
+    /// keeps what was collected until then.
+    pub fn keep_partial(mut self) -> Self {
+        self.keep_partial = true;
+        self
+    }
+
+    /// Called before fetching every page after the first one with the last item
+    /// collected so far and the number of items. Returns whether the next page
+    /// should be fetched.
+    pub fn next_page(&mut self, cursor: C, items: usize) -> Result<bool, PaginationStopped> {
+        let limit = if items > self.max_items {
+            Some(PaginationLimit::Items)
+        } else if self.pages >= self.max_pages {
+            Some(PaginationLimit::Pages)
+        } else if self.last_cursor.as_ref() == Some(&cursor) {
+            Some(PaginationLimit::StalledCursor)
+        } else {
+            None
+        };
+        let Some(limit) = limit else {
+            self.pages += 1;
+            self.last_cursor = Some(cursor);
+            return Ok(true);
+        };
+        let stopped = PaginationStopped {
+            limit,
+            pages: self.pages,
+            items,
+        };
+        if !self.keep_partial {
+            return Err(stopped);
+        }
+        ryot_log!(
+            warn,
+            "{} at {:?}, keeping what was fetched",
+            stopped,
+            cursor
+        );
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_pages_while_the_cursor_moves() {
+        let mut guard = PaginationGuard::new(10, 100);
+        assert!(guard.next_page(1, 10).unwrap());
+        assert!(guard.next_page(2, 20).unwrap());
+        assert!(guard.next_page(3, 30).unwrap());
+    }
+
+    #[test]
+    fn stops_after_the_page_limit() {
+        let mut guard = PaginationGuard::new(2, 100);
+        assert!(guard.next_page(1, 10).unwrap());
+        let stopped = guard.next_page(2, 20).unwrap_err();
+        assert_eq!(stopped.limit, PaginationLimit::Pages);
+        assert_eq!(stopped.pages, 2);
+        assert_eq!(stopped.items, 20);
+    }
+
+    #[test]
+    fn stops_when_the_cursor_does_not_move() {
+        let mut guard = PaginationGuard::new(10, 100);
+        assert!(guard.next_page("a", 10).unwrap());
+        let stopped = guard.next_page("a", 20).unwrap_err();
+        assert_eq!(stopped.limit, PaginationLimit::StalledCursor);
+    }
+
+    #[test]
+    fn stops_after_the_item_limit() {
+        let mut guard = PaginationGuard::new(10, 15);
+        assert!(guard.next_page(1, 15).unwrap());
+        let stopped = guard.next_page(2, 16).unwrap_err();
+        assert_eq!(stopped.limit, PaginationLimit::Items);
+        assert_eq!(stopped.items, 16);
+    }
+
+    #[test]
+    fn keeps_partial_results_without_an_error() {
+        let mut guard = PaginationGuard::new(10, 100).keep_partial();
+        assert!(guard.next_page(1, 10).unwrap());
+        assert!(!guard.next_page(1, 10).unwrap());
+    }
+}
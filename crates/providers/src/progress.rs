@@ -0,0 +1,53 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use enums::MediaSource;
+
+// DEV: Only entries that were explicitly tracked are updated, so that calls which
+// nobody is watching do not leave anything behind.
+static PAGES: LazyLock<Mutex<HashMap<(MediaSource, String), PageProgress>>> =
+    LazyLock::new(Default::default);
+
+/// How many of the pages (or seasons) of an entity have been fetched so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PageProgress {
+    pub fetched: usize,
+    pub total: Option<usize>,
+}
+
+/// Start recording the page progress of the next details call for an entity.
+pub fn track_page_progress(source: MediaSource, identifier: &str) {
+    PAGES
+        .lock()
+        .unwrap()
+        .insert((source, identifier.to_owned()), PageProgress::default());
+}
+
+pub fn report_page_progress(
+    source: MediaSource,
+    identifier: &str,
+    fetched: usize,
+    total: Option<usize>,
+) {
+    let mut pages = PAGES.lock().unwrap();
+    if let Some(progress) = pages.get_mut(&(source, identifier.to_owned())) {
+        *progress = PageProgress { fetched, total };
+    }
+}
+
+pub fn page_progress(source: MediaSource, identifier: &str) -> Option<PageProgress> {
+    PAGES
+        .lock()
+        .unwrap()
+        .get(&(source, identifier.to_owned()))
+        .copied()
+}
+
+pub fn untrack_page_progress(source: MediaSource, identifier: &str) {
+    PAGES
+        .lock()
+        .unwrap()
+        .remove(&(source, identifier.to_owned()));
+}
@@ -19,7 +19,7 @@ use enums::{MediaLot, MediaSource};
 use hashbag::HashBag;
 use itertools::Itertools;
 use media_models::{
-    ExternalIdentifiers, MetadataDetails, MetadataGroupSearchItem, MetadataImage,
+    ExternalIdentifiers, MediaDuration, MetadataDetails, MetadataGroupSearchItem, MetadataImage,
     MetadataImageForMediaDetails, MetadataPerson, MetadataPersonRelated, MetadataSearchItem,
     MetadataVideo, MetadataVideoSource, MovieSpecifics, PartialMetadataPerson,
     PartialMetadataWithoutId, PeopleSearchItem, PersonSourceSpecifics, ShowEpisode, ShowSeason,
@@ -36,8 +36,16 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::{
+    circuit_breaker::check_retry_after, pagination::PaginationGuard, progress::report_page_progress,
+};
+
 static URL: &str = "https://api.themoviedb.org/3";
 static FILE: &str = "tmdb.json";
+const MAX_SUGGESTION_PAGES: usize = 50;
+const MAX_SUGGESTIONS: usize = 1_000;
+const MAX_SEASONS: usize = 200;
+const MAX_EPISODES: usize = 20_000;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct Settings {
@@ -126,6 +134,7 @@ struct TmdbMediaEntry {
     title: Option<String>,
     adult: Option<bool>,
     vote_average: Option<Decimal>,
+    vote_count: Option<i32>,
     overview: Option<String>,
     poster_path: Option<String>,
     backdrop_path: Option<String>,
@@ -138,6 +147,26 @@ struct TmdbMediaEntry {
     genres: Option<Vec<NamedObject>>,
     belongs_to_collection: Option<IdObject>,
     videos: Option<TmdbVideoResults>,
+    keywords: Option<TmdbKeywords>,
+}
+
+// DEV: Movies return the keywords in `keywords` and shows in `results`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct TmdbKeywords {
+    keywords: Option<Vec<NamedObject>>,
+    results: Option<Vec<NamedObject>>,
+}
+
+impl TmdbKeywords {
+    fn names(self) -> Vec<String> {
+        self.keywords
+            .or(self.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|k| k.name)
+            .unique()
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -179,6 +208,17 @@ struct TmdbNonMediaEntity {
     place_of_birth: Option<String>,
 }
 
+pub fn metadata_url(lot: MediaLot, identifier: &str, slug: &str) -> Option<String> {
+    let bw = match lot {
+        MediaLot::Movie => "movie",
+        MediaLot::Show => "tv",
+        _ => return None,
+    };
+    Some(format!(
+        "https://www.themoviedb.org/{bw}/{identifier}-{slug}"
+    ))
+}
+
 #[derive(Debug, Clone)]
 pub struct TmdbService {
     client: Client,
@@ -213,7 +253,8 @@ impl TmdbService {
             .get(format!("{}/{}/{}/images", URL, type_, identifier))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let new_images: TmdbImagesResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         if let Some(imgs) = new_images.posters {
             for image in imgs {
@@ -249,6 +290,7 @@ impl TmdbService {
             _ => unreachable!(),
         };
         let mut suggestions = vec![];
+        let mut guard = PaginationGuard::new(MAX_SUGGESTION_PAGES, MAX_SUGGESTIONS).keep_partial();
         for page in 1.. {
             let new_recs: TmdbListResponse = self
                 .client
@@ -256,7 +298,8 @@ impl TmdbService {
                 .query(&json!({ "page": page }))
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json()
                 .await
                 .map_err(|e| anyhow!(e))?;
@@ -274,7 +317,9 @@ impl TmdbService {
                     is_recommendation: None,
                 });
             }
-            if new_recs.page >= new_recs.total_pages {
+            if new_recs.page >= new_recs.total_pages
+                || !guard.next_page(new_recs.page, suggestions.len())?
+            {
                 break;
             }
         }
@@ -292,7 +337,8 @@ impl TmdbService {
             .query(&json!({ "language": self.language }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -362,7 +408,8 @@ impl TmdbService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json::<TmdbChangesResponse>()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -379,7 +426,8 @@ impl TmdbService {
             .get(format!("{}/{}/{}/external_ids", URL, type_, identifier))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         rsp.json().await.map_err(|e| anyhow!(e))
     }
 }
@@ -444,7 +492,8 @@ impl MediaProvider for NonMediaTmdbService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: TmdbListResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .results
@@ -489,7 +538,8 @@ impl MediaProvider for NonMediaTmdbService {
             .query(&json!({ "language": self.base.language }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -512,7 +562,8 @@ impl MediaProvider for NonMediaTmdbService {
                 .query(&json!({ "language": self.base.language }))
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?
                 .json()
                 .await
                 .map_err(|e| anyhow!(e))?;
@@ -547,7 +598,8 @@ impl MediaProvider for NonMediaTmdbService {
                         )
                         .send()
                         .await
-                        .map_err(|e| anyhow!(e))?
+                        .map_err(|e| anyhow!(e))
+                        .and_then(check_retry_after)?
                         .json()
                         .await
                         .map_err(|e| anyhow!(e))?;
@@ -602,6 +654,20 @@ impl NonMediaTmdbService {
         external_id: &str,
         external_source: &str,
     ) -> Result<String> {
+        self.candidates_by_external_id(external_id, external_source)
+            .await?
+            .into_iter()
+            .next()
+            .map(|(_, id)| id)
+            .ok_or_else(|| anyhow!("No results found"))
+    }
+
+    /// Every movie and show that has the external id, movies first.
+    pub async fn candidates_by_external_id(
+        &self,
+        external_id: &str,
+        external_source: &str,
+    ) -> Result<Vec<(MediaLot, String)>> {
         let details: TmdbFindByExternalSourceResponse = self
             .base
             .client
@@ -609,17 +675,20 @@ impl NonMediaTmdbService {
             .query(&json!({ "language": self.base.language, "external_source": external_source }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
-        if !details.movie_results.is_empty() {
-            Ok(details.movie_results[0].id.to_string())
-        } else if !details.tv_results.is_empty() {
-            Ok(details.tv_results[0].id.to_string())
-        } else {
-            Err(anyhow!("No results found"))
-        }
+        let movies = details
+            .movie_results
+            .into_iter()
+            .map(|e| (MediaLot::Movie, e.id.to_string()));
+        let shows = details
+            .tv_results
+            .into_iter()
+            .map(|e| (MediaLot::Show, e.id.to_string()));
+        Ok(movies.chain(shows).collect_vec())
     }
 }
 
@@ -663,7 +732,8 @@ impl MediaProvider for TmdbMovieService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: TmdbListResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
 
         let resp = search
@@ -697,11 +767,12 @@ impl MediaProvider for TmdbMovieService {
             .get(format!("{}/movie/{}", URL, &identifier))
             .query(&json!({
                 "language": self.base.language,
-                "append_to_response": "videos",
+                "append_to_response": "videos,keywords",
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let data: TmdbMediaEntry = rsp.json().await.map_err(|e| anyhow!(e))?;
         let mut videos = vec![];
         if let Some(vid) = data.videos {
@@ -719,7 +790,8 @@ impl MediaProvider for TmdbMovieService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let credits: TmdbCreditsResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let mut people = vec![];
         people.extend(
@@ -809,6 +881,7 @@ impl MediaProvider for TmdbMovieService {
                 .into_iter()
                 .map(|g| g.name)
                 .collect(),
+            tags: data.keywords.unwrap_or_default().names(),
             people,
             url_images: image_ids
                 .into_iter()
@@ -825,18 +898,11 @@ impl MediaProvider for TmdbMovieService {
             publish_date: data.release_date.and_then(|r| convert_string_to_date(&r)),
             description: data.overview,
             movie_specifics: Some(MovieSpecifics {
-                runtime: data.runtime,
+                runtime: data.runtime.map(MediaDuration::from_minutes),
             }),
             suggestions,
-            provider_rating: if let Some(av) = data.vote_average {
-                if av != dec!(0) {
-                    Some(av * dec!(10))
-                } else {
-                    None
-                }
-            } else {
-                None
-            },
+            provider_rating: data.vote_average.filter(|av| *av != dec!(0)),
+            provider_rating_count: data.vote_count,
             group_identifiers: Vec::from_iter(data.belongs_to_collection)
                 .into_iter()
                 .map(|c| c.id.to_string())
@@ -872,7 +938,8 @@ impl MediaProvider for TmdbMovieService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: TmdbListResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .results
@@ -918,7 +985,8 @@ impl MediaProvider for TmdbMovieService {
             .query(&json!({ "language": self.base.language }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -956,6 +1024,7 @@ impl MediaProvider for TmdbMovieService {
                     .unique()
                     .map(|p| MetadataImage {
                         url: StoredUrl::Url(self.base.get_image_url(p)),
+                        ..Default::default()
                     })
                     .collect(),
                 lot: MediaLot::Movie,
@@ -1001,11 +1070,12 @@ impl MediaProvider for TmdbShowService {
             .get(format!("{}/tv/{}", URL, &identifier))
             .query(&json!({
                 "language": self.base.language,
-                "append_to_response": "videos",
+                "append_to_response": "videos,keywords",
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let show_data: TmdbMediaEntry = rsp.json().await.map_err(|e| anyhow!(e))?;
         let mut videos = vec![];
         if let Some(vid) = show_data.videos {
@@ -1045,8 +1115,16 @@ impl MediaProvider for TmdbShowService {
             season_number: i32,
             episodes: Vec<TmdbEpisode>,
         }
-        let mut seasons = vec![];
-        for s in show_data.seasons.unwrap_or_default().iter() {
+        let mut seasons: Vec<TmdbSeason> = vec![];
+        let all_seasons = show_data.seasons.unwrap_or_default();
+        let mut guard = PaginationGuard::new(MAX_SEASONS, MAX_EPISODES).keep_partial();
+        for (idx, s) in all_seasons.iter().enumerate() {
+            if let Some(last) = seasons.last() {
+                let episodes = seasons.iter().map(|s| s.episodes.len()).sum();
+                if !guard.next_page(last.season_number, episodes)? {
+                    break;
+                }
+            }
             let rsp = self
                 .base
                 .client
@@ -1061,7 +1139,8 @@ impl MediaProvider for TmdbShowService {
                 }))
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?;
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?;
             let mut data: TmdbSeason = rsp.json().await.map_err(|e| anyhow!(e))?;
             let rsp = self
                 .base
@@ -1077,7 +1156,8 @@ impl MediaProvider for TmdbShowService {
                 }))
                 .send()
                 .await
-                .map_err(|e| anyhow!(e))?;
+                .map_err(|e| anyhow!(e))
+                .and_then(check_retry_after)?;
             #[derive(Debug, Serialize, Deserialize, Clone)]
             struct TmdbSeasonCredit {
                 cast: Vec<TmdbCredit>,
@@ -1087,6 +1167,12 @@ impl MediaProvider for TmdbShowService {
                 e.guest_stars.extend(credits.cast.clone());
             }
             seasons.push(data);
+            report_page_progress(
+                MediaSource::Tmdb,
+                identifier,
+                idx + 1,
+                Some(all_seasons.len()),
+            );
         }
         let mut people = seasons
             .iter()
@@ -1144,11 +1230,12 @@ impl MediaProvider for TmdbShowService {
             .iter()
             .filter(|s| !SHOW_SPECIAL_SEASON_NAMES.contains(&s.name.as_str()))
             .collect_vec();
-        let total_runtime = seasons_without_specials
-            .iter()
-            .flat_map(|s| s.episodes.iter())
-            .map(|e| e.runtime.unwrap_or_default())
-            .sum();
+        let runtime = show_runtime(
+            seasons_without_specials
+                .iter()
+                .flat_map(|s| s.episodes.iter())
+                .map(|e| e.runtime),
+        );
         let total_seasons = seasons_without_specials.len();
         let total_episodes = seasons_without_specials
             .iter()
@@ -1173,6 +1260,7 @@ impl MediaProvider for TmdbShowService {
                 .map(|g| g.name)
                 .unique()
                 .collect(),
+            tags: show_data.keywords.unwrap_or_default().names(),
             publish_date: convert_string_to_date(
                 &show_data.first_air_date.clone().unwrap_or_default(),
             ),
@@ -1186,11 +1274,7 @@ impl MediaProvider for TmdbShowService {
             videos,
             publish_year: convert_date_to_year(&show_data.first_air_date.unwrap_or_default()),
             show_specifics: Some(ShowSpecifics {
-                runtime: if total_runtime == 0 {
-                    None
-                } else {
-                    Some(total_runtime)
-                },
+                runtime,
                 total_seasons: if total_seasons == 0 {
                     None
                 } else {
@@ -1226,7 +1310,7 @@ impl MediaProvider for TmdbShowService {
                                     ShowEpisode {
                                         id: e.id,
                                         name: e.name,
-                                        runtime: e.runtime,
+                                        runtime: e.runtime.map(MediaDuration::from_minutes),
                                         publish_date: convert_string_to_date(
                                             &e.air_date.unwrap_or_default(),
                                         ),
@@ -1243,15 +1327,8 @@ impl MediaProvider for TmdbShowService {
             suggestions,
             watch_providers,
             external_identifiers: Some(external_identifiers),
-            provider_rating: if let Some(av) = show_data.vote_average {
-                if av != dec!(0) {
-                    Some(av * dec!(10))
-                } else {
-                    None
-                }
-            } else {
-                None
-            },
+            provider_rating: show_data.vote_average.filter(|av| *av != dec!(0)),
+            provider_rating_count: show_data.vote_count,
             ..Default::default()
         })
     }
@@ -1281,7 +1358,8 @@ impl MediaProvider for TmdbShowService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: TmdbListResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .results
@@ -1365,3 +1443,27 @@ fn replace_from_end(input_string: String, search_string: &str, replace_string: &
     }
     input_string
 }
+
+/// The total runtime of a show, TMDB reports the runtimes of episodes in minutes.
+fn show_runtime(episode_runtimes: impl Iterator<Item = Option<i32>>) -> Option<MediaDuration> {
+    let total = episode_runtimes
+        .flatten()
+        .map(MediaDuration::from_minutes)
+        .sum::<MediaDuration>();
+    (total.minutes() != 0).then_some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn show_runtime_sums_episode_minutes() {
+        assert_eq!(
+            show_runtime([Some(42), None, Some(58)].into_iter()).map(|r| r.minutes()),
+            Some(100)
+        );
+        assert_eq!(show_runtime([None, Some(0)].into_iter()), None);
+        assert_eq!(show_runtime(std::iter::empty()), None);
+    }
+}
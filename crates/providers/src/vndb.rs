@@ -7,22 +7,29 @@ use dependent_models::SearchResults;
 use enums::{MediaLot, MediaSource};
 use itertools::Itertools;
 use media_models::{
-    MetadataDetails, MetadataImageForMediaDetails, MetadataPerson, MetadataSearchItem,
-    PartialMetadataPerson, PeopleSearchItem, PersonSourceSpecifics, VisualNovelSpecifics,
+    MediaDuration, MetadataDetails, MetadataImageForMediaDetails, MetadataPerson,
+    MetadataSearchItem, PartialMetadataPerson, PeopleSearchItem, PersonSourceSpecifics,
+    VisualNovelSpecifics,
 };
 use reqwest::Client;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use traits::{MediaProvider, MediaProviderLanguages};
 
+use crate::circuit_breaker::check_retry_after;
+
 static URL: &str = "https://api.vndb.org/kana";
 const METADATA_FIELDS_SMALL: &str = "title,image.url,released,screenshots.url,developers.name";
 const METADATA_FIELDS: &str = const_str::concat!(
     METADATA_FIELDS_SMALL,
     ",",
-    "length_minutes,tags.name,developers.id,devstatus,description,rating"
+    "length_minutes,tags.name,developers.id,devstatus,description,rating,votecount"
 );
 
+pub fn metadata_url(_lot: MediaLot, identifier: &str, _slug: &str) -> Option<String> {
+    Some(format!("https://vndb.org/{identifier}"))
+}
+
 #[derive(Debug, Clone)]
 pub struct VndbService {
     client: Client,
@@ -62,6 +69,7 @@ struct ItemResponse {
     #[serde(alias = "name")]
     title: Option<String>,
     rating: Option<Decimal>,
+    votecount: Option<i32>,
     released: Option<String>,
     description: Option<String>,
     image: Option<ImageLinks>,
@@ -100,7 +108,8 @@ impl MediaProvider for VndbService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?
             .json::<SearchResponse>()
             .await
             .map_err(|e| anyhow!(e))?;
@@ -144,7 +153,8 @@ impl MediaProvider for VndbService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let data: SearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let item = data.results.unwrap_or_default().pop().unwrap();
         Ok(MetadataPerson {
@@ -174,7 +184,8 @@ impl MediaProvider for VndbService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let data: SearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let item = data.results.unwrap_or_default().pop().unwrap();
         let d = self.vndb_response_to_search_response(item);
@@ -200,7 +211,8 @@ impl MediaProvider for VndbService {
             }))
             .send()
             .await
-            .map_err(|e| anyhow!(e))?;
+            .map_err(|e| anyhow!(e))
+            .and_then(check_retry_after)?;
         let search: SearchResponse = rsp.json().await.map_err(|e| anyhow!(e))?;
         let resp = search
             .results
@@ -282,11 +294,40 @@ impl VndbService {
             publish_year: item.released.clone().and_then(|d| convert_date_to_year(&d)),
             publish_date: item.released.and_then(|d| convert_string_to_date(&d)),
             visual_novel_specifics: Some(VisualNovelSpecifics {
-                length: item.length_minutes,
+                length: item.length_minutes.map(MediaDuration::from_minutes),
             }),
             provider_rating: item.rating,
+            provider_rating_count: item.votecount,
             url_images: images.unique().collect(),
             ..Default::default()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn visual_novel_length_is_stored_in_minutes() {
+        let service = VndbService {
+            client: Client::new(),
+        };
+        let item: ItemResponse = serde_json::from_value(json!({
+            "id": "v17",
+            "title": "Ever17",
+            "length_minutes": 1830
+        }))
+        .unwrap();
+        let details = service.vndb_response_to_search_response(item);
+        assert_eq!(
+            details
+                .visual_novel_specifics
+                .and_then(|s| s.length)
+                .map(|l| l.minutes()),
+            Some(1830)
+        );
+    }
+}
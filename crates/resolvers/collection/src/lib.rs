@@ -4,8 +4,11 @@ use async_graphql::{Context, Object, Result};
 use collection_service::CollectionService;
 use common_models::{ChangeCollectionToEntityInput, StringIdObject};
 use dependent_models::CollectionContents;
-use media_models::{CollectionContentsInput, CollectionItem, CreateOrUpdateCollectionInput};
-use traits::AuthProvider;
+use media_models::{
+    CollectionContentsInput, CollectionItem, CreateCollectionShareTokenInput,
+    CreateOrUpdateCollectionInput, ReorderCollectionEntityInput,
+};
+use traits::{AuthProvider, Validate};
 
 #[derive(Default)]
 pub struct CollectionQuery;
@@ -55,6 +58,7 @@ impl CollectionMutation {
     ) -> Result<StringIdObject> {
         let service = gql_ctx.data_unchecked::<Arc<CollectionService>>();
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        input.validate()?;
         service.create_or_update_collection(&user_id, input).await
     }
 
@@ -69,6 +73,18 @@ impl CollectionMutation {
         service.add_entity_to_collection(&user_id, input).await
     }
 
+    /// Move an entity of a collection to a new position, shifting the entities
+    /// in between.
+    async fn reorder_collection_entity(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: ReorderCollectionEntityInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<CollectionService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.reorder_collection_entity(&user_id, input).await
+    }
+
     /// Remove an entity from a collection if it is not there, otherwise do nothing.
     async fn remove_entity_from_collection(
         &self,
@@ -80,6 +96,31 @@ impl CollectionMutation {
         service.remove_entity_from_collection(&user_id, input).await
     }
 
+    /// Create a token with which anyone can see the media in a collection without
+    /// logging in. Any previous token of the collection stops working.
+    async fn create_collection_share_token(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: CreateCollectionShareTokenInput,
+    ) -> Result<String> {
+        let service = gql_ctx.data_unchecked::<Arc<CollectionService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.create_collection_share_token(&user_id, input).await
+    }
+
+    /// Stop sharing a collection.
+    async fn revoke_collection_share_token(
+        &self,
+        gql_ctx: &Context<'_>,
+        collection_id: String,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<CollectionService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .revoke_collection_share_token(&user_id, collection_id)
+            .await
+    }
+
     /// Delete a collection.
     async fn delete_collection(
         &self,
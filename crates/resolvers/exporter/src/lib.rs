@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use async_graphql::{Context, Object, Result};
-use common_models::ExportJob;
+use common_models::{ExportJob, ScheduledExportStatus};
 use exporter_service::ExporterService;
 use traits::AuthProvider;
 
@@ -18,6 +18,16 @@ impl ExporterQuery {
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
         service.user_exports(user_id).await
     }
+
+    /// Get the outcome of the last run of the scheduled exports. Admin only.
+    async fn last_scheduled_export_status(
+        &self,
+        gql_ctx: &Context<'_>,
+    ) -> Result<Option<ScheduledExportStatus>> {
+        let service = gql_ctx.data_unchecked::<Arc<ExporterService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.last_scheduled_export_status(&user_id).await
+    }
 }
 
 #[derive(Default)]
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Error, Object, Result};
 use file_storage_service::FileStorageService;
 use media_models::{PresignedPutUrlInput, PresignedPutUrlResponse};
 
@@ -30,7 +30,8 @@ impl FileStorageMutation {
         let service = gql_ctx.data_unchecked::<Arc<FileStorageService>>();
         let (key, upload_url) = service
             .get_presigned_put_url(input.file_name, input.prefix, true, None)
-            .await;
+            .await
+            .ok_or_else(|| Error::new("Could not create an upload URL"))?;
         Ok(PresignedPutUrlResponse { upload_url, key })
     }
 
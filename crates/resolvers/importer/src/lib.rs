@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
 use async_graphql::{Context, Object, Result};
-use database_models::import_report;
+use common_models::StringIdObject;
+use database_models::{csv_import_preset, import_report};
+use importer_models::ImportFormatDetection;
 use importer_service::ImporterService;
-use media_models::DeployImportJobInput;
+use media_models::{CreateOrUpdateCsvImportPresetInput, DeployImportJobInput};
 use traits::AuthProvider;
 
 #[derive(Default)]
@@ -19,6 +21,28 @@ impl ImporterQuery {
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
         service.import_reports(user_id).await
     }
+
+    /// Get the import sources that are likely to understand an uploaded file,
+    /// most likely first.
+    async fn detect_import_format(
+        &self,
+        gql_ctx: &Context<'_>,
+        uploaded_path: String,
+    ) -> Result<Vec<ImportFormatDetection>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        self.user_id_from_ctx(gql_ctx).await?;
+        service.detect_import_format(uploaded_path).await
+    }
+
+    /// Get all the column mapping presets saved by the user for the generic CSV importer.
+    async fn csv_import_presets(
+        &self,
+        gql_ctx: &Context<'_>,
+    ) -> Result<Vec<csv_import_preset::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.csv_import_presets(user_id).await
+    }
 }
 
 #[derive(Default)]
@@ -42,4 +66,28 @@ impl ImporterMutation {
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
         service.deploy_import_job(user_id, input).await
     }
+
+    /// Create or update a column mapping preset for the generic CSV importer.
+    async fn create_or_update_csv_import_preset(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: CreateOrUpdateCsvImportPresetInput,
+    ) -> Result<StringIdObject> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .create_or_update_csv_import_preset(user_id, input)
+            .await
+    }
+
+    /// Delete a column mapping preset for the generic CSV importer.
+    async fn delete_csv_import_preset(
+        &self,
+        gql_ctx: &Context<'_>,
+        preset_id: String,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.delete_csv_import_preset(user_id, preset_id).await
+    }
 }
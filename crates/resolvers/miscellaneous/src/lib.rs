@@ -1,22 +1,38 @@
 use std::sync::Arc;
 
 use async_graphql::{Context, Object, Result};
-use common_models::{BackgroundJob, SearchInput, StringIdObject};
+use chrono::NaiveDate;
+use common_models::{
+    BackgroundJob, CreateOrUpdateAnnouncementInput, ProviderStatus, ScheduledJobRuns, SearchInput,
+    StringIdObject,
+};
+use database_models::{announcement, safety_snapshot, seen, video_game_session};
 use dependent_models::{
-    CoreDetails, GenreDetails, MetadataGroupDetails, PersonDetails, SearchResults,
-    UserMetadataDetails, UserMetadataGroupDetails, UserPersonDetails,
+    AdminDashboard, CoreDetails, GenreDetails, MetadataGroupDetails, PersonDetails, PersonWorks,
+    SearchResults, SeenChangesResponse, UserDashboard, UserMetadataDetails,
+    UserMetadataGroupDetails, UserPersonDetails,
 };
+use enums::MediaLot;
 use media_models::{
-    CommitMediaInput, CommitPersonInput, CreateCustomMetadataInput, CreateOrUpdateReviewInput,
-    CreateReviewCommentInput, GenreDetailsInput, GenreListItem, GraphqlCalendarEvent,
-    GraphqlMetadataDetails, GroupedCalendarEvent, MetadataGroupSearchInput,
-    MetadataGroupSearchItem, MetadataGroupsListInput, MetadataListInput, MetadataPartialDetails,
-    MetadataSearchInput, MetadataSearchItemResponse, PeopleListInput, PeopleSearchInput,
-    PeopleSearchItem, ProgressUpdateInput, ProviderLanguageInformation, UpdateSeenItemInput,
-    UserCalendarEventInput, UserUpcomingCalendarEventInput,
+    AddMetadataImageInput, BulkCommitMetadataResult, CommitMediaInput, CommitPersonInput,
+    CreateCustomMetadataInput, CreateOrUpdateReviewInput, CreateReviewCommentInput,
+    DeleteMetadataImageInput, DeleteReviewCommentInput, GenreDetailsInput, GenreListItem,
+    GraphqlCalendarEvent, GraphqlMetadataDetails, GroupedCalendarEvent,
+    ImportOrExportItemReviewComment, InstancePopularInput, InstancePopularItem,
+    MarkEpisodeSkippedInput, MarkShowSeasonSeenInput, MediaByTagInput, MediaReviewsInput,
+    MetadataFieldChange, MetadataGroupSearchInput, MetadataGroupSearchItem,
+    MetadataGroupsListInput, MetadataIdentifierType, MetadataListInput, MetadataListItem,
+    MetadataLookupResult, MetadataPartialDetails, MetadataRefreshProgress, MetadataSearchInput,
+    MetadataSearchItemResponse, MetadataSuggestionsInput, MetadataTagInput, PeopleListInput,
+    PeopleSearchInput, PeopleSearchItem, PersonWorksInput, PostReviewCommentInput,
+    ProgressUpdateInput, ProviderLanguageInformation, QuickLogResult, RandomEpisodeInput,
+    RandomEpisodeResult, RandomMetadataInput, ReviewCommentsInput, ReviewItem, SeenChangesInput,
+    SeenEdition, SeenHistoryInput, SwapMetadataSourceInput, UpdateCustomMetadataInput,
+    UpdateMetadataSortTitleInput, UpdateSeenItemInput, UserCalendarEventInput,
+    UserUpcomingCalendarEventInput, VideoGameSessionsInput,
 };
 use miscellaneous_service::MiscellaneousService;
-use traits::AuthProvider;
+use traits::{AuthProvider, Validate};
 
 #[derive(Default)]
 pub struct MiscellaneousQuery;
@@ -51,6 +67,28 @@ impl MiscellaneousQuery {
         service.metadata_details(&metadata_id).await
     }
 
+    /// Get the progress of the last refresh of a media deployed with
+    /// `deployUpdateMetadataJob`. Clients poll this until it is finished.
+    async fn metadata_refresh_progress(
+        &self,
+        gql_ctx: &Context<'_>,
+        metadata_id: String,
+    ) -> Result<Option<MetadataRefreshProgress>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        service.metadata_refresh_progress(&metadata_id).await
+    }
+
+    /// Get the editions of a media that the provider knows about, so that the one
+    /// that was consumed can be picked.
+    async fn editions_for_metadata(
+        &self,
+        gql_ctx: &Context<'_>,
+        metadata_id: String,
+    ) -> Result<Vec<SeenEdition>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        service.editions_for_metadata(metadata_id).await
+    }
+
     /// Get details about a creator present in the database.
     async fn person_details(
         &self,
@@ -61,14 +99,61 @@ impl MiscellaneousQuery {
         service.person_details(person_id).await
     }
 
-    /// Get details about a genre present in the database.
+    /// Get a page of the reviews of a media item. The reviews of other users are
+    /// only returned when they are public.
+    async fn reviews_for_media(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MediaReviewsInput,
+    ) -> Result<SearchResults<ReviewItem>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.reviews_for_media(&user_id, input).await
+    }
+
+    /// Get the media a creator worked on, one type at a time, along with the
+    /// number of works of every type.
+    async fn person_works(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: PersonWorksInput,
+    ) -> Result<PersonWorks> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        service.person_works(input).await
+    }
+
+    /// Get details about a genre along with the media in the user's library that
+    /// belongs to it.
     async fn genre_details(
         &self,
         gql_ctx: &Context<'_>,
         input: GenreDetailsInput,
     ) -> Result<GenreDetails> {
         let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
-        service.genre_details(input).await
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.genre_details(user_id, input).await
+    }
+
+    /// Get paginated list of media that have a tag.
+    async fn media_by_tag(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MediaByTagInput,
+    ) -> Result<SearchResults<String>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        self.user_id_from_ctx(gql_ctx).await?;
+        service.media_by_tag(input).await
+    }
+
+    /// Get the media that the most users of this instance have been consuming recently.
+    async fn instance_popular(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: InstancePopularInput,
+    ) -> Result<Vec<InstancePopularItem>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.instance_popular(user_id, input).await
     }
 
     /// Get details about a metadata group present in the database.
@@ -92,6 +177,18 @@ impl MiscellaneousQuery {
         service.metadata_list(user_id, input).await
     }
 
+    /// Get the same media items as `metadataList` along with the seen status and
+    /// average rating of the user for each of them.
+    async fn metadata_list_items(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MetadataListInput,
+    ) -> Result<SearchResults<MetadataListItem>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.metadata_list_items(user_id, input).await
+    }
+
     /// Search for a list of media for a given type.
     async fn metadata_search(
         &self,
@@ -103,14 +200,27 @@ impl MiscellaneousQuery {
         service.metadata_search(&user_id, input).await
     }
 
-    /// Get paginated list of genres.
+    /// Get paginated list of genres with the number of media in the user's library
+    /// that belong to each.
     async fn genres_list(
         &self,
         gql_ctx: &Context<'_>,
         input: SearchInput,
     ) -> Result<SearchResults<GenreListItem>> {
         let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
-        service.genres_list(input).await
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.genres_list(user_id, input).await
+    }
+
+    /// Get paginated list of all the metadata groups known to the instance, like
+    /// franchises fetched from the providers.
+    async fn all_metadata_groups(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: SearchInput,
+    ) -> Result<SearchResults<String>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        service.all_metadata_groups(input).await
     }
 
     /// Get paginated list of metadata groups.
@@ -133,6 +243,24 @@ impl MiscellaneousQuery {
         service.providers_language_information()
     }
 
+    /// Get the circuit breaker state of all the providers. Admin only.
+    async fn provider_status(&self, gql_ctx: &Context<'_>) -> Result<Vec<ProviderStatus>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.provider_status(user_id).await
+    }
+
+    /// Get the announcements that are currently active and have not been dismissed
+    /// by the currently logged in user.
+    async fn active_announcements(
+        &self,
+        gql_ctx: &Context<'_>,
+    ) -> Result<Vec<announcement::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.active_announcements(user_id).await
+    }
+
     /// Get details that can be displayed to a user for a metadata group.
     async fn user_metadata_group_details(
         &self,
@@ -157,6 +285,86 @@ impl MiscellaneousQuery {
         service.user_metadata_details(user_id, metadata_id).await
     }
 
+    /// Get the comments on a review, most recent first.
+    async fn review_comments(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: ReviewCommentsInput,
+    ) -> Result<SearchResults<ImportOrExportItemReviewComment>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.review_comments(user_id, input).await
+    }
+
+    /// Get the seen history of a media item for the user, most recently updated
+    /// first.
+    async fn user_metadata_seen_history(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: SeenHistoryInput,
+    ) -> Result<SearchResults<seen::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.user_metadata_seen_history(user_id, input).await
+    }
+
+    /// Get the seen items of the user that changed after the given position, least
+    /// recently updated first.
+    async fn user_seen_changes(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: SeenChangesInput,
+    ) -> Result<SeenChangesResponse> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.user_seen_changes(user_id, input).await
+    }
+
+    /// Get the suggestions for a media item in the order that the provider returned
+    /// them. These are only fetched from the provider when the item is refreshed.
+    async fn metadata_suggestions(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MetadataSuggestionsInput,
+    ) -> Result<SearchResults<String>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        service.metadata_suggestions(input).await
+    }
+
+    /// Get the play sessions that game launchers reported, most recent first.
+    async fn user_video_game_sessions(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: VideoGameSessionsInput,
+    ) -> Result<SearchResults<video_game_session::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.user_video_game_sessions(user_id, input).await
+    }
+
+    /// Pick a random media item from the user's library. Returns nothing when no
+    /// media matches the filters.
+    async fn random_metadata(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: RandomMetadataInput,
+    ) -> Result<Option<String>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.random_metadata(user_id, input).await
+    }
+
+    /// Pick a random episode of a show or podcast that was not seen recently.
+    async fn random_episode(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: RandomEpisodeInput,
+    ) -> Result<RandomEpisodeResult> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.random_episode(user_id, input).await
+    }
+
     /// Get details that can be displayed to a user for a creator.
     async fn user_person_details(
         &self,
@@ -190,6 +398,48 @@ impl MiscellaneousQuery {
         service.user_upcoming_calendar_events(user_id, input).await
     }
 
+    /// Get the next times at which each scheduled job will run. Only for admins.
+    async fn next_scheduled_runs(&self, gql_ctx: &Context<'_>) -> Result<Vec<ScheduledJobRuns>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.next_scheduled_runs(user_id).await
+    }
+
+    /// Get the safety snapshots taken before destructive operations. Only for admins.
+    async fn safety_snapshots(&self, gql_ctx: &Context<'_>) -> Result<Vec<safety_snapshot::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.safety_snapshots(user_id).await
+    }
+
+    /// Get statistics about the whole instance. The figures from the database are
+    /// served from the cache unless `refresh` is set.
+    async fn admin_dashboard(
+        &self,
+        gql_ctx: &Context<'_>,
+        refresh: Option<bool>,
+    ) -> Result<AdminDashboard> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .admin_dashboard(user_id, refresh.unwrap_or_default())
+            .await
+    }
+
+    /// Get all the sections of the dashboard in one go. Sections are served from the
+    /// cache unless `refresh` is set.
+    async fn user_dashboard(
+        &self,
+        gql_ctx: &Context<'_>,
+        refresh: Option<bool>,
+    ) -> Result<UserDashboard> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .user_dashboard(user_id, refresh.unwrap_or_default())
+            .await
+    }
+
     /// Get paginated list of people.
     async fn people_list(
         &self,
@@ -243,6 +493,7 @@ impl MiscellaneousMutation {
     ) -> Result<StringIdObject> {
         let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        input.validate()?;
         service.create_or_update_review(&user_id, input).await
     }
 
@@ -264,6 +515,52 @@ impl MiscellaneousMutation {
         service.delete_seen_item(&user_id, seen_id).await
     }
 
+    /// Add an uploaded image to a media item. It is kept when the media is refreshed.
+    async fn add_metadata_image(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: AddMetadataImageInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.add_metadata_image(user_id, input).await
+    }
+
+    /// Remove an image that was added to a media item by a user.
+    async fn delete_metadata_image(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: DeleteMetadataImageInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.delete_metadata_image(user_id, input).await
+    }
+
+    /// Replace the provider data of a media item with the data from another source
+    /// of the same type. The history, reviews and collections of the item are
+    /// kept. With `dry_run` only the changes are returned. Only admins can do this.
+    async fn swap_metadata_source(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: SwapMetadataSourceInput,
+    ) -> Result<Vec<MetadataFieldChange>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.swap_metadata_source(user_id, input).await
+    }
+
+    /// Set the title that a media item is sorted by. Only admins can do this.
+    async fn update_metadata_sort_title(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: UpdateMetadataSortTitleInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.update_metadata_sort_title(user_id, input).await
+    }
+
     /// Create a custom media item.
     async fn create_custom_metadata(
         &self,
@@ -278,6 +575,17 @@ impl MiscellaneousMutation {
             .map(|m| StringIdObject { id: m.id })
     }
 
+    /// Update a custom media item that the user created.
+    async fn update_custom_metadata(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: UpdateCustomMetadataInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.update_custom_metadata(user_id, input).await
+    }
+
     /// Deploy job to update progress of media items in bulk. For seen items in progress,
     /// progress is updated only if it has actually changed.
     async fn deploy_bulk_progress_update(
@@ -287,9 +595,35 @@ impl MiscellaneousMutation {
     ) -> Result<bool> {
         let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        input.validate()?;
         service.deploy_bulk_progress_update(user_id, input).await
     }
 
+    /// Mark a media item as seen using the default action in the user's preferences.
+    /// For shows, the next episode that has not been seen yet is logged.
+    async fn quick_log(
+        &self,
+        gql_ctx: &Context<'_>,
+        metadata_id: String,
+        date: Option<NaiveDate>,
+    ) -> Result<QuickLogResult> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.quick_log(user_id, metadata_id, date).await
+    }
+
+    /// Restore the seen and review rows saved in a safety snapshot. Rows that still
+    /// exist are skipped. Only for admins.
+    async fn restore_safety_snapshot(
+        &self,
+        gql_ctx: &Context<'_>,
+        snapshot_id: String,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.restore_safety_snapshot(user_id, snapshot_id).await
+    }
+
     /// Deploy a job to update a media item's metadata.
     async fn deploy_update_metadata_job(
         &self,
@@ -300,6 +634,18 @@ impl MiscellaneousMutation {
         service.deploy_update_metadata_job(&metadata_id, true).await
     }
 
+    /// Fetch the details of a media item from its provider right away and return a
+    /// summary of what changed.
+    async fn update_metadata(
+        &self,
+        gql_ctx: &Context<'_>,
+        metadata_id: String,
+    ) -> Result<Vec<String>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.update_metadata(user_id, metadata_id).await
+    }
+
     /// Deploy a job to update a person's metadata.
     async fn deploy_update_person_job(
         &self,
@@ -322,8 +668,23 @@ impl MiscellaneousMutation {
             .await
     }
 
+    /// Delete a media item along with everything users have attached to it. If other
+    /// users have activity on it, `force` has to be set. Admin only.
+    async fn delete_metadata(
+        &self,
+        gql_ctx: &Context<'_>,
+        metadata_id: String,
+        force: Option<bool>,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.delete_metadata(user_id, metadata_id, force).await
+    }
+
     /// Merge a media item into another. This will move all `seen`, `collection`
-    /// and `review` associations with to the metadata.
+    /// and `review` associations with to the metadata. Both must be of the same
+    /// type, and the merged item is deleted once no user has anything attached
+    /// to it.
     async fn merge_metadata(
         &self,
         gql_ctx: &Context<'_>,
@@ -362,6 +723,18 @@ impl MiscellaneousMutation {
             .map(|m| StringIdObject { id: m.id })
     }
 
+    /// Fetch the details of many media items at once and add them to the user's
+    /// library. Every item reports whether it succeeded on its own.
+    async fn bulk_commit_metadata(
+        &self,
+        gql_ctx: &Context<'_>,
+        items: Vec<CommitMediaInput>,
+    ) -> Result<Vec<BulkCommitMetadataResult>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.bulk_commit_metadata(user_id, items).await
+    }
+
     /// Fetches details about a person and creates a person item in the database.
     async fn commit_person(
         &self,
@@ -372,6 +745,23 @@ impl MiscellaneousMutation {
         service.commit_person(input).await
     }
 
+    /// Find a media by an ISBN, IMDb id or TMDB id and create it in the database.
+    /// Identifiers that could refer to more than one media return the candidates
+    /// instead, pass `lot` to pick one of them.
+    async fn lookup_by_identifier(
+        &self,
+        gql_ctx: &Context<'_>,
+        identifier_type: MetadataIdentifierType,
+        value: String,
+        lot: Option<MediaLot>,
+    ) -> Result<MetadataLookupResult> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .lookup_by_identifier(user_id, identifier_type, value, lot)
+            .await
+    }
+
     /// Fetch details about a media group and create a media group item in the database.
     async fn commit_metadata_group(
         &self,
@@ -382,6 +772,30 @@ impl MiscellaneousMutation {
         service.commit_metadata_group(input).await
     }
 
+    /// Comment on a review or reply to a comment. The author of the review is
+    /// notified.
+    async fn post_review_comment(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: PostReviewCommentInput,
+    ) -> Result<StringIdObject> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        input.validate()?;
+        service.post_review_comment(user_id, input).await
+    }
+
+    /// Delete a comment. Only the author of the comment or the review can do this.
+    async fn delete_review_comment(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: DeleteReviewCommentInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.delete_review_comment(user_id, input).await
+    }
+
     /// Create, like or delete a comment on a review.
     async fn create_review_comment(
         &self,
@@ -390,9 +804,104 @@ impl MiscellaneousMutation {
     ) -> Result<bool> {
         let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        input.validate()?;
         service.create_review_comment(user_id, input).await
     }
 
+    /// Add a tag to a media item. Tags are shared by all users of the instance.
+    async fn add_metadata_tag(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MetadataTagInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.add_metadata_tag(&user_id, input).await
+    }
+
+    /// Remove a tag from a media item. Users can remove the tags they added, admins
+    /// can remove any tag.
+    async fn remove_metadata_tag(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MetadataTagInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.remove_metadata_tag(&user_id, input).await
+    }
+
+    /// Hide a tag everywhere or show it again. Admin only.
+    async fn update_tag_visibility(
+        &self,
+        gql_ctx: &Context<'_>,
+        tag_id: String,
+        is_hidden: bool,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .update_tag_visibility(&user_id, tag_id, is_hidden)
+            .await
+    }
+
+    /// Create or update an announcement that is shown to all users. Admin only.
+    async fn create_or_update_announcement(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: CreateOrUpdateAnnouncementInput,
+    ) -> Result<StringIdObject> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.create_or_update_announcement(user_id, input).await
+    }
+
+    /// Delete an announcement. Admin only.
+    async fn delete_announcement(
+        &self,
+        gql_ctx: &Context<'_>,
+        announcement_id: String,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.delete_announcement(user_id, announcement_id).await
+    }
+
+    /// Hide an announcement for the currently logged in user.
+    async fn dismiss_announcement(
+        &self,
+        gql_ctx: &Context<'_>,
+        announcement_id: String,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.dismiss_announcement(user_id, announcement_id).await
+    }
+
+    /// Mark an episode, or every episode of a season, as skipped. Skipped episodes
+    /// count towards finishing the show but are excluded from statistics.
+    async fn mark_episode_skipped(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MarkEpisodeSkippedInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.mark_episode_skipped(user_id, input).await
+    }
+
+    /// Mark every episode of a season of a show as seen in one go. Returns the ids
+    /// of the seen items that were created.
+    async fn mark_show_season_seen(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: MarkShowSeasonSeenInput,
+    ) -> Result<Vec<String>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.mark_show_season_seen(user_id, input).await
+    }
+
     /// Update the attributes of a seen item.
     async fn update_seen_item(
         &self,
@@ -1,15 +1,29 @@
-use std::{fs::write, path::PathBuf, sync::Arc};
+use std::{
+    fs::{read, remove_file, write},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use anyhow::Result;
+use application_utils::AuthContext;
 use async_graphql::http::GraphiQLSource;
 use axum::{
     extract::{Multipart, Path},
-    http::StatusCode,
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
     response::{Html, IntoResponse},
     Extension, Json,
 };
+use chrono::Utc;
+use collection_service::CollectionService;
 use common_utils::{ryot_log, TEMP_DIR};
-use integration_service::IntegrationService;
+use database_utils::check_token;
+use exporter_service::ExporterService;
+use file_storage_service::read_local_object;
+use integration_service::{IntegrationService, WebhookResponse};
+use media_models::SharedCollection;
 use nanoid::nanoid;
 use serde_json::json;
 
@@ -24,7 +38,9 @@ pub async fn graphql_playground() -> impl IntoResponse {
 pub async fn config_handler(
     Extension(config): Extension<Arc<config::AppConfig>>,
 ) -> impl IntoResponse {
-    Json(config.masked_value())
+    let mut value = serde_json::to_value(config.masked_value()).unwrap();
+    value["warnings"] = json!(config.validate().warnings);
+    Json(value)
 }
 
 /// Upload a file to the temporary file system. Primarily to be used for uploading
@@ -47,17 +63,119 @@ pub async fn upload_file(
     Ok(Json(json!(res)))
 }
 
+/// Serve an image that was stored in the local data directory because file storage
+/// is not configured.
+pub async fn stored_image(
+    Path(key): Path<String>,
+) -> std::result::Result<impl IntoResponse, StatusCode> {
+    let body = read_local_object(&key).ok_or(StatusCode::NOT_FOUND)?;
+    let content_type = match key.rsplit_once('.').map(|(_, e)| e) {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        _ => "application/octet-stream",
+    };
+    Ok(([(CONTENT_TYPE, content_type)], body))
+}
+
+pub async fn shared_collection(
+    Path(token): Path<String>,
+    Extension(collection_service): Extension<Arc<CollectionService>>,
+) -> std::result::Result<Json<SharedCollection>, StatusCode> {
+    let collection = collection_service
+        .shared_collection(token)
+        .await
+        .map_err(|e| {
+            ryot_log!(error, "{:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    collection.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Download the media of the current user as a zip of CSV files, one for each
+/// media type.
+pub async fn csv_export(
+    auth_ctx: AuthContext,
+    Extension(config): Extension<Arc<config::AppConfig>>,
+    Extension(exporter_service): Extension<Arc<ExporterService>>,
+) -> std::result::Result<impl IntoResponse, StatusCode> {
+    let (Some(auth_token), Some(user_id)) = (auth_ctx.auth_token, auth_ctx.user_id) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    check_token(
+        &auth_token,
+        false,
+        &config.users.jwt_secret,
+        &exporter_service.0.db,
+    )
+    .await
+    .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let export_path = exporter_service
+        .perform_csv_export(&user_id)
+        .await
+        .map_err(|e| {
+            ryot_log!(error, "{:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let body = read(&export_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    remove_file(&export_path).ok();
+    let file_name = format!("ryot-export-{}.zip", Utc::now().format("%Y-%m-%d"));
+    Ok((
+        [
+            (CONTENT_TYPE, "application/zip".to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        body,
+    ))
+}
+
 pub async fn integration_webhook(
     Path(integration_slug): Path<String>,
     Extension(integration_service): Extension<Arc<IntegrationService>>,
+    headers: HeaderMap,
     payload: String,
 ) -> std::result::Result<(StatusCode, String), StatusCode> {
+    let headers = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.to_string(), value)
+        })
+        .collect();
     let response = integration_service
-        .process_integration_webhook(integration_slug, payload)
+        .process_integration_webhook(integration_slug, headers, payload)
         .await
         .map_err(|e| {
             ryot_log!(error, "{:?}", e);
             StatusCode::UNPROCESSABLE_ENTITY
         })?;
-    Ok((StatusCode::OK, response))
+    Ok(webhook_response(response))
+}
+
+/// Queued requests are accepted before they are processed, so the sending server
+/// does not wait on imports.
+fn webhook_response(response: WebhookResponse) -> (StatusCode, String) {
+    match response {
+        WebhookResponse::Queued(delivery_id) => (StatusCode::ACCEPTED, delivery_id),
+        WebhookResponse::Processed(message) => (StatusCode::OK, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queued_webhooks_are_accepted_before_processing() {
+        let (status, body) = webhook_response(WebhookResponse::Queued("iwd_1".to_owned()));
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body, "iwd_1");
+        let (status, _) = webhook_response(WebhookResponse::Processed("Done".to_owned()));
+        assert_eq!(status, StatusCode::OK);
+    }
 }
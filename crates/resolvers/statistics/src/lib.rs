@@ -2,7 +2,10 @@ use std::sync::Arc;
 
 use async_graphql::{Context, Object, Result};
 use dependent_models::DailyUserActivitiesResponse;
-use media_models::{DailyUserActivitiesInput, DailyUserActivityItem};
+use media_models::{
+    ConsumptionHeatmap, ConsumptionHeatmapInput, ConsumptionStreaks, ConsumptionStreaksInput,
+    DailyUserActivitiesInput, DailyUserActivityItem, UserYearlySummary,
+};
 use statistics_service::StatisticsService;
 use traits::AuthProvider;
 
@@ -24,10 +27,67 @@ impl StatisticsQuery {
         service.daily_user_activities(&user_id, input).await
     }
 
+    /// Get the number of seen entries for each day of the week and hour of the day
+    /// for the currently logged in user.
+    async fn consumption_heatmap(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: ConsumptionHeatmapInput,
+    ) -> Result<ConsumptionHeatmap> {
+        let service = gql_ctx.data_unchecked::<Arc<StatisticsService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.consumption_heatmap(&user_id, input).await
+    }
+
+    /// Get the current and longest streaks of days on which the currently logged in
+    /// user consumed something, with their seen entries per weekday and month.
+    async fn consumption_streaks(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: ConsumptionStreaksInput,
+    ) -> Result<ConsumptionStreaks> {
+        let service = gql_ctx.data_unchecked::<Arc<StatisticsService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.consumption_streaks(&user_id, input).await
+    }
+
     /// Get a summary of all the media items that have been consumed by this user.
     async fn latest_user_summary(&self, gql_ctx: &Context<'_>) -> Result<DailyUserActivityItem> {
         let service = gql_ctx.data_unchecked::<Arc<StatisticsService>>();
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
         service.latest_user_summary(&user_id).await
     }
+
+    /// Get what the currently logged in user finished in a calendar year, like the
+    /// number of movies, episodes, minutes and pages, and their favorite genres. The
+    /// summary is cached and kept up to date as seen entries change.
+    async fn user_yearly_summary(
+        &self,
+        gql_ctx: &Context<'_>,
+        year: i32,
+    ) -> Result<UserYearlySummary> {
+        let service = gql_ctx.data_unchecked::<Arc<StatisticsService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.user_yearly_summary(&user_id, year).await
+    }
+}
+
+#[derive(Default)]
+pub struct StatisticsMutation;
+
+impl AuthProvider for StatisticsMutation {}
+
+#[Object]
+impl StatisticsMutation {
+    /// Compute the yearly summary of the currently logged in user from scratch, for
+    /// when the cached one is out of date.
+    async fn regenerate_user_yearly_summary(
+        &self,
+        gql_ctx: &Context<'_>,
+        year: i32,
+    ) -> Result<UserYearlySummary> {
+        let service = gql_ctx.data_unchecked::<Arc<StatisticsService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.regenerate_user_yearly_summary(&user_id, year).await
+    }
 }
@@ -2,15 +2,22 @@ use std::sync::Arc;
 
 use async_graphql::{Context, Object, Result};
 use common_models::{StringIdObject, UpdateComplexJsonInput};
-use database_models::{access_link, integration, notification_platform, user};
+use database_models::{
+    access_link, integration, integration_webhook_delivery, notification_platform,
+    outbound_webhook_delivery, user, webhook_debug_capture,
+};
 use dependent_models::UserDetailsResult;
+use enums::OutboundWebhookEvent;
+use integration_service::IntegrationService;
 use media_models::{
     AuthUserInput, CreateAccessLinkInput, CreateUserIntegrationInput,
     CreateUserNotificationPlatformInput, LoginResult, OidcTokenOutput, ProcessAccessLinkInput,
     ProcessAccessLinkResult, RegisterResult, RegisterUserInput, UpdateUserInput,
-    UpdateUserIntegrationInput, UpdateUserNotificationPlatformInput,
+    UpdateUserIntegrationInput, UpdateUserNotificationPlatformInput, WebhookSamplePayloadKind,
+    WebhookSimulationStep,
 };
-use traits::AuthProvider;
+use traits::{AuthProvider, Validate};
+use user_models::UserPreferences;
 use user_service::UserService;
 
 #[derive(Default)]
@@ -58,6 +65,47 @@ impl UserQuery {
         service.user_integrations(&user_id).await
     }
 
+    /// Get the requests captured while debug mode was enabled for an integration.
+    async fn webhook_debug_captures(
+        &self,
+        gql_ctx: &Context<'_>,
+        integration_id: String,
+    ) -> Result<Vec<webhook_debug_capture::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<IntegrationService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .webhook_debug_captures(&user_id, integration_id)
+            .await
+    }
+
+    /// Get the most recent webhook requests received by an integration and
+    /// whether they have been processed.
+    async fn integration_webhook_deliveries(
+        &self,
+        gql_ctx: &Context<'_>,
+        integration_id: String,
+    ) -> Result<Vec<integration_webhook_delivery::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<IntegrationService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .integration_webhook_deliveries(&user_id, integration_id)
+            .await
+    }
+
+    /// Get the most recent events sent by a webhook integration, including the
+    /// ones that will not be retried anymore.
+    async fn outbound_webhook_deliveries(
+        &self,
+        gql_ctx: &Context<'_>,
+        integration_id: String,
+    ) -> Result<Vec<outbound_webhook_delivery::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<IntegrationService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .outbound_webhook_deliveries(&user_id, integration_id)
+            .await
+    }
+
     /// Get all the notification platforms for the currently logged in user.
     async fn user_notification_platforms(
         &self,
@@ -89,6 +137,13 @@ impl UserQuery {
         let service = gql_ctx.data_unchecked::<Arc<UserService>>();
         service.user_by_oidc_issuer_id(oidc_issuer_id).await
     }
+
+    /// Get the preferences that newly registered users start with. Admin only.
+    async fn default_user_preferences(&self, gql_ctx: &Context<'_>) -> Result<UserPreferences> {
+        let service = gql_ctx.data_unchecked::<Arc<UserService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service.default_user_preferences(&user_id).await
+    }
 }
 
 #[derive(Default)]
@@ -177,9 +232,36 @@ impl UserMutation {
     ) -> Result<bool> {
         let service = gql_ctx.data_unchecked::<Arc<UserService>>();
         let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        input.validate()?;
         service.update_user_preference(user_id, input).await
     }
 
+    /// Change the preferences that newly registered users start with. Admin only.
+    async fn update_default_user_preference(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: UpdateComplexJsonInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<UserService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        input.validate()?;
+        service.update_default_user_preference(user_id, input).await
+    }
+
+    /// Apply a property of the default preferences to all users who have not
+    /// changed it themselves. Returns the number of users updated. Admin only.
+    async fn push_default_user_preference(
+        &self,
+        gql_ctx: &Context<'_>,
+        property: String,
+    ) -> Result<usize> {
+        let service = gql_ctx.data_unchecked::<Arc<UserService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .push_default_user_preference(user_id, property)
+            .await
+    }
+
     /// Create an integration for the currently logged in user.
     async fn create_user_integration(
         &self,
@@ -215,6 +297,35 @@ impl UserMutation {
             .await
     }
 
+    /// Run a sample payload through an integration of the currently logged in user
+    /// and get the outcome of each processing step.
+    async fn simulate_webhook(
+        &self,
+        gql_ctx: &Context<'_>,
+        integration_id: String,
+        sample_payload_kind: WebhookSamplePayloadKind,
+    ) -> Result<Vec<WebhookSimulationStep>> {
+        let service = gql_ctx.data_unchecked::<Arc<IntegrationService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .simulate_webhook(&user_id, integration_id, sample_payload_kind)
+            .await
+    }
+
+    /// Send a test event to a webhook integration of the currently logged in user.
+    async fn test_outbound_webhook(
+        &self,
+        gql_ctx: &Context<'_>,
+        integration_id: String,
+        event: OutboundWebhookEvent,
+    ) -> Result<outbound_webhook_delivery::Model> {
+        let service = gql_ctx.data_unchecked::<Arc<IntegrationService>>();
+        let user_id = self.user_id_from_ctx(gql_ctx).await?;
+        service
+            .test_outbound_webhook(&user_id, integration_id, event)
+            .await
+    }
+
     /// Add a notification platform for the currently logged in user.
     async fn create_user_notification_platform(
         &self,
@@ -5,6 +5,7 @@ use common_utils::ryot_log;
 use database_models::{application_cache, prelude::ApplicationCache};
 use sea_orm::{ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use sea_query::OnConflict;
+use serde::{de::DeserializeOwned, Serialize};
 use uuid::Uuid;
 
 pub struct CacheService {
@@ -18,14 +19,16 @@ impl CacheService {
 }
 
 impl CacheService {
-    pub async fn set_with_expiry(
+    async fn upsert(
         &self,
         key: ApplicationCacheKey,
+        value: Option<serde_json::Value>,
         expiry_hours: i64,
     ) -> Result<Uuid> {
         let now = Utc::now();
         let to_insert = application_cache::ActiveModel {
             key: ActiveValue::Set(key),
+            value: ActiveValue::Set(value),
             expires_at: ActiveValue::Set(Some(now + Duration::hours(expiry_hours))),
             created_at: ActiveValue::Set(now),
             ..Default::default()
@@ -34,6 +37,7 @@ impl CacheService {
             .on_conflict(
                 OnConflict::column(application_cache::Column::Key)
                     .update_columns([
+                        application_cache::Column::Value,
                         application_cache::Column::ExpiresAt,
                         application_cache::Column::CreatedAt,
                     ])
@@ -46,18 +50,54 @@ impl CacheService {
         Ok(insert_id)
     }
 
-    pub async fn get(&self, key: ApplicationCacheKey) -> Result<Option<()>> {
+    async fn get_unexpired(
+        &self,
+        key: ApplicationCacheKey,
+    ) -> Result<Option<application_cache::Model>> {
         let cache = ApplicationCache::find()
             .filter(application_cache::Column::Key.eq(key))
             .one(&self.db)
             .await?;
-        Ok(cache
-            .filter(|cache| {
-                cache
-                    .expires_at
-                    .map_or(false, |expires_at| expires_at > Utc::now())
-            })
-            .map(|_| ()))
+        Ok(cache.filter(|cache| {
+            cache
+                .expires_at
+                .map_or(false, |expires_at| expires_at > Utc::now())
+        }))
+    }
+
+    pub async fn set_with_expiry(
+        &self,
+        key: ApplicationCacheKey,
+        expiry_hours: i64,
+    ) -> Result<Uuid> {
+        self.upsert(key, None, expiry_hours).await
+    }
+
+    pub async fn get(&self, key: ApplicationCacheKey) -> Result<Option<()>> {
+        Ok(self.get_unexpired(key).await?.map(|_| ()))
+    }
+
+    pub async fn set_value_with_expiry<T: Serialize>(
+        &self,
+        key: ApplicationCacheKey,
+        value: &T,
+        expiry_hours: i64,
+    ) -> Result<Uuid> {
+        let value = serde_json::to_value(value)?;
+        self.upsert(key, Some(value), expiry_hours).await
+    }
+
+    /// Get the cached value for the key. Entries that can not be deserialized into
+    /// the requested type are treated as missing.
+    pub async fn get_value<T: DeserializeOwned>(
+        &self,
+        key: ApplicationCacheKey,
+    ) -> Result<Option<T>> {
+        Ok(self
+            .get_unexpired(key)
+            .await?
+            .and_then(|cache| cache.value)
+            .and_then(|value| serde_json::from_value(value).ok()))
     }
 
     pub async fn delete(&self, key: ApplicationCacheKey) -> Result<bool> {
@@ -68,3 +108,77 @@ impl CacheService {
         Ok(deleted.rows_affected > 0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common_models::UserDashboardSection;
+    use sea_orm::{prelude::DateTimeUtc, DatabaseBackend, MockDatabase, MockExecResult};
+    use serde_json::json;
+
+    use super::*;
+
+    fn section_key(section: UserDashboardSection) -> ApplicationCacheKey {
+        ApplicationCacheKey::UserDashboardSection {
+            section,
+            user_id: "user".to_owned(),
+        }
+    }
+
+    fn cached(section: UserDashboardSection, expires_at: DateTimeUtc) -> application_cache::Model {
+        application_cache::Model {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            key: section_key(section),
+            value: Some(json!(3)),
+            expires_at: Some(expires_at),
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_sections_are_computed_again() {
+        let now = Utc::now();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([
+                vec![cached(
+                    UserDashboardSection::Summary,
+                    now - Duration::hours(1),
+                )],
+                vec![cached(
+                    UserDashboardSection::PendingNotifications,
+                    now + Duration::hours(1),
+                )],
+            ])
+            .into_connection();
+        let cache = CacheService::new(&db);
+        let summary = cache
+            .get_value::<i64>(section_key(UserDashboardSection::Summary))
+            .await
+            .unwrap();
+        assert_eq!(summary, None);
+        let pending_notifications = cache
+            .get_value::<i64>(section_key(UserDashboardSection::PendingNotifications))
+            .await
+            .unwrap();
+        assert_eq!(pending_notifications, Some(3));
+    }
+
+    #[tokio::test]
+    async fn invalidating_a_section_leaves_the_others_cached() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let cache = CacheService::new(&db);
+        let deleted = cache
+            .delete(section_key(UserDashboardSection::PendingNotifications))
+            .await
+            .unwrap();
+        assert!(deleted);
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(log.contains("DELETE FROM"));
+        assert!(log.contains("PendingNotifications"));
+        assert!(!log.contains("Summary"));
+    }
+}
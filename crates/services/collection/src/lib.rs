@@ -1,41 +1,50 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use application_utils::graphql_to_db_order;
 use async_graphql::{Error, Result};
+use chrono::{Duration, Utc};
 use common_models::{
-    ChangeCollectionToEntityInput, DefaultCollection, SearchDetails, StringIdObject,
+    ChangeCollectionToEntityInput, DefaultCollection, SearchDetails, SearchInput, StringIdObject,
 };
 use common_utils::PAGE_SIZE;
 use database_models::{
-    collection, collection_to_entity,
+    collection, collection_to_entity, metadata, metadata_to_genre,
     prelude::{
         Collection, CollectionToEntity, Exercise, Metadata, MetadataGroup, Person, User,
         UserToEntity, Workout,
     },
-    user_to_entity,
+    review, seen, user_to_entity,
 };
 use database_utils::{
     add_entity_to_collection, create_or_update_collection, ilike_sql, item_reviews,
     remove_entity_from_collection,
 };
 use dependent_models::{CollectionContents, SearchResults};
-use enums::EntityLot;
+use enums::{EntityLot, SeenState};
 use media_models::{
-    CollectionContentsInput, CollectionContentsSortBy, CollectionItem,
-    CreateOrUpdateCollectionInput, EntityWithLot,
+    CollectionContentsFilter, CollectionContentsInput, CollectionContentsSortBy, CollectionItem,
+    CreateCollectionShareTokenInput, CreateOrUpdateCollectionInput, EntityWithLot,
+    ReorderCollectionEntityInput, SharedCollection, SharedCollectionItem, SmartCollectionFilter,
+    SmartCollectionSeenStatus, SortInput,
 };
 use migrations::{
     AliasedCollection, AliasedCollectionToEntity, AliasedExercise, AliasedMetadata,
     AliasedMetadataGroup, AliasedPerson, AliasedUser, AliasedUserToEntity,
 };
+use nanoid::nanoid;
 use sea_orm::{
-    ColumnTrait, EntityTrait, ItemsAndPagesNumber, Iterable, JoinType, ModelTrait, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    prelude::DateTimeUtc, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait,
+    ItemsAndPagesNumber, Iterable, JoinType, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait, Select, TransactionTrait,
 };
 use sea_query::{
     extension::postgres::PgExpr, Alias, Condition, Expr, Func, PgFunc, Query, SimpleExpr,
 };
 use supporting_service::SupportingService;
+use uuid::Uuid;
+
+const SHARE_TOKEN_LENGTH: usize = 32;
+const SHARED_COLLECTION_ITEMS_LIMIT: u64 = 500;
 
 pub struct CollectionService(pub Arc<SupportingService>);
 
@@ -148,7 +157,19 @@ impl CollectionService {
         };
 
         let take = input.take.unwrap_or_else(|| PAGE_SIZE.try_into().unwrap());
-        let results = if take != 0 {
+        let results = if let Some(smart_filter) =
+            collection.smart_filter.clone().filter(|_| take != 0)
+        {
+            self.smart_collection_contents(
+                &collection.user_id,
+                smart_filter,
+                filter,
+                search,
+                sort,
+                take,
+            )
+            .await?
+        } else if take != 0 {
             let paginator = CollectionToEntity::find()
                 .left_join(Metadata)
                 .left_join(MetadataGroup)
@@ -208,15 +229,28 @@ impl CollectionService {
                 })
                 .order_by(
                     match sort.by {
+                        CollectionContentsSortBy::Rank => {
+                            Expr::col(collection_to_entity::Column::Rank)
+                        }
                         CollectionContentsSortBy::LastUpdatedOn => {
                             Expr::col(collection_to_entity::Column::LastUpdatedOn)
                         }
                         CollectionContentsSortBy::Title => Expr::expr(Func::coalesce([
-                            Expr::col((AliasedMetadata::Table, AliasedMetadata::Title)).into(),
-                            Expr::col((AliasedMetadataGroup::Table, AliasedMetadataGroup::Title))
+                            Expr::col((AliasedMetadata::Table, metadata::Column::SortTitle)).into(),
+                            Func::lower(Expr::col((
+                                AliasedMetadata::Table,
+                                AliasedMetadata::Title,
+                            )))
+                            .into(),
+                            Func::lower(Expr::col((
+                                AliasedMetadataGroup::Table,
+                                AliasedMetadataGroup::Title,
+                            )))
+                            .into(),
+                            Func::lower(Expr::col((AliasedPerson::Table, AliasedPerson::Name)))
+                                .into(),
+                            Func::lower(Expr::col((AliasedExercise::Table, AliasedExercise::Id)))
                                 .into(),
-                            Expr::col((AliasedPerson::Table, AliasedPerson::Name)).into(),
-                            Expr::col((AliasedExercise::Table, AliasedExercise::Id)).into(),
                         ])),
                         CollectionContentsSortBy::Date => Expr::expr(Func::coalesce([
                             Expr::col((AliasedMetadata::Table, AliasedMetadata::PublishDate))
@@ -276,6 +310,142 @@ impl CollectionService {
         })
     }
 
+    /// Evaluate the filter of a smart collection against the library of its owner.
+    async fn smart_collection_contents(
+        &self,
+        user_id: &String,
+        smart_filter: SmartCollectionFilter,
+        filter: CollectionContentsFilter,
+        search: SearchInput,
+        sort: SortInput<CollectionContentsSortBy>,
+        take: u64,
+    ) -> Result<SearchResults<EntityWithLot>> {
+        let page: u64 = search.page.unwrap_or(1).try_into().unwrap();
+        if filter
+            .entity_lot
+            .is_some_and(|lot| lot != EntityLot::Metadata)
+        {
+            return Ok(SearchResults {
+                details: SearchDetails::default(),
+                items: vec![],
+            });
+        }
+        let library = Query::select()
+            .column(user_to_entity::Column::MetadataId)
+            .from(user_to_entity::Entity)
+            .and_where(user_to_entity::Column::UserId.eq(user_id))
+            .and_where(user_to_entity::Column::MetadataId.is_not_null())
+            .to_owned();
+        let seen_with_state = |state: Option<SeenState>| {
+            let mut query = Query::select()
+                .column(seen::Column::MetadataId)
+                .from(seen::Entity)
+                .and_where(seen::Column::UserId.eq(user_id))
+                .to_owned();
+            if let Some(state) = state {
+                query.and_where(seen::Column::State.eq(state));
+            }
+            query
+        };
+        let paginator = Metadata::find()
+            .select_only()
+            .column(metadata::Column::Id)
+            .filter(metadata::Column::Id.in_subquery(library))
+            .apply_if(smart_filter.lot.or(filter.metadata_lot), |query, v| {
+                query.filter(metadata::Column::Lot.eq(v))
+            })
+            .apply_if(smart_filter.genre_id, |query, v| {
+                query.filter(
+                    metadata::Column::Id.in_subquery(
+                        Query::select()
+                            .column(metadata_to_genre::Column::MetadataId)
+                            .from(metadata_to_genre::Entity)
+                            .and_where(metadata_to_genre::Column::GenreId.eq(v))
+                            .to_owned(),
+                    ),
+                )
+            })
+            .apply_if(smart_filter.publish_year_start, |query, v| {
+                query.filter(metadata::Column::PublishYear.gte(v))
+            })
+            .apply_if(smart_filter.publish_year_end, |query, v| {
+                query.filter(metadata::Column::PublishYear.lte(v))
+            })
+            .apply_if(smart_filter.seen_status, |query, v| {
+                query.filter(match v {
+                    SmartCollectionSeenStatus::NotStarted => {
+                        metadata::Column::Id.not_in_subquery(seen_with_state(None))
+                    }
+                    SmartCollectionSeenStatus::InProgress => metadata::Column::Id
+                        .in_subquery(seen_with_state(Some(SeenState::InProgress))),
+                    SmartCollectionSeenStatus::Completed => metadata::Column::Id
+                        .in_subquery(seen_with_state(Some(SeenState::Completed))),
+                })
+            })
+            .apply_if(smart_filter.minimum_rating, |query, v| {
+                query.filter(
+                    metadata::Column::Id.in_subquery(
+                        Query::select()
+                            .column(review::Column::MetadataId)
+                            .from(review::Entity)
+                            .and_where(review::Column::UserId.eq(user_id))
+                            .and_where(review::Column::MetadataId.is_not_null())
+                            .group_by_col(review::Column::MetadataId)
+                            .and_having(
+                                Expr::expr(Func::avg(Expr::col(review::Column::Rating))).gte(v),
+                            )
+                            .to_owned(),
+                    ),
+                )
+            })
+            .apply_if(search.query, |query, v| {
+                query.filter(Expr::col(metadata::Column::Title).ilike(ilike_sql(&v)))
+            })
+            .order_by(
+                match sort.by {
+                    CollectionContentsSortBy::LastUpdatedOn => {
+                        Expr::col(metadata::Column::LastUpdatedOn)
+                    }
+                    CollectionContentsSortBy::Date => Expr::col(metadata::Column::PublishDate),
+                    // DEV: Smart collections have no ranks, so they are sorted by title
+                    CollectionContentsSortBy::Rank | CollectionContentsSortBy::Title => {
+                        Expr::expr(Func::coalesce([
+                            Expr::col(metadata::Column::SortTitle).into(),
+                            Func::lower(Expr::col(metadata::Column::Title)).into(),
+                        ]))
+                    }
+                },
+                graphql_to_db_order(sort.order),
+            )
+            .order_by_asc(metadata::Column::Id)
+            .into_tuple::<String>()
+            .paginate(&self.0.db, take);
+        let ItemsAndPagesNumber {
+            number_of_items,
+            number_of_pages,
+        } = paginator.num_items_and_pages().await?;
+        let items = paginator
+            .fetch_page(page - 1)
+            .await?
+            .into_iter()
+            .map(|entity_id| EntityWithLot {
+                entity_id,
+                entity_lot: EntityLot::Metadata,
+            })
+            .collect();
+        Ok(SearchResults {
+            details: SearchDetails {
+                total: number_of_items.try_into().unwrap(),
+                next_page: if page < number_of_pages {
+                    Some((page + 1).try_into().unwrap())
+                } else {
+                    None
+                },
+            },
+            items,
+        })
+    }
+
     pub async fn create_or_update_collection(
         &self,
         user_id: &String,
@@ -300,6 +470,125 @@ impl CollectionService {
         Ok(resp.is_ok())
     }
 
+    pub async fn create_collection_share_token(
+        &self,
+        user_id: &String,
+        input: CreateCollectionShareTokenInput,
+    ) -> Result<String> {
+        let collection = self.owned_collection(user_id, &input.collection_id).await?;
+        let expires_on = match input.expires_in_days {
+            Some(days) if days <= 0 => {
+                return Err(Error::new("The token must be valid for at least a day"))
+            }
+            Some(days) => Some(Utc::now() + Duration::days(days)),
+            None => None,
+        };
+        let token = nanoid!(SHARE_TOKEN_LENGTH);
+        let mut collection: collection::ActiveModel = collection.into();
+        collection.share_token = ActiveValue::Set(Some(token.clone()));
+        collection.share_token_expires_on = ActiveValue::Set(expires_on);
+        collection.update(&self.0.db).await?;
+        Ok(token)
+    }
+
+    pub async fn revoke_collection_share_token(
+        &self,
+        user_id: &String,
+        collection_id: String,
+    ) -> Result<bool> {
+        let collection = self.owned_collection(user_id, &collection_id).await?;
+        let mut collection: collection::ActiveModel = collection.into();
+        collection.share_token = ActiveValue::Set(None);
+        collection.share_token_expires_on = ActiveValue::Set(None);
+        collection.update(&self.0.db).await?;
+        Ok(true)
+    }
+
+    async fn owned_collection(
+        &self,
+        user_id: &String,
+        collection_id: &String,
+    ) -> Result<collection::Model> {
+        let collection = Collection::find_by_id(collection_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Collection not found"))?;
+        if &collection.user_id != user_id {
+            return Err(Error::new("This collection does not belong to you"));
+        }
+        Ok(collection)
+    }
+
+    /// The media in the collection the token was created for. Nothing is returned
+    /// for revoked or expired tokens.
+    pub async fn shared_collection(&self, token: String) -> Result<Option<SharedCollection>> {
+        let Some(collection) = shared_collection_by_token(token, Utc::now())
+            .one(&self.0.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let metadata_ids = match collection.smart_filter.clone() {
+            Some(smart_filter) => self
+                .smart_collection_contents(
+                    &collection.user_id,
+                    smart_filter,
+                    CollectionContentsFilter::default(),
+                    SearchInput::default(),
+                    SortInput {
+                        by: CollectionContentsSortBy::Title,
+                        ..Default::default()
+                    },
+                    SHARED_COLLECTION_ITEMS_LIMIT,
+                )
+                .await?
+                .items
+                .into_iter()
+                .map(|e| e.entity_id)
+                .collect(),
+            None => {
+                CollectionToEntity::find()
+                    .select_only()
+                    .column(collection_to_entity::Column::MetadataId)
+                    .filter(collection_to_entity::Column::CollectionId.eq(&collection.id))
+                    .filter(collection_to_entity::Column::MetadataId.is_not_null())
+                    .order_by_asc(collection_to_entity::Column::Rank)
+                    .limit(SHARED_COLLECTION_ITEMS_LIMIT)
+                    .into_tuple::<String>()
+                    .all(&self.0.db)
+                    .await?
+            }
+        };
+        let mut metadata = Metadata::find()
+            .filter(metadata::Column::Id.is_in(metadata_ids.clone()))
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .map(|m| (m.id.clone(), m))
+            .collect::<HashMap<_, _>>();
+        let mut items = vec![];
+        for metadata_id in metadata_ids {
+            let Some(m) = metadata.remove(&metadata_id) else {
+                continue;
+            };
+            let image = match m.images.and_then(|i| i.into_iter().next()) {
+                Some(i) => Some(self.0.file_storage_service.get_stored_asset(i.url).await),
+                None => None,
+            };
+            items.push(SharedCollectionItem {
+                image,
+                lot: m.lot,
+                title: m.title,
+                publish_year: m.publish_year,
+            });
+        }
+        Ok(Some(SharedCollection {
+            items,
+            name: collection.name,
+            description: collection.description,
+        }))
+    }
+
     pub async fn add_entity_to_collection(
         &self,
         user_id: &String,
@@ -308,11 +597,176 @@ impl CollectionService {
         add_entity_to_collection(user_id, input, &self.0).await
     }
 
+    pub async fn reorder_collection_entity(
+        &self,
+        user_id: &String,
+        input: ReorderCollectionEntityInput,
+    ) -> Result<bool> {
+        let collection = Collection::find()
+            .left_join(UserToEntity)
+            .filter(user_to_entity::Column::UserId.eq(user_id))
+            .filter(collection::Column::Name.eq(input.collection_name))
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Collection does not exist"))?;
+        let txn = self.0.db.begin().await?;
+        // DEV: Concurrent reorders wait for each other so that ranks are never shared
+        Collection::find_by_id(&collection.id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+        let entries = CollectionToEntity::find()
+            .select_only()
+            .columns([
+                collection_to_entity::Column::Id,
+                collection_to_entity::Column::EntityId,
+                collection_to_entity::Column::Rank,
+            ])
+            .filter(collection_to_entity::Column::CollectionId.eq(&collection.id))
+            .order_by_asc(collection_to_entity::Column::Rank)
+            .into_tuple::<(Uuid, String, i32)>()
+            .all(&txn)
+            .await?;
+        let changed = reordered_ranks(entries, &input.entity_id, input.new_position)?;
+        for (id, rank) in changed {
+            CollectionToEntity::update_many()
+                .col_expr(collection_to_entity::Column::Rank, Expr::value(rank))
+                .filter(collection_to_entity::Column::Id.eq(id))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(true)
+    }
+
     pub async fn remove_entity_from_collection(
         &self,
         user_id: &String,
         input: ChangeCollectionToEntityInput,
     ) -> Result<StringIdObject> {
-        remove_entity_from_collection(&self.0.db, user_id, input).await
+        remove_entity_from_collection(user_id, input, &self.0).await
+    }
+}
+
+/// The collection that a share token is valid for. Revoked tokens are removed
+/// from the collection, so only the expiry needs to be checked.
+fn shared_collection_by_token(token: String, now: DateTimeUtc) -> Select<Collection> {
+    Collection::find()
+        .filter(collection::Column::ShareToken.eq(token))
+        .filter(
+            Condition::any()
+                .add(collection::Column::ShareTokenExpiresOn.is_null())
+                .add(collection::Column::ShareTokenExpiresOn.gt(now)),
+        )
+}
+
+/// The new ranks of the entities of a collection, ordered by rank, when one of
+/// them moves to a position starting from 1. Only the entities between the old
+/// and new positions change, they take over each other's ranks.
+fn reordered_ranks(
+    mut entries: Vec<(Uuid, String, i32)>,
+    entity_id: &String,
+    new_position: usize,
+) -> Result<Vec<(Uuid, i32)>> {
+    let Some(old_index) = entries.iter().position(|(_, id, _)| id == entity_id) else {
+        return Err(Error::new("Entity is not in this collection"));
+    };
+    let new_index = new_position.clamp(1, entries.len()) - 1;
+    let range = old_index.min(new_index)..=old_index.max(new_index);
+    let ranks = entries[range.clone()]
+        .iter()
+        .map(|(_, _, rank)| *rank)
+        .collect::<Vec<_>>();
+    let entry = entries.remove(old_index);
+    entries.insert(new_index, entry);
+    let changed = entries[range]
+        .iter()
+        .zip(ranks)
+        .filter(|((_, _, old_rank), rank)| old_rank != rank)
+        .map(|((id, _, _), rank)| (*id, rank))
+        .collect();
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::DatabaseBackend;
+
+    use super::*;
+
+    fn entries(ranks: &[i32]) -> Vec<(Uuid, String, i32)> {
+        ranks
+            .iter()
+            .enumerate()
+            .map(|(i, rank)| {
+                (
+                    Uuid::from_u128(i as u128),
+                    format!("movie_{}", i + 1),
+                    *rank,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn moving_an_entity_up_shifts_the_ones_it_passes() {
+        // DEV: Ranks can have gaps after removals, the gaps are kept
+        let changed = reordered_ranks(entries(&[1, 2, 4, 7, 8]), &"movie_4".to_owned(), 2).unwrap();
+        assert_eq!(
+            changed,
+            [
+                (Uuid::from_u128(3), 2),
+                (Uuid::from_u128(1), 4),
+                (Uuid::from_u128(2), 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn moving_an_entity_down_past_the_end_puts_it_last() {
+        let changed = reordered_ranks(entries(&[1, 2, 3]), &"movie_1".to_owned(), 10).unwrap();
+        assert_eq!(
+            changed,
+            [
+                (Uuid::from_u128(1), 1),
+                (Uuid::from_u128(2), 2),
+                (Uuid::from_u128(0), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn reordering_keeps_every_rank_unique() {
+        let ranks = [1, 2, 3, 4, 5];
+        for position in 1..=ranks.len() {
+            let mut after = entries(&ranks);
+            let changed = reordered_ranks(after.clone(), &"movie_3".to_owned(), position).unwrap();
+            for (id, rank) in changed {
+                after.iter_mut().find(|(i, _, _)| *i == id).unwrap().2 = rank;
+            }
+            let mut after = after
+                .into_iter()
+                .map(|(_, _, rank)| rank)
+                .collect::<Vec<_>>();
+            after.sort();
+            assert_eq!(after, ranks);
+        }
+        assert!(reordered_ranks(entries(&ranks), &"movie_9".to_owned(), 1).is_err());
+        assert!(reordered_ranks(entries(&ranks), &"movie_3".to_owned(), 3)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn expired_share_tokens_do_not_find_the_collection() {
+        let now = "2024-03-02T12:00:00Z".parse::<DateTimeUtc>().unwrap();
+        let sql = shared_collection_by_token("abc".to_owned(), now)
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#""collection"."share_token" = 'abc'"#));
+        assert!(sql.contains(concat!(
+            r#"("collection"."share_token_expires_on" IS NULL"#,
+            r#" OR "collection"."share_token_expires_on" > '2024-03-02 12:00:00"#
+        )));
     }
 }
@@ -1,22 +1,31 @@
-use std::{collections::HashMap, fs::File as StdFile, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs::{copy, create_dir_all, read_dir, remove_file, rename, File as StdFile},
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+};
 
 use async_graphql::{Error, Result};
 use background::ApplicationJob;
-use chrono::{DateTime, Utc};
-use common_models::ExportJob;
-use common_utils::{IsFeatureEnabled, TEMP_DIR};
+use chrono::{DateTime, NaiveDate, Utc};
+use common_models::{ApplicationCacheKey, ExportJob, ScheduledExportStatus};
+use common_utils::{ryot_log, IsFeatureEnabled, TEMP_DIR};
 use database_models::{
     prelude::{
-        Exercise, Metadata, MetadataGroup, Person, Seen, UserToEntity, Workout, WorkoutTemplate,
+        Exercise, Metadata, MetadataGroup, Person, Review, Seen, User, UserToEntity, Workout,
+        WorkoutTemplate,
     },
-    seen, user_to_entity, workout, workout_template,
+    review, seen, user, user_to_entity, workout, workout_template,
 };
 use database_utils::{
-    entity_in_collections, item_reviews, user_measurements_list, user_workout_details,
-    user_workout_template_details,
+    admin_account_guard, entity_in_collections, item_reviews, user_measurements_list,
+    user_workout_details, user_workout_template_details,
+};
+use dependent_models::{
+    ImportOrExportWorkoutItem, ImportOrExportWorkoutTemplateItem, COMPLETE_EXPORT_VERSION,
 };
-use dependent_models::{ImportOrExportWorkoutItem, ImportOrExportWorkoutTemplateItem};
-use enums::EntityLot;
+use enums::{EntityLot, MediaLot, MediaSource, SeenState};
 use fitness_models::UserMeasurementsListInput;
 use media_models::{
     ImportOrExportExerciseItem, ImportOrExportItemRating, ImportOrExportItemReview,
@@ -28,14 +37,17 @@ use reqwest::{
     header::{CONTENT_LENGTH, CONTENT_TYPE},
     Body, Client,
 };
+use rust_decimal::Decimal;
 use sea_orm::{
     strum::Display, ColumnTrait, EntityTrait, EnumIter, Iterable, ModelTrait, QueryFilter,
     QueryOrder, QuerySelect,
 };
+use serde::Serialize;
 use struson::writer::{JsonStreamWriter, JsonWriter};
 use supporting_service::SupportingService;
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
+use zip::{write::SimpleFileOptions, ZipWriter};
 
 #[derive(Eq, PartialEq, Copy, Display, Clone, Debug, EnumIter)]
 #[strum(serialize_all = "snake_case")]
@@ -49,6 +61,24 @@ enum ExportItem {
     WorkoutTemplates,
 }
 
+/// A row of the CSV export. The rating is in the scale chosen by the user.
+#[derive(Debug, Serialize)]
+struct MediaCsvRow {
+    title: String,
+    identifier: String,
+    source: MediaSource,
+    publish_year: Option<i32>,
+    rating: Option<Decimal>,
+    times_seen: usize,
+    first_seen_on: Option<NaiveDate>,
+    last_seen_on: Option<NaiveDate>,
+    collections: String,
+    review: Option<String>,
+}
+
+const SCHEDULED_EXPORT_PREFIX: &str = "ryot-export-scheduled-";
+const SCHEDULED_EXPORT_STATUS_EXPIRY_HOURS: i64 = 24 * 30;
+
 pub struct ExporterService(pub Arc<SupportingService>);
 
 impl ExporterService {
@@ -105,39 +135,256 @@ impl ExporterService {
             ));
         }
         let started_at = Utc::now();
+        let export_path = self.write_export(&user_id).await?;
+        let ended_at = Utc::now();
+        let uploaded = self
+            .upload_export(&user_id, &export_path, started_at, ended_at)
+            .await;
+        remove_file(&export_path).ok();
+        uploaded?;
+        Ok(true)
+    }
+
+    /// Export the data of every user to the destinations set in the config. The
+    /// outcome is kept so that admins can see it.
+    pub async fn perform_scheduled_exports(&self) -> Result<()> {
+        if !self.0.config.exporter.is_enabled() {
+            return Ok(());
+        }
+        let started_at = Utc::now();
+        let user_ids = User::find()
+            .select_only()
+            .column(user::Column::Id)
+            .filter(
+                user::Column::IsDisabled
+                    .ne(true)
+                    .or(user::Column::IsDisabled.is_null()),
+            )
+            .into_tuple::<String>()
+            .all(&self.0.db)
+            .await?;
+        let mut exported_users = 0;
+        let mut failures = vec![];
+        for user_id in user_ids {
+            match self.perform_scheduled_export(&user_id).await {
+                Ok(()) => exported_users += 1,
+                Err(e) => {
+                    ryot_log!(
+                        error,
+                        "Scheduled export of user {} failed: {:?}",
+                        user_id,
+                        e
+                    );
+                    failures.push(format!("{}: {}", user_id, e.message));
+                }
+            }
+        }
+        let status = ScheduledExportStatus {
+            started_at,
+            failures,
+            exported_users,
+            ended_at: Utc::now(),
+        };
+        self.0
+            .cache_service
+            .set_value_with_expiry(
+                ApplicationCacheKey::LastScheduledExportStatus,
+                &status,
+                SCHEDULED_EXPORT_STATUS_EXPIRY_HOURS,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn last_scheduled_export_status(
+        &self,
+        user_id: &String,
+    ) -> Result<Option<ScheduledExportStatus>> {
+        admin_account_guard(user_id, &self.0).await?;
+        self.0
+            .cache_service
+            .get_value(ApplicationCacheKey::LastScheduledExportStatus)
+            .await
+    }
+
+    async fn perform_scheduled_export(&self, user_id: &String) -> Result<()> {
+        let started_at = Utc::now();
+        let export_path = self.write_export(user_id).await?;
+        let ended_at = Utc::now();
+        let named_path = export_path.with_file_name(format!(
+            "{}{}.json",
+            SCHEDULED_EXPORT_PREFIX,
+            started_at.format("%Y%m%dT%H%M%SZ")
+        ));
+        // DEV: The temporary file is removed whether the export went through or not
+        let result = match rename(&export_path, &named_path) {
+            Ok(()) => {
+                self.store_scheduled_export(user_id, &named_path, started_at, ended_at)
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        remove_file(&export_path).ok();
+        remove_file(&named_path).ok();
+        result
+    }
+
+    async fn store_scheduled_export(
+        &self,
+        user_id: &String,
+        export_path: &PathBuf,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let config = &self.0.config.exporter;
+        let file_name = export_path
+            .file_name()
+            .ok_or_else(|| Error::new("Export file has no name"))?;
+        if !config.scheduled_directory.is_empty() {
+            let directory = PathBuf::from(&config.scheduled_directory).join(user_id);
+            create_dir_all(&directory)?;
+            copy(export_path, directory.join(file_name))?;
+            let existing = read_dir(&directory)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .filter(|n| n.starts_with(SCHEDULED_EXPORT_PREFIX) && n.ends_with(".json"))
+                .collect::<Vec<_>>();
+            for name in expired_scheduled_exports(existing, config.scheduled_keep_last) {
+                remove_file(directory.join(name))?;
+            }
+        }
+        if config.scheduled_to_file_storage {
+            self.upload_export(user_id, export_path, started_at, ended_at)
+                .await?;
+            let existing = self
+                .0
+                .file_storage_service
+                .list_objects_at_prefix(format!("exports/{}", user_id))
+                .await
+                .into_iter()
+                .map(|(_, key)| key)
+                .filter(|k| k.contains(SCHEDULED_EXPORT_PREFIX))
+                .collect::<Vec<_>>();
+            for key in expired_scheduled_exports(existing, config.scheduled_keep_last) {
+                self.0.file_storage_service.delete_object(key).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Export the media of a user as one CSV file per media type, bundled in a zip
+    /// file in the temporary directory.
+    pub async fn perform_csv_export(&self, user_id: &String) -> Result<PathBuf> {
+        let related_metadata = UserToEntity::find()
+            .filter(user_to_entity::Column::UserId.eq(user_id))
+            .filter(user_to_entity::Column::MetadataId.is_not_null())
+            .find_also_related(Metadata)
+            .all(&self.0.db)
+            .await?;
+        let mut writers: HashMap<MediaLot, csv::Writer<Vec<u8>>> = HashMap::new();
+        for (_, m) in related_metadata {
+            let Some(m) = m else {
+                continue;
+            };
+            let seen_on = m
+                .find_related(Seen)
+                .filter(seen::Column::UserId.eq(user_id))
+                .filter(seen::Column::State.eq(SeenState::Completed))
+                .all(&self.0.db)
+                .await?
+                .into_iter()
+                .map(|s| s.finished_on.or(s.started_on))
+                .collect::<Vec<_>>();
+            let latest_review = item_reviews(user_id, &m.id, EntityLot::Metadata, false, &self.0)
+                .await?
+                .into_iter()
+                .next();
+            let collections =
+                entity_in_collections(&self.0.db, user_id, &m.id, EntityLot::Metadata)
+                    .await?
+                    .into_iter()
+                    .map(|c| c.name)
+                    .collect::<Vec<_>>();
+            let row = MediaCsvRow {
+                title: m.title,
+                source: m.source,
+                identifier: m.identifier,
+                publish_year: m.publish_year,
+                times_seen: seen_on.len(),
+                collections: collections.join(", "),
+                first_seen_on: seen_on.iter().flatten().min().copied(),
+                last_seen_on: seen_on.iter().flatten().max().copied(),
+                rating: latest_review.as_ref().and_then(|r| r.rating),
+                review: latest_review.and_then(|r| r.text_original),
+            };
+            writers
+                .entry(m.lot)
+                .or_insert_with(|| csv::Writer::from_writer(vec![]))
+                .serialize(row)?;
+        }
+        let export_path = PathBuf::from(TEMP_DIR).join(format!("ryot-export-{}.zip", nanoid!()));
+        let mut zip = ZipWriter::new(StdFile::create(&export_path)?);
+        for (lot, writer) in writers {
+            zip.start_file(format!("{}.csv", lot), SimpleFileOptions::default())?;
+            zip.write_all(&writer.into_inner()?)?;
+        }
+        zip.finish()?;
+        Ok(export_path)
+    }
+
+    /// Write all the data of a user to a JSON file in the temporary directory.
+    async fn write_export(&self, user_id: &String) -> Result<PathBuf> {
         let export_path = PathBuf::from(TEMP_DIR).join(format!("ryot-export-{}.json", nanoid!()));
-        let file = std::fs::File::create(&export_path).unwrap();
+        if let Err(e) = self.write_export_to(user_id, &export_path).await {
+            remove_file(&export_path).ok();
+            return Err(e);
+        }
+        Ok(export_path)
+    }
+
+    async fn write_export_to(&self, user_id: &String, export_path: &PathBuf) -> Result<()> {
+        let file = StdFile::create(export_path)?;
         let mut writer = JsonStreamWriter::new(file);
-        writer.begin_object().unwrap();
+        writer.begin_object()?;
+        writer.name("version")?;
+        writer.number_value(COMPLETE_EXPORT_VERSION)?;
         for export in ExportItem::iter() {
             writer.name(&export.to_string())?;
-            writer.begin_array().unwrap();
+            writer.begin_array()?;
             match export {
-                ExportItem::Media => self.export_media(&user_id, &mut writer).await?,
-                ExportItem::People => self.export_people(&user_id, &mut writer).await?,
-                ExportItem::Workouts => self.export_workouts(&user_id, &mut writer).await?,
-                ExportItem::Exercises => self.export_exercises(&user_id, &mut writer).await?,
-                ExportItem::MediaGroups => self.export_media_group(&user_id, &mut writer).await?,
-                ExportItem::Measurements => self.export_measurements(&user_id, &mut writer).await?,
+                ExportItem::Media => self.export_media(user_id, &mut writer).await?,
+                ExportItem::People => self.export_people(user_id, &mut writer).await?,
+                ExportItem::Workouts => self.export_workouts(user_id, &mut writer).await?,
+                ExportItem::Exercises => self.export_exercises(user_id, &mut writer).await?,
+                ExportItem::MediaGroups => self.export_media_group(user_id, &mut writer).await?,
+                ExportItem::Measurements => self.export_measurements(user_id, &mut writer).await?,
                 ExportItem::WorkoutTemplates => {
-                    self.export_workout_templates(&user_id, &mut writer).await?
+                    self.export_workout_templates(user_id, &mut writer).await?
                 }
             };
-            writer.end_array().unwrap();
+            writer.end_array()?;
         }
-        writer.end_object().unwrap();
-        writer.finish_document().unwrap();
-        let ended_at = Utc::now();
+        writer.end_object()?;
+        writer.finish_document()?;
+        Ok(())
+    }
+
+    async fn upload_export(
+        &self,
+        user_id: &String,
+        export_path: &PathBuf,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let file_name = export_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| Error::new("Export file has no name"))?;
         let (_key, url) = self
             .0
             .file_storage_service
             .get_presigned_put_url(
-                export_path
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string(),
+                file_name.to_string(),
                 format!("exports/{}", user_id),
                 false,
                 Some(HashMap::from([
@@ -145,10 +392,11 @@ impl ExporterService {
                     ("ended_at".to_string(), ended_at.to_rfc2822()),
                 ])),
             )
-            .await;
-        let file = File::open(&export_path).await.unwrap();
-        let content_length = file.metadata().await.unwrap().len();
-        let content_type = mime_guess::from_path(&export_path).first_or_octet_stream();
+            .await
+            .ok_or_else(|| Error::new("Could not create an upload URL for the export"))?;
+        let file = File::open(export_path).await?;
+        let content_length = file.metadata().await?.len();
+        let content_type = mime_guess::from_path(export_path).first_or_octet_stream();
         let stream = FramedRead::new(file, BytesCodec::new());
         let body = Body::wrap_stream(stream);
         let client = Client::new();
@@ -160,9 +408,9 @@ impl ExporterService {
             .header("x-amz-meta-ended_at", ended_at.to_rfc2822())
             .body(body)
             .send()
-            .await
-            .unwrap();
-        Ok(true)
+            .await?
+            .error_for_status()?;
+        Ok(())
     }
 
     async fn export_media(
@@ -186,6 +434,8 @@ impl ExporterService {
             let seen_history = m
                 .find_related(Seen)
                 .filter(seen::Column::UserId.eq(user_id))
+                .order_by_asc(seen::Column::LastUpdatedOn)
+                .order_by_asc(seen::Column::Id)
                 .all(&self.0.db)
                 .await
                 .unwrap();
@@ -206,6 +456,9 @@ impl ExporterService {
                         started_on: s.started_on,
                         ended_on: s.finished_on,
                         provider_watched_on: s.provider_watched_on,
+                        edition: s.edition,
+                        pages_read: s.pages_read,
+                        minutes_consumed: s.minutes_consumed,
                         show_season_number,
                         show_episode_number,
                         podcast_episode_number,
@@ -215,11 +468,9 @@ impl ExporterService {
                     }
                 })
                 .collect();
-            let reviews = item_reviews(user_id, &m.id, EntityLot::Metadata, false, &self.0)
-                .await?
-                .into_iter()
-                .map(|r| self.get_review_export_item(r))
-                .collect();
+            let reviews = self
+                .export_reviews(user_id, &m.id, EntityLot::Metadata)
+                .await?;
             let collections =
                 entity_in_collections(&self.0.db, user_id, &m.id, EntityLot::Metadata)
                     .await?
@@ -258,11 +509,9 @@ impl ExporterService {
                 .await
                 .unwrap()
                 .unwrap();
-            let reviews = item_reviews(user_id, &m.id, EntityLot::MetadataGroup, false, &self.0)
-                .await?
-                .into_iter()
-                .map(|r| self.get_review_export_item(r))
-                .collect();
+            let reviews = self
+                .export_reviews(user_id, &m.id, EntityLot::MetadataGroup)
+                .await?;
             let collections =
                 entity_in_collections(&self.0.db, user_id, &m.id, EntityLot::MetadataGroup)
                     .await?
@@ -300,11 +549,9 @@ impl ExporterService {
                 .await
                 .unwrap()
                 .unwrap();
-            let reviews = item_reviews(user_id, &p.id, EntityLot::Person, false, &self.0)
-                .await?
-                .into_iter()
-                .map(|r| self.get_review_export_item(r))
-                .collect();
+            let reviews = self
+                .export_reviews(user_id, &p.id, EntityLot::Person)
+                .await?;
             let collections = entity_in_collections(&self.0.db, user_id, &p.id, EntityLot::Person)
                 .await?
                 .into_iter()
@@ -385,11 +632,9 @@ impl ExporterService {
                 .await
                 .unwrap()
                 .unwrap();
-            let reviews = item_reviews(user_id, &e.id, EntityLot::Exercise, false, &self.0)
-                .await?
-                .into_iter()
-                .map(|r| self.get_review_export_item(r))
-                .collect();
+            let reviews = self
+                .export_reviews(user_id, &e.id, EntityLot::Exercise)
+                .await?;
             let collections =
                 entity_in_collections(&self.0.db, user_id, &e.id, EntityLot::Exercise)
                     .await?
@@ -431,31 +676,213 @@ impl ExporterService {
         Ok(())
     }
 
-    fn get_review_export_item(&self, rev: ReviewItem) -> ImportOrExportItemRating {
-        let (show_season_number, show_episode_number) = match rev.show_extra_information {
-            Some(d) => (Some(d.season), Some(d.episode)),
-            None => (None, None),
-        };
-        let podcast_episode_number = rev.podcast_extra_information.map(|d| d.episode);
-        let anime_episode_number = rev.anime_extra_information.and_then(|d| d.episode);
-        let manga_chapter_number = rev.manga_extra_information.and_then(|d| d.chapter);
-        ImportOrExportItemRating {
-            review: Some(ImportOrExportItemReview {
-                visibility: Some(rev.visibility),
-                date: Some(rev.posted_on),
-                spoiler: Some(rev.is_spoiler),
-                text: rev.text_original,
-            }),
-            rating: rev.rating,
-            show_season_number,
-            show_episode_number,
-            podcast_episode_number,
-            anime_episode_number,
-            manga_chapter_number,
-            comments: match rev.comments.is_empty() {
-                true => None,
-                false => Some(rev.comments),
-            },
+    async fn export_reviews(
+        &self,
+        user_id: &String,
+        entity_id: &String,
+        entity_lot: EntityLot,
+    ) -> Result<Vec<ImportOrExportItemRating>> {
+        let reviews = item_reviews(user_id, entity_id, entity_lot, false, &self.0).await?;
+        let ratings = Review::find()
+            .select_only()
+            .column(review::Column::Id)
+            .column(review::Column::Rating)
+            .filter(review::Column::Id.is_in(reviews.iter().map(|r| r.id.clone())))
+            .into_tuple::<(String, Option<Decimal>)>()
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        Ok(reviews
+            .into_iter()
+            .map(|r| {
+                let rating = ratings.get(&r.id).copied().flatten();
+                review_export_item(r, rating)
+            })
+            .collect())
+    }
+}
+
+/// The file names or storage keys of the scheduled exports to remove so that only
+/// the newest `keep_last` are left.
+fn expired_scheduled_exports(mut exports: Vec<String>, keep_last: usize) -> Vec<String> {
+    // DEV: Storage keys start with a random id, so they are sorted by the timestamp in
+    // the file name, which sorts the same way as the time
+    exports.sort_by_key(|e| e.rsplit(SCHEDULED_EXPORT_PREFIX).next().map(String::from));
+    exports.truncate(exports.len().saturating_sub(keep_last));
+    exports
+}
+
+/// A review as written to an export. Imports expect ratings out of 100, so the
+/// stored rating is used instead of the one in the review scale of the user.
+fn review_export_item(rev: ReviewItem, stored_rating: Option<Decimal>) -> ImportOrExportItemRating {
+    let (show_season_number, show_episode_number) = match rev.show_extra_information {
+        Some(d) => (Some(d.season), Some(d.episode)),
+        None => (None, None),
+    };
+    let podcast_episode_number = rev.podcast_extra_information.map(|d| d.episode);
+    let anime_episode_number = rev.anime_extra_information.and_then(|d| d.episode);
+    let manga_chapter_number = rev.manga_extra_information.and_then(|d| d.chapter);
+    ImportOrExportItemRating {
+        review: Some(ImportOrExportItemReview {
+            visibility: Some(rev.visibility),
+            date: Some(rev.posted_on),
+            spoiler: Some(rev.is_spoiler),
+            text: rev.text_original,
+        }),
+        rating: stored_rating,
+        show_season_number,
+        show_episode_number,
+        podcast_episode_number,
+        anime_episode_number,
+        manga_chapter_number,
+        comments: match rev.comments.is_empty() {
+            true => None,
+            false => Some(rev.comments),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_models::IdAndNamedObject;
+    use enums::Visibility;
+    use media_models::SeenShowExtraInformation;
+
+    use super::*;
+
+    fn round_trip(row: MediaCsvRow) -> (csv::StringRecord, csv::StringRecord) {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(row).unwrap();
+        let data = writer.into_inner().unwrap();
+        let mut reader = csv::Reader::from_reader(data.as_slice());
+        let headers = reader.headers().unwrap().clone();
+        let mut records = reader.records().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        (headers, records.remove(0))
+    }
+
+    #[test]
+    fn media_csv_row_keeps_special_characters() {
+        let review = "Great, \"really\" great.\nWould watch again 🍿";
+        let (headers, record) = round_trip(MediaCsvRow {
+            title: "Amélie, or \"Le Fabuleux Destin\"".to_owned(),
+            identifier: "194".to_owned(),
+            source: MediaSource::Tmdb,
+            publish_year: Some(2001),
+            rating: Some(Decimal::new(855, 1)),
+            times_seen: 2,
+            first_seen_on: NaiveDate::from_ymd_opt(2020, 2, 29),
+            last_seen_on: NaiveDate::from_ymd_opt(2024, 1, 1),
+            collections: "Watchlist, 映画".to_owned(),
+            review: Some(review.to_owned()),
+        });
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            vec![
+                "title",
+                "identifier",
+                "source",
+                "publish_year",
+                "rating",
+                "times_seen",
+                "first_seen_on",
+                "last_seen_on",
+                "collections",
+                "review"
+            ]
+        );
+        assert_eq!(&record[0], "Amélie, or \"Le Fabuleux Destin\"");
+        assert_eq!(&record[3], "2001");
+        assert_eq!(&record[4], "85.5");
+        assert_eq!(&record[5], "2");
+        assert_eq!(&record[6], "2020-02-29");
+        assert_eq!(&record[7], "2024-01-01");
+        assert_eq!(&record[8], "Watchlist, 映画");
+        assert_eq!(&record[9], review);
+    }
+
+    #[test]
+    fn media_csv_row_writes_empty_fields_for_missing_values() {
+        let (_, record) = round_trip(MediaCsvRow {
+            title: "Untitled".to_owned(),
+            identifier: "1".to_owned(),
+            source: MediaSource::Custom,
+            publish_year: None,
+            rating: None,
+            times_seen: 0,
+            first_seen_on: None,
+            last_seen_on: None,
+            collections: String::new(),
+            review: None,
+        });
+        assert_eq!(record.len(), 10);
+        for index in [3, 4, 6, 7, 8, 9] {
+            assert_eq!(&record[index], "");
         }
     }
+
+    #[test]
+    fn reviews_are_exported_with_the_stored_rating() {
+        // DEV: Shown as 4 out of 5 stars to a user on the five star scale
+        let review = ReviewItem {
+            id: "rev_1".to_owned(),
+            is_spoiler: false,
+            posted_on: Utc::now(),
+            visibility: Visibility::Public,
+            rating: Some(Decimal::from(4)),
+            posted_by: IdAndNamedObject {
+                id: "user_1".to_owned(),
+                name: "User".to_owned(),
+            },
+            text_original: Some("Loved the finale".to_owned()),
+            text_rendered: Some("<p>Loved the finale</p>".to_owned()),
+            seen_items_associated_with: vec![],
+            comments: vec![],
+            comments_count: 0,
+            show_extra_information: Some(SeenShowExtraInformation {
+                season: 2,
+                episode: 10,
+            }),
+            podcast_extra_information: None,
+            anime_extra_information: None,
+            manga_extra_information: None,
+        };
+        let exported = review_export_item(review, Some(Decimal::from(80)));
+        assert_eq!(exported.rating, Some(Decimal::from(80)));
+        assert_eq!(
+            (exported.show_season_number, exported.show_episode_number),
+            (Some(2), Some(10))
+        );
+        let text = exported.review.unwrap().text;
+        assert_eq!(text.as_deref(), Some("Loved the finale"));
+        assert!(exported.comments.is_none());
+    }
+
+    #[test]
+    fn only_the_newest_scheduled_exports_are_kept() {
+        let keys = [
+            "exports/user_1/zz9/ryot-export-scheduled-20240303T000000Z.json",
+            "exports/user_1/aa1/ryot-export-scheduled-20240301T000000Z.json",
+            "exports/user_1/mm5/ryot-export-scheduled-20240302T000000Z.json",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            expired_scheduled_exports(keys.clone(), 2),
+            ["exports/user_1/aa1/ryot-export-scheduled-20240301T000000Z.json"]
+        );
+        assert_eq!(expired_scheduled_exports(keys.clone(), 0).len(), 3);
+        assert!(expired_scheduled_exports(keys, 5).is_empty());
+        let names = [
+            "ryot-export-scheduled-20240302T000000Z.json",
+            "ryot-export-scheduled-20231231T235959Z.json",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            expired_scheduled_exports(names, 1),
+            ["ryot-export-scheduled-20231231T235959Z.json"]
+        );
+    }
 }
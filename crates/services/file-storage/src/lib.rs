@@ -1,21 +1,59 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
 
-use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::{presigning::PresigningConfig, primitives::ByteStream};
 use chrono::Duration;
 use common_models::StoredUrl;
+use common_utils::{normalize_image_url, DATA_DIR};
 use nanoid::nanoid;
 
+/// The path of an object in a local directory. Keys that could point outside of
+/// the directory are rejected.
+fn local_object_path(dir: &Path, key: &str) -> Option<PathBuf> {
+    let relative = Path::new(key);
+    let is_safe = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    (is_safe && !key.is_empty()).then(|| dir.join(relative))
+}
+
+fn store_local_object(dir: &Path, key: &str, body: Vec<u8>) -> bool {
+    let Some(path) = local_object_path(dir, key) else {
+        return false;
+    };
+    if path.exists() {
+        return true;
+    }
+    path.parent()
+        .map_or(true, |p| fs::create_dir_all(p).is_ok())
+        && fs::write(path, body).is_ok()
+}
+
+/// Read an object that was stored in the local data directory.
+pub fn read_local_object(key: &str) -> Option<Vec<u8>> {
+    fs::read(local_object_path(Path::new(DATA_DIR), key)?).ok()
+}
+
 #[derive(Debug)]
 pub struct FileStorageService {
     s3_client: aws_sdk_s3::Client,
     bucket_name: String,
+    image_proxy_url: String,
 }
 
 impl FileStorageService {
-    pub fn new(s3_client: aws_sdk_s3::Client, bucket_name: String) -> Self {
+    pub fn new(
+        s3_client: aws_sdk_s3::Client,
+        bucket_name: String,
+        image_proxy_url: String,
+    ) -> Self {
         Self {
             s3_client,
             bucket_name,
+            image_proxy_url,
         }
     }
 
@@ -53,13 +91,34 @@ impl FileStorageService {
             .is_ok()
     }
 
+    pub async fn object_exists(&self, key: &str) -> bool {
+        self.s3_client
+            .head_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await
+            .is_ok()
+    }
+
+    pub async fn put_object(&self, key: &str, body: Vec<u8>) -> bool {
+        self.s3_client
+            .put_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .is_ok()
+    }
+
     pub async fn get_presigned_put_url(
         &self,
         filename: String,
         prefix: String,
         with_uploads: bool,
         metadata: Option<HashMap<String, String>>,
-    ) -> (String, String) {
+    ) -> Option<(String, String)> {
         let first = if with_uploads { "uploads/" } else { "" };
         let key = format!("{}{}/{}-{}", first, prefix, nanoid!(10), filename);
         let url = self
@@ -73,10 +132,10 @@ impl FileStorageService {
                     .unwrap(),
             )
             .await
-            .unwrap()
+            .ok()?
             .uri()
             .to_string();
-        (key, url)
+        Some((key, url))
     }
 
     pub async fn list_objects_at_prefix(&self, prefix: String) -> Vec<(i64, String)> {
@@ -106,10 +165,75 @@ impl FileStorageService {
             .unwrap()
     }
 
+    fn is_stored_locally(&self, key: &str) -> bool {
+        local_object_path(Path::new(DATA_DIR), key).map_or(false, |p| p.is_file())
+    }
+
+    /// Store an object in the bucket, or in the local data directory when file
+    /// storage is not configured. Objects that already exist are not written again.
+    pub async fn store_object(&self, key: &str, body: Vec<u8>) -> bool {
+        if !self.is_enabled().await {
+            return store_local_object(Path::new(DATA_DIR), key, body);
+        }
+        self.object_exists(key).await || self.put_object(key, body).await
+    }
+
+    pub async fn stored_object_exists(&self, key: &str) -> bool {
+        self.is_stored_locally(key) || self.object_exists(key).await
+    }
+
+    pub async fn delete_stored_object(&self, key: String) -> bool {
+        match local_object_path(Path::new(DATA_DIR), &key) {
+            Some(path) if path.is_file() => fs::remove_file(path).is_ok(),
+            _ => self.delete_object(key).await,
+        }
+    }
+
     pub async fn get_stored_asset(&self, url: StoredUrl) -> String {
         match url {
-            StoredUrl::Url(u) => u,
+            StoredUrl::Url(u) => normalize_image_url(u, &self.image_proxy_url),
+            StoredUrl::S3(u) if self.is_stored_locally(&u) => format!("/backend/images/{}", u),
             StoredUrl::S3(u) => self.get_presigned_url(u).await,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ryot-file-storage-{}", nanoid!(8)))
+    }
+
+    #[test]
+    fn local_object_path_rejects_keys_outside_the_directory() {
+        let dir = Path::new("/srv/data");
+        assert_eq!(
+            local_object_path(dir, "metadata-images/abc.png"),
+            Some(PathBuf::from("/srv/data/metadata-images/abc.png"))
+        );
+        assert_eq!(local_object_path(dir, "../secrets"), None);
+        assert_eq!(
+            local_object_path(dir, "metadata-images/../../secrets"),
+            None
+        );
+        assert_eq!(local_object_path(dir, "/etc/passwd"), None);
+        assert_eq!(local_object_path(dir, ""), None);
+    }
+
+    #[test]
+    fn store_local_object_keeps_one_copy_of_identical_uploads() {
+        let dir = test_dir();
+        let key = "metadata-images/abc.png";
+        // DEV: Two media items uploading the same file end up with the same key
+        assert!(store_local_object(&dir, key, b"first".to_vec()));
+        assert!(store_local_object(&dir, key, b"second".to_vec()));
+        assert_eq!(fs::read(dir.join(key)).unwrap(), b"first");
+        assert_eq!(
+            fs::read_dir(dir.join("metadata-images")).unwrap().count(),
+            1
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
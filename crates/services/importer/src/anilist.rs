@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use application_utils::get_base_http_client;
+use async_graphql::{Error, Result};
+use chrono::NaiveDate;
+use common_models::DefaultCollection;
+use common_utils::{ryot_log, APPLICATION_JSON_HEADER};
+use convert_case::{Case, Casing};
+use dependent_models::ImportResult;
+use enums::{ImportSource, MediaLot, MediaSource, Visibility};
+use media_models::{
+    DeployAnilistImportInput, ImportOrExportItemRating, ImportOrExportItemReview,
+    ImportOrExportMediaItem, ImportOrExportMediaItemSeen,
+};
+use reqwest::header::CONTENT_TYPE;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{ImportFailStep, ImportFailedItem};
+
+const API_URL: &str = "https://graphql.anilist.co";
+const QUERY: &str = r#"
+query ($userName: String, $type: MediaType) {
+  MediaListCollection(userName: $userName, type: $type) {
+    user {
+      mediaListOptions {
+        scoreFormat
+      }
+    }
+    lists {
+      name
+      isCustomList
+      entries {
+        status
+        score
+        progress
+        repeat
+        notes
+        startedAt { year month day }
+        completedAt { year month day }
+        media {
+          id
+          title {
+            userPreferred
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct FuzzyDate {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+impl FuzzyDate {
+    fn date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year?, self.month?, self.day.unwrap_or(1))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Title {
+    #[serde(rename = "userPreferred")]
+    user_preferred: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Media {
+    id: i64,
+    title: Title,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    status: String,
+    score: Decimal,
+    progress: Option<i32>,
+    repeat: Option<i32>,
+    notes: Option<String>,
+    started_at: FuzzyDate,
+    completed_at: FuzzyDate,
+    media: Option<Media>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct List {
+    name: String,
+    is_custom_list: bool,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListOptions {
+    score_format: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    media_list_options: ListOptions,
+}
+
+#[derive(Debug, Deserialize)]
+struct Collection {
+    user: User,
+    lists: Vec<List>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CollectionData {
+    media_list_collection: Collection,
+}
+
+#[derive(Debug, Deserialize)]
+struct CollectionResponse {
+    data: Option<CollectionData>,
+}
+
+/// AniList stores scores in the format chosen by the user, which is converted
+/// to the internal rating out of 100.
+fn scale_score(score: Decimal, score_format: &str) -> Option<Decimal> {
+    if score <= dec!(0) {
+        return None;
+    }
+    Some(match score_format {
+        "POINT_10" | "POINT_10_DECIMAL" => score * dec!(10),
+        "POINT_5" => score * dec!(20),
+        // DEV: AniList uses the same values when it converts smileys to other formats
+        "POINT_3" => match score.round().to_i64().unwrap_or_default() {
+            1 => dec!(35),
+            2 => dec!(60),
+            _ => dec!(85),
+        },
+        _ => score,
+    })
+}
+
+fn convert_entry(entry: Entry, lot: MediaLot, score_format: &str) -> ImportOrExportMediaItem {
+    let media = entry.media.unwrap();
+    let mut collections = vec![];
+    match entry.status.as_str() {
+        "PLANNING" => collections.push(DefaultCollection::Watchlist.to_string()),
+        "CURRENT" | "REPEATING" => collections.push(DefaultCollection::InProgress.to_string()),
+        "COMPLETED" => {}
+        status => collections.push(status.to_case(Case::Title)),
+    }
+    let started_on = entry.started_at.date();
+    let ended_on = entry.completed_at.date();
+    let seen_item = || ImportOrExportMediaItemSeen {
+        started_on,
+        ended_on,
+        provider_watched_on: Some(ImportSource::Anilist.to_string()),
+        ..Default::default()
+    };
+    let mut seen_history = vec![];
+    if entry.status != "PLANNING" {
+        seen_history.extend((1..entry.progress.unwrap_or_default() + 1).map(|i| {
+            let (anime_episode_number, manga_chapter_number) = match lot {
+                MediaLot::Anime => (Some(i), None),
+                _ => (None, Some(Decimal::from(i))),
+            };
+            ImportOrExportMediaItemSeen {
+                anime_episode_number,
+                manga_chapter_number,
+                ..seen_item()
+            }
+        }));
+        // DEV: Every rewatch of the whole media is one more seen entry
+        seen_history.extend((0..entry.repeat.unwrap_or_default()).map(|_| seen_item()));
+    }
+    let rating = scale_score(entry.score, score_format);
+    let review =
+        entry
+            .notes
+            .filter(|n| !n.trim().is_empty())
+            .map(|text| ImportOrExportItemReview {
+                text: Some(text),
+                spoiler: Some(false),
+                visibility: Some(Visibility::Private),
+                date: None,
+            });
+    let mut reviews = vec![];
+    if rating.is_some() || review.is_some() {
+        reviews.push(ImportOrExportItemRating {
+            rating,
+            review,
+            ..Default::default()
+        });
+    }
+    ImportOrExportMediaItem {
+        lot,
+        reviews,
+        collections,
+        seen_history,
+        source: MediaSource::Anilist,
+        identifier: media.id.to_string(),
+        source_id: media.title.user_preferred,
+    }
+}
+
+pub async fn import(input: DeployAnilistImportInput) -> Result<ImportResult> {
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let client = get_base_http_client(Some(vec![(CONTENT_TYPE, APPLICATION_JSON_HEADER.clone())]));
+    for (lot, media_type) in [(MediaLot::Anime, "ANIME"), (MediaLot::Manga, "MANGA")] {
+        ryot_log!(
+            debug,
+            "Fetching the {media_type} lists of {}",
+            input.username
+        );
+        let rsp = client
+            .post(API_URL)
+            .json(&json!({
+                "query": QUERY,
+                "variables": { "userName": input.username, "type": media_type }
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        let Some(data) = rsp.json::<CollectionResponse>().await?.data else {
+            return Err(Error::new(format!(
+                "Could not get the lists of {}, the profile might be private",
+                input.username
+            )));
+        };
+        let collection = data.media_list_collection;
+        let score_format = collection.user.media_list_options.score_format;
+        let mut items: HashMap<i64, ImportOrExportMediaItem> = HashMap::new();
+        // DEV: Entries of custom lists are copies of the ones in the status lists, so
+        // they only add a collection
+        let (custom_lists, status_lists): (Vec<_>, Vec<_>) =
+            collection.lists.into_iter().partition(|l| l.is_custom_list);
+        for entry in status_lists.into_iter().flat_map(|l| l.entries) {
+            let Some(id) = entry.media.as_ref().map(|m| m.id) else {
+                failed_items.push(ImportFailedItem {
+                    lot: Some(lot),
+                    step: ImportFailStep::ItemDetailsFromSource,
+                    identifier: entry.status,
+                    error: Some("Entry is not linked to an AniList media".to_owned()),
+                });
+                continue;
+            };
+            items.insert(id, convert_entry(entry, lot, &score_format));
+        }
+        for list in custom_lists {
+            for entry in list.entries {
+                let item = entry.media.and_then(|m| items.get_mut(&m.id));
+                if let Some(item) = item {
+                    item.collections.push(list.name.clone());
+                }
+            }
+        }
+        media.extend(items.into_values());
+    }
+    Ok(ImportResult {
+        metadata: media,
+        failed_items,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_score_converts_each_format_to_a_rating_out_of_100() {
+        assert_eq!(scale_score(dec!(100), "POINT_100"), Some(dec!(100)));
+        assert_eq!(scale_score(dec!(7.5), "POINT_10_DECIMAL"), Some(dec!(75)));
+        assert_eq!(scale_score(dec!(8), "POINT_10"), Some(dec!(80)));
+        assert_eq!(scale_score(dec!(4), "POINT_5"), Some(dec!(80)));
+    }
+
+    #[test]
+    fn scale_score_maps_smileys_to_fixed_ratings() {
+        assert_eq!(scale_score(dec!(1), "POINT_3"), Some(dec!(35)));
+        assert_eq!(scale_score(dec!(2), "POINT_3"), Some(dec!(60)));
+        assert_eq!(scale_score(dec!(3), "POINT_3"), Some(dec!(85)));
+    }
+
+    #[test]
+    fn scale_score_ignores_unscored_entries() {
+        assert_eq!(scale_score(dec!(0), "POINT_10"), None);
+        assert_eq!(scale_score(dec!(-1), "POINT_100"), None);
+    }
+}
@@ -1,8 +1,11 @@
-use std::future::Future;
+use std::{collections::HashMap, future::Future};
 
 use anyhow::anyhow;
-use application_utils::{get_base_http_client, get_podcast_episode_number_by_name};
+use application_utils::{
+    get_base_http_client, get_podcast_episode_number_by_name, text::title_key,
+};
 use async_graphql::Result;
+use chrono::{DateTime, NaiveDate};
 use common_utils::ryot_log;
 use data_encoding::BASE64;
 use database_models::metadata;
@@ -17,14 +20,106 @@ use reqwest::{
     header::{HeaderValue, AUTHORIZATION},
     Client,
 };
+use rust_decimal_macros::dec;
 use serde_json::json;
 use specific_models::audiobookshelf as audiobookshelf_models;
+use traits::MediaProvider;
 
 use super::{ImportFailStep, ImportFailedItem};
 
+fn timestamp_to_date(timestamp: Option<i64>) -> Option<NaiveDate> {
+    timestamp
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|d| d.date_naive())
+}
+
+const LISTENING_SESSIONS_PAGE_SIZE: i32 = 100;
+
+/// The item and episode that a listening session was for.
+type SessionKey = (String, Option<String>);
+
+/// Turn the listening progress of an item into a seen entry. Finished items are
+/// completed on the day they were last listened to, or the day they were marked
+/// as finished when there is no listening session. The others are partially done.
+fn progress_to_seen(
+    progress: Option<audiobookshelf_models::ItemProgress>,
+    is_ebook: bool,
+    last_listened_at: Option<i64>,
+) -> ImportOrExportMediaItemSeen {
+    let mut seen = ImportOrExportMediaItemSeen {
+        provider_watched_on: Some(ImportSource::Audiobookshelf.to_string()),
+        ..Default::default()
+    };
+    let Some(progress) = progress else {
+        return seen;
+    };
+    seen.started_on = timestamp_to_date(progress.started_at);
+    if progress.is_finished {
+        seen.ended_on = timestamp_to_date(last_listened_at.or(progress.finished_at));
+        return seen;
+    }
+    let fraction = match (is_ebook, progress.current_time, progress.duration) {
+        (true, _, _) => progress.ebook_progress.unwrap_or(progress.progress),
+        (false, Some(current), Some(duration)) if duration > dec!(0) => current / duration,
+        _ => progress.progress,
+    };
+    seen.progress = Some((fraction * dec!(100)).round_dp(2).min(dec!(100)));
+    seen
+}
+
+/// Items without an external id are looked up by their title and author. Only
+/// a result with the same title is used, so that a loose match does not import
+/// the progress into another work.
+async fn search_identifier(
+    metadata: &audiobookshelf_models::ItemMetadata,
+    provider: &(dyn MediaProvider + Send + Sync),
+) -> Option<String> {
+    let query = format!(
+        "{} {}",
+        metadata.title,
+        metadata.author_name.clone().unwrap_or_default()
+    );
+    let title = title_key(&metadata.title);
+    provider
+        .metadata_search(query.trim(), None, true)
+        .await
+        .ok()?
+        .items
+        .into_iter()
+        .find(|i| title_key(&i.title) == title)
+        .map(|i| i.identifier)
+}
+
+/// When each item, and each episode of a podcast, was last listened to.
+async fn get_last_listened_at(client: &Client, url: &str) -> Result<HashMap<SessionKey, i64>> {
+    let mut last_listened_at = HashMap::new();
+    let mut page = 0;
+    loop {
+        let response = client
+            .get(format!("{}/me/listening-sessions", url))
+            .query(&json!({ "page": page, "itemsPerPage": LISTENING_SESSIONS_PAGE_SIZE }))
+            .send()
+            .await
+            .map_err(|e| anyhow!(e))?
+            .json::<audiobookshelf_models::ListeningSessionsResponse>()
+            .await?;
+        for session in response.sessions {
+            let key = (session.library_item_id, session.episode_id);
+            let at = last_listened_at.entry(key).or_insert(session.updated_at);
+            *at = (*at).max(session.updated_at);
+        }
+        page += 1;
+        if page >= response.num_pages {
+            break;
+        }
+    }
+    Ok(last_listened_at)
+}
+
 pub async fn import<F>(
     input: DeployUrlAndKeyImportInput,
     isbn_service: &GoogleBooksService,
+    audible_service: &(dyn MediaProvider + Send + Sync),
     commit_metadata: impl Fn(CommitMediaInput) -> F,
 ) -> Result<ImportResult>
 where
@@ -37,6 +132,7 @@ where
         AUTHORIZATION,
         HeaderValue::from_str(&format!("Bearer {}", input.api_key)).unwrap(),
     )]));
+    let last_listened_at = get_last_listened_at(&client, &url).await?;
     let libraries_resp = client
         .get(format!("{}/libraries", url))
         .send()
@@ -47,118 +143,132 @@ where
         .unwrap();
     for library in libraries_resp.libraries {
         ryot_log!(debug, "Importing library {:?}", library.name.unwrap());
-        let mut query = json!({ "expanded": "1" });
-        if let Some(audiobookshelf_models::MediaType::Book) = library.media_type {
-            query["filter"] = json!(format!("progress.{}", BASE64.encode(b"finished")));
+        let filters = match library.media_type {
+            Some(audiobookshelf_models::MediaType::Book) => {
+                vec![Some("finished"), Some("in-progress")]
+            }
+            _ => vec![None],
+        };
+        let mut items = vec![];
+        for filter in filters {
+            let mut query = json!({ "expanded": "1" });
+            if let Some(filter) = filter {
+                query["filter"] = json!(format!("progress.{}", BASE64.encode(filter.as_bytes())));
+            }
+            let response = client
+                .get(&format!("{}/libraries/{}/items", url, library.id))
+                .query(&query)
+                .send()
+                .await
+                .map_err(|e| anyhow!(e))?
+                .json::<audiobookshelf_models::ListResponse>()
+                .await
+                .unwrap();
+            items.extend(response.results);
         }
-        let finished_items = client
-            .get(&format!("{}/libraries/{}/items", url, library.id))
-            .query(&query)
-            .send()
-            .await
-            .map_err(|e| anyhow!(e))?
-            .json::<audiobookshelf_models::ListResponse>()
-            .await
-            .unwrap();
-        let len = finished_items.results.len();
-        for (idx, item) in finished_items.results.into_iter().enumerate() {
+        let len = items.len();
+        for (idx, item) in items.into_iter().enumerate() {
             let metadata = item.media.clone().unwrap().metadata;
             let title = metadata.title.clone();
             ryot_log!(debug, "Importing item {:?} ({}/{})", title, idx + 1, len);
-            let (identifier, lot, source, episodes) =
-                if Some("epub".to_string()) == item.media.as_ref().unwrap().ebook_format {
-                    match &metadata.isbn {
-                        Some(isbn) => match isbn_service.id_from_isbn(isbn).await {
-                            Some(id) => (id, MediaLot::Book, MediaSource::GoogleBooks, None),
-                            _ => {
-                                failed_items.push(ImportFailedItem {
-                                    error: Some("No Google Books ID found".to_string()),
-                                    identifier: title,
-                                    lot: None,
-                                    step: ImportFailStep::InputTransformation,
-                                });
+            let is_ebook = Some("epub".to_string()) == item.media.as_ref().unwrap().ebook_format;
+            let (identifier, lot, source, episodes) = if is_ebook {
+                let mut identifier = None;
+                if let Some(isbn) = &metadata.isbn {
+                    identifier = isbn_service.id_from_isbn(isbn).await;
+                }
+                if identifier.is_none() {
+                    identifier = search_identifier(&metadata, isbn_service).await;
+                }
+                match identifier {
+                    Some(id) => (id, MediaLot::Book, MediaSource::GoogleBooks, None),
+                    None => {
+                        failed_items.push(ImportFailedItem {
+                            error: Some("No Google Books ID found".to_string()),
+                            identifier: title,
+                            lot: Some(MediaLot::Book),
+                            step: ImportFailStep::MediaDetailsFromProvider,
+                        });
+                        continue;
+                    }
+                }
+            } else if let Some(asin) = metadata.asin.clone() {
+                (asin, MediaLot::AudioBook, MediaSource::Audible, None)
+            } else if let Some(itunes_id) = metadata.itunes_id.clone() {
+                let item_details = get_item_details(&client, &url, &item.id, None).await?;
+                match item_details.media.and_then(|m| m.episodes) {
+                    Some(episodes) => {
+                        let lot = MediaLot::Podcast;
+                        let source = MediaSource::Itunes;
+                        let mut to_return = vec![];
+                        for episode in episodes {
+                            ryot_log!(debug, "Importing episode {:?}", episode.title);
+                            let episode_details = get_item_details(
+                                &client,
+                                &url,
+                                &item.id,
+                                Some(episode.id.clone().unwrap()),
+                            )
+                            .await?;
+                            let Some(progress) = episode_details.user_media_progress else {
                                 continue;
+                            };
+                            let podcast = commit_metadata(CommitMediaInput {
+                                identifier: itunes_id.clone(),
+                                lot,
+                                source,
+                                ..Default::default()
+                            })
+                            .await?;
+                            if let Some(pe) = podcast.podcast_specifics.and_then(|p| {
+                                get_podcast_episode_number_by_name(&p, &episode.title)
+                            }) {
+                                let key = (item.id.clone(), episode.id.clone());
+                                to_return.push((
+                                    pe,
+                                    Some(progress),
+                                    last_listened_at.get(&key).copied(),
+                                ));
                             }
-                        },
-                        _ => {
-                            failed_items.push(ImportFailedItem {
-                                error: Some("No ISBN found".to_string()),
-                                identifier: title,
-                                lot: None,
-                                step: ImportFailStep::InputTransformation,
-                            });
-                            continue;
                         }
+                        (itunes_id, lot, source, Some(to_return))
                     }
-                } else if let Some(asin) = metadata.asin.clone() {
-                    (asin, MediaLot::AudioBook, MediaSource::Audible, None)
-                } else if let Some(itunes_id) = metadata.itunes_id.clone() {
-                    let item_details = get_item_details(&client, &url, &item.id, None).await?;
-                    match item_details.media.and_then(|m| m.episodes) {
-                        Some(episodes) => {
-                            let lot = MediaLot::Podcast;
-                            let source = MediaSource::Itunes;
-                            let mut to_return = vec![];
-                            for episode in episodes {
-                                ryot_log!(debug, "Importing episode {:?}", episode.title);
-                                let episode_details = get_item_details(
-                                    &client,
-                                    &url,
-                                    &item.id,
-                                    Some(episode.id.unwrap()),
-                                )
-                                .await?;
-                                if let Some(true) =
-                                    episode_details.user_media_progress.map(|u| u.is_finished)
-                                {
-                                    let podcast = commit_metadata(CommitMediaInput {
-                                        identifier: itunes_id.clone(),
-                                        lot,
-                                        source,
-                                        ..Default::default()
-                                    })
-                                    .await?;
-                                    if let Some(pe) = podcast.podcast_specifics.and_then(|p| {
-                                        get_podcast_episode_number_by_name(&p, &episode.title)
-                                    }) {
-                                        to_return.push(pe);
-                                    }
-                                }
-                            }
-                            (itunes_id, lot, source, Some(to_return))
-                        }
-                        _ => {
-                            failed_items.push(ImportFailedItem {
-                                error: Some("No episodes found for podcast".to_string()),
-                                identifier: title,
-                                lot: Some(MediaLot::Podcast),
-                                step: ImportFailStep::ItemDetailsFromSource,
-                            });
-                            continue;
-                        }
+                    _ => {
+                        failed_items.push(ImportFailedItem {
+                            error: Some("No episodes found for podcast".to_string()),
+                            identifier: title,
+                            lot: Some(MediaLot::Podcast),
+                            step: ImportFailStep::ItemDetailsFromSource,
+                        });
+                        continue;
                     }
-                } else {
-                    ryot_log!(
-                        debug,
-                        "No ASIN, ISBN or iTunes ID found for item {:?}",
-                        item
-                    );
-                    continue;
-                };
+                }
+            } else if let Some(asin) = search_identifier(&metadata, audible_service).await {
+                (asin, MediaLot::AudioBook, MediaSource::Audible, None)
+            } else {
+                failed_items.push(ImportFailedItem {
+                    error: Some("No ASIN, ISBN or iTunes ID found".to_string()),
+                    identifier: title,
+                    lot: None,
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                });
+                continue;
+            };
             let mut seen_history = vec![];
             if let Some(podcasts) = episodes {
-                for episode in podcasts {
+                for (episode, progress, listened_at) in podcasts {
                     seen_history.push(ImportOrExportMediaItemSeen {
-                        provider_watched_on: Some(ImportSource::Audiobookshelf.to_string()),
                         podcast_episode_number: Some(episode),
-                        ..Default::default()
+                        ..progress_to_seen(progress, false, listened_at)
                     });
                 }
             } else {
-                seen_history.push(ImportOrExportMediaItemSeen {
-                    provider_watched_on: Some(ImportSource::Audiobookshelf.to_string()),
-                    ..Default::default()
-                });
+                let item_details = get_item_details(&client, &url, &item.id, None).await?;
+                seen_history.push(progress_to_seen(
+                    item_details.user_media_progress,
+                    is_ebook,
+                    last_listened_at.get(&(item.id.clone(), None)).copied(),
+                ));
             };
             media.push(ImportOrExportMediaItem {
                 lot,
@@ -0,0 +1,126 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+};
+
+use async_graphql::{Error, Result};
+use flate2::bufread::GzDecoder;
+use itertools::Itertools;
+use rust_decimal::Decimal;
+
+// DEV: Only the beginning of the file is needed to fingerprint it
+const SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// The parts of an uploaded file that importers use to recognize their own
+/// export format.
+#[derive(Debug, Default)]
+pub struct ImportFileSample {
+    pub csv_headers: Vec<String>,
+    pub json_keys: Vec<String>,
+    pub xml_root: Option<String>,
+    pub contents: String,
+}
+
+impl ImportFileSample {
+    pub fn from_path(path: &str) -> Result<Self> {
+        let file = File::open(path).map_err(|e| Error::new(e.to_string()))?;
+        let mut reader = BufReader::new(file);
+        let mut raw = vec![];
+        reader
+            .by_ref()
+            .take(SAMPLE_SIZE)
+            .read_to_end(&mut raw)
+            .map_err(|e| Error::new(e.to_string()))?;
+        let contents = if raw.starts_with(&[0x1f, 0x8b]) {
+            let mut decoded = String::new();
+            GzDecoder::new(raw.as_slice())
+                .take(SAMPLE_SIZE)
+                .read_to_string(&mut decoded)
+                .ok();
+            decoded
+        } else {
+            String::from_utf8_lossy(&raw).into_owned()
+        };
+        let trimmed = contents.trim_start_matches('\u{feff}').trim_start();
+        let mut sample = Self::default();
+        if trimmed.starts_with('{') {
+            sample.json_keys = json_top_level_keys(trimmed);
+        } else if trimmed.starts_with('<') {
+            sample.xml_root = xml_root_element(trimmed);
+        } else if let Some(first_line) = trimmed.lines().next() {
+            let delimiter = if first_line.contains(';') { ';' } else { ',' };
+            sample.csv_headers = first_line
+                .split(delimiter)
+                .map(|h| h.trim().trim_matches('"').to_owned())
+                .filter(|h| !h.is_empty())
+                .collect_vec();
+        }
+        sample.contents = contents;
+        Ok(sample)
+    }
+
+    /// The fraction of the expected headers that are present in the file.
+    pub fn csv_header_confidence(&self, expected: &[&str]) -> Decimal {
+        let matched = expected
+            .iter()
+            .filter(|e| self.csv_headers.iter().any(|h| h == *e))
+            .count();
+        Decimal::from(matched) / Decimal::from(expected.len().max(1))
+    }
+}
+
+// DEV: The file might be truncated, so we only look at keys at the first nesting level
+fn json_top_level_keys(contents: &str) -> Vec<String> {
+    let mut keys = vec![];
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut current = String::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => {
+                    escaped = false;
+                    current.push(c);
+                }
+                '\\' => escaped = true,
+                '"' => {
+                    in_string = false;
+                    let rest = chars.clone().find(|c| !c.is_whitespace());
+                    if depth == 1 && rest == Some(':') {
+                        keys.push(current.clone());
+                    }
+                }
+                _ => current.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                current.clear();
+            }
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    keys
+}
+
+fn xml_root_element(contents: &str) -> Option<String> {
+    let mut rest = contents;
+    loop {
+        let start = rest.find('<')?;
+        rest = &rest[start + 1..];
+        if rest.starts_with('?') || rest.starts_with('!') {
+            continue;
+        }
+        let name = rest
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+            .collect::<String>();
+        return Some(name).filter(|n| !n.is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::{Error, Result};
+use chrono::NaiveDate;
+use common_models::DefaultCollection;
+use common_utils::ryot_log;
+use csv::{Reader, StringRecord};
+use database_utils::metadata_by_external_id;
+use dependent_models::ImportResult;
+use dependent_utils::{get_isbn_service, get_metadata_provider, get_tmdb_non_media_service};
+use enums::{ExternalIdType, ImportSource, MediaSource};
+use itertools::Itertools;
+use media_models::{
+    DeployGenericCsvMappedImportInput, GenericCsvColumnMapping, ImportOrExportItemRating,
+    ImportOrExportMediaItemSeen,
+};
+use rust_decimal::Decimal;
+use supporting_service::SupportingService;
+
+use super::{ImportFailStep, ImportFailedItem, ImportOrExportMediaItem};
+
+fn mapped_columns(mapping: &GenericCsvColumnMapping) -> Vec<&String> {
+    [
+        Some(&mapping.title),
+        mapping.identifier.as_ref(),
+        mapping.identifier_type.as_ref(),
+        mapping.rating.as_ref(),
+        mapping.date.as_ref(),
+        mapping.status.as_ref(),
+        mapping.collection.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect_vec()
+}
+
+/// Check that every column referenced by the mapping is present in the file.
+pub fn validate_mapping(csv_path: &str, mapping: &GenericCsvColumnMapping) -> Result<()> {
+    let mut reader = Reader::from_path(csv_path).map_err(|e| Error::new(e.to_string()))?;
+    let headers = reader.headers().map_err(|e| Error::new(e.to_string()))?;
+    validate_headers(headers, mapping)
+}
+
+fn validate_headers(headers: &StringRecord, mapping: &GenericCsvColumnMapping) -> Result<()> {
+    let unmatched = mapped_columns(mapping)
+        .into_iter()
+        .filter(|c| !headers.iter().any(|h| h.trim() == c.trim()))
+        .collect_vec();
+    if !unmatched.is_empty() {
+        return Err(Error::new(format!(
+            "The following columns were not found in the file: {}. Available columns are: {}",
+            unmatched.into_iter().join(", "),
+            headers.iter().join(", ")
+        )));
+    }
+    Ok(())
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%Y/%m/%d", "%d-%m-%Y", "%d/%m/%Y"]
+        .into_iter()
+        .find_map(|f| NaiveDate::parse_from_str(value, f).ok())
+}
+
+/// The seen entries and collections of a row. Only a completed status, or a date
+/// without any status, counts as having been seen. Other statuses like "dropped"
+/// or "on hold" are ignored.
+fn status_entries(
+    status: Option<&str>,
+    ended_on: Option<NaiveDate>,
+) -> (Vec<ImportOrExportMediaItemSeen>, Vec<String>) {
+    let seen = || ImportOrExportMediaItemSeen {
+        ended_on,
+        provider_watched_on: Some(ImportSource::GenericCsv.to_string()),
+        ..Default::default()
+    };
+    match status.map(|s| s.to_lowercase()).as_deref() {
+        Some(
+            "to-read" | "to read" | "want to read" | "plan to watch" | "planned" | "watchlist",
+        ) => (vec![], vec![DefaultCollection::Watchlist.to_string()]),
+        Some(
+            "currently-reading" | "currently reading" | "reading" | "watching" | "in progress"
+            | "playing",
+        ) => (vec![], vec![DefaultCollection::InProgress.to_string()]),
+        Some("completed" | "read" | "watched" | "played" | "finished") => (vec![seen()], vec![]),
+        None if ended_on.is_some() => (vec![seen()], vec![]),
+        _ => (vec![], vec![]),
+    }
+}
+
+pub async fn import(
+    input: DeployGenericCsvMappedImportInput,
+    mapping: GenericCsvColumnMapping,
+    ss: &Arc<SupportingService>,
+) -> Result<ImportResult> {
+    validate_mapping(&input.csv_path, &mapping)?;
+    let lot = input.lot;
+    let provider = get_metadata_provider(lot, input.source, ss).await?;
+    let isbn_service = get_isbn_service(&ss.config).await?;
+    let tmdb_service = get_tmdb_non_media_service(ss).await?;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let mut reader = Reader::from_path(&input.csv_path).map_err(|e| Error::new(e.to_string()))?;
+    let headers: HashMap<String, usize> = reader
+        .headers()
+        .map_err(|e| Error::new(e.to_string()))?
+        .iter()
+        .enumerate()
+        .map(|(idx, h)| (h.trim().to_owned(), idx))
+        .collect();
+    let records = reader.records().collect_vec();
+    let total = records.len();
+    for (idx, result) in records.into_iter().enumerate() {
+        let record: StringRecord = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: Some(lot),
+                    step: ImportFailStep::InputTransformation,
+                    identifier: idx.to_string(),
+                    error: Some(e.to_string()),
+                });
+                continue;
+            }
+        };
+        let get = |column: Option<&String>| {
+            column
+                .and_then(|c| headers.get(c.trim()))
+                .and_then(|i| record.get(*i))
+                .map(|v| v.trim().to_owned())
+                .filter(|v| !v.is_empty())
+        };
+        let title = get(Some(&mapping.title)).unwrap_or_default();
+        ryot_log!(debug, "Getting details for {title:?} ({idx}/{total})");
+        let identifier_type = get(mapping.identifier_type.as_ref()).map(|t| t.to_lowercase());
+        let resolved = match (get(mapping.identifier.as_ref()), identifier_type.as_deref()) {
+            (Some(isbn), Some("isbn")) => isbn_service
+                .id_from_isbn(&isbn)
+                .await
+                .map(|i| (MediaSource::GoogleBooks, i))
+                .ok_or_else(|| format!("Could not find a book with ISBN: {isbn}")),
+            (Some(imdb_id), Some("imdb")) => {
+                let existing = metadata_by_external_id(ExternalIdType::Imdb, &imdb_id, &ss.db)
+                    .await?
+                    .filter(|m| m.lot == lot);
+                match existing {
+                    Some(metadata) => Ok((metadata.source, metadata.identifier)),
+                    None => tmdb_service
+                        .find_by_external_id(&imdb_id, "imdb_id")
+                        .await
+                        .map(|i| (MediaSource::Tmdb, i))
+                        .map_err(|e| e.to_string()),
+                }
+            }
+            (Some(identifier), _) => Ok((input.source, identifier)),
+            (None, _) if !title.is_empty() => provider
+                .metadata_search(&title, None, false)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| {
+                    r.items
+                        .into_iter()
+                        .next()
+                        .map(|i| (input.source, i.identifier))
+                        .ok_or_else(|| format!("No results found for title: {title}"))
+                }),
+            (None, _) => Err("Neither a title nor an identifier is present".to_owned()),
+        };
+        let (source, identifier) = match resolved {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: Some(lot),
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                    identifier: title,
+                    error: Some(e),
+                });
+                continue;
+            }
+        };
+        let ended_on = get(mapping.date.as_ref()).and_then(|d| parse_date(&d));
+        let (seen_history, mut collections) =
+            status_entries(get(mapping.status.as_ref()).as_deref(), ended_on);
+        if let Some(c) = get(mapping.collection.as_ref()) {
+            collections.extend(
+                c.split(',')
+                    .map(|c| c.trim().to_owned())
+                    .filter(|c| !c.is_empty()),
+            );
+        }
+        let rating = get(mapping.rating.as_ref()).and_then(|r| r.parse::<Decimal>().ok());
+        media.push(ImportOrExportMediaItem {
+            lot,
+            source,
+            identifier,
+            collections,
+            seen_history,
+            source_id: title,
+            reviews: match rating {
+                Some(rating) => vec![ImportOrExportItemRating {
+                    rating: Some(rating),
+                    ..Default::default()
+                }],
+                None => vec![],
+            },
+        });
+    }
+    Ok(ImportResult {
+        metadata: media,
+        failed_items,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset() -> GenericCsvColumnMapping {
+        GenericCsvColumnMapping {
+            title: "Title".to_owned(),
+            identifier: Some("ISBN".to_owned()),
+            identifier_type: None,
+            rating: Some("Rating".to_owned()),
+            date: Some("Date Read".to_owned()),
+            status: Some("Status".to_owned()),
+            collection: None,
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn validate_headers_accepts_a_matching_layout() {
+        let headers = StringRecord::from(vec!["ISBN", " Title ", "Rating", "Status", "Date Read"]);
+        assert!(validate_headers(&headers, &preset()).is_ok());
+    }
+
+    #[test]
+    fn validate_headers_lists_the_unmatched_columns() {
+        let headers = StringRecord::from(vec!["Name", "ISBN", "Rating", "Status", "Finished"]);
+        let error = validate_headers(&headers, &preset()).unwrap_err().message;
+        assert!(error.contains("not found in the file: Title, Date Read."));
+        assert!(error.contains("Available columns are: Name, ISBN, Rating, Status, Finished"));
+    }
+
+    #[test]
+    fn renamed_preset_matches_the_other_layout() {
+        let first = StringRecord::from(vec!["Title", "ISBN", "Rating", "Status", "Date Read"]);
+        let second = StringRecord::from(vec!["Name", "ISBN", "Rating", "Status", "Finished"]);
+        let mut mapping = preset();
+        mapping.title = "Name".to_owned();
+        mapping.date = Some("Finished".to_owned());
+        assert!(validate_headers(&second, &mapping).is_ok());
+        assert!(validate_headers(&first, &mapping).is_err());
+    }
+
+    #[test]
+    fn status_entries_only_marks_completed_rows_as_seen() {
+        let (seen, collections) = status_entries(Some("Finished"), date(2024, 3, 5));
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].ended_on, date(2024, 3, 5));
+        assert!(collections.is_empty());
+
+        let (seen, _) = status_entries(None, date(2024, 3, 5));
+        assert_eq!(seen.len(), 1);
+
+        for status in ["dropped", "On Hold", "paused"] {
+            let (seen, collections) = status_entries(Some(status), date(2024, 3, 5));
+            assert!(seen.is_empty(), "{status}");
+            assert!(collections.is_empty(), "{status}");
+        }
+        assert!(status_entries(None, None).0.is_empty());
+    }
+
+    #[test]
+    fn status_entries_adds_rows_to_default_collections() {
+        let (seen, collections) = status_entries(Some("Want to Read"), date(2024, 3, 5));
+        assert!(seen.is_empty());
+        assert_eq!(collections, vec![DefaultCollection::Watchlist.to_string()]);
+
+        let (seen, collections) = status_entries(Some("watching"), None);
+        assert!(seen.is_empty());
+        assert_eq!(collections, vec![DefaultCollection::InProgress.to_string()]);
+    }
+}
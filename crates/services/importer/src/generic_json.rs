@@ -1,14 +1,49 @@
 use std::fs;
 
-use async_graphql::Result;
-use dependent_models::{CompleteExport, ImportResult};
+use async_graphql::{Error, Result};
+use dependent_models::{CompleteExport, ImportResult, COMPLETE_EXPORT_VERSION};
 use enums::ImportSource;
 use itertools::Itertools;
 use media_models::DeployJsonImportInput;
+use rust_decimal::Decimal;
+
+use super::ImportFileSample;
+
+const EXPORT_KEYS: [&str; 8] = [
+    "version",
+    "media",
+    "people",
+    "measurements",
+    "workouts",
+    "media_groups",
+    "exercises",
+    "workout_templates",
+];
+
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    if sample.json_keys.is_empty() {
+        return Decimal::ZERO;
+    }
+    let matched = sample
+        .json_keys
+        .iter()
+        .filter(|k| EXPORT_KEYS.contains(&k.as_str()))
+        .count();
+    Decimal::from(matched) / Decimal::from(sample.json_keys.len())
+}
 
 pub async fn import(input: DeployJsonImportInput) -> Result<ImportResult> {
     let export = fs::read_to_string(input.export)?;
-    let complete_data = serde_json::from_str::<CompleteExport>(&export).unwrap();
+    let complete_data = serde_json::from_str::<CompleteExport>(&export)
+        .map_err(|e| Error::new(format!("The file is not a valid Ryot export: {}", e)))?;
+    // DEV: Exports without a version were made before it was added and use version 1
+    let version = complete_data.version.unwrap_or(1);
+    if version > COMPLETE_EXPORT_VERSION {
+        return Err(Error::new(format!(
+            "Export version {} is not supported, the latest supported version is {}",
+            version, COMPLETE_EXPORT_VERSION
+        )));
+    }
 
     let media = complete_data
         .media
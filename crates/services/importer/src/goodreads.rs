@@ -14,27 +14,78 @@ use providers::google_books::GoogleBooksService;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::Deserialize;
+use traits::MediaProvider;
 
-use super::{ImportFailStep, ImportFailedItem};
+use super::{ImportFailStep, ImportFailedItem, ImportFileSample};
 
 #[derive(Debug, Deserialize)]
 struct Book {
     #[serde(rename = "Title")]
     title: String,
+    #[serde(rename = "Author")]
+    author: String,
+    #[serde(rename = "ISBN")]
+    isbn: String,
     #[serde(rename = "ISBN13")]
     isbn13: String,
     #[serde(rename = "My Rating")]
     rating: Decimal,
     #[serde(rename = "Date Read")]
     date_read: Option<String>,
+    #[serde(rename = "Exclusive Shelf")]
+    exclusive_shelf: String,
     #[serde(rename = "Bookshelves")]
-    bookshelf: String,
+    bookshelves: String,
     #[serde(rename = "My Review")]
     review: String,
     #[serde(rename = "Read Count")]
     read_count: usize,
 }
 
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    sample.csv_header_confidence(&[
+        "Book Id",
+        "Title",
+        "Author",
+        "ISBN13",
+        "My Rating",
+        "Exclusive Shelf",
+        "Bookshelves",
+        "Date Read",
+    ])
+}
+
+/// Goodreads wraps the ISBNs in a formula (`="9780141439518"`) so that spreadsheets
+/// do not turn them into numbers.
+fn clean_isbn(isbn: &str) -> Option<String> {
+    let isbn = isbn.trim_start_matches('=').trim_matches('"').trim();
+    (!isbn.is_empty()).then(|| isbn.to_owned())
+}
+
+async fn book_identifier(
+    record: &Book,
+    isbn_service: &GoogleBooksService,
+) -> Result<String, String> {
+    for isbn in [&record.isbn13, &record.isbn]
+        .into_iter()
+        .filter_map(|i| clean_isbn(i))
+    {
+        if let Some(identifier) = isbn_service.id_from_isbn(&isbn).await {
+            return Ok(identifier);
+        }
+    }
+    let query = format!("{} {}", record.title, record.author);
+    isbn_service
+        .metadata_search(query.trim(), None, false)
+        .await
+        .map_err(|e| e.to_string())?
+        .items
+        .into_iter()
+        .next()
+        .map(|i| i.identifier)
+        .ok_or_else(|| format!("No book found by ISBN or for the search: {}", query))
+}
+
 pub async fn import(
     input: DeployGenericCsvImportInput,
     isbn_service: &GoogleBooksService,
@@ -66,80 +117,94 @@ pub async fn import(
             "Getting details for {title:?} ({idx}/{total})",
             title = record.title
         );
-        let isbn = record.isbn13[2..record.isbn13.len() - 1].to_owned();
-        if isbn.is_empty() {
-            failed_items.push(ImportFailedItem {
-                lot: Some(lot),
-                step: ImportFailStep::InputTransformation,
-                identifier: record.title,
-                error: Some("ISBN is empty".to_owned()),
-            });
-            continue;
-        }
-        if let Some(identifier) = isbn_service.id_from_isbn(&isbn).await {
-            let mut seen_history = vec![
+        let identifier = match book_identifier(&record, isbn_service).await {
+            Ok(identifier) => identifier,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: Some(lot),
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                    identifier: record.title,
+                    error: Some(e),
+                });
+                continue;
+            }
+        };
+        let mut seen_history = vec![];
+        if record.exclusive_shelf == "read" {
+            seen_history = vec![
                 ImportOrExportMediaItemSeen {
                     started_on: None,
                     ended_on: None,
                     provider_watched_on: Some(ImportSource::Goodreads.to_string()),
                     ..Default::default()
                 };
-                record.read_count
+                record.read_count.max(1)
             ];
-            if let Some(w) = record.date_read {
-                let w = NaiveDate::parse_from_str(&w, "%Y/%m/%d").unwrap();
-                seen_history.first_mut().unwrap().ended_on = Some(w);
-            }
-            let mut collections = vec![];
-            if !record.bookshelf.is_empty() {
-                collections.push(match record.bookshelf.as_str() {
-                    "to-read" => "Watchlist".to_owned(),
-                    "currently-reading" => "In Progress".to_owned(),
-                    s => s.to_case(Case::Title),
-                });
-            }
-            let mut rating = None;
-            if record.rating > dec!(0) {
-                rating = Some(
-                    record
-                        .rating
-                        // DEV: Rates items out of 10
-                        .saturating_mul(dec!(10)),
-                );
-            }
-            let mut review = None;
-            if !record.review.is_empty() {
-                review = Some(ImportOrExportItemReview {
-                    date: None,
-                    spoiler: Some(false),
-                    text: Some(record.review),
-                    visibility: None,
-                });
+            let date_read = record
+                .date_read
+                .as_deref()
+                .filter(|d| !d.is_empty())
+                .map(|d| NaiveDate::parse_from_str(d, "%Y/%m/%d"));
+            match date_read {
+                Some(Ok(d)) => seen_history.first_mut().unwrap().ended_on = Some(d),
+                Some(Err(e)) => failed_items.push(ImportFailedItem {
+                    lot: Some(lot),
+                    step: ImportFailStep::SeenHistoryConversion,
+                    identifier: record.title.clone(),
+                    error: Some(format!("Could not parse the date read: {}", e)),
+                }),
+                None => {}
             }
-            media.push(ImportOrExportMediaItem {
-                lot,
-                source,
-                identifier,
-                collections,
-                seen_history,
-                source_id: record.title.clone(),
-                reviews: vec![ImportOrExportItemRating {
-                    review,
-                    rating,
-                    ..Default::default()
-                }],
+        }
+        let mut collections = vec![];
+        match record.exclusive_shelf.as_str() {
+            "to-read" => collections.push("Watchlist".to_owned()),
+            "currently-reading" => collections.push("In Progress".to_owned()),
+            _ => {}
+        }
+        collections.extend(
+            record
+                .bookshelves
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty() && *s != record.exclusive_shelf)
+                .map(|s| s.to_case(Case::Title)),
+        );
+        let mut rating = None;
+        if record.rating > dec!(0) {
+            rating = Some(
+                record
+                    .rating
+                    // DEV: Goodreads rates items out of 5
+                    .saturating_mul(dec!(20)),
+            );
+        }
+        let mut review = None;
+        if !record.review.is_empty() {
+            review = Some(ImportOrExportItemReview {
+                date: None,
+                spoiler: Some(false),
+                text: Some(record.review),
+                visibility: None,
+            });
+        }
+        let mut reviews = vec![];
+        if rating.is_some() || review.is_some() {
+            reviews.push(ImportOrExportItemRating {
+                review,
+                rating,
+                ..Default::default()
             });
-        } else {
-            failed_items.push(ImportFailedItem {
-                lot: Some(lot),
-                step: ImportFailStep::InputTransformation,
-                identifier: record.title,
-                error: Some(format!(
-                    "Could not convert ISBN: {} to Google Books ID",
-                    isbn,
-                )),
-            })
         }
+        media.push(ImportOrExportMediaItem {
+            lot,
+            source,
+            identifier,
+            collections,
+            seen_history,
+            reviews,
+            source_id: record.title.clone(),
+        });
     }
     Ok(ImportResult {
         metadata: media,
@@ -147,3 +212,23 @@ pub async fn import(
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_isbn_unwraps_spreadsheet_formulas() {
+        assert_eq!(
+            clean_isbn("=\"9780141439518\""),
+            Some("9780141439518".to_owned())
+        );
+        assert_eq!(clean_isbn("0141439513"), Some("0141439513".to_owned()));
+    }
+
+    #[test]
+    fn clean_isbn_ignores_missing_values() {
+        assert_eq!(clean_isbn(""), None);
+        assert_eq!(clean_isbn("=\"\""), None);
+    }
+}
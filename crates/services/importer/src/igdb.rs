@@ -5,10 +5,11 @@ use dependent_models::ImportResult;
 use enums::{MediaLot, MediaSource};
 use itertools::Itertools;
 use media_models::{DeployIgdbImportInput, ImportOrExportMediaItem, ImportOrExportMediaItemSeen};
+use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::Deserialize;
 
-use super::{ImportFailStep, ImportFailedItem};
+use super::{ImportFailStep, ImportFailedItem, ImportFileSample};
 
 #[derive(Debug, Deserialize)]
 struct Item {
@@ -16,6 +17,10 @@ struct Item {
     game: String,
 }
 
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    sample.csv_header_confidence(&["id", "game"])
+}
+
 pub async fn import(input: DeployIgdbImportInput) -> Result<ImportResult> {
     let lot = MediaLot::VideoGame;
     let source = MediaSource::Igdb;
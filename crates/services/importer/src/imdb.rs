@@ -1,15 +1,23 @@
 use async_graphql::Result;
+use chrono::NaiveDate;
 use common_models::DefaultCollection;
 use common_utils::ryot_log;
 use csv::Reader;
+use database_utils::metadata_by_external_id;
 use dependent_models::ImportResult;
-use enums::{MediaLot, MediaSource};
+use enums::{ExternalIdType, ImportSource, MediaLot, MediaSource};
 use itertools::Itertools;
-use media_models::{DeployGenericCsvImportInput, ImportOrExportMediaItem};
+use media_models::{
+    DeployGenericCsvImportInput, ImportOrExportItemRating, ImportOrExportMediaItem,
+    ImportOrExportMediaItemSeen,
+};
 pub use providers::tmdb::NonMediaTmdbService;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::DatabaseConnection;
 use serde::Deserialize;
 
-use super::{ImportFailStep, ImportFailedItem};
+use super::{ImportFailStep, ImportFailedItem, ImportFileSample};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -18,13 +26,64 @@ struct Item {
     id: String,
     #[serde(rename = "Title Type")]
     title_type: String,
+    #[serde(default, rename = "Your Rating")]
+    your_rating: Option<Decimal>,
+    #[serde(default, rename = "Date Rated")]
+    date_rated: Option<NaiveDate>,
+    // DEV: Only the watchlist export has this column
+    #[serde(default, rename = "Position")]
+    position: Option<i32>,
+}
+
+/// Rated movies are marked as seen on the date they were rated and items of the
+/// watchlist are added to the Watchlist collection. Rated shows only get the
+/// rating since the export does not say which episodes were watched.
+fn convert_item(
+    record: Item,
+    lot: MediaLot,
+    source: MediaSource,
+    identifier: String,
+) -> ImportOrExportMediaItem {
+    let mut seen_history = vec![];
+    let mut reviews = vec![];
+    if let Some(rating) = record.your_rating {
+        if lot == MediaLot::Movie {
+            seen_history.push(ImportOrExportMediaItemSeen {
+                ended_on: record.date_rated,
+                provider_watched_on: Some(ImportSource::Imdb.to_string()),
+                ..Default::default()
+            });
+        }
+        // DEV: IMDb rates from 1 to 10
+        reviews.push(ImportOrExportItemRating {
+            rating: Some(rating.saturating_mul(dec!(10))),
+            ..Default::default()
+        });
+    }
+    let mut collections = vec![];
+    if record.position.is_some() {
+        collections.push(DefaultCollection::Watchlist.to_string());
+    }
+    ImportOrExportMediaItem {
+        lot,
+        source,
+        reviews,
+        identifier,
+        collections,
+        seen_history,
+        source_id: record.id,
+    }
+}
+
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    sample.csv_header_confidence(&["Const", "Title", "Title Type", "URL", "IMDb Rating"])
 }
 
 pub async fn import(
     input: DeployGenericCsvImportInput,
     tmdb_service: &NonMediaTmdbService,
+    db: &DatabaseConnection,
 ) -> Result<ImportResult> {
-    let source = MediaSource::Tmdb;
     let mut media = vec![];
     let mut failed_items = vec![];
     let ratings_reader = Reader::from_path(input.csv_path)
@@ -58,6 +117,18 @@ pub async fn import(
                 continue;
             }
         };
+        let existing = metadata_by_external_id(ExternalIdType::Imdb, &record.id, db)
+            .await?
+            .filter(|m| m.lot == lot);
+        if let Some(metadata) = existing {
+            media.push(convert_item(
+                record,
+                lot,
+                metadata.source,
+                metadata.identifier,
+            ));
+            continue;
+        }
         let identifier = match tmdb_service
             .find_by_external_id(&record.id, "imdb_id")
             .await
@@ -80,14 +151,7 @@ pub async fn import(
             idx + 1,
             total
         );
-        media.push(ImportOrExportMediaItem {
-            identifier,
-            lot,
-            source,
-            source_id: record.id,
-            collections: vec![DefaultCollection::Watchlist.to_string()],
-            ..Default::default()
-        });
+        media.push(convert_item(record, lot, MediaSource::Tmdb, identifier));
     }
     Ok(ImportResult {
         metadata: media,
@@ -95,3 +159,64 @@ pub async fn import(
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(csv: &str) -> Vec<Item> {
+        Reader::from_reader(csv.as_bytes())
+            .deserialize()
+            .collect::<Result<Vec<Item>, _>>()
+            .unwrap()
+    }
+
+    fn convert(record: Item, lot: MediaLot) -> ImportOrExportMediaItem {
+        convert_item(record, lot, MediaSource::Tmdb, "1".to_owned())
+    }
+
+    #[test]
+    fn rated_movie_is_seen_on_the_rating_date() {
+        let mut items = parse(
+            "Const,Your Rating,Date Rated,Title,Title Type\n\
+             tt0111161,7,2024-03-05,\"Shawshank, The\",Movie\n",
+        );
+        let item = convert(items.remove(0), MediaLot::Movie);
+        assert_eq!(item.source_id, "tt0111161");
+        assert_eq!(item.reviews.len(), 1);
+        assert_eq!(item.reviews[0].rating, Some(dec!(70)));
+        assert_eq!(item.seen_history.len(), 1);
+        assert_eq!(
+            item.seen_history[0].ended_on,
+            NaiveDate::from_ymd_opt(2024, 3, 5)
+        );
+        assert!(item.collections.is_empty());
+    }
+
+    #[test]
+    fn watchlist_row_is_added_to_the_watchlist() {
+        let mut items = parse(
+            "Position,Const,Created,Title,Title Type\n\
+             1,tt0068646,2024-01-01,The Godfather,movie\n",
+        );
+        let item = convert(items.remove(0), MediaLot::Movie);
+        assert_eq!(
+            item.collections,
+            vec![DefaultCollection::Watchlist.to_string()]
+        );
+        assert!(item.reviews.is_empty());
+        assert!(item.seen_history.is_empty());
+    }
+
+    #[test]
+    fn rated_show_only_keeps_the_rating() {
+        let mut items = parse(
+            "Const,Your Rating,Date Rated,Title,Title Type\n\
+             tt0903747,10,2024-03-05,Breaking Bad,TV Series\n",
+        );
+        let item = convert(items.remove(0), MediaLot::Show);
+        assert_eq!(item.reviews.len(), 1);
+        assert_eq!(item.reviews[0].rating, Some(dec!(100)));
+        assert!(item.seen_history.is_empty());
+    }
+}
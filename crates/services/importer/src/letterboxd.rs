@@ -0,0 +1,249 @@
+use std::{collections::HashMap, fs::File, io::Read};
+
+use async_graphql::Result;
+use chrono::NaiveDate;
+use common_models::DefaultCollection;
+use common_utils::{convert_naive_to_utc, ryot_log};
+use csv::Reader;
+use dependent_models::ImportResult;
+use enums::{ImportSource, MediaLot, MediaSource};
+use media_models::{
+    DeployLetterboxdImportInput, ImportOrExportItemRating, ImportOrExportItemReview,
+    ImportOrExportMediaItemSeen,
+};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{de::DeserializeOwned, Deserialize};
+use traits::MediaProvider;
+use zip::ZipArchive;
+
+use super::{ImportFailStep, ImportFailedItem, ImportOrExportMediaItem};
+
+#[derive(Debug, Deserialize)]
+struct Common {
+    #[serde(rename = "Date")]
+    date: Option<NaiveDate>,
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Year")]
+    year: Option<i32>,
+    #[serde(rename = "Letterboxd URI")]
+    uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Rating {
+    #[serde(flatten)]
+    common: Common,
+    #[serde(rename = "Rating")]
+    rating: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Review {
+    #[serde(flatten)]
+    common: Common,
+    #[serde(rename = "Rating")]
+    rating: Option<Decimal>,
+    #[serde(rename = "Review")]
+    review: Option<String>,
+    #[serde(rename = "Watched Date")]
+    watched_date: Option<NaiveDate>,
+}
+
+/// All the rows of the export that refer to the same film.
+#[derive(Debug, Default)]
+struct Film {
+    name: String,
+    year: Option<i32>,
+    watched_on: Vec<Option<NaiveDate>>,
+    rating: Option<Decimal>,
+    reviews: Vec<ImportOrExportItemRating>,
+    in_watchlist: bool,
+}
+
+/// Read one of the CSV files, either from the uploaded zip or from the file that
+/// was uploaded separately.
+fn read_file(
+    input: &DeployLetterboxdImportInput,
+    file_name: &str,
+    separate_path: &Option<String>,
+) -> Result<Option<Vec<u8>>> {
+    let mut contents = vec![];
+    if let Some(path) = separate_path {
+        File::open(path)?.read_to_end(&mut contents)?;
+        return Ok(Some(contents));
+    }
+    let Some(export) = &input.export else {
+        return Ok(None);
+    };
+    let mut archive = ZipArchive::new(File::open(export)?)?;
+    let Ok(mut file) = archive.by_name(file_name) else {
+        return Ok(None);
+    };
+    file.read_to_end(&mut contents)?;
+    Ok(Some(contents))
+}
+
+fn read_rows<T: DeserializeOwned>(
+    input: &DeployLetterboxdImportInput,
+    file_name: &str,
+    separate_path: &Option<String>,
+    failed_items: &mut Vec<ImportFailedItem>,
+) -> Result<Vec<T>> {
+    let Some(contents) = read_file(input, file_name, separate_path)? else {
+        return Ok(vec![]);
+    };
+    let mut rows = vec![];
+    for (idx, result) in Reader::from_reader(contents.as_slice())
+        .deserialize()
+        .enumerate()
+    {
+        match result {
+            Ok(r) => rows.push(r),
+            Err(e) => failed_items.push(ImportFailedItem {
+                lot: Some(MediaLot::Movie),
+                step: ImportFailStep::InputTransformation,
+                identifier: idx.to_string(),
+                error: Some(format!("{}: {:#?}", file_name, e)),
+            }),
+        }
+    }
+    Ok(rows)
+}
+
+fn film_entry<'a>(films: &'a mut HashMap<String, Film>, common: &Common) -> &'a mut Film {
+    films.entry(common.uri.clone()).or_insert_with(|| Film {
+        name: common.name.clone(),
+        year: common.year,
+        ..Default::default()
+    })
+}
+
+// DEV: Letterboxd rates films from 0.5 to 5 stars
+fn scale_rating(rating: Option<Decimal>) -> Option<Decimal> {
+    rating
+        .filter(|r| *r > dec!(0))
+        .map(|r| r.saturating_mul(dec!(20)))
+}
+
+/// Letterboxd exports do not contain TMDB ids, so films are found by searching
+/// TMDB for the title and picking the result released in the same year.
+async fn tmdb_identifier(
+    film: &Film,
+    provider: &(dyn MediaProvider + Send + Sync),
+) -> Result<String, String> {
+    let results = provider
+        .metadata_search(&film.name, None, true)
+        .await
+        .map_err(|e| e.to_string())?;
+    results
+        .items
+        .into_iter()
+        .find(|i| film.year.is_none() || i.publish_year == film.year)
+        .map(|i| i.identifier)
+        .ok_or_else(|| match film.year {
+            Some(year) => format!("No TMDB movie found for {} ({})", film.name, year),
+            None => format!("No TMDB movie found for {}", film.name),
+        })
+}
+
+pub async fn import(
+    input: DeployLetterboxdImportInput,
+    provider: &(dyn MediaProvider + Send + Sync),
+) -> Result<ImportResult> {
+    let lot = MediaLot::Movie;
+    let source = MediaSource::Tmdb;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let mut films = HashMap::new();
+
+    let watched: Vec<Common> = read_rows(&input, "watched.csv", &input.watched, &mut failed_items)?;
+    for row in watched {
+        film_entry(&mut films, &row).watched_on.push(row.date);
+    }
+    let ratings: Vec<Rating> = read_rows(&input, "ratings.csv", &input.ratings, &mut failed_items)?;
+    for row in ratings {
+        film_entry(&mut films, &row.common).rating = scale_rating(row.rating);
+    }
+    let reviews: Vec<Review> = read_rows(&input, "reviews.csv", &input.reviews, &mut failed_items)?;
+    for row in reviews {
+        let date = row.watched_date.or(row.common.date);
+        film_entry(&mut films, &row.common)
+            .reviews
+            .push(ImportOrExportItemRating {
+                rating: scale_rating(row.rating),
+                review: row.review.map(|text| ImportOrExportItemReview {
+                    text: Some(text),
+                    spoiler: Some(false),
+                    visibility: None,
+                    date: date.map(convert_naive_to_utc),
+                }),
+                ..Default::default()
+            });
+    }
+    let watchlist: Vec<Common> =
+        read_rows(&input, "watchlist.csv", &input.watchlist, &mut failed_items)?;
+    for row in watchlist {
+        film_entry(&mut films, &row).in_watchlist = true;
+    }
+
+    let total = films.len();
+    for (idx, (uri, film)) in films.into_iter().enumerate() {
+        ryot_log!(
+            debug,
+            "Getting details for {name:?} ({idx}/{total})",
+            name = film.name
+        );
+        let identifier = match tmdb_identifier(&film, provider).await {
+            Ok(identifier) => identifier,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: Some(lot),
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                    identifier: uri,
+                    error: Some(e),
+                });
+                continue;
+            }
+        };
+        let seen_history = film
+            .watched_on
+            .into_iter()
+            .map(|ended_on| ImportOrExportMediaItemSeen {
+                ended_on,
+                provider_watched_on: Some(ImportSource::Letterboxd.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        let mut reviews = film.reviews;
+        if reviews.iter().all(|r| r.rating.is_none()) {
+            match reviews.last_mut() {
+                Some(review) => review.rating = film.rating,
+                None if film.rating.is_some() => reviews.push(ImportOrExportItemRating {
+                    rating: film.rating,
+                    ..Default::default()
+                }),
+                None => {}
+            }
+        }
+        let mut collections = vec![];
+        if film.in_watchlist {
+            collections.push(DefaultCollection::Watchlist.to_string());
+        }
+        media.push(ImportOrExportMediaItem {
+            lot,
+            source,
+            reviews,
+            identifier,
+            collections,
+            seen_history,
+            source_id: uri,
+        });
+    }
+    Ok(ImportResult {
+        metadata: media,
+        failed_items,
+        ..Default::default()
+    })
+}
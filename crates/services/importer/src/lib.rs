@@ -1,44 +1,167 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
-use async_graphql::Result;
+use application_utils::{dates::local_to_utc, get_current_date};
+use async_graphql::{Error, Result};
 use background::ApplicationJob;
-use chrono::{DateTime, Duration, NaiveDateTime, Offset, TimeZone, Utc};
-use common_models::BackgroundJob;
-use common_utils::ryot_log;
-use database_models::{import_report, prelude::ImportReport};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use common_models::{BackgroundJob, StringIdObject};
+use common_utils::{ryot_log, TEMP_DIR};
+use database_models::{
+    csv_import_preset, import_report,
+    prelude::{CsvImportPreset, ImportReport},
+};
 use dependent_utils::{
-    commit_metadata, deploy_background_job, get_isbn_service, get_tmdb_non_media_service,
-    process_import,
+    commit_metadata, deploy_background_job, get_isbn_service, get_metadata_provider,
+    get_tmdb_non_media_service, process_import,
+};
+use enums::{ImportSource, MediaLot, MediaSource};
+use importer_models::{
+    ImportDetails, ImportFailStep, ImportFailedItem, ImportFormatDetection, ImportResultResponse,
+};
+use itertools::Itertools;
+use media_models::{
+    CreateOrUpdateCsvImportPresetInput, DeployGenericCsvImportInput,
+    DeployGenericCsvMappedImportInput, DeployImportJobInput, DeployJsonImportInput,
+    DeployMalImportInput, GenericCsvColumnMapping, ImportOrExportMediaItem,
+};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
 };
-use enums::ImportSource;
-use importer_models::{ImportFailStep, ImportFailedItem, ImportResultResponse};
-use media_models::{DeployImportJobInput, ImportOrExportMediaItem};
-use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
 use supporting_service::SupportingService;
 use traits::TraceOk;
 
+use detection::ImportFileSample;
+use sanitize::{DateSanitizer, DEFAULT_EARLIEST_VALID_YEAR};
+
+mod anilist;
 mod audiobookshelf;
+mod detection;
+mod generic_csv;
 mod generic_json;
 mod goodreads;
 mod igdb;
 mod imdb;
 mod jellyfin;
+mod letterboxd;
 mod mal;
 mod media_tracker;
 mod movary;
 mod open_scale;
+mod sanitize;
 mod story_graph;
 mod strong_app;
 mod trakt;
 
+type Detector = fn(&ImportFileSample) -> Decimal;
+
+/// The fingerprints of all the importers that work with an uploaded file.
+const DETECTORS: [(ImportSource, Detector); 9] = [
+    (ImportSource::GenericJson, generic_json::detect),
+    (ImportSource::Goodreads, goodreads::detect),
+    (ImportSource::Igdb, igdb::detect),
+    (ImportSource::Imdb, imdb::detect),
+    (ImportSource::Mal, mal::detect),
+    (ImportSource::Movary, movary::detect),
+    (ImportSource::OpenScale, open_scale::detect),
+    (ImportSource::StoryGraph, story_graph::detect),
+    (ImportSource::StrongApp, strong_app::detect),
+];
+
+/// Detections below this confidence are not used for automatic imports.
+const AUTO_DETECT_MIN_CONFIDENCE: Decimal = dec!(0.8);
+
+/// The import sources that recognize the file, most likely first.
+fn rank_import_formats(sample: &ImportFileSample) -> Vec<ImportFormatDetection> {
+    DETECTORS
+        .into_iter()
+        .map(|(source, detect)| ImportFormatDetection {
+            source,
+            confidence: detect(sample).round_dp(2),
+        })
+        .filter(|d| d.confidence > Decimal::ZERO)
+        .sorted_by(|a, b| b.confidence.cmp(&a.confidence))
+        .collect_vec()
+}
+
 pub struct ImporterService(pub Arc<SupportingService>);
 
 impl ImporterService {
+    pub async fn detect_import_format(
+        &self,
+        uploaded_path: String,
+    ) -> Result<Vec<ImportFormatDetection>> {
+        let path = PathBuf::from(&uploaded_path)
+            .canonicalize()
+            .map_err(|_| Error::new("Uploaded file does not exist"))?;
+        let temp_dir = PathBuf::from(TEMP_DIR).canonicalize()?;
+        if !path.starts_with(&temp_dir) {
+            return Err(Error::new("Only files uploaded to the server can be used"));
+        }
+        let sample = ImportFileSample::from_path(&path.to_string_lossy())?;
+        Ok(rank_import_formats(&sample))
+    }
+
+    async fn apply_auto_detection(&self, input: &mut DeployImportJobInput) -> Result<()> {
+        let Some(path) = input.auto_detect_path.clone() else {
+            return Ok(());
+        };
+        let detected = self
+            .detect_import_format(path.clone())
+            .await?
+            .into_iter()
+            .next()
+            .filter(|d| d.confidence >= AUTO_DETECT_MIN_CONFIDENCE);
+        let Some(detected) = detected else {
+            return Err(Error::new(
+                "Could not confidently detect the format of this file, please select the source manually",
+            ));
+        };
+        ryot_log!(debug, "Detected import source {:?}", detected);
+        match detected.source {
+            ImportSource::Goodreads
+            | ImportSource::StoryGraph
+            | ImportSource::Imdb
+            | ImportSource::OpenScale => {
+                input.generic_csv = Some(DeployGenericCsvImportInput { csv_path: path });
+            }
+            ImportSource::GenericJson => {
+                input.generic_json = Some(DeployJsonImportInput { export: path });
+            }
+            ImportSource::Mal => {
+                let sample = ImportFileSample::from_path(&path)?;
+                input.mal = Some(match mal::is_manga_export(&sample) {
+                    true => DeployMalImportInput {
+                        anime_path: None,
+                        manga_path: Some(path),
+                    },
+                    false => DeployMalImportInput {
+                        anime_path: Some(path),
+                        manga_path: None,
+                    },
+                });
+            }
+            source => {
+                return Err(Error::new(format!(
+                    "Detected {source} but it needs more details, please select the source manually"
+                )));
+            }
+        }
+        input.source = detected.source;
+        Ok(())
+    }
+
     pub async fn deploy_import_job(
         &self,
         user_id: String,
-        input: DeployImportJobInput,
+        mut input: DeployImportJobInput,
     ) -> Result<bool> {
+        self.apply_auto_detection(&mut input).await?;
+        if let Some(mapped) = &input.generic_csv_mapped {
+            let mapping = self.resolve_csv_mapping(&user_id, mapped).await?;
+            generic_csv::validate_mapping(&mapped.csv_path, &mapping)?;
+        }
         let job = ApplicationJob::ImportFromExternalSource(user_id, Box::new(input));
         self.0.perform_application_job(job).await?;
         ryot_log!(debug, "Deployed import job");
@@ -55,13 +178,106 @@ impl ImporterService {
         Ok(reports)
     }
 
+    pub async fn csv_import_presets(
+        &self,
+        user_id: String,
+    ) -> Result<Vec<csv_import_preset::Model>> {
+        let presets = CsvImportPreset::find()
+            .filter(csv_import_preset::Column::UserId.eq(user_id))
+            .order_by_asc(csv_import_preset::Column::Name)
+            .all(&self.0.db)
+            .await?;
+        Ok(presets)
+    }
+
+    pub async fn create_or_update_csv_import_preset(
+        &self,
+        user_id: String,
+        input: CreateOrUpdateCsvImportPresetInput,
+    ) -> Result<StringIdObject> {
+        let name = input.name.trim().to_owned();
+        if name.is_empty() {
+            return Err(Error::new("Preset name can not be empty"));
+        }
+        let preset = match input.update_id {
+            Some(id) => {
+                let Some(preset) = CsvImportPreset::find_by_id(id)
+                    .filter(csv_import_preset::Column::UserId.eq(&user_id))
+                    .one(&self.0.db)
+                    .await?
+                else {
+                    return Err(Error::new("Preset with the given ID could not be found"));
+                };
+                let mut preset: csv_import_preset::ActiveModel = preset.into();
+                preset.name = ActiveValue::Set(name);
+                preset.mapping = ActiveValue::Set(input.mapping);
+                preset.update(&self.0.db).await?
+            }
+            None => {
+                let preset = csv_import_preset::ActiveModel {
+                    name: ActiveValue::Set(name),
+                    user_id: ActiveValue::Set(user_id),
+                    mapping: ActiveValue::Set(input.mapping),
+                    ..Default::default()
+                };
+                preset
+                    .insert(&self.0.db)
+                    .await
+                    .map_err(|_| Error::new("A preset with this name already exists"))?
+            }
+        };
+        Ok(StringIdObject { id: preset.id })
+    }
+
+    pub async fn delete_csv_import_preset(
+        &self,
+        user_id: String,
+        preset_id: String,
+    ) -> Result<bool> {
+        let Some(preset) = CsvImportPreset::find_by_id(preset_id)
+            .filter(csv_import_preset::Column::UserId.eq(user_id))
+            .one(&self.0.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        preset.delete(&self.0.db).await?;
+        Ok(true)
+    }
+
+    async fn resolve_csv_mapping(
+        &self,
+        user_id: &String,
+        input: &DeployGenericCsvMappedImportInput,
+    ) -> Result<GenericCsvColumnMapping> {
+        if let Some(mapping) = &input.mapping {
+            return Ok(mapping.clone());
+        }
+        let Some(preset_id) = &input.preset_id else {
+            return Err(Error::new("Either a mapping or a preset must be provided"));
+        };
+        CsvImportPreset::find_by_id(preset_id)
+            .filter(csv_import_preset::Column::UserId.eq(user_id))
+            .one(&self.0.db)
+            .await?
+            .map(|p| p.mapping)
+            .ok_or_else(|| Error::new("Preset with the given ID could not be found"))
+    }
+
     pub async fn start_importing(
         &self,
         user_id: String,
         input: Box<DeployImportJobInput>,
     ) -> Result<()> {
         let db_import_job = self.start_import_job(&user_id, input.source).await?;
-        let import = match input.source {
+        let date_sanitizer = DateSanitizer::new(
+            input.invalid_date_action.unwrap_or_default(),
+            input
+                .earliest_valid_year
+                .unwrap_or(DEFAULT_EARLIEST_VALID_YEAR),
+            get_current_date(&self.0.timezone),
+        );
+        let mut import = match input.source {
             ImportSource::StrongApp => {
                 strong_app::import(input.strong_app.unwrap(), &self.0.timezone)
                     .await
@@ -79,6 +295,35 @@ impl ImporterService {
             .unwrap(),
             ImportSource::Trakt => trakt::import(input.trakt.unwrap()).await.unwrap(),
             ImportSource::Movary => movary::import(input.movary.unwrap()).await.unwrap(),
+            ImportSource::Anilist => {
+                let input = input.anilist.unwrap();
+                let username = input.username.clone();
+                match anilist::import(input).await {
+                    Ok(import) => import,
+                    Err(e) => {
+                        self.fail_import_job(db_import_job, username, e.message)
+                            .await?;
+                        return Ok(());
+                    }
+                }
+            }
+            ImportSource::Letterboxd => {
+                let input = input.letterboxd.unwrap();
+                let identifier = input
+                    .export
+                    .clone()
+                    .unwrap_or_else(|| ImportSource::Letterboxd.to_string());
+                let provider =
+                    get_metadata_provider(MediaLot::Movie, MediaSource::Tmdb, &self.0).await?;
+                match letterboxd::import(input, provider.as_ref()).await {
+                    Ok(import) => import,
+                    Err(e) => {
+                        self.fail_import_job(db_import_job, identifier, e.message)
+                            .await?;
+                        return Ok(());
+                    }
+                }
+            }
             ImportSource::StoryGraph => story_graph::import(
                 input.generic_csv.unwrap(),
                 &get_isbn_service(&self.0.config).await.unwrap(),
@@ -88,6 +333,9 @@ impl ImporterService {
             ImportSource::Audiobookshelf => audiobookshelf::import(
                 input.url_and_key.unwrap(),
                 &get_isbn_service(&self.0.config).await.unwrap(),
+                get_metadata_provider(MediaLot::AudioBook, MediaSource::Audible, &self.0)
+                    .await?
+                    .as_ref(),
                 |input| commit_metadata(input, &self.0),
             )
             .await
@@ -96,9 +344,30 @@ impl ImporterService {
             ImportSource::Imdb => imdb::import(
                 input.generic_csv.unwrap(),
                 &get_tmdb_non_media_service(&self.0).await.unwrap(),
+                &self.0.db,
             )
             .await
             .unwrap(),
+            ImportSource::GenericCsv => {
+                let input = input.generic_csv_mapped.unwrap();
+                let mapping = match self.resolve_csv_mapping(&user_id, &input).await {
+                    Ok(mapping) => mapping,
+                    Err(e) => {
+                        self.fail_import_job(db_import_job, input.csv_path, e.message)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let csv_path = input.csv_path.clone();
+                match generic_csv::import(input, mapping, &self.0).await {
+                    Ok(import) => import,
+                    Err(e) => {
+                        self.fail_import_job(db_import_job, csv_path, e.message)
+                            .await?;
+                        return Ok(());
+                    }
+                }
+            }
             ImportSource::GenericJson => generic_json::import(input.generic_json.unwrap())
                 .await
                 .unwrap(),
@@ -109,6 +378,7 @@ impl ImporterService {
             }
             ImportSource::Jellyfin => jellyfin::import(input.jellyfin.unwrap()).await.unwrap(),
         };
+        date_sanitizer.sanitize(&mut import);
         let details = process_import(&user_id, false, import, &self.0).await?;
         self.finish_import_job(db_import_job, details).await?;
         deploy_background_job(
@@ -148,6 +418,36 @@ impl ImporterService {
         let model = model.update(&self.0.db).await.unwrap();
         Ok(model)
     }
+
+    /// Marks the job as failed when nothing could be read from the source, with the
+    /// reason as its only failed item.
+    async fn fail_import_job(
+        &self,
+        job: import_report::Model,
+        identifier: String,
+        error: String,
+    ) -> Result<import_report::Model> {
+        ryot_log!(
+            debug,
+            "Import job with id = {id} failed: {error}",
+            id = job.id
+        );
+        let mut model: import_report::ActiveModel = job.into();
+        model.finished_on = ActiveValue::Set(Some(Utc::now()));
+        model.details = ActiveValue::Set(Some(ImportResultResponse {
+            import: ImportDetails { total: 0 },
+            failed_items: vec![ImportFailedItem {
+                identifier,
+                lot: None,
+                error: Some(error),
+                step: ImportFailStep::ItemDetailsFromSource,
+            }],
+            warnings: vec![],
+        }));
+        model.was_success = ActiveValue::Set(Some(false));
+        let model = model.update(&self.0.db).await?;
+        Ok(model)
+    }
 }
 
 pub mod utils {
@@ -157,11 +457,60 @@ pub mod utils {
         date_time: NaiveDateTime,
         timezone: &chrono_tz::Tz,
     ) -> DateTime<Utc> {
-        let offset = timezone
-            .offset_from_utc_datetime(&Utc::now().naive_utc())
-            .fix()
-            .local_minus_utc();
-        let offset = Duration::try_seconds(offset.into()).unwrap();
-        DateTime::<Utc>::from_naive_utc_and_offset(date_time, Utc) - offset
+        local_to_utc(date_time, timezone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect_fixture(name: &str) -> Vec<ImportFormatDetection> {
+        let path = format!(
+            "{}/src/fixtures/detection/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        rank_import_formats(&ImportFileSample::from_path(&path).unwrap())
+    }
+
+    #[test]
+    fn fixtures_are_detected_as_their_source_with_high_confidence() {
+        for (fixture, source) in [
+            ("goodreads.csv", ImportSource::Goodreads),
+            ("story_graph.csv", ImportSource::StoryGraph),
+            ("movary.csv", ImportSource::Movary),
+            ("imdb.csv", ImportSource::Imdb),
+            ("strong_app.csv", ImportSource::StrongApp),
+            ("mal.xml", ImportSource::Mal),
+            ("generic.json", ImportSource::GenericJson),
+        ] {
+            let detections = detect_fixture(fixture);
+            let top = detections.first().unwrap();
+            assert_eq!(top.source, source, "{}", fixture);
+            assert!(top.confidence >= AUTO_DETECT_MIN_CONFIDENCE, "{}", fixture);
+            assert!(
+                detections[1..]
+                    .iter()
+                    .all(|d| d.confidence < AUTO_DETECT_MIN_CONFIDENCE),
+                "{}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    fn shared_headers_rank_the_matching_source_first() {
+        // DEV: Goodreads also has a "Title" column, which is a weak match at most
+        let detections = detect_fixture("story_graph.csv");
+        assert_eq!(detections[0].source, ImportSource::StoryGraph);
+        assert!(detections
+            .iter()
+            .any(|d| d.source == ImportSource::Goodreads && d.confidence < dec!(0.5)));
+    }
+
+    #[test]
+    fn unrecognizable_files_have_no_detection() {
+        assert_eq!(detect_fixture("unknown.txt"), vec![]);
     }
 }
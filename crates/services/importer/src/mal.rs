@@ -5,7 +5,9 @@ use std::{
 
 use async_graphql::Result;
 use chrono::NaiveDate;
+use common_models::DefaultCollection;
 use common_utils::convert_string_to_date;
+use convert_case::{Case, Casing};
 use dependent_models::ImportResult;
 use enums::{ImportSource, MediaLot, MediaSource};
 use flate2::bufread::GzDecoder;
@@ -18,36 +20,66 @@ use rust_decimal::{prelude::FromPrimitive, Decimal};
 use rust_decimal_macros::dec;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+use super::{ImportFailStep, ImportFailedItem, ImportFileSample};
+
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    match sample.xml_root.as_deref() {
+        Some("myanimelist") => dec!(1),
+        _ => dec!(0),
+    }
+}
+
+/// Whether the export contains manga rather than anime.
+pub fn is_manga_export(sample: &ImportFileSample) -> bool {
+    sample.contents.contains("<manga>")
+}
+
 pub async fn import(input: DeployMalImportInput) -> Result<ImportResult> {
-    let anime_data = input
-        .anime_path
-        .map(|p| decode_data::<DataRoot>(&p).unwrap())
-        .unwrap_or_default();
-    let manga_data = input
-        .manga_path
-        .map(|p| decode_data::<DataRoot>(&p).unwrap())
-        .unwrap_or_default();
     let mut media = vec![];
-    for item in anime_data.items.into_iter() {
-        media.push(convert_to_format(item, MediaLot::Anime));
-    }
-    for item in manga_data.items.into_iter() {
-        media.push(convert_to_format(item, MediaLot::Manga));
+    let mut failed_items = vec![];
+    // DEV: The kind of export is decided by its elements and not by the input it
+    // was uploaded to, so an anime export in the manga input still works
+    for path in [input.anime_path, input.manga_path].into_iter().flatten() {
+        let data = match decode_data::<DataRoot>(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: None,
+                    step: ImportFailStep::ItemDetailsFromSource,
+                    identifier: path,
+                    error: Some(e.message),
+                });
+                continue;
+            }
+        };
+        for item in data.anime.into_iter() {
+            media.push(convert_to_format(item, MediaLot::Anime));
+        }
+        for item in data.manga.into_iter() {
+            media.push(convert_to_format(item, MediaLot::Manga));
+        }
     }
     Ok(ImportResult {
         metadata: media,
+        failed_items,
         ..Default::default()
     })
 }
 
+/// MAL offers the export as a gzipped file, but it is also accepted after it
+/// has been extracted.
 fn decode_data<T>(path: &str) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    let data = BufReader::new(File::open(path)?);
-    let mut decoder = GzDecoder::new(data);
+    let mut data = vec![];
+    BufReader::new(File::open(path)?).read_to_end(&mut data)?;
     let mut string_data = String::new();
-    decoder.read_to_string(&mut string_data)?;
+    if data.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(data.as_slice()).read_to_string(&mut string_data)?;
+    } else {
+        string_data = String::from_utf8(data)?;
+    }
     let deserialized = serde_xml_rs::from_str::<T>(&string_data)?;
     Ok(deserialized)
 }
@@ -61,7 +93,23 @@ fn get_date(date: String) -> Option<NaiveDate> {
 }
 
 fn convert_to_format(item: Item, lot: MediaLot) -> ImportOrExportMediaItem {
-    let seen_history = (1..item.done + 1)
+    let mut collections = vec![];
+    let done = match item.my_status.as_str() {
+        "Plan to Watch" | "Plan to Read" => {
+            collections.push(DefaultCollection::Watchlist.to_string());
+            0
+        }
+        "Watching" | "Reading" => {
+            collections.push(DefaultCollection::InProgress.to_string());
+            item.done
+        }
+        "Completed" | "" => item.done,
+        status => {
+            collections.push(status.to_case(Case::Title));
+            item.done
+        }
+    };
+    let seen_history = (1..done + 1)
         .map(|i| {
             let (anime_episode, manga_chapter) = match lot {
                 MediaLot::Anime => (Some(i), None),
@@ -94,14 +142,16 @@ fn convert_to_format(item: Item, lot: MediaLot) -> ImportOrExportMediaItem {
         seen_history,
         source_id: item.title.clone(),
         reviews: vec![review_item],
-        ..Default::default()
+        collections,
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct DataRoot {
-    #[serde(alias = "manga", alias = "anime")]
-    items: Vec<Item>,
+    #[serde(default)]
+    anime: Vec<Item>,
+    #[serde(default)]
+    manga: Vec<Item>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -117,4 +167,7 @@ struct Item {
     my_start_date: String,
     my_finish_date: String,
     my_score: u32,
+    /// One of Completed, Watching/Reading, Plan to Watch/Read, On-Hold or Dropped.
+    #[serde(default)]
+    my_status: String,
 }
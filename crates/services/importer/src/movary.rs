@@ -13,7 +13,7 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
-use super::{ImportFailStep, ImportFailedItem, ImportOrExportMediaItem};
+use super::{ImportFailStep, ImportFailedItem, ImportFileSample, ImportOrExportMediaItem};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -39,6 +39,10 @@ struct History {
     comment: Option<String>,
 }
 
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    sample.csv_header_confidence(&["title", "tmdbId", "imdbId", "watchedAt"])
+}
+
 pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
     let lot = MediaLot::Movie;
     let source = MediaSource::Tmdb;
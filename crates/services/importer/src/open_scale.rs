@@ -9,7 +9,7 @@ use media_models::DeployGenericCsvImportInput;
 use rust_decimal::Decimal;
 use serde::Deserialize;
 
-use super::{utils, ImportFailStep, ImportFailedItem};
+use super::{utils, ImportFailStep, ImportFailedItem, ImportFileSample};
 
 #[derive(Debug, Deserialize)]
 struct Record {
@@ -36,6 +36,19 @@ struct Record {
     weight: Option<Decimal>,
 }
 
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    sample.csv_header_confidence(&[
+        "dateTime",
+        "weight",
+        "fat",
+        "water",
+        "muscle",
+        "lbm",
+        "bone",
+        "visceralFat",
+    ])
+}
+
 pub async fn import(
     input: DeployGenericCsvImportInput,
     timezone: &chrono_tz::Tz,
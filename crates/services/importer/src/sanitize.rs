@@ -0,0 +1,252 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+use common_utils::convert_naive_to_utc;
+use dependent_models::ImportResult;
+use enums::MediaLot;
+use importer_models::ImportWarningItem;
+use media_models::{ImportInvalidDateAction, ImportOrExportItemRating};
+
+pub const DEFAULT_EARLIEST_VALID_YEAR: i32 = 1900;
+
+/// Removes or fixes dates that can not be correct before they are imported, so
+/// that they do not end up in the statistics.
+pub struct DateSanitizer {
+    today: NaiveDate,
+    earliest: NaiveDate,
+    action: ImportInvalidDateAction,
+}
+
+impl DateSanitizer {
+    pub fn new(action: ImportInvalidDateAction, earliest_year: i32, today: NaiveDate) -> Self {
+        let earliest = NaiveDate::from_ymd_opt(earliest_year, 1, 1)
+            .or(NaiveDate::from_ymd_opt(DEFAULT_EARLIEST_VALID_YEAR, 1, 1))
+            .unwrap();
+        Self {
+            today,
+            action,
+            earliest,
+        }
+    }
+
+    /// The date that should be used instead and a description of the change, if
+    /// the date needs to be changed.
+    fn adjust(&self, date: NaiveDate) -> Option<(Option<NaiveDate>, String)> {
+        // DEV: A lot of apps write the unix epoch when they do not know the date, there
+        // is no valid date to replace it with.
+        if date == DateTime::UNIX_EPOCH.date_naive() {
+            return Some((
+                None,
+                format!("{date} is a placeholder date and was removed"),
+            ));
+        }
+        let (closest, reason) = if date < self.earliest {
+            (
+                self.earliest,
+                format!("{date} is before {}", self.earliest.year()),
+            )
+        } else if date > self.today {
+            (self.today, format!("{date} is in the future"))
+        } else {
+            return None;
+        };
+        Some(match self.action {
+            ImportInvalidDateAction::Clear => (None, format!("{reason} and was removed")),
+            ImportInvalidDateAction::Clamp => (
+                Some(closest),
+                format!("{reason} and was changed to {closest}"),
+            ),
+        })
+    }
+
+    fn check_date(
+        &self,
+        date: &mut Option<NaiveDate>,
+        field: &str,
+        lot: Option<MediaLot>,
+        identifier: &str,
+        warnings: &mut Vec<ImportWarningItem>,
+    ) {
+        if let Some((replacement, reason)) = date.and_then(|d| self.adjust(d)) {
+            *date = replacement;
+            warnings.push(ImportWarningItem {
+                lot,
+                identifier: identifier.to_owned(),
+                warning: format!("The {field} {reason}"),
+            });
+        }
+    }
+
+    fn check_reviews(
+        &self,
+        reviews: &mut [ImportOrExportItemRating],
+        lot: Option<MediaLot>,
+        identifier: &str,
+        warnings: &mut Vec<ImportWarningItem>,
+    ) {
+        for review in reviews.iter_mut().filter_map(|r| r.review.as_mut()) {
+            let mut date = review.date.map(|d| d.date_naive());
+            self.check_date(&mut date, "review date", lot, identifier, warnings);
+            if date != review.date.map(|d| d.date_naive()) {
+                review.date = date.map(convert_naive_to_utc);
+            }
+        }
+    }
+
+    pub fn sanitize(&self, import: &mut ImportResult) {
+        let mut warnings = vec![];
+        for item in import.metadata.iter_mut() {
+            let identifier = match item.source_id.is_empty() {
+                true => item.identifier.clone(),
+                false => item.source_id.clone(),
+            };
+            let lot = Some(item.lot);
+            for seen in item.seen_history.iter_mut() {
+                self.check_date(
+                    &mut seen.started_on,
+                    "start date",
+                    lot,
+                    &identifier,
+                    &mut warnings,
+                );
+                self.check_date(
+                    &mut seen.ended_on,
+                    "end date",
+                    lot,
+                    &identifier,
+                    &mut warnings,
+                );
+            }
+            self.check_reviews(&mut item.reviews, lot, &identifier, &mut warnings);
+        }
+        for item in import.metadata_groups.iter_mut() {
+            let lot = Some(item.lot);
+            self.check_reviews(&mut item.reviews, lot, &item.title, &mut warnings);
+        }
+        for item in import.people.iter_mut() {
+            self.check_reviews(&mut item.reviews, None, &item.name, &mut warnings);
+        }
+        import.warnings.extend(warnings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use media_models::{
+        ImportOrExportItemReview, ImportOrExportMediaItem, ImportOrExportMediaItemSeen,
+    };
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    fn sanitizer(action: ImportInvalidDateAction) -> DateSanitizer {
+        DateSanitizer::new(action, 1950, date(2024, 6, 1).unwrap())
+    }
+
+    fn import_with_seen(
+        started_on: Option<NaiveDate>,
+        ended_on: Option<NaiveDate>,
+    ) -> ImportResult {
+        ImportResult {
+            metadata: vec![ImportOrExportMediaItem {
+                lot: MediaLot::Book,
+                identifier: "book".to_owned(),
+                seen_history: vec![ImportOrExportMediaItemSeen {
+                    started_on,
+                    ended_on,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid_dates_are_kept() {
+        let mut import = import_with_seen(date(1950, 1, 1), date(2024, 6, 1));
+        sanitizer(ImportInvalidDateAction::Clear).sanitize(&mut import);
+        let seen = &import.metadata[0].seen_history[0];
+        assert_eq!(seen.started_on, date(1950, 1, 1));
+        assert_eq!(seen.ended_on, date(2024, 6, 1));
+        assert!(import.warnings.is_empty());
+    }
+
+    #[test]
+    fn invalid_dates_are_cleared() {
+        let mut import = import_with_seen(date(1949, 12, 31), date(2024, 6, 2));
+        sanitizer(ImportInvalidDateAction::Clear).sanitize(&mut import);
+        let seen = &import.metadata[0].seen_history[0];
+        assert_eq!(seen.started_on, None);
+        assert_eq!(seen.ended_on, None);
+        assert_eq!(import.warnings.len(), 2);
+        assert_eq!(import.warnings[0].identifier, "book");
+        assert_eq!(
+            import.warnings[0].warning,
+            "The start date 1949-12-31 is before 1950 and was removed"
+        );
+    }
+
+    #[test]
+    fn invalid_dates_are_clamped() {
+        let mut import = import_with_seen(date(1800, 1, 1), date(2030, 1, 1));
+        sanitizer(ImportInvalidDateAction::Clamp).sanitize(&mut import);
+        let seen = &import.metadata[0].seen_history[0];
+        assert_eq!(seen.started_on, date(1950, 1, 1));
+        assert_eq!(seen.ended_on, date(2024, 6, 1));
+        assert_eq!(
+            import.warnings[1].warning,
+            "The end date 2030-01-01 is in the future and was changed to 2024-06-01"
+        );
+    }
+
+    #[test]
+    fn the_unix_epoch_is_removed_even_when_clamping() {
+        let mut import = import_with_seen(None, date(1970, 1, 1));
+        DateSanitizer::new(
+            ImportInvalidDateAction::Clamp,
+            1900,
+            date(2024, 6, 1).unwrap(),
+        )
+        .sanitize(&mut import);
+        assert_eq!(import.metadata[0].seen_history[0].ended_on, None);
+        assert_eq!(import.warnings.len(), 1);
+    }
+
+    #[test]
+    fn review_dates_are_checked() {
+        let mut import = ImportResult {
+            metadata: vec![ImportOrExportMediaItem {
+                lot: MediaLot::Movie,
+                source_id: "source".to_owned(),
+                reviews: vec![ImportOrExportItemRating {
+                    review: Some(ImportOrExportItemReview {
+                        date: date(2100, 1, 1).map(convert_naive_to_utc),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        sanitizer(ImportInvalidDateAction::Clamp).sanitize(&mut import);
+        let review = import.metadata[0].reviews[0].review.as_ref().unwrap();
+        assert_eq!(review.date, date(2024, 6, 1).map(convert_naive_to_utc));
+        assert_eq!(import.warnings[0].identifier, "source");
+    }
+
+    #[test]
+    fn an_invalid_earliest_year_falls_back_to_the_default() {
+        let sanitizer = DateSanitizer::new(
+            ImportInvalidDateAction::Clear,
+            i32::MAX,
+            date(2024, 6, 1).unwrap(),
+        );
+        assert_eq!(
+            Some(sanitizer.earliest),
+            date(DEFAULT_EARLIEST_VALID_YEAR, 1, 1)
+        );
+    }
+}
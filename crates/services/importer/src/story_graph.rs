@@ -1,5 +1,6 @@
 use async_graphql::Result;
 use chrono::NaiveDate;
+use common_models::DefaultCollection;
 use common_utils::ryot_log;
 use convert_case::{Case, Casing};
 use csv::Reader;
@@ -15,17 +16,7 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
-use super::{ImportFailStep, ImportFailedItem, ImportOrExportMediaItem};
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-enum ReadStatus {
-    #[serde(rename = "to-read")]
-    ToRead,
-    #[serde(rename = "currently-reading")]
-    CurrentlyReading,
-    Other(String),
-}
+use super::{ImportFailStep, ImportFailedItem, ImportFileSample, ImportOrExportMediaItem};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct History {
@@ -34,7 +25,7 @@ struct History {
     #[serde(rename = "ISBN/UID")]
     isbn: Option<String>,
     #[serde(rename = "Read Status")]
-    read_status: ReadStatus,
+    read_status: String,
     #[serde(rename = "Read Count")]
     read_count: usize,
     #[serde(rename = "Star Rating")]
@@ -43,10 +34,50 @@ struct History {
     review: Option<String>,
     #[serde(rename = "Last Date Read")]
     last_date_read: Option<String>,
+    #[serde(rename = "Dates Read")]
+    dates_read: Option<String>,
     #[serde(rename = "Tags")]
     tags: Option<String>,
 }
 
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    sample.csv_header_confidence(&[
+        "Title",
+        "Authors",
+        "ISBN/UID",
+        "Read Status",
+        "Star Rating",
+        "Last Date Read",
+        "Read Count",
+    ])
+}
+
+type ReadRange = (Option<NaiveDate>, Option<NaiveDate>);
+
+fn parse_date(date: &str) -> Result<Option<NaiveDate>, String> {
+    let date = date.trim();
+    if date.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(date, "%Y/%m/%d")
+        .map(Some)
+        .map_err(|e| format!("Invalid date {:?}: {}", date, e))
+}
+
+/// "Dates Read" has one range per read separated by semicolons, for example
+/// `2023/01/02-2023/01/20;2024/03/01-`. A range without an end is still being read.
+fn parse_dates_read(dates_read: &str) -> Vec<Result<ReadRange, String>> {
+    dates_read
+        .split(';')
+        .map(|r| r.trim())
+        .filter(|r| !r.is_empty())
+        .map(|range| match range.split_once('-') {
+            Some((start, end)) => Ok((parse_date(start)?, parse_date(end)?)),
+            None => Ok((None, parse_date(range)?)),
+        })
+        .collect()
+}
+
 pub async fn import(
     input: DeployGenericCsvImportInput,
     isbn_service: &GoogleBooksService,
@@ -80,24 +111,69 @@ pub async fn import(
         );
         if let Some(isbn) = record.isbn {
             if let Some(identifier) = isbn_service.id_from_isbn(&isbn).await {
-                let mut seen_history = vec![
-                    ImportOrExportMediaItemSeen {
-                        started_on: None,
-                        ended_on: None,
-                        provider_watched_on: Some(ImportSource::StoryGraph.to_string()),
-                        ..Default::default()
-                    };
-                    record.read_count
-                ];
-                if let Some(w) = record.last_date_read {
-                    let w = NaiveDate::parse_from_str(&w, "%Y/%m/%d").unwrap();
-                    seen_history.first_mut().unwrap().ended_on = Some(w);
+                let mut ranges = vec![];
+                for range in parse_dates_read(record.dates_read.as_deref().unwrap_or_default()) {
+                    match range {
+                        Ok(r) => ranges.push(r),
+                        Err(e) => failed_items.push(ImportFailedItem {
+                            lot: Some(lot),
+                            step: ImportFailStep::SeenHistoryConversion,
+                            identifier: record.title.clone(),
+                            error: Some(e),
+                        }),
+                    }
+                }
+                let seen_item = |started_on, ended_on| ImportOrExportMediaItemSeen {
+                    started_on,
+                    ended_on,
+                    provider_watched_on: Some(ImportSource::StoryGraph.to_string()),
+                    ..Default::default()
+                };
+                let mut seen_history = vec![];
+                match record.read_status.as_str() {
+                    "to-read" => {}
+                    "currently-reading" => {
+                        let started_on = ranges
+                            .iter()
+                            .rev()
+                            .find(|(_, end)| end.is_none())
+                            .and_then(|(start, _)| *start);
+                        seen_history.push(ImportOrExportMediaItemSeen {
+                            progress: Some(dec!(0)),
+                            ..seen_item(started_on, None)
+                        });
+                    }
+                    _ if !ranges.is_empty() => {
+                        seen_history.extend(
+                            ranges
+                                .into_iter()
+                                .filter(|(_, end)| end.is_some())
+                                .map(|(start, end)| seen_item(start, end)),
+                        );
+                    }
+                    _ => {
+                        seen_history = vec![seen_item(None, None); record.read_count];
+                        match record.last_date_read.as_deref().map(parse_date) {
+                            Some(Ok(date)) => {
+                                if let Some(first) = seen_history.first_mut() {
+                                    first.ended_on = date;
+                                }
+                            }
+                            Some(Err(e)) => failed_items.push(ImportFailedItem {
+                                lot: Some(lot),
+                                step: ImportFailStep::SeenHistoryConversion,
+                                identifier: record.title.clone(),
+                                error: Some(e),
+                            }),
+                            None => {}
+                        }
+                    }
                 }
                 let mut collections = vec![];
-                collections.push(match record.read_status {
-                    ReadStatus::ToRead => "Watchlist".to_owned(),
-                    ReadStatus::CurrentlyReading => "In Progress".to_owned(),
-                    ReadStatus::Other(s) => s.to_case(Case::Title),
+                collections.push(match record.read_status.as_str() {
+                    "to-read" => DefaultCollection::Watchlist.to_string(),
+                    "currently-reading" => DefaultCollection::InProgress.to_string(),
+                    s => s.to_case(Case::Title),
                 });
                 if let Some(t) = record.tags {
                     collections.extend(t.split(", ").map(|d| d.to_case(Case::Title)))
@@ -149,3 +225,49 @@ pub async fn import(
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn parse_dates_read_splits_ranges() {
+        assert_eq!(
+            parse_dates_read("2023/01/02-2023/01/20; 2024/03/01-"),
+            vec![
+                Ok((date(2023, 1, 2), date(2023, 1, 20))),
+                Ok((date(2024, 3, 1), None))
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_dates_read_treats_a_single_date_as_the_end() {
+        assert_eq!(
+            parse_dates_read("2022/12/31"),
+            vec![Ok((None, date(2022, 12, 31)))]
+        );
+        assert_eq!(
+            parse_dates_read("-2022/12/31"),
+            vec![Ok((None, date(2022, 12, 31)))]
+        );
+    }
+
+    #[test]
+    fn parse_dates_read_skips_empty_ranges() {
+        assert_eq!(parse_dates_read(""), vec![]);
+        assert_eq!(parse_dates_read(" ; ;"), vec![]);
+    }
+
+    #[test]
+    fn parse_dates_read_reports_invalid_dates_per_range() {
+        let ranges = parse_dates_read("2023/13/01-2023/01/20;2024/03/01-2024/03/05");
+        assert_eq!(ranges.len(), 2);
+        assert!(ranges[0].as_ref().unwrap_err().contains("2023/13/01"));
+        assert_eq!(ranges[1], Ok((date(2024, 3, 1), date(2024, 3, 5))));
+    }
+}
@@ -14,7 +14,7 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
-use super::utils;
+use super::{utils, ImportFileSample};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "PascalCase")]
@@ -37,6 +37,17 @@ struct Entry {
     exercise_name: String,
 }
 
+pub fn detect(sample: &ImportFileSample) -> Decimal {
+    sample.csv_header_confidence(&[
+        "Date",
+        "Workout Name",
+        "Exercise Name",
+        "Set Order",
+        "Weight",
+        "Reps",
+    ])
+}
+
 pub async fn import(
     input: DeployStrongAppImportInput,
     timezone: &chrono_tz::Tz,
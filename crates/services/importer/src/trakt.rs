@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use application_utils::get_base_http_client;
 use async_graphql::Result;
 use common_utils::{ryot_log, APPLICATION_JSON_HEADER};
@@ -10,16 +12,29 @@ use media_models::{
     CreateOrUpdateCollectionInput, DeployTraktImportInput, ImportOrExportItemRating,
     ImportOrExportItemReview, ImportOrExportMediaItemSeen,
 };
-use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use providers::{
+    circuit_breaker::{check_retry_after, ProviderRateLimited},
+    pagination::PaginationGuard,
+};
+use reqwest::{
+    header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Client, RequestBuilder, Response,
+};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use sea_orm::prelude::DateTimeUtc;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use tokio::time::sleep;
 
 use super::{ImportFailStep, ImportFailedItem, ImportOrExportMediaItem};
 
 const API_URL: &str = "https://api.trakt.tv";
 const API_VERSION: &str = "2";
+const MAX_HISTORY_PAGES: usize = 1_000;
+const MAX_HISTORY_ITEMS: usize = 1_000_000;
+const MAX_RATE_LIMIT_RETRIES: usize = 5;
+const HISTORY_PAGE_SIZE: usize = 1_000;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Id {
@@ -54,12 +69,81 @@ struct ListResponse {
     items: Vec<ListItemResponse>,
 }
 
+#[derive(Debug, Serialize)]
+struct HistoryQuery {
+    page: usize,
+    limit: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_at: Option<DateTimeUtc>,
+}
+
+/// Trakt rate limits its API, so requests that are asked to back off are retried
+/// after the delay sent by Trakt.
+async fn send(request: impl Fn() -> RequestBuilder) -> anyhow::Result<Response> {
+    let mut retries = 0;
+    loop {
+        let rsp = request().send().await?;
+        match check_retry_after(rsp) {
+            Ok(rsp) => return Ok(rsp.error_for_status()?),
+            Err(e) if retries < MAX_RATE_LIMIT_RETRIES => {
+                let Some(rate_limited) = e.downcast_ref::<ProviderRateLimited>() else {
+                    return Err(e);
+                };
+                let seconds = rate_limited.retry_after.num_seconds().max(1) as u64;
+                ryot_log!(
+                    debug,
+                    "Trakt rate limit reached, retrying in {seconds} seconds"
+                );
+                sleep(Duration::from_secs(seconds)).await;
+                retries += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn get_json<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    query: serde_json::Value,
+) -> anyhow::Result<T> {
+    Ok(send(|| client.get(url).query(&query)).await?.json().await?)
+}
+
+async fn get_history_page(
+    client: &Client,
+    url: &str,
+    page: usize,
+    start_at: Option<DateTimeUtc>,
+) -> anyhow::Result<Vec<ListItemResponse>> {
+    let query = HistoryQuery {
+        page,
+        start_at,
+        limit: HISTORY_PAGE_SIZE,
+    };
+    get_json(client, url, serde_json::to_value(query)?).await
+}
+
+fn request_failed(what: String, error: anyhow::Error) -> ImportFailedItem {
+    ImportFailedItem {
+        lot: None,
+        identifier: what,
+        step: ImportFailStep::ItemDetailsFromSource,
+        error: Some(error.to_string()),
+    }
+}
+
 pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
     let mut media = vec![];
     let mut failed_items = vec![];
 
-    let url = format!("{}/users/{}", API_URL, input.username);
-    let client = get_base_http_client(Some(vec![
+    let username = match input.username.is_empty() && input.access_token.is_some() {
+        // DEV: Trakt resolves `me` to the owner of the access token
+        true => "me",
+        false => &input.username,
+    };
+    let url = format!("{}/users/{}", API_URL, username);
+    let mut headers = vec![
         (CONTENT_TYPE, APPLICATION_JSON_HEADER.clone()),
         (
             HeaderName::from_static("trakt-api-key"),
@@ -69,26 +153,29 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
             HeaderName::from_static("trakt-api-version"),
             HeaderValue::from_static(API_VERSION),
         ),
-    ]));
-    let rsp = client.get(format!("{}/lists", url)).send().await.unwrap();
-    let mut lists: Vec<ListResponse> = rsp.json().await.unwrap();
+    ];
+    if let Some(token) = input.access_token.as_ref() {
+        headers.push((AUTHORIZATION, format!("Bearer {}", token).parse()?));
+    }
+    let client = get_base_http_client(Some(headers));
+    let mut lists: Vec<ListResponse> =
+        get_json(&client, &format!("{}/lists", url), json!({})).await?;
 
     for list in lists.iter_mut() {
-        let rsp = client
-            .get(&format!("{}/lists/{}/items", url, list.ids.trakt))
-            .send()
-            .await
-            .unwrap();
-        let items: Vec<ListItemResponse> = rsp.json().await.unwrap();
-        list.items = items;
+        let list_url = format!("{}/lists/{}/items", url, list.ids.trakt);
+        match get_json(&client, &list_url, json!({})).await {
+            Ok(items) => list.items = items,
+            Err(e) => failed_items.push(request_failed(format!("List {}", list.name), e)),
+        }
     }
     for list in ["watchlist", "favorites"] {
-        let rsp = client
-            .get(format!("{}/{}", url, list))
-            .send()
-            .await
-            .unwrap();
-        let items: Vec<ListItemResponse> = rsp.json().await.unwrap();
+        let items = match get_json(&client, &format!("{}/{}", url, list), json!({})).await {
+            Ok(items) => items,
+            Err(e) => {
+                failed_items.push(request_failed(format!("List {}", list), e));
+                continue;
+            }
+        };
         lists.push(ListResponse {
             name: list.to_owned(),
             description: None,
@@ -128,12 +215,15 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         .collect_vec();
 
     for type_ in ["movies", "shows"] {
-        let rsp = client
-            .get(format!("{}/ratings/{}", url, type_))
-            .send()
-            .await
-            .unwrap();
-        let ratings: Vec<ListItemResponse> = rsp.json().await.unwrap();
+        let ratings_url = format!("{}/ratings/{}", url, type_);
+        let ratings: Vec<ListItemResponse> = match get_json(&client, &ratings_url, json!({})).await
+        {
+            Ok(ratings) => ratings,
+            Err(e) => {
+                failed_items.push(request_failed(format!("Ratings of {}", type_), e));
+                continue;
+            }
+        };
         for item in ratings.iter() {
             match process_item(item) {
                 Ok(mut d) => {
@@ -161,31 +251,53 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         }
     }
 
-    let mut histories = vec![];
-    let rsp = client
-        .head(format!("{}/history", url))
-        .query(&serde_json::json!({ "limit": 1000 }))
-        .send()
-        .await
-        .unwrap();
-    let total_history = rsp
-        .headers()
-        .get("x-pagination-page-count")
-        .expect("pagination to be present")
-        .to_str()
-        .unwrap()
-        .parse::<usize>()
-        .unwrap();
+    let mut histories: Vec<ListItemResponse> = vec![];
+    let history_url = format!("{}/history", url);
+    let start_at = input.history_start_at;
+    let head_query = HistoryQuery {
+        page: 1,
+        start_at,
+        limit: HISTORY_PAGE_SIZE,
+    };
+    let total_history = match send(|| client.head(&history_url).query(&head_query)).await {
+        Ok(rsp) => rsp
+            .headers()
+            .get("x-pagination-page-count")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.parse::<usize>().ok())
+            .unwrap_or(1),
+        Err(e) => {
+            failed_items.push(request_failed("History".to_owned(), e));
+            0
+        }
+    };
+    let mut guard = PaginationGuard::new(MAX_HISTORY_PAGES, MAX_HISTORY_ITEMS);
+    let mut failed_pages = vec![];
     for page in 1..total_history + 1 {
         ryot_log!(debug, "Fetching user history {page:?}/{total_history:?}");
-        let rsp = client
-            .get(format!("{}/history", url))
-            .query(&serde_json::json!({ "page": page, "limit": 1000 }))
-            .send()
-            .await
-            .unwrap();
-        let history: Vec<ListItemResponse> = rsp.json().await.unwrap();
-        histories.extend(history);
+        // DEV: A page that fails is skipped so that the plays on the other pages
+        // are still imported, it gets one more try once they are done
+        match get_history_page(&client, &history_url, page, start_at).await {
+            Ok(history) => {
+                histories.extend(history);
+                if let Some(last) = histories.last() {
+                    let trakt_id = [&last.episode, &last.movie, &last.show]
+                        .into_iter()
+                        .flatten()
+                        .next()
+                        .map(|i| i.ids.trakt);
+                    guard.next_page((last.watched_at, trakt_id), histories.len())?;
+                }
+            }
+            Err(_) => failed_pages.push(page),
+        }
+    }
+    for page in failed_pages {
+        ryot_log!(debug, "Retrying user history page {page:?}");
+        match get_history_page(&client, &history_url, page, start_at).await {
+            Ok(history) => histories.extend(history),
+            Err(e) => failed_items.push(request_failed(format!("History page {}", page), e)),
+        }
     }
 
     for item in histories.iter() {
@@ -1,36 +1,66 @@
 use std::sync::Arc;
 
 use async_graphql::{Error, Result as GqlResult};
-use chrono::Utc;
-use common_utils::ryot_log;
+use background::CoreApplicationJob;
+use chrono::{DateTime, Duration, Utc};
+use common_utils::{ryot_log, OUTBOUND_WEBHOOK_PAYLOAD_VERSION};
 use database_models::{
-    integration, metadata,
-    prelude::{CollectionToEntity, Integration, Metadata, Seen, UserToEntity},
-    seen, user_to_entity,
+    functions::associate_user_with_entity,
+    integration, integration_item_mapping, integration_webhook_delivery, metadata,
+    outbound_webhook_delivery,
+    prelude::{
+        Collection, CollectionToEntity, Integration, IntegrationItemMapping,
+        IntegrationWebhookDelivery, Metadata, OutboundWebhookDelivery, Seen, UserToEntity,
+        VideoGameSession, WebhookDebugCapture,
+    },
+    user_to_entity, video_game_session, webhook_debug_capture,
+};
+use database_utils::{metadata_by_external_id, user_by_id};
+use dependent_models::{ImportResult, ImportServerItemId};
+use dependent_utils::{
+    commit_metadata, process_import, queue_notifications_to_user_platforms,
+    queue_outbound_webhook_event,
+};
+use enums::{
+    EntityLot, ExternalIdType, IntegrationHealthState, IntegrationLot, IntegrationProvider,
+    IntegrationWebhookDeliveryStatus, MediaLot, MediaSource, OutboundWebhookDeliveryStatus,
+    OutboundWebhookEvent,
+};
+use itertools::Itertools;
+use media_models::{
+    CommitMediaInput, OutboundWebhookPayload, WebhookSamplePayloadKind, WebhookSimulationStep,
 };
-use database_utils::user_by_id;
-use dependent_models::ImportResult;
-use dependent_utils::{commit_metadata, process_import};
-use enums::{EntityLot, IntegrationLot, IntegrationProvider, MediaLot};
-use media_models::SeenShowExtraInformation;
+use nanoid::nanoid;
 use providers::google_books::GoogleBooksService;
 use push::jellyfin::JellyfinPushIntegration;
 use rust_decimal_macros::dec;
-use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, Insert,
+    ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select,
+};
+use serde_json::json;
 use sink::generic_json::GenericJsonSinkIntegration;
 use supporting_service::SupportingService;
 use traits::TraceOk;
 use uuid::Uuid;
 
 mod push;
+mod samples;
 mod sink;
 mod utils;
 mod yank;
 
 use crate::{
-    push::{radarr::RadarrPushIntegration, sonarr::SonarrPushIntegration},
+    push::{
+        radarr::RadarrPushIntegration, sonarr::SonarrPushIntegration,
+        webhook::WebhookPushIntegration,
+    },
     sink::{
-        emby::EmbySinkIntegration, jellyfin::JellyfinSinkIntegration, kodi::KodiSinkIntegration,
+        emby::EmbySinkIntegration,
+        game_session::{GameHeartbeat, GameSessionSinkIntegration},
+        jellyfin::JellyfinSinkIntegration,
+        kodi::KodiSinkIntegration,
         plex::PlexSinkIntegration,
     },
     yank::{audiobookshelf::AudiobookshelfYankIntegration, komga::KomgaYankIntegration},
@@ -38,56 +68,571 @@ use crate::{
 
 pub struct IntegrationService(pub Arc<SupportingService>);
 
+// DEV: Anything that looks like it could carry credentials is never stored
+const REDACTED_HEADER_PARTS: [&str; 6] = [
+    "authorization",
+    "cookie",
+    "token",
+    "api-key",
+    "apikey",
+    "secret",
+];
+const WEBHOOK_DEBUG_CAPTURES_TO_KEEP: u64 = 50;
+const WEBHOOK_DELIVERIES_TO_KEEP: u64 = 100;
+const WEBHOOK_DELIVERY_ATTEMPTS: i32 = 3;
+// DEV: Retries are picked up by the integrations sync job, so a delay is rounded up
+// to its next run
+const WEBHOOK_DELIVERY_RETRY_DELAY_MINUTES: i64 = 1;
+// DEV: A delivery is claimed for this long. If the server stopped while processing
+// it, it is picked up again once this has passed.
+const WEBHOOK_DELIVERY_CLAIM_MINUTES: i64 = 15;
+const FAILURES_BEFORE_ERRORING: i32 = 3;
+// DEV: Retries are picked up by the integrations sync job, so a delay is rounded up
+// to its next run. Deliveries are dead lettered once these are exhausted.
+const OUTBOUND_WEBHOOK_RETRY_DELAYS_MINUTES: [i64; 4] = [1, 5, 30, 120];
+
+/// How a webhook request was handled, returned to the server that sent it.
+pub enum WebhookResponse {
+    /// The request was queued, along with the id of the delivery.
+    Queued(String),
+    /// The request was processed before responding.
+    Processed(String),
+}
+
+enum WebhookOutcome {
+    Updated,
+    ParseFailed(String),
+    ImportFailed(String),
+}
+
+/// The last time the integration received data, integrations that have never
+/// been triggered count from when they were created.
+fn last_activity_on(integration: &integration::Model) -> DateTime<Utc> {
+    integration
+        .last_triggered_on
+        .unwrap_or(integration.created_on)
+}
+
+/// An integration is erroring after a run of failed attempts and stale when it
+/// has not received data within its expected activity window.
+///
+/// Erroring is based on consecutive failures rather than on a failure ratio. The
+/// outcome of every attempt is not stored, and a long healthy history would hide
+/// an integration that broke recently behind a low ratio. A single success
+/// resets the count.
+fn integration_health_state(
+    integration: &integration::Model,
+    now: DateTime<Utc>,
+) -> IntegrationHealthState {
+    if integration.consecutive_failures >= FAILURES_BEFORE_ERRORING {
+        return IntegrationHealthState::Erroring;
+    }
+    let is_stale = integration.expected_activity_days.map_or(false, |days| {
+        last_activity_on(integration) + Duration::days(days.into()) < now
+    });
+    match is_stale {
+        true => IntegrationHealthState::Stale,
+        false => IntegrationHealthState::Healthy,
+    }
+}
+
+/// Whether the user should be told that an integration went stale. Alerts are not
+/// sent while they are snoozed, and only one is sent per stale period.
+fn should_alert_stale(integration: &integration::Model, now: DateTime<Utc>) -> bool {
+    let is_snoozed = integration
+        .alerts_snoozed_until
+        .map_or(false, |until| until > now);
+    // DEV: The alert is re-armed once the integration receives data again
+    let already_alerted = integration
+        .stale_alert_sent_on
+        .map_or(false, |on| on > last_activity_on(integration));
+    integration_health_state(integration, now) == IntegrationHealthState::Stale
+        && !is_snoozed
+        && !already_alerted
+}
+
+fn apply_progress_thresholds(integration: &integration::Model, import: &mut ImportResult) {
+    import.metadata.iter_mut().for_each(|media| {
+        media.seen_history.retain(|update| match update.progress {
+            Some(progress) if progress < integration.minimum_progress.unwrap() => {
+                ryot_log!(
+                    debug,
+                    "Progress update for integration {} is below minimum threshold",
+                    integration.id
+                );
+                false
+            }
+            Some(_) => true,
+            None => false,
+        });
+        media.seen_history.iter_mut().for_each(|update| {
+            update.ended_on = Some(Utc::now().date_naive());
+            if let Some(progress) = update.progress {
+                if progress > integration.maximum_progress.unwrap() {
+                    ryot_log!(
+                        debug,
+                        "Changing progress to 100 for integration {}",
+                        integration.id
+                    );
+                    update.progress = Some(dec!(100));
+                }
+            }
+        });
+    });
+}
+
+/// Media that the user does not have yet is also added to the collection set on the
+/// integration. Media the user already has is left alone so that removing it from
+/// the collection by hand sticks.
+async fn add_new_items_to_default_collection(
+    integration: &integration::Model,
+    import: &mut ImportResult,
+    db: &DatabaseConnection,
+) -> GqlResult<()> {
+    let Some(collection) = &integration.default_collection else {
+        return Ok(());
+    };
+    for item in import.metadata.iter_mut() {
+        let is_known = Metadata::find()
+            .inner_join(UserToEntity)
+            .filter(user_to_entity::Column::UserId.eq(&integration.user_id))
+            .filter(metadata::Column::Lot.eq(item.lot))
+            .filter(metadata::Column::Source.eq(item.source))
+            .filter(metadata::Column::Identifier.eq(&item.identifier))
+            .count(db)
+            .await?
+            > 0;
+        if !is_known && !item.collections.contains(collection) {
+            item.collections.push(collection.clone());
+        }
+    }
+    Ok(())
+}
+
+/// The session of a game that a heartbeat at this time belongs to, if it is still
+/// running.
+fn running_game_session(
+    user_id: &String,
+    metadata_id: &String,
+    at: DateTime<Utc>,
+    idle_gap: Duration,
+) -> Select<VideoGameSession> {
+    VideoGameSession::find()
+        .filter(video_game_session::Column::UserId.eq(user_id))
+        .filter(video_game_session::Column::MetadataId.eq(metadata_id))
+        .filter(video_game_session::Column::LastHeartbeatOn.gte(at - idle_gap))
+        .filter(video_game_session::Column::StartedOn.lte(at + idle_gap))
+        .order_by_desc(video_game_session::Column::LastHeartbeatOn)
+}
+
+fn extended_game_session(
+    session: video_game_session::Model,
+    at: DateTime<Utc>,
+) -> video_game_session::Model {
+    // DEV: Queued heartbeats can arrive out of order
+    let started_on = session.started_on.min(at);
+    let last_heartbeat_on = session.last_heartbeat_on.max(at);
+    let duration = (last_heartbeat_on - started_on).num_seconds();
+    video_game_session::Model {
+        started_on,
+        last_heartbeat_on,
+        duration: duration.try_into().unwrap_or(i32::MAX),
+        ..session
+    }
+}
+
+/// The debug mode expires on its own, so it is checked against the current time on
+/// every request instead of relying on it being turned off.
+fn is_in_debug_mode(integration: &integration::Model, now: DateTime<Utc>) -> bool {
+    integration
+        .debug_mode_until
+        .map_or(false, |until| until > now)
+}
+
+/// Media servers can re-import an item under a new id, which then replaces the
+/// mapping that was stored for it.
+fn upsert_item_mapping(
+    integration_id: &str,
+    metadata_id: String,
+    item: ImportServerItemId,
+) -> Insert<integration_item_mapping::ActiveModel> {
+    // DEV: `before_save` is not run by `Entity::insert`, so the id is set here
+    let mapping = integration_item_mapping::ActiveModel {
+        id: ActiveValue::Set(format!("iim_{}", nanoid!(12))),
+        metadata_id: ActiveValue::Set(metadata_id),
+        updated_on: ActiveValue::Set(Utc::now()),
+        server_id: ActiveValue::Set(item.server_id),
+        server_item_id: ActiveValue::Set(item.server_item_id),
+        integration_id: ActiveValue::Set(integration_id.to_owned()),
+    };
+    IntegrationItemMapping::insert(mapping).on_conflict(
+        OnConflict::columns([
+            integration_item_mapping::Column::IntegrationId,
+            integration_item_mapping::Column::MetadataId,
+        ])
+        .update_columns([
+            integration_item_mapping::Column::ServerItemId,
+            integration_item_mapping::Column::ServerId,
+            integration_item_mapping::Column::UpdatedOn,
+        ])
+        .to_owned(),
+    )
+}
+
+/// The queued deliveries of an integration that are due, in the order in which
+/// they were received.
+fn due_webhook_deliveries(
+    integration_id: String,
+    now: DateTime<Utc>,
+) -> Select<IntegrationWebhookDelivery> {
+    IntegrationWebhookDelivery::find()
+        .filter(integration_webhook_delivery::Column::IntegrationId.eq(integration_id))
+        .filter(
+            integration_webhook_delivery::Column::Status
+                .eq(IntegrationWebhookDeliveryStatus::Queued),
+        )
+        .filter(integration_webhook_delivery::Column::NextAttemptOn.lte(now))
+        .order_by_asc(integration_webhook_delivery::Column::ReceivedOn)
+}
+
+/// Record an attempt at processing a delivery. Deliveries whose import failed stay
+/// queued with a growing delay until they run out of attempts. Also returns whether
+/// the delivery is finished.
+fn attempted_webhook_delivery(
+    delivery: integration_webhook_delivery::Model,
+    outcome: WebhookOutcome,
+    now: DateTime<Utc>,
+) -> (integration_webhook_delivery::ActiveModel, bool) {
+    let attempts = delivery.attempts + 1;
+    let mut to_update: integration_webhook_delivery::ActiveModel = delivery.into();
+    to_update.attempts = ActiveValue::Set(attempts);
+    match outcome {
+        WebhookOutcome::ImportFailed(e) if attempts < WEBHOOK_DELIVERY_ATTEMPTS => {
+            let delay = WEBHOOK_DELIVERY_RETRY_DELAY_MINUTES * i64::from(attempts);
+            to_update.error = ActiveValue::Set(Some(e));
+            to_update.next_attempt_on = ActiveValue::Set(now + Duration::minutes(delay));
+            return (to_update, false);
+        }
+        WebhookOutcome::Updated => {
+            to_update.error = ActiveValue::Set(None);
+            to_update.status = ActiveValue::Set(IntegrationWebhookDeliveryStatus::Processed);
+        }
+        WebhookOutcome::ParseFailed(e) | WebhookOutcome::ImportFailed(e) => {
+            to_update.error = ActiveValue::Set(Some(e));
+            to_update.status = ActiveValue::Set(IntegrationWebhookDeliveryStatus::Failed);
+        }
+    }
+    to_update.processed_on = ActiveValue::Set(Some(now));
+    (to_update, true)
+}
+
+/// How long to wait before retrying a failed outbound webhook delivery. There is no
+/// retry once the delays are exhausted, the delivery is then dead lettered.
+fn outbound_webhook_retry_delay(attempts: i32, retry_on_failure: bool) -> Option<i64> {
+    let attempt = usize::try_from(attempts - 1).ok()?;
+    OUTBOUND_WEBHOOK_RETRY_DELAYS_MINUTES
+        .get(attempt)
+        .copied()
+        .filter(|_| retry_on_failure)
+}
+
+fn redact_headers(headers: Vec<(String, String)>) -> Vec<String> {
+    headers
+        .into_iter()
+        .filter(|(name, _)| {
+            let name = name.to_lowercase();
+            !REDACTED_HEADER_PARTS.iter().any(|p| name.contains(p))
+        })
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect_vec()
+}
+
+fn describe_parse_outcome(parsed: &anyhow::Result<ImportResult>) -> (bool, String) {
+    match parsed {
+        Ok(r) => (true, format!("Parsed {} item(s)", r.metadata.len())),
+        Err(e) => (false, e.to_string()),
+    }
+}
+
 impl IntegrationService {
-    async fn integration_progress_update(
+    async fn record_integration_outcome(
         &self,
         integration: integration::Model,
+        was_success: bool,
+    ) -> GqlResult<()> {
+        let mut updated = integration.clone();
+        match was_success {
+            true => {
+                updated.consecutive_failures = 0;
+                updated.last_triggered_on = Some(Utc::now());
+            }
+            false => updated.consecutive_failures += 1,
+        }
+        updated.health_state = integration_health_state(&updated, Utc::now());
+        let mut to_update: integration::ActiveModel = integration.into();
+        to_update.consecutive_failures = ActiveValue::Set(updated.consecutive_failures);
+        to_update.last_triggered_on = ActiveValue::Set(updated.last_triggered_on);
+        to_update.health_state = ActiveValue::Set(updated.health_state);
+        to_update.update(&self.0.db).await?;
+        Ok(())
+    }
+
+    /// Remember the ids that the media server uses for the items, replacing the
+    /// ones seen before in case the server re-imported them with new ids.
+    async fn save_server_item_ids(
+        &self,
+        integration_id: &String,
+        server_item_ids: Vec<ImportServerItemId>,
+    ) -> GqlResult<()> {
+        for item in server_item_ids {
+            let Some(metadata_id) = Metadata::find()
+                .select_only()
+                .column(metadata::Column::Id)
+                .filter(metadata::Column::Lot.eq(item.lot))
+                .filter(metadata::Column::Source.eq(item.source))
+                .filter(metadata::Column::Identifier.eq(&item.identifier))
+                .into_tuple::<String>()
+                .one(&self.0.db)
+                .await?
+            else {
+                continue;
+            };
+            upsert_item_mapping(integration_id, metadata_id, item)
+                .exec_without_returning(&self.0.db)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn import_integration_progress(
+        &self,
+        integration: &integration::Model,
         updates: ImportResult,
     ) -> GqlResult<()> {
         let mut import = updates;
-        import.metadata.iter_mut().for_each(|media| {
-            media.seen_history.retain(|update| match update.progress {
-                Some(progress) if progress < integration.minimum_progress.unwrap() => {
-                    ryot_log!(
-                        debug,
-                        "Progress update for integration {} is below minimum threshold",
-                        integration.id
-                    );
-                    false
-                }
-                Some(_) => true,
-                None => false,
-            });
-            media.seen_history.iter_mut().for_each(|update| {
-                update.ended_on = Some(Utc::now().date_naive());
-                if let Some(progress) = update.progress {
-                    if progress > integration.maximum_progress.unwrap() {
-                        ryot_log!(
-                            debug,
-                            "Changing progress to 100 for integration {}",
-                            integration.id
-                        );
-                        update.progress = Some(dec!(100));
-                    }
+        apply_progress_thresholds(integration, &mut import);
+        add_new_items_to_default_collection(integration, &mut import, &self.0.db).await?;
+        let server_item_ids = std::mem::take(&mut import.server_item_ids);
+        process_import(&integration.user_id, true, import, &self.0).await?;
+        self.save_server_item_ids(&integration.id, server_item_ids)
+            .await?;
+        Ok(())
+    }
+
+    async fn integration_progress_update(
+        &self,
+        integration: integration::Model,
+        updates: ImportResult,
+    ) -> GqlResult<()> {
+        let was_success = match self
+            .import_integration_progress(&integration, updates)
+            .await
+        {
+            Ok(_) => true,
+            Err(err) => {
+                ryot_log!(debug, "Error updating progress: {:?}", err);
+                false
+            }
+        };
+        self.record_integration_outcome(integration, was_success)
+            .await
+    }
+
+    async fn parse_webhook_payload(
+        &self,
+        integration: &integration::Model,
+        payload: String,
+    ) -> GqlResult<(&'static str, anyhow::Result<ImportResult>)> {
+        let parsed = match integration.provider {
+            IntegrationProvider::Kodi => {
+                let kodi = KodiSinkIntegration::new(payload);
+                ("kodi", kodi.yank_progress().await)
+            }
+            IntegrationProvider::Emby => {
+                let emby = EmbySinkIntegration::new(payload, self.0.db.clone());
+                ("emby", emby.yank_progress().await)
+            }
+            IntegrationProvider::JellyfinSink => {
+                let jellyfin = JellyfinSinkIntegration::new(payload);
+                ("jellyfin", jellyfin.yank_progress().await)
+            }
+            IntegrationProvider::Plex => {
+                let specifics = integration.clone().provider_specifics.unwrap();
+                let plex =
+                    PlexSinkIntegration::new(payload, specifics.plex_username, self.0.db.clone());
+                ("plex", plex.yank_progress().await)
+            }
+            IntegrationProvider::GenericJson => {
+                let generic_json = GenericJsonSinkIntegration::new(payload);
+                ("generic_json", generic_json.yank_progress().await)
+            }
+            _ => return Err(Error::new("Unsupported integration source".to_owned())),
+        };
+        Ok(parsed)
+    }
+
+    async fn capture_webhook_request(
+        &self,
+        integration_id: &String,
+        headers: Vec<String>,
+        body: String,
+        parser: &str,
+        parsed: &anyhow::Result<ImportResult>,
+    ) -> GqlResult<()> {
+        let (was_success, outcome) = describe_parse_outcome(parsed);
+        let capture = webhook_debug_capture::ActiveModel {
+            body: ActiveValue::Set(body),
+            headers: ActiveValue::Set(headers),
+            outcome: ActiveValue::Set(outcome),
+            parser: ActiveValue::Set(parser.to_owned()),
+            was_success: ActiveValue::Set(was_success),
+            captured_on: ActiveValue::Set(Utc::now()),
+            integration_id: ActiveValue::Set(integration_id.to_owned()),
+            ..Default::default()
+        };
+        capture.insert(&self.0.db).await?;
+        let to_keep = WebhookDebugCapture::find()
+            .select_only()
+            .column(webhook_debug_capture::Column::Id)
+            .filter(webhook_debug_capture::Column::IntegrationId.eq(integration_id))
+            .order_by_desc(webhook_debug_capture::Column::CapturedOn)
+            .limit(WEBHOOK_DEBUG_CAPTURES_TO_KEEP)
+            .into_tuple::<String>()
+            .all(&self.0.db)
+            .await?;
+        WebhookDebugCapture::delete_many()
+            .filter(webhook_debug_capture::Column::IntegrationId.eq(integration_id))
+            .filter(webhook_debug_capture::Column::Id.is_not_in(to_keep))
+            .exec(&self.0.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Open a session for the game in the heartbeat or extend the one that is
+    /// running. Every game has its own session so two games can run at once.
+    async fn record_game_heartbeat(
+        &self,
+        integration: &integration::Model,
+        heartbeat: GameHeartbeat,
+    ) -> GqlResult<()> {
+        let found =
+            metadata_by_external_id(heartbeat.id_type, &heartbeat.value, &self.0.db).await?;
+        let metadata = match found {
+            Some(m) => m,
+            None if heartbeat.id_type == ExternalIdType::Igdb => {
+                commit_metadata(
+                    CommitMediaInput {
+                        lot: MediaLot::VideoGame,
+                        source: MediaSource::Igdb,
+                        identifier: heartbeat.value.clone(),
+                        force_update: None,
+                    },
+                    &self.0,
+                )
+                .await?
+            }
+            None => {
+                return Err(Error::new(format!(
+                    "No game is known by the {} id {}",
+                    heartbeat.id_type, heartbeat.value
+                )))
+            }
+        };
+        if metadata.lot != MediaLot::VideoGame {
+            return Err(Error::new(format!(
+                "{} is not a video game",
+                metadata.title
+            )));
+        }
+        let at = heartbeat.received_on;
+        let idle_gap = Duration::minutes(self.0.config.integration.game_session_idle_minutes);
+        let running = running_game_session(&integration.user_id, &metadata.id, at, idle_gap)
+            .one(&self.0.db)
+            .await?;
+        match running {
+            Some(session) => {
+                let extended = extended_game_session(session.clone(), at);
+                let mut session: video_game_session::ActiveModel = session.into();
+                session.started_on = ActiveValue::Set(extended.started_on);
+                session.last_heartbeat_on = ActiveValue::Set(extended.last_heartbeat_on);
+                session.duration = ActiveValue::Set(extended.duration);
+                session.update(&self.0.db).await?;
+            }
+            None => {
+                video_game_session::ActiveModel {
+                    started_on: ActiveValue::Set(at),
+                    last_heartbeat_on: ActiveValue::Set(at),
+                    metadata_id: ActiveValue::Set(metadata.id.clone()),
+                    user_id: ActiveValue::Set(integration.user_id.clone()),
+                    integration_id: ActiveValue::Set(Some(integration.id.clone())),
+                    ..Default::default()
                 }
-            });
-        });
-        match process_import(&integration.user_id, true, import, &self.0).await {
-            Ok(_) => {
-                let mut to_update: integration::ActiveModel = integration.into();
-                to_update.last_triggered_on = ActiveValue::Set(Some(Utc::now()));
-                to_update.update(&self.0.db).await?;
+                .insert(&self.0.db)
+                .await?;
             }
-            Err(err) => ryot_log!(debug, "Error updating progress: {:?}", err),
         }
+        associate_user_with_entity(
+            &self.0.db,
+            &integration.user_id,
+            metadata.id,
+            EntityLot::Metadata,
+        )
+        .await?;
         Ok(())
     }
 
+    /// Parse a webhook payload and import the progress in it. Requests are only
+    /// captured when headers are passed, so that retries are not captured twice.
+    async fn handle_webhook_payload(
+        &self,
+        integration: &integration::Model,
+        headers: Option<Vec<String>>,
+        payload: String,
+    ) -> GqlResult<WebhookOutcome> {
+        if integration.provider == IntegrationProvider::GameSession {
+            let heartbeat = match GameSessionSinkIntegration::new(payload).heartbeat() {
+                Ok(h) => h,
+                Err(e) => return Ok(WebhookOutcome::ParseFailed(e.to_string())),
+            };
+            return Ok(
+                match self.record_game_heartbeat(integration, heartbeat).await {
+                    Ok(_) => WebhookOutcome::Updated,
+                    Err(e) => WebhookOutcome::ImportFailed(e.message),
+                },
+            );
+        }
+        let body = is_in_debug_mode(integration, Utc::now()).then(|| payload.clone());
+        let (parser, maybe_progress_update) =
+            self.parse_webhook_payload(integration, payload).await?;
+        if let (Some(body), Some(headers)) = (body, headers) {
+            self.capture_webhook_request(
+                &integration.id,
+                headers,
+                body,
+                parser,
+                &maybe_progress_update,
+            )
+            .await
+            .trace_ok();
+        }
+        let updates = match maybe_progress_update {
+            Ok(pu) => pu,
+            Err(e) => return Ok(WebhookOutcome::ParseFailed(e.to_string())),
+        };
+        Ok(
+            match self.import_integration_progress(integration, updates).await {
+                Ok(_) => WebhookOutcome::Updated,
+                Err(e) => WebhookOutcome::ImportFailed(e.message),
+            },
+        )
+    }
+
     pub async fn process_integration_webhook(
         &self,
         integration_slug: String,
+        headers: Vec<(String, String)>,
         payload: String,
-    ) -> GqlResult<String> {
+    ) -> GqlResult<WebhookResponse> {
         ryot_log!(
             debug,
             "Processing integration webhook for slug: {}",
@@ -101,40 +646,463 @@ impl IntegrationService {
         if integration.is_disabled.unwrap_or_default() || preferences.general.disable_integrations {
             return Err(Error::new("Integration is disabled".to_owned()));
         }
-        let maybe_progress_update = match integration.provider {
-            IntegrationProvider::Kodi => {
-                let kodi = KodiSinkIntegration::new(payload);
-                kodi.yank_progress().await
-            }
-            IntegrationProvider::Emby => {
-                let emby = EmbySinkIntegration::new(payload, self.0.db.clone());
-                emby.yank_progress().await
+        if payload.trim().is_empty() {
+            return Err(Error::new("Webhook payload is empty".to_owned()));
+        }
+        let headers = redact_headers(headers);
+        if self.0.config.integration.process_webhooks_synchronously {
+            let outcome = self
+                .handle_webhook_payload(&integration, Some(headers), payload)
+                .await?;
+            let was_success = matches!(outcome, WebhookOutcome::Updated);
+            self.record_integration_outcome(integration, was_success)
+                .await
+                .trace_ok();
+            return match outcome {
+                WebhookOutcome::ParseFailed(e) => Err(Error::new(e)),
+                WebhookOutcome::ImportFailed(e) => {
+                    ryot_log!(debug, "Error updating progress: {:?}", e);
+                    Ok(WebhookResponse::Processed(
+                        "Progress updated successfully".to_owned(),
+                    ))
+                }
+                WebhookOutcome::Updated => Ok(WebhookResponse::Processed(
+                    "Progress updated successfully".to_owned(),
+                )),
+            };
+        }
+        let delivery = integration_webhook_delivery::ActiveModel {
+            payload: ActiveValue::Set(payload),
+            headers: ActiveValue::Set(headers),
+            received_on: ActiveValue::Set(Utc::now()),
+            integration_id: ActiveValue::Set(integration.id.clone()),
+            status: ActiveValue::Set(IntegrationWebhookDeliveryStatus::Queued),
+            ..Default::default()
+        }
+        .insert(&self.0.db)
+        .await?;
+        self.0
+            .perform_core_application_job(CoreApplicationJob::ProcessIntegrationWebhookDeliveries(
+                integration.id,
+            ))
+            .await?;
+        Ok(WebhookResponse::Queued(delivery.id))
+    }
+
+    /// Mark a queued delivery as being processed. Only one job can claim it, so
+    /// this returns `false` when another one already picked it up.
+    async fn claim_webhook_delivery(&self, delivery_id: &String) -> GqlResult<bool> {
+        let now = Utc::now();
+        let claimed = IntegrationWebhookDelivery::update_many()
+            .col_expr(
+                integration_webhook_delivery::Column::NextAttemptOn,
+                Expr::value(now + Duration::minutes(WEBHOOK_DELIVERY_CLAIM_MINUTES)),
+            )
+            .filter(integration_webhook_delivery::Column::Id.eq(delivery_id))
+            .filter(
+                integration_webhook_delivery::Column::Status
+                    .eq(IntegrationWebhookDeliveryStatus::Queued),
+            )
+            .filter(integration_webhook_delivery::Column::NextAttemptOn.lte(now))
+            .exec(&self.0.db)
+            .await?;
+        Ok(claimed.rows_affected == 1)
+    }
+
+    async fn process_webhook_delivery(
+        &self,
+        delivery: integration_webhook_delivery::Model,
+    ) -> GqlResult<()> {
+        let integration = Integration::find_by_id(&delivery.integration_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Integration does not exist".to_owned()))?;
+        let attempts = delivery.attempts + 1;
+        let headers = (attempts == 1).then(|| delivery.headers.clone());
+        let outcome = self
+            .handle_webhook_payload(&integration, headers, delivery.payload.clone())
+            .await
+            .unwrap_or_else(|e| WebhookOutcome::ParseFailed(e.message));
+        let integration_id = delivery.integration_id.clone();
+        let delivery_id = delivery.id.clone();
+        let was_success = matches!(outcome, WebhookOutcome::Updated);
+        let (to_update, is_finished) = attempted_webhook_delivery(delivery, outcome, Utc::now());
+        let updated = to_update.update(&self.0.db).await?;
+        if !is_finished {
+            ryot_log!(
+                debug,
+                "Retrying webhook delivery {} after error: {:?}",
+                delivery_id,
+                updated.error
+            );
+            return Ok(());
+        }
+        self.record_integration_outcome(integration, was_success)
+            .await
+            .trace_ok();
+        let to_keep = IntegrationWebhookDelivery::find()
+            .select_only()
+            .column(integration_webhook_delivery::Column::Id)
+            .filter(integration_webhook_delivery::Column::IntegrationId.eq(&integration_id))
+            .order_by_desc(integration_webhook_delivery::Column::ReceivedOn)
+            .limit(WEBHOOK_DELIVERIES_TO_KEEP)
+            .into_tuple::<String>()
+            .all(&self.0.db)
+            .await?;
+        IntegrationWebhookDelivery::delete_many()
+            .filter(integration_webhook_delivery::Column::IntegrationId.eq(&integration_id))
+            .filter(
+                integration_webhook_delivery::Column::Status
+                    .ne(IntegrationWebhookDeliveryStatus::Queued),
+            )
+            .filter(integration_webhook_delivery::Column::Id.is_not_in(to_keep))
+            .exec(&self.0.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Process the queued webhook deliveries of an integration that are due, in the
+    /// order in which they were received. Deliveries that another job already
+    /// claimed are skipped.
+    pub async fn process_webhook_deliveries(&self, integration_id: String) -> GqlResult<()> {
+        let deliveries = due_webhook_deliveries(integration_id, Utc::now())
+            .all(&self.0.db)
+            .await?;
+        for delivery in deliveries {
+            if !self.claim_webhook_delivery(&delivery.id).await? {
+                ryot_log!(debug, "Webhook delivery {} already claimed", delivery.id);
+                continue;
             }
-            IntegrationProvider::JellyfinSink => {
-                let jellyfin = JellyfinSinkIntegration::new(payload);
-                jellyfin.yank_progress().await
+            self.process_webhook_delivery(delivery).await?;
+        }
+        Ok(())
+    }
+
+    /// Queue a job for every integration with deliveries that are due. These are
+    /// the ones waiting for a retry and the ones whose job never finished.
+    pub async fn requeue_stuck_webhook_deliveries(&self) -> GqlResult<()> {
+        let integration_ids = IntegrationWebhookDelivery::find()
+            .select_only()
+            .column(integration_webhook_delivery::Column::IntegrationId)
+            .distinct()
+            .filter(
+                integration_webhook_delivery::Column::Status
+                    .eq(IntegrationWebhookDeliveryStatus::Queued),
+            )
+            .filter(integration_webhook_delivery::Column::NextAttemptOn.lte(Utc::now()))
+            .into_tuple::<String>()
+            .all(&self.0.db)
+            .await?;
+        for integration_id in integration_ids {
+            ryot_log!(
+                debug,
+                "Requeueing webhook deliveries for integration {}",
+                integration_id
+            );
+            self.0
+                .perform_core_application_job(
+                    CoreApplicationJob::ProcessIntegrationWebhookDeliveries(integration_id),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn integration_webhook_deliveries(
+        &self,
+        user_id: &String,
+        integration_id: String,
+    ) -> GqlResult<Vec<integration_webhook_delivery::Model>> {
+        let integration = Integration::find_by_id(integration_id)
+            .filter(integration::Column::UserId.eq(user_id))
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Integration does not exist".to_owned()))?;
+        let deliveries = integration
+            .find_related(IntegrationWebhookDelivery)
+            .order_by_desc(integration_webhook_delivery::Column::ReceivedOn)
+            .all(&self.0.db)
+            .await?;
+        Ok(deliveries)
+    }
+
+    async fn attempt_outbound_webhook_delivery(
+        &self,
+        integration: &integration::Model,
+        delivery: outbound_webhook_delivery::Model,
+        retry_on_failure: bool,
+    ) -> GqlResult<outbound_webhook_delivery::Model> {
+        let specifics = integration.provider_specifics.clone().unwrap_or_default();
+        let outcome = match specifics.webhook_url {
+            None => Err("The integration does not have a webhook url".to_owned()),
+            Some(url) => WebhookPushIntegration::new(url, specifics.webhook_secret)
+                .push_event(&delivery.id, delivery.event, delivery.payload.clone())
+                .await
+                .map_err(|e| e.to_string()),
+        };
+        let (response_status, error) = match outcome {
+            Ok(status) if status.is_success() => (Some(status), None),
+            Ok(status) => (Some(status), Some(format!("Received status {}", status))),
+            Err(e) => (None, Some(e)),
+        };
+        let attempts = delivery.attempts + 1;
+        let delivery_id = delivery.id.clone();
+        let mut to_update: outbound_webhook_delivery::ActiveModel = delivery.into();
+        to_update.attempts = ActiveValue::Set(attempts);
+        to_update.response_status =
+            ActiveValue::Set(response_status.map(|s| i32::from(s.as_u16())));
+        to_update.error = ActiveValue::Set(error.clone());
+        let retry_delay = outbound_webhook_retry_delay(attempts, retry_on_failure);
+        match (error, retry_delay) {
+            (None, _) => {
+                to_update.delivered_on = ActiveValue::Set(Some(Utc::now()));
+                to_update.status = ActiveValue::Set(OutboundWebhookDeliveryStatus::Delivered);
             }
-            IntegrationProvider::Plex => {
-                let specifics = integration.clone().provider_specifics.unwrap();
-                let plex =
-                    PlexSinkIntegration::new(payload, specifics.plex_username, self.0.db.clone());
-                plex.yank_progress().await
+            (Some(e), Some(delay)) => {
+                ryot_log!(
+                    debug,
+                    "Retrying outbound webhook delivery {} in {} minutes after error: {}",
+                    delivery_id,
+                    delay,
+                    e
+                );
+                to_update.next_attempt_on = ActiveValue::Set(Utc::now() + Duration::minutes(delay));
             }
-            IntegrationProvider::GenericJson => {
-                let generic_json = GenericJsonSinkIntegration::new(payload);
-                generic_json.yank_progress().await
+            (Some(_), None) => {
+                to_update.status = ActiveValue::Set(OutboundWebhookDeliveryStatus::DeadLetter);
             }
-            _ => return Err(Error::new("Unsupported integration source".to_owned())),
+        }
+        Ok(to_update.update(&self.0.db).await?)
+    }
+
+    /// Send the queued outbound webhook deliveries of an integration that are due,
+    /// in the order in which they were created.
+    pub async fn process_outbound_webhook_deliveries(
+        &self,
+        integration_id: String,
+    ) -> GqlResult<()> {
+        let Some(integration) = Integration::find_by_id(&integration_id)
+            .one(&self.0.db)
+            .await?
+        else {
+            return Ok(());
         };
-        match maybe_progress_update {
-            Ok(pu) => {
-                self.integration_progress_update(integration, pu)
-                    .await
-                    .trace_ok();
-                Ok("Progress updated successfully".to_owned())
-            }
-            Err(e) => Err(Error::new(e.to_string())),
+        if integration.is_disabled.unwrap_or_default() {
+            return Ok(());
         }
+        let deliveries = OutboundWebhookDelivery::find()
+            .filter(outbound_webhook_delivery::Column::IntegrationId.eq(&integration_id))
+            .filter(
+                outbound_webhook_delivery::Column::Status.eq(OutboundWebhookDeliveryStatus::Queued),
+            )
+            .filter(outbound_webhook_delivery::Column::NextAttemptOn.lte(Utc::now()))
+            .order_by_asc(outbound_webhook_delivery::Column::CreatedOn)
+            .all(&self.0.db)
+            .await?;
+        for delivery in deliveries {
+            self.attempt_outbound_webhook_delivery(&integration, delivery, true)
+                .await?;
+        }
+        let to_keep = OutboundWebhookDelivery::find()
+            .select_only()
+            .column(outbound_webhook_delivery::Column::Id)
+            .filter(outbound_webhook_delivery::Column::IntegrationId.eq(&integration_id))
+            .order_by_desc(outbound_webhook_delivery::Column::CreatedOn)
+            .limit(WEBHOOK_DELIVERIES_TO_KEEP)
+            .into_tuple::<String>()
+            .all(&self.0.db)
+            .await?;
+        OutboundWebhookDelivery::delete_many()
+            .filter(outbound_webhook_delivery::Column::IntegrationId.eq(&integration_id))
+            .filter(
+                outbound_webhook_delivery::Column::Status.ne(OutboundWebhookDeliveryStatus::Queued),
+            )
+            .filter(outbound_webhook_delivery::Column::Id.is_not_in(to_keep))
+            .exec(&self.0.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn retry_outbound_webhook_deliveries(&self) -> GqlResult<()> {
+        let integration_ids = OutboundWebhookDelivery::find()
+            .select_only()
+            .column(outbound_webhook_delivery::Column::IntegrationId)
+            .distinct()
+            .filter(
+                outbound_webhook_delivery::Column::Status.eq(OutboundWebhookDeliveryStatus::Queued),
+            )
+            .filter(outbound_webhook_delivery::Column::NextAttemptOn.lte(Utc::now()))
+            .into_tuple::<String>()
+            .all(&self.0.db)
+            .await?;
+        for integration_id in integration_ids {
+            self.process_outbound_webhook_deliveries(integration_id)
+                .await
+                .trace_ok();
+        }
+        Ok(())
+    }
+
+    pub async fn outbound_webhook_deliveries(
+        &self,
+        user_id: &String,
+        integration_id: String,
+    ) -> GqlResult<Vec<outbound_webhook_delivery::Model>> {
+        let integration = Integration::find_by_id(integration_id)
+            .filter(integration::Column::UserId.eq(user_id))
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Integration does not exist".to_owned()))?;
+        let deliveries = integration
+            .find_related(OutboundWebhookDelivery)
+            .order_by_desc(outbound_webhook_delivery::Column::CreatedOn)
+            .all(&self.0.db)
+            .await?;
+        Ok(deliveries)
+    }
+
+    /// Send a test event to a webhook integration right away. It is not retried if
+    /// it fails, the outcome is returned and also shows up in the delivery log.
+    pub async fn test_outbound_webhook(
+        &self,
+        user_id: &String,
+        integration_id: String,
+        event: OutboundWebhookEvent,
+    ) -> GqlResult<outbound_webhook_delivery::Model> {
+        let integration = Integration::find_by_id(integration_id)
+            .filter(integration::Column::UserId.eq(user_id))
+            .filter(integration::Column::Provider.eq(IntegrationProvider::Webhook))
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Integration does not exist".to_owned()))?;
+        let payload = serde_json::to_string(&OutboundWebhookPayload {
+            event,
+            media: None,
+            is_test: true,
+            timestamp: Utc::now(),
+            user_id: user_id.to_owned(),
+            version: OUTBOUND_WEBHOOK_PAYLOAD_VERSION,
+            details: Some(json!({ "message": "This is a test event sent from Ryot" })),
+        })?;
+        let delivery = outbound_webhook_delivery::ActiveModel {
+            event: ActiveValue::Set(event),
+            payload: ActiveValue::Set(payload),
+            integration_id: ActiveValue::Set(integration.id.clone()),
+            status: ActiveValue::Set(OutboundWebhookDeliveryStatus::Queued),
+            ..Default::default()
+        }
+        .insert(&self.0.db)
+        .await?;
+        self.attempt_outbound_webhook_delivery(&integration, delivery, false)
+            .await
+    }
+
+    pub async fn webhook_debug_captures(
+        &self,
+        user_id: &String,
+        integration_id: String,
+    ) -> GqlResult<Vec<webhook_debug_capture::Model>> {
+        let integration = Integration::find_by_id(integration_id)
+            .filter(integration::Column::UserId.eq(user_id))
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Integration does not exist".to_owned()))?;
+        let captures = integration
+            .find_related(WebhookDebugCapture)
+            .order_by_desc(webhook_debug_capture::Column::CapturedOn)
+            .all(&self.0.db)
+            .await?;
+        Ok(captures)
+    }
+
+    /// Run a built-in sample payload through the same parsing and filtering that
+    /// a real webhook goes through, without updating any progress.
+    pub async fn simulate_webhook(
+        &self,
+        user_id: &String,
+        integration_id: String,
+        sample_payload_kind: WebhookSamplePayloadKind,
+    ) -> GqlResult<Vec<WebhookSimulationStep>> {
+        let mut steps = vec![];
+        let mut push_step = |step: &str, was_success: bool, message: String| {
+            steps.push(WebhookSimulationStep {
+                message,
+                was_success,
+                step: step.to_owned(),
+            });
+            was_success
+        };
+        let integration = Integration::find_by_id(integration_id)
+            .filter(integration::Column::UserId.eq(user_id))
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Integration does not exist".to_owned()))?;
+        push_step(
+            "Load integration",
+            true,
+            format!(
+                "Found {:?} integration {}",
+                integration.provider, integration.id
+            ),
+        );
+        let preferences = user_by_id(&integration.user_id, &self.0).await?.preferences;
+        let is_disabled =
+            integration.is_disabled.unwrap_or_default() || preferences.general.disable_integrations;
+        let message = match is_disabled {
+            true => "Integration is disabled, real webhooks will be rejected".to_owned(),
+            false => "Integration is enabled".to_owned(),
+        };
+        if !push_step("Check enabled", !is_disabled, message) {
+            return Ok(steps);
+        }
+        let plex_user = integration
+            .provider_specifics
+            .clone()
+            .and_then(|s| s.plex_username);
+        let Some(payload) =
+            samples::sample_payload(integration.provider, sample_payload_kind, plex_user)
+        else {
+            push_step(
+                "Build sample payload",
+                false,
+                format!(
+                    "No {:?} sample is available for this integration",
+                    sample_payload_kind
+                ),
+            );
+            return Ok(steps);
+        };
+        push_step("Build sample payload", true, payload.clone());
+        let (parser, parsed) = self.parse_webhook_payload(&integration, payload).await?;
+        let (was_success, outcome) = describe_parse_outcome(&parsed);
+        let Ok(mut import) = parsed else {
+            push_step("Parse payload", false, format!("{}: {}", parser, outcome));
+            return Ok(steps);
+        };
+        push_step(
+            "Parse payload",
+            was_success,
+            format!("{}: {}", parser, outcome),
+        );
+        apply_progress_thresholds(&integration, &mut import);
+        let updates = import
+            .metadata
+            .iter()
+            .map(|m| m.seen_history.len())
+            .sum::<usize>();
+        if !push_step(
+            "Apply progress thresholds",
+            updates > 0,
+            format!("{} progress update(s) remain after filtering", updates),
+        ) {
+            return Ok(steps);
+        }
+        push_step(
+            "Update progress",
+            true,
+            "Skipped since this is a simulation".to_owned(),
+        );
+        Ok(steps)
     }
 
     pub async fn handle_entity_added_to_collection_event(
@@ -155,7 +1123,23 @@ impl IntegrationService {
             .into_tuple::<String>()
             .all(&self.0.db)
             .await?;
+        let collection = Collection::find_by_id(&cte.collection_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Collection does not exist"))?;
         for user_id in users {
+            queue_outbound_webhook_event(
+                &user_id,
+                OutboundWebhookEvent::ItemAddedToCollection,
+                Some(&cte.entity_id),
+                Some(json!({
+                    "collection_id": collection.id,
+                    "collection_name": collection.name,
+                })),
+                &self.0,
+            )
+            .await
+            .trace_ok();
             let integrations = Integration::find()
                 .filter(integration::Column::UserId.eq(user_id))
                 .filter(integration::Column::Lot.eq(IntegrationLot::Push))
@@ -223,17 +1207,35 @@ impl IntegrationService {
     }
 
     pub async fn handle_on_seen_complete(&self, id: String) -> GqlResult<()> {
-        let (seen, show_extra_information, metadata_title, metadata_lot) = Seen::find_by_id(id)
-            .left_join(Metadata)
+        let seen = Seen::find_by_id(id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Seen with the given ID could not be found"))?;
+        let (metadata_title, metadata_lot) = Metadata::find_by_id(&seen.metadata_id)
             .select_only()
-            .columns([seen::Column::UserId, seen::Column::ShowExtraInformation])
             .columns([metadata::Column::Title, metadata::Column::Lot])
-            .into_tuple::<(String, Option<SeenShowExtraInformation>, String, MediaLot)>()
+            .into_tuple::<(String, MediaLot)>()
             .one(&self.0.db)
             .await?
-            .ok_or_else(|| Error::new("Seen with the given ID could not be found"))?;
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        queue_outbound_webhook_event(
+            &seen.user_id,
+            OutboundWebhookEvent::SeenCompleted,
+            Some(&seen.metadata_id),
+            Some(json!({
+                "seen_id": seen.id,
+                "finished_on": seen.finished_on,
+                "show_extra_information": seen.show_extra_information,
+                "podcast_extra_information": seen.podcast_extra_information,
+                "anime_extra_information": seen.anime_extra_information,
+                "manga_extra_information": seen.manga_extra_information,
+            })),
+            &self.0,
+        )
+        .await
+        .trace_ok();
         let integrations = Integration::find()
-            .filter(integration::Column::UserId.eq(seen))
+            .filter(integration::Column::UserId.eq(&seen.user_id))
             .filter(integration::Column::Lot.eq(IntegrationLot::Push))
             .filter(integration::Column::Provider.eq(IntegrationProvider::JellyfinPush))
             .all(&self.0.db)
@@ -248,7 +1250,7 @@ impl IntegrationService {
                         specifics.jellyfin_push_password.unwrap(),
                         &metadata_lot,
                         &metadata_title,
-                        &show_extra_information,
+                        &seen.show_extra_information,
                     );
                     integration.push_progress().await?;
                 }
@@ -301,9 +1303,22 @@ impl IntegrationService {
                 }
                 _ => continue,
             };
-            if let Ok(update) = response {
-                to_update_integrations.push(integration.id.clone());
-                progress_updates.push((integration, update));
+            match response {
+                Ok(update) => {
+                    to_update_integrations.push(integration.id.clone());
+                    progress_updates.push((integration, update));
+                }
+                Err(e) => {
+                    ryot_log!(
+                        debug,
+                        "Error yanking integration {}: {:?}",
+                        integration.id,
+                        e
+                    );
+                    self.record_integration_outcome(integration, false)
+                        .await
+                        .trace_ok();
+                }
             }
         }
         for (integration, progress_updates) in progress_updates.into_iter() {
@@ -328,4 +1343,480 @@ impl IntegrationService {
         }
         Ok(())
     }
+
+    /// Refresh the health of every integration that expects regular activity and
+    /// notify the user once each time one of them goes stale.
+    pub async fn alert_stale_integrations(&self) -> GqlResult<()> {
+        let now = Utc::now();
+        let integrations = Integration::find()
+            .filter(integration::Column::ExpectedActivityDays.is_not_null())
+            .filter(integration::Column::Lot.ne(IntegrationLot::Push))
+            .all(&self.0.db)
+            .await?;
+        for integration in integrations {
+            if integration.is_disabled.unwrap_or_default() {
+                continue;
+            }
+            let health_state = integration_health_state(&integration, now);
+            let last_activity = last_activity_on(&integration);
+            let should_alert = should_alert_stale(&integration, now);
+            if health_state == integration.health_state && !should_alert {
+                continue;
+            }
+            if should_alert {
+                let msg = format!(
+                    "Your {} integration has not received any data since {}. Please check that it is still configured correctly.",
+                    integration.provider,
+                    last_activity.format("%Y-%m-%d %H:%M UTC")
+                );
+                queue_notifications_to_user_platforms(&integration.user_id, &msg, &self.0)
+                    .await
+                    .trace_ok();
+            }
+            let mut to_update: integration::ActiveModel = integration.into();
+            to_update.health_state = ActiveValue::Set(health_state);
+            if should_alert {
+                to_update.stale_alert_sent_on = ActiveValue::Set(Some(now));
+            }
+            to_update.update(&self.0.db).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use media_models::ImportOrExportMediaItem;
+    use sea_orm::{DatabaseBackend, MockDatabase, QueryTrait, Value};
+
+    use super::*;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2024-03-{day:02}T12:00:00Z"))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn integration(last_triggered_on: Option<DateTime<Utc>>) -> integration::Model {
+        integration::Model {
+            id: "integration".to_owned(),
+            minimum_progress: None,
+            maximum_progress: None,
+            user_id: "user".to_owned(),
+            lot: IntegrationLot::Sink,
+            provider: IntegrationProvider::Plex,
+            sync_to_owned_collection: None,
+            default_collection: None,
+            is_disabled: None,
+            created_on: at(1),
+            last_triggered_on,
+            debug_mode_until: None,
+            expected_activity_days: Some(7),
+            alerts_snoozed_until: None,
+            stale_alert_sent_on: None,
+            consecutive_failures: 0,
+            health_state: IntegrationHealthState::Healthy,
+            provider_specifics: None,
+        }
+    }
+
+    fn imported_movie(collections: Vec<String>) -> ImportResult {
+        ImportResult {
+            metadata: vec![ImportOrExportMediaItem {
+                collections,
+                lot: MediaLot::Movie,
+                source: MediaSource::Tmdb,
+                identifier: "603".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn items_of_user(count: i64) -> MockDatabase {
+        let count = BTreeMap::from([("num_items".to_owned(), Value::BigInt(Some(count)))]);
+        MockDatabase::new(DatabaseBackend::Postgres).append_query_results([[count]])
+    }
+
+    #[tokio::test]
+    async fn new_media_is_added_to_the_default_collection() {
+        let mut integration = integration(None);
+        integration.default_collection = Some("From Jellyfin".to_owned());
+        let mut import = imported_movie(vec![]);
+        let db = items_of_user(0).into_connection();
+        add_new_items_to_default_collection(&integration, &mut import, &db)
+            .await
+            .unwrap();
+        assert_eq!(import.metadata[0].collections, ["From Jellyfin"]);
+        // DEV: A repeated webhook for the same media while it is being imported
+        // does not add the collection twice
+        let db = items_of_user(0).into_connection();
+        add_new_items_to_default_collection(&integration, &mut import, &db)
+            .await
+            .unwrap();
+        assert_eq!(import.metadata[0].collections, ["From Jellyfin"]);
+    }
+
+    #[tokio::test]
+    async fn media_the_user_has_is_not_added_again() {
+        let mut integration = integration(None);
+        integration.default_collection = Some("From Jellyfin".to_owned());
+        let mut import = imported_movie(vec![]);
+        let db = items_of_user(1).into_connection();
+        add_new_items_to_default_collection(&integration, &mut import, &db)
+            .await
+            .unwrap();
+        assert!(import.metadata[0].collections.is_empty());
+    }
+
+    #[tokio::test]
+    async fn nothing_is_added_once_the_setting_is_removed() {
+        let mut import = imported_movie(vec!["Watchlist".to_owned()]);
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        add_new_items_to_default_collection(&integration(None), &mut import, &db)
+            .await
+            .unwrap();
+        assert_eq!(import.metadata[0].collections, ["Watchlist"]);
+        assert!(db.into_transaction_log().is_empty());
+    }
+
+    #[test]
+    fn heartbeats_keep_a_session_per_game_until_it_goes_idle() {
+        let idle_gap = Duration::minutes(15);
+        let minute = |m| at(1) + Duration::minutes(m);
+        // DEV: Two games overlap for a while, the first one is picked up again after
+        // a break that is longer than the idle gap
+        let heartbeats = [
+            ("game_a", 0),
+            ("game_a", 5),
+            ("game_b", 7),
+            ("game_a", 10),
+            ("game_b", 12),
+            ("game_a", 40),
+            ("game_a", 38),
+        ];
+        let mut sessions: Vec<video_game_session::Model> = vec![];
+        for (game, m) in heartbeats {
+            let at = minute(m);
+            // DEV: Mirrors the filter and order of `running_game_session`
+            let running = sessions
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.metadata_id == game)
+                .filter(|(_, s)| s.last_heartbeat_on >= at - idle_gap)
+                .filter(|(_, s)| s.started_on <= at + idle_gap)
+                .max_by_key(|(_, s)| s.last_heartbeat_on)
+                .map(|(idx, _)| idx);
+            match running {
+                Some(idx) => sessions[idx] = extended_game_session(sessions[idx].clone(), at),
+                None => sessions.push(video_game_session::Model {
+                    id: format!("vgs_{}", sessions.len()),
+                    user_id: "user".to_owned(),
+                    metadata_id: game.to_owned(),
+                    integration_id: None,
+                    started_on: at,
+                    last_heartbeat_on: at,
+                    duration: 0,
+                }),
+            }
+        }
+        let summary = sessions
+            .iter()
+            .map(|s| (s.metadata_id.as_str(), s.started_on, s.duration))
+            .collect_vec();
+        assert_eq!(
+            summary,
+            [
+                ("game_a", minute(0), 600),
+                ("game_b", minute(7), 300),
+                ("game_a", minute(38), 120),
+            ]
+        );
+        let sql = running_game_session(
+            &"user".to_owned(),
+            &"game_a".to_owned(),
+            minute(40),
+            idle_gap,
+        )
+        .build(DatabaseBackend::Postgres)
+        .to_string();
+        assert!(sql.contains(r#""video_game_session"."metadata_id" = 'game_a'"#));
+        assert!(sql.contains(r#""video_game_session"."last_heartbeat_on" >= '2024-03-01 12:25:00"#));
+        assert!(sql.contains(r#""video_game_session"."started_on" <= '2024-03-01 12:55:00"#));
+    }
+
+    fn delivery(attempts: i32) -> integration_webhook_delivery::Model {
+        integration_webhook_delivery::Model {
+            id: "delivery".to_owned(),
+            integration_id: "integration".to_owned(),
+            received_on: at(1),
+            next_attempt_on: at(1),
+            processed_on: None,
+            headers: vec![],
+            payload: "{}".to_owned(),
+            status: IntegrationWebhookDeliveryStatus::Queued,
+            attempts,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn outbound_webhooks_back_off_until_they_are_dead_lettered() {
+        let delays = (1..=5)
+            .map(|attempts| outbound_webhook_retry_delay(attempts, true))
+            .collect_vec();
+        assert_eq!(delays, [Some(1), Some(5), Some(30), Some(120), None]);
+        // DEV: Test events are sent once and never retried
+        assert_eq!(outbound_webhook_retry_delay(1, false), None);
+    }
+
+    #[test]
+    fn failed_imports_are_retried_until_they_run_out_of_attempts() {
+        let error = || WebhookOutcome::ImportFailed("Provider is down".to_owned());
+        let (first, is_finished) = attempted_webhook_delivery(delivery(0), error(), at(2));
+        assert!(!is_finished);
+        assert_eq!(first.attempts, ActiveValue::Set(1));
+        assert_eq!(
+            first.status,
+            ActiveValue::Unchanged(IntegrationWebhookDeliveryStatus::Queued)
+        );
+        assert_eq!(
+            first.next_attempt_on,
+            ActiveValue::Set(at(2) + Duration::minutes(1))
+        );
+        assert_eq!(first.processed_on, ActiveValue::Unchanged(None));
+        // DEV: The delay grows with every attempt
+        let (second, is_finished) = attempted_webhook_delivery(delivery(1), error(), at(2));
+        assert!(!is_finished);
+        assert_eq!(
+            second.next_attempt_on,
+            ActiveValue::Set(at(2) + Duration::minutes(2))
+        );
+        let (last, is_finished) =
+            attempted_webhook_delivery(delivery(WEBHOOK_DELIVERY_ATTEMPTS - 1), error(), at(2));
+        assert!(is_finished);
+        assert_eq!(
+            last.status,
+            ActiveValue::Set(IntegrationWebhookDeliveryStatus::Failed)
+        );
+        assert_eq!(last.processed_on, ActiveValue::Set(Some(at(2))));
+    }
+
+    #[test]
+    fn processed_deliveries_are_not_retried() {
+        let mut failed = delivery(1);
+        failed.error = Some("Provider is down".to_owned());
+        let (updated, is_finished) =
+            attempted_webhook_delivery(failed, WebhookOutcome::Updated, at(2));
+        assert!(is_finished);
+        assert_eq!(
+            updated.status,
+            ActiveValue::Set(IntegrationWebhookDeliveryStatus::Processed)
+        );
+        assert_eq!(updated.error, ActiveValue::Set(None));
+        // DEV: A payload that can not be parsed will not parse on a retry either
+        let (unparsed, is_finished) = attempted_webhook_delivery(
+            delivery(0),
+            WebhookOutcome::ParseFailed("Invalid payload".to_owned()),
+            at(2),
+        );
+        assert!(is_finished);
+        assert_eq!(
+            unparsed.status,
+            ActiveValue::Set(IntegrationWebhookDeliveryStatus::Failed)
+        );
+    }
+
+    #[test]
+    fn due_deliveries_are_processed_in_the_order_received() {
+        let sql = due_webhook_deliveries("integration".to_owned(), at(2))
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#""integration_id" = 'integration'"#));
+        assert!(sql.contains(r#""status" = 'queued'"#));
+        assert!(sql.contains(r#""next_attempt_on" <= '2024-03-02 12:00:00"#));
+        assert!(sql.ends_with(r#"ORDER BY "integration_webhook_delivery"."received_on" ASC"#));
+    }
+
+    #[test]
+    fn reimported_items_replace_their_mapping() {
+        let item = ImportServerItemId {
+            lot: MediaLot::Show,
+            source: MediaSource::Tmdb,
+            identifier: "1396".to_owned(),
+            server_item_id: "new-id".to_owned(),
+            server_id: Some("srv".to_owned()),
+        };
+        let sql = upsert_item_mapping("int_1", "met_1".to_owned(), item)
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains("'new-id'"));
+        assert!(sql.contains(r#"ON CONFLICT ("integration_id", "metadata_id") DO UPDATE SET"#));
+        assert!(sql.contains(r#""server_item_id" = "excluded"."server_item_id""#));
+        assert!(sql.contains(r#""server_id" = "excluded"."server_id""#));
+    }
+
+    #[test]
+    fn debug_mode_expires_on_its_own() {
+        let mut integration = integration(None);
+        assert!(!is_in_debug_mode(&integration, at(2)));
+        integration.debug_mode_until = Some(at(2) + Duration::hours(1));
+        assert!(is_in_debug_mode(&integration, at(2)));
+        assert!(is_in_debug_mode(
+            &integration,
+            at(2) + Duration::minutes(59)
+        ));
+        assert!(!is_in_debug_mode(&integration, at(2) + Duration::hours(1)));
+        assert!(!is_in_debug_mode(&integration, at(3)));
+    }
+
+    #[test]
+    fn captured_requests_do_not_store_credentials() {
+        let headers = vec![
+            ("Content-Type".to_owned(), "application/json".to_owned()),
+            ("Authorization".to_owned(), "Bearer abc".to_owned()),
+            ("X-Emby-Token".to_owned(), "abc".to_owned()),
+            ("X-Api-Key".to_owned(), "abc".to_owned()),
+            ("Cookie".to_owned(), "session=abc".to_owned()),
+            ("User-Agent".to_owned(), "PlexMediaServer".to_owned()),
+        ];
+        assert_eq!(
+            redact_headers(headers),
+            vec![
+                "Content-Type: application/json",
+                "User-Agent: PlexMediaServer"
+            ]
+        );
+        assert_eq!(
+            describe_parse_outcome(&Ok(ImportResult::default())),
+            (true, "Parsed 0 item(s)".to_owned())
+        );
+        assert_eq!(
+            describe_parse_outcome(&Err(anyhow::anyhow!("Ignoring event type"))),
+            (false, "Ignoring event type".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn plex_sample_is_simulated_through_the_sink() {
+        // DEV: Neither of the sample's ids are stored yet, so it falls back to its TMDb id
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<metadata::Model>::new(), vec![]])
+            .into_connection();
+        let mut integration = integration(None);
+        integration.minimum_progress = Some(dec!(2));
+        integration.maximum_progress = Some(dec!(95));
+        let payload = samples::sample_payload(
+            IntegrationProvider::Plex,
+            WebhookSamplePayloadKind::Movie,
+            Some("alice".to_owned()),
+        )
+        .unwrap();
+        let mut import = PlexSinkIntegration::new(payload, Some("alice".to_owned()), db)
+            .yank_progress()
+            .await
+            .unwrap();
+        apply_progress_thresholds(&integration, &mut import);
+        let media = &import.metadata[0];
+        assert_eq!(
+            (media.lot, media.source, media.identifier.as_str()),
+            (MediaLot::Movie, MediaSource::Tmdb, "550")
+        );
+        assert_eq!(media.seen_history.len(), 1);
+        assert_eq!(media.seen_history[0].progress, Some(dec!(100)));
+        assert_eq!(
+            media.seen_history[0].provider_watched_on.as_deref(),
+            Some("Plex")
+        );
+    }
+
+    #[tokio::test]
+    async fn plex_sample_is_ignored_for_another_user() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let payload = samples::sample_payload(
+            IntegrationProvider::Plex,
+            WebhookSamplePayloadKind::Movie,
+            Some("alice".to_owned()),
+        )
+        .unwrap();
+        let error = PlexSinkIntegration::new(payload, Some("bob".to_owned()), db)
+            .yank_progress()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Ignoring non matching user"));
+    }
+
+    #[test]
+    fn integration_goes_stale_after_the_expected_activity_window() {
+        let integration = integration(Some(at(2)));
+        assert_eq!(
+            integration_health_state(&integration, at(9)),
+            IntegrationHealthState::Healthy
+        );
+        assert_eq!(
+            integration_health_state(&integration, at(10)),
+            IntegrationHealthState::Stale
+        );
+    }
+
+    #[test]
+    fn integration_without_window_or_activity() {
+        let never_triggered = integration(None);
+        assert_eq!(
+            integration_health_state(&never_triggered, at(8)),
+            IntegrationHealthState::Healthy
+        );
+        assert_eq!(
+            integration_health_state(&never_triggered, at(9)),
+            IntegrationHealthState::Stale
+        );
+        let no_window = integration::Model {
+            expected_activity_days: None,
+            ..integration(Some(at(2)))
+        };
+        assert_eq!(
+            integration_health_state(&no_window, at(30)),
+            IntegrationHealthState::Healthy
+        );
+    }
+
+    #[test]
+    fn integration_is_erroring_after_consecutive_failures() {
+        let mut integration = integration(Some(at(2)));
+        integration.consecutive_failures = FAILURES_BEFORE_ERRORING - 1;
+        assert_eq!(
+            integration_health_state(&integration, at(3)),
+            IntegrationHealthState::Healthy
+        );
+        integration.consecutive_failures = FAILURES_BEFORE_ERRORING;
+        assert_eq!(
+            integration_health_state(&integration, at(3)),
+            IntegrationHealthState::Erroring
+        );
+    }
+
+    #[test]
+    fn stale_alert_is_not_sent_while_snoozed() {
+        let integration = integration::Model {
+            alerts_snoozed_until: Some(at(15)),
+            ..integration(Some(at(2)))
+        };
+        assert!(!should_alert_stale(&integration, at(10)));
+        assert!(!should_alert_stale(&integration, at(14)));
+        assert!(should_alert_stale(&integration, at(16)));
+    }
+
+    #[test]
+    fn stale_alert_is_sent_once_per_stale_period() {
+        let mut integration = integration(Some(at(2)));
+        assert!(should_alert_stale(&integration, at(10)));
+        integration.stale_alert_sent_on = Some(at(10));
+        assert!(!should_alert_stale(&integration, at(20)));
+        // DEV: New data re-arms the alert for the next stale period
+        integration.last_triggered_on = Some(at(12));
+        assert!(!should_alert_stale(&integration, at(15)));
+        assert!(should_alert_stale(&integration, at(20)));
+    }
 }
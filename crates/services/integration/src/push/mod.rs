@@ -1,3 +1,4 @@
 pub mod jellyfin;
 pub mod radarr;
 pub mod sonarr;
+pub mod webhook;
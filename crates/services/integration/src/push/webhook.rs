@@ -0,0 +1,67 @@
+use application_utils::get_base_http_client;
+use common_utils::APPLICATION_JSON_HEADER;
+use enums::OutboundWebhookEvent;
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use sha2::Sha256;
+
+/// The signature sent in the `X-Ryot-Signature` header. Receivers can compute the
+/// HMAC-SHA256 of the raw body with the shared secret and compare it to this.
+pub(crate) fn sign_payload(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub(crate) struct WebhookPushIntegration {
+    url: String,
+    secret: Option<String>,
+}
+
+impl WebhookPushIntegration {
+    pub const fn new(url: String, secret: Option<String>) -> Self {
+        Self { url, secret }
+    }
+
+    pub async fn push_event(
+        &self,
+        delivery_id: &str,
+        event: OutboundWebhookEvent,
+        body: String,
+    ) -> anyhow::Result<StatusCode> {
+        let client = get_base_http_client(None);
+        let mut request = client
+            .post(&self.url)
+            .header(CONTENT_TYPE, APPLICATION_JSON_HEADER.clone())
+            .header("X-Ryot-Event", event.to_string())
+            .header("X-Ryot-Delivery", delivery_id);
+        if let Some(secret) = &self.secret {
+            request = request.header("X-Ryot-Signature", sign_payload(secret, &body));
+        }
+        let response = request.body(body).send().await?;
+        Ok(response.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_payload_matches_reference_hmac() {
+        // DEV: Test case 2 of RFC 4231
+        assert_eq!(
+            sign_payload("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn sign_payload_depends_on_secret_and_body() {
+        let signature = sign_payload("secret", r#"{"event":"seen"}"#);
+        assert_ne!(signature, sign_payload("other", r#"{"event":"seen"}"#));
+        assert_ne!(signature, sign_payload("secret", r#"{"event":"seen" }"#));
+        assert_eq!(signature, sign_payload("secret", r#"{"event":"seen"}"#));
+    }
+}
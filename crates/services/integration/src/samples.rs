@@ -0,0 +1,113 @@
+use enums::IntegrationProvider;
+use media_models::WebhookSamplePayloadKind;
+use serde_json::json;
+
+// DEV: These mirror what the respective servers actually send, trimmed down to
+// the fields that the sink integrations read. The movie is Fight Club and the
+// episode is the pilot of Breaking Bad.
+pub(crate) fn sample_payload(
+    provider: IntegrationProvider,
+    kind: WebhookSamplePayloadKind,
+    plex_user: Option<String>,
+) -> Option<String> {
+    let payload = match (provider, kind) {
+        (IntegrationProvider::Plex, kind) => {
+            let metadata = match kind {
+                WebhookSamplePayloadKind::Movie => json!({
+                    "type": "movie",
+                    "duration": 8340000,
+                    "Guid": [{ "id": "imdb://tt0137523" }, { "id": "tmdb://550" }]
+                }),
+                WebhookSamplePayloadKind::ShowEpisode => json!({
+                    "type": "episode",
+                    "duration": 3480000,
+                    "grandparentTitle": "Breaking Bad",
+                    "parentIndex": 1,
+                    "index": 1,
+                    "Guid": [{ "id": "imdb://tt0959621" }, { "id": "tmdb://62085" }]
+                }),
+            };
+            json!({
+                "event": "media.scrobble",
+                "user": true,
+                "owner": true,
+                "Account": { "title": plex_user.unwrap_or_else(|| "ryot".to_owned()) },
+                "Metadata": metadata
+            })
+        }
+        (IntegrationProvider::JellyfinSink | IntegrationProvider::Emby, kind) => {
+            let (item, series) = match kind {
+                WebhookSamplePayloadKind::Movie => (
+                    json!({
+                        "Type": "Movie",
+                        "Name": "Fight Club",
+                        "RunTimeTicks": 83400000000_i64,
+                        "ProviderIds": { "Tmdb": "550" }
+                    }),
+                    None,
+                ),
+                WebhookSamplePayloadKind::ShowEpisode => (
+                    json!({
+                        "Type": "Episode",
+                        "Name": "Pilot",
+                        "SeriesName": "Breaking Bad",
+                        "ParentIndexNumber": 1,
+                        "IndexNumber": 1,
+                        "RunTimeTicks": 34800000000_i64,
+                        "ProviderIds": {}
+                    }),
+                    Some(json!({
+                        "Type": "Series",
+                        "Name": "Breaking Bad",
+                        "ProviderIds": { "Tmdb": "1396" }
+                    })),
+                ),
+            };
+            let position = json!({ "PositionTicks": 34800000000_i64 });
+            json!({
+                "Event": "playback.stop",
+                "Item": item,
+                "Series": series,
+                "Session": { "PlayState": position },
+                "PlaybackInfo": position
+            })
+        }
+        (IntegrationProvider::Kodi, WebhookSamplePayloadKind::Movie) => json!({
+            "identifier": "550",
+            "lot": "movie",
+            "progress": 100
+        }),
+        (IntegrationProvider::Kodi, WebhookSamplePayloadKind::ShowEpisode) => json!({
+            "identifier": "1396",
+            "lot": "show",
+            "progress": 100,
+            "show_season_number": 1,
+            "show_episode_number": 1
+        }),
+        (IntegrationProvider::GenericJson, kind) => {
+            let item = match kind {
+                WebhookSamplePayloadKind::Movie => json!({
+                    "lot": "movie",
+                    "source": "tmdb",
+                    "identifier": "550",
+                    "source_id": "Fight Club",
+                    "seen_history": [{}],
+                    "reviews": [],
+                    "collections": []
+                }),
+                WebhookSamplePayloadKind::ShowEpisode => json!({
+                    "lot": "show",
+                    "source": "tmdb",
+                    "identifier": "1396",
+                    "source_id": "Breaking Bad",
+                    "seen_history": [{ "show_season_number": 1, "show_episode_number": 1 }],
+                    "reviews": [],
+                    "collections": []
+                }),
+            };
+            json!({ "media": [item] })
+        }
+        _ => return None,
+    };
+    Some(payload.to_string())
+}
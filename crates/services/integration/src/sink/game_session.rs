@@ -0,0 +1,53 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use enums::ExternalIdType;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+
+mod models {
+    use super::*;
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct GameSessionHeartbeatPayload {
+        /// The store that the id belongs to, `steam` or `igdb`.
+        pub store: String,
+        pub id: String,
+        pub timestamp: Option<DateTimeUtc>,
+    }
+}
+
+/// A sign from a game launcher that a game is being played at this moment.
+#[derive(Debug, Clone)]
+pub(crate) struct GameHeartbeat {
+    pub id_type: ExternalIdType,
+    pub value: String,
+    pub received_on: DateTimeUtc,
+}
+
+pub(crate) struct GameSessionSinkIntegration {
+    payload: String,
+}
+
+impl GameSessionSinkIntegration {
+    pub const fn new(payload: String) -> Self {
+        Self { payload }
+    }
+
+    pub fn heartbeat(&self) -> Result<GameHeartbeat> {
+        let payload: models::GameSessionHeartbeatPayload = serde_json::from_str(&self.payload)?;
+        let id_type = match payload.store.to_lowercase().as_str() {
+            "steam" => ExternalIdType::Steam,
+            "igdb" => ExternalIdType::Igdb,
+            store => bail!("Unsupported store {store:?}, only steam and igdb are supported"),
+        };
+        let value = payload.id.trim().to_owned();
+        if value.is_empty() {
+            bail!("The game id is empty");
+        }
+        Ok(GameHeartbeat {
+            id_type,
+            value,
+            received_on: payload.timestamp.unwrap_or_else(Utc::now),
+        })
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{bail, Result};
-use dependent_models::ImportResult;
+use dependent_models::{ImportResult, ImportServerItemId};
 use enums::{MediaLot, MediaSource};
 use media_models::{ImportOrExportMediaItem, ImportOrExportMediaItemSeen};
 use rust_decimal::Decimal;
@@ -27,6 +27,7 @@ mod models {
     #[derive(Serialize, Deserialize, Debug, Clone)]
     #[serde(rename_all = "PascalCase")]
     pub struct JellyfinWebhookItemPayload {
+        pub id: Option<String>,
         pub run_time_ticks: Option<Decimal>,
         #[serde(rename = "Type")]
         pub item_type: String,
@@ -88,7 +89,22 @@ impl JellyfinSinkIntegration {
             _ => bail!("Only movies and shows supported"),
         };
 
+        // DEV: Episodes link to their show since that is where the next one is played from
+        let server_item_id = match lot {
+            MediaLot::Show => payload.series.as_ref().and_then(|s| s.id.clone()),
+            _ => payload.item.id.clone(),
+        };
+
         Ok(ImportResult {
+            server_item_ids: Vec::from_iter(server_item_id.map(|server_item_id| {
+                ImportServerItemId {
+                    lot,
+                    server_item_id,
+                    server_id: None,
+                    source: MediaSource::Tmdb,
+                    identifier: identifier.clone(),
+                }
+            })),
             metadata: vec![ImportOrExportMediaItem {
                 lot,
                 identifier,
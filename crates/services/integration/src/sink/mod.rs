@@ -1,4 +1,5 @@
 pub mod emby;
+pub mod game_session;
 pub mod generic_json;
 pub mod jellyfin;
 pub mod kodi;
@@ -1,6 +1,8 @@
-use anyhow::{bail, Context, Result};
-use dependent_models::ImportResult;
-use enums::{MediaLot, MediaSource};
+use anyhow::{anyhow, bail, Context, Result};
+use database_models::metadata;
+use database_utils::metadata_by_external_id;
+use dependent_models::{ImportResult, ImportServerItemId};
+use enums::{ExternalIdType, MediaLot, MediaSource};
 use media_models::{ImportOrExportMediaItem, ImportOrExportMediaItemSeen};
 use regex::Regex;
 use rust_decimal::Decimal;
@@ -32,6 +34,10 @@ mod models {
         pub episode_number: Option<i32>,
         #[serde(rename = "Guid")]
         pub guids: Vec<PlexWebhookMetadataGuid>,
+        #[serde(rename = "ratingKey")]
+        pub rating_key: Option<String>,
+        #[serde(rename = "grandparentRatingKey")]
+        pub show_rating_key: Option<String>,
     }
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct PlexWebhookAccount {
@@ -39,6 +45,10 @@ mod models {
         pub plex_user: String,
     }
     #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct PlexWebhookServer {
+        pub uuid: String,
+    }
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct PlexWebhookPayload {
         #[serde(rename = "event")]
         pub event_type: String,
@@ -48,6 +58,8 @@ mod models {
         pub metadata: PlexWebhookMetadataPayload,
         #[serde(rename = "Account")]
         pub account: PlexWebhookAccount,
+        #[serde(rename = "Server")]
+        pub server: Option<PlexWebhookServer>,
     }
 }
 
@@ -86,6 +98,32 @@ impl PlexSinkIntegration {
             .ok_or_else(|| anyhow::anyhow!("No TMDb ID associated with this media"))
     }
 
+    /// Movies already stored under one of the ids Plex sends are matched directly,
+    /// this also covers agents that do not send a TMDb id.
+    async fn find_movie_by_external_id(
+        &self,
+        guids: &[models::PlexWebhookMetadataGuid],
+    ) -> Result<Option<metadata::Model>> {
+        for guid in guids {
+            let Some((scheme, value)) = guid.id.split_once("://") else {
+                continue;
+            };
+            let id_type = match scheme {
+                "tmdb" => ExternalIdType::TmdbMovie,
+                "imdb" => ExternalIdType::Imdb,
+                "tvdb" => ExternalIdType::Tvdb,
+                _ => continue,
+            };
+            let found = metadata_by_external_id(id_type, value, &self.db)
+                .await
+                .map_err(|e| anyhow!(e.message))?;
+            if let Some(metadata) = found.filter(|m| m.lot == MediaLot::Movie) {
+                return Ok(Some(metadata));
+            }
+        }
+        Ok(None)
+    }
+
     async fn get_media_info<'a>(
         &self,
         metadata: &'a models::PlexWebhookMetadataPayload,
@@ -128,15 +166,42 @@ impl PlexSinkIntegration {
             _ => bail!("Ignoring event type {:#?}", payload.event_type),
         };
 
-        let identifier = self.get_tmdb_identifier(&payload.metadata.guids)?;
-        let (identifier, lot) = self.get_media_info(&payload.metadata, identifier).await?;
+        let existing = match payload.metadata.item_type.as_str() {
+            "movie" => {
+                self.find_movie_by_external_id(&payload.metadata.guids)
+                    .await?
+            }
+            _ => None,
+        };
+        let (identifier, lot, source) = match existing {
+            Some(metadata) => (metadata.identifier, metadata.lot, metadata.source),
+            None => {
+                let identifier = self.get_tmdb_identifier(&payload.metadata.guids)?;
+                let (identifier, lot) = self.get_media_info(&payload.metadata, identifier).await?;
+                (identifier, lot, MediaSource::Tmdb)
+            }
+        };
         let progress = self.calculate_progress(&payload)?;
+        // DEV: Episodes link to their show since that is where the next one is played from
+        let server_item_id = match lot {
+            MediaLot::Show => payload.metadata.show_rating_key.clone(),
+            _ => payload.metadata.rating_key.clone(),
+        };
 
         Ok(ImportResult {
+            server_item_ids: Vec::from_iter(server_item_id.map(|server_item_id| {
+                ImportServerItemId {
+                    lot,
+                    server_item_id,
+                    source,
+                    identifier: identifier.clone(),
+                    server_id: payload.server.as_ref().map(|s| s.uuid.clone()),
+                }
+            })),
             metadata: vec![ImportOrExportMediaItem {
                 lot,
                 identifier,
-                source: MediaSource::Tmdb,
+                source,
                 seen_history: vec![ImportOrExportMediaItemSeen {
                     progress: Some(progress),
                     provider_watched_on: Some("Plex".to_string()),
@@ -150,3 +215,51 @@ impl PlexSinkIntegration {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use database_models::metadata_external_id;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn movies_are_matched_by_their_imdb_id() {
+        // DEV: The item was committed from TMDb, the agent only sends its IMDb id
+        let payload = r#"{
+            "event": "media.scrobble",
+            "user": true,
+            "owner": true,
+            "Metadata": {
+                "type": "movie",
+                "duration": 8160000,
+                "ratingKey": "42",
+                "Guid": [{ "id": "imdb://tt0133093" }]
+            },
+            "Account": { "title": "neo" }
+        }"#;
+        let external_id = metadata_external_id::Model {
+            id_type: ExternalIdType::Imdb,
+            value: "tt0133093".to_owned(),
+            metadata_id: "met_1".to_owned(),
+        };
+        let movie = metadata::Model {
+            id: "met_1".to_owned(),
+            lot: MediaLot::Movie,
+            source: MediaSource::Tmdb,
+            identifier: "603".to_owned(),
+            ..Default::default()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[(external_id, movie)]])
+            .into_connection();
+        let sink = PlexSinkIntegration::new(payload.to_owned(), None, db);
+        let result = sink.yank_progress().await.unwrap();
+        let item = &result.metadata[0];
+        assert_eq!(item.identifier, "603");
+        assert_eq!(item.source, MediaSource::Tmdb);
+        assert_eq!(item.lot, MediaLot::Movie);
+        assert_eq!(item.seen_history[0].progress, Some(dec!(100)));
+        assert_eq!(result.server_item_ids[0].server_item_id, "42");
+    }
+}
@@ -1,122 +1,173 @@
 use std::{
     cmp::Reverse,
     collections::{HashMap, HashSet},
+    fs,
+    future::Future,
+    path::PathBuf,
     sync::Arc,
 };
 
 use application_utils::{
-    get_current_date, get_podcast_episode_by_number, get_show_episode_by_numbers,
-    graphql_to_db_order,
+    get_base_http_client, get_current_date, get_podcast_episode_by_number,
+    get_show_episode_by_numbers, graphql_to_db_order,
+    text::{description_preview, is_cjk, label_key, normalize_description},
 };
 use async_graphql::{Error, Result};
-use background::{ApplicationJob, CoreApplicationJob};
-use chrono::{Days, Duration, NaiveDate, Utc};
+use background::{
+    job_outcomes_since, next_scheduled_runs, ApplicationJob, CoreApplicationJob, ScheduledJobKind,
+};
+use chrono::{Datelike, Days, Duration, NaiveDate, Utc};
 use common_models::{
     ApplicationCacheKey, BackendError, BackgroundJob, ChangeCollectionToEntityInput,
-    DefaultCollection, IdAndNamedObject, MediaStateChanged, SearchDetails, SearchInput, StoredUrl,
-    StringIdObject,
+    CreateOrUpdateAnnouncementInput, DefaultCollection, IdAndNamedObject, InstancePopularWindow,
+    MediaStateChanged, ProviderStatus, ScheduledJobRuns, SearchDetails, SearchInput, StoredUrl,
+    StringIdObject, UserDashboardSection,
 };
 use common_utils::{
-    get_first_and_last_day_of_month, ryot_log, IsFeatureEnabled, PAGE_SIZE,
-    SHOW_SPECIAL_SEASON_NAMES,
+    get_first_and_last_day_of_month, normalize_isbn, ryot_log, upgrade_image_url, IsFeatureEnabled,
+    MAX_PAGE_SIZE, PAGE_SIZE, SHOW_SPECIAL_SEASON_NAMES, TEMP_DIR,
 };
 use database_models::{
-    access_link, application_cache, calendar_event, collection, collection_to_entity,
+    access_link, announcement, application_cache, calendar_event, collection, collection_to_entity,
     functions::{associate_user_with_entity, get_user_to_entity_association},
-    genre, import_report, metadata, metadata_group, metadata_to_genre, metadata_to_metadata,
-    metadata_to_metadata_group, metadata_to_person, monitored_entity, notification_platform,
-    person,
+    genre, import_report, metadata, metadata_external_id, metadata_group, metadata_to_genre,
+    metadata_to_metadata, metadata_to_metadata_group, metadata_to_person, metadata_to_tag,
+    monitored_entity, notification_delivery, notification_platform, person,
     prelude::{
-        AccessLink, ApplicationCache, CalendarEvent, Collection, CollectionToEntity, Genre,
-        ImportReport, Metadata, MetadataGroup, MetadataToGenre, MetadataToMetadata,
-        MetadataToMetadataGroup, MetadataToPerson, MonitoredEntity, NotificationPlatform, Person,
-        QueuedNotification, Review, Seen, User, UserToEntity,
+        AccessLink, Announcement, ApplicationCache, CalendarEvent, Collection, CollectionToEntity,
+        Genre, ImportReport, Metadata, MetadataExternalId, MetadataGroup, MetadataToGenre,
+        MetadataToMetadata, MetadataToMetadataGroup, MetadataToPerson, MetadataToTag,
+        MonitoredEntity, NotificationDelivery, NotificationPlatform, Person, QueuedNotification,
+        Review, SafetySnapshot, Seen, Tag, User, UserToEntity, VideoGameSession,
     },
-    queued_notification, review, seen, user, user_to_entity,
+    queued_notification, review, safety_snapshot, seen, tag, user, user_to_entity,
+    video_game_session,
 };
 use database_utils::{
-    add_entity_to_collection, apply_collection_filter, calculate_user_activities_and_summary,
+    add_entity_to_collection, admin_account_guard, apply_collection_filter,
+    apply_publish_year_filter, calculate_user_activities_and_summary, create_safety_snapshot,
     entity_in_collections, entity_in_collections_with_collection_to_entity_ids, ilike_sql,
-    item_reviews, remove_entity_from_collection, revoke_access_link, user_by_id,
+    item_reviews, latest_user_summary, metadata_by_external_id, metadata_content_warning_condition,
+    metadata_release_status_condition, refresh_cached_user_yearly_summary,
+    remove_entity_from_collection, restore_safety_snapshot, review_items, revoke_access_link,
+    user_by_id, user_playback_links, user_recommendations,
 };
 use dependent_models::{
-    CoreDetails, GenreDetails, MetadataBaseData, MetadataGroupDetails, PersonDetails,
-    SearchResults, UserMetadataDetails, UserMetadataGroupDetails, UserPersonDetails,
+    AdminDashboard, AdminDashboardLotCount, AdminDashboardStatistics,
+    AdminDashboardUserContribution, CoreDetails, GenreDetails, MetadataBaseData,
+    MetadataFinishedStatus, MetadataGroupDetails, PersonDetails, PersonWorks, SearchResults,
+    SeenChangesResponse, UserDashboard, UserDashboardInProgressItem, UserMediaInProgressThread,
+    UserMetadataDetails, UserMetadataGroupDetails, UserMetadataGroupPart, UserPersonDetails,
+    UserPersonWork,
 };
 use dependent_utils::{
-    commit_metadata, commit_metadata_group_internal, commit_metadata_internal, commit_person,
-    create_partial_metadata, deploy_after_handle_media_seen_tasks, deploy_background_job,
-    deploy_update_metadata_job, first_metadata_image_as_url, get_metadata_provider,
-    get_openlibrary_service, get_tmdb_non_media_service, get_users_and_cte_monitoring_entity,
-    get_users_monitoring_entity, handle_after_media_seen_tasks, is_metadata_finished_by_user,
-    metadata_images_as_urls, post_review, progress_update,
+    associate_tag_with_metadata, commit_metadata, commit_metadata_group_internal,
+    commit_metadata_internal, commit_person, create_partial_metadata,
+    deploy_after_handle_media_seen_tasks, deploy_background_job,
+    deploy_tracked_update_metadata_job, first_metadata_image_as_url, get_isbn_service,
+    get_metadata_provider, get_openlibrary_service, get_tmdb_non_media_service,
+    get_users_and_cte_monitoring_entity, get_users_monitoring_entity,
+    handle_after_media_seen_tasks, is_metadata_finished_by_user, metadata_images_as_urls,
+    metadata_refresh_progress, metadata_sort_title, post_review, progress_update,
     queue_media_state_changed_notification_for_user, queue_notifications_to_user_platforms,
-    refresh_collection_to_entity_association, update_metadata_and_notify_users,
+    refresh_collection_to_entity_association, requeue_postponed_metadata_updates,
+    seen_changes_cursor, seen_changes_query, seen_history, seen_history_query,
+    swap_metadata_source, update_custom_metadata_internal, update_metadata_and_notify_users,
 };
 use enums::{
-    EntityLot, MediaLot, MediaSource, MetadataToMetadataRelation, SeenState, UserToMediaReason,
+    EntityLot, ExternalIdType, MediaLot, MediaSource, MetadataToMetadataRelation,
+    NotificationDeliveryStatus, SeenState, UserLot, UserToMediaReason, Visibility,
 };
 use env_utils::APP_VERSION;
-use futures::TryStreamExt;
+use futures::{future::join_all, stream, StreamExt, TryStreamExt};
 use itertools::Itertools;
 use markdown::{to_html_with_options as markdown_to_html_opts, CompileOptions, Options};
 use media_models::{
-    CommitMediaInput, CommitPersonInput, CreateCustomMetadataInput, CreateOrUpdateReviewInput,
-    CreateReviewCommentInput, GenreDetailsInput, GenreListItem, GraphqlCalendarEvent,
-    GraphqlMediaAssets, GraphqlMetadataDetails, GraphqlMetadataGroup, GraphqlVideoAsset,
-    GroupedCalendarEvent, ImportOrExportItemReviewComment, MediaAssociatedPersonStateChanges,
-    MediaGeneralFilter, MediaSortBy, MetadataCreator, MetadataCreatorGroupedByRole,
-    MetadataDetails, MetadataFreeCreator, MetadataGroupSearchInput, MetadataGroupSearchItem,
-    MetadataGroupsListInput, MetadataImage, MetadataImageForMediaDetails, MetadataListInput,
-    MetadataPartialDetails, MetadataSearchInput, MetadataSearchItemResponse, MetadataVideo,
-    MetadataVideoSource, PartialMetadata, PartialMetadataWithoutId, PeopleListInput,
+    AddMetadataImageInput, BulkCommitMetadataResult, CommitMediaInput, CommitPersonInput,
+    CreateCustomMetadataInput, CreateOrUpdateReviewInput, CreateReviewCommentInput,
+    DeleteMetadataImageInput, DeleteReviewCommentInput, EntityWithLot, GenreDetailsInput,
+    GenreListItem, GraphqlCalendarEvent, GraphqlMediaAssets, GraphqlMetadataDetails,
+    GraphqlMetadataGroup, GraphqlVideoAsset, GroupedCalendarEvent, ImportOrExportItemReviewComment,
+    InstancePopularInput, InstancePopularItem, MarkEpisodeSkippedInput, MarkShowSeasonSeenInput,
+    MediaAssociatedPersonStateChanges, MediaByTagInput, MediaGeneralFilter, MediaReviewsInput,
+    MediaSortBy, MetadataCreator, MetadataCreatorGroupedByRole, MetadataDetails,
+    MetadataExternalIdentifier, MetadataFieldChange, MetadataFreeCreator, MetadataGroupSearchInput,
+    MetadataGroupSearchItem, MetadataGroupsListInput, MetadataIdentifierType, MetadataImage,
+    MetadataImageForMediaDetails, MetadataListInput, MetadataListItem, MetadataLookupCandidate,
+    MetadataLookupResult, MetadataPartialDetails, MetadataRefreshProgress, MetadataSearchInput,
+    MetadataSearchItem, MetadataSearchItemResponse, MetadataSuggestionsInput, MetadataTagInput,
+    MetadataVideo, MetadataVideoSource, PartialMetadata, PartialMetadataWithoutId, PeopleListInput,
     PeopleSearchInput, PeopleSearchItem, PersonAndMetadataGroupsSortBy, PersonDetailsGroupedByRole,
-    PersonDetailsItemWithCharacter, PodcastSpecifics, ProgressUpdateInput,
-    ProviderLanguageInformation, ReviewPostedEvent, SeenAnimeExtraInformation,
-    SeenPodcastExtraInformation, SeenShowExtraInformation, ShowSpecifics, UpdateSeenItemInput,
-    UserCalendarEventInput, UserMediaNextEntry, UserMetadataDetailsEpisodeProgress,
-    UserMetadataDetailsShowSeasonProgress, UserUpcomingCalendarEventInput,
+    PersonDetailsItemWithCharacter, PersonWorksInput, PersonWorksLotCount, PodcastSpecifics,
+    PostReviewCommentInput, ProgressUpdateInput, ProgressUpdateResultUnion,
+    ProviderLanguageInformation, QuickLogResult, RandomEpisode, RandomEpisodeInput,
+    RandomEpisodeResult, RandomEpisodeUnavailableReason, RandomMetadataInput, ReviewCommentsInput,
+    ReviewItem, ReviewPostedEvent, SeenAnimeExtraInformation, SeenChangesInput, SeenEdition,
+    SeenHistoryInput, SeenPodcastExtraInformation, SeenShowExtraInformation, ShowSeason,
+    ShowSpecifics, SwapMetadataSourceInput, UpdateCustomMetadataInput,
+    UpdateMetadataSortTitleInput, UpdateSeenItemInput, UserCalendarEventInput, UserMediaNextEntry,
+    UserMetadataDetailsEpisodeProgress, UserMetadataDetailsShowSeasonProgress,
+    UserUpcomingCalendarEventInput, VideoGameSessionsInput,
 };
 use migrations::{
     AliasedCalendarEvent, AliasedMetadata, AliasedMetadataToGenre, AliasedReview, AliasedSeen,
     AliasedUserToEntity,
 };
 use nanoid::nanoid;
-use notification_service::send_notification;
+use notification_service::{
+    notification_delivery_counts, notification_platform_host, record_notification_delivery,
+    send_notification,
+};
 use providers::{
     anilist::{AnilistService, NonMediaAnilistService},
+    attribution,
     audible::AudibleService,
+    circuit_breaker::{provider_statuses, CircuitBreakingProvider},
     google_books::GoogleBooksService,
     igdb::IgdbService,
     itunes::ITunesService,
     listennotes::ListennotesService,
     mal::{MalService, NonMediaMalService},
     manga_updates::MangaUpdatesService,
+    metadata_group_source_url, metadata_source_url,
     openlibrary::OpenlibraryService,
+    person_source_url,
     tmdb::TmdbService,
     vndb::VndbService,
 };
+use rand::{seq::SliceRandom, thread_rng};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use sea_orm::{
     prelude::DateTimeUtc, query::UpdateMany, sea_query::NullOrdering, ActiveModelTrait,
     ActiveValue, ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait,
     FromQueryResult, ItemsAndPagesNumber, Iterable, JoinType, ModelTrait, Order, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, QueryTrait, RelationTrait, Statement, TransactionTrait,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, RelationTrait, Select, Statement,
+    TransactionTrait,
 };
 use sea_query::{
     extension::postgres::PgExpr, Alias, Asterisk, Cond, Condition, Expr, Func, PgFunc,
-    PostgresQueryBuilder, Query, SelectStatement,
+    PostgresQueryBuilder, Query, SelectStatement, SimpleExpr,
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use strum::IntoEnumIterator;
 use supporting_service::SupportingService;
 use tokio::time::{sleep, Duration as TokioDuration};
 use traits::{MediaProvider, MediaProviderLanguages, TraceOk};
-use user_models::{DashboardElementLot, UserReviewScale};
+use user_models::{DashboardElementLot, UserQuickLogAction};
 use uuid::Uuid;
 
 type Provider = Box<(dyn MediaProvider + Send + Sync)>;
 
+const IMAGE_VALIDATION_BATCH_SIZE: u64 = 50;
+const NOTIFICATION_RETRY_DELAYS_MINUTES: [i64; 4] = [1, 5, 30, 120];
+// DEV: A delivery is claimed for this long so that overlapping runs do not send it
+// twice. If the server stopped while sending it, it is picked up again after this.
+const NOTIFICATION_DELIVERY_CLAIM_MINUTES: i64 = 15;
+const BULK_COMMIT_CONCURRENCY: usize = 5;
+const FORCED_METADATA_REFRESH_INTERVAL_HOURS: i64 = 1;
+
 #[derive(Debug, Clone)]
 struct CustomService {}
 
@@ -130,6 +181,405 @@ impl MediaProviderLanguages for CustomService {
     }
 }
 
+/// The first aired episode of a show that has not been seen or skipped, going
+/// through the seasons in order. Special seasons are not considered.
+fn next_unseen_show_episode(
+    specifics: &ShowSpecifics,
+    history: &[seen::Model],
+    today: NaiveDate,
+) -> Option<(i32, i32)> {
+    let finished = history
+        .iter()
+        .filter(|h| matches!(h.state, SeenState::Completed | SeenState::Skipped))
+        .filter_map(|h| h.show_extra_information.as_ref())
+        .map(|s| (s.season, s.episode))
+        .collect::<HashSet<_>>();
+    specifics
+        .seasons
+        .iter()
+        .filter(|s| !SHOW_SPECIAL_SEASON_NAMES.contains(&s.name.as_str()))
+        .sorted_by_key(|s| s.season_number)
+        .flat_map(|s| {
+            s.episodes
+                .iter()
+                .sorted_by_key(|e| e.episode_number)
+                .filter(|e| e.publish_date.map_or(true, |d| d <= today))
+                .map(move |e| (s.season_number, e.episode_number))
+        })
+        .find(|e| !finished.contains(e))
+}
+
+/// The announcements that have started, have not ended yet and were not dismissed
+/// by the user.
+fn active_announcements_query(user_id: &str, now: DateTimeUtc) -> Select<Announcement> {
+    Announcement::find()
+        .filter(announcement::Column::StartsAt.lte(now))
+        .filter(
+            Condition::any()
+                .add(announcement::Column::EndsAt.is_null())
+                .add(announcement::Column::EndsAt.gt(now)),
+        )
+        .filter(
+            Expr::expr(
+                Expr::val(user_id).eq(PgFunc::any(Expr::col(announcement::Column::DismissedBy))),
+            )
+            .not(),
+        )
+        .order_by_desc(announcement::Column::StartsAt)
+}
+
+// DEV: The user is appended in a single statement so that users who dismiss the
+// announcement at the same time do not overwrite each other
+fn dismiss_announcement_query(announcement_id: &str, user_id: &str) -> UpdateMany<Announcement> {
+    Announcement::update_many()
+        .col_expr(
+            announcement::Column::DismissedBy,
+            Expr::cust_with_values(r#"ARRAY_APPEND("dismissed_by", $1)"#, [user_id]),
+        )
+        .filter(announcement::Column::Id.eq(announcement_id))
+        .filter(
+            Expr::expr(
+                Expr::val(user_id).eq(PgFunc::any(Expr::col(announcement::Column::DismissedBy))),
+            )
+            .not(),
+        )
+}
+
+/// The date a quick log is recorded on. A date given explicitly always wins over
+/// the action the user prefers.
+fn quick_log_date(
+    date: Option<NaiveDate>,
+    action: UserQuickLogAction,
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    date.or(match action {
+        UserQuickLogAction::Now => Some(today),
+        UserQuickLogAction::InThePast => None,
+    })
+}
+
+/// The finished date of every time the media was seen from start to end, oldest
+/// first. Episodic media only counts once each of its episodes has been seen or
+/// skipped, and a run is finished on the date its last episode was.
+fn finished_runs(metadata: &metadata::Model, history: &[seen::Model]) -> Vec<Option<NaiveDate>> {
+    let finished = history
+        .iter()
+        .filter(|h| matches!(h.state, SeenState::Completed | SeenState::Skipped));
+    let (episodes, episode_of): (Vec<(i32, i32)>, fn(&seen::Model) -> Option<(i32, i32)>) =
+        if let Some(specifics) = &metadata.show_specifics {
+            let episodes = specifics
+                .seasons
+                .iter()
+                .filter(|s| !SHOW_SPECIAL_SEASON_NAMES.contains(&s.name.as_str()))
+                .flat_map(|s| {
+                    s.episodes
+                        .iter()
+                        .map(move |e| (s.season_number, e.episode_number))
+                })
+                .collect();
+            (episodes, |h| {
+                h.show_extra_information
+                    .as_ref()
+                    .map(|s| (s.season, s.episode))
+            })
+        } else if let Some(specifics) = &metadata.podcast_specifics {
+            let episodes = specifics.episodes.iter().map(|e| (0, e.number)).collect();
+            (episodes, |h| {
+                h.podcast_extra_information.as_ref().map(|p| (0, p.episode))
+            })
+        } else if let Some(count) = metadata.anime_specifics.as_ref().and_then(|a| a.episodes) {
+            ((1..count + 1).map(|e| (0, e)).collect(), |h| {
+                h.anime_extra_information
+                    .as_ref()
+                    .and_then(|a| a.episode)
+                    .map(|e| (0, e))
+            })
+        } else {
+            return finished
+                .filter(|h| h.state == SeenState::Completed)
+                .map(|h| h.finished_on)
+                .sorted()
+                .collect();
+        };
+    let mut dates = HashMap::<(i32, i32), Vec<Option<NaiveDate>>>::new();
+    for seen in finished {
+        if let Some(episode) = episode_of(seen) {
+            dates.entry(episode).or_default().push(seen.finished_on);
+        }
+    }
+    dates.values_mut().for_each(|d| d.sort());
+    let runs = episodes
+        .iter()
+        .map(|e| dates.get(e).map_or(0, |d| d.len()))
+        .min()
+        .unwrap_or_default();
+    (0..runs)
+        .map(|run| episodes.iter().filter_map(|e| dates[e][run]).max())
+        .collect()
+}
+
+/// Shows are keyed by season and episode, podcasts only by episode.
+type EpisodeKey = (Option<i32>, i32);
+
+struct RandomEpisodeCandidates {
+    eligible: Vec<(EpisodeKey, String, Option<NaiveDate>)>,
+    last_seen: HashMap<EpisodeKey, Option<NaiveDate>>,
+}
+
+/// The aired episodes that the random picker can choose from, along with when
+/// each episode was last seen.
+fn random_episode_candidates(
+    meta: &metadata::Model,
+    history: &[seen::Model],
+    input: &RandomEpisodeInput,
+    today: NaiveDate,
+) -> Result<RandomEpisodeCandidates, RandomEpisodeUnavailableReason> {
+    let include_specials = input.include_specials.unwrap_or_default();
+    let episodes = match (&meta.show_specifics, &meta.podcast_specifics) {
+        (Some(show), _) => show
+            .seasons
+            .iter()
+            .filter(|s| include_specials || !SHOW_SPECIAL_SEASON_NAMES.contains(&s.name.as_str()))
+            .flat_map(|s| {
+                s.episodes.iter().map(|e| {
+                    (
+                        (Some(s.season_number), e.episode_number),
+                        e.name.clone(),
+                        e.publish_date,
+                    )
+                })
+            })
+            .collect_vec(),
+        (None, Some(podcast)) => podcast
+            .episodes
+            .iter()
+            .map(|e| ((None, e.number), e.title.clone(), Some(e.publish_date)))
+            .collect_vec(),
+        (None, None) => return Err(RandomEpisodeUnavailableReason::NotShowOrPodcast),
+    };
+    let aired = episodes
+        .into_iter()
+        .filter(|(_, _, publish_date)| publish_date.map_or(true, |d| d <= today))
+        .collect_vec();
+    if aired.is_empty() {
+        return Err(RandomEpisodeUnavailableReason::NoAiredEpisodes);
+    }
+    let mut last_seen: HashMap<EpisodeKey, Option<NaiveDate>> = HashMap::new();
+    for seen in history.iter().filter(|h| h.state == SeenState::Completed) {
+        let key = match (
+            &seen.show_extra_information,
+            &seen.podcast_extra_information,
+        ) {
+            (Some(s), _) => (Some(s.season), s.episode),
+            (None, Some(p)) => (None, p.episode),
+            (None, None) => continue,
+        };
+        let entry = last_seen.entry(key).or_default();
+        *entry = (*entry).max(seen.finished_on);
+    }
+    let unseen_only = input.unseen_only.unwrap_or_default();
+    let avoid_after = input
+        .avoid_recent_days
+        .map(|days| today - Duration::days(days));
+    let eligible = aired
+        .into_iter()
+        .filter(|(key, _, _)| match last_seen.get(key) {
+            None => true,
+            Some(_) if unseen_only => false,
+            Some(on) => match (on, avoid_after) {
+                (Some(on), Some(after)) => *on <= after,
+                _ => true,
+            },
+        })
+        .collect_vec();
+    if eligible.is_empty() {
+        return Err(match unseen_only {
+            true => RandomEpisodeUnavailableReason::AllSeen,
+            false => RandomEpisodeUnavailableReason::AllSeenRecently,
+        });
+    }
+    Ok(RandomEpisodeCandidates {
+        eligible,
+        last_seen,
+    })
+}
+
+/// The show season, show episode and podcast episode numbers of an episode, in
+/// the shape that a progress update expects them.
+fn episode_key_numbers(key: EpisodeKey) -> (Option<i32>, Option<i32>, Option<i32>) {
+    match key {
+        (Some(season), episode) => (Some(season), Some(episode), None),
+        (None, episode) => (None, None, Some(episode)),
+    }
+}
+
+/// The parts of the admin dashboard that are read from the database.
+async fn admin_dashboard_statistics(
+    db: &DatabaseConnection,
+    now: DateTimeUtc,
+) -> Result<AdminDashboardStatistics> {
+    let active_since = now - Duration::days(30);
+    let total_users = User::find().count(db).await?;
+    let active_users = User::find()
+        .filter(
+            Condition::any()
+                .add(user::Column::LastLoginOn.gte(active_since))
+                .add(
+                    user::Column::Id.in_subquery(
+                        Query::select()
+                            .column(seen::Column::UserId)
+                            .from(seen::Entity)
+                            .and_where(seen::Column::LastUpdatedOn.gte(active_since))
+                            .to_owned(),
+                    ),
+                ),
+        )
+        .count(db)
+        .await?;
+    let metadata_per_lot = Metadata::find()
+        .select_only()
+        .column(metadata::Column::Lot)
+        .column_as(metadata::Column::Id.count(), "count")
+        .group_by(metadata::Column::Lot)
+        .order_by_asc(metadata::Column::Lot)
+        .into_tuple::<(MediaLot, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(lot, count)| AdminDashboardLotCount { lot, count })
+        .collect_vec();
+    let database_size_bytes = db
+        .query_one(Statement::from_string(
+            DatabaseBackend::Postgres,
+            "SELECT pg_database_size(current_database())",
+        ))
+        .await?
+        .and_then(|qr| qr.try_get_by_index::<i64>(0).ok())
+        .unwrap_or_default();
+    let user_contributions = User::find()
+        .select_only()
+        .column(user::Column::Id)
+        .column(user::Column::Name)
+        .column_as(
+            Expr::cust(r#"(SELECT COUNT(*) FROM "seen" WHERE "seen"."user_id" = "user"."id")"#),
+            "seen_count",
+        )
+        .column_as(
+            Expr::cust(r#"(SELECT COUNT(*) FROM "review" WHERE "review"."user_id" = "user"."id")"#),
+            "review_count",
+        )
+        .order_by_desc(Expr::cust(r#""seen_count""#))
+        .into_tuple::<(String, String, i64, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(
+            |(user_id, name, seen_count, review_count)| AdminDashboardUserContribution {
+                name,
+                user_id,
+                seen_count,
+                review_count,
+            },
+        )
+        .collect_vec();
+    Ok(AdminDashboardStatistics {
+        total_users,
+        active_users,
+        metadata_per_lot,
+        user_contributions,
+        database_size_bytes,
+    })
+}
+
+/// The parts of a group in release order along with how far the user has got with
+/// each. Parts that have not been released yet have no date and are listed last.
+fn metadata_group_parts(
+    members: Vec<(
+        metadata_to_metadata_group::Model,
+        metadata::Model,
+        MetadataFinishedStatus,
+    )>,
+) -> Vec<UserMetadataGroupPart> {
+    members
+        .into_iter()
+        .sorted_by_key(|(association, metadata, _)| {
+            (
+                metadata.publish_year.is_none(),
+                metadata.publish_year,
+                metadata.publish_date.is_none(),
+                metadata.publish_date,
+                association.part,
+            )
+        })
+        .map(|(association, metadata, status)| UserMetadataGroupPart {
+            is_finished: status.is_finished,
+            has_interacted: status.has_seen,
+            part: association.part,
+            metadata_id: metadata.id,
+            publish_year: metadata.publish_year,
+        })
+        .collect_vec()
+}
+
+/// Sections that are invalidated when their data changes can be cached for longer,
+/// the rest depend on time passing and are kept for a shorter period.
+fn dashboard_section_expiry_hours(section: UserDashboardSection) -> i64 {
+    match section {
+        UserDashboardSection::InProgress
+        | UserDashboardSection::Summary
+        | UserDashboardSection::Favorites
+        | UserDashboardSection::PendingNotifications => 24,
+        UserDashboardSection::Upcoming => 6,
+        UserDashboardSection::Recommendations => 1,
+    }
+}
+
+/// Skipped episodes count towards the season being seen, but an episode that was
+/// also watched is only reported as watched.
+fn show_season_progress(
+    season: ShowSeason,
+    history: &[seen::Model],
+    today: NaiveDate,
+) -> UserMetadataDetailsShowSeasonProgress {
+    let mut episodes = vec![];
+    let mut aired_episodes = 0;
+    for episode in season.episodes {
+        let (skipped, seen): (Vec<_>, Vec<_>) = history
+            .iter()
+            .filter(|h| {
+                h.show_extra_information.as_ref().map_or(false, |s| {
+                    s.season == season.season_number && s.episode == episode.episode_number
+                })
+            })
+            .partition(|h| h.state == SeenState::Skipped);
+        if episode.publish_date.map_or(true, |d| d <= today) {
+            aired_episodes += 1;
+        }
+        episodes.push(UserMetadataDetailsEpisodeProgress {
+            episode_number: episode.episode_number,
+            times_seen: seen.len(),
+            times_skipped: skipped.len(),
+            last_seen_on: seen.iter().filter_map(|h| h.finished_on).max(),
+        })
+    }
+    let times_season_seen = episodes
+        .iter()
+        .map(|e| e.times_seen + e.times_skipped)
+        .min()
+        .unwrap_or_default();
+    UserMetadataDetailsShowSeasonProgress {
+        aired_episodes,
+        times_seen: times_season_seen,
+        season_number: season.season_number,
+        watched_episodes: episodes.iter().filter(|e| e.times_seen > 0).count(),
+        skipped_episodes: episodes
+            .iter()
+            .filter(|e| e.times_seen == 0 && e.times_skipped > 0)
+            .count(),
+        episodes,
+    }
+}
+
 pub struct MiscellaneousService(pub Arc<SupportingService>);
 
 impl MiscellaneousService {
@@ -383,6 +833,7 @@ ORDER BY RANDOM() LIMIT 10;
             .filter(
                 metadata_to_metadata::Column::Relation.eq(MetadataToMetadataRelation::Suggestion),
             )
+            .order_by_asc(metadata_to_metadata::Column::Index)
             .into_tuple::<String>()
             .all(&self.0.db)
             .await?;
@@ -408,6 +859,7 @@ ORDER BY RANDOM() LIMIT 10;
                 metadata::Column::Title,
                 metadata::Column::Images,
                 metadata::Column::PublishYear,
+                metadata::Column::DescriptionPreview,
             ])
             .into_model::<MetadataPartialDetails>()
             .one(&self.0.db)
@@ -424,7 +876,14 @@ ORDER BY RANDOM() LIMIT 10;
         metadata_id: &String,
         force_update: bool,
     ) -> Result<bool> {
-        deploy_update_metadata_job(metadata_id, force_update, &self.0).await
+        deploy_tracked_update_metadata_job(metadata_id, force_update, &self.0).await
+    }
+
+    pub async fn metadata_refresh_progress(
+        &self,
+        metadata_id: &String,
+    ) -> Result<Option<MetadataRefreshProgress>> {
+        metadata_refresh_progress(metadata_id, &self.0).await
     }
 
     pub async fn metadata_details(&self, metadata_id: &String) -> Result<GraphqlMetadataDetails> {
@@ -435,54 +894,26 @@ ORDER BY RANDOM() LIMIT 10;
             genres,
             suggestions,
         } = self.generic_metadata(metadata_id).await?;
-        let slug = slug::slugify(&model.title);
-        let identifier = &model.identifier;
-        let source_url = match model.source {
-            MediaSource::Custom => None,
-            // DEV: This is updated by the specifics
-            MediaSource::MangaUpdates => None,
-            MediaSource::Itunes => Some(format!(
-                "https://podcasts.apple.com/us/podcast/{slug}/id{identifier}"
-            )),
-            MediaSource::GoogleBooks => Some(format!(
-                "https://www.google.co.in/books/edition/{slug}/{identifier}"
-            )),
-            MediaSource::Audible => Some(format!("https://www.audible.com/pd/{slug}/{identifier}")),
-            MediaSource::Openlibrary => {
-                Some(format!("https://openlibrary.org/works/{identifier}/{slug}"))
-            }
-            MediaSource::Tmdb => {
-                let bw = match model.lot {
-                    MediaLot::Movie => "movie",
-                    MediaLot::Show => "tv",
-                    _ => unreachable!(),
-                };
-                Some(format!(
-                    "https://www.themoviedb.org/{bw}/{identifier}-{slug}"
-                ))
-            }
-            MediaSource::Listennotes => Some(format!(
-                "https://www.listennotes.com/podcasts/{slug}-{identifier}"
-            )),
-            MediaSource::Igdb => Some(format!("https://www.igdb.com/games/{slug}")),
-            MediaSource::Anilist => {
-                let bw = match model.lot {
-                    MediaLot::Anime => "anime",
-                    MediaLot::Manga => "manga",
-                    _ => unreachable!(),
-                };
-                Some(format!("https://anilist.co/{bw}/{identifier}/{slug}"))
-            }
-            MediaSource::Mal => {
-                let bw = match model.lot {
-                    MediaLot::Anime => "anime",
-                    MediaLot::Manga => "manga",
-                    _ => unreachable!(),
-                };
-                Some(format!("https://myanimelist.net/{bw}/{identifier}/{slug}"))
-            }
-            MediaSource::Vndb => Some(format!("https://vndb.org/{identifier}")),
-        };
+        let source_url = metadata_source_url(
+            model.source,
+            model.lot,
+            &model.identifier,
+            &model.title,
+            model
+                .video_game_specifics
+                .as_ref()
+                .and_then(|v| v.slug.as_deref()),
+        )
+        .or_else(|| model.manga_specifics.as_ref().and_then(|m| m.url.clone()));
+        let mut contributors = MetadataToPerson::find()
+            .select_only()
+            .column(person::Column::Source)
+            .distinct()
+            .join(JoinType::Join, metadata_to_person::Relation::Person.def())
+            .filter(metadata_to_person::Column::MetadataId.eq(metadata_id))
+            .into_tuple::<MediaSource>()
+            .all(&self.0.db)
+            .await?;
 
         let group = {
             let association = MetadataToMetadataGroup::find()
@@ -497,6 +928,7 @@ ORDER BY RANDOM() LIMIT 10;
                         .one(&self.0.db)
                         .await?
                         .unwrap();
+                    contributors.push(grp.source);
                     Some(GraphqlMetadataGroup {
                         id: grp.id,
                         name: grp.title,
@@ -506,14 +938,33 @@ ORDER BY RANDOM() LIMIT 10;
             }
         };
         let watch_providers = model.watch_providers.unwrap_or_default();
+        let tags = self
+            .metadata_tags(metadata_id)
+            .await?
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        let external_ids = MetadataExternalId::find()
+            .filter(metadata_external_id::Column::MetadataId.eq(metadata_id))
+            .order_by_asc(metadata_external_id::Column::IdType)
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .map(|e| MetadataExternalIdentifier {
+                id_type: e.id_type,
+                value: e.value,
+            })
+            .collect();
 
         let resp = GraphqlMetadataDetails {
+            tags,
             group,
             assets,
             genres,
             creators,
             source_url,
             suggestions,
+            attribution: attribution(model.source, contributors),
             id: model.id,
             lot: model.lot,
             watch_providers,
@@ -522,6 +973,7 @@ ORDER BY RANDOM() LIMIT 10;
             is_nsfw: model.is_nsfw,
             is_partial: model.is_partial,
             identifier: model.identifier,
+            images_unreachable: model.images_unreachable,
             description: model.description,
             publish_date: model.publish_date,
             publish_year: model.publish_year,
@@ -531,9 +983,12 @@ ORDER BY RANDOM() LIMIT 10;
             manga_specifics: model.manga_specifics,
             anime_specifics: model.anime_specifics,
             provider_rating: model.provider_rating,
+            provider_rating_scale: model.provider_rating_scale,
+            provider_rating_count: model.provider_rating_count,
             production_status: model.production_status,
             original_language: model.original_language,
             podcast_specifics: model.podcast_specifics,
+            external_ids,
             external_identifiers: model.external_identifiers,
             video_game_specifics: model.video_game_specifics,
             audio_book_specifics: model.audio_book_specifics,
@@ -552,68 +1007,48 @@ ORDER BY RANDOM() LIMIT 10;
             entity_in_collections(&self.0.db, &user_id, &metadata_id, EntityLot::Metadata).await?;
         let reviews =
             item_reviews(&user_id, &metadata_id, EntityLot::Metadata, true, &self.0).await?;
-        let (_, history) = is_metadata_finished_by_user(&user_id, &metadata_id, &self.0.db).await?;
+        let history = seen_history(&user_id, &metadata_id, &self.0.db).await?;
+        let warning_keys = user_by_id(&user_id, &self.0)
+            .await?
+            .preferences
+            .general
+            .content_warning_tags
+            .iter()
+            .map(|t| label_key(t))
+            .collect::<HashSet<_>>();
+        let content_warnings = self
+            .metadata_tags(&metadata_id)
+            .await?
+            .into_iter()
+            .filter(|t| warning_keys.contains(&t.key))
+            .map(|t| t.name)
+            .collect_vec();
         let in_progress = history
             .iter()
             .find(|h| h.state == SeenState::InProgress || h.state == SeenState::OnAHold)
             .cloned();
-        let next_entry = history.first().and_then(|h| {
-            if let Some(s) = &media_details.model.show_specifics {
-                let all_episodes = s
-                    .seasons
-                    .iter()
-                    .map(|s| (s.season_number, &s.episodes))
-                    .collect_vec()
-                    .into_iter()
-                    .flat_map(|(s, e)| {
-                        e.iter().map(move |e| UserMediaNextEntry {
-                            season: Some(s),
-                            episode: Some(e.episode_number),
-                            ..Default::default()
-                        })
-                    })
-                    .collect_vec();
-                let next = all_episodes.iter().position(|e| {
-                    e.season == Some(h.show_extra_information.as_ref().unwrap().season)
-                        && e.episode == Some(h.show_extra_information.as_ref().unwrap().episode)
-                });
-                Some(all_episodes.get(next? + 1)?.clone())
-            } else if let Some(p) = &media_details.model.podcast_specifics {
-                let all_episodes = p
-                    .episodes
-                    .iter()
-                    .map(|e| UserMediaNextEntry {
-                        episode: Some(e.number),
-                        ..Default::default()
-                    })
-                    .collect_vec();
-                let next = all_episodes.iter().position(|e| {
-                    e.episode == Some(h.podcast_extra_information.as_ref().unwrap().episode)
-                });
-                Some(all_episodes.get(next? + 1)?.clone())
-            } else if let Some(_anime_spec) = &media_details.model.anime_specifics {
-                h.anime_extra_information.as_ref().and_then(|hist| {
-                    hist.episode.map(|e| UserMediaNextEntry {
-                        episode: Some(e + 1),
-                        ..Default::default()
-                    })
-                })
-            } else if let Some(_manga_spec) = &media_details.model.manga_specifics {
-                h.manga_extra_information.as_ref().and_then(|hist| {
-                    hist.chapter
-                        .map(|e| UserMediaNextEntry {
-                            chapter: Some(e.floor() + dec!(1)),
-                            ..Default::default()
-                        })
-                        .or(hist.volume.map(|e| UserMediaNextEntry {
-                            volume: Some(e + 1),
-                            ..Default::default()
-                        }))
-                })
-            } else {
-                None
-            }
-        });
+        let playback_links = user_playback_links(&user_id, &metadata_id, &self.0).await?;
+        let with_playback_links = |e: UserMediaNextEntry| UserMediaNextEntry {
+            playback_links: playback_links.clone(),
+            ..e
+        };
+        // DEV: The entry after the most recently seen one is used even when earlier
+        // episodes were left out, so that a gap does not send the user backwards
+        let listened = listened_podcast_episodes(&history);
+        let next_entry = match history.first() {
+            Some(h) => next_entry_after(h, &media_details.model, &listened),
+            None => first_entry(&media_details.model, &listened),
+        }
+        .map(with_playback_links);
+        let in_progress_threads = history
+            .iter()
+            .filter(|h| h.state == SeenState::InProgress || h.state == SeenState::OnAHold)
+            .map(|h| UserMediaInProgressThread {
+                seen: h.clone(),
+                next_entry: next_entry_after(h, &media_details.model, &listened)
+                    .map(with_playback_links),
+            })
+            .collect_vec();
         let metadata_alias = Alias::new("m");
         let seen_alias = Alias::new("s");
         let seen_select = Query::select()
@@ -660,36 +1095,14 @@ ORDER BY RANDOM() LIMIT 10;
             }
         };
         let seen_by_user_count = history.len();
+        let consumption_history = finished_runs(&media_details.model, &history);
         let show_progress = if let Some(show_specifics) = media_details.model.show_specifics {
-            let mut seasons = vec![];
-            for season in show_specifics.seasons {
-                let mut episodes = vec![];
-                for episode in season.episodes {
-                    let seen = history
-                        .iter()
-                        .filter(|h| {
-                            h.show_extra_information.as_ref().map_or(false, |s| {
-                                s.season == season.season_number
-                                    && s.episode == episode.episode_number
-                            })
-                        })
-                        .collect_vec();
-                    episodes.push(UserMetadataDetailsEpisodeProgress {
-                        episode_number: episode.episode_number,
-                        times_seen: seen.len(),
-                    })
-                }
-                let times_season_seen = episodes
-                    .iter()
-                    .map(|e| e.times_seen)
-                    .min()
-                    .unwrap_or_default();
-                seasons.push(UserMetadataDetailsShowSeasonProgress {
-                    episodes,
-                    times_seen: times_season_seen,
-                    season_number: season.season_number,
-                })
-            }
+            let today = get_current_date(&self.0.timezone);
+            let seasons = show_specifics
+                .seasons
+                .into_iter()
+                .map(|season| show_season_progress(season, &history, today))
+                .collect_vec();
             Some(seasons)
         } else {
             None
@@ -709,28 +1122,147 @@ ORDER BY RANDOM() LIMIT 10;
                     episodes.push(UserMetadataDetailsEpisodeProgress {
                         episode_number: episode.number,
                         times_seen: seen.len(),
+                        times_skipped: 0,
+                        last_seen_on: seen.iter().filter_map(|h| h.finished_on).max(),
                     })
                 }
                 Some(episodes)
             } else {
                 None
             };
+        let video_game_playtime = match media_details.model.lot {
+            MediaLot::VideoGame => VideoGameSession::find()
+                .select_only()
+                .column_as(video_game_session::Column::Duration.sum(), "playtime")
+                .filter(video_game_session::Column::UserId.eq(&user_id))
+                .filter(video_game_session::Column::MetadataId.eq(&metadata_id))
+                .into_tuple::<Option<i64>>()
+                .one(&self.0.db)
+                .await?
+                .flatten(),
+            _ => None,
+        };
         Ok(UserMetadataDetails {
             reviews,
             history,
+            video_game_playtime,
             next_entry,
             collections,
             in_progress,
+            in_progress_threads,
+            content_warnings,
             show_progress,
             average_rating,
             podcast_progress,
             seen_by_user_count,
+            times_consumed: consumption_history.len(),
+            consumption_history,
+            playback_links,
             seen_by_all_count: seen_by,
             has_interacted: user_to_meta.is_some(),
             media_reason: user_to_meta.and_then(|n| n.media_reason),
         })
     }
 
+    pub async fn user_video_game_sessions(
+        &self,
+        user_id: String,
+        input: VideoGameSessionsInput,
+    ) -> Result<SearchResults<video_game_session::Model>> {
+        let page = input.page.unwrap_or(1).max(1);
+        let query = VideoGameSession::find()
+            .filter(video_game_session::Column::UserId.eq(user_id))
+            .apply_if(input.metadata_id, |query, v| {
+                query.filter(video_game_session::Column::MetadataId.eq(v))
+            })
+            .order_by_desc(video_game_session::Column::StartedOn);
+        let total = query.clone().count(&self.0.db).await?;
+        let total: i32 = total.try_into().unwrap();
+        let data = query.paginate(&self.0.db, PAGE_SIZE.try_into().unwrap());
+        let items = data.fetch_page((page - 1).try_into().unwrap()).await?;
+        let next_page = if total - (page * PAGE_SIZE) > 0 {
+            Some(page + 1)
+        } else {
+            None
+        };
+        Ok(SearchResults {
+            details: SearchDetails { total, next_page },
+            items,
+        })
+    }
+
+    pub async fn user_metadata_seen_history(
+        &self,
+        user_id: String,
+        input: SeenHistoryInput,
+    ) -> Result<SearchResults<seen::Model>> {
+        let page = input.page.unwrap_or(1).max(1);
+        let take = input.take.unwrap_or(PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let query = seen_history_query(&user_id, &input.metadata_id);
+        let total = query.clone().count(&self.0.db).await?;
+        let total: i32 = total.try_into().unwrap();
+        let data = query.paginate(&self.0.db, take.try_into().unwrap());
+        let items = data.fetch_page((page - 1).try_into().unwrap()).await?;
+        let next_page = if total - (page * take) > 0 {
+            Some(page + 1)
+        } else {
+            None
+        };
+        Ok(SearchResults {
+            details: SearchDetails { total, next_page },
+            items,
+        })
+    }
+
+    pub async fn user_seen_changes(
+        &self,
+        user_id: String,
+        input: SeenChangesInput,
+    ) -> Result<SeenChangesResponse> {
+        let take = input.take.unwrap_or(PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let items = seen_changes_query(&user_id, input.after.as_ref())
+            .limit(u64::try_from(take).unwrap())
+            .all(&self.0.db)
+            .await?;
+        let has_more = items.len() == usize::try_from(take).unwrap();
+        let next_cursor = items.last().map(seen_changes_cursor).or(input.after);
+        Ok(SeenChangesResponse {
+            items,
+            has_more,
+            next_cursor,
+        })
+    }
+
+    pub async fn metadata_suggestions(
+        &self,
+        input: MetadataSuggestionsInput,
+    ) -> Result<SearchResults<String>> {
+        let page = input.page.unwrap_or(1).max(1);
+        let query = MetadataToMetadata::find()
+            .select_only()
+            .column(metadata_to_metadata::Column::ToMetadataId)
+            .filter(metadata_to_metadata::Column::FromMetadataId.eq(input.metadata_id))
+            .filter(
+                metadata_to_metadata::Column::Relation.eq(MetadataToMetadataRelation::Suggestion),
+            )
+            .order_by_asc(metadata_to_metadata::Column::Index);
+        let total = query.clone().count(&self.0.db).await?;
+        let total: i32 = total.try_into().unwrap();
+        let data = query
+            .into_tuple::<String>()
+            .paginate(&self.0.db, PAGE_SIZE.try_into().unwrap());
+        let items = data.fetch_page((page - 1).try_into().unwrap()).await?;
+        let next_page = if total - (page * PAGE_SIZE) > 0 {
+            Some(page + 1)
+        } else {
+            None
+        };
+        Ok(SearchResults {
+            details: SearchDetails { total, next_page },
+            items,
+        })
+    }
+
     pub async fn user_person_details(
         &self,
         user_id: String,
@@ -739,7 +1271,26 @@ ORDER BY RANDOM() LIMIT 10;
         let reviews = item_reviews(&user_id, &person_id, EntityLot::Person, true, &self.0).await?;
         let collections =
             entity_in_collections(&self.0.db, &user_id, &person_id, EntityLot::Person).await?;
+        let associations = MetadataToPerson::find()
+            .filter(metadata_to_person::Column::PersonId.eq(&person_id))
+            .order_by_asc(metadata_to_person::Column::Role)
+            .order_by_asc(metadata_to_person::Column::MetadataId)
+            .all(&self.0.db)
+            .await?;
+        let mut works = vec![];
+        for association in associations {
+            let status =
+                is_metadata_finished_by_user(&user_id, &association.metadata_id, &self.0.db)
+                    .await?;
+            works.push(UserPersonWork {
+                is_finished: status.is_finished,
+                has_interacted: status.has_seen,
+                role: association.role,
+                metadata_id: association.metadata_id,
+            });
+        }
         Ok(UserPersonDetails {
+            works,
             reviews,
             collections,
         })
@@ -765,9 +1316,23 @@ ORDER BY RANDOM() LIMIT 10;
             &self.0,
         )
         .await?;
+        let members = MetadataToMetadataGroup::find()
+            .filter(metadata_to_metadata_group::Column::MetadataGroupId.eq(&metadata_group_id))
+            .find_also_related(Metadata)
+            .all(&self.0.db)
+            .await?;
+        let mut parts = vec![];
+        for (association, metadata) in members {
+            let Some(metadata) = metadata else {
+                continue;
+            };
+            let status = is_metadata_finished_by_user(&user_id, &metadata.id, &self.0.db).await?;
+            parts.push((association, metadata, status));
+        }
         Ok(UserMetadataGroupDetails {
             reviews,
             collections,
+            parts: metadata_group_parts(parts),
         })
     }
 
@@ -966,26 +1531,302 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(events)
     }
 
-    pub async fn metadata_list(
+    async fn dashboard_section<T, F>(
         &self,
-        user_id: String,
-        input: MetadataListInput,
-    ) -> Result<SearchResults<String>> {
-        let preferences = user_by_id(&user_id, &self.0).await?.preferences;
+        user_id: &String,
+        section: UserDashboardSection,
+        refresh: bool,
+        compute: F,
+    ) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T>>,
+    {
+        let key = ApplicationCacheKey::UserDashboardSection {
+            section,
+            user_id: user_id.to_owned(),
+        };
+        if !refresh {
+            if let Some(cached) = self.0.cache_service.get_value(key.clone()).await? {
+                return Ok(cached);
+            }
+        }
+        let value = compute.await?;
+        self.0
+            .cache_service
+            .set_value_with_expiry(key, &value, dashboard_section_expiry_hours(section))
+            .await?;
+        Ok(value)
+    }
 
-        let avg_rating_col = "user_average_rating";
-        let cloned_user_id_1 = user_id.clone();
-        let cloned_user_id_2 = user_id.clone();
+    async fn user_default_collection_entities(
+        &self,
+        user_id: &String,
+        collection: DefaultCollection,
+        take: Option<u64>,
+    ) -> Result<Vec<EntityWithLot>> {
+        let items = CollectionToEntity::find()
+            .select_only()
+            .columns([
+                collection_to_entity::Column::EntityId,
+                collection_to_entity::Column::EntityLot,
+            ])
+            .inner_join(Collection)
+            .filter(collection::Column::UserId.eq(user_id))
+            .filter(collection::Column::Name.eq(collection.to_string()))
+            .order_by_desc(collection_to_entity::Column::LastUpdatedOn)
+            .limit(take)
+            .into_tuple::<(String, EntityLot)>()
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .map(|(entity_id, entity_lot)| EntityWithLot {
+                entity_id,
+                entity_lot,
+            })
+            .collect_vec();
+        Ok(items)
+    }
 
-        let order_by = input
-            .sort
+    /// The in progress collection, with one entry for every episode that is underway
+    /// so that parallel threads of a show each get their own next episode.
+    async fn user_in_progress_items(
+        &self,
+        user_id: &String,
+        take: Option<u64>,
+    ) -> Result<Vec<UserDashboardInProgressItem>> {
+        let entities = self
+            .user_default_collection_entities(user_id, DefaultCollection::InProgress, take)
+            .await?;
+        let metadata_ids = entities
+            .iter()
+            .filter(|e| e.entity_lot == EntityLot::Metadata)
+            .map(|e| e.entity_id.clone())
+            .collect_vec();
+        let threads = Seen::find()
+            .filter(seen::Column::UserId.eq(user_id))
+            .filter(seen::Column::MetadataId.is_in(metadata_ids.clone()))
+            .filter(seen::Column::State.eq(SeenState::InProgress))
+            .order_by_desc(seen::Column::LastUpdatedOn)
+            .order_by_desc(seen::Column::Id)
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .into_group_map_by(|s| s.metadata_id.clone());
+        let listened = Seen::find()
+            .select_only()
+            .column(seen::Column::MetadataId)
+            .column(seen::Column::PodcastEpisodeNumber)
+            .filter(seen::Column::UserId.eq(user_id))
+            .filter(seen::Column::MetadataId.is_in(metadata_ids.clone()))
+            .filter(seen::Column::State.is_in([SeenState::Completed, SeenState::Skipped]))
+            .filter(seen::Column::PodcastEpisodeNumber.is_not_null())
+            .into_tuple::<(String, i32)>()
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .into_grouping_map()
+            .collect::<HashSet<_>>();
+        let metadata = Metadata::find()
+            .filter(metadata::Column::Id.is_in(metadata_ids))
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .map(|m| (m.id.clone(), m))
+            .collect::<HashMap<_, _>>();
+        let mut items = vec![];
+        for entity in entities {
+            let seen_items = threads.get(&entity.entity_id).cloned().unwrap_or_default();
+            if seen_items.is_empty() {
+                items.push(UserDashboardInProgressItem {
+                    entity_id: entity.entity_id,
+                    entity_lot: entity.entity_lot,
+                    seen_id: None,
+                    next_entry: None,
+                });
+                continue;
+            }
+            for seen in seen_items {
+                let listened = listened.get(&entity.entity_id).cloned().unwrap_or_default();
+                let next_entry = metadata
+                    .get(&entity.entity_id)
+                    .and_then(|m| next_entry_after(&seen, m, &listened));
+                items.push(UserDashboardInProgressItem {
+                    entity_id: entity.entity_id.clone(),
+                    entity_lot: entity.entity_lot,
+                    seen_id: Some(seen.id),
+                    next_entry,
+                });
+            }
+        }
+        if let Some(take) = take {
+            items.truncate(take.try_into().unwrap());
+        }
+        Ok(items)
+    }
+
+    async fn user_pending_notifications_count(&self, user_id: &String) -> Result<u64> {
+        let count = QueuedNotification::find()
+            .filter(queued_notification::Column::UserId.eq(user_id))
+            .count(&self.0.db)
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn user_dashboard(&self, user_id: String, refresh: bool) -> Result<UserDashboard> {
+        let preferences = user_by_id(&user_id, &self.0).await?.preferences.general;
+        let num_elements = |lot: DashboardElementLot| {
+            preferences
+                .dashboard
+                .iter()
+                .find(|e| e.section == lot)
+                .and_then(|e| e.num_elements)
+        };
+        let (in_progress, upcoming, summary, recommendations, pending_notifications, favorites) = tokio::join!(
+            self.dashboard_section(
+                &user_id,
+                UserDashboardSection::InProgress,
+                refresh,
+                self.user_in_progress_items(
+                    &user_id,
+                    num_elements(DashboardElementLot::InProgress)
+                ),
+            ),
+            self.dashboard_section(
+                &user_id,
+                UserDashboardSection::Upcoming,
+                refresh,
+                self.user_upcoming_calendar_events(
+                    user_id.clone(),
+                    UserUpcomingCalendarEventInput::NextMedia(
+                        num_elements(DashboardElementLot::Upcoming).unwrap_or(PAGE_SIZE as u64)
+                    ),
+                ),
+            ),
+            self.dashboard_section(
+                &user_id,
+                UserDashboardSection::Summary,
+                refresh,
+                latest_user_summary(&user_id, &self.0.db),
+            ),
+            self.dashboard_section(
+                &user_id,
+                UserDashboardSection::Recommendations,
+                refresh,
+                user_recommendations(&user_id, &self.0),
+            ),
+            self.dashboard_section(
+                &user_id,
+                UserDashboardSection::PendingNotifications,
+                refresh,
+                self.user_pending_notifications_count(&user_id),
+            ),
+            self.dashboard_section(
+                &user_id,
+                UserDashboardSection::Favorites,
+                refresh,
+                self.user_default_collection_entities(
+                    &user_id,
+                    DefaultCollection::Favorites,
+                    Some(PAGE_SIZE as u64)
+                ),
+            ),
+        );
+        Ok(UserDashboard {
+            summary: summary?,
+            upcoming: upcoming?,
+            favorites: favorites?,
+            in_progress: in_progress?,
+            recommendations: recommendations?,
+            pending_notifications: pending_notifications?,
+        })
+    }
+
+    pub async fn metadata_list_items(
+        &self,
+        user_id: String,
+        input: MetadataListInput,
+    ) -> Result<SearchResults<MetadataListItem>> {
+        let review_scale = user_by_id(&user_id, &self.0)
+            .await?
+            .preferences
+            .general
+            .review_scale;
+        let results = self.metadata_list(user_id.clone(), input).await?;
+        let latest_seen = Seen::find()
+            .select_only()
+            .column(seen::Column::MetadataId)
+            .column(seen::Column::State)
+            .column(seen::Column::LastUpdatedOn)
+            .distinct_on([seen::Column::MetadataId])
+            .filter(seen::Column::UserId.eq(&user_id))
+            .filter(seen::Column::MetadataId.is_in(results.items.clone()))
+            .order_by_asc(seen::Column::MetadataId)
+            .order_by_desc(seen::Column::LastUpdatedOn)
+            .order_by_desc(seen::Column::Id)
+            .into_tuple::<(String, SeenState, DateTimeUtc)>()
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .map(|(metadata_id, state, on)| (metadata_id, (state, on)))
+            .collect::<HashMap<_, _>>();
+        let ratings = Review::find()
+            .select_only()
+            .column(review::Column::MetadataId)
+            .column_as(Func::avg(Expr::col(review::Column::Rating)), "rating")
+            .filter(review::Column::UserId.eq(&user_id))
+            .filter(review::Column::MetadataId.is_in(results.items.clone()))
+            .filter(review::Column::Rating.is_not_null())
+            .group_by(review::Column::MetadataId)
+            .into_tuple::<(String, Decimal)>()
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let items = results
+            .items
+            .into_iter()
+            .map(|metadata_id| {
+                let seen = latest_seen.get(&metadata_id);
+                MetadataListItem {
+                    seen_status: seen.map(|(state, _)| *state),
+                    last_seen_on: seen.map(|(_, on)| *on),
+                    average_rating: ratings
+                        .get(&metadata_id)
+                        .map(|r| review_scale.denormalize(*r)),
+                    metadata_id,
+                }
+            })
+            .collect();
+        Ok(SearchResults {
+            details: results.details,
+            items,
+        })
+    }
+
+    pub async fn metadata_list(
+        &self,
+        user_id: String,
+        input: MetadataListInput,
+    ) -> Result<SearchResults<String>> {
+        let preferences = user_by_id(&user_id, &self.0).await?.preferences;
+        let today = get_current_date(&self.0.timezone);
+        let warning_tags = preferences.general.content_warning_tags.clone();
+
+        let avg_rating_col = "user_average_rating";
+        let has_media_reason = |reason: UserToMediaReason| {
+            Expr::val(reason.to_string())
+                .eq(PgFunc::any(Expr::col(user_to_entity::Column::MediaReason)))
+        };
+        let cloned_user_id_1 = user_id.clone();
+        let cloned_user_id_2 = user_id.clone();
+
+        let order_by = input
+            .sort
             .clone()
             .map(|a| graphql_to_db_order(a.order))
             .unwrap_or(Order::Asc);
-        let review_scale = match preferences.general.review_scale {
-            UserReviewScale::OutOfFive => 20,
-            UserReviewScale::OutOfHundred | UserReviewScale::ThreePointSmiley => 1,
-        };
+        let review_scale = preferences.general.review_scale.divisor();
         let take = input.take.unwrap_or(PAGE_SIZE as u64);
         let page: u64 = input
             .search
@@ -1002,7 +1843,7 @@ ORDER BY RANDOM() LIMIT 10;
                     Func::avg(
                         Expr::col((AliasedReview::Table, AliasedReview::Rating)).div(review_scale),
                     ),
-                    review_scale,
+                    1,
                 ),
                 avg_rating_col,
             )
@@ -1033,13 +1874,44 @@ ORDER BY RANDOM() LIMIT 10;
                         )
                     }),
             )
-            .apply_if(input.search.and_then(|s| s.query), |query, v| {
-                query.filter(
-                    Cond::any()
-                        .add(Expr::col(metadata::Column::Title).ilike(ilike_sql(&v)))
-                        .add(Expr::col(metadata::Column::Description).ilike(ilike_sql(&v))),
-                )
-            })
+            .apply_if(
+                input
+                    .search
+                    .and_then(|s| s.query)
+                    .map(|q| q.trim().to_owned())
+                    .filter(|q| !q.is_empty()),
+                |query, v| {
+                    query.filter(
+                        Cond::any()
+                            .add(Expr::col(metadata::Column::Title).ilike(ilike_sql(&v)))
+                            .add(Expr::col(metadata::Column::Description).ilike(ilike_sql(&v)))
+                            .add(
+                                metadata::Column::Id.in_subquery(
+                                    Query::select()
+                                        .column(metadata_to_person::Column::MetadataId)
+                                        .from(metadata_to_person::Entity)
+                                        .inner_join(
+                                            person::Entity,
+                                            Expr::col((person::Entity, person::Column::Id)).equals((
+                                                metadata_to_person::Entity,
+                                                metadata_to_person::Column::PersonId,
+                                            )),
+                                        )
+                                        .and_where(
+                                            Expr::col((person::Entity, person::Column::Name))
+                                                .ilike(ilike_sql(&v)),
+                                        )
+                                        .to_owned(),
+                                ),
+                            )
+                            // DEV: Creators without a person row are only stored on the metadata
+                            .add(Expr::cust_with_values(
+                                r#"EXISTS (SELECT 1 FROM jsonb_array_elements("metadata"."free_creators") AS "fc" WHERE "fc"->>'name' ILIKE $1)"#,
+                                [ilike_sql(&v)],
+                            )),
+                    )
+                },
+            )
             .apply_if(
                 input.filter.clone().and_then(|f| f.collections),
                 |query, v| {
@@ -1052,17 +1924,38 @@ ORDER BY RANDOM() LIMIT 10;
                     )
                 },
             )
+            .apply_if(input.filter.clone(), |query, v| {
+                apply_publish_year_filter(query, &v)
+            })
+            .apply_if(
+                input.filter.clone().and_then(|f| f.release_status),
+                |query, v| query.filter(metadata_release_status_condition(v, today)),
+            )
+            .apply_if(
+                input
+                    .filter
+                    .clone()
+                    .and_then(|f| f.exclude_content_warnings)
+                    .filter(|e| *e && !warning_tags.is_empty()),
+                |query, _| query.filter(metadata_content_warning_condition(&warning_tags).not()),
+            )
             .apply_if(input.filter.and_then(|f| f.general), |query, v| match v {
                 MediaGeneralFilter::All => query.filter(metadata::Column::Id.is_not_null()),
                 MediaGeneralFilter::Rated => query.filter(review::Column::Id.is_not_null()),
                 MediaGeneralFilter::Unrated => query.filter(review::Column::Id.is_null()),
-                MediaGeneralFilter::Unfinished => query.filter(
-                    Expr::expr(
-                        Expr::val(UserToMediaReason::Finished.to_string())
-                            .eq(PgFunc::any(Expr::col(user_to_entity::Column::MediaReason))),
-                    )
-                    .not(),
-                ),
+                MediaGeneralFilter::Unfinished => {
+                    query.filter(has_media_reason(UserToMediaReason::Finished).not())
+                }
+                MediaGeneralFilter::Completed => {
+                    query.filter(has_media_reason(UserToMediaReason::Finished))
+                }
+                MediaGeneralFilter::OnWatchlist => {
+                    query.filter(has_media_reason(UserToMediaReason::Watchlist))
+                }
+                MediaGeneralFilter::NotStarted => query.filter(seen::Column::Id.is_null()),
+                MediaGeneralFilter::InProgress => query
+                    .filter(seen::Column::State.eq(SeenState::InProgress))
+                    .filter(seen::Column::Progress.lt(dec!(100))),
                 s => query.filter(seen::Column::State.eq(match s {
                     MediaGeneralFilter::Dropped => SeenState::Dropped,
                     MediaGeneralFilter::OnAHold => SeenState::OnAHold,
@@ -1073,7 +1966,13 @@ ORDER BY RANDOM() LIMIT 10;
                 MediaSortBy::LastUpdated => query
                     .order_by(user_to_entity::Column::LastUpdatedOn, order_by)
                     .group_by(user_to_entity::Column::LastUpdatedOn),
-                MediaSortBy::Title => query.order_by(metadata::Column::Title, order_by),
+                MediaSortBy::Title => query.order_by(
+                    Expr::expr(Func::coalesce([
+                        Expr::col(metadata::Column::SortTitle).into(),
+                        Func::lower(Expr::col(metadata::Column::Title)).into(),
+                    ])),
+                    order_by,
+                ),
                 MediaSortBy::ReleaseDate => query.order_by_with_nulls(
                     metadata::Column::PublishYear,
                     order_by,
@@ -1094,6 +1993,17 @@ ORDER BY RANDOM() LIMIT 10;
                     order_by,
                     NullOrdering::Last,
                 ),
+                // DEV: Every episode of a show or podcast has its own seen entry
+                MediaSortBy::TimesConsumed => query.order_by(
+                    Func::count_distinct(
+                        Expr::case(
+                            seen::Column::State.eq(SeenState::Completed),
+                            Expr::col((seen::Entity, seen::Column::Id)),
+                        )
+                        .finally(Expr::val(Option::<String>::None)),
+                    ),
+                    order_by,
+                ),
             })
             .into_tuple::<String>()
             .paginate(&self.0.db, take);
@@ -1142,6 +2052,198 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(true)
     }
 
+    pub async fn quick_log(
+        &self,
+        user_id: String,
+        metadata_id: String,
+        date: Option<NaiveDate>,
+    ) -> Result<QuickLogResult> {
+        let preferences = user_by_id(&user_id, &self.0).await?.preferences.general;
+        let today = get_current_date(&self.0.timezone);
+        let meta = Metadata::find_by_id(&metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata with the given id does not exist"))?;
+        let (show_season_number, show_episode_number) = match &meta.show_specifics {
+            None => (None, None),
+            Some(_) if !preferences.quick_log_auto_advance => {
+                return Err(Error::new(
+                    "Automatically picking the next episode is disabled in your preferences",
+                ));
+            }
+            Some(specifics) => {
+                let history = seen_history(&user_id, &metadata_id, &self.0.db).await?;
+                let (season, episode) = next_unseen_show_episode(specifics, &history, today)
+                    .ok_or_else(|| Error::new("All aired episodes have already been seen"))?;
+                (Some(season), Some(episode))
+            }
+        };
+        self.log_quick_progress(
+            &user_id,
+            metadata_id,
+            date,
+            (show_season_number, show_episode_number),
+            None,
+        )
+        .await
+    }
+
+    /// Log an item as seen on the given date, or on the date picked by the user's
+    /// quick log preference.
+    async fn log_quick_progress(
+        &self,
+        user_id: &String,
+        metadata_id: String,
+        date: Option<NaiveDate>,
+        (show_season_number, show_episode_number): (Option<i32>, Option<i32>),
+        podcast_episode_number: Option<i32>,
+    ) -> Result<QuickLogResult> {
+        let preferences = user_by_id(user_id, &self.0).await?.preferences.general;
+        let date = quick_log_date(
+            date,
+            preferences.quick_log_action,
+            get_current_date(&self.0.timezone),
+        );
+        let input = ProgressUpdateInput {
+            date,
+            metadata_id,
+            show_season_number,
+            show_episode_number,
+            podcast_episode_number,
+            edition: None,
+            pages_read: None,
+            change_state: None,
+            minutes_consumed: None,
+            progress: Some(dec!(100)),
+            allow_progress_decrease: None,
+            manga_volume_number: None,
+            provider_watched_on: None,
+            anime_episode_number: None,
+            manga_chapter_number: None,
+        };
+        let seen_id = match progress_update(user_id, false, input, &self.0).await? {
+            ProgressUpdateResultUnion::Ok(StringIdObject { id }) => id,
+            ProgressUpdateResultUnion::Error(e) => {
+                return Err(Error::new(format!("Could not log progress: {:?}", e.error)));
+            }
+        };
+        Ok(QuickLogResult {
+            date,
+            seen_id,
+            show_season_number,
+            show_episode_number,
+            podcast_episode_number,
+        })
+    }
+
+    /// Pick a media item of the user's library at random. Only items that match all
+    /// of the given filters are considered.
+    pub async fn random_metadata(
+        &self,
+        user_id: String,
+        input: RandomMetadataInput,
+    ) -> Result<Option<String>> {
+        let metadata_id = Metadata::find()
+            .select_only()
+            .column(metadata::Column::Id)
+            .filter(metadata::Column::Lot.eq(input.lot))
+            .filter(metadata::Column::Id.in_subquery(user_metadata_ids_query(&user_id)))
+            .apply_if(input.genre_id, |query, v| {
+                query.filter(
+                    metadata::Column::Id.in_subquery(
+                        Query::select()
+                            .column(metadata_to_genre::Column::MetadataId)
+                            .from(metadata_to_genre::Entity)
+                            .and_where(metadata_to_genre::Column::GenreId.eq(v))
+                            .to_owned(),
+                    ),
+                )
+            })
+            .apply_if(input.collection_id, |query, v| {
+                query.filter(
+                    metadata::Column::Id.in_subquery(
+                        Query::select()
+                            .column(collection_to_entity::Column::MetadataId)
+                            .from(collection_to_entity::Entity)
+                            .and_where(collection_to_entity::Column::CollectionId.eq(v))
+                            .and_where(collection_to_entity::Column::MetadataId.is_not_null())
+                            .to_owned(),
+                    ),
+                )
+            })
+            .apply_if(input.unseen_only.filter(|u| *u), |query, _| {
+                query.filter(
+                    metadata::Column::Id.not_in_subquery(
+                        Query::select()
+                            .column(seen::Column::MetadataId)
+                            .from(seen::Entity)
+                            .and_where(seen::Column::UserId.eq(&user_id))
+                            .to_owned(),
+                    ),
+                )
+            })
+            .order_by(SimpleExpr::FunctionCall(Func::random()), Order::Asc)
+            .into_tuple::<String>()
+            .one(&self.0.db)
+            .await?;
+        Ok(metadata_id)
+    }
+
+    /// Pick an aired episode of a show or podcast at random, leaving out the ones
+    /// that were finished recently.
+    pub async fn random_episode(
+        &self,
+        user_id: String,
+        input: RandomEpisodeInput,
+    ) -> Result<RandomEpisodeResult> {
+        let unavailable = |reason| RandomEpisodeResult {
+            episode: None,
+            unavailable_reason: Some(reason),
+        };
+        let meta = Metadata::find_by_id(&input.metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata with the given id does not exist"))?;
+        let today = get_current_date(&self.0.timezone);
+        let history = seen_history(&user_id, &input.metadata_id, &self.0.db).await?;
+        let RandomEpisodeCandidates {
+            mut eligible,
+            last_seen,
+        } = match random_episode_candidates(&meta, &history, &input, today) {
+            Ok(candidates) => candidates,
+            Err(reason) => return Ok(unavailable(reason)),
+        };
+        eligible.shuffle(&mut thread_rng());
+        let (key, name, publish_date) = eligible.swap_remove(0);
+        let (show_season_number, show_episode_number, podcast_episode_number) =
+            episode_key_numbers(key);
+        let logged = match input.and_mark_started.unwrap_or_default() {
+            false => None,
+            true => Some(
+                self.log_quick_progress(
+                    &user_id,
+                    input.metadata_id,
+                    None,
+                    (show_season_number, show_episode_number),
+                    podcast_episode_number,
+                )
+                .await?,
+            ),
+        };
+        Ok(RandomEpisodeResult {
+            unavailable_reason: None,
+            episode: Some(RandomEpisode {
+                name,
+                logged,
+                publish_date,
+                show_season_number,
+                show_episode_number,
+                podcast_episode_number,
+                last_seen_on: last_seen.get(&key).copied().flatten(),
+            }),
+        })
+    }
+
     pub async fn deploy_background_job(
         &self,
         user_id: &String,
@@ -1202,13 +2304,13 @@ ORDER BY RANDOM() LIMIT 10;
             for ute in all_user_to_entities {
                 let mut new_reasons = HashSet::new();
                 let (entity_id, entity_lot) = if let Some(metadata_id) = ute.metadata_id.clone() {
-                    let (is_finished, seen_history) =
+                    let status =
                         is_metadata_finished_by_user(&ute.user_id, &metadata_id, &self.0.db)
                             .await?;
-                    if !seen_history.is_empty() {
+                    if status.has_seen {
                         new_reasons.insert(UserToMediaReason::Seen);
                     }
-                    if !seen_history.is_empty() && is_finished {
+                    if status.has_seen && status.is_finished {
                         new_reasons.insert(UserToMediaReason::Finished);
                     }
                     (metadata_id, EntityLot::Metadata)
@@ -1282,43 +2384,161 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(())
     }
 
-    pub async fn commit_metadata_group_internal(
+    async fn create_partial_metadata(
+        &self,
+        data: PartialMetadataWithoutId,
+    ) -> Result<PartialMetadata> {
+        create_partial_metadata(data, &self.0.db).await
+    }
+
+    pub async fn editions_for_metadata(&self, metadata_id: String) -> Result<Vec<SeenEdition>> {
+        let metadata = Metadata::find_by_id(metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata with the given ID does not exist"))?;
+        let editions = match metadata.source {
+            MediaSource::Openlibrary => get_openlibrary_service(&self.0.config)
+                .await?
+                .editions(&metadata.identifier)
+                .await
+                .map_err(|e| Error::new(e.to_string()))?,
+            _ => vec![],
+        };
+        Ok(editions)
+    }
+
+    pub async fn mark_episode_skipped(
         &self,
-        identifier: &String,
-        lot: MediaLot,
-        source: MediaSource,
-    ) -> Result<(String, Vec<PartialMetadataWithoutId>)> {
-        let existing_group = MetadataGroup::find()
-            .filter(metadata_group::Column::Identifier.eq(identifier))
-            .filter(metadata_group::Column::Lot.eq(lot))
-            .filter(metadata_group::Column::Source.eq(source))
+        user_id: String,
+        input: MarkEpisodeSkippedInput,
+    ) -> Result<bool> {
+        let metadata = Metadata::find_by_id(&input.metadata_id)
             .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        let season = metadata
+            .show_specifics
+            .and_then(|s| {
+                s.seasons
+                    .into_iter()
+                    .find(|s| s.season_number == input.season)
+            })
+            .ok_or_else(|| Error::new("Season does not exist"))?;
+        let episodes = season
+            .episodes
+            .into_iter()
+            .map(|e| e.episode_number)
+            .filter(|e| input.episode.map_or(true, |i| i == *e))
+            .collect_vec();
+        if episodes.is_empty() {
+            return Err(Error::new("Episode does not exist"));
+        }
+        let already_skipped = Seen::find()
+            .filter(seen::Column::UserId.eq(&user_id))
+            .filter(seen::Column::MetadataId.eq(&input.metadata_id))
+            .filter(seen::Column::State.eq(SeenState::Skipped))
+            .filter(seen::Column::ShowSeasonNumber.eq(input.season))
+            .filter(seen::Column::ShowEpisodeNumber.is_in(episodes.clone()))
+            .all(&self.0.db)
             .await?;
-        let provider = get_metadata_provider(lot, source, &self.0).await?;
-        let (group_details, associated_items) = provider.metadata_group_details(identifier).await?;
-        let group_id = match existing_group {
-            Some(eg) => {
-                let mut eg: metadata_group::ActiveModel = eg.into();
-                eg.is_partial = ActiveValue::Set(Some(false));
-                let eg = eg.update(&self.0.db).await?;
-                eg.id
-            }
-            None => {
-                let mut db_group: metadata_group::ActiveModel =
-                    group_details.into_model("".to_string(), None).into();
-                db_group.id = ActiveValue::NotSet;
-                let new_group = db_group.insert(&self.0.db).await?;
-                new_group.id
+        if !input.is_skipped {
+            Seen::delete_many()
+                .filter(seen::Column::Id.is_in(already_skipped.into_iter().map(|s| s.id)))
+                .exec(&self.0.db)
+                .await?;
+            return Ok(true);
+        }
+        let already_skipped = already_skipped
+            .into_iter()
+            .filter_map(|s| s.show_episode_number)
+            .collect::<HashSet<_>>();
+        let mut last_skipped = None;
+        for episode in episodes {
+            if already_skipped.contains(&episode) {
+                continue;
             }
-        };
-        Ok((group_id, associated_items))
+            let skipped = seen::ActiveModel {
+                progress: ActiveValue::Set(dec!(100)),
+                user_id: ActiveValue::Set(user_id.clone()),
+                state: ActiveValue::Set(SeenState::Skipped),
+                metadata_id: ActiveValue::Set(input.metadata_id.clone()),
+                show_extra_information: ActiveValue::Set(Some(SeenShowExtraInformation {
+                    episode,
+                    season: input.season,
+                })),
+                ..Default::default()
+            };
+            last_skipped = Some(skipped.insert(&self.0.db).await?);
+        }
+        if let Some(seen) = last_skipped {
+            deploy_after_handle_media_seen_tasks(seen, &self.0).await?;
+        }
+        Ok(true)
     }
 
-    async fn create_partial_metadata(
+    pub async fn mark_show_season_seen(
         &self,
-        data: PartialMetadataWithoutId,
-    ) -> Result<PartialMetadata> {
-        create_partial_metadata(data, &self.0.db).await
+        user_id: String,
+        input: MarkShowSeasonSeenInput,
+    ) -> Result<Vec<String>> {
+        let metadata = Metadata::find_by_id(&input.metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        let season = metadata
+            .show_specifics
+            .and_then(|s| {
+                s.seasons
+                    .into_iter()
+                    .find(|s| s.season_number == input.season)
+            })
+            .ok_or_else(|| Error::new("Season does not exist"))?;
+        let already_seen = match input.force {
+            true => HashSet::new(),
+            false => Seen::find()
+                .select_only()
+                .column(seen::Column::ShowEpisodeNumber)
+                .filter(seen::Column::UserId.eq(&user_id))
+                .filter(seen::Column::MetadataId.eq(&input.metadata_id))
+                .filter(seen::Column::State.eq(SeenState::Completed))
+                .filter(seen::Column::Progress.eq(dec!(100)))
+                .filter(seen::Column::ShowSeasonNumber.eq(input.season))
+                .into_tuple::<Option<i32>>()
+                .all(&self.0.db)
+                .await?
+                .into_iter()
+                .flatten()
+                .collect(),
+        };
+        let episodes = unseen_season_episodes(season, &already_seen);
+        let txn = self.0.db.begin().await?;
+        let mut created = vec![];
+        for episode in episodes {
+            let seen = seen::ActiveModel {
+                progress: ActiveValue::Set(dec!(100)),
+                user_id: ActiveValue::Set(user_id.clone()),
+                state: ActiveValue::Set(SeenState::Completed),
+                finished_on: ActiveValue::Set(input.date),
+                metadata_id: ActiveValue::Set(input.metadata_id.clone()),
+                show_extra_information: ActiveValue::Set(Some(SeenShowExtraInformation {
+                    episode,
+                    season: input.season,
+                })),
+                ..Default::default()
+            };
+            created.push(seen.insert(&txn).await?);
+        }
+        txn.commit().await?;
+        for seen in created.iter() {
+            self.0
+                .perform_application_job(ApplicationJob::HandleOnSeenComplete(seen.id.clone()))
+                .await?;
+        }
+        let ids = created.iter().map(|s| s.id.clone()).collect_vec();
+        if let Some(seen) = created.pop() {
+            deploy_after_handle_media_seen_tasks(seen, &self.0).await?;
+        }
+        Ok(ids)
     }
 
     pub async fn update_seen_item(
@@ -1336,19 +2556,28 @@ ORDER BY RANDOM() LIMIT 10;
         if seen.user_id != user_id {
             return Err(Error::new("No seen found for this user and metadata"));
         }
+        let show_extra_information = edited_seen_show_episode(&seen, &input)?;
+        let previous_year = seen.finished_on.map(|d| d.year());
         let mut seen: seen::ActiveModel = seen.into();
+        // DEV: Only the dates change, the progress of the seen item is left as is
         if let Some(started_on) = input.started_on {
             seen.started_on = ActiveValue::Set(Some(started_on));
         }
         if let Some(finished_on) = input.finished_on {
             seen.finished_on = ActiveValue::Set(Some(finished_on));
         }
+        if let Some(show_extra_information) = show_extra_information {
+            seen.show_extra_information = ActiveValue::Set(Some(show_extra_information));
+        }
         if let Some(provider_watched_on) = input.provider_watched_on {
             seen.provider_watched_on = ActiveValue::Set(Some(provider_watched_on));
         }
         if let Some(manual_time_spent) = input.manual_time_spent {
             seen.manual_time_spent = ActiveValue::Set(Some(manual_time_spent));
         }
+        if let Some(edition) = input.edition {
+            seen.edition = ActiveValue::Set(Some(edition));
+        }
         if let Some(review_id) = input.review_id {
             let (review, to_update_review_id) = match review_id.is_empty() {
                 false => (
@@ -1370,6 +2599,13 @@ ORDER BY RANDOM() LIMIT 10;
             seen.review_id = ActiveValue::Set(to_update_review_id);
         }
         let seen = seen.update(&self.0.db).await.unwrap();
+        // DEV: The summary of the new year is refreshed with the rest of the seen tasks
+        if let Some(year) = previous_year.filter(|y| Some(*y) != seen.finished_on.map(|d| d.year()))
+        {
+            refresh_cached_user_yearly_summary(&user_id, year, &self.0)
+                .await
+                .trace_ok();
+        }
         deploy_after_handle_media_seen_tasks(seen, &self.0).await?;
         Ok(true)
     }
@@ -1407,6 +2643,28 @@ ORDER BY RANDOM() LIMIT 10;
         merge_from: String,
         merge_into: String,
     ) -> Result<bool> {
+        if merge_from == merge_into {
+            return Err(Error::new("Can not merge a media item into itself"));
+        }
+        let lots = Metadata::find()
+            .select_only()
+            .column(metadata::Column::Lot)
+            .filter(metadata::Column::Id.is_in([merge_from.clone(), merge_into.clone()]))
+            .into_tuple::<MediaLot>()
+            .all(&self.0.db)
+            .await?;
+        match lots.as_slice() {
+            [first, second] if first == second => {}
+            [_, _] => return Err(Error::new("Can not merge media items of different types")),
+            _ => return Err(Error::new("Metadata does not exist")),
+        }
+        create_safety_snapshot(
+            Some(&user_id),
+            &[merge_from.clone(), merge_into.clone()],
+            format!("Merging {} into {}", merge_from, merge_into),
+            &self.0,
+        )
+        .await?;
         let txn = self.0.db.begin().await?;
         for old_seen in Seen::find()
             .filter(seen::Column::MetadataId.eq(&merge_from))
@@ -1468,27 +2726,35 @@ ORDER BY RANDOM() LIMIT 10;
                 let mut item_active: collection_to_entity::ActiveModel = item.into();
                 item_active.metadata_id = ActiveValue::Set(Some(merge_into.clone()));
                 item_active.update(&txn).await?;
+            } else {
+                item.delete(&txn).await?;
             }
         }
-        if let Some(_association) =
+        MetadataExternalId::update_many()
+            .filter(metadata_external_id::Column::MetadataId.eq(&merge_from))
+            .col_expr(
+                metadata_external_id::Column::MetadataId,
+                Expr::value(merge_into.clone()),
+            )
+            .exec(&txn)
+            .await?;
+        if let Some(association) =
             get_user_to_entity_association(&txn, &user_id, merge_into.clone(), EntityLot::Metadata)
                 .await
         {
-            let old_association = get_user_to_entity_association(
-                &txn,
-                &user_id,
-                merge_from.clone(),
-                EntityLot::Metadata,
-            )
-            .await
-            .unwrap();
-            let mut cloned: user_to_entity::ActiveModel = old_association.clone().into();
-            cloned.needs_to_be_updated = ActiveValue::Set(Some(true));
-            cloned.update(&txn).await?;
+            // DEV: The reasons of the remaining association are recalculated later
+            UserToEntity::delete_many()
+                .filter(user_to_entity::Column::MetadataId.eq(&merge_from))
+                .filter(user_to_entity::Column::UserId.eq(&user_id))
+                .exec(&txn)
+                .await?;
+            let mut association: user_to_entity::ActiveModel = association.into();
+            association.needs_to_be_updated = ActiveValue::Set(Some(true));
+            association.update(&txn).await?;
         } else {
             UserToEntity::update_many()
-                .filter(user_to_entity::Column::MetadataId.eq(merge_from))
-                .filter(user_to_entity::Column::UserId.eq(user_id))
+                .filter(user_to_entity::Column::MetadataId.eq(&merge_from))
+                .filter(user_to_entity::Column::UserId.eq(&user_id))
                 .set(user_to_entity::ActiveModel {
                     metadata_id: ActiveValue::Set(Some(merge_into.clone())),
                     ..Default::default()
@@ -1496,6 +2762,28 @@ ORDER BY RANDOM() LIMIT 10;
                 .exec(&txn)
                 .await?;
         }
+        // DEV: Other users may still track the duplicate, it is only removed once
+        // nobody does
+        let still_used = UserToEntity::find()
+            .filter(user_to_entity::Column::MetadataId.eq(&merge_from))
+            .count(&txn)
+            .await?
+            + Seen::find()
+                .filter(seen::Column::MetadataId.eq(&merge_from))
+                .count(&txn)
+                .await?
+            + Review::find()
+                .filter(review::Column::MetadataId.eq(&merge_from))
+                .count(&txn)
+                .await?
+            + CollectionToEntity::find()
+                .filter(collection_to_entity::Column::MetadataId.eq(&merge_from))
+                .count(&txn)
+                .await?;
+        if still_used == 0 {
+            ryot_log!(debug, "Deleting merged metadata {}", merge_from);
+            Metadata::delete_by_id(&merge_from).exec(&txn).await?;
+        }
         txn.commit().await?;
         Ok(true)
     }
@@ -1504,6 +2792,157 @@ ORDER BY RANDOM() LIMIT 10;
         commit_metadata(input, &self.0).await
     }
 
+    pub async fn bulk_commit_metadata(
+        &self,
+        user_id: String,
+        items: Vec<CommitMediaInput>,
+    ) -> Result<Vec<BulkCommitMetadataResult>> {
+        let user_id = &user_id;
+        let results = stream::iter(items)
+            .map(|input| async move {
+                let identifier = input.identifier.clone();
+                let committed = async {
+                    let metadata = self.commit_metadata(input).await?;
+                    associate_user_with_entity(
+                        &self.0.db,
+                        user_id,
+                        metadata.id.clone(),
+                        EntityLot::Metadata,
+                    )
+                    .await?;
+                    Ok::<_, Error>(metadata.id)
+                }
+                .await;
+                match committed {
+                    Ok(metadata_id) => BulkCommitMetadataResult {
+                        identifier,
+                        metadata_id: Some(metadata_id),
+                        error: None,
+                    },
+                    Err(e) => BulkCommitMetadataResult {
+                        identifier,
+                        metadata_id: None,
+                        error: Some(e.message),
+                    },
+                }
+            })
+            .buffered(BULK_COMMIT_CONCURRENCY)
+            .collect()
+            .await;
+        Ok(results)
+    }
+
+    pub async fn lookup_by_identifier(
+        &self,
+        user_id: String,
+        identifier_type: MetadataIdentifierType,
+        value: String,
+        lot: Option<MediaLot>,
+    ) -> Result<MetadataLookupResult> {
+        let value = value.trim();
+        let lot_matches = |l: MediaLot| lot.map_or(true, |lot| lot == l);
+        let candidates = match identifier_type {
+            MetadataIdentifierType::Isbn => {
+                if !lot_matches(MediaLot::Book) {
+                    return Err(Error::new("An ISBN can only be used to look up books"));
+                }
+                let isbn =
+                    normalize_isbn(value).ok_or_else(|| Error::new("The ISBN is not valid"))?;
+                let identifier = get_isbn_service(&self.0.config)
+                    .await?
+                    .id_from_isbn(&isbn)
+                    .await
+                    .ok_or_else(|| Error::new(format!("No book was found for ISBN {isbn}")))?;
+                vec![(MediaLot::Book, MediaSource::GoogleBooks, identifier)]
+            }
+            MetadataIdentifierType::Imdb => {
+                let is_valid = value.strip_prefix("tt").map_or(false, |n| {
+                    !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())
+                });
+                if !is_valid {
+                    return Err(Error::new("The IMDb id is not valid"));
+                }
+                let existing = metadata_by_external_id(ExternalIdType::Imdb, value, &self.0.db)
+                    .await?
+                    .filter(|m| lot_matches(m.lot));
+                if let Some(m) = existing {
+                    vec![(m.lot, m.source, m.identifier)]
+                } else {
+                    get_tmdb_non_media_service(&self.0)
+                        .await?
+                        .candidates_by_external_id(value, "imdb_id")
+                        .await
+                        .map_err(|e| Error::new(e.to_string()))?
+                        .into_iter()
+                        .filter(|(l, _)| lot_matches(*l))
+                        .map(|(l, identifier)| (l, MediaSource::Tmdb, identifier))
+                        .collect_vec()
+                }
+            }
+            MetadataIdentifierType::Tmdb => {
+                if value.parse::<u64>().is_err() {
+                    return Err(Error::new("The TMDB id is not valid"));
+                }
+                // DEV: Movies and shows share the id space, so only the lot tells them apart
+                [MediaLot::Movie, MediaLot::Show]
+                    .into_iter()
+                    .filter(|l| lot_matches(*l))
+                    .map(|l| (l, MediaSource::Tmdb, value.to_owned()))
+                    .collect_vec()
+            }
+        };
+        let (lot, source, identifier) = match candidates.len() {
+            0 => return Err(Error::new("No media was found for the identifier")),
+            1 => candidates.into_iter().next().unwrap(),
+            _ => {
+                return Ok(MetadataLookupResult {
+                    details: None,
+                    already_in_library: false,
+                    candidates: candidates
+                        .into_iter()
+                        .map(|(lot, source, identifier)| MetadataLookupCandidate {
+                            lot,
+                            source,
+                            identifier,
+                        })
+                        .collect_vec(),
+                });
+            }
+        };
+        let existing = Metadata::find()
+            .filter(metadata::Column::Lot.eq(lot))
+            .filter(metadata::Column::Source.eq(source))
+            .filter(metadata::Column::Identifier.eq(&identifier))
+            .one(&self.0.db)
+            .await?;
+        let already_in_library = match existing {
+            None => false,
+            Some(m) => {
+                UserToEntity::find()
+                    .filter(user_to_entity::Column::UserId.eq(&user_id))
+                    .filter(user_to_entity::Column::MetadataId.eq(m.id))
+                    .count(&self.0.db)
+                    .await?
+                    > 0
+            }
+        };
+        let metadata = commit_metadata(
+            CommitMediaInput {
+                lot,
+                source,
+                identifier,
+                force_update: None,
+            },
+            &self.0,
+        )
+        .await?;
+        Ok(MetadataLookupResult {
+            already_in_library,
+            candidates: vec![],
+            details: Some(self.metadata_details(&metadata.id).await?),
+        })
+    }
+
     pub async fn commit_person(&self, input: CommitPersonInput) -> Result<StringIdObject> {
         commit_person(input, &self.0.db).await
     }
@@ -1513,6 +2952,13 @@ ORDER BY RANDOM() LIMIT 10;
         user_id: String,
         metadata_id: String,
     ) -> Result<bool> {
+        create_safety_snapshot(
+            Some(&user_id),
+            &[metadata_id.clone()],
+            format!("Removing {} from the library", metadata_id),
+            &self.0,
+        )
+        .await?;
         let delete_review = Review::delete_many()
             .filter(review::Column::MetadataId.eq(&metadata_id))
             .filter(review::Column::UserId.eq(&user_id))
@@ -1566,7 +3012,6 @@ ORDER BY RANDOM() LIMIT 10;
                 items: vec![],
             });
         }
-        let cloned_user_id = user_id.to_owned();
         let preferences = user_by_id(user_id, &self.0).await?.preferences;
         let provider = get_metadata_provider(input.lot, input.source, &self.0).await?;
         let results = provider
@@ -1577,49 +3022,12 @@ ORDER BY RANDOM() LIMIT 10;
             .iter()
             .map(|i| i.identifier.to_owned())
             .collect_vec();
-        let interactions = Metadata::find()
-            .join(
-                JoinType::LeftJoin,
-                metadata::Relation::UserToEntity
-                    .def()
-                    .on_condition(move |_left, right| {
-                        Condition::all().add(
-                            Expr::col((right, user_to_entity::Column::UserId))
-                                .eq(cloned_user_id.clone()),
-                        )
-                    }),
-            )
-            .select_only()
-            .column(metadata::Column::Identifier)
-            .column_as(
-                Expr::col((Alias::new("metadata"), metadata::Column::Id)),
-                "database_id",
-            )
-            .column_as(
-                Expr::col((Alias::new("user_to_entity"), user_to_entity::Column::Id)).is_not_null(),
-                "has_interacted",
-            )
-            .filter(metadata::Column::Lot.eq(input.lot))
-            .filter(metadata::Column::Source.eq(input.source))
-            .filter(metadata::Column::Identifier.is_in(&all_identifiers))
-            .into_tuple::<(String, String, bool)>()
-            .all(&self.0.db)
-            .await?
-            .into_iter()
-            .map(|(key, value1, value2)| (key, (value1, value2)));
-        let interactions = HashMap::<_, _>::from_iter(interactions.into_iter());
-        let data = results
-            .items
-            .into_iter()
-            .map(|i| {
-                let interaction = interactions.get(&i.identifier).cloned();
-                MetadataSearchItemResponse {
-                    has_interacted: interaction.clone().unwrap_or_default().1,
-                    database_id: interaction.map(|i| i.0),
-                    item: i,
-                }
-            })
-            .collect();
+        let interactions =
+            metadata_search_interactions(user_id, input.lot, input.source, &all_identifiers)
+                .into_tuple()
+                .all(&self.0.db)
+                .await?;
+        let data = annotate_metadata_search_items(results.items, interactions);
         let results = SearchResults {
             details: results.details,
             items: data,
@@ -1706,7 +3114,11 @@ ORDER BY RANDOM() LIMIT 10;
             MediaSource::Mal => Box::new(NonMediaMalService::new().await),
             MediaSource::Custom => return err(),
         };
-        Ok(service)
+        Ok(Box::new(CircuitBreakingProvider::new(
+            source,
+            &self.0.config.media,
+            service,
+        )))
     }
 
     pub async fn commit_metadata_group(&self, input: CommitMediaInput) -> Result<StringIdObject> {
@@ -1739,6 +3151,36 @@ ORDER BY RANDOM() LIMIT 10;
         post_review(user_id, input, &self.0).await
     }
 
+    pub async fn reviews_for_media(
+        &self,
+        user_id: &String,
+        input: MediaReviewsInput,
+    ) -> Result<SearchResults<ReviewItem>> {
+        let page = input.page.unwrap_or(1);
+        let paginator = visible_media_reviews(user_id, &input.metadata_id)
+            .find_also_related(User)
+            .order_by_desc(review::Column::PostedOn)
+            .order_by_asc(review::Column::Id)
+            .paginate(&self.0.db, PAGE_SIZE as u64);
+        let ItemsAndPagesNumber {
+            number_of_items,
+            number_of_pages,
+        } = paginator.num_items_and_pages().await?;
+        let reviews = paginator.fetch_page(page - 1).await?;
+        let items = review_items(user_id, reviews, &self.0).await?;
+        Ok(SearchResults {
+            details: SearchDetails {
+                total: number_of_items.try_into().unwrap(),
+                next_page: if page < number_of_pages {
+                    Some((page + 1).try_into().unwrap())
+                } else {
+                    None
+                },
+            },
+            items,
+        })
+    }
+
     pub async fn delete_review(&self, user_id: String, review_id: String) -> Result<bool> {
         let review = Review::find()
             .filter(review::Column::Id.eq(review_id))
@@ -1819,77 +3261,232 @@ ORDER BY RANDOM() LIMIT 10;
             .all(&self.0.db)
             .await
             .unwrap();
+        // DEV: Migrations that change how activities are counted ask for a full
+        // recalculation instead of deleting the existing activities
+        let recalculation_key = ApplicationCacheKey::PendingFullSummaryRecalculation;
+        let from_beginning = self
+            .0
+            .cache_service
+            .get(recalculation_key.clone())
+            .await?
+            .is_some();
         for user_id in all_users {
-            calculate_user_activities_and_summary(&self.0.db, &user_id, false).await?;
+            calculate_user_activities_and_summary(&user_id, from_beginning, &self.0).await?;
+        }
+        if from_beginning {
+            self.0.cache_service.delete(recalculation_key).await?;
         }
         Ok(())
     }
 
-    pub async fn create_custom_metadata(
+    // DEV: Metadata is shared by all users, so only the creator of custom media and
+    // admins can change its images
+    async fn metadata_image_guard(
+        &self,
+        user_id: &String,
+        metadata: &metadata::Model,
+    ) -> Result<()> {
+        if metadata.created_by_user_id.as_ref() != Some(user_id) {
+            admin_account_guard(user_id, &self.0).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn add_metadata_image(
         &self,
         user_id: String,
-        input: CreateCustomMetadataInput,
-    ) -> Result<metadata::Model> {
-        let identifier = nanoid!(10);
-        let images = input
-            .images
-            .unwrap_or_default()
-            .into_iter()
-            .map(|i| MetadataImageForMediaDetails { image: i })
-            .collect();
-        let videos = input
-            .videos
-            .unwrap_or_default()
+        input: AddMetadataImageInput,
+    ) -> Result<bool> {
+        let metadata = Metadata::find_by_id(&input.metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        self.metadata_image_guard(&user_id, &metadata).await?;
+        let path = PathBuf::from(&input.file_path)
+            .canonicalize()
+            .map_err(|_| Error::new("Uploaded file does not exist"))?;
+        let temp_dir = PathBuf::from(TEMP_DIR).canonicalize()?;
+        if !path.starts_with(&temp_dir) {
+            return Err(Error::new("Only files uploaded to the server can be used"));
+        }
+        let contents = fs::read(&path)?;
+        let key = metadata_image_key(&contents, path.extension().and_then(|e| e.to_str()));
+        if !self
+            .0
+            .file_storage_service
+            .store_object(&key, contents)
+            .await
+        {
+            return Err(Error::new("Could not store the uploaded image"));
+        }
+        fs::remove_file(&path).ok();
+        ryot_log!(debug, "User {} added an image to {}", user_id, metadata.id);
+        let images = with_uploaded_image(metadata.images.clone().unwrap_or_default(), key);
+        let mut metadata: metadata::ActiveModel = metadata.into();
+        metadata.images = ActiveValue::Set(Some(images));
+        metadata.update(&self.0.db).await?;
+        Ok(true)
+    }
+
+    /// Whether a stored image is still used by any media item.
+    async fn is_image_key_referenced(&self, key: &str) -> Result<bool> {
+        // DEV: The text search only narrows down the candidates, one key can be a part
+        // of another so the decoded images are compared
+        let candidates = Metadata::find()
+            .select_only()
+            .column(metadata::Column::Images)
+            .filter(
+                Expr::expr(Func::cast_as(
+                    Expr::col(metadata::Column::Images),
+                    Alias::new("text"),
+                ))
+                .like(format!("%{}%", key)),
+            )
+            .into_tuple::<Option<Vec<MetadataImage>>>()
+            .all(&self.0.db)
+            .await?;
+        Ok(candidates
             .into_iter()
-            .map(|i| MetadataVideo {
-                identifier: StoredUrl::S3(i),
-                source: MetadataVideoSource::Custom,
-            })
-            .collect();
-        let creators = input
-            .creators
+            .any(|images| images_reference_key(&images.unwrap_or_default(), key)))
+    }
+
+    pub async fn delete_metadata_image(
+        &self,
+        user_id: String,
+        input: DeleteMetadataImageInput,
+    ) -> Result<bool> {
+        let metadata = Metadata::find_by_id(&input.metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        self.metadata_image_guard(&user_id, &metadata).await?;
+        let url = StoredUrl::S3(input.key.clone());
+        let mut images = metadata.images.clone().unwrap_or_default();
+        let total = images.len();
+        images.retain(|i| !(i.is_user_provided && i.url == url));
+        if images.len() == total {
+            return Err(Error::new("Image was not uploaded by a user"));
+        }
+        ryot_log!(
+            debug,
+            "User {} removed an image from {}",
+            user_id,
+            metadata.id
+        );
+        let mut metadata: metadata::ActiveModel = metadata.into();
+        metadata.images = ActiveValue::Set(Some(images));
+        metadata.update(&self.0.db).await?;
+        if !self.is_image_key_referenced(&input.key).await? {
+            self.0
+                .file_storage_service
+                .delete_stored_object(input.key)
+                .await;
+        }
+        Ok(true)
+    }
+
+    pub async fn delete_metadata(
+        &self,
+        user_id: String,
+        metadata_id: String,
+        force: Option<bool>,
+    ) -> Result<bool> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let metadata = Metadata::find_by_id(&metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        let users = other_users_with_metadata_activity(&user_id, &metadata_id, &self.0.db).await?;
+        if !users.is_empty() && !force.unwrap_or_default() {
+            return Err(Error::new(format!(
+                "{} other users have activity on this media, set force to delete it anyway",
+                users.len()
+            )));
+        }
+        let uploaded_images = metadata
+            .images
+            .clone()
             .unwrap_or_default()
             .into_iter()
-            .map(|c| MetadataFreeCreator {
-                name: c,
-                role: "Creator".to_string(),
-                image: None,
+            .filter_map(|i| match i.url {
+                StoredUrl::S3(key) => Some(key),
+                StoredUrl::Url(_) => None,
             })
-            .collect();
-        let is_partial = match input.lot {
-            MediaLot::Anime => input.anime_specifics.is_none(),
-            MediaLot::AudioBook => input.audio_book_specifics.is_none(),
-            MediaLot::Book => input.book_specifics.is_none(),
-            MediaLot::Manga => input.manga_specifics.is_none(),
-            MediaLot::Movie => input.movie_specifics.is_none(),
-            MediaLot::Podcast => input.podcast_specifics.is_none(),
-            MediaLot::Show => input.show_specifics.is_none(),
-            MediaLot::VideoGame => input.video_game_specifics.is_none(),
-            MediaLot::VisualNovel => input.visual_novel_specifics.is_none(),
-        };
-        let details = MetadataDetails {
-            identifier,
-            title: input.title,
-            description: input.description,
-            lot: input.lot,
-            source: MediaSource::Custom,
-            creators,
-            genres: input.genres.unwrap_or_default(),
-            s3_images: images,
-            videos,
-            publish_year: input.publish_year,
-            anime_specifics: input.anime_specifics,
-            audio_book_specifics: input.audio_book_specifics,
-            book_specifics: input.book_specifics,
-            manga_specifics: input.manga_specifics,
-            movie_specifics: input.movie_specifics,
-            podcast_specifics: input.podcast_specifics,
-            show_specifics: input.show_specifics,
-            video_game_specifics: input.video_game_specifics,
-            visual_novel_specifics: input.visual_novel_specifics,
-            ..Default::default()
-        };
+            .collect_vec();
+        create_safety_snapshot(
+            None,
+            &[metadata_id.clone()],
+            format!("Deleting {} ({})", metadata.id, metadata.title),
+            &self.0,
+        )
+        .await?;
+        // DEV: Seen items, reviews, collection entries, user associations and the
+        // creator and genre associations are removed by the foreign keys
+        let txn = self.0.db.begin().await?;
+        Metadata::delete_by_id(&metadata_id).exec(&txn).await?;
+        txn.commit().await?;
+        ryot_log!(
+            debug,
+            "User {} deleted metadata {} ({})",
+            user_id,
+            metadata.id,
+            metadata.title
+        );
+        for key in uploaded_images {
+            if !self.is_image_key_referenced(&key).await? {
+                self.0.file_storage_service.delete_stored_object(key).await;
+            }
+        }
+        Ok(true)
+    }
+
+    pub async fn swap_metadata_source(
+        &self,
+        user_id: String,
+        input: SwapMetadataSourceInput,
+    ) -> Result<Vec<MetadataFieldChange>> {
+        admin_account_guard(&user_id, &self.0).await?;
+        swap_metadata_source(input, &self.0).await
+    }
+
+    pub async fn update_metadata_sort_title(
+        &self,
+        user_id: String,
+        input: UpdateMetadataSortTitleInput,
+    ) -> Result<bool> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let metadata = Metadata::find_by_id(&input.metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        let sort_title_override = input
+            .sort_title
+            .map(|s| s.trim().to_owned())
+            .filter(|s| !s.is_empty());
+        // DEV: The romanized title is not stored, the next refresh takes it into account
+        let sort_title = metadata_sort_title(
+            &metadata.title,
+            None,
+            sort_title_override.as_deref(),
+            &self.0,
+        );
+        let mut metadata: metadata::ActiveModel = metadata.into();
+        metadata.sort_title = ActiveValue::Set(Some(sort_title));
+        metadata.sort_title_override = ActiveValue::Set(sort_title_override);
+        metadata.update(&self.0.db).await?;
+        Ok(true)
+    }
+
+    pub async fn create_custom_metadata(
+        &self,
+        user_id: String,
+        input: CreateCustomMetadataInput,
+    ) -> Result<metadata::Model> {
+        let (details, is_partial) = custom_metadata_details(nanoid!(10), input);
         let media = commit_metadata_internal(details, Some(is_partial), &self.0).await?;
+        let mut media: metadata::ActiveModel = media.into();
+        media.created_by_user_id = ActiveValue::Set(Some(user_id.clone()));
+        let media = media.update(&self.0.db).await?;
         add_entity_to_collection(
             &user_id,
             ChangeCollectionToEntityInput {
@@ -1905,6 +3502,32 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(media)
     }
 
+    pub async fn update_custom_metadata(
+        &self,
+        user_id: String,
+        input: UpdateCustomMetadataInput,
+    ) -> Result<bool> {
+        let metadata = Metadata::find_by_id(&input.existing_metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        if metadata.source != MediaSource::Custom {
+            return Err(Error::new("Only custom media can be edited"));
+        }
+        if metadata.created_by_user_id.as_ref() != Some(&user_id) {
+            return Err(Error::new(
+                "Only the user who created this media can edit it",
+            ));
+        }
+        if metadata.lot != input.update.lot {
+            return Err(Error::new("The type of a media item can not be changed"));
+        }
+        let (details, is_partial) =
+            custom_metadata_details(metadata.identifier.clone(), input.update);
+        update_custom_metadata_internal(metadata, details, is_partial, &self.0).await?;
+        Ok(true)
+    }
+
     fn get_db_stmt(&self, stmt: SelectStatement) -> Statement {
         let (sql, values) = stmt.build(PostgresQueryBuilder {});
         Statement::from_sql_and_values(DatabaseBackend::Postgres, sql, values)
@@ -1972,39 +3595,252 @@ ORDER BY RANDOM() LIMIT 10;
             .collect()
     }
 
-    async fn get_monitored_entities(
-        &self,
-        entity_lot: EntityLot,
-    ) -> Result<HashMap<String, HashSet<String>>> {
-        let monitored_entities = MonitoredEntity::find()
-            .filter(monitored_entity::Column::EntityLot.eq(entity_lot))
+    pub async fn safety_snapshots(&self, user_id: String) -> Result<Vec<safety_snapshot::Model>> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let snapshots = SafetySnapshot::find()
+            .order_by_desc(safety_snapshot::Column::CreatedOn)
             .all(&self.0.db)
             .await?;
-        let mut monitored_by = HashMap::new();
-        for entity in monitored_entities {
-            let user_ids = monitored_by
-                .entry(entity.entity_id)
-                .or_insert(HashSet::new());
-            user_ids.insert(entity.user_id);
-        }
-        Ok(monitored_by)
+        Ok(snapshots)
     }
 
-    async fn update_monitored_metadata_and_queue_notifications(&self) -> Result<()> {
-        let meta_map = self.get_monitored_entities(EntityLot::Metadata).await?;
-        ryot_log!(
-            debug,
-            "Users to be notified for metadata state changes: {:?}",
-            meta_map
-        );
-        for (metadata_id, _) in meta_map {
-            self.update_metadata_and_notify_users(&metadata_id, true)
-                .await
+    pub async fn restore_safety_snapshot(
+        &self,
+        user_id: String,
+        snapshot_id: String,
+    ) -> Result<bool> {
+        admin_account_guard(&user_id, &self.0).await?;
+        restore_safety_snapshot(snapshot_id, &self.0).await
+    }
+
+    pub async fn next_scheduled_runs(&self, user_id: String) -> Result<Vec<ScheduledJobRuns>> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let runs = ScheduledJobKind::iter()
+            .map(|job| {
+                let schedule = job.schedule(&self.0.config).map_err(Error::new)?;
+                Ok(ScheduledJobRuns {
+                    job: job.to_string(),
+                    cron_expression: job.cron_expression(&self.0.config),
+                    next_runs: next_scheduled_runs(&schedule, &self.0.timezone, 5),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    pub async fn admin_dashboard(&self, user_id: String, refresh: bool) -> Result<AdminDashboard> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let key = ApplicationCacheKey::AdminDashboardStatistics;
+        let cached = match refresh {
+            true => None,
+            false => self.0.cache_service.get_value(key.clone()).await?,
+        };
+        let statistics = match cached {
+            Some(statistics) => statistics,
+            None => {
+                let statistics = admin_dashboard_statistics(&self.0.db, Utc::now()).await?;
+                self.0
+                    .cache_service
+                    .set_value_with_expiry(key, &statistics, 1)
+                    .await?;
+                statistics
+            }
+        };
+        let (jobs_last_week, failed_jobs_last_week) =
+            job_outcomes_since(Utc::now() - Duration::days(7));
+        let (delivered_notifications, failed_notification_attempts) =
+            notification_delivery_counts();
+        let queued_notification_deliveries = NotificationDelivery::find()
+            .filter(notification_delivery::Column::Status.eq(NotificationDeliveryStatus::Queued))
+            .count(&self.0.db)
+            .await?;
+        Ok(AdminDashboard {
+            statistics,
+            jobs_last_week,
+            failed_jobs_last_week,
+            delivered_notifications,
+            failed_notification_attempts,
+            queued_notification_deliveries,
+            provider_statuses: provider_statuses(),
+        })
+    }
+
+    pub async fn provider_status(&self, user_id: String) -> Result<Vec<ProviderStatus>> {
+        admin_account_guard(&user_id, &self.0).await?;
+        Ok(provider_statuses())
+    }
+
+    pub async fn create_or_update_announcement(
+        &self,
+        user_id: String,
+        input: CreateOrUpdateAnnouncementInput,
+    ) -> Result<StringIdObject> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let starts_at = input.starts_at.unwrap_or_else(Utc::now);
+        if input.ends_at.map_or(false, |e| e <= starts_at) {
+            return Err(Error::new("The announcement must end after it starts"));
+        }
+        let mut to_save = match input.update_id {
+            Some(id) => Announcement::find_by_id(id)
+                .one(&self.0.db)
+                .await?
+                .ok_or_else(|| Error::new("Announcement does not exist"))?
+                .into(),
+            None => announcement::ActiveModel {
+                ..Default::default()
+            },
+        };
+        to_save.message = ActiveValue::Set(input.message);
+        to_save.severity = ActiveValue::Set(input.severity);
+        to_save.starts_at = ActiveValue::Set(starts_at);
+        to_save.ends_at = ActiveValue::Set(input.ends_at);
+        to_save.is_dismissible = ActiveValue::Set(input.is_dismissible);
+        let announcement = to_save.save(&self.0.db).await?;
+        Ok(StringIdObject {
+            id: announcement.id.unwrap(),
+        })
+    }
+
+    pub async fn delete_announcement(
+        &self,
+        user_id: String,
+        announcement_id: String,
+    ) -> Result<bool> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let deleted = Announcement::delete_by_id(announcement_id)
+            .exec(&self.0.db)
+            .await?;
+        Ok(deleted.rows_affected > 0)
+    }
+
+    pub async fn active_announcements(&self, user_id: String) -> Result<Vec<announcement::Model>> {
+        let announcements = active_announcements_query(&user_id, Utc::now())
+            .all(&self.0.db)
+            .await?;
+        Ok(announcements)
+    }
+
+    pub async fn dismiss_announcement(
+        &self,
+        user_id: String,
+        announcement_id: String,
+    ) -> Result<bool> {
+        let announcement = Announcement::find_by_id(announcement_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Announcement does not exist"))?;
+        if !announcement.is_dismissible {
+            return Err(Error::new("This announcement can not be dismissed"));
+        }
+        dismiss_announcement_query(&announcement.id, &user_id)
+            .exec(&self.0.db)
+            .await?;
+        Ok(true)
+    }
+
+    async fn get_monitored_entities(
+        &self,
+        entity_lot: EntityLot,
+    ) -> Result<HashMap<String, HashSet<String>>> {
+        let monitored_entities = MonitoredEntity::find()
+            .filter(monitored_entity::Column::EntityLot.eq(entity_lot))
+            .all(&self.0.db)
+            .await?;
+        let mut monitored_by = HashMap::new();
+        for entity in monitored_entities {
+            let user_ids = monitored_by
+                .entry(entity.entity_id)
+                .or_insert(HashSet::new());
+            user_ids.insert(entity.user_id);
+        }
+        Ok(monitored_by)
+    }
+
+    async fn update_monitored_metadata_and_queue_notifications(&self) -> Result<()> {
+        let meta_map = self.get_monitored_entities(EntityLot::Metadata).await?;
+        ryot_log!(
+            debug,
+            "Users to be notified for metadata state changes: {:?}",
+            meta_map
+        );
+        for (metadata_id, _) in meta_map {
+            self.update_metadata_and_notify_users(&metadata_id, true)
+                .await
                 .ok();
         }
         Ok(())
     }
 
+    /// Refresh the shows and podcasts that users are watching or want to watch so
+    /// that new episodes show up. Providers are called in parallel, but the items
+    /// of a single provider one after the other to stay within its rate limits.
+    pub async fn refresh_in_progress_media(&self) -> Result<()> {
+        let mut metadata_ids = HashSet::new();
+        metadata_ids.extend(
+            Seen::find()
+                .select_only()
+                .column(seen::Column::MetadataId)
+                .filter(seen::Column::State.eq(SeenState::InProgress))
+                .distinct()
+                .into_tuple::<String>()
+                .all(&self.0.db)
+                .await?,
+        );
+        metadata_ids.extend(
+            CollectionToEntity::find()
+                .select_only()
+                .column(collection_to_entity::Column::MetadataId)
+                .inner_join(Collection)
+                .filter(collection::Column::Name.is_in([
+                    DefaultCollection::Watchlist.to_string(),
+                    DefaultCollection::InProgress.to_string(),
+                ]))
+                .filter(collection_to_entity::Column::MetadataId.is_not_null())
+                .distinct()
+                .into_tuple::<String>()
+                .all(&self.0.db)
+                .await?,
+        );
+        let refreshed_before =
+            Utc::now() - Duration::hours(self.0.config.media.in_progress_refresh_after_hours);
+        let to_refresh = Metadata::find()
+            .select_only()
+            .column(metadata::Column::Source)
+            .column(metadata::Column::Id)
+            .filter(metadata::Column::Id.is_in(metadata_ids))
+            .filter(metadata::Column::Lot.is_in([MediaLot::Show, MediaLot::Podcast]))
+            .filter(metadata::Column::Source.ne(MediaSource::Custom))
+            .filter(
+                Condition::any()
+                    .add(metadata::Column::LastRefreshedOn.is_null())
+                    .add(metadata::Column::LastRefreshedOn.lt(refreshed_before)),
+            )
+            .into_tuple::<(MediaSource, String)>()
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .into_group_map();
+        let delay = TokioDuration::from_secs(self.0.config.media.in_progress_refresh_delay_seconds);
+        join_all(to_refresh.into_iter().map(|(source, ids)| async move {
+            ryot_log!(
+                debug,
+                "Refreshing {} in progress items from {:?}",
+                ids.len(),
+                source
+            );
+            for (idx, metadata_id) in ids.iter().enumerate() {
+                if idx > 0 {
+                    sleep(delay).await;
+                }
+                self.update_metadata_and_notify_users(metadata_id, true)
+                    .await
+                    .trace_ok();
+            }
+        }))
+        .await;
+        Ok(())
+    }
+
     async fn update_monitored_people_and_queue_notifications(&self) -> Result<()> {
         let person_map = self.get_monitored_entities(EntityLot::Person).await?;
         ryot_log!(
@@ -2020,7 +3856,11 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(())
     }
 
-    pub async fn genres_list(&self, input: SearchInput) -> Result<SearchResults<GenreListItem>> {
+    pub async fn genres_list(
+        &self,
+        user_id: String,
+        input: SearchInput,
+    ) -> Result<SearchResults<GenreListItem>> {
         let page: u64 = input.page.unwrap_or(1).try_into().unwrap();
         let num_items = "num_items";
         let query = Genre::find()
@@ -2037,6 +3877,13 @@ ORDER BY RANDOM() LIMIT 10;
                 )
             })
             .join(JoinType::Join, genre::Relation::MetadataToGenre.def())
+            .filter(
+                Expr::col((
+                    AliasedMetadataToGenre::Table,
+                    AliasedMetadataToGenre::MetadataId,
+                ))
+                .in_subquery(user_metadata_ids_query(&user_id)),
+            )
             .group_by(Expr::tuple([
                 Expr::col(genre::Column::Id).into(),
                 Expr::col(genre::Column::Name).into(),
@@ -2141,6 +3988,37 @@ ORDER BY RANDOM() LIMIT 10;
         })
     }
 
+    /// Every group that is known to the instance, whether the user has interacted
+    /// with it or not.
+    pub async fn all_metadata_groups(&self, input: SearchInput) -> Result<SearchResults<String>> {
+        let page: u64 = input.page.unwrap_or(1).try_into().unwrap();
+        let paginator = MetadataGroup::find()
+            .select_only()
+            .column(metadata_group::Column::Id)
+            .apply_if(input.query, |query, v| {
+                query.filter(Expr::col(metadata_group::Column::Title).ilike(ilike_sql(&v)))
+            })
+            .order_by_asc(metadata_group::Column::Title)
+            .into_tuple::<String>()
+            .paginate(&self.0.db, PAGE_SIZE.try_into().unwrap());
+        let ItemsAndPagesNumber {
+            number_of_items,
+            number_of_pages,
+        } = paginator.num_items_and_pages().await?;
+        let items = paginator.fetch_page(page - 1).await?;
+        Ok(SearchResults {
+            details: SearchDetails {
+                total: number_of_items.try_into().unwrap(),
+                next_page: if page < number_of_pages {
+                    Some((page + 1).try_into().unwrap())
+                } else {
+                    None
+                },
+            },
+            items,
+        })
+    }
+
     pub async fn people_list(
         &self,
         user_id: String,
@@ -2254,28 +4132,7 @@ ORDER BY RANDOM() LIMIT 10;
             })
             .sorted_by_key(|f| Reverse(f.count))
             .collect_vec();
-        let slug = slug::slugify(&details.name);
-        let identifier = &details.identifier;
-        let source_url = match details.source {
-            MediaSource::Custom
-            | MediaSource::Anilist
-            | MediaSource::Listennotes
-            | MediaSource::Itunes
-            | MediaSource::MangaUpdates
-            | MediaSource::Mal
-            | MediaSource::Vndb
-            | MediaSource::GoogleBooks => None,
-            MediaSource::Audible => Some(format!(
-                "https://www.audible.com/author/{slug}/{identifier}"
-            )),
-            MediaSource::Openlibrary => Some(format!(
-                "https://openlibrary.org/authors/{identifier}/{slug}"
-            )),
-            MediaSource::Tmdb => Some(format!(
-                "https://www.themoviedb.org/person/{identifier}-{slug}"
-            )),
-            MediaSource::Igdb => Some(format!("https://www.igdb.com/companies/{slug}")),
-        };
+        let source_url = person_source_url(details.source, &details.identifier, &details.name);
         Ok(PersonDetails {
             details,
             contents,
@@ -2283,14 +4140,27 @@ ORDER BY RANDOM() LIMIT 10;
         })
     }
 
-    pub async fn genre_details(&self, input: GenreDetailsInput) -> Result<GenreDetails> {
+    pub async fn person_works(&self, input: PersonWorksInput) -> Result<PersonWorks> {
+        person_works(input, &self.0.db).await
+    }
+
+    pub async fn genre_details(
+        &self,
+        user_id: String,
+        input: GenreDetailsInput,
+    ) -> Result<GenreDetails> {
         let page = input.page.unwrap_or(1);
         let genre = Genre::find_by_id(input.genre_id.clone())
             .one(&self.0.db)
             .await?
-            .unwrap();
+            .ok_or_else(|| Error::new("Genre does not exist"))?;
         let paginator = MetadataToGenre::find()
             .filter(metadata_to_genre::Column::GenreId.eq(input.genre_id))
+            .filter(
+                metadata_to_genre::Column::MetadataId
+                    .in_subquery(user_metadata_ids_query(&user_id)),
+            )
+            .order_by_asc(metadata_to_genre::Column::MetadataId)
             .paginate(&self.0.db, PAGE_SIZE as u64);
         let ItemsAndPagesNumber {
             number_of_items,
@@ -2320,6 +4190,221 @@ ORDER BY RANDOM() LIMIT 10;
         })
     }
 
+    async fn metadata_tags(&self, metadata_id: &String) -> Result<Vec<tag::Model>> {
+        let tags = Tag::find()
+            .inner_join(MetadataToTag)
+            .filter(metadata_to_tag::Column::MetadataId.eq(metadata_id))
+            .filter(tag::Column::IsHidden.eq(false))
+            .order_by_asc(tag::Column::Name)
+            .all(&self.0.db)
+            .await?;
+        Ok(tags)
+    }
+
+    pub async fn media_by_tag(&self, input: MediaByTagInput) -> Result<SearchResults<String>> {
+        let page = input.page.unwrap_or(1);
+        let paginator = MetadataToTag::find()
+            .inner_join(Tag)
+            .filter(tag::Column::Key.eq(label_key(&input.tag)))
+            .filter(tag::Column::IsHidden.eq(false))
+            .order_by_desc(metadata_to_tag::Column::CreatedOn)
+            .paginate(&self.0.db, PAGE_SIZE as u64);
+        let ItemsAndPagesNumber {
+            number_of_items,
+            number_of_pages,
+        } = paginator.num_items_and_pages().await?;
+        let mut items = vec![];
+        for association in paginator.fetch_page(page - 1).await? {
+            items.push(association.metadata_id);
+        }
+        Ok(SearchResults {
+            details: SearchDetails {
+                total: number_of_items.try_into().unwrap(),
+                next_page: if page < number_of_pages {
+                    Some((page + 1).try_into().unwrap())
+                } else {
+                    None
+                },
+            },
+            items,
+        })
+    }
+
+    pub async fn add_metadata_tag(
+        &self,
+        user_id: &String,
+        input: MetadataTagInput,
+    ) -> Result<bool> {
+        let added =
+            associate_tag_with_metadata(&input.tag, &input.metadata_id, Some(user_id), &self.0.db)
+                .await?;
+        if added.is_none() {
+            return Err(Error::new("This tag has been removed by an admin"));
+        }
+        Ok(true)
+    }
+
+    pub async fn remove_metadata_tag(
+        &self,
+        user_id: &String,
+        input: MetadataTagInput,
+    ) -> Result<bool> {
+        let Some(association) = MetadataToTag::find()
+            .inner_join(Tag)
+            .filter(metadata_to_tag::Column::MetadataId.eq(&input.metadata_id))
+            .filter(tag::Column::Key.eq(label_key(&input.tag)))
+            .one(&self.0.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        // DEV: Users can take back their own tags, everything else needs an admin
+        if association.user_id.as_ref() != Some(user_id) {
+            admin_account_guard(user_id, &self.0).await?;
+        }
+        association.delete(&self.0.db).await?;
+        Ok(true)
+    }
+
+    /// Recompute the keys of all tags when a migration asks for it. Tags that end up
+    /// with the same key are merged into the oldest one.
+    async fn recalculate_tag_keys(&self) -> Result<()> {
+        let pending_key = ApplicationCacheKey::PendingTagKeyRecalculation;
+        if self
+            .0
+            .cache_service
+            .get(pending_key.clone())
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+        let groups = Tag::find()
+            .order_by_asc(tag::Column::CreatedOn)
+            .all(&self.0.db)
+            .await?
+            .into_iter()
+            .into_group_map_by(|t| label_key(&t.name));
+        let txn = self.0.db.begin().await?;
+        // DEV: Every tag gets a key that can not clash first, so that a tag can take
+        // the key that another one is about to give up
+        Tag::update_many()
+            .col_expr(tag::Column::Key, Expr::col(tag::Column::Id).into())
+            .exec(&txn)
+            .await?;
+        for (key, mut tags) in groups {
+            let kept = tags.remove(0);
+            let is_hidden = tags.iter().any(|t| t.is_hidden) || kept.is_hidden;
+            for duplicate in tags {
+                let already_tagged = MetadataToTag::find()
+                    .select_only()
+                    .column(metadata_to_tag::Column::MetadataId)
+                    .filter(metadata_to_tag::Column::TagId.eq(&kept.id))
+                    .into_query();
+                MetadataToTag::update_many()
+                    .col_expr(metadata_to_tag::Column::TagId, Expr::value(kept.id.clone()))
+                    .filter(metadata_to_tag::Column::TagId.eq(&duplicate.id))
+                    .filter(metadata_to_tag::Column::MetadataId.not_in_subquery(already_tagged))
+                    .exec(&txn)
+                    .await?;
+                duplicate.delete(&txn).await?;
+            }
+            let mut kept: tag::ActiveModel = kept.into();
+            kept.key = ActiveValue::Set(key);
+            kept.is_hidden = ActiveValue::Set(is_hidden);
+            kept.update(&txn).await?;
+        }
+        txn.commit().await?;
+        self.0.cache_service.delete(pending_key).await?;
+        Ok(())
+    }
+
+    pub async fn update_tag_visibility(
+        &self,
+        user_id: &String,
+        tag_id: String,
+        is_hidden: bool,
+    ) -> Result<bool> {
+        admin_account_guard(user_id, &self.0).await?;
+        let Some(tag) = Tag::find_by_id(tag_id).one(&self.0.db).await? else {
+            return Err(Error::new("The tag does not exist"));
+        };
+        let mut tag: tag::ActiveModel = tag.into();
+        tag.is_hidden = ActiveValue::Set(is_hidden);
+        tag.update(&self.0.db).await?;
+        Ok(true)
+    }
+
+    pub async fn instance_popular(
+        &self,
+        user_id: String,
+        input: InstancePopularInput,
+    ) -> Result<Vec<InstancePopularItem>> {
+        let include_nsfw = user_by_id(&user_id, &self.0)
+            .await?
+            .preferences
+            .general
+            .display_nsfw;
+        let users_count = User::find().count(&self.0.db).await?;
+        let ranking: Vec<(String, i64)> = if users_count <= 1 {
+            // DEV: There is nobody to compare with, so show the user's own recent activity
+            Seen::find()
+                .select_only()
+                .column(seen::Column::MetadataId)
+                .column_as(Expr::val(1_i64), "users_count")
+                .inner_join(Metadata)
+                .filter(seen::Column::UserId.eq(&user_id))
+                .filter(metadata::Column::Lot.eq(input.lot))
+                .group_by(seen::Column::MetadataId)
+                .order_by_desc(Expr::col((seen::Entity, seen::Column::LastUpdatedOn)).max())
+                .order_by_desc(seen::Column::MetadataId)
+                .limit(PAGE_SIZE as u64)
+                .into_tuple()
+                .all(&self.0.db)
+                .await?
+        } else {
+            let key = ApplicationCacheKey::InstancePopular {
+                lot: input.lot,
+                window: input.window,
+                include_nsfw,
+            };
+            match self.0.cache_service.get_value(key.clone()).await? {
+                Some(cached) => cached,
+                None => {
+                    let ranking: Vec<(String, i64)> =
+                        instance_popular_query(input.lot, input.window, include_nsfw, Utc::now())
+                            .into_tuple()
+                            .all(&self.0.db)
+                            .await?;
+                    self.0
+                        .cache_service
+                        .set_value_with_expiry(key, &ranking, 1)
+                        .await?;
+                    ranking
+                }
+            }
+        };
+        let mut items = vec![];
+        for (metadata_id, users_count) in ranking {
+            let Some(metadata) = Metadata::find_by_id(&metadata_id).one(&self.0.db).await? else {
+                continue;
+            };
+            let image =
+                first_metadata_image_as_url(&metadata.images, &self.0.file_storage_service).await;
+            items.push(InstancePopularItem {
+                users_count,
+                database_id: metadata_id,
+                item: MetadataSearchItem {
+                    image,
+                    title: metadata.title,
+                    identifier: metadata.identifier,
+                    publish_year: metadata.publish_year,
+                },
+            });
+        }
+        Ok(items)
+    }
+
     pub async fn metadata_group_details(
         &self,
         metadata_group_id: String,
@@ -2338,27 +4423,7 @@ ORDER BY RANDOM() LIMIT 10;
             );
         }
         group.display_images = images;
-        let slug = slug::slugify(&group.title);
-        let identifier = &group.identifier;
-
-        let source_url = match group.source {
-            MediaSource::Custom
-            | MediaSource::Anilist
-            | MediaSource::Listennotes
-            | MediaSource::Itunes
-            | MediaSource::MangaUpdates
-            | MediaSource::Mal
-            | MediaSource::Openlibrary
-            | MediaSource::Vndb
-            | MediaSource::GoogleBooks => None,
-            MediaSource::Audible => Some(format!(
-                "https://www.audible.com/series/{slug}/{identifier}"
-            )),
-            MediaSource::Tmdb => Some(format!(
-                "https://www.themoviedb.org/collections/{identifier}-{slug}"
-            )),
-            MediaSource::Igdb => Some(format!("https://www.igdb.com/collection/{slug}")),
-        };
+        let source_url = metadata_group_source_url(group.source, &group.identifier, &group.title);
 
         let contents = MetadataToMetadataGroup::find()
             .select_only()
@@ -2398,11 +4463,10 @@ ORDER BY RANDOM() LIMIT 10;
                         queue_notifications_to_user_platforms(
                             &user.user_id,
                             &reminder.text,
-                            &self.0.db,
+                            &self.0,
                         )
                         .await?;
                         remove_entity_from_collection(
-                            &self.0.db,
                             &user.user_id,
                             ChangeCollectionToEntityInput {
                                 creator_user_id: col.user_id.clone(),
@@ -2411,6 +4475,7 @@ ORDER BY RANDOM() LIMIT 10;
                                 entity_lot: cte.entity_lot,
                                 ..Default::default()
                             },
+                            &self.0,
                         )
                         .await?;
                     }
@@ -2420,53 +4485,210 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(())
     }
 
-    pub async fn create_review_comment(
+    /// The review that comments are read from or written to. Private reviews are
+    /// only visible to their author, so nobody else can comment on them.
+    async fn commentable_review(
         &self,
-        user_id: String,
-        input: CreateReviewCommentInput,
-    ) -> Result<bool> {
-        let review = Review::find_by_id(input.review_id)
+        user_id: &String,
+        review_id: &String,
+    ) -> Result<review::Model> {
+        let review = Review::find_by_id(review_id)
             .one(&self.0.db)
             .await?
-            .unwrap();
-        let mut comments = review.comments.clone();
-        if input.should_delete.unwrap_or_default() {
-            let position = comments
-                .iter()
-                .position(|r| &r.id == input.comment_id.as_ref().unwrap())
-                .unwrap();
-            comments.remove(position);
-        } else if input.increment_likes.unwrap_or_default() {
-            let comment = comments
-                .iter_mut()
-                .find(|r| &r.id == input.comment_id.as_ref().unwrap())
-                .unwrap();
-            comment.liked_by.insert(user_id.clone());
-        } else if input.decrement_likes.unwrap_or_default() {
-            let comment = comments
-                .iter_mut()
-                .find(|r| &r.id == input.comment_id.as_ref().unwrap())
-                .unwrap();
-            comment.liked_by.remove(&user_id);
-        } else {
-            let user = user_by_id(&user_id, &self.0).await?;
-            comments.push(ImportOrExportItemReviewComment {
-                id: nanoid!(20),
-                text: input.text.unwrap(),
-                user: IdAndNamedObject {
-                    id: user_id,
-                    name: user.name,
-                },
-                liked_by: HashSet::new(),
-                created_on: Utc::now(),
-            });
+            .ok_or_else(|| Error::new("Review does not exist"))?;
+        if !can_comment_on_review(&review, user_id) {
+            return Err(Error::new(
+                "Only the author can comment on a private review",
+            ));
         }
-        let mut review: review::ActiveModel = review.into();
-        review.comments = ActiveValue::Set(comments);
-        review.update(&self.0.db).await?;
+        Ok(review)
+    }
+
+    async fn save_review_comments(
+        &self,
+        review: review::Model,
+        comments: Vec<ImportOrExportItemReviewComment>,
+    ) -> Result<()> {
+        let mut review: review::ActiveModel = review.into();
+        review.comments = ActiveValue::Set(comments);
+        review.update(&self.0.db).await?;
+        Ok(())
+    }
+
+    pub async fn post_review_comment(
+        &self,
+        user_id: String,
+        input: PostReviewCommentInput,
+    ) -> Result<StringIdObject> {
+        let review = self.commentable_review(&user_id, &input.review_id).await?;
+        let mut comments = review.comments.clone();
+        if let Some(parent_id) = &input.parent_comment_id {
+            let parent = comments
+                .iter()
+                .find(|c| c.deleted_on.is_none() && &c.id == parent_id)
+                .ok_or_else(|| Error::new("Comment to reply to does not exist"))?;
+            if parent.parent_comment_id.is_some() {
+                return Err(Error::new("Replies can not be replied to"));
+            }
+        }
+        let user = user_by_id(&user_id, &self.0).await?;
+        let id = nanoid!(20);
+        comments.push(ImportOrExportItemReviewComment {
+            id: id.clone(),
+            text: input.text,
+            user: IdAndNamedObject {
+                id: user_id.clone(),
+                name: user.name.clone(),
+            },
+            liked_by: HashSet::new(),
+            created_on: Utc::now(),
+            parent_comment_id: input.parent_comment_id,
+            deleted_on: None,
+        });
+        let author_id = review.user_id.clone();
+        let url = self.get_entity_details_frontend_url(
+            review.entity_id.clone(),
+            review.entity_lot,
+            Some("reviews"),
+        );
+        self.save_review_comments(review, comments).await?;
+        if let Some(notification) =
+            review_comment_notification(&author_id, &user_id, &user.name, &url)
+        {
+            queue_media_state_changed_notification_for_user(&author_id, &notification, &self.0)
+                .await
+                .trace_ok();
+        }
+        Ok(StringIdObject { id })
+    }
+
+    pub async fn delete_review_comment(
+        &self,
+        user_id: String,
+        input: DeleteReviewCommentInput,
+    ) -> Result<bool> {
+        let review = self.commentable_review(&user_id, &input.review_id).await?;
+        let mut comments = review.comments.clone();
+        let comment = comments
+            .iter_mut()
+            .find(|c| c.deleted_on.is_none() && c.id == input.comment_id)
+            .ok_or_else(|| Error::new("Comment does not exist"))?;
+        if comment.user.id != user_id && review.user_id != user_id {
+            return Err(Error::new(
+                "Only the author of the comment or the review can delete it",
+            ));
+        }
+        comment.deleted_on = Some(Utc::now());
+        self.save_review_comments(review, comments).await?;
+        Ok(true)
+    }
+
+    /// The comments on a review, most recent first. Deleted comments are left out.
+    pub async fn review_comments(
+        &self,
+        user_id: String,
+        input: ReviewCommentsInput,
+    ) -> Result<SearchResults<ImportOrExportItemReviewComment>> {
+        let review = self.commentable_review(&user_id, &input.review_id).await?;
+        let page = input.page.unwrap_or(1).max(1);
+        let take = input.take.unwrap_or(PAGE_SIZE).max(1);
+        let comments = review
+            .comments
+            .into_iter()
+            .filter(|c| c.deleted_on.is_none())
+            .sorted_by_key(|c| Reverse(c.created_on))
+            .collect_vec();
+        let total: i32 = comments.len().try_into().unwrap();
+        let items = comments
+            .into_iter()
+            .skip(((page - 1) * take).try_into().unwrap())
+            .take(take.try_into().unwrap())
+            .collect_vec();
+        let next_page = if total - (page * take) > 0 {
+            Some(page + 1)
+        } else {
+            None
+        };
+        Ok(SearchResults {
+            details: SearchDetails { total, next_page },
+            items,
+        })
+    }
+
+    /// Like a comment, or remove the like. Posting and deleting are kept here so
+    /// that older clients keep working.
+    pub async fn create_review_comment(
+        &self,
+        user_id: String,
+        input: CreateReviewCommentInput,
+    ) -> Result<bool> {
+        if input.should_delete.unwrap_or_default() {
+            let input = DeleteReviewCommentInput {
+                review_id: input.review_id,
+                comment_id: input.comment_id.unwrap_or_default(),
+            };
+            return self.delete_review_comment(user_id, input).await;
+        }
+        let like = match (input.increment_likes, input.decrement_likes) {
+            (Some(true), _) => true,
+            (_, Some(true)) => false,
+            _ => {
+                let input = PostReviewCommentInput {
+                    review_id: input.review_id,
+                    text: input.text.unwrap_or_default(),
+                    parent_comment_id: input.parent_comment_id,
+                };
+                self.post_review_comment(user_id, input).await?;
+                return Ok(true);
+            }
+        };
+        let review = self.commentable_review(&user_id, &input.review_id).await?;
+        let mut comments = review.comments.clone();
+        let comment = comments
+            .iter_mut()
+            .find(|c| c.deleted_on.is_none() && Some(&c.id) == input.comment_id.as_ref())
+            .ok_or_else(|| Error::new("Comment does not exist"))?;
+        match like {
+            true => comment.liked_by.insert(user_id),
+            false => comment.liked_by.remove(&user_id),
+        };
+        self.save_review_comments(review, comments).await?;
         Ok(true)
     }
 
+    pub async fn recalculate_sort_titles(&self, recalculate_all: bool) -> Result<()> {
+        let mut query = Metadata::find().select_only().columns([
+            metadata::Column::Id,
+            metadata::Column::Title,
+            metadata::Column::SortTitle,
+            metadata::Column::SortTitleOverride,
+        ]);
+        if !recalculate_all {
+            query = query.filter(metadata::Column::SortTitle.is_null());
+        }
+        let mut meta_stream = query
+            .into_tuple::<(String, String, Option<String>, Option<String>)>()
+            .stream(&self.0.db)
+            .await?;
+        while let Some((id, title, current, sort_title_override)) = meta_stream.try_next().await? {
+            // DEV: These were sorted by the romanized title which is only known on refresh
+            if current.is_some() && sort_title_override.is_none() && is_cjk(&title) {
+                continue;
+            }
+            let sort_title =
+                metadata_sort_title(&title, None, sort_title_override.as_deref(), &self.0);
+            if current.as_ref() == Some(&sort_title) {
+                continue;
+            }
+            Metadata::update_many()
+                .filter(metadata::Column::Id.eq(id))
+                .col_expr(metadata::Column::SortTitle, Expr::value(sort_title))
+                .exec(&self.0.db)
+                .await?;
+        }
+        Ok(())
+    }
+
     pub async fn recalculate_calendar_events(&self) -> Result<()> {
         let date_to_calculate_from = get_current_date(&self.0.timezone).pred_opt().unwrap();
 
@@ -2672,6 +4894,7 @@ ORDER BY RANDOM() LIMIT 10;
                 .into_iter()
                 .map(|i| MetadataImage {
                     url: StoredUrl::Url(i),
+                    ..Default::default()
                 })
                 .collect()
         });
@@ -2735,10 +4958,54 @@ ORDER BY RANDOM() LIMIT 10;
         &self,
         metadata_id: &String,
         force_update: bool,
-    ) -> Result<()> {
+    ) -> Result<Vec<String>> {
         update_metadata_and_notify_users(metadata_id, force_update, &self.0).await
     }
 
+    pub async fn update_metadata(
+        &self,
+        user_id: String,
+        metadata_id: String,
+    ) -> Result<Vec<String>> {
+        let metadata = Metadata::find_by_id(&metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        if metadata.source == MediaSource::Custom {
+            return Err(Error::new("Custom media does not have a provider"));
+        }
+        // DEV: Every refresh hits the provider, so users other than admins can refresh a
+        // media item once per interval
+        let cache_key = ApplicationCacheKey::MetadataForcedRefresh {
+            metadata_id: metadata_id.clone(),
+        };
+        if user_by_id(&user_id, &self.0).await?.lot != UserLot::Admin {
+            if self.0.cache_service.get(cache_key.clone()).await?.is_some() {
+                return Err(Error::new(
+                    "This media was refreshed recently, please try again later",
+                ));
+            }
+            self.0
+                .cache_service
+                .set_with_expiry(cache_key, FORCED_METADATA_REFRESH_INTERVAL_HOURS)
+                .await?;
+        }
+        let changes = self
+            .update_metadata_and_notify_users(&metadata_id, true)
+            .await?;
+        let updated = Metadata::find_by_id(&metadata_id)
+            .one(&self.0.db)
+            .await?
+            .ok_or_else(|| Error::new("Metadata does not exist"))?;
+        // DEV: The provider details are only saved when they could be fetched
+        if updated.last_updated_on == metadata.last_updated_on {
+            return Err(Error::new(
+                "Could not fetch the details of this media from the provider",
+            ));
+        }
+        Ok(changes)
+    }
+
     pub async fn update_person_and_notify_users(&self, person_id: String) -> Result<()> {
         let notifications = self
             .update_person(person_id.clone())
@@ -2807,7 +5074,7 @@ ORDER BY RANDOM() LIMIT 10;
                     "New review posted for {} ({}, {}) by {}.",
                     event.obj_title, event.entity_lot, url, event.username
                 ),
-                &self.0.db,
+                &self.0,
             )
             .await?;
         }
@@ -2941,6 +5208,12 @@ ORDER BY RANDOM() LIMIT 10;
         }
         ryot_log!(debug, "Deleting all queued notifications");
         QueuedNotification::delete_many().exec(&self.0.db).await?;
+        ryot_log!(debug, "Deleting finished notification deliveries");
+        NotificationDelivery::delete_many()
+            .filter(notification_delivery::Column::Status.ne(NotificationDeliveryStatus::Queued))
+            .filter(notification_delivery::Column::CreatedOn.lt(Utc::now() - Duration::days(7)))
+            .exec(&self.0.db)
+            .await?;
         ryot_log!(debug, "Deleting revoked access tokens");
         AccessLink::delete_many()
             .filter(access_link::Column::IsRevoked.eq(true))
@@ -2954,6 +5227,72 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(())
     }
 
+    pub async fn validate_metadata_images(&self) -> Result<()> {
+        // DEV: Only a few items are checked on every run so that the providers are not
+        // flooded with requests. Items are checked again when their images change.
+        let to_check = Metadata::find()
+            .filter(metadata::Column::ImagesUnreachable.is_null())
+            .filter(metadata::Column::Images.is_not_null())
+            .order_by_desc(metadata::Column::LastUpdatedOn)
+            .limit(IMAGE_VALIDATION_BATCH_SIZE)
+            .all(&self.0.db)
+            .await?;
+        let client = get_base_http_client(None);
+        for meta in to_check {
+            let images = meta.images.clone().unwrap_or_default();
+            let mut reachable = images.is_empty();
+            for image in images {
+                reachable = match image.url {
+                    StoredUrl::S3(key) => {
+                        self.0.file_storage_service.stored_object_exists(&key).await
+                    }
+                    StoredUrl::Url(url) => client
+                        .head(upgrade_image_url(url))
+                        .send()
+                        .await
+                        .map_or(false, |r| {
+                            r.status().is_success() || r.status().as_u16() == 405
+                        }),
+                };
+                if reachable {
+                    break;
+                }
+            }
+            if !reachable {
+                ryot_log!(debug, "No images of metadata {} could be loaded", meta.id);
+            }
+            let mut meta: metadata::ActiveModel = meta.into();
+            meta.images_unreachable = ActiveValue::Set(Some(!reachable));
+            meta.update(&self.0.db).await?;
+        }
+        Ok(())
+    }
+
+    // DEV: Rows stored before descriptions were normalized do not have a preview. Once
+    // they have all been converted, this does not find anything to do.
+    async fn normalize_metadata_descriptions(&self) -> Result<()> {
+        let to_normalize = Metadata::find()
+            .select_only()
+            .columns([metadata::Column::Id, metadata::Column::Description])
+            .filter(metadata::Column::Description.is_not_null())
+            .filter(metadata::Column::DescriptionPreview.is_null())
+            .into_tuple::<(String, String)>()
+            .all(&self.0.db)
+            .await?;
+        ryot_log!(debug, "Normalizing {} descriptions", to_normalize.len());
+        for (metadata_id, description) in to_normalize {
+            let description = normalize_description(&description);
+            let preview = description.as_deref().map(description_preview);
+            Metadata::update_many()
+                .filter(metadata::Column::Id.eq(metadata_id))
+                .col_expr(metadata::Column::Description, Expr::value(description))
+                .col_expr(metadata::Column::DescriptionPreview, Expr::value(preview))
+                .exec(&self.0.db)
+                .await?;
+        }
+        Ok(())
+    }
+
     pub async fn put_entities_in_partial_state(&self) -> Result<()> {
         async fn update_partial_states<Column1, Column2, Column3, T>(
             ute_filter_column: Column1,
@@ -2986,9 +5325,10 @@ ORDER BY RANDOM() LIMIT 10;
             }
             Ok(())
         }
+        // DEV: Custom media can not be fetched again, so it is never made partial
         update_partial_states(
             user_to_entity::Column::MetadataId,
-            Metadata::update_many(),
+            Metadata::update_many().filter(metadata::Column::Source.ne(MediaSource::Custom)),
             metadata::Column::Id,
             metadata::Column::IsPartial,
             &self.0.db,
@@ -3043,13 +5383,92 @@ ORDER BY RANDOM() LIMIT 10;
                     );
                     continue;
                 }
-                if let Err(err) =
-                    send_notification(notification.platform_specifics, &self.0.config, &msg).await
-                {
-                    ryot_log!(trace, "Error sending notification: {:?}", err);
-                }
+                queue_notification_delivery(
+                    &notification.id,
+                    &msg,
+                    Duration::seconds(self.0.config.notifications.coalesce_window_seconds),
+                    Utc::now(),
+                    &self.0.db,
+                )
+                .await?;
+            }
+        }
+        self.process_notification_deliveries().await?;
+        Ok(())
+    }
+
+    async fn attempt_notification_delivery(
+        &self,
+        delivery: notification_delivery::Model,
+        platform: notification_platform::Model,
+    ) -> Result<()> {
+        let error = match platform.is_disabled.unwrap_or_default() {
+            true => Some("The notification platform is disabled".to_owned()),
+            false => send_notification(
+                platform.platform_specifics,
+                &self.0.config,
+                &delivery.message,
+            )
+            .await
+            .err()
+            .map(|e| e.to_string()),
+        };
+        record_notification_delivery(error.is_none());
+        attempted_notification_delivery(
+            delivery,
+            error,
+            platform.is_disabled.unwrap_or_default(),
+            Utc::now(),
+        )
+        .update(&self.0.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Mark a queued delivery as being sent. Only one run can claim it, so this
+    /// returns `false` when another one already picked it up.
+    async fn claim_notification_delivery(&self, delivery_id: &String) -> Result<bool> {
+        let claimed = claimed_notification_delivery(delivery_id, Utc::now())
+            .exec(&self.0.db)
+            .await?;
+        Ok(claimed.rows_affected == 1)
+    }
+
+    pub async fn requeue_postponed_metadata_updates(&self) -> Result<()> {
+        requeue_postponed_metadata_updates(&self.0).await
+    }
+
+    /// Send the queued notification deliveries that are due. Only a bounded number
+    /// is picked up per run and sends to the same host are limited, everything
+    /// else stays queued in the database for the next run.
+    pub async fn process_notification_deliveries(&self) -> Result<()> {
+        let config = &self.0.config.notifications;
+        let deliveries = due_notification_deliveries(Utc::now(), config.max_deliveries_per_run)
+            .find_also_related(NotificationPlatform)
+            .all(&self.0.db)
+            .await?;
+        let mut claimed = vec![];
+        for (delivery, platform) in deliveries {
+            let Some(platform) = platform else {
+                continue;
+            };
+            if self.claim_notification_delivery(&delivery.id).await? {
+                claimed.push((delivery, platform));
             }
         }
+        let claimed = claimed
+            .into_iter()
+            .map(|(delivery, platform)| {
+                let host = notification_platform_host(&platform.platform_specifics, &self.0.config);
+                (host, (delivery, platform))
+            })
+            .collect_vec();
+        send_limited_per_host(
+            claimed,
+            config.max_concurrent_per_host,
+            |(delivery, platform)| self.attempt_notification_delivery(delivery, platform),
+        )
+        .await;
         Ok(())
     }
 
@@ -3086,10 +5505,16 @@ ORDER BY RANDOM() LIMIT 10;
             .trace_ok();
         ryot_log!(trace, "Removing old user summaries and regenerating them");
         self.regenerate_user_summaries().await.trace_ok();
+        ryot_log!(trace, "Recalculating tag keys");
+        self.recalculate_tag_keys().await.trace_ok();
         ryot_log!(trace, "Removing useless data");
         self.remove_useless_data().await.trace_ok();
         ryot_log!(trace, "Putting entities in partial state");
         self.put_entities_in_partial_state().await.trace_ok();
+        ryot_log!(trace, "Validating metadata images");
+        self.validate_metadata_images().await.trace_ok();
+        ryot_log!(trace, "Normalizing metadata descriptions");
+        self.normalize_metadata_descriptions().await.trace_ok();
         // DEV: This is called after removing useless data so that recommendations are not
         // delete right after they are downloaded.
         ryot_log!(trace, "Downloading recommendations for users");
@@ -3111,3 +5536,1755 @@ ORDER BY RANDOM() LIMIT 10;
         Ok(true)
     }
 }
+
+/// The media items in the library of a user, for use in sub queries.
+fn user_metadata_ids_query(user_id: &String) -> SelectStatement {
+    Query::select()
+        .column(user_to_entity::Column::MetadataId)
+        .from(user_to_entity::Entity)
+        .and_where(user_to_entity::Column::UserId.eq(user_id))
+        .and_where(user_to_entity::Column::MetadataId.is_not_null())
+        .to_owned()
+}
+
+/// The details of a custom media item and whether it is still missing the
+/// specifics of its type.
+fn custom_metadata_details(
+    identifier: String,
+    input: CreateCustomMetadataInput,
+) -> (MetadataDetails, bool) {
+    let images = input
+        .images
+        .unwrap_or_default()
+        .into_iter()
+        .map(|i| MetadataImageForMediaDetails { image: i })
+        .collect();
+    let videos = input
+        .videos
+        .unwrap_or_default()
+        .into_iter()
+        .map(|i| MetadataVideo {
+            identifier: StoredUrl::S3(i),
+            source: MetadataVideoSource::Custom,
+        })
+        .collect();
+    let creators = input
+        .creators
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| MetadataFreeCreator {
+            name: c,
+            role: "Creator".to_string(),
+            image: None,
+        })
+        .collect();
+    let is_partial = match input.lot {
+        MediaLot::Anime => input.anime_specifics.is_none(),
+        MediaLot::AudioBook => input.audio_book_specifics.is_none(),
+        MediaLot::Book => input.book_specifics.is_none(),
+        MediaLot::Manga => input.manga_specifics.is_none(),
+        MediaLot::Movie => input.movie_specifics.is_none(),
+        MediaLot::Podcast => input.podcast_specifics.is_none(),
+        MediaLot::Show => input.show_specifics.is_none(),
+        MediaLot::VideoGame => input.video_game_specifics.is_none(),
+        MediaLot::VisualNovel => input.visual_novel_specifics.is_none(),
+    };
+    let details = MetadataDetails {
+        identifier,
+        title: input.title,
+        description: input.description,
+        lot: input.lot,
+        source: MediaSource::Custom,
+        creators,
+        genres: input.genres.unwrap_or_default(),
+        s3_images: images,
+        videos,
+        is_nsfw: input.is_nsfw,
+        publish_year: input
+            .publish_year
+            .or_else(|| input.publish_date.map(|d| d.year())),
+        publish_date: input.publish_date,
+        anime_specifics: input.anime_specifics,
+        audio_book_specifics: input.audio_book_specifics,
+        book_specifics: input.book_specifics,
+        manga_specifics: input.manga_specifics,
+        movie_specifics: input.movie_specifics,
+        podcast_specifics: input.podcast_specifics,
+        show_specifics: input.show_specifics,
+        video_game_specifics: input.video_game_specifics,
+        visual_novel_specifics: input.visual_novel_specifics,
+        ..Default::default()
+    };
+    (details, is_partial)
+}
+
+/// The podcast episodes that the user has finished or skipped.
+fn listened_podcast_episodes(history: &[seen::Model]) -> HashSet<i32> {
+    history
+        .iter()
+        .filter(|h| matches!(h.state, SeenState::Completed | SeenState::Skipped))
+        .filter_map(|h| h.podcast_extra_information.as_ref().map(|p| p.episode))
+        .collect()
+}
+
+// DEV: Podcast episodes are mostly independent of each other, so the lowest numbered
+// one that was not listened to is suggested even when it comes before the latest one
+fn first_unlistened_podcast_episode(
+    podcast: &PodcastSpecifics,
+    listened: &HashSet<i32>,
+    current: Option<i32>,
+) -> Option<UserMediaNextEntry> {
+    let episode = podcast
+        .episodes
+        .iter()
+        .map(|e| e.number)
+        .filter(|n| !listened.contains(n) && Some(*n) != current)
+        .min()?;
+    Some(UserMediaNextEntry {
+        episode: Some(episode),
+        ..Default::default()
+    })
+}
+
+/// The episode to start with when nothing of a show or podcast has been seen yet.
+/// Special seasons of shows are not considered.
+fn first_entry(model: &metadata::Model, listened: &HashSet<i32>) -> Option<UserMediaNextEntry> {
+    if let Some(s) = &model.show_specifics {
+        let season = s
+            .seasons
+            .iter()
+            .filter(|s| !SHOW_SPECIAL_SEASON_NAMES.contains(&s.name.as_str()))
+            .filter(|s| !s.episodes.is_empty())
+            .min_by_key(|s| s.season_number)?;
+        let episode = season.episodes.iter().map(|e| e.episode_number).min()?;
+        Some(UserMediaNextEntry {
+            season: Some(season.season_number),
+            episode: Some(episode),
+            ..Default::default()
+        })
+    } else if let Some(p) = &model.podcast_specifics {
+        first_unlistened_podcast_episode(p, listened, None)
+    } else {
+        None
+    }
+}
+
+/// The episode or chapter that comes after the one in a seen entry. For podcasts
+/// this is the first episode that was not listened to.
+fn next_entry_after(
+    h: &seen::Model,
+    model: &metadata::Model,
+    listened: &HashSet<i32>,
+) -> Option<UserMediaNextEntry> {
+    if let Some(s) = &model.show_specifics {
+        let all_episodes = s
+            .seasons
+            .iter()
+            .sorted_by_key(|s| s.season_number)
+            .map(|s| (s.season_number, &s.episodes))
+            .collect_vec()
+            .into_iter()
+            .flat_map(|(s, e)| {
+                e.iter()
+                    .sorted_by_key(|e| e.episode_number)
+                    .map(move |e| UserMediaNextEntry {
+                        season: Some(s),
+                        episode: Some(e.episode_number),
+                        ..Default::default()
+                    })
+            })
+            .collect_vec();
+        let next = all_episodes.iter().position(|e| {
+            e.season == Some(h.show_extra_information.as_ref()?.season)
+                && e.episode == Some(h.show_extra_information.as_ref()?.episode)
+        });
+        Some(all_episodes.get(next? + 1)?.clone())
+    } else if let Some(p) = &model.podcast_specifics {
+        let current = h.podcast_extra_information.as_ref().map(|p| p.episode);
+        first_unlistened_podcast_episode(p, listened, current)
+    } else if let Some(_anime_spec) = &model.anime_specifics {
+        h.anime_extra_information.as_ref().and_then(|hist| {
+            hist.episode.map(|e| UserMediaNextEntry {
+                episode: Some(e + 1),
+                ..Default::default()
+            })
+        })
+    } else if let Some(_manga_spec) = &model.manga_specifics {
+        h.manga_extra_information.as_ref().and_then(|hist| {
+            hist.chapter
+                .map(|e| UserMediaNextEntry {
+                    chapter: Some(e.floor() + dec!(1)),
+                    ..Default::default()
+                })
+                .or(hist.volume.map(|e| UserMediaNextEntry {
+                    volume: Some(e + 1),
+                    ..Default::default()
+                }))
+        })
+    } else {
+        None
+    }
+}
+
+/// The storage key of an uploaded image. It is derived from the contents so that
+/// the same file is stored once even when it is added to several media items.
+fn metadata_image_key(contents: &[u8], extension: Option<&str>) -> String {
+    let extension = extension.map_or_else(|| "png".to_owned(), |e| e.to_lowercase());
+    format!(
+        "metadata-images/{:x}.{}",
+        Sha256::digest(contents),
+        extension
+    )
+}
+
+/// Put an uploaded image first unless the media item already has it.
+fn with_uploaded_image(mut images: Vec<MetadataImage>, key: String) -> Vec<MetadataImage> {
+    let url = StoredUrl::S3(key);
+    if !images.iter().any(|i| i.url == url) {
+        images.insert(
+            0,
+            MetadataImage {
+                url,
+                is_user_provided: true,
+            },
+        );
+    }
+    images
+}
+
+/// Whether any of the images is the stored object with this key.
+fn images_reference_key(images: &[MetadataImage], key: &str) -> bool {
+    images
+        .iter()
+        .any(|i| matches!(&i.url, StoredUrl::S3(k) if k == key))
+}
+
+/// Checks the dates of an edited seen item and returns the show episode it moves
+/// to, if any.
+fn edited_seen_show_episode(
+    seen: &seen::Model,
+    input: &UpdateSeenItemInput,
+) -> Result<Option<SeenShowExtraInformation>> {
+    let started_on = input.started_on.or(seen.started_on);
+    let finished_on = input.finished_on.or(seen.finished_on);
+    if let (Some(started_on), Some(finished_on)) = (started_on, finished_on) {
+        if finished_on < started_on {
+            return Err(Error::new(
+                "The finished date can not be earlier than the started date",
+            ));
+        }
+    }
+    match (input.show_season_number, input.show_episode_number) {
+        (None, None) => Ok(None),
+        (season, episode) => {
+            let Some(existing) = seen.show_extra_information.clone() else {
+                return Err(Error::new(
+                    "The season and episode can only be changed for a show",
+                ));
+            };
+            Ok(Some(SeenShowExtraInformation {
+                season: season.unwrap_or(existing.season),
+                episode: episode.unwrap_or(existing.episode),
+            }))
+        }
+    }
+}
+
+/// Queue a message for a platform. It is joined to a delivery for the same
+/// platform that was queued within the coalescing window and has not been
+/// attempted yet, so that a burst of notifications ends up as one message.
+async fn queue_notification_delivery<C: ConnectionTrait>(
+    platform_id: &String,
+    msg: &str,
+    window: Duration,
+    now: DateTimeUtc,
+    db: &C,
+) -> Result<()> {
+    let pending = NotificationDelivery::find()
+        .filter(notification_delivery::Column::NotificationPlatformId.eq(platform_id))
+        .filter(notification_delivery::Column::Status.eq(NotificationDeliveryStatus::Queued))
+        .filter(notification_delivery::Column::Attempts.eq(0))
+        .filter(notification_delivery::Column::CreatedOn.gte(now - window))
+        .order_by_desc(notification_delivery::Column::CreatedOn)
+        .one(db)
+        .await?;
+    match pending {
+        Some(pending) => {
+            let message = format!("{}\n{}", pending.message, msg);
+            let mut to_update: notification_delivery::ActiveModel = pending.into();
+            to_update.message = ActiveValue::Set(message);
+            to_update.update(db).await?;
+        }
+        None => {
+            let to_insert = notification_delivery::ActiveModel {
+                created_on: ActiveValue::Set(now),
+                message: ActiveValue::Set(msg.to_owned()),
+                next_attempt_on: ActiveValue::Set(now + window),
+                status: ActiveValue::Set(NotificationDeliveryStatus::Queued),
+                notification_platform_id: ActiveValue::Set(platform_id.to_owned()),
+                ..Default::default()
+            };
+            to_insert.insert(db).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The queued deliveries whose next attempt is due, oldest first. Deliveries
+/// claimed by a worker that stopped become due again once the claim expires.
+fn due_notification_deliveries(now: DateTimeUtc, limit: u64) -> Select<NotificationDelivery> {
+    NotificationDelivery::find()
+        .filter(notification_delivery::Column::Status.eq(NotificationDeliveryStatus::Queued))
+        .filter(notification_delivery::Column::NextAttemptOn.lte(now))
+        .order_by_asc(notification_delivery::Column::CreatedOn)
+        .limit(limit)
+}
+
+fn claimed_notification_delivery(
+    delivery_id: &String,
+    now: DateTimeUtc,
+) -> UpdateMany<NotificationDelivery> {
+    NotificationDelivery::update_many()
+        .col_expr(
+            notification_delivery::Column::NextAttemptOn,
+            Expr::value(now + Duration::minutes(NOTIFICATION_DELIVERY_CLAIM_MINUTES)),
+        )
+        .filter(notification_delivery::Column::Id.eq(delivery_id))
+        .filter(notification_delivery::Column::Status.eq(NotificationDeliveryStatus::Queued))
+        .filter(notification_delivery::Column::NextAttemptOn.lte(now))
+}
+
+/// The delivery after an attempt to send it. Failed deliveries are retried with
+/// increasing delays and dead-lettered once they run out of attempts.
+fn attempted_notification_delivery(
+    delivery: notification_delivery::Model,
+    error: Option<String>,
+    is_platform_disabled: bool,
+    now: DateTimeUtc,
+) -> notification_delivery::ActiveModel {
+    let attempts = delivery.attempts + 1;
+    let delivery_id = delivery.id.clone();
+    let mut to_update: notification_delivery::ActiveModel = delivery.into();
+    to_update.attempts = ActiveValue::Set(attempts);
+    to_update.error = ActiveValue::Set(error.clone());
+    let retry_delay = NOTIFICATION_RETRY_DELAYS_MINUTES
+        .get(usize::try_from(attempts - 1).unwrap())
+        .filter(|_| !is_platform_disabled);
+    match (error, retry_delay) {
+        (None, _) => {
+            to_update.delivered_on = ActiveValue::Set(Some(now));
+            to_update.status = ActiveValue::Set(NotificationDeliveryStatus::Delivered);
+        }
+        (Some(e), Some(delay)) => {
+            ryot_log!(
+                debug,
+                "Retrying notification delivery {} in {} minutes after error: {}",
+                delivery_id,
+                delay,
+                e
+            );
+            to_update.next_attempt_on = ActiveValue::Set(now + Duration::minutes(*delay));
+        }
+        (Some(_), None) => {
+            to_update.status = ActiveValue::Set(NotificationDeliveryStatus::DeadLetter);
+        }
+    }
+    to_update
+}
+
+/// Send everything at once, except that at most `max_concurrent` sends to the
+/// same host are in flight at any time.
+async fn send_limited_per_host<T, F, Fut>(items: Vec<(String, T)>, max_concurrent: usize, send: F)
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let by_host = items.into_iter().into_group_map();
+    ryot_log!(
+        debug,
+        "Sending notification deliveries to {} hosts",
+        by_host.len()
+    );
+    let max_concurrent = max_concurrent.max(1);
+    join_all(by_host.into_values().map(|items| {
+        stream::iter(items)
+            .map(&send)
+            .buffer_unordered(max_concurrent)
+            .for_each(|result| async move {
+                result.trace_ok();
+            })
+    }))
+    .await;
+}
+
+/// The users other than the caller who have seen, reviewed, collected or
+/// otherwise interacted with a media item.
+async fn other_users_with_metadata_activity<C: ConnectionTrait>(
+    user_id: &String,
+    metadata_id: &String,
+    db: &C,
+) -> Result<HashSet<String>> {
+    let mut users = HashSet::new();
+    users.extend(
+        Seen::find()
+            .select_only()
+            .column(seen::Column::UserId)
+            .filter(seen::Column::MetadataId.eq(metadata_id))
+            .distinct()
+            .into_tuple::<String>()
+            .all(db)
+            .await?,
+    );
+    users.extend(
+        Review::find()
+            .select_only()
+            .column(review::Column::UserId)
+            .filter(review::Column::MetadataId.eq(metadata_id))
+            .distinct()
+            .into_tuple::<String>()
+            .all(db)
+            .await?,
+    );
+    users.extend(
+        UserToEntity::find()
+            .select_only()
+            .column(user_to_entity::Column::UserId)
+            .filter(user_to_entity::Column::MetadataId.eq(metadata_id))
+            .distinct()
+            .into_tuple::<String>()
+            .all(db)
+            .await?,
+    );
+    users.extend(
+        CollectionToEntity::find()
+            .select_only()
+            .column(collection::Column::UserId)
+            .inner_join(Collection)
+            .filter(collection_to_entity::Column::MetadataId.eq(metadata_id))
+            .distinct()
+            .into_tuple::<String>()
+            .all(db)
+            .await?,
+    );
+    users.remove(user_id);
+    Ok(users)
+}
+
+/// The reviews of a media item that a user can see, their own and the public ones
+/// of everyone else.
+fn visible_media_reviews(user_id: &String, metadata_id: &String) -> Select<Review> {
+    Review::find()
+        .filter(review::Column::MetadataId.eq(metadata_id))
+        .filter(
+            review::Column::UserId
+                .eq(user_id)
+                .or(review::Column::Visibility.eq(Visibility::Public)),
+        )
+}
+
+/// One page of the works of a person along with the number of works of each type.
+async fn person_works<C: ConnectionTrait>(input: PersonWorksInput, db: &C) -> Result<PersonWorks> {
+    let page = input.page.unwrap_or(1);
+    if Person::find_by_id(&input.person_id).count(db).await? == 0 {
+        return Err(Error::new("Person does not exist"));
+    }
+    let works = Query::select()
+        .column(metadata_to_person::Column::MetadataId)
+        .from(metadata_to_person::Entity)
+        .and_where(metadata_to_person::Column::PersonId.eq(&input.person_id))
+        .to_owned();
+    let lots = Metadata::find()
+        .select_only()
+        .column(metadata::Column::Lot)
+        .column_as(Func::count(Expr::col(metadata::Column::Id)), "count")
+        .filter(metadata::Column::Id.in_subquery(works.clone()))
+        .group_by(metadata::Column::Lot)
+        .order_by_asc(metadata::Column::Lot)
+        .into_model::<PersonWorksLotCount>()
+        .all(db)
+        .await?;
+    let paginator = Metadata::find()
+        .select_only()
+        .column(metadata::Column::Id)
+        .filter(metadata::Column::Id.in_subquery(works))
+        .apply_if(input.lot, |query, v| {
+            query.filter(metadata::Column::Lot.eq(v))
+        })
+        .order_by_with_nulls(
+            metadata::Column::PublishDate,
+            Order::Desc,
+            NullOrdering::Last,
+        )
+        .order_by_asc(metadata::Column::Id)
+        .into_tuple::<String>()
+        .paginate(db, PAGE_SIZE as u64);
+    let ItemsAndPagesNumber {
+        number_of_items,
+        number_of_pages,
+    } = paginator.num_items_and_pages().await?;
+    let items = paginator.fetch_page(page - 1).await?;
+    Ok(PersonWorks {
+        lots,
+        works: SearchResults {
+            details: SearchDetails {
+                total: number_of_items.try_into().unwrap(),
+                next_page: if page < number_of_pages {
+                    Some((page + 1).try_into().unwrap())
+                } else {
+                    None
+                },
+            },
+            items,
+        },
+    })
+}
+
+/// The episodes of a season to mark as seen, leaving out the ones in `already_seen`.
+fn unseen_season_episodes(season: ShowSeason, already_seen: &HashSet<i32>) -> Vec<i32> {
+    season
+        .episodes
+        .into_iter()
+        .map(|e| e.episode_number)
+        .filter(|e| !already_seen.contains(e))
+        .collect_vec()
+}
+
+/// Metadata ranked by the number of distinct users with seen activity in the
+/// window. Users who opted out of activity sharing are not counted.
+fn instance_popular_query(
+    lot: MediaLot,
+    window: InstancePopularWindow,
+    include_nsfw: bool,
+    now: DateTimeUtc,
+) -> Select<Seen> {
+    let days = match window {
+        InstancePopularWindow::Week => 7,
+        InstancePopularWindow::Month => 30,
+        InstancePopularWindow::Quarter => 90,
+    };
+    Seen::find()
+        .select_only()
+        .column(seen::Column::MetadataId)
+        .column_as(
+            Func::count_distinct(Expr::col((seen::Entity, seen::Column::UserId))),
+            "users_count",
+        )
+        .inner_join(Metadata)
+        .inner_join(User)
+        .filter(metadata::Column::Lot.eq(lot))
+        .filter(seen::Column::LastUpdatedOn.gte(now - Duration::days(days)))
+        .filter(Expr::cust(
+            r#"("user"."preferences" -> 'general' ->> 'disable_activity_sharing') IS DISTINCT FROM 'true'"#,
+        ))
+        .apply_if(Some(include_nsfw).filter(|&n| !n), |query, _v| {
+            query.filter(
+                Condition::any()
+                    .add(metadata::Column::IsNsfw.is_null())
+                    .add(metadata::Column::IsNsfw.eq(false)),
+            )
+        })
+        .group_by(seen::Column::MetadataId)
+        .order_by_desc(Expr::cust(r#""users_count""#))
+        .limit(PAGE_SIZE as u64)
+}
+
+/// What the user has done with the media in a page of search results, found with a
+/// single query.
+// DEV: The provider results are cached for everyone, so the user specific
+// information is added after fetching them
+fn metadata_search_interactions(
+    user_id: &String,
+    lot: MediaLot,
+    source: MediaSource,
+    identifiers: &[String],
+) -> Select<Metadata> {
+    let cloned_user_id = user_id.to_owned();
+    let latest_seen_state = Query::select()
+        .column(seen::Column::State)
+        .from(Seen)
+        .and_where(seen::Column::UserId.eq(user_id))
+        .and_where(
+            Expr::col((Seen, seen::Column::MetadataId))
+                .equals((Alias::new("metadata"), metadata::Column::Id)),
+        )
+        // DEV: Entries updated at the same instant are ordered by their ID so that
+        // the same state is picked every time
+        .order_by(seen::Column::LastUpdatedOn, Order::Desc)
+        .order_by(seen::Column::Id, Order::Desc)
+        .limit(1)
+        .to_owned();
+    Metadata::find()
+        .join(
+            JoinType::LeftJoin,
+            metadata::Relation::UserToEntity
+                .def()
+                .on_condition(move |_left, right| {
+                    Condition::all().add(
+                        Expr::col((right, user_to_entity::Column::UserId))
+                            .eq(cloned_user_id.clone()),
+                    )
+                }),
+        )
+        .select_only()
+        .column(metadata::Column::Identifier)
+        .column_as(
+            Expr::col((Alias::new("metadata"), metadata::Column::Id)),
+            "database_id",
+        )
+        .column_as(
+            Expr::col((Alias::new("user_to_entity"), user_to_entity::Column::Id)).is_not_null(),
+            "has_interacted",
+        )
+        .expr_as(
+            SimpleExpr::SubQuery(None, Box::new(latest_seen_state.into_sub_query_statement())),
+            "seen_status",
+        )
+        .filter(metadata::Column::Lot.eq(lot))
+        .filter(metadata::Column::Source.eq(source))
+        .filter(metadata::Column::Identifier.is_in(identifiers))
+}
+
+/// Attach what the user has done with each search result. Results that are not in
+/// the database are returned as they are.
+fn annotate_metadata_search_items(
+    items: Vec<MetadataSearchItem>,
+    interactions: Vec<(String, String, bool, Option<SeenState>)>,
+) -> Vec<MetadataSearchItemResponse> {
+    let interactions: HashMap<_, _> = interactions
+        .into_iter()
+        .map(|(key, value1, value2, value3)| (key, (value1, value2, value3)))
+        .collect();
+    items
+        .into_iter()
+        .map(|i| {
+            let interaction = interactions.get(&i.identifier).cloned();
+            MetadataSearchItemResponse {
+                has_interacted: interaction.clone().unwrap_or_default().1,
+                seen_status: interaction.clone().and_then(|i| i.2),
+                database_id: interaction.map(|i| i.0),
+                item: i,
+            }
+        })
+        .collect()
+}
+
+/// Private reviews are only visible to their author, so nobody else can comment
+/// on them.
+fn can_comment_on_review(review: &review::Model, user_id: &str) -> bool {
+    review.visibility != Visibility::Private || review.user_id == user_id
+}
+
+/// The notification for the author of a review when someone else comments on it.
+fn review_comment_notification(
+    author_id: &str,
+    commenter_id: &str,
+    commenter_name: &str,
+    url: &str,
+) -> Option<(String, MediaStateChanged)> {
+    (author_id != commenter_id).then(|| {
+        (
+            format!("{} commented on your review ({}).", commenter_name, url),
+            MediaStateChanged::ReviewCommentPosted,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::TimeZone;
+    use media_models::{
+        AnimeSpecifics, PodcastEpisode, SeenAnimeExtraInformation, SeenShowExtraInformation,
+        ShowEpisode,
+    };
+    use sea_orm::{MockDatabase, Value};
+
+    use super::*;
+
+    fn date(day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+    }
+
+    fn review(visibility: Visibility) -> review::Model {
+        review::Model {
+            visibility,
+            id: "review".to_owned(),
+            posted_on: Utc::now(),
+            rating: None,
+            text: None,
+            is_spoiler: false,
+            user_id: "author".to_owned(),
+            entity_id: "metadata".to_owned(),
+            metadata_id: Some("metadata".to_owned()),
+            person_id: None,
+            entity_lot: EntityLot::Metadata,
+            metadata_group_id: None,
+            collection_id: None,
+            exercise_id: None,
+            show_extra_information: None,
+            podcast_extra_information: None,
+            anime_extra_information: None,
+            manga_extra_information: None,
+            comments: vec![],
+        }
+    }
+
+    #[test]
+    fn instance_popular_leaves_out_users_who_opted_out() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 30, 12, 0, 0).unwrap();
+        let sql = instance_popular_query(MediaLot::Movie, InstancePopularWindow::Month, true, now)
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#"COUNT(DISTINCT "seen"."user_id") AS "users_count""#));
+        assert!(sql.contains(
+            r#"("user"."preferences" -> 'general' ->> 'disable_activity_sharing') IS DISTINCT FROM 'true'"#
+        ));
+        assert!(!sql.contains("is_nsfw"));
+        let restricted =
+            instance_popular_query(MediaLot::Movie, InstancePopularWindow::Month, false, now)
+                .build(DatabaseBackend::Postgres)
+                .to_string();
+        assert!(restricted
+            .contains(r#"("metadata"."is_nsfw" IS NULL OR "metadata"."is_nsfw" = FALSE)"#));
+    }
+
+    #[test]
+    fn instance_popular_windows_start_from_now() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 30, 12, 0, 0).unwrap();
+        for (window, start) in [
+            (InstancePopularWindow::Week, "2024-06-23 12:00:00"),
+            (InstancePopularWindow::Month, "2024-05-31 12:00:00"),
+            (InstancePopularWindow::Quarter, "2024-04-01 12:00:00"),
+        ] {
+            let sql = instance_popular_query(MediaLot::Movie, window, true, now)
+                .build(DatabaseBackend::Postgres)
+                .to_string();
+            assert!(sql.contains(&format!(r#""seen"."last_updated_on" >= '{start}"#)));
+        }
+    }
+
+    fn search_item(identifier: &str) -> MetadataSearchItem {
+        MetadataSearchItem {
+            identifier: identifier.to_owned(),
+            title: identifier.to_owned(),
+            image: None,
+            publish_year: None,
+        }
+    }
+
+    #[test]
+    fn search_results_are_matched_against_the_library_in_one_query() {
+        let identifiers = ["603".to_owned(), "604".to_owned()];
+        let sql = metadata_search_interactions(
+            &"user".to_owned(),
+            MediaLot::Movie,
+            MediaSource::Tmdb,
+            &identifiers,
+        )
+        .build(DatabaseBackend::Postgres)
+        .to_string();
+        assert!(sql.contains(r#""metadata"."identifier" IN ('603', '604')"#));
+        assert!(sql.contains(r#"LEFT JOIN "user_to_entity""#));
+        assert!(sql.contains(r#"ORDER BY "last_updated_on" DESC, "id" DESC LIMIT 1"#));
+        assert!(sql.contains(r#"AS "seen_status""#));
+    }
+
+    #[test]
+    fn search_results_only_show_the_media_the_user_tracks() {
+        // DEV: Of three results, one is tracked and seen, one is only in the database
+        // and one has never been imported
+        let interactions = vec![
+            (
+                "603".to_owned(),
+                "met_1".to_owned(),
+                true,
+                Some(SeenState::Completed),
+            ),
+            ("604".to_owned(), "met_2".to_owned(), false, None),
+        ];
+        let items = vec![search_item("603"), search_item("604"), search_item("605")];
+        let annotated = annotate_metadata_search_items(items, interactions);
+        let summary = annotated
+            .iter()
+            .map(|r| {
+                (
+                    r.item.identifier.as_str(),
+                    r.has_interacted,
+                    r.database_id.as_deref(),
+                    r.seen_status,
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            summary,
+            [
+                ("603", true, Some("met_1"), Some(SeenState::Completed)),
+                ("604", false, Some("met_2"), None),
+                ("605", false, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_author_can_comment_on_a_private_review() {
+        let private = review(Visibility::Private);
+        assert!(can_comment_on_review(&private, "author"));
+        assert!(!can_comment_on_review(&private, "reader"));
+        let public = review(Visibility::Public);
+        assert!(can_comment_on_review(&public, "reader"));
+    }
+
+    #[test]
+    fn review_authors_are_notified_of_comments_by_others() {
+        let (message, event) =
+            review_comment_notification("author", "reader", "Reader", "/media/item/1/reviews")
+                .unwrap();
+        assert_eq!(
+            message,
+            "Reader commented on your review (/media/item/1/reviews)."
+        );
+        assert_eq!(event, MediaStateChanged::ReviewCommentPosted);
+        // DEV: Replying on your own review does not notify you
+        assert!(review_comment_notification("author", "author", "Author", "/").is_none());
+    }
+
+    fn seen(state: SeenState, finished_on: Option<NaiveDate>) -> seen::Model {
+        seen::Model {
+            state,
+            finished_on,
+            id: nanoid!(),
+            progress: dec!(100),
+            started_on: None,
+            user_id: "user".to_owned(),
+            metadata_id: "metadata".to_owned(),
+            provider_watched_on: None,
+            updated_at: vec![],
+            show_extra_information: None,
+            podcast_extra_information: None,
+            anime_extra_information: None,
+            manga_extra_information: None,
+            manual_time_spent: None,
+            edition: None,
+            pages_read: None,
+            minutes_consumed: None,
+            last_updated_on: Utc::now(),
+            num_times_updated: 1,
+            show_season_number: None,
+            show_episode_number: None,
+            podcast_episode_number: None,
+            review_id: None,
+        }
+    }
+
+    fn episode_seen(
+        state: SeenState,
+        finished_on: Option<NaiveDate>,
+        season: i32,
+        episode: i32,
+    ) -> seen::Model {
+        seen::Model {
+            show_extra_information: Some(SeenShowExtraInformation { season, episode }),
+            ..seen(state, finished_on)
+        }
+    }
+
+    fn show(seasons: &[(i32, &str, i32)]) -> metadata::Model {
+        let seasons = seasons
+            .iter()
+            .map(|(season_number, name, episodes)| ShowSeason {
+                season_number: *season_number,
+                name: name.to_string(),
+                episodes: (1..episodes + 1)
+                    .map(|episode_number| ShowEpisode {
+                        episode_number,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .collect();
+        metadata::Model {
+            lot: MediaLot::Show,
+            show_specifics: Some(ShowSpecifics {
+                seasons,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn seen_edit(
+        started_on: Option<NaiveDate>,
+        finished_on: Option<NaiveDate>,
+    ) -> UpdateSeenItemInput {
+        UpdateSeenItemInput {
+            started_on,
+            finished_on,
+            seen_id: "seen".to_owned(),
+            review_id: None,
+            manual_time_spent: None,
+            provider_watched_on: None,
+            edition: None,
+            show_season_number: None,
+            show_episode_number: None,
+        }
+    }
+
+    #[test]
+    fn seen_items_can_not_be_finished_before_they_were_started() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 5, d);
+        let started = seen::Model {
+            started_on: day(10),
+            ..seen(SeenState::Completed, day(12))
+        };
+        assert!(edited_seen_show_episode(&started, &seen_edit(None, day(11))).is_ok());
+        // DEV: The stored start date counts when only the finished date is edited
+        let error = edited_seen_show_episode(&started, &seen_edit(None, day(9))).unwrap_err();
+        assert_eq!(
+            error.message,
+            "The finished date can not be earlier than the started date"
+        );
+        assert!(edited_seen_show_episode(&started, &seen_edit(day(13), None)).is_err());
+    }
+
+    #[test]
+    fn only_seen_shows_can_move_to_another_episode() {
+        let edit = UpdateSeenItemInput {
+            show_episode_number: Some(4),
+            ..seen_edit(None, None)
+        };
+        let watched = episode_seen(SeenState::Completed, None, 2, 3);
+        assert_eq!(
+            edited_seen_show_episode(&watched, &edit).unwrap(),
+            Some(SeenShowExtraInformation {
+                season: 2,
+                episode: 4
+            })
+        );
+        let movie = seen(SeenState::Completed, None);
+        assert!(edited_seen_show_episode(&movie, &edit).is_err());
+        assert_eq!(
+            edited_seen_show_episode(&movie, &seen_edit(None, None)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn marking_a_season_skips_completed_episodes_unless_forced() {
+        let season = || {
+            show(&[(1, "Season 1", 5)])
+                .show_specifics
+                .unwrap()
+                .seasons
+                .remove(0)
+        };
+        let completed = HashSet::from([2, 4]);
+        assert_eq!(unseen_season_episodes(season(), &completed), [1, 3, 5]);
+        // DEV: `force` marks every episode, so nothing counts as already seen
+        assert_eq!(
+            unseen_season_episodes(season(), &HashSet::new()),
+            [1, 2, 3, 4, 5]
+        );
+    }
+
+    fn tuple_row(values: Vec<Value>) -> BTreeMap<String, Value> {
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn admin_dashboard_aggregates_every_user() {
+        let count = |n: i64| BTreeMap::from([("num_items".to_owned(), Value::BigInt(Some(n)))]);
+        let text = |s: &str| Value::String(Some(Box::new(s.to_owned())));
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![count(3)], vec![count(2)]])
+            .append_query_results([vec![
+                tuple_row(vec![text("book"), Value::BigInt(Some(12))]),
+                tuple_row(vec![text("movie"), Value::BigInt(Some(40))]),
+            ]])
+            .append_query_results([vec![tuple_row(vec![Value::BigInt(Some(9_437_184))])]])
+            .append_query_results([vec![
+                tuple_row(vec![
+                    text("usr_2"),
+                    text("bob"),
+                    Value::BigInt(Some(5000)),
+                    Value::BigInt(Some(1)),
+                ]),
+                tuple_row(vec![
+                    text("usr_1"),
+                    text("alice"),
+                    Value::BigInt(Some(20)),
+                    Value::BigInt(Some(4)),
+                ]),
+                tuple_row(vec![
+                    text("usr_3"),
+                    text("carol"),
+                    Value::BigInt(Some(0)),
+                    Value::BigInt(Some(0)),
+                ]),
+            ]])
+            .into_connection();
+        let now = DateTimeUtc::from_timestamp(1_730_000_000, 0).unwrap();
+        let statistics = admin_dashboard_statistics(&db, now).await.unwrap();
+        assert_eq!((statistics.total_users, statistics.active_users), (3, 2));
+        assert_eq!(
+            statistics
+                .metadata_per_lot
+                .iter()
+                .map(|l| (l.lot, l.count))
+                .collect_vec(),
+            vec![(MediaLot::Book, 12), (MediaLot::Movie, 40)]
+        );
+        assert_eq!(statistics.database_size_bytes, 9_437_184);
+        assert_eq!(
+            statistics
+                .user_contributions
+                .iter()
+                .map(|u| (u.name.as_str(), u.seen_count, u.review_count))
+                .collect_vec(),
+            vec![("bob", 5000, 1), ("alice", 20, 4), ("carol", 0, 0)]
+        );
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 5);
+        // DEV: Users are active if they logged in or recorded something in the last 30 days
+        let active_users = &log[1].statements()[0];
+        assert!(active_users.sql.contains(r#""user"."last_login_on" >= $1"#));
+        assert!(active_users
+            .sql
+            .contains(r#""user"."id" IN (SELECT "user_id" FROM "seen""#));
+        assert!(format!("{:?}", active_users.values).contains("2024-09-27"));
+    }
+
+    fn random_input() -> RandomEpisodeInput {
+        RandomEpisodeInput {
+            metadata_id: "metadata".to_owned(),
+            avoid_recent_days: None,
+            unseen_only: None,
+            include_specials: None,
+            and_mark_started: None,
+        }
+    }
+
+    fn eligible_keys(
+        meta: &metadata::Model,
+        history: &[seen::Model],
+        input: &RandomEpisodeInput,
+    ) -> Result<Vec<EpisodeKey>, RandomEpisodeUnavailableReason> {
+        random_episode_candidates(meta, history, input, date(31).unwrap()).map(|c| {
+            c.eligible
+                .into_iter()
+                .map(|(key, _, _)| key)
+                .sorted()
+                .collect_vec()
+        })
+    }
+
+    #[test]
+    fn random_episodes_avoid_the_ones_seen_recently() {
+        let meta = show(&[(0, "Specials", 1), (1, "Season 1", 3)]);
+        let history = vec![
+            episode_seen(SeenState::Completed, date(1), 1, 1),
+            episode_seen(SeenState::Completed, date(28), 1, 2),
+            // DEV: Episodes that were only started do not count as seen
+            episode_seen(SeenState::InProgress, date(30), 1, 3),
+        ];
+        let mut input = random_input();
+        assert_eq!(
+            eligible_keys(&meta, &history, &input),
+            Ok(vec![(Some(1), 1), (Some(1), 2), (Some(1), 3)])
+        );
+        input.avoid_recent_days = Some(7);
+        assert_eq!(
+            eligible_keys(&meta, &history, &input),
+            Ok(vec![(Some(1), 1), (Some(1), 3)])
+        );
+        input.avoid_recent_days = Some(31);
+        assert_eq!(
+            eligible_keys(&meta, &history, &input),
+            Ok(vec![(Some(1), 3)])
+        );
+        input.unseen_only = Some(true);
+        input.avoid_recent_days = None;
+        input.include_specials = Some(true);
+        assert_eq!(
+            eligible_keys(&meta, &history, &input),
+            Ok(vec![(Some(0), 1), (Some(1), 3)])
+        );
+        let last_seen = random_episode_candidates(&meta, &history, &input, date(31).unwrap())
+            .unwrap()
+            .last_seen;
+        assert_eq!(last_seen.get(&(Some(1), 2)), Some(&date(28)));
+    }
+
+    #[test]
+    fn random_episodes_report_why_none_can_be_picked() {
+        let meta = show(&[(1, "Season 1", 2)]);
+        let history = vec![
+            episode_seen(SeenState::Completed, date(29), 1, 1),
+            episode_seen(SeenState::Completed, date(30), 1, 2),
+        ];
+        let mut input = random_input();
+        input.avoid_recent_days = Some(7);
+        assert_eq!(
+            eligible_keys(&meta, &history, &input),
+            Err(RandomEpisodeUnavailableReason::AllSeenRecently)
+        );
+        input.unseen_only = Some(true);
+        assert_eq!(
+            eligible_keys(&meta, &history, &input),
+            Err(RandomEpisodeUnavailableReason::AllSeen)
+        );
+        let mut unaired = show(&[(1, "Season 1", 1)]);
+        unaired.show_specifics.as_mut().unwrap().seasons[0].episodes[0].publish_date =
+            NaiveDate::from_ymd_opt(2024, 2, 1);
+        assert_eq!(
+            eligible_keys(&unaired, &[], &random_input()),
+            Err(RandomEpisodeUnavailableReason::NoAiredEpisodes)
+        );
+        let movie = metadata::Model {
+            lot: MediaLot::Movie,
+            ..Default::default()
+        };
+        assert_eq!(
+            eligible_keys(&movie, &[], &random_input()),
+            Err(RandomEpisodeUnavailableReason::NotShowOrPodcast)
+        );
+    }
+
+    #[test]
+    fn random_episodes_are_marked_started_with_their_numbers() {
+        assert_eq!(episode_key_numbers((Some(2), 5)), (Some(2), Some(5), None));
+        assert_eq!(episode_key_numbers((None, 12)), (None, None, Some(12)));
+    }
+
+    #[test]
+    fn group_parts_are_listed_in_release_order_with_the_user_status() {
+        let member = |id: &str, part, publish_year, status| {
+            (
+                metadata_to_metadata_group::Model {
+                    part,
+                    metadata_group_id: "group".to_owned(),
+                    metadata_id: id.to_owned(),
+                },
+                metadata::Model {
+                    publish_year,
+                    id: id.to_owned(),
+                    ..Default::default()
+                },
+                status,
+            )
+        };
+        let finished = MetadataFinishedStatus {
+            is_finished: true,
+            has_seen: true,
+        };
+        let started = MetadataFinishedStatus {
+            is_finished: false,
+            has_seen: true,
+        };
+        let parts = metadata_group_parts(vec![
+            member("endgame", 4, Some(2019), MetadataFinishedStatus::default()),
+            member("unreleased", 5, None, MetadataFinishedStatus::default()),
+            member("avengers", 1, Some(2012), finished),
+            member("infinity_war", 3, Some(2018), started),
+            member("ultron", 2, Some(2015), finished),
+        ]);
+        assert_eq!(
+            parts
+                .iter()
+                .map(|p| (p.metadata_id.as_str(), p.has_interacted, p.is_finished))
+                .collect_vec(),
+            vec![
+                ("avengers", true, true),
+                ("ultron", true, true),
+                ("infinity_war", true, false),
+                ("endgame", false, false),
+                ("unreleased", false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn time_dependent_dashboard_sections_expire_sooner() {
+        let invalidated = [
+            UserDashboardSection::InProgress,
+            UserDashboardSection::Summary,
+            UserDashboardSection::Favorites,
+            UserDashboardSection::PendingNotifications,
+        ];
+        for section in invalidated {
+            assert_eq!(dashboard_section_expiry_hours(section), 24);
+        }
+        assert!(
+            dashboard_section_expiry_hours(UserDashboardSection::Upcoming)
+                < dashboard_section_expiry_hours(UserDashboardSection::Summary)
+        );
+        assert_eq!(
+            dashboard_section_expiry_hours(UserDashboardSection::Recommendations),
+            1
+        );
+    }
+
+    #[test]
+    fn skipped_episodes_complete_the_season_without_being_watched() {
+        let season = show(&[(1, "Season 1", 4)])
+            .show_specifics
+            .unwrap()
+            .seasons
+            .remove(0);
+        let history = vec![
+            episode_seen(SeenState::Completed, date(1), 1, 1),
+            episode_seen(SeenState::Skipped, date(1), 1, 2),
+            episode_seen(SeenState::Completed, date(2), 1, 3),
+        ];
+        let progress = show_season_progress(season.clone(), &history, date(3).unwrap());
+        assert_eq!(progress.times_seen, 0);
+        assert_eq!(
+            (
+                progress.aired_episodes,
+                progress.watched_episodes,
+                progress.skipped_episodes
+            ),
+            (4, 2, 1)
+        );
+        let mut history = history;
+        history.push(episode_seen(SeenState::Skipped, date(3), 1, 4));
+        // DEV: Watching an episode that was skipped earlier makes it a watched one
+        history.push(episode_seen(SeenState::Completed, date(3), 1, 2));
+        let progress = show_season_progress(season, &history, date(3).unwrap());
+        assert_eq!(progress.times_seen, 1);
+        assert_eq!(
+            (progress.watched_episodes, progress.skipped_episodes),
+            (3, 1)
+        );
+        let second = &progress.episodes[1];
+        assert_eq!((second.times_seen, second.times_skipped), (1, 1));
+        assert_eq!(second.last_seen_on, date(3));
+    }
+
+    #[test]
+    fn unaired_episodes_are_not_counted_as_aired() {
+        let mut season = show(&[(1, "Season 1", 2)])
+            .show_specifics
+            .unwrap()
+            .seasons
+            .remove(0);
+        season.episodes[1].publish_date = date(10);
+        let progress = show_season_progress(season, &[], date(3).unwrap());
+        assert_eq!(progress.aired_episodes, 1);
+        assert_eq!(progress.times_seen, 0);
+    }
+
+    #[test]
+    fn finished_runs_of_movies_are_completed_entries_oldest_first() {
+        let movie = metadata::Model {
+            lot: MediaLot::Movie,
+            ..Default::default()
+        };
+        let history = vec![
+            seen(SeenState::Completed, date(5)),
+            seen(SeenState::InProgress, None),
+            seen(SeenState::Completed, date(2)),
+            seen(SeenState::Dropped, date(3)),
+        ];
+        assert_eq!(finished_runs(&movie, &history), vec![date(2), date(5)]);
+        assert_eq!(finished_runs(&movie, &[]), vec![]);
+    }
+
+    #[test]
+    fn finished_runs_of_shows_need_every_episode() {
+        let metadata = show(&[(0, "Specials", 3), (1, "Season 1", 2)]);
+        let history = vec![
+            episode_seen(SeenState::Completed, date(1), 1, 1),
+            episode_seen(SeenState::Completed, date(3), 1, 1),
+        ];
+        assert_eq!(finished_runs(&metadata, &history), vec![]);
+        let history = vec![
+            episode_seen(SeenState::Completed, date(1), 1, 1),
+            episode_seen(SeenState::Completed, date(2), 1, 2),
+            episode_seen(SeenState::Completed, date(3), 1, 1),
+            episode_seen(SeenState::Skipped, date(4), 1, 2),
+            episode_seen(SeenState::InProgress, date(5), 1, 1),
+        ];
+        assert_eq!(finished_runs(&metadata, &history), vec![date(2), date(4)]);
+    }
+
+    #[test]
+    fn finished_runs_of_anime_count_episodes() {
+        let metadata = metadata::Model {
+            lot: MediaLot::Anime,
+            anime_specifics: Some(AnimeSpecifics {
+                episodes: Some(2),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let anime_seen = |day, episode| seen::Model {
+            anime_extra_information: Some(SeenAnimeExtraInformation {
+                episode: Some(episode),
+            }),
+            ..seen(SeenState::Completed, date(day))
+        };
+        assert_eq!(finished_runs(&metadata, &[anime_seen(1, 1)]), vec![]);
+        assert_eq!(
+            finished_runs(&metadata, &[anime_seen(2, 2), anime_seen(1, 1)]),
+            vec![date(2)]
+        );
+    }
+
+    fn show_specifics(metadata: metadata::Model) -> ShowSpecifics {
+        metadata.show_specifics.unwrap()
+    }
+
+    #[test]
+    fn next_unseen_show_episode_advances_across_seasons() {
+        let specifics = show_specifics(show(&[(2, "Season 2", 2), (1, "Season 1", 2)]));
+        let today = date(10).unwrap();
+        assert_eq!(
+            next_unseen_show_episode(&specifics, &[], today),
+            Some((1, 1))
+        );
+        let history = vec![
+            episode_seen(SeenState::Completed, date(1), 1, 1),
+            episode_seen(SeenState::Skipped, date(2), 1, 2),
+        ];
+        assert_eq!(
+            next_unseen_show_episode(&specifics, &history, today),
+            Some((2, 1))
+        );
+        let history = vec![
+            episode_seen(SeenState::Completed, date(1), 1, 1),
+            episode_seen(SeenState::InProgress, date(2), 1, 2),
+        ];
+        assert_eq!(
+            next_unseen_show_episode(&specifics, &history, today),
+            Some((1, 2))
+        );
+    }
+
+    #[test]
+    fn next_unseen_show_episode_skips_specials_and_unaired_episodes() {
+        let mut specifics = show_specifics(show(&[(0, "Specials", 2), (1, "Season 1", 2)]));
+        specifics.seasons[1].episodes[1].publish_date = date(20);
+        let history = vec![episode_seen(SeenState::Completed, date(1), 1, 1)];
+        assert_eq!(
+            next_unseen_show_episode(&specifics, &history, date(10).unwrap()),
+            None
+        );
+        assert_eq!(
+            next_unseen_show_episode(&specifics, &history, date(20).unwrap()),
+            Some((1, 2))
+        );
+    }
+
+    #[test]
+    fn quick_log_date_prefers_the_explicit_date() {
+        let today = date(10).unwrap();
+        for action in [UserQuickLogAction::Now, UserQuickLogAction::InThePast] {
+            assert_eq!(quick_log_date(date(3), action, today), date(3));
+        }
+        assert_eq!(
+            quick_log_date(None, UserQuickLogAction::Now, today),
+            Some(today)
+        );
+        assert_eq!(
+            quick_log_date(None, UserQuickLogAction::InThePast, today),
+            None
+        );
+    }
+
+    #[test]
+    fn active_announcements_query_filters_by_window_and_user() {
+        let sql = |user_id| {
+            active_announcements_query(user_id, Utc::now())
+                .build(DatabaseBackend::Postgres)
+                .to_string()
+        };
+        let for_user = sql("usr_1");
+        assert!(for_user.contains(r#""announcement"."starts_at" <="#));
+        assert!(for_user.contains(r#""announcement"."ends_at" IS NULL"#));
+        assert!(for_user.contains(r#""announcement"."ends_at" >"#));
+        assert!(for_user.contains(r#"NOT"#));
+        assert!(for_user.contains(r#"'usr_1' = ANY("dismissed_by")"#));
+        let for_other = sql("usr_2");
+        assert!(for_other.contains(r#"'usr_2' = ANY("dismissed_by")"#));
+        assert!(!for_other.contains("usr_1"));
+    }
+
+    #[test]
+    fn dismiss_announcement_query_only_appends_the_user() {
+        let sql = dismiss_announcement_query("ann_1", "usr_1")
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#"ARRAY_APPEND("dismissed_by", 'usr_1')"#));
+        assert!(sql.contains(r#""announcement"."id" = 'ann_1'"#));
+        assert!(sql.contains(r#"'usr_1' = ANY("dismissed_by")"#));
+        assert!(!sql.contains("usr_2"));
+    }
+
+    fn s3_image(key: &str) -> MetadataImage {
+        MetadataImage {
+            url: StoredUrl::S3(key.to_owned()),
+            is_user_provided: true,
+        }
+    }
+
+    #[test]
+    fn images_reference_key_matches_whole_keys() {
+        let images = vec![
+            s3_image("metadata-images/abc.png"),
+            MetadataImage {
+                url: StoredUrl::Url("https://example.com/metadata-images/def.png".to_owned()),
+                ..Default::default()
+            },
+        ];
+        assert!(images_reference_key(&images, "metadata-images/abc.png"));
+        assert!(!images_reference_key(&images, "metadata-images/ab"));
+        assert!(!images_reference_key(&images, "images/abc.png"));
+        assert!(!images_reference_key(&images, "metadata-images/def.png"));
+        assert!(!images_reference_key(&[], "metadata-images/abc.png"));
+    }
+
+    #[test]
+    fn metadata_image_key_is_shared_by_identical_uploads() {
+        let first = metadata_image_key(b"cover", Some("PNG"));
+        let second = metadata_image_key(b"cover", Some("png"));
+        assert_eq!(first, second);
+        assert!(first.starts_with("metadata-images/") && first.ends_with(".png"));
+        assert_ne!(first, metadata_image_key(b"another cover", Some("png")));
+        assert_eq!(
+            metadata_image_key(b"cover", None),
+            metadata_image_key(b"cover", Some("png"))
+        );
+    }
+
+    #[test]
+    fn image_shared_by_two_items_stays_referenced_after_one_removes_it() {
+        let key = metadata_image_key(b"cover", Some("jpg"));
+        let first = with_uploaded_image(vec![], key.clone());
+        let second = with_uploaded_image(
+            vec![MetadataImage {
+                url: StoredUrl::Url("https://provider.example/cover.jpg".to_owned()),
+                ..Default::default()
+            }],
+            key.clone(),
+        );
+        assert_eq!(first, vec![s3_image(&key)]);
+        assert_eq!(second[0], s3_image(&key));
+        assert_eq!(with_uploaded_image(second.clone(), key.clone()), second);
+        // DEV: The first item removes the image, the stored object must be kept
+        let first = first
+            .into_iter()
+            .filter(|i| i.url != StoredUrl::S3(key.clone()))
+            .collect_vec();
+        assert!(!images_reference_key(&first, &key));
+        assert!(images_reference_key(&second, &key));
+    }
+
+    fn podcast(numbers: &[i32]) -> metadata::Model {
+        metadata::Model {
+            lot: MediaLot::Podcast,
+            podcast_specifics: Some(PodcastSpecifics {
+                episodes: numbers
+                    .iter()
+                    .map(|number| PodcastEpisode {
+                        number: *number,
+                        ..Default::default()
+                    })
+                    .collect(),
+                total_episodes: numbers.len(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn podcast_seen(state: SeenState, episode: i32) -> seen::Model {
+        seen::Model {
+            podcast_extra_information: Some(SeenPodcastExtraInformation { episode }),
+            ..seen(state, date(1))
+        }
+    }
+
+    fn next_episode(season: Option<i32>, episode: i32) -> Option<UserMediaNextEntry> {
+        Some(UserMediaNextEntry {
+            season,
+            episode: Some(episode),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn first_entry_starts_shows_and_podcasts_at_the_beginning() {
+        let metadata = show(&[(0, "Specials", 3), (1, "Season 1", 2)]);
+        assert_eq!(
+            first_entry(&metadata, &HashSet::new()),
+            next_episode(Some(1), 1)
+        );
+        let metadata = podcast(&[3, 1, 2]);
+        assert_eq!(
+            first_entry(&metadata, &HashSet::new()),
+            next_episode(None, 1)
+        );
+        assert_eq!(first_entry(&podcast(&[]), &HashSet::new()), None);
+    }
+
+    #[test]
+    fn next_entry_is_none_when_everything_is_finished() {
+        let metadata = show(&[(1, "Season 1", 2)]);
+        let last = episode_seen(SeenState::Completed, date(1), 1, 2);
+        assert_eq!(next_entry_after(&last, &metadata, &HashSet::new()), None);
+        let metadata = podcast(&[1, 2, 3]);
+        let history = vec![
+            podcast_seen(SeenState::Completed, 3),
+            podcast_seen(SeenState::Skipped, 2),
+            podcast_seen(SeenState::Completed, 1),
+        ];
+        let listened = listened_podcast_episodes(&history);
+        assert_eq!(next_entry_after(&history[0], &metadata, &listened), None);
+    }
+
+    #[test]
+    fn next_entry_of_shows_continues_after_a_gap() {
+        let metadata = show(&[(1, "Season 1", 3), (2, "Season 2", 2)]);
+        let last = episode_seen(SeenState::Completed, date(2), 1, 3);
+        assert_eq!(
+            next_entry_after(&last, &metadata, &HashSet::new()),
+            next_episode(Some(2), 1)
+        );
+    }
+
+    #[test]
+    fn next_entry_of_podcasts_is_the_first_unlistened_episode() {
+        let metadata = podcast(&[1, 2, 3, 4]);
+        let history = vec![
+            podcast_seen(SeenState::Completed, 3),
+            podcast_seen(SeenState::Dropped, 2),
+            podcast_seen(SeenState::Completed, 1),
+        ];
+        let listened = listened_podcast_episodes(&history);
+        assert_eq!(listened, HashSet::from([1, 3]));
+        assert_eq!(
+            next_entry_after(&history[0], &metadata, &listened),
+            next_episode(None, 2)
+        );
+        // DEV: An episode that is underway is not suggested as its own next entry
+        let in_progress = podcast_seen(SeenState::InProgress, 2);
+        assert_eq!(
+            next_entry_after(&in_progress, &metadata, &listened),
+            next_episode(None, 4)
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_to_one_host_are_capped_while_other_hosts_proceed() {
+        #[derive(Default)]
+        struct Receiver {
+            in_flight: HashMap<String, usize>,
+            peak: HashMap<String, usize>,
+            peak_overall: usize,
+            received: usize,
+        }
+        let receiver = std::sync::Mutex::new(Receiver::default());
+        let deliveries = (0..6)
+            .map(|_| "ntfy.example.com")
+            .chain((0..2).map(|_| "gotify.example.com"))
+            .map(|host| (host.to_owned(), host.to_owned()))
+            .collect_vec();
+        send_limited_per_host(deliveries, 2, |host| {
+            let receiver = &receiver;
+            async move {
+                {
+                    let mut r = receiver.lock().unwrap();
+                    *r.in_flight.entry(host.clone()).or_default() += 1;
+                    let in_flight = r.in_flight[&host];
+                    let peak = r.peak.entry(host.clone()).or_default();
+                    *peak = (*peak).max(in_flight);
+                    let overall: usize = r.in_flight.values().sum();
+                    r.peak_overall = r.peak_overall.max(overall);
+                }
+                // DEV: A slow receiver, so that sends pile up if they are not limited
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                let mut r = receiver.lock().unwrap();
+                *r.in_flight.get_mut(&host).unwrap() -= 1;
+                r.received += 1;
+                Ok(())
+            }
+        })
+        .await;
+        let receiver = receiver.into_inner().unwrap();
+        assert_eq!(receiver.received, 8);
+        assert_eq!(receiver.peak["ntfy.example.com"], 2);
+        assert_eq!(receiver.peak["gotify.example.com"], 2);
+        assert_eq!(receiver.peak_overall, 4);
+    }
+
+    fn queued_delivery(message: &str, attempts: i32) -> notification_delivery::Model {
+        let created_on = Utc.with_ymd_and_hms(2024, 3, 2, 12, 0, 0).unwrap();
+        notification_delivery::Model {
+            attempts,
+            created_on,
+            id: "ntd_1".to_owned(),
+            notification_platform_id: "platform".to_owned(),
+            message: message.to_owned(),
+            next_attempt_on: created_on,
+            delivered_on: None,
+            status: NotificationDeliveryStatus::Queued,
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn notifications_within_the_window_are_joined_into_one_delivery() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 2, 12, 0, 10).unwrap();
+        let window = Duration::seconds(30);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<notification_delivery::Model>::new()])
+            .append_query_results([vec![queued_delivery("Season 2 is out", 0)]])
+            .append_query_results([vec![queued_delivery("Season 2 is out", 0)]])
+            .append_query_results([vec![queued_delivery("Season 2 is out\nA new movie", 0)]])
+            .into_connection();
+        let platform = "platform".to_owned();
+        queue_notification_delivery(&platform, "Season 2 is out", window, now, &db)
+            .await
+            .unwrap();
+        queue_notification_delivery(&platform, "A new movie", window, now, &db)
+            .await
+            .unwrap();
+        let log = db.into_transaction_log();
+        let values = |i: usize| log[i].statements()[0].values.clone().unwrap().0;
+        // DEV: Only deliveries created since the start of the window are joined
+        assert!(values(0).contains(&Value::from(now - window)));
+        assert!(log[1].statements()[0].sql.starts_with("INSERT"));
+        assert!(values(1).contains(&Value::from(now + window)));
+        assert!(log[3].statements()[0].sql.starts_with("UPDATE"));
+        assert!(values(3).contains(&Value::from("Season 2 is out\nA new movie")));
+    }
+
+    #[test]
+    fn deliveries_resume_from_the_stored_retry_state() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 2, 12, 0, 0).unwrap();
+        let failed = attempted_notification_delivery(
+            queued_delivery("Season 2 is out", 1),
+            Some("Connection refused".to_owned()),
+            false,
+            now,
+        );
+        assert_eq!(failed.attempts, ActiveValue::Set(2));
+        assert_eq!(
+            failed.status,
+            ActiveValue::Unchanged(NotificationDeliveryStatus::Queued)
+        );
+        assert_eq!(
+            failed.next_attempt_on,
+            ActiveValue::Set(now + Duration::minutes(5))
+        );
+        // DEV: A worker that starts after a crash picks up whatever is due from the table
+        let due = due_notification_deliveries(now + Duration::minutes(5), 10)
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(due.contains(r#""status" = 'queued'"#));
+        assert!(due.contains(r#""next_attempt_on" <= '2024-03-02 12:05:00"#));
+        // DEV: A claim of a crashed worker runs out, then the delivery is due again
+        let claim = claimed_notification_delivery(&"ntd_1".to_owned(), now)
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(claim.contains(r#"SET "next_attempt_on" = '2024-03-02 12:15:00"#));
+        assert!(claim.contains(r#""next_attempt_on" <= '2024-03-02 12:00:00"#));
+        let exhausted = attempted_notification_delivery(
+            queued_delivery("Season 2 is out", 4),
+            Some("Connection refused".to_owned()),
+            false,
+            now,
+        );
+        assert_eq!(
+            exhausted.status,
+            ActiveValue::Set(NotificationDeliveryStatus::DeadLetter)
+        );
+    }
+
+    #[tokio::test]
+    async fn deleting_media_counts_every_other_user_with_activity_once() {
+        let users = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| tuple_row(vec![Value::from(*id)]))
+                .collect_vec()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([users(&["admin", "user_1"])])
+            .append_query_results([users(&["user_1"])])
+            .append_query_results([users(&["admin", "user_2"])])
+            .append_query_results([users(&["user_3"])])
+            .into_connection();
+        let others =
+            other_users_with_metadata_activity(&"admin".to_owned(), &"meta_1".to_owned(), &db)
+                .await
+                .unwrap();
+        // DEV: The caller's own activity does not need `force`
+        assert_eq!(
+            others.into_iter().sorted().collect_vec(),
+            ["user_1", "user_2", "user_3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn works_of_a_creator_are_counted_and_paged_per_type() {
+        let count = |n: i64| BTreeMap::from([("num_items".to_owned(), Value::BigInt(Some(n)))]);
+        let lot_count = |lot: MediaLot, n: i64| {
+            BTreeMap::from([
+                ("lot".to_owned(), Value::from(lot)),
+                ("count".to_owned(), Value::BigInt(Some(n))),
+            ])
+        };
+        let movies = (1..=PAGE_SIZE)
+            .map(|i| tuple_row(vec![Value::from(format!("movie_{}", i))]))
+            .collect_vec();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[count(1)]])
+            .append_query_results([[lot_count(MediaLot::Book, 3), lot_count(MediaLot::Movie, 25)]])
+            .append_query_results([[count(25)]])
+            .append_query_results([movies])
+            .into_connection();
+        let input = PersonWorksInput {
+            person_id: "person_1".to_owned(),
+            lot: Some(MediaLot::Movie),
+            page: None,
+        };
+        let works = person_works(input, &db).await.unwrap();
+        assert_eq!(
+            works.lots.iter().map(|l| (l.lot, l.count)).collect_vec(),
+            [(MediaLot::Book, 3), (MediaLot::Movie, 25)]
+        );
+        assert_eq!(works.works.details.total, 25);
+        assert_eq!(works.works.details.next_page, Some(2));
+        assert_eq!(works.works.items.len(), 20);
+        let log = db.into_transaction_log();
+        // DEV: The counts cover every type, only the page is limited to movies
+        assert!(log[1].statements()[0]
+            .sql
+            .contains(r#"GROUP BY "metadata"."lot""#));
+        let page = &log[3].statements()[0];
+        assert!(page.sql.contains(r#""metadata"."lot" = $"#));
+        assert!(page
+            .values
+            .as_ref()
+            .unwrap()
+            .0
+            .contains(&Value::from(MediaLot::Movie)));
+        assert!(page.sql.contains("LIMIT"));
+    }
+
+    #[test]
+    fn private_reviews_are_only_visible_to_their_author() {
+        let sql = visible_media_reviews(&"user_1".to_owned(), &"meta_1".to_owned())
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#""review"."metadata_id" = 'meta_1'"#));
+        assert!(
+            sql.contains(r#"("review"."user_id" = 'user_1' OR "review"."visibility" = 'public')"#)
+        );
+    }
+}
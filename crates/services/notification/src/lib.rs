@@ -1,4 +1,7 @@
-use std::env;
+use std::{
+    env,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use anyhow::{anyhow, Result};
 use askama::Template;
@@ -12,11 +15,57 @@ use lettre::{
 };
 use reqwest::{
     header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE},
-    Client,
+    Client, Url,
 };
 use serde::{Deserialize, Serialize};
 use user_models::NotificationPlatformSpecifics;
 
+static DELIVERED_NOTIFICATIONS: AtomicU64 = AtomicU64::new(0);
+static FAILED_NOTIFICATION_ATTEMPTS: AtomicU64 = AtomicU64::new(0);
+
+pub fn record_notification_delivery(successful: bool) {
+    match successful {
+        true => DELIVERED_NOTIFICATIONS.fetch_add(1, Ordering::Relaxed),
+        false => FAILED_NOTIFICATION_ATTEMPTS.fetch_add(1, Ordering::Relaxed),
+    };
+}
+
+/// The number of notifications delivered and the number of failed attempts since
+/// the last restart.
+pub fn notification_delivery_counts() -> (u64, u64) {
+    (
+        DELIVERED_NOTIFICATIONS.load(Ordering::Relaxed),
+        FAILED_NOTIFICATION_ATTEMPTS.load(Ordering::Relaxed),
+    )
+}
+
+/// The host that a notification to this platform is sent to. Sends to the same
+/// host are limited so that self hosted servers are not flooded.
+pub fn notification_platform_host(
+    specifics: &NotificationPlatformSpecifics,
+    config: &AppConfig,
+) -> String {
+    let host_of = |url: &str| {
+        Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_owned()))
+            .unwrap_or_else(|| url.to_owned())
+    };
+    match specifics {
+        NotificationPlatformSpecifics::Apprise { url, .. }
+        | NotificationPlatformSpecifics::Discord { url }
+        | NotificationPlatformSpecifics::Gotify { url, .. } => host_of(url),
+        NotificationPlatformSpecifics::Ntfy { url, .. } => {
+            host_of(url.as_deref().unwrap_or("https://ntfy.sh"))
+        }
+        NotificationPlatformSpecifics::PushBullet { .. } => "api.pushbullet.com".to_owned(),
+        NotificationPlatformSpecifics::PushOver { .. } => "api.pushover.net".to_owned(),
+        NotificationPlatformSpecifics::PushSafer { .. } => "www.pushsafer.com".to_owned(),
+        NotificationPlatformSpecifics::Email { .. } => config.server.smtp.server.clone(),
+        NotificationPlatformSpecifics::Telegram { .. } => "api.telegram.org".to_owned(),
+    }
+}
+
 // TODO: Allow formatting messages
 pub async fn send_notification(
     specifics: NotificationPlatformSpecifics,
@@ -40,6 +89,7 @@ pub async fn send_notification(
                 }))
                 .send()
                 .await
+                .and_then(|r| r.error_for_status())
                 .map_err(|e| anyhow!(e))?;
         }
         NotificationPlatformSpecifics::Discord { url } => {
@@ -52,6 +102,7 @@ pub async fn send_notification(
                 }))
                 .send()
                 .await
+                .and_then(|r| r.error_for_status())
                 .map_err(|e| anyhow!(e))?;
         }
         NotificationPlatformSpecifics::Gotify {
@@ -74,6 +125,7 @@ pub async fn send_notification(
                 }))
                 .send()
                 .await
+                .and_then(|r| r.error_for_status())
                 .map_err(|e| anyhow!(e))?;
         }
         NotificationPlatformSpecifics::Ntfy {
@@ -106,6 +158,7 @@ pub async fn send_notification(
                 .body(msg.to_owned())
                 .send()
                 .await
+                .and_then(|r| r.error_for_status())
                 .map_err(|e| anyhow!(e))?;
         }
         NotificationPlatformSpecifics::PushBullet { api_token } => {
@@ -119,6 +172,7 @@ pub async fn send_notification(
                 }))
                 .send()
                 .await
+                .and_then(|r| r.error_for_status())
                 .map_err(|e| anyhow!(e))?;
         }
         NotificationPlatformSpecifics::PushOver { key, app_key } => {
@@ -131,6 +185,7 @@ pub async fn send_notification(
                     }))
                     .send()
                     .await
+                    .and_then(|r| r.error_for_status())
                     .map_err(|e| anyhow!(e))?;
         }
         NotificationPlatformSpecifics::PushSafer { key } => {
@@ -143,6 +198,7 @@ pub async fn send_notification(
                 }))
                 .send()
                 .await
+                .and_then(|r| r.error_for_status())
                 .map_err(|e| anyhow!(e))?;
         }
         NotificationPlatformSpecifics::Email { email } => {
@@ -157,15 +213,14 @@ pub async fn send_notification(
             }
             .render()?;
 
-            let credentials = Credentials::new(
-                config.server.smtp.user.to_owned(),
-                config.server.smtp.password.to_owned(),
-            );
-
-            let mailer = SmtpTransport::relay(&config.server.smtp.server)
-                .unwrap()
-                .credentials(credentials)
-                .build();
+            let mut mailer = SmtpTransport::relay(&config.server.smtp.server)?;
+            if !config.server.smtp.user.is_empty() {
+                mailer = mailer.credentials(Credentials::new(
+                    config.server.smtp.user.to_owned(),
+                    config.server.smtp.password.to_owned(),
+                ));
+            }
+            let mailer = mailer.build();
 
             let mailbox = config.server.smtp.mailbox.parse().unwrap();
             let email = Message::builder()
@@ -195,6 +250,7 @@ pub async fn send_notification(
                 }))
                 .send()
                 .await
+                .and_then(|r| r.error_for_status())
                 .map_err(|e| anyhow!(e))?;
         }
     }
@@ -1,25 +1,27 @@
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashSet, sync::Arc};
 
 use application_utils::user_id_from_token;
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use async_graphql::{Error, Result};
-use chrono::{Timelike, Utc};
-use common_models::{DefaultCollection, StringIdObject, UpdateComplexJsonInput};
+use chrono::{Duration, Utc};
+use common_models::{
+    DefaultCollection, StringIdObject, UpdateComplexJsonInput, UserDashboardSection,
+};
 use common_utils::ryot_log;
 use database_models::{
-    access_link, integration, metadata, notification_platform,
-    prelude::{AccessLink, Integration, Metadata, NotificationPlatform, User},
+    access_link, default_user_preferences, integration, notification_platform,
+    prelude::{AccessLink, DefaultUserPreferences, Integration, NotificationPlatform, User},
     user,
 };
 use database_utils::{
     admin_account_guard, create_or_update_collection,
-    deploy_job_to_calculate_user_activities_and_summary, ilike_sql, pro_instance_guard,
-    revoke_access_link, user_by_id,
+    deploy_job_to_calculate_user_activities_and_summary, ilike_sql,
+    invalidate_user_dashboard_sections, pro_instance_guard, revoke_access_link, user_by_id,
+    user_recommendations,
 };
 use dependent_models::UserDetailsResult;
 use enum_meta::Meta;
 use enums::{IntegrationLot, IntegrationProvider, NotificationPlatformLot, UserLot};
-use fitness_models::UserUnitSystem;
 use itertools::Itertools;
 use jwt_service::{sign, AccessLinkClaims};
 use media_models::{
@@ -38,51 +40,69 @@ use openidconnect::{
     CsrfToken, Nonce, Scope, TokenResponse,
 };
 use sea_orm::{
-    prelude::Expr,
-    sea_query::{extension::postgres::PgExpr, Func},
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, Iterable, ModelTrait, Order,
-    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    prelude::Expr, sea_query::extension::postgres::PgExpr, ActiveModelTrait, ActiveValue,
+    ColumnTrait, EntityTrait, Iterable, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QueryTrait,
 };
 use supporting_service::SupportingService;
-use user_models::{
-    DashboardElementLot, GridPacking, NotificationPlatformSpecifics, UserPreferences,
-    UserReviewScale,
-};
+use user_models::{apply_preference_change, NotificationPlatformSpecifics, UserPreferences};
+
+const WEBHOOK_DEBUG_MODE_DURATION_HOURS: i64 = 1;
 
 fn empty_nonce_verifier(_nonce: Option<&Nonce>) -> Result<(), String> {
     Ok(())
 }
 
+fn is_preference_customized(customized_fields: &HashSet<String>, property: &str) -> bool {
+    customized_fields.iter().any(|field| {
+        field == property
+            || field.starts_with(&format!("{property}."))
+            || property.starts_with(&format!("{field}."))
+    })
+}
+
+/// A user who resets their preferences follows the defaults that are pushed
+/// afterwards again.
+fn reset_preferences(mut defaults: UserPreferences) -> UserPreferences {
+    defaults.customized_fields.clear();
+    defaults
+}
+
+fn customize_preference(
+    preferences: &mut UserPreferences,
+    input: &UpdateComplexJsonInput,
+) -> Result<()> {
+    apply_preference_change(preferences, input)?;
+    preferences.customized_fields.insert(input.property.clone());
+    Ok(())
+}
+
+/// The preferences of a user after the default value of a property is pushed to
+/// them, or `None` when they changed the property themselves or already have it.
+fn pushed_preferences(
+    preferences: &UserPreferences,
+    property: &str,
+    default_value: &serde_json::Value,
+) -> Result<Option<UserPreferences>> {
+    if is_preference_customized(&preferences.customized_fields, property) {
+        return Ok(None);
+    }
+    let mut value = serde_json::to_value(preferences)?;
+    let Some(target) = value.pointer_mut(&format!("/{}", property.replace('.', "/"))) else {
+        return Ok(None);
+    };
+    if *target == *default_value {
+        return Ok(None);
+    }
+    *target = default_value.clone();
+    Ok(Some(serde_json::from_value(value)?))
+}
+
 pub struct UserService(pub Arc<SupportingService>);
 
 impl UserService {
     pub async fn user_recommendations(&self, user_id: &String) -> Result<Vec<String>> {
-        let preferences = user_by_id(user_id, &self.0).await?.preferences;
-        let limit = preferences
-            .general
-            .dashboard
-            .into_iter()
-            .find(|d| d.section == DashboardElementLot::Recommendations)
-            .unwrap()
-            .num_elements;
-        let current_hour = Utc::now().hour();
-        let recs = Metadata::find()
-            .filter(metadata::Column::IsRecommendation.eq(true))
-            .order_by(
-                Expr::expr(Func::md5(
-                    Expr::col(metadata::Column::Title)
-                        .concat(Expr::val(user_id))
-                        .concat(Expr::val(current_hour)),
-                )),
-                Order::Desc,
-            )
-            .limit(limit)
-            .all(&self.0.db)
-            .await?
-            .into_iter()
-            .map(|r| r.id)
-            .collect_vec();
-        Ok(recs)
+        user_recommendations(user_id, &self.0).await
     }
 
     pub async fn user_access_links(&self, user_id: &String) -> Result<Vec<access_link::Model>> {
@@ -277,7 +297,7 @@ impl UserService {
             password: ActiveValue::Set(password),
             oidc_issuer_id: ActiveValue::Set(oidc_issuer_id),
             lot: ActiveValue::Set(lot),
-            preferences: ActiveValue::Set(UserPreferences::default()),
+            preferences: ActiveValue::Set(self.instance_default_preferences().await?),
             ..Default::default()
         };
         let user = user.insert(&self.0.db).await.unwrap();
@@ -398,362 +418,87 @@ impl UserService {
         user_id: String,
         input: UpdateComplexJsonInput,
     ) -> Result<bool> {
-        let err = || Error::new("Incorrect property value encountered");
         let user_model = user_by_id(&user_id, &self.0).await?;
         let mut preferences = user_model.preferences.clone();
         match input.property.is_empty() {
-            true => preferences = UserPreferences::default(),
-            false => {
-                let (left, right) = input.property.split_once('.').ok_or_else(err)?;
-                let value_bool = input.value.parse::<bool>();
-                match left {
-                    "fitness" => {
-                        let (left, right) = right.split_once('.').ok_or_else(err)?;
-                        match left {
-                            "measurements" => {
-                                let (left, right) = right.split_once('.').ok_or_else(err)?;
-                                match left {
-                                    "custom" => {
-                                        preferences.fitness.measurements.custom =
-                                            serde_json::from_str(&input.value).unwrap()
-                                    }
-                                    "inbuilt" => match right {
-                                        "weight" => {
-                                            preferences.fitness.measurements.inbuilt.weight =
-                                                value_bool.unwrap()
-                                        }
-                                        "body_mass_index" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .body_mass_index = value_bool.unwrap();
-                                        }
-                                        "total_body_water" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .total_body_water = value_bool.unwrap();
-                                        }
-                                        "muscle" => {
-                                            preferences.fitness.measurements.inbuilt.muscle =
-                                                value_bool.unwrap();
-                                        }
-                                        "lean_body_mass" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .lean_body_mass = value_bool.unwrap();
-                                        }
-                                        "body_fat" => {
-                                            preferences.fitness.measurements.inbuilt.body_fat =
-                                                value_bool.unwrap();
-                                        }
-                                        "bone_mass" => {
-                                            preferences.fitness.measurements.inbuilt.bone_mass =
-                                                value_bool.unwrap();
-                                        }
-                                        "visceral_fat" => {
-                                            preferences.fitness.measurements.inbuilt.visceral_fat =
-                                                value_bool.unwrap();
-                                        }
-                                        "waist_circumference" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .waist_circumference = value_bool.unwrap();
-                                        }
-                                        "waist_to_height_ratio" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .waist_to_height_ratio = value_bool.unwrap();
-                                        }
-                                        "hip_circumference" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .hip_circumference = value_bool.unwrap();
-                                        }
-                                        "waist_to_hip_ratio" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .waist_to_hip_ratio = value_bool.unwrap();
-                                        }
-                                        "chest_circumference" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .chest_circumference = value_bool.unwrap();
-                                        }
-                                        "thigh_circumference" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .thigh_circumference = value_bool.unwrap();
-                                        }
-                                        "biceps_circumference" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .biceps_circumference = value_bool.unwrap();
-                                        }
-                                        "neck_circumference" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .neck_circumference = value_bool.unwrap();
-                                        }
-                                        "body_fat_caliper" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .body_fat_caliper = value_bool.unwrap();
-                                        }
-                                        "chest_skinfold" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .chest_skinfold = value_bool.unwrap();
-                                        }
-                                        "abdominal_skinfold" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .abdominal_skinfold = value_bool.unwrap();
-                                        }
-                                        "thigh_skinfold" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .thigh_skinfold = value_bool.unwrap();
-                                        }
-                                        "basal_metabolic_rate" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .basal_metabolic_rate = value_bool.unwrap();
-                                        }
-                                        "total_daily_energy_expenditure" => {
-                                            preferences
-                                                .fitness
-                                                .measurements
-                                                .inbuilt
-                                                .total_daily_energy_expenditure =
-                                                value_bool.unwrap();
-                                        }
-                                        "calories" => {
-                                            preferences.fitness.measurements.inbuilt.calories =
-                                                value_bool.unwrap();
-                                        }
-                                        _ => return Err(err()),
-                                    },
-                                    _ => return Err(err()),
-                                }
-                            }
-                            "exercises" => match right.split_once('.') {
-                                Some((left, right)) => match left {
-                                    "set_rest_timers" => {
-                                        let value = input.value.parse().ok();
-                                        let set_rest_timers =
-                                            &mut preferences.fitness.exercises.set_rest_timers;
-                                        match right {
-                                            "drop" => set_rest_timers.drop = value,
-                                            "normal" => set_rest_timers.normal = value,
-                                            "warmup" => set_rest_timers.warmup = value,
-                                            "failure" => set_rest_timers.failure = value,
-                                            _ => return Err(err()),
-                                        }
-                                    }
-                                    _ => return Err(err()),
-                                },
-                                None => match right {
-                                    "unit_system" => {
-                                        preferences.fitness.exercises.unit_system =
-                                            UserUnitSystem::from_str(&input.value).unwrap();
-                                    }
-                                    _ => return Err(err()),
-                                },
-                            },
-                            "logging" => match right {
-                                "show_details_while_editing" => {
-                                    preferences.fitness.logging.show_details_while_editing =
-                                        value_bool.unwrap();
-                                }
-                                _ => return Err(err()),
-                            },
-                            _ => return Err(err()),
-                        }
-                    }
-                    "features_enabled" => {
-                        let (left, right) = right.split_once('.').ok_or_else(err)?;
-                        match left {
-                            "others" => match right {
-                                "collections" => {
-                                    preferences.features_enabled.others.collections =
-                                        value_bool.unwrap()
-                                }
-                                "calendar" => {
-                                    preferences.features_enabled.others.calendar =
-                                        value_bool.unwrap()
-                                }
-                                _ => return Err(err()),
-                            },
-                            "fitness" => match right {
-                                "enabled" => {
-                                    preferences.features_enabled.fitness.enabled =
-                                        value_bool.unwrap()
-                                }
-                                "measurements" => {
-                                    preferences.features_enabled.fitness.measurements =
-                                        value_bool.unwrap()
-                                }
-                                "workouts" => {
-                                    preferences.features_enabled.fitness.workouts =
-                                        value_bool.unwrap()
-                                }
-                                "templates" => {
-                                    preferences.features_enabled.fitness.templates =
-                                        value_bool.unwrap()
-                                }
-                                _ => return Err(err()),
-                            },
-                            "media" => {
-                                match right {
-                                    "enabled" => {
-                                        preferences.features_enabled.media.enabled =
-                                            value_bool.unwrap()
-                                    }
-                                    "audio_book" => {
-                                        preferences.features_enabled.media.audio_book =
-                                            value_bool.unwrap()
-                                    }
-                                    "book" => {
-                                        preferences.features_enabled.media.book =
-                                            value_bool.unwrap()
-                                    }
-                                    "movie" => {
-                                        preferences.features_enabled.media.movie =
-                                            value_bool.unwrap()
-                                    }
-                                    "podcast" => {
-                                        preferences.features_enabled.media.podcast =
-                                            value_bool.unwrap()
-                                    }
-                                    "show" => {
-                                        preferences.features_enabled.media.show =
-                                            value_bool.unwrap()
-                                    }
-                                    "video_game" => {
-                                        preferences.features_enabled.media.video_game =
-                                            value_bool.unwrap()
-                                    }
-                                    "visual_novel" => {
-                                        preferences.features_enabled.media.visual_novel =
-                                            value_bool.unwrap()
-                                    }
-                                    "manga" => {
-                                        preferences.features_enabled.media.manga =
-                                            value_bool.unwrap()
-                                    }
-                                    "anime" => {
-                                        preferences.features_enabled.media.anime =
-                                            value_bool.unwrap()
-                                    }
-                                    "people" => {
-                                        preferences.features_enabled.media.people =
-                                            value_bool.unwrap()
-                                    }
-                                    "groups" => {
-                                        preferences.features_enabled.media.groups =
-                                            value_bool.unwrap()
-                                    }
-                                    "genres" => {
-                                        preferences.features_enabled.media.genres =
-                                            value_bool.unwrap()
-                                    }
-                                    _ => return Err(err()),
-                                };
-                            }
-                            _ => return Err(err()),
-                        }
-                    }
-                    "notifications" => match right {
-                        "to_send" => {
-                            preferences.notifications.to_send =
-                                serde_json::from_str(&input.value).unwrap();
-                        }
-                        "enabled" => {
-                            preferences.notifications.enabled = value_bool.unwrap();
-                        }
-                        _ => return Err(err()),
-                    },
-                    "general" => match right {
-                        "review_scale" => {
-                            preferences.general.review_scale =
-                                UserReviewScale::from_str(&input.value).unwrap();
-                        }
-                        "grid_packing" => {
-                            preferences.general.grid_packing =
-                                GridPacking::from_str(&input.value).unwrap();
-                        }
-                        "display_nsfw" => {
-                            preferences.general.display_nsfw = value_bool.unwrap();
-                        }
-                        "dashboard" => {
-                            preferences.general.dashboard =
-                                serde_json::from_str(&input.value).unwrap();
-                        }
-                        "disable_integrations" => {
-                            preferences.general.disable_integrations = value_bool.unwrap();
-                        }
-                        "persist_queries" => {
-                            preferences.general.persist_queries = value_bool.unwrap();
-                        }
-                        "disable_navigation_animation" => {
-                            preferences.general.disable_navigation_animation = value_bool.unwrap();
-                        }
-                        "disable_videos" => {
-                            preferences.general.disable_videos = value_bool.unwrap();
-                        }
-                        "disable_watch_providers" => {
-                            preferences.general.disable_watch_providers = value_bool.unwrap();
-                        }
-                        "watch_providers" => {
-                            preferences.general.watch_providers =
-                                serde_json::from_str(&input.value).unwrap();
-                        }
-                        "disable_reviews" => {
-                            preferences.general.disable_reviews = value_bool.unwrap();
-                        }
-                        _ => return Err(err()),
-                    },
-                    _ => return Err(err()),
-                };
-            }
+            true => preferences = reset_preferences(self.instance_default_preferences().await?),
+            false => customize_preference(&mut preferences, &input)?,
         };
         let mut user_model: user::ActiveModel = user_model.into();
         user_model.preferences = ActiveValue::Set(preferences);
         user_model.update(&self.0.db).await?;
+        invalidate_user_dashboard_sections(
+            &user_id,
+            &UserDashboardSection::iter().collect_vec(),
+            &self.0,
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn instance_default_preferences(&self) -> Result<UserPreferences> {
+        let defaults = DefaultUserPreferences::find().one(&self.0.db).await?;
+        Ok(defaults.map(|d| d.preferences).unwrap_or_default())
+    }
+
+    pub async fn default_user_preferences(&self, user_id: &String) -> Result<UserPreferences> {
+        admin_account_guard(user_id, &self.0).await?;
+        self.instance_default_preferences().await
+    }
+
+    pub async fn update_default_user_preference(
+        &self,
+        user_id: String,
+        input: UpdateComplexJsonInput,
+    ) -> Result<bool> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let existing = DefaultUserPreferences::find().one(&self.0.db).await?;
+        let mut preferences = existing.clone().map(|d| d.preferences).unwrap_or_default();
+        match input.property.is_empty() {
+            true => preferences = UserPreferences::default(),
+            false => apply_preference_change(&mut preferences, &input)?,
+        };
+        let mut to_save: default_user_preferences::ActiveModel = match existing {
+            Some(e) => e.into(),
+            None => Default::default(),
+        };
+        to_save.preferences = ActiveValue::Set(preferences);
+        to_save.last_updated_on = ActiveValue::Set(Utc::now());
+        to_save.save(&self.0.db).await?;
         Ok(true)
     }
 
+    /// Copy a single property from the default preferences to every user who has
+    /// not changed it themselves. Returns the number of users that were updated.
+    pub async fn push_default_user_preference(
+        &self,
+        user_id: String,
+        property: String,
+    ) -> Result<usize> {
+        admin_account_guard(&user_id, &self.0).await?;
+        let err = || Error::new("Incorrect property encountered");
+        let pointer = format!("/{}", property.replace('.', "/"));
+        let defaults = serde_json::to_value(self.instance_default_preferences().await?)?;
+        let default_value = defaults.pointer(&pointer).cloned().ok_or_else(err)?;
+        let mut updated = 0;
+        for user in User::find().all(&self.0.db).await? {
+            let Some(preferences) =
+                pushed_preferences(&user.preferences, &property, &default_value)?
+            else {
+                continue;
+            };
+            let mut to_update: user::ActiveModel = user.into();
+            to_update.preferences = ActiveValue::Set(preferences);
+            to_update.update(&self.0.db).await?;
+            updated += 1;
+        }
+        ryot_log!(
+            debug,
+            "Pushed default preference {property:?} to {updated} users"
+        );
+        Ok(updated)
+    }
+
     pub async fn update_user_integration(
         &self,
         user_id: String,
@@ -781,6 +526,22 @@ impl UserService {
         if let Some(d) = input.is_disabled {
             db_integration.is_disabled = ActiveValue::Set(Some(d));
         }
+        if let Some(d) = input.debug_mode {
+            db_integration.debug_mode_until = ActiveValue::Set(match d {
+                true => Some(Utc::now() + Duration::hours(WEBHOOK_DEBUG_MODE_DURATION_HOURS)),
+                false => None,
+            });
+        }
+        if let Some(d) = input.expected_activity_days {
+            db_integration.expected_activity_days = ActiveValue::Set(Some(d).filter(|d| *d > 0));
+        }
+        if let Some(s) = input.snooze_alerts_until {
+            db_integration.alerts_snoozed_until = ActiveValue::Set(Some(s));
+        }
+        if let Some(c) = input.default_collection {
+            db_integration.default_collection =
+                ActiveValue::Set(Some(c.trim().to_owned()).filter(|c| !c.is_empty()));
+        }
         db_integration.update(&self.0.db).await?;
         Ok(true)
     }
@@ -801,9 +562,19 @@ impl UserService {
             }
             IntegrationProvider::Radarr
             | IntegrationProvider::Sonarr
-            | IntegrationProvider::JellyfinPush => IntegrationLot::Push,
+            | IntegrationProvider::JellyfinPush
+            | IntegrationProvider::Webhook => IntegrationLot::Push,
             _ => IntegrationLot::Sink,
         };
+        if input.provider == IntegrationProvider::Webhook
+            && input
+                .provider_specifics
+                .as_ref()
+                .and_then(|s| s.webhook_url.as_ref())
+                .is_none()
+        {
+            return Err(Error::new("A webhook url is required"));
+        }
         let to_insert = integration::ActiveModel {
             lot: ActiveValue::Set(lot),
             user_id: ActiveValue::Set(user_id),
@@ -811,6 +582,15 @@ impl UserService {
             minimum_progress: ActiveValue::Set(input.minimum_progress),
             maximum_progress: ActiveValue::Set(input.maximum_progress),
             provider_specifics: ActiveValue::Set(input.provider_specifics),
+            expected_activity_days: ActiveValue::Set(
+                input.expected_activity_days.filter(|d| *d > 0),
+            ),
+            default_collection: ActiveValue::Set(
+                input
+                    .default_collection
+                    .map(|c| c.trim().to_owned())
+                    .filter(|c| !c.is_empty()),
+            ),
             ..Default::default()
         };
         let integration = to_insert.insert(&self.0.db).await?;
@@ -1042,3 +822,82 @@ impl UserService {
         Ok(user)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(property: &str, value: &str) -> UpdateComplexJsonInput {
+        UpdateComplexJsonInput {
+            property: property.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn is_preference_customized_matches_parents_and_children() {
+        let fields = HashSet::from(["general.dashboard".to_owned()]);
+        assert!(is_preference_customized(&fields, "general.dashboard"));
+        assert!(is_preference_customized(&fields, "general"));
+        let fields = HashSet::from(["general".to_owned()]);
+        assert!(is_preference_customized(&fields, "general.display_nsfw"));
+        let fields = HashSet::from(["general.display".to_owned()]);
+        assert!(!is_preference_customized(&fields, "general.display_nsfw"));
+    }
+
+    #[test]
+    fn customized_preferences_are_not_overwritten_by_a_push() {
+        let mut preferences = UserPreferences::default();
+        customize_preference(&mut preferences, &change("general.display_nsfw", "false")).unwrap();
+        assert!(preferences
+            .customized_fields
+            .contains("general.display_nsfw"));
+        let pushed = pushed_preferences(
+            &preferences,
+            "general.display_nsfw",
+            &serde_json::json!(true),
+        );
+        assert_eq!(pushed.unwrap(), None);
+    }
+
+    #[test]
+    fn push_updates_preferences_that_were_not_customized() {
+        let mut preferences = UserPreferences::default();
+        customize_preference(&mut preferences, &change("general.display_nsfw", "false")).unwrap();
+        let pushed = pushed_preferences(
+            &preferences,
+            "general.disable_videos",
+            &serde_json::json!(true),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(pushed.general.disable_videos);
+        assert!(!pushed.general.display_nsfw);
+        assert_eq!(pushed.customized_fields, preferences.customized_fields);
+
+        let unchanged = pushed_preferences(
+            &preferences,
+            "general.disable_videos",
+            &serde_json::json!(false),
+        );
+        assert_eq!(unchanged.unwrap(), None);
+    }
+
+    #[test]
+    fn reset_preferences_receive_later_pushes() {
+        let mut defaults = UserPreferences::default();
+        defaults
+            .customized_fields
+            .insert("general.display_nsfw".to_owned());
+        let preferences = reset_preferences(defaults);
+        assert!(preferences.customized_fields.is_empty());
+        let pushed = pushed_preferences(
+            &preferences,
+            "general.display_nsfw",
+            &serde_json::json!(false),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(!pushed.general.display_nsfw);
+    }
+}
@@ -15,6 +15,10 @@ use media_models::{
 };
 use sea_orm::{prelude::DateTimeUtc, DatabaseConnection};
 
+mod validation;
+
+pub use validation::{Validate, ValidationErrors, MAX_REVIEW_TEXT_LENGTH};
+
 #[async_trait]
 pub trait MediaProvider {
     /// Search for media via a query.
@@ -40,6 +44,16 @@ pub trait MediaProvider {
         bail!("This provider does not support checking if metadata has been updated")
     }
 
+    /// Get suggestions that are too costly to fetch along with the details. These
+    /// are only fetched when a media item is refreshed.
+    #[allow(unused_variables)]
+    async fn metadata_suggestions(
+        &self,
+        identifier: &str,
+    ) -> Result<Vec<PartialMetadataWithoutId>> {
+        Ok(vec![])
+    }
+
     /// Search for people via a query.
     #[allow(unused_variables)]
     async fn people_search(
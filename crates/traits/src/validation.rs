@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+
+use async_graphql::{Error, ErrorExtensions, Name, Result as GraphqlResult, Value};
+use common_models::{BackendError, UpdateComplexJsonInput};
+use media_models::{
+    CreateOrUpdateCollectionInput, CreateOrUpdateReviewInput, CreateReviewCommentInput,
+    PostReviewCommentInput, ProgressUpdateInput,
+};
+use rust_decimal_macros::dec;
+use user_models::{apply_preference_change, UserPreferences, UNKNOWN_PREFERENCE_ERROR};
+
+pub const MAX_REVIEW_TEXT_LENGTH: usize = 50_000;
+
+/// What is wrong with an input, keyed by the path of the offending field.
+#[derive(Debug, Default)]
+pub struct ValidationErrors(BTreeMap<String, String>);
+
+impl ValidationErrors {
+    /// Record the message against the field unless the condition holds. Only the
+    /// first failure of each field is kept.
+    pub fn check(&mut self, field: &str, is_valid: bool, message: impl Into<String>) {
+        if !is_valid {
+            self.0
+                .entry(field.to_owned())
+                .or_insert_with(|| message.into());
+        }
+    }
+
+    pub fn into_result(self) -> GraphqlResult<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let fields = Value::Object(
+            self.0
+                .into_iter()
+                .map(|(field, message)| (Name::new(field), Value::from(message)))
+                .collect(),
+        );
+        let code = BackendError::ValidationFailed.to_string();
+        Err(Error::new(code.clone()).extend_with(|_, e| {
+            e.set("code", code);
+            e.set("fields", fields);
+        }))
+    }
+}
+
+/// Checks that resolvers run on their inputs before handing them to services.
+/// Failures are reported with the `VALIDATION_FAILED` code and a `fields`
+/// extension mapping each field path to a message.
+pub trait Validate {
+    fn validate_fields(&self, errors: &mut ValidationErrors);
+
+    fn validate(&self) -> GraphqlResult<()> {
+        let mut errors = ValidationErrors::default();
+        self.validate_fields(&mut errors);
+        errors.into_result()
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        for (idx, item) in self.iter().enumerate() {
+            let mut item_errors = ValidationErrors::default();
+            item.validate_fields(&mut item_errors);
+            for (field, message) in item_errors.0 {
+                errors.check(&format!("{idx}.{field}"), false, message);
+            }
+        }
+    }
+}
+
+fn is_not_negative<T: Default + PartialOrd>(value: Option<T>) -> bool {
+    value.map_or(true, |v| v >= T::default())
+}
+
+impl Validate for ProgressUpdateInput {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "metadataId",
+            !self.metadata_id.is_empty(),
+            "Metadata id is required",
+        );
+        errors.check(
+            "progress",
+            self.progress
+                .map_or(true, |p| p >= dec!(0) && p <= dec!(100)),
+            "Progress must be between 0 and 100",
+        );
+        errors.check(
+            "showEpisodeNumber",
+            self.show_season_number.is_some() == self.show_episode_number.is_some(),
+            "Season and episode numbers must be provided together",
+        );
+        errors.check(
+            "showSeasonNumber",
+            is_not_negative(self.show_season_number),
+            "Season number can not be negative",
+        );
+        errors.check(
+            "showEpisodeNumber",
+            is_not_negative(self.show_episode_number),
+            "Episode number can not be negative",
+        );
+        errors.check(
+            "podcastEpisodeNumber",
+            is_not_negative(self.podcast_episode_number),
+            "Episode number can not be negative",
+        );
+        errors.check(
+            "animeEpisodeNumber",
+            is_not_negative(self.anime_episode_number),
+            "Episode number can not be negative",
+        );
+        errors.check(
+            "mangaChapterNumber",
+            is_not_negative(self.manga_chapter_number),
+            "Chapter number can not be negative",
+        );
+        errors.check(
+            "mangaVolumeNumber",
+            is_not_negative(self.manga_volume_number),
+            "Volume number can not be negative",
+        );
+        errors.check(
+            "pagesRead",
+            is_not_negative(self.pages_read),
+            "Pages read can not be negative",
+        );
+        errors.check(
+            "minutesConsumed",
+            is_not_negative(self.minutes_consumed),
+            "Minutes consumed can not be negative",
+        );
+    }
+}
+
+impl Validate for CreateOrUpdateReviewInput {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "entityId",
+            !self.entity_id.is_empty(),
+            "Entity id is required",
+        );
+        errors.check(
+            "rating",
+            self.rating.is_some() || self.text.is_some(),
+            "At-least one of rating or review is required",
+        );
+        errors.check(
+            "rating",
+            is_not_negative(self.rating),
+            "Rating can not be negative",
+        );
+        if let Some(length) = self.text.as_ref().map(|t| t.chars().count()) {
+            errors.check(
+                "text",
+                length <= MAX_REVIEW_TEXT_LENGTH,
+                format!(
+                    "The review is {length} characters long, the limit is {MAX_REVIEW_TEXT_LENGTH}"
+                ),
+            );
+        }
+    }
+}
+
+impl Validate for CreateReviewCommentInput {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        let changes_existing = self.should_delete.unwrap_or_default()
+            || self.increment_likes.unwrap_or_default()
+            || self.decrement_likes.unwrap_or_default();
+        errors.check(
+            "commentId",
+            !changes_existing || self.comment_id.is_some(),
+            "Comment id is required to change an existing comment",
+        );
+        errors.check(
+            "text",
+            changes_existing || self.text.as_ref().map_or(false, |t| !t.trim().is_empty()),
+            "Comment text is required",
+        );
+    }
+}
+
+impl Validate for PostReviewCommentInput {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "text",
+            !self.text.trim().is_empty(),
+            "Comment text is required",
+        );
+    }
+}
+
+impl Validate for CreateOrUpdateCollectionInput {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "name",
+            !self.name.trim().is_empty(),
+            "Collection name is required",
+        );
+        errors.check(
+            "collaborators",
+            self.collaborators
+                .as_ref()
+                .map_or(true, |c| c.iter().all(|c| !c.is_empty())),
+            "Collaborator ids can not be empty",
+        );
+        if let Some(filter) = self.smart_filter.value() {
+            errors.check(
+                "smartFilter.publishYearEnd",
+                match (filter.publish_year_start, filter.publish_year_end) {
+                    (Some(start), Some(end)) => start <= end,
+                    _ => true,
+                },
+                "The end year can not be before the start year",
+            );
+            errors.check(
+                "smartFilter.minimumRating",
+                filter
+                    .minimum_rating
+                    .map_or(true, |r| r >= dec!(0) && r <= dec!(100)),
+                "Minimum rating must be between 0 and 100",
+            );
+        }
+    }
+}
+
+impl Validate for UpdateComplexJsonInput {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        // DEV: An empty property resets the preferences to the defaults
+        if self.property.is_empty() {
+            return;
+        }
+        let is_path = self
+            .property
+            .split('.')
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        errors.check("property", is_path, "Property must be a dot delimited path");
+        if !is_path {
+            return;
+        }
+        // DEV: Applying the change to throwaway preferences checks that the property
+        // exists and that the value parses into its type
+        if let Err(e) = apply_preference_change(&mut UserPreferences::default(), self) {
+            let field = match e.message == UNKNOWN_PREFERENCE_ERROR {
+                true => "property",
+                false => "value",
+            };
+            errors.check(field, false, e.message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::MaybeUndefined;
+    use media_models::SmartCollectionFilter;
+
+    use super::*;
+
+    fn preference_errors(property: &str, value: &str) -> BTreeMap<String, String> {
+        let input = UpdateComplexJsonInput {
+            property: property.to_owned(),
+            value: value.to_owned(),
+        };
+        let mut errors = ValidationErrors::default();
+        input.validate_fields(&mut errors);
+        errors.0
+    }
+
+    #[test]
+    fn preference_update_accepts_values_of_the_right_type() {
+        assert!(preference_errors("", "").is_empty());
+        assert!(preference_errors("general.display_nsfw", "true").is_empty());
+        assert!(preference_errors("general.review_scale", "OUT_OF_TEN").is_empty());
+        assert!(preference_errors("general.watch_providers", "[]").is_empty());
+        assert!(preference_errors("general.grid_packing", "normal").is_empty());
+    }
+
+    #[test]
+    fn preference_update_rejects_values_of_the_wrong_type() {
+        for (property, value) in [
+            ("general.display_nsfw", "yes"),
+            ("general.review_scale", "OUT_OF_SEVEN"),
+            ("general.dashboard", "{"),
+            ("features_enabled.media.anime", "1"),
+        ] {
+            let errors = preference_errors(property, value);
+            assert_eq!(errors.keys().collect::<Vec<_>>(), ["value"], "{property}");
+        }
+    }
+
+    #[test]
+    fn preference_update_rejects_unknown_properties() {
+        let errors = preference_errors("general.unknown", "true");
+        assert_eq!(errors.get("property").unwrap(), UNKNOWN_PREFERENCE_ERROR);
+        let errors = preference_errors("general..display_nsfw", "true");
+        assert_eq!(
+            errors.get("property").unwrap(),
+            "Property must be a dot delimited path"
+        );
+        assert!(!errors.contains_key("value"));
+    }
+
+    fn smart_collection_errors(filter: SmartCollectionFilter) -> BTreeMap<String, String> {
+        let input = CreateOrUpdateCollectionInput {
+            name: "Unwatched 80s movies".to_owned(),
+            description: None,
+            update_id: None,
+            collaborators: None,
+            information_template: None,
+            smart_filter: MaybeUndefined::Value(filter),
+        };
+        let mut errors = ValidationErrors::default();
+        input.validate_fields(&mut errors);
+        errors.0
+    }
+
+    #[test]
+    fn smart_collection_filters_need_a_valid_year_range_and_rating() {
+        let eighties = SmartCollectionFilter {
+            publish_year_start: Some(1980),
+            publish_year_end: Some(1989),
+            minimum_rating: Some(dec!(80)),
+            ..Default::default()
+        };
+        assert!(smart_collection_errors(eighties.clone()).is_empty());
+        let errors = smart_collection_errors(SmartCollectionFilter {
+            publish_year_start: Some(1989),
+            publish_year_end: Some(1980),
+            minimum_rating: Some(dec!(101)),
+            ..eighties
+        });
+        assert_eq!(
+            errors.keys().collect::<Vec<_>>(),
+            ["smartFilter.minimumRating", "smartFilter.publishYearEnd"]
+        );
+    }
+}
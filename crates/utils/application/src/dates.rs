@@ -0,0 +1,179 @@
+//! Helpers to bucket timestamps into calendar days. Everything that groups
+//! activity by day should go through these so that daylight saving transitions
+//! and leap days are handled the same way everywhere.
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc,
+};
+use chrono_tz::Tz;
+
+/// The calendar day on which an instant falls in the given timezone.
+pub fn local_date(instant: DateTime<Utc>, timezone: &Tz) -> NaiveDate {
+    instant.with_timezone(timezone).date_naive()
+}
+
+/// The day of the week, counted from Monday, and the hour at which an instant
+/// falls in the given timezone.
+pub fn local_weekday_and_hour(instant: DateTime<Utc>, timezone: &Tz) -> (usize, usize) {
+    let local = instant.with_timezone(timezone);
+    (
+        usize::try_from(local.weekday().num_days_from_monday()).unwrap(),
+        usize::try_from(local.hour()).unwrap(),
+    )
+}
+
+/// Convert a wall clock time in the given timezone to an instant. Times that
+/// occur twice when the clocks go back resolve to the first occurrence, and
+/// times skipped when the clocks go forward are moved past the gap.
+pub fn local_to_utc(date_time: NaiveDateTime, timezone: &Tz) -> DateTime<Utc> {
+    let mut candidate = date_time;
+    // DEV: No real timezone has a gap longer than a day, this just guards the loop
+    for _ in 0..(24 * 4) {
+        match timezone.from_local_datetime(&candidate) {
+            LocalResult::Single(dt) => return dt.with_timezone(&Utc),
+            LocalResult::Ambiguous(earliest, _) => return earliest.with_timezone(&Utc),
+            LocalResult::None => candidate += Duration::minutes(15),
+        }
+    }
+    DateTime::from_naive_utc_and_offset(date_time, Utc)
+}
+
+/// The instants at which a calendar day starts and ends in the given timezone.
+/// Days on which the clocks change are 23 or 25 hours long.
+pub fn local_day_bounds(date: NaiveDate, timezone: &Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+    let start = local_to_utc(date.and_time(midnight), timezone);
+    let end = date
+        .succ_opt()
+        .map(|next| local_to_utc(next.and_time(midnight), timezone))
+        .unwrap_or(start + Duration::days(1));
+    (start, end)
+}
+
+/// Every calendar day between the two dates, both inclusive. The dates are
+/// stepped by calendar day instead of by 24 hours so that no day is skipped
+/// or repeated around clock changes.
+pub fn date_range_buckets(start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+    start.iter_days().take_while(|d| *d <= end).collect()
+}
+
+/// The same day of the year in another year, for year over year comparisons.
+/// February 29 maps to February 28 when the other year is not a leap year.
+pub fn same_day_in_year(date: NaiveDate, year: i32) -> NaiveDate {
+    date.with_year(year)
+        .or_else(|| NaiveDate::from_ymd_opt(year, date.month(), date.day() - 1))
+        .unwrap_or(date)
+}
+
+/// The part of a year that corresponds to the part of the current year up to
+/// and including `today`, both ends inclusive.
+pub fn year_to_date_range(today: NaiveDate, year: i32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::from_ymd_opt(year, 1, 1)?;
+    Some((start, same_day_in_year(today, year)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::{America, Australia, Europe};
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn day_length(date: NaiveDate, timezone: &Tz) -> i64 {
+        let (start, end) = local_day_bounds(date, timezone);
+        (end - start).num_hours()
+    }
+
+    #[test]
+    fn local_day_bounds_has_23_and_25_hour_days() {
+        assert_eq!(day_length(date(2024, 3, 10), &America::New_York), 23);
+        assert_eq!(day_length(date(2024, 11, 3), &America::New_York), 25);
+        assert_eq!(day_length(date(2024, 3, 31), &Europe::Berlin), 23);
+        assert_eq!(day_length(date(2024, 10, 27), &Europe::Berlin), 25);
+        assert_eq!(day_length(date(2024, 10, 6), &Australia::Sydney), 23);
+        assert_eq!(day_length(date(2024, 4, 7), &Australia::Sydney), 25);
+        assert_eq!(day_length(date(2024, 3, 11), &America::New_York), 24);
+    }
+
+    #[test]
+    fn local_day_bounds_covers_every_instant_once() {
+        // DEV: Sao Paulo used to move its clocks forward at midnight, so that day
+        // started at 01:00
+        let cases = [
+            (America::New_York, 2024),
+            (Europe::Berlin, 2024),
+            (Australia::Sydney, 2024),
+            (America::Sao_Paulo, 2018),
+            (Tz::UTC, 2024),
+        ];
+        for (timezone, year) in cases {
+            let days = date_range_buckets(date(year, 1, 1), date(year, 12, 31));
+            assert_eq!(days.len(), 366 - usize::from(year % 4 != 0));
+            for pair in days.windows(2) {
+                let (start, end) = local_day_bounds(pair[0], &timezone);
+                assert_eq!(end, local_day_bounds(pair[1], &timezone).0);
+                assert_eq!(local_date(start, &timezone), pair[0]);
+                assert_eq!(local_date(end - Duration::seconds(1), &timezone), pair[0]);
+                assert!((23..=25).contains(&(end - start).num_hours()));
+            }
+        }
+    }
+
+    #[test]
+    fn local_weekday_and_hour_uses_the_local_clock() {
+        let instant = date(2024, 1, 1).and_hms_opt(3, 30, 0).unwrap().and_utc();
+        // DEV: Monday in UTC, still Sunday evening in New York
+        assert_eq!(local_weekday_and_hour(instant, &Tz::UTC), (0, 3));
+        assert_eq!(local_weekday_and_hour(instant, &America::New_York), (6, 22));
+    }
+
+    #[test]
+    fn local_to_utc_resolves_skipped_and_repeated_times() {
+        let at = |d: NaiveDate, h, m| d.and_hms_opt(h, m, 0).unwrap();
+        assert_eq!(
+            local_to_utc(at(date(2024, 3, 10), 2, 30), &America::New_York),
+            at(date(2024, 3, 10), 7, 0).and_utc()
+        );
+        assert_eq!(
+            local_to_utc(at(date(2024, 11, 3), 1, 30), &America::New_York),
+            at(date(2024, 11, 3), 5, 30).and_utc()
+        );
+        assert_eq!(
+            local_to_utc(at(date(2018, 11, 4), 0, 0), &America::Sao_Paulo),
+            at(date(2018, 11, 4), 3, 0).and_utc()
+        );
+    }
+
+    #[test]
+    fn date_range_buckets_includes_both_ends() {
+        assert_eq!(
+            date_range_buckets(date(2024, 2, 28), date(2024, 3, 1)),
+            vec![date(2024, 2, 28), date(2024, 2, 29), date(2024, 3, 1)]
+        );
+        assert!(date_range_buckets(date(2024, 3, 2), date(2024, 3, 1)).is_empty());
+    }
+
+    #[test]
+    fn same_day_in_year_maps_leap_day_to_february_28() {
+        assert_eq!(same_day_in_year(date(2024, 2, 29), 2023), date(2023, 2, 28));
+        assert_eq!(same_day_in_year(date(2024, 2, 29), 2028), date(2028, 2, 29));
+        assert_eq!(same_day_in_year(date(2024, 3, 1), 2023), date(2023, 3, 1));
+        assert_eq!(same_day_in_year(date(2023, 2, 28), 2024), date(2024, 2, 28));
+    }
+
+    #[test]
+    fn year_to_date_range_ends_on_the_same_day() {
+        assert_eq!(
+            year_to_date_range(date(2024, 2, 29), 2023),
+            Some((date(2023, 1, 1), date(2023, 2, 28)))
+        );
+        assert_eq!(
+            year_to_date_range(date(2024, 7, 4), 2024),
+            Some((date(2024, 1, 1), date(2024, 7, 4)))
+        );
+    }
+}
@@ -19,6 +19,9 @@ use reqwest::{
 };
 use sea_orm::Order;
 
+pub mod dates;
+pub mod text;
+
 pub fn user_id_from_token(token: &str, jwt_secret: &str) -> Result<String> {
     jwt_service::verify(token, jwt_secret)
         .map(|c| c.sub)
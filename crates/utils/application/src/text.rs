@@ -0,0 +1,414 @@
+//! Helpers to clean up the text that providers send. Some providers return
+//! descriptions as HTML with entities and some as plain text, everything is
+//! stored as plain text with paragraphs separated by a blank line.
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+const PREVIEW_LENGTH: usize = 300;
+
+// DEV: These end a paragraph, every other tag is dropped without a trace
+const BLOCK_TAGS: [&str; 17] = [
+    "p",
+    "div",
+    "li",
+    "ul",
+    "ol",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "pre",
+    "table",
+    "tr",
+    "section",
+    "article",
+];
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "middot" => '·',
+        "bull" => '•',
+        _ => return None,
+    })
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match entity {
+            Some((character, length)) => {
+                decoded.push(character);
+                rest = &rest[length..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let is_tag = after
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let Some(end) = after.find('>').filter(|_| is_tag) else {
+            text.push('<');
+            rest = after;
+            continue;
+        };
+        let name = after[..end]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if name == "br" {
+            text.push('\n');
+        } else if BLOCK_TAGS.contains(&name.as_str()) {
+            text.push_str("\n\n");
+        }
+        rest = &after[end + 1..];
+    }
+    text.push_str(rest);
+    text
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut paragraphs = vec![];
+    let mut current = vec![];
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !line.is_empty() {
+            current.push(line);
+        } else if !current.is_empty() {
+            paragraphs.push(current.join("\n"));
+            current.clear();
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Convert a description to plain text. Entities are decoded, tags are removed
+/// and runs of whitespace are collapsed while paragraph breaks are kept.
+pub fn normalize_description(description: &str) -> Option<String> {
+    // DEV: Entities are decoded first so that encoded tags are removed as well
+    let text = collapse_whitespace(&strip_tags(&decode_entities(description)));
+    Some(text).filter(|t| !t.is_empty())
+}
+
+/// The beginning of a description on a single line, cut at a word boundary so
+/// that it can be shown in lists.
+pub fn description_preview(description: &str) -> String {
+    let text = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    let Some((cut, _)) = text.char_indices().nth(PREVIEW_LENGTH) else {
+        return text;
+    };
+    let head = &text[..cut];
+    let head = match text[cut..].starts_with(' ') {
+        true => head,
+        false => head.rfind(' ').map_or(head, |space| &head[..space]),
+    };
+    format!("{}…", head.trim_end_matches([' ', ',', ';', ':']))
+}
+
+// DEV: Words that end like a plural but are not one, or are the same in both forms
+const SINGULAR_WORDS: &[&str] = &[
+    "mathematics",
+    "news",
+    "physics",
+    "politics",
+    "series",
+    "species",
+];
+
+// DEV: Plurals ending in "ies" whose singular ends in "ie" and not in "y"
+const IE_PLURALS: &[&str] = &[
+    "cookies", "indies", "lies", "movies", "pies", "rookies", "ties", "zombies",
+];
+
+fn singular_word(word: &str) -> String {
+    if SINGULAR_WORDS.contains(&word) {
+        return word.to_owned();
+    }
+    if let Some(stem) = word.strip_suffix("ies").filter(|s| s.len() > 1) {
+        if !IE_PLURALS.contains(&word) {
+            return format!("{stem}y");
+        }
+    }
+    let keeps_s = ["ss", "us", "is"].iter().any(|e| word.ends_with(e));
+    match word.strip_suffix('s') {
+        Some(stem) if stem.len() > 2 && !keeps_s => stem.to_owned(),
+        _ => word.to_owned(),
+    }
+}
+
+/// The form of a genre or tag name that is used to tell whether two names mean
+/// the same thing. Case, spacing and simple plurals are ignored, so "Time Travel"
+/// and "time-travels" both become "time travel".
+pub fn label_key(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|w| !w.is_empty())
+        .map(singular_word)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// DEV: Elided articles like "l'" are written without the trailing space
+fn leading_articles(language: &str) -> &'static [&'static str] {
+    match language {
+        "en" => &["the ", "a ", "an "],
+        "de" => &["der ", "die ", "das ", "ein ", "eine "],
+        "es" => &["el ", "la ", "los ", "las ", "un ", "una "],
+        "fr" => &["le ", "la ", "les ", "l'", "un ", "une "],
+        "it" => &[
+            "il ", "lo ", "la ", "i ", "gli ", "le ", "l'", "un ", "uno ", "una ",
+        ],
+        "nl" => &["de ", "het ", "een "],
+        "pt" => &["o ", "a ", "os ", "as ", "um ", "uma "],
+        _ => &[],
+    }
+}
+
+fn is_cjk_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+    )
+}
+
+/// Whether the text is written at least partly in Chinese, Japanese or Korean
+/// script.
+pub fn is_cjk(text: &str) -> bool {
+    text.chars().any(is_cjk_char)
+}
+
+/// The form of a title that is compared when sorting. Accents and width
+/// variants are folded, case is ignored and leading punctuation is dropped.
+pub fn sort_key(title: &str) -> String {
+    let folded = title
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| match c {
+            '‘' | '’' | 'ʼ' => '\'',
+            c => c,
+        })
+        .collect::<String>()
+        .to_lowercase();
+    let words = folded.split_whitespace().collect::<Vec<_>>().join(" ");
+    words
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_owned()
+}
+
+/// The form of a title that is compared to tell whether two sources mean the
+/// same work. On top of `sort_key`, punctuation is ignored so that
+/// "Dune: Messiah" and "Dune - Messiah" match.
+pub fn title_key(title: &str) -> String {
+    sort_key(title)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The title that a media is sorted by. Titles in Chinese, Japanese or Korean
+/// script use the romanized title when the provider sent one, and a leading
+/// article of any of the given languages is dropped so that
+/// "The Matrix" sorts under "m".
+pub fn sort_title(title: &str, romanized_title: Option<&str>, languages: &[String]) -> String {
+    let title = match romanized_title.filter(|r| !r.trim().is_empty()) {
+        Some(romanized) if is_cjk(title) => romanized,
+        _ => title,
+    };
+    let key = sort_key(title);
+    let article = languages
+        .iter()
+        .flat_map(|l| leading_articles(&l.to_lowercase()))
+        .filter(|a| key.len() > a.len() && key.starts_with(**a))
+        .max_by_key(|a| a.len());
+    match article {
+        Some(article) => sort_key(&key[article.len()..]),
+        None => key,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_description_removes_entity_encoded_tags() {
+        assert_eq!(
+            normalize_description("Before &lt;img src=x onerror=alert(1)&gt; after"),
+            Some("Before after".to_owned())
+        );
+        assert_eq!(
+            normalize_description("&lt;script&gt;alert(1)&lt;/script&gt;"),
+            Some("alert(1)".to_owned())
+        );
+    }
+
+    #[test]
+    fn normalize_description_keeps_paragraphs() {
+        assert_eq!(
+            normalize_description("<p>First   paragraph.</p><p>Second<br>line.</p>"),
+            Some("First paragraph.\n\nSecond\nline.".to_owned())
+        );
+        assert_eq!(
+            normalize_description("One.\n\n\n\nTwo."),
+            Some("One.\n\nTwo.".to_owned())
+        );
+    }
+
+    #[test]
+    fn normalize_description_keeps_text_that_looks_like_markup() {
+        assert_eq!(
+            normalize_description("1 &lt; 2 &amp;&amp; 3 > 2"),
+            Some("1 < 2 && 3 > 2".to_owned())
+        );
+        assert_eq!(normalize_description("<p> </p>"), None);
+    }
+
+    #[test]
+    fn label_key_ignores_case_spacing_and_plurals() {
+        assert_eq!(label_key("Time Travel"), "time travel");
+        assert_eq!(label_key(" time-travels "), "time travel");
+        assert_eq!(label_key("Comedies"), label_key("comedy"));
+        assert_eq!(label_key("Zombies"), label_key("zombie"));
+        assert_eq!(label_key("Movies"), "movie");
+        assert_eq!(label_key("Superhero_Movies"), "superhero movie");
+    }
+
+    #[test]
+    fn label_key_keeps_words_that_are_not_plurals() {
+        assert_eq!(label_key("Series"), "series");
+        assert_eq!(label_key("News"), "news");
+        assert_eq!(label_key("Comics"), label_key("comic"));
+        assert_eq!(label_key("Boss"), "boss");
+        assert_eq!(label_key("Virus"), "virus");
+        assert_eq!(label_key("Analysis"), "analysis");
+        assert_eq!(label_key("Bus"), "bus");
+    }
+
+    #[test]
+    fn title_key_ignores_punctuation_case_and_accents() {
+        assert_eq!(title_key("Dune: Messiah"), title_key("dune - messiah"));
+        assert_eq!(title_key("Les Misérables"), "les miserables");
+        assert_eq!(title_key("  The   Hobbit!"), "the hobbit");
+        assert_ne!(title_key("Dune"), title_key("Dune Messiah"));
+    }
+
+    #[test]
+    fn description_preview_cuts_at_word_boundary() {
+        let description = "word ".repeat(100);
+        let preview = description_preview(&description);
+        assert!(preview.ends_with("word…"));
+        assert!(preview.chars().count() <= PREVIEW_LENGTH + 1);
+        assert_eq!(description_preview("Short\n\ntext"), "Short text");
+    }
+
+    #[test]
+    fn is_cjk_detects_chinese_japanese_and_korean_script() {
+        assert!(is_cjk("東京"));
+        assert!(is_cjk("ソードアート・オンライン"));
+        assert!(is_cjk("한국어"));
+        assert!(is_cjk("Attack on Titan 進撃の巨人"));
+        assert!(!is_cjk("Pokémon"));
+        assert!(!is_cjk(""));
+    }
+
+    #[test]
+    fn sort_key_folds_accents_width_and_leading_punctuation() {
+        assert_eq!(sort_key("Amélie"), "amelie");
+        assert_eq!(sort_key("ＡＢＣ"), "abc");
+        assert_eq!(sort_key("...And   Justice for All"), "and justice for all");
+        assert_eq!(sort_key("’Salem’s Lot"), "salem's lot");
+    }
+
+    #[test]
+    fn sort_title_drops_leading_articles_of_the_given_languages() {
+        let languages = |l: &[&str]| l.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            sort_title("The Matrix", None, &languages(&["en"])),
+            "matrix"
+        );
+        assert_eq!(
+            sort_title("An Officer", None, &languages(&["EN"])),
+            "officer"
+        );
+        assert_eq!(sort_title("The 'Burbs", None, &languages(&["en"])), "burbs");
+        assert_eq!(
+            sort_title("Das Boot", None, &languages(&["en", "de"])),
+            "boot"
+        );
+        assert_eq!(
+            sort_title("L'Étranger", None, &languages(&["fr"])),
+            "etranger"
+        );
+        assert_eq!(sort_title("The Matrix", None, &[]), "the matrix");
+        assert_eq!(sort_title("The", None, &languages(&["en"])), "the");
+    }
+
+    #[test]
+    fn sort_title_uses_the_romanized_title_for_cjk_titles() {
+        assert_eq!(
+            sort_title("進撃の巨人", Some("Shingeki no Kyojin"), &[]),
+            "shingeki no kyojin"
+        );
+        assert_eq!(sort_title("進撃の巨人", Some("  "), &[]), "進撃の巨人");
+        assert_eq!(sort_title("Amélie", Some("Amelie Poulain"), &[]), "amelie");
+    }
+}
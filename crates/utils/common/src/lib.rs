@@ -2,7 +2,7 @@ use std::{convert::TryInto, fmt};
 
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use env_utils::APP_VERSION;
-use reqwest::header::HeaderValue;
+use reqwest::{header::HeaderValue, Url};
 use serde::de;
 
 pub const PROJECT_NAME: &str = "ryot";
@@ -22,10 +22,31 @@ pub const COMPILATION_TIMESTAMP: i64 = compile_time::unix!();
 pub const AVATAR_URL: &str =
     "https://raw.githubusercontent.com/IgnisDa/ryot/main/libs/assets/icon-512x512.png";
 pub const TEMP_DIR: &str = "tmp";
+pub const DATA_DIR: &str = "data";
 pub const SHOW_SPECIAL_SEASON_NAMES: [&str; 2] = ["Specials", "Extras"];
 pub static APPLICATION_JSON_HEADER: HeaderValue = HeaderValue::from_static("application/json");
 pub const FRONTEND_OAUTH_ENDPOINT: &str = "/api/auth";
 pub const PAGE_SIZE: i32 = 20;
+/// The largest page that a client can ask for when it picks the page size.
+pub const MAX_PAGE_SIZE: i32 = 100;
+pub const OUTBOUND_WEBHOOK_PAYLOAD_VERSION: i32 = 1;
+// DEV: These hosts serve the same content over https, but some providers still
+// return plain http links to them.
+pub const HTTPS_UPGRADABLE_IMAGE_HOSTS: [&str; 13] = [
+    "anilist.co",
+    "googleapis.com",
+    "googleusercontent.com",
+    "books.google.com",
+    "igdb.com",
+    "listennotes.com",
+    "mangaupdates.com",
+    "media-amazon.com",
+    "myanimelist.net",
+    "mzstatic.com",
+    "openlibrary.org",
+    "tmdb.org",
+    "vndb.org",
+];
 
 #[macro_export]
 macro_rules! ryot_log {
@@ -111,3 +132,165 @@ where
 
     deserializer.deserialize_any(JsonStringVisitor)
 }
+
+/// Change plain http image URLs of hosts that are known to support https to use
+/// https instead. Other URLs are returned unchanged.
+pub fn upgrade_image_url(url: String) -> String {
+    let Ok(mut parsed) = Url::parse(&url) else {
+        return url;
+    };
+    let upgradable = parsed.host_str().map_or(false, |host| {
+        HTTPS_UPGRADABLE_IMAGE_HOSTS
+            .iter()
+            .any(|h| host == *h || host.ends_with(&format!(".{h}")))
+    });
+    if parsed.scheme() != "http" || !upgradable || parsed.set_scheme("https").is_err() {
+        return url;
+    }
+    parsed.to_string()
+}
+
+/// Make an image URL loadable from a page served over https. Plain http URLs that
+/// can not be upgraded are routed through the image proxy when one is configured.
+pub fn normalize_image_url(url: String, image_proxy_url: &str) -> String {
+    let url = upgrade_image_url(url);
+    if !url.starts_with("http://") || image_proxy_url.is_empty() {
+        return url;
+    }
+    Url::parse_with_params(image_proxy_url, &[("url", &url)])
+        .map(|u| u.to_string())
+        .unwrap_or(url)
+}
+
+fn isbn_13_check_digit(digits: &[u32]) -> u32 {
+    let sum: u32 = digits
+        .iter()
+        .take(12)
+        .enumerate()
+        .map(|(idx, d)| if idx % 2 == 0 { *d } else { d * 3 })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+/// Validate an ISBN-10 or ISBN-13 and return it in its ISBN-13 form. Hyphens and
+/// spaces, like the ones printed under barcodes, are ignored.
+pub fn normalize_isbn(value: &str) -> Option<String> {
+    let cleaned = value
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .collect::<String>()
+        .to_uppercase();
+    match cleaned.len() {
+        10 => {
+            let mut sum = 0;
+            for (idx, c) in cleaned.chars().enumerate() {
+                let digit = match c {
+                    'X' if idx == 9 => 10,
+                    _ => c.to_digit(10)?,
+                };
+                sum += digit * (10 - idx as u32);
+            }
+            if sum % 11 != 0 {
+                return None;
+            }
+            let mut digits = vec![9, 7, 8];
+            digits.extend(cleaned.chars().take(9).map(|c| c.to_digit(10).unwrap()));
+            digits.push(isbn_13_check_digit(&digits));
+            Some(digits.iter().map(|d| d.to_string()).collect())
+        }
+        13 => {
+            let digits = cleaned
+                .chars()
+                .map(|c| c.to_digit(10))
+                .collect::<Option<Vec<_>>>()?;
+            (isbn_13_check_digit(&digits) == digits[12]).then_some(cleaned)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrade_image_url_upgrades_known_hosts() {
+        assert_eq!(
+            upgrade_image_url("http://books.google.com/books/content?id=1".to_owned()),
+            "https://books.google.com/books/content?id=1"
+        );
+        assert_eq!(
+            upgrade_image_url("http://covers.openlibrary.org/b/id/1-L.jpg".to_owned()),
+            "https://covers.openlibrary.org/b/id/1-L.jpg"
+        );
+    }
+
+    #[test]
+    fn upgrade_image_url_keeps_other_urls() {
+        for url in [
+            "http://example.com/image.png",
+            "http://notopenlibrary.org/image.png",
+            "https://openlibrary.org/image.png",
+            "ftp://openlibrary.org/image.png",
+            "not a url",
+        ] {
+            assert_eq!(upgrade_image_url(url.to_owned()), url);
+        }
+    }
+
+    #[test]
+    fn normalize_isbn_converts_isbn_10() {
+        assert_eq!(
+            normalize_isbn("0-306-40615-2"),
+            Some("9780306406157".to_owned())
+        );
+        assert_eq!(
+            normalize_isbn("0 8044 2957 x"),
+            Some("9780804429573".to_owned())
+        );
+    }
+
+    #[test]
+    fn normalize_isbn_keeps_valid_isbn_13() {
+        assert_eq!(
+            normalize_isbn("978-0-306-40615-7"),
+            Some("9780306406157".to_owned())
+        );
+    }
+
+    #[test]
+    fn normalize_isbn_rejects_invalid_values() {
+        for value in [
+            "0-306-40615-3",
+            "978-0-306-40615-8",
+            "X306406152",
+            "97803064061X7",
+            "12345",
+            "",
+        ] {
+            assert_eq!(normalize_isbn(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn normalize_image_url_proxies_plain_http_only_when_configured() {
+        assert_eq!(
+            normalize_image_url(
+                "http://example.com/a.png".to_owned(),
+                "https://proxy.test/image"
+            ),
+            "https://proxy.test/image?url=http%3A%2F%2Fexample.com%2Fa.png"
+        );
+        assert_eq!(
+            normalize_image_url("http://example.com/a.png".to_owned(), ""),
+            "http://example.com/a.png"
+        );
+        assert_eq!(
+            normalize_image_url(
+                "http://openlibrary.org/a.png".to_owned(),
+                "https://proxy.test/image"
+            ),
+            "https://openlibrary.org/a.png"
+        );
+    }
+}
@@ -1,52 +1,77 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    fs::{remove_file, File},
+    io::{self, BufReader},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use application_utils::{
-    get_podcast_episode_by_number, get_show_episode_by_numbers, GraphqlRepresentation,
+    dates::{
+        date_range_buckets, local_date, local_day_bounds, local_weekday_and_hour,
+        year_to_date_range,
+    },
+    get_current_date, get_podcast_episode_by_number, get_show_episode_by_numbers,
+    text::label_key,
+    GraphqlRepresentation,
 };
-use async_graphql::{Error, Result};
+use async_graphql::{Error, MaybeUndefined, Result};
 use background::ApplicationJob;
-use chrono::Utc;
+use chrono::{Datelike, Months, NaiveDate, Timelike, Utc};
 use common_models::{
-    BackendError, ChangeCollectionToEntityInput, DefaultCollection, IdAndNamedObject,
-    StringIdObject,
+    ApplicationCacheKey, BackendError, ChangeCollectionToEntityInput, DefaultCollection,
+    IdAndNamedObject, StringIdObject, UserDashboardSection,
 };
-use common_utils::{ryot_log, IsFeatureEnabled};
+use common_utils::{ryot_log, IsFeatureEnabled, TEMP_DIR};
 use database_models::{
     access_link, collection, collection_to_entity, daily_user_activity,
     functions::associate_user_with_entity,
-    metadata,
+    integration, integration_item_mapping, metadata, metadata_to_tag,
     prelude::{
-        AccessLink, Collection, CollectionToEntity, DailyUserActivity, Metadata, Review, Seen,
-        User, UserMeasurement, UserToEntity, Workout, WorkoutTemplate,
+        AccessLink, Collection, CollectionToEntity, DailyUserActivity, Integration,
+        IntegrationItemMapping, Metadata, MetadataExternalId, MetadataToTag, Review,
+        SafetySnapshot, Seen, Tag, User, UserMeasurement, UserToEntity, VideoGameSession, Workout,
+        WorkoutTemplate,
     },
-    review, seen, user, user_measurement, user_to_entity, workout,
+    review, safety_snapshot, seen, tag, user, user_measurement, user_to_entity, video_game_session,
+    workout,
+};
+use dependent_models::{
+    DailyUserActivitiesResponse, UserWorkoutDetails, UserWorkoutTemplateDetails,
+};
+use enums::{
+    EntityLot, ExternalIdType, IntegrationProvider, MediaLot, MediaSource, SeenState, UserLot,
+    Visibility,
 };
-use dependent_models::{UserWorkoutDetails, UserWorkoutTemplateDetails};
-use enums::{EntityLot, MediaLot, SeenState, UserLot, Visibility};
 use fitness_models::UserMeasurementsListInput;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use futures::TryStreamExt;
 use itertools::Itertools;
 use jwt_service::{verify, Claims};
-use markdown::to_html as markdown_to_html;
+use markdown::{to_html as markdown_to_html, to_html_with_options, Options};
 use media_models::{
-    AnimeSpecifics, AudioBookSpecifics, BookSpecifics, CreateOrUpdateCollectionInput,
-    MangaSpecifics, MovieSpecifics, PodcastSpecifics, ReviewItem, SeenAnimeExtraInformation,
-    SeenMangaExtraInformation, SeenPodcastExtraInformation, SeenShowExtraInformation,
-    ShowSpecifics, VideoGameSpecifics, VisualNovelSpecifics,
+    AnimeSpecifics, AudioBookSpecifics, BookSpecifics, ConsumptionHeatmap, ConsumptionHeatmapInput,
+    ConsumptionMonthCount, ConsumptionStreaks, ConsumptionStreaksInput,
+    CreateOrUpdateCollectionInput, DailyUserActivitiesInput, DailyUserActivitiesResponseGroupedBy,
+    DailyUserActivityItem, MangaSpecifics, MediaDuration, MediaFilter, MediaReleaseStatus,
+    MovieSpecifics, PlaybackLink, PodcastSpecifics, ReviewItem, SeenAnimeExtraInformation,
+    SeenEdition, SeenMangaExtraInformation, ShowSpecifics, UserYearlySummary,
+    UserYearlySummaryGenre, UserYearlySummaryLot, VideoGameSpecifics, VisualNovelSpecifics,
 };
 use migrations::AliasedCollectionToEntity;
 use rust_decimal::{prelude::ToPrimitive, Decimal};
-use rust_decimal_macros::dec;
 use sea_orm::{
     prelude::{Date, DateTimeUtc, Expr},
-    sea_query::{OnConflict, PgFunc},
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult,
-    Iterable, ModelTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Select,
-    TransactionTrait,
+    sea_query::{Alias, Func, OnConflict, PgFunc, SimpleExpr},
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseBackend,
+    DatabaseConnection, EntityTrait, FromQueryResult, Iden, IntoActiveModel, Iterable, ModelTrait,
+    Order, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Select, Statement, TransactionTrait,
+    Value,
 };
 use serde::{Deserialize, Serialize};
 use supporting_service::SupportingService;
-use user_models::UserReviewScale;
+use user_models::DashboardElementLot;
 use uuid::Uuid;
 
 pub async fn revoke_access_link(db: &DatabaseConnection, access_link_id: String) -> Result<bool> {
@@ -180,6 +205,93 @@ pub async fn entity_in_collections(
     Ok(eic.into_iter().map(|(c, _)| c).collect_vec())
 }
 
+// DEV: Plex hosts its web app centrally, so a link works even without a base url
+const PLEX_WEB_APP_URL: &str = "https://app.plex.tv";
+
+/// The url that opens a mapped item in the web app of the media server.
+pub fn playback_link_url(
+    integration: &integration::Model,
+    mapping: &integration_item_mapping::Model,
+) -> Option<String> {
+    let specifics = integration.provider_specifics.as_ref();
+    let item_id = &mapping.server_item_id;
+    match integration.provider {
+        IntegrationProvider::JellyfinSink => {
+            let base_url = specifics.and_then(|s| s.jellyfin_sink_web_base_url.as_ref())?;
+            Some(format!(
+                "{}/web/index.html#!/details?id={}",
+                base_url.trim_end_matches('/'),
+                item_id
+            ))
+        }
+        IntegrationProvider::Plex => {
+            let server_id = mapping.server_id.as_ref()?;
+            let base_url = specifics
+                .and_then(|s| s.plex_web_base_url.as_deref())
+                .unwrap_or(PLEX_WEB_APP_URL);
+            Some(format!(
+                "{}/web/index.html#!/server/{}/details?key=%2Flibrary%2Fmetadata%2F{}",
+                base_url.trim_end_matches('/'),
+                server_id,
+                item_id
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The value under which the old source of a media item is remembered after its
+/// source is swapped, so that integrations that still send it keep matching.
+pub fn previous_source_external_id(lot: MediaLot, source: MediaSource, identifier: &str) -> String {
+    let name = |value: serde_json::Value| value.as_str().unwrap_or_default().to_owned();
+    format!(
+        "{}:{}:{}",
+        name(serde_json::to_value(lot).unwrap()),
+        name(serde_json::to_value(source).unwrap()),
+        identifier
+    )
+}
+
+/// Find the media item that another service knows by this id. Takes precedence
+/// over searching a provider since it can not pick the wrong item.
+pub async fn metadata_by_external_id(
+    id_type: ExternalIdType,
+    value: &str,
+    db: &DatabaseConnection,
+) -> Result<Option<metadata::Model>> {
+    let found = MetadataExternalId::find_by_id((id_type, value.to_owned()))
+        .find_also_related(Metadata)
+        .one(db)
+        .await?;
+    Ok(found.and_then(|(_, metadata)| metadata))
+}
+
+pub async fn user_playback_links(
+    user_id: &String,
+    metadata_id: &String,
+    ss: &Arc<SupportingService>,
+) -> Result<Vec<PlaybackLink>> {
+    let mappings = IntegrationItemMapping::find()
+        .find_also_related(Integration)
+        .filter(integration_item_mapping::Column::MetadataId.eq(metadata_id))
+        .filter(integration::Column::UserId.eq(user_id))
+        .order_by_desc(integration_item_mapping::Column::UpdatedOn)
+        .all(&ss.db)
+        .await?;
+    let links = mappings
+        .into_iter()
+        .filter_map(|(mapping, integration)| {
+            let integration = integration?;
+            let url = playback_link_url(&integration, &mapping)?;
+            Some(PlaybackLink {
+                url,
+                provider: integration.provider,
+            })
+        })
+        .collect();
+    Ok(links)
+}
+
 pub async fn user_workout_details(
     user_id: &String,
     workout_id: String,
@@ -229,6 +341,90 @@ pub async fn user_workout_template_details(
     })
 }
 
+/// The condition that matches metadata with the given release status on the
+/// given day.
+pub fn metadata_release_status_condition(
+    status: MediaReleaseStatus,
+    today: NaiveDate,
+) -> Condition {
+    let date_is_unknown = metadata::Column::PublishDate.is_null();
+    match status {
+        MediaReleaseStatus::Released => Condition::any()
+            .add(metadata::Column::PublishDate.lte(today))
+            .add(
+                Condition::all()
+                    .add(date_is_unknown)
+                    .add(metadata::Column::PublishYear.lt(today.year())),
+            ),
+        MediaReleaseStatus::Unreleased => Condition::any()
+            .add(metadata::Column::PublishDate.gt(today))
+            .add(
+                Condition::all()
+                    .add(date_is_unknown)
+                    .add(metadata::Column::PublishYear.gte(today.year())),
+            ),
+        MediaReleaseStatus::Unknown => Condition::all()
+            .add(date_is_unknown)
+            .add(metadata::Column::PublishYear.is_null()),
+    }
+}
+
+/// Recommendations leave out media that has not been released yet. Media whose
+/// release date is not known is kept.
+// DEV: Negating the unreleased condition would also drop media without dates,
+// since comparisons with NULL are neither true nor false
+fn recommendable_release_condition(today: NaiveDate) -> Condition {
+    Condition::any()
+        .add(metadata_release_status_condition(
+            MediaReleaseStatus::Released,
+            today,
+        ))
+        .add(metadata_release_status_condition(
+            MediaReleaseStatus::Unknown,
+            today,
+        ))
+}
+
+/// The condition that matches metadata with any of the tags that a user wants
+/// to be warned about.
+pub fn metadata_content_warning_condition(warning_tags: &[String]) -> SimpleExpr {
+    let keys = warning_tags.iter().map(|t| label_key(t)).collect_vec();
+    metadata::Column::Id.in_subquery(
+        MetadataToTag::find()
+            .select_only()
+            .column(metadata_to_tag::Column::MetadataId)
+            .inner_join(Tag)
+            .filter(tag::Column::Key.is_in(keys))
+            .filter(tag::Column::IsHidden.eq(false))
+            .into_query(),
+    )
+}
+
+/// Restrict the query to metadata published within the year range or decade
+/// from the filter, both ends inclusive.
+pub fn apply_publish_year_filter<E>(query: Select<E>, filter: &MediaFilter) -> Select<E>
+where
+    E: EntityTrait,
+{
+    let decade = filter.decade.map(|d| d - d.rem_euclid(10));
+    let range = filter.publish_year_range.as_ref();
+    let start = [range.and_then(|r| r.start), decade]
+        .into_iter()
+        .flatten()
+        .max();
+    let end = [range.and_then(|r| r.end), decade.map(|d| d + 9)]
+        .into_iter()
+        .flatten()
+        .min();
+    query
+        .apply_if(start, |query, v| {
+            query.filter(metadata::Column::PublishYear.gte(v))
+        })
+        .apply_if(end, |query, v| {
+            query.filter(metadata::Column::PublishYear.lte(v))
+        })
+}
+
 pub fn apply_collection_filter<E, C, D>(
     query: Select<E>,
     collection_id: Option<Vec<String>>,
@@ -283,40 +479,53 @@ pub async fn add_entity_to_collection(
         .await
         .unwrap()
         .unwrap();
+    if collection.smart_filter.is_some() {
+        return Err(Error::new(
+            "Entities can not be added to a smart collection",
+        ));
+    }
+    let collection_name = collection.name.clone();
+    let txn = ss.db.begin().await?;
+    // DEV: Locking the collection keeps concurrent additions from taking the same rank
+    Collection::find_by_id(&collection.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
     let mut updated: collection::ActiveModel = collection.into();
     updated.last_updated_on = ActiveValue::Set(Utc::now());
-    let collection = updated.update(&ss.db).await.unwrap();
+    let collection = updated.update(&txn).await?;
     let column = get_cte_column_from_lot(input.entity_lot);
     let resp = if let Some(etc) = CollectionToEntity::find()
         .filter(CteColAlias::CollectionId.eq(collection.id.clone()))
         .filter(column.eq(input.entity_id.clone()))
-        .one(&ss.db)
+        .one(&txn)
         .await?
     {
         let mut to_update: collection_to_entity::ActiveModel = etc.into();
         to_update.last_updated_on = ActiveValue::Set(Utc::now());
-        to_update.update(&ss.db).await?
+        let updated = to_update.update(&txn).await?;
+        txn.commit().await?;
+        updated
     } else {
-        let mut created_collection = collection_to_entity::ActiveModel {
-            collection_id: ActiveValue::Set(collection.id),
-            information: ActiveValue::Set(input.information),
-            ..Default::default()
-        };
-        let id = input.entity_id.clone();
-        match input.entity_lot {
-            EntityLot::Metadata => created_collection.metadata_id = ActiveValue::Set(Some(id)),
-            EntityLot::Person => created_collection.person_id = ActiveValue::Set(Some(id)),
-            EntityLot::MetadataGroup => {
-                created_collection.metadata_group_id = ActiveValue::Set(Some(id))
-            }
-            EntityLot::Exercise => created_collection.exercise_id = ActiveValue::Set(Some(id)),
-            EntityLot::Workout => created_collection.workout_id = ActiveValue::Set(Some(id)),
-            EntityLot::WorkoutTemplate => {
-                created_collection.workout_template_id = ActiveValue::Set(Some(id))
-            }
-            EntityLot::Collection => unreachable!(),
-        }
-        let created = created_collection.insert(&ss.db).await?;
+        let last_rank = CollectionToEntity::find()
+            .select_only()
+            .column_as(Expr::col(CteColAlias::Rank).max(), "rank")
+            .filter(CteColAlias::CollectionId.eq(collection.id.clone()))
+            .into_tuple::<Option<i32>>()
+            .one(&txn)
+            .await?
+            .flatten()
+            .unwrap_or_default();
+        let created = new_collection_entity(
+            collection.id,
+            input.entity_id.clone(),
+            input.entity_lot,
+            input.information,
+            last_rank + 1,
+        )
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
         ryot_log!(debug, "Created collection to entity: {:?}", created);
         if input.entity_lot != EntityLot::Workout && input.entity_lot != EntityLot::WorkoutTemplate
         {
@@ -326,11 +535,39 @@ pub async fn add_entity_to_collection(
         }
         created
     };
+    invalidate_collection_dashboard_sections(user_id, &collection_name, ss).await?;
     ss.perform_application_job(ApplicationJob::HandleEntityAddedToCollectionEvent(resp.id))
         .await?;
     Ok(true)
 }
 
+/// A collection holds any kind of entity, it is stored in the column of its lot.
+fn new_collection_entity(
+    collection_id: String,
+    entity_id: String,
+    entity_lot: EntityLot,
+    information: Option<serde_json::Value>,
+    rank: i32,
+) -> collection_to_entity::ActiveModel {
+    let mut created = collection_to_entity::ActiveModel {
+        collection_id: ActiveValue::Set(collection_id),
+        information: ActiveValue::Set(information),
+        rank: ActiveValue::Set(rank),
+        ..Default::default()
+    };
+    let id = Some(entity_id);
+    match entity_lot {
+        EntityLot::Metadata => created.metadata_id = ActiveValue::Set(id),
+        EntityLot::Person => created.person_id = ActiveValue::Set(id),
+        EntityLot::MetadataGroup => created.metadata_group_id = ActiveValue::Set(id),
+        EntityLot::Exercise => created.exercise_id = ActiveValue::Set(id),
+        EntityLot::Workout => created.workout_id = ActiveValue::Set(id),
+        EntityLot::WorkoutTemplate => created.workout_template_id = ActiveValue::Set(id),
+        EntityLot::Collection => unreachable!(),
+    }
+    created
+}
+
 pub fn user_claims_from_token(token: &str, jwt_secret: &str) -> Result<Claims> {
     verify(token, jwt_secret).map_err(|e| Error::new(format!("Encountered error: {:?}", e)))
 }
@@ -368,15 +605,15 @@ pub async fn check_token(
 }
 
 pub async fn remove_entity_from_collection(
-    db: &DatabaseConnection,
     user_id: &String,
     input: ChangeCollectionToEntityInput,
+    ss: &Arc<SupportingService>,
 ) -> Result<StringIdObject> {
     let collect = Collection::find()
         .left_join(UserToEntity)
         .filter(collection::Column::Name.eq(input.collection_name))
         .filter(user_to_entity::Column::UserId.eq(input.creator_user_id))
-        .one(db)
+        .one(&ss.db)
         .await
         .unwrap()
         .unwrap();
@@ -384,14 +621,25 @@ pub async fn remove_entity_from_collection(
     CollectionToEntity::delete_many()
         .filter(collection_to_entity::Column::CollectionId.eq(collect.id.clone()))
         .filter(column.eq(input.entity_id.clone()))
-        .exec(db)
+        .exec(&ss.db)
         .await?;
+    invalidate_collection_dashboard_sections(user_id, &collect.name, ss).await?;
     if input.entity_lot != EntityLot::Workout && input.entity_lot != EntityLot::WorkoutTemplate {
-        associate_user_with_entity(db, user_id, input.entity_id, input.entity_lot).await?;
+        associate_user_with_entity(&ss.db, user_id, input.entity_id, input.entity_lot).await?;
     }
     Ok(StringIdObject { id: collect.id })
 }
 
+/// Render the Markdown of a review. GitHub flavored constructs like tables and
+/// strikethrough are supported, raw HTML and `javascript:` style links are not.
+pub fn render_review_text(text: &str) -> String {
+    // DEV: Dangerous HTML and protocols are disabled by default, they must stay that
+    // way since reviews can be viewed by other users.
+    to_html_with_options(text, &Options::gfm()).unwrap_or_else(|_| markdown_to_html(text))
+}
+
+const REVIEW_COMMENTS_TO_SHOW: usize = 50;
+
 pub async fn item_reviews(
     user_id: &String,
     entity_id: &String,
@@ -421,25 +669,41 @@ pub async fn item_reviews(
         .all(&ss.db)
         .await
         .unwrap();
+    let reviews = review_items(user_id, all_reviews, ss).await?;
+    let all_reviews = reviews
+        .into_iter()
+        .filter(|r| match r.visibility {
+            Visibility::Private => &r.posted_by.id == user_id,
+            _ => true,
+        })
+        .collect();
+    Ok(all_reviews)
+}
+
+/// Convert reviews into what is shown to a user, with ratings in the review scale
+/// of that user.
+pub async fn review_items(
+    user_id: &String,
+    all_reviews: Vec<(review::Model, Option<user::Model>)>,
+    ss: &Arc<SupportingService>,
+) -> Result<Vec<ReviewItem>> {
+    let preferences = user_by_id(user_id, ss).await?.preferences;
     let mut reviews = vec![];
     for (review, user) in all_reviews {
         let user = user.unwrap();
-        let rating = match true {
-            true => {
-                let preferences = user_by_id(user_id, ss).await?.preferences;
-                review.rating.map(|s| {
-                    s.checked_div(match preferences.general.review_scale {
-                        UserReviewScale::OutOfFive => dec!(20),
-                        UserReviewScale::OutOfHundred | UserReviewScale::ThreePointSmiley => {
-                            dec!(1)
-                        }
-                    })
-                    .unwrap()
-                    .round_dp(1)
-                })
-            }
-            false => review.rating,
-        };
+        let rating = review
+            .rating
+            .map(|r| preferences.general.review_scale.denormalize(r));
+        let comments = review
+            .comments
+            .into_iter()
+            .filter(|c| c.deleted_on.is_none())
+            .collect_vec();
+        let comments_count = comments.len();
+        let comments = comments
+            .into_iter()
+            .skip(comments_count.saturating_sub(REVIEW_COMMENTS_TO_SHOW))
+            .collect_vec();
         let seen_items_associated_with = Seen::find()
             .select_only()
             .column(seen::Column::Id)
@@ -455,7 +719,7 @@ pub async fn item_reviews(
             is_spoiler: review.is_spoiler,
             visibility: review.visibility,
             text_original: review.text.clone(),
-            text_rendered: review.text.map(|t| markdown_to_html(&t)),
+            text_rendered: review.text.map(|t| render_review_text(&t)),
             show_extra_information: review.show_extra_information,
             podcast_extra_information: review.podcast_extra_information,
             anime_extra_information: review.anime_extra_information,
@@ -464,18 +728,12 @@ pub async fn item_reviews(
                 id: user.id,
                 name: user.name,
             },
-            comments: review.comments,
+            comments,
+            comments_count,
         };
         reviews.push(to_push);
     }
-    let all_reviews = reviews
-        .into_iter()
-        .filter(|r| match r.visibility {
-            Visibility::Private => &r.posted_by.id == user_id,
-            _ => true,
-        })
-        .collect();
-    Ok(all_reviews)
+    Ok(reviews)
 }
 
 pub async fn create_or_update_collection(
@@ -491,6 +749,7 @@ pub async fn create_or_update_collection(
         .await
         .unwrap();
     let mut new_name = input.name.clone();
+    let becomes_smart = input.smart_filter.value().is_some();
     let created = match meta {
         Some(m) if input.update_id.is_none() => m.id,
         _ => {
@@ -499,9 +758,11 @@ pub async fn create_or_update_collection(
                     Some(i) => {
                         let already = Collection::find_by_id(i.clone())
                             .one(&txn)
-                            .await
-                            .unwrap()
-                            .unwrap();
+                            .await?
+                            .ok_or_else(|| Error::new("Collection does not exist"))?;
+                        if &already.user_id != user_id {
+                            return Err(Error::new("This collection does not belong to you"));
+                        }
                         if DefaultCollection::iter()
                             .map(|s| s.to_string())
                             .contains(&already.name)
@@ -517,6 +778,11 @@ pub async fn create_or_update_collection(
                 user_id: ActiveValue::Set(user_id.to_owned()),
                 description: ActiveValue::Set(input.description),
                 information_template: ActiveValue::Set(input.information_template),
+                smart_filter: match input.smart_filter {
+                    MaybeUndefined::Undefined => ActiveValue::NotSet,
+                    MaybeUndefined::Null => ActiveValue::Set(None),
+                    MaybeUndefined::Value(filter) => ActiveValue::Set(Some(filter)),
+                },
                 ..Default::default()
             };
             let inserted = col
@@ -524,6 +790,14 @@ pub async fn create_or_update_collection(
                 .await
                 .map_err(|_| Error::new("There was an error creating the collection".to_owned()))?;
             let id = inserted.id.unwrap();
+            if becomes_smart {
+                // DEV: The contents of a smart collection come from its filter, so
+                // entities added while it was a regular collection are dropped
+                CollectionToEntity::delete_many()
+                    .filter(CteColAlias::CollectionId.eq(&id))
+                    .exec(&txn)
+                    .await?;
+            }
             let mut collaborators = vec![user_id.to_owned()];
             if let Some(input_collaborators) = input.collaborators {
                 collaborators.extend(input_collaborators);
@@ -546,16 +820,70 @@ pub async fn create_or_update_collection(
     Ok(StringIdObject { id: created })
 }
 
+/// Only finished entries count towards the activities, skipped episodes are left
+/// out of the watch time and the episode counts.
+fn seen_for_activities(user_id: &String, start_from: DateTimeUtc) -> Select<Seen> {
+    Seen::find()
+        .filter(seen::Column::UserId.eq(user_id))
+        .filter(seen::Column::State.eq(SeenState::Completed))
+        .filter(seen::Column::LastUpdatedOn.gt(start_from))
+}
+
+/// The chosen edition is more accurate than the provider's page count.
+fn book_pages_read(edition: Option<&SeenEdition>, book: &BookSpecifics) -> Option<i32> {
+    edition.and_then(|e| e.pages).or(book.pages)
+}
+
+/// Counts a finished entry towards the day's activity. Shows and podcasts are counted
+/// once per series for each day, while each of their episodes is counted separately.
+fn count_finished_media(
+    activity: &mut daily_user_activity::Model,
+    series_consumed: &mut HashSet<(Date, String)>,
+    metadata_id: &str,
+    lot: MediaLot,
+    is_episode: bool,
+) {
+    let is_new_series = series_consumed.insert((activity.date, metadata_id.to_owned()));
+    match lot {
+        MediaLot::Book => activity.book_count += 1,
+        MediaLot::Show => {
+            if is_new_series {
+                activity.show_count += 1;
+            }
+            if is_episode {
+                activity.show_episode_count += 1;
+            }
+        }
+        MediaLot::Anime => activity.anime_count += 1,
+        MediaLot::Movie => activity.movie_count += 1,
+        MediaLot::Manga => activity.manga_count += 1,
+        MediaLot::Podcast => {
+            if is_new_series {
+                activity.podcast_count += 1;
+            }
+            if is_episode {
+                activity.podcast_episode_count += 1;
+            }
+        }
+        MediaLot::VideoGame => activity.video_game_count += 1,
+        MediaLot::AudioBook => activity.audio_book_count += 1,
+        MediaLot::VisualNovel => activity.visual_novel_count += 1,
+    };
+}
+
 pub async fn calculate_user_activities_and_summary(
-    db: &DatabaseConnection,
     user_id: &String,
     calculate_from_beginning: bool,
+    ss: &Arc<SupportingService>,
 ) -> Result<()> {
+    let db = &ss.db;
+    let timezone = &ss.timezone;
     #[derive(Debug, Serialize, Deserialize, Clone, FromQueryResult)]
     struct SeenItem {
         seen_id: String,
-        show_extra_information: Option<SeenShowExtraInformation>,
-        podcast_extra_information: Option<SeenPodcastExtraInformation>,
+        show_season_number: Option<i32>,
+        show_episode_number: Option<i32>,
+        podcast_episode_number: Option<i32>,
         anime_extra_information: Option<SeenAnimeExtraInformation>,
         manga_extra_information: Option<SeenMangaExtraInformation>,
         metadata_id: String,
@@ -569,6 +897,7 @@ pub async fn calculate_user_activities_and_summary(
         show_specifics: Option<ShowSpecifics>,
         video_game_specifics: Option<VideoGameSpecifics>,
         manual_time_spent: Option<Decimal>,
+        edition: Option<SeenEdition>,
         visual_novel_specifics: Option<VisualNovelSpecifics>,
         anime_specifics: Option<AnimeSpecifics>,
         manga_specifics: Option<MangaSpecifics>,
@@ -590,7 +919,9 @@ pub async fn calculate_user_activities_and_summary(
             .map(|i| i.date)
             .unwrap_or_default(),
     };
+    let (start_from, _) = local_day_bounds(start_from, timezone);
     let mut activities = HashMap::new();
+    let mut series_consumed = HashSet::new();
 
     fn get_activity_count<'a>(
         entity_id: String,
@@ -609,22 +940,21 @@ pub async fn calculate_user_activities_and_summary(
         existing.entity_ids.push(entity_id);
         existing
     }
-    let mut seen_stream = Seen::find()
-        .filter(seen::Column::UserId.eq(user_id))
-        .filter(seen::Column::State.eq(SeenState::Completed))
-        .filter(seen::Column::LastUpdatedOn.gt(start_from))
+    let mut seen_stream = seen_for_activities(user_id, start_from)
         .left_join(Metadata)
         .select_only()
         .column_as(seen::Column::Id, "seen_id")
         .columns([
-            seen::Column::ShowExtraInformation,
-            seen::Column::PodcastExtraInformation,
+            seen::Column::ShowSeasonNumber,
+            seen::Column::ShowEpisodeNumber,
+            seen::Column::PodcastEpisodeNumber,
             seen::Column::AnimeExtraInformation,
             seen::Column::MangaExtraInformation,
             seen::Column::MetadataId,
             seen::Column::FinishedOn,
             seen::Column::LastUpdatedOn,
             seen::Column::ManualTimeSpent,
+            seen::Column::Edition,
         ])
         .column_as(metadata::Column::Lot, "metadata_lot")
         .columns([
@@ -645,58 +975,74 @@ pub async fn calculate_user_activities_and_summary(
     while let Some(seen) = seen_stream.try_next().await? {
         let default_date = Date::from_ymd_opt(2023, 4, 3).unwrap(); // DEV: The first commit of Ryot
         let date = seen.finished_on.unwrap_or(default_date);
+        let is_episode = match seen.metadata_lot {
+            MediaLot::Show => {
+                seen.show_season_number.is_some() && seen.show_episode_number.is_some()
+            }
+            MediaLot::Podcast => seen.podcast_episode_number.is_some(),
+            _ => false,
+        };
         let activity = get_activity_count(seen.seen_id, &mut activities, user_id, date);
-        if let (Some(show_seen), Some(show_extra)) =
-            (seen.show_specifics, seen.show_extra_information)
-        {
-            if let Some(runtime) =
-                get_show_episode_by_numbers(&show_seen, show_extra.season, show_extra.episode)
-                    .and_then(|(_, e)| e.runtime)
+        count_finished_media(
+            activity,
+            &mut series_consumed,
+            &seen.metadata_id,
+            seen.metadata_lot,
+            is_episode,
+        );
+        if let (Some(show_seen), Some(season), Some(episode)) = (
+            seen.show_specifics,
+            seen.show_season_number,
+            seen.show_episode_number,
+        ) {
+            if let Some(runtime) = get_show_episode_by_numbers(&show_seen, season, episode)
+                .and_then(|(_, e)| e.runtime)
             {
-                activity.show_duration += runtime;
+                activity.show_duration += runtime.minutes();
             }
-        } else if let (Some(podcast_seen), Some(podcast_extra)) =
-            (seen.podcast_specifics, seen.podcast_extra_information)
+        } else if let (Some(podcast_seen), Some(episode)) =
+            (seen.podcast_specifics, seen.podcast_episode_number)
         {
             if let Some(runtime) =
-                get_podcast_episode_by_number(&podcast_seen, podcast_extra.episode)
-                    .and_then(|e| e.runtime)
+                get_podcast_episode_by_number(&podcast_seen, episode).and_then(|e| e.runtime)
             {
-                activity.podcast_duration += runtime;
+                activity.podcast_duration += runtime.minutes();
             }
         } else if let Some(audio_book_extra) = seen.audio_book_specifics {
             if let Some(runtime) = audio_book_extra.runtime {
-                activity.audio_book_duration += runtime;
+                activity.audio_book_duration += runtime.minutes();
             }
         } else if let Some(movie_extra) = seen.movie_specifics {
             if let Some(runtime) = movie_extra.runtime {
-                activity.movie_duration += runtime;
+                activity.movie_duration += runtime.minutes();
             }
         } else if let Some(book_extra) = seen.book_specifics {
-            if let Some(pages) = book_extra.pages {
+            if let Some(pages) = book_pages_read(seen.edition.as_ref(), &book_extra) {
                 activity.book_pages += pages;
             }
         } else if let Some(visual_novel_extra) = seen.visual_novel_specifics {
             if let Some(runtime) = visual_novel_extra.length {
-                activity.visual_novel_duration += runtime;
+                activity.visual_novel_duration += runtime.minutes();
             }
         } else if let Some(_video_game_extra) = seen.video_game_specifics {
             if let Some(manual_time_spent) = seen.manual_time_spent {
-                activity.video_game_duration +=
-                    (manual_time_spent / dec!(60)).to_i32().unwrap_or_default();
+                let time_spent =
+                    MediaDuration::from_seconds(manual_time_spent.to_i64().unwrap_or_default());
+                activity.video_game_duration += time_spent.minutes();
             }
         }
-        match seen.metadata_lot {
-            MediaLot::Book => activity.book_count += 1,
-            MediaLot::Show => activity.show_count += 1,
-            MediaLot::Anime => activity.anime_count += 1,
-            MediaLot::Movie => activity.movie_count += 1,
-            MediaLot::Manga => activity.manga_count += 1,
-            MediaLot::Podcast => activity.podcast_count += 1,
-            MediaLot::VideoGame => activity.video_game_count += 1,
-            MediaLot::AudioBook => activity.audio_book_count += 1,
-            MediaLot::VisualNovel => activity.visual_novel_count += 1,
-        };
+    }
+
+    // DEV: Play sessions count towards the time spent but not towards the games finished
+    let mut session_stream = VideoGameSession::find()
+        .filter(video_game_session::Column::UserId.eq(user_id))
+        .filter(video_game_session::Column::StartedOn.gte(start_from))
+        .stream(db)
+        .await?;
+    while let Some(item) = session_stream.try_next().await? {
+        let date = local_date(item.started_on, timezone);
+        let activity = get_activity_count(item.id, &mut activities, user_id, date);
+        activity.video_game_duration += item.duration / 60;
     }
 
     let mut workout_stream = Workout::find()
@@ -705,7 +1051,7 @@ pub async fn calculate_user_activities_and_summary(
         .stream(db)
         .await?;
     while let Some(item) = workout_stream.try_next().await? {
-        let date = item.end_time.date_naive();
+        let date = local_date(item.end_time, timezone);
         let activity = get_activity_count(item.id, &mut activities, user_id, date);
         activity.workout_count += 1;
         activity.workout_duration += item.duration / 60;
@@ -723,7 +1069,7 @@ pub async fn calculate_user_activities_and_summary(
         .stream(db)
         .await?;
     while let Some(item) = measurement_stream.try_next().await? {
-        let date = item.timestamp.date_naive();
+        let date = local_date(item.timestamp, timezone);
         let activity =
             get_activity_count(item.timestamp.to_string(), &mut activities, user_id, date);
         activity.measurement_count += 1;
@@ -735,7 +1081,7 @@ pub async fn calculate_user_activities_and_summary(
         .stream(db)
         .await?;
     while let Some(item) = review_stream.try_next().await? {
-        let date = item.posted_on.date_naive();
+        let date = local_date(item.posted_on, timezone);
         let activity = get_activity_count(item.id, &mut activities, user_id, date);
         match item.entity_lot {
             EntityLot::Person => activity.person_review_count += 1,
@@ -762,8 +1108,8 @@ pub async fn calculate_user_activities_and_summary(
             + activity.person_review_count
             + activity.exercise_review_count;
         let total_metadata_count = activity.movie_count
-            + activity.show_count
-            + activity.podcast_count
+            + activity.show_episode_count
+            + activity.podcast_episode_count
             + activity.anime_count
             + activity.manga_count
             + activity.audio_book_count
@@ -787,9 +1133,750 @@ pub async fn calculate_user_activities_and_summary(
         model.insert(db).await.ok();
     }
 
+    // DEV: The cached summary is only dropped once the new one is stored, otherwise a
+    // dashboard request made in between would cache the old one again
+    invalidate_user_dashboard_sections(user_id, &[UserDashboardSection::Summary], ss).await?;
     Ok(())
 }
 
+/// Count the seen entries of a user by the local day of the week and hour at which
+/// they were last updated.
+pub async fn consumption_heatmap(
+    user_id: &String,
+    input: ConsumptionHeatmapInput,
+    timezone: &chrono_tz::Tz,
+    db: &DatabaseConnection,
+) -> Result<ConsumptionHeatmap> {
+    let entries = Seen::find()
+        .select_only()
+        .columns([seen::Column::LastUpdatedOn, seen::Column::FinishedOn])
+        .inner_join(Metadata)
+        .filter(seen::Column::UserId.eq(user_id))
+        .apply_if(input.lot, |query, v| {
+            query.filter(metadata::Column::Lot.eq(v))
+        })
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(consumption_heatmap_counts(entries, &input, timezone))
+}
+
+fn consumption_heatmap_counts(
+    entries: Vec<(DateTimeUtc, Option<Date>)>,
+    input: &ConsumptionHeatmapInput,
+    timezone: &chrono_tz::Tz,
+) -> ConsumptionHeatmap {
+    let mut counts = vec![vec![0; 24]; 7];
+    let mut unknown_time_count = 0;
+    for (last_updated_on, finished_on) in entries {
+        let updated_date = local_date(last_updated_on, timezone);
+        // DEV: Entries logged for an earlier day (like imported ones) only have a date,
+        // their timestamp is when they were logged and not when they were consumed
+        let date = match finished_on {
+            Some(finished_on) if finished_on != updated_date => {
+                if input.year.map_or(true, |y| y == finished_on.year()) {
+                    unknown_time_count += 1;
+                }
+                continue;
+            }
+            _ => updated_date,
+        };
+        if input.year.map_or(true, |y| y == date.year()) {
+            let (day, hour) = local_weekday_and_hour(last_updated_on, timezone);
+            counts[day][hour] += 1;
+        }
+    }
+    ConsumptionHeatmap {
+        counts,
+        unknown_time_count: input
+            .include_unknown_time
+            .unwrap_or_default()
+            .then_some(unknown_time_count),
+    }
+}
+
+// DEV: Entries logged on the day they were finished take the local date of when they
+// were logged, the others (like imported ones) only have a date
+const SEEN_DAYS_CTE: &str = r#"
+WITH "entries" AS (
+    SELECT COALESCE(
+        NULLIF("finished_on", ("last_updated_on" AT TIME ZONE 'UTC')::DATE),
+        ("last_updated_on" AT TIME ZONE $2)::DATE
+    ) AS "day"
+    FROM "seen" WHERE "user_id" = $1
+)
+"#;
+
+/// Compute the streaks of consecutive days on which a user consumed something, and
+/// how their seen entries are spread over weekdays and the last months.
+pub async fn consumption_streaks(
+    user_id: &String,
+    input: ConsumptionStreaksInput,
+    timezone: &chrono_tz::Tz,
+    db: &DatabaseConnection,
+) -> Result<ConsumptionStreaks> {
+    let timezone = match input.timezone {
+        Some(tz) => tz
+            .parse::<chrono_tz::Tz>()
+            .map_err(|_| Error::new(format!("Unknown timezone {:?}", tz)))?,
+        None => *timezone,
+    };
+    let today = local_date(Utc::now(), &timezone);
+    let first_month = today
+        .with_day(1)
+        .and_then(|d| d.checked_sub_months(Months::new(11)))
+        .ok_or_else(|| Error::new("Could not calculate the first month"))?;
+    let statement = |sql: &str, extra: Vec<Value>| {
+        let mut values: Vec<Value> = vec![user_id.to_owned().into(), timezone.name().into()];
+        values.extend(extra);
+        Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!("{SEEN_DAYS_CTE}{sql}"),
+            values,
+        )
+    };
+
+    #[derive(Debug, FromQueryResult)]
+    struct Streaks {
+        current: i64,
+        longest: i64,
+    }
+    let streaks = Streaks::find_by_statement(statement(
+        r#"
+, "streaks" AS (
+    SELECT MAX("day") AS "last_day", COUNT(*) AS "length" FROM (
+        SELECT "day", "day" - (ROW_NUMBER() OVER (ORDER BY "day"))::INTEGER AS "streak"
+        FROM (SELECT DISTINCT "day" FROM "entries") "days"
+    ) "ranked"
+    GROUP BY "streak"
+)
+SELECT
+    COALESCE(MAX("length") FILTER (WHERE "last_day" >= $3::DATE - 1), 0) AS "current",
+    COALESCE(MAX("length"), 0) AS "longest"
+FROM "streaks";
+        "#,
+        vec![today.into()],
+    ))
+    .one(db)
+    .await?;
+
+    #[derive(Debug, FromQueryResult)]
+    struct WeekdayCount {
+        weekday: i32,
+        count: i64,
+    }
+    let weekdays = WeekdayCount::find_by_statement(statement(
+        r#"
+SELECT EXTRACT(ISODOW FROM "day")::INTEGER AS "weekday", COUNT(*) AS "count"
+FROM "entries" GROUP BY "weekday";
+        "#,
+        vec![],
+    ))
+    .all(db)
+    .await?;
+    let weekday_counts = (1..=7)
+        .map(|weekday| {
+            weekdays
+                .iter()
+                .find(|w| w.weekday == weekday)
+                .map_or(0, |w| w.count)
+        })
+        .collect();
+
+    let months = ConsumptionMonthCount::find_by_statement(statement(
+        r#"
+SELECT DATE_TRUNC('month', "day")::DATE AS "month", COUNT(*) AS "count"
+FROM "entries" WHERE "day" >= $3 AND "day" <= $4
+GROUP BY "month";
+        "#,
+        vec![first_month.into(), today.into()],
+    ))
+    .all(db)
+    .await?;
+    let monthly_counts = (0..12)
+        .filter_map(|i| first_month.checked_add_months(Months::new(i)))
+        .map(|month| ConsumptionMonthCount {
+            month,
+            count: months
+                .iter()
+                .find(|m| m.month == month)
+                .map_or(0, |m| m.count),
+        })
+        .collect();
+
+    // DEV: On February 29 the previous year is counted up to February 28
+    let (Some(current_year), Some(previous_year)) = (
+        year_to_date_range(today, today.year()),
+        year_to_date_range(today, today.year() - 1),
+    ) else {
+        return Err(Error::new("Could not calculate the year to date"));
+    };
+    #[derive(Debug, FromQueryResult)]
+    struct YearToDate {
+        current: i64,
+        previous: i64,
+    }
+    let year_to_date = YearToDate::find_by_statement(statement(
+        r#"
+SELECT
+    COUNT(*) FILTER (WHERE "day" BETWEEN $3 AND $4) AS "current",
+    COUNT(*) FILTER (WHERE "day" BETWEEN $5 AND $6) AS "previous"
+FROM "entries";
+        "#,
+        vec![
+            current_year.0.into(),
+            current_year.1.into(),
+            previous_year.0.into(),
+            previous_year.1.into(),
+        ],
+    ))
+    .one(db)
+    .await?;
+
+    Ok(ConsumptionStreaks {
+        current_streak_days: streaks.as_ref().map_or(0, |s| s.current),
+        longest_streak_days: streaks.as_ref().map_or(0, |s| s.longest),
+        weekday_counts,
+        monthly_counts,
+        year_to_date_count: year_to_date.as_ref().map_or(0, |y| y.current),
+        previous_year_to_date_count: year_to_date.as_ref().map_or(0, |y| y.previous),
+    })
+}
+
+pub async fn daily_user_activities(
+    user_id: &String,
+    input: DailyUserActivitiesInput,
+    db: &DatabaseConnection,
+) -> Result<DailyUserActivitiesResponse> {
+    // TODO: https://github.com/SeaQL/sea-query/pull/825 when merged
+    struct DateTrunc;
+    impl Iden for DateTrunc {
+        fn unquoted(&self, s: &mut dyn Write) {
+            write!(s, "DATE_TRUNC").unwrap();
+        }
+    }
+    let precondition = DailyUserActivity::find()
+        .filter(daily_user_activity::Column::UserId.eq(user_id))
+        .apply_if(input.end_date, |query, v| {
+            query.filter(daily_user_activity::Column::Date.lte(v))
+        })
+        .apply_if(input.start_date, |query, v| {
+            query.filter(daily_user_activity::Column::Date.gte(v))
+        })
+        .select_only();
+    let grouped_by = if let Some(group_by) = input.group_by {
+        group_by
+    } else {
+        let total = precondition
+            .clone()
+            .expr_as(
+                daily_user_activity::Column::Date
+                    .max()
+                    .sub(daily_user_activity::Column::Date.min())
+                    .add(1),
+                "num_days",
+            )
+            .into_tuple::<Option<i32>>()
+            .one(db)
+            .await?;
+        if let Some(Some(num_days)) = total {
+            if num_days >= 500 {
+                DailyUserActivitiesResponseGroupedBy::Year
+            } else if num_days >= 200 {
+                DailyUserActivitiesResponseGroupedBy::Month
+            } else {
+                DailyUserActivitiesResponseGroupedBy::Day
+            }
+        } else {
+            DailyUserActivitiesResponseGroupedBy::Day
+        }
+    };
+    let day_alias = Expr::col(Alias::new("day"));
+    let truncate_date = |date: SimpleExpr| {
+        Expr::expr(Func::cast_as(
+            Func::cust(DateTrunc)
+                .arg(Expr::val(grouped_by.to_string()))
+                .arg(date),
+            Alias::new("DATE"),
+        ))
+    };
+    let mut items = precondition
+        .column_as(
+            truncate_date(daily_user_activity::Column::Date.into_expr().into()),
+            "day",
+        )
+        .column_as(
+            daily_user_activity::Column::MetadataReviewCount.sum(),
+            "total_metadata_review_count",
+        )
+        .column_as(
+            daily_user_activity::Column::CollectionReviewCount.sum(),
+            "total_collection_review_count",
+        )
+        .column_as(
+            daily_user_activity::Column::MetadataGroupReviewCount.sum(),
+            "total_metadata_group_review_count",
+        )
+        .column_as(
+            daily_user_activity::Column::PersonReviewCount.sum(),
+            "total_person_review_count",
+        )
+        .column_as(
+            daily_user_activity::Column::MeasurementCount.sum(),
+            "measurement_count",
+        )
+        .column_as(
+            daily_user_activity::Column::WorkoutCount.sum(),
+            "workout_count",
+        )
+        .column_as(
+            daily_user_activity::Column::WorkoutDuration.sum(),
+            "total_workout_duration",
+        )
+        .column_as(
+            daily_user_activity::Column::AudioBookCount.sum(),
+            "audio_book_count",
+        )
+        .column_as(
+            daily_user_activity::Column::AudioBookDuration.sum(),
+            "total_audio_book_duration",
+        )
+        .column_as(daily_user_activity::Column::AnimeCount.sum(), "anime_count")
+        .column_as(daily_user_activity::Column::BookCount.sum(), "book_count")
+        .column_as(
+            daily_user_activity::Column::BookPages.sum(),
+            "total_book_pages",
+        )
+        .column_as(
+            daily_user_activity::Column::PodcastCount.sum(),
+            "podcast_count",
+        )
+        .column_as(
+            daily_user_activity::Column::PodcastEpisodeCount.sum(),
+            "podcast_episode_count",
+        )
+        .column_as(
+            daily_user_activity::Column::PodcastDuration.sum(),
+            "total_podcast_duration",
+        )
+        .column_as(daily_user_activity::Column::MangaCount.sum(), "manga_count")
+        .column_as(daily_user_activity::Column::MovieCount.sum(), "movie_count")
+        .column_as(
+            daily_user_activity::Column::MovieDuration.sum(),
+            "total_movie_duration",
+        )
+        .column_as(daily_user_activity::Column::ShowCount.sum(), "show_count")
+        .column_as(
+            daily_user_activity::Column::ShowEpisodeCount.sum(),
+            "show_episode_count",
+        )
+        .column_as(
+            daily_user_activity::Column::ShowDuration.sum(),
+            "total_show_duration",
+        )
+        .column_as(
+            daily_user_activity::Column::VideoGameDuration.sum(),
+            "total_video_game_duration",
+        )
+        .column_as(
+            daily_user_activity::Column::VideoGameCount.sum(),
+            "video_game_count",
+        )
+        .column_as(
+            daily_user_activity::Column::VisualNovelCount.sum(),
+            "visual_novel_count",
+        )
+        .column_as(
+            daily_user_activity::Column::VisualNovelDuration.sum(),
+            "total_visual_novel_duration",
+        )
+        .column_as(
+            daily_user_activity::Column::WorkoutPersonalBests.sum(),
+            "total_workout_personal_bests",
+        )
+        .column_as(
+            daily_user_activity::Column::WorkoutWeight.sum(),
+            "total_workout_weight",
+        )
+        .column_as(
+            daily_user_activity::Column::WorkoutReps.sum(),
+            "total_workout_reps",
+        )
+        .column_as(
+            daily_user_activity::Column::WorkoutDistance.sum(),
+            "total_workout_distance",
+        )
+        .column_as(
+            daily_user_activity::Column::WorkoutRestTime.sum(),
+            "total_workout_rest_time",
+        )
+        .column_as(
+            daily_user_activity::Column::TotalMetadataCount.sum(),
+            "total_metadata_count",
+        )
+        .column_as(
+            daily_user_activity::Column::TotalReviewCount.sum(),
+            "total_review_count",
+        )
+        .column_as(daily_user_activity::Column::TotalCount.sum(), "total_count")
+        .column_as(
+            daily_user_activity::Column::TotalDuration.sum(),
+            "total_duration",
+        )
+        .group_by(day_alias.clone())
+        .order_by_asc(day_alias.clone())
+        .into_model::<DailyUserActivityItem>()
+        .all(db)
+        .await
+        .unwrap();
+    // DEV: The daily rows can not be summed up to get the number of distinct series
+    // consumed in a period, since the same series can be watched on multiple days.
+    let distinct_series_count = |lot: MediaLot| {
+        Func::count_distinct(
+            Expr::case(
+                metadata::Column::Lot.eq(lot),
+                Expr::col((seen::Entity, seen::Column::MetadataId)),
+            )
+            .finally(Expr::val(Option::<String>::None)),
+        )
+    };
+    let series_counts: HashMap<NaiveDate, (i64, i64)> = Seen::find()
+        .filter(seen::Column::UserId.eq(user_id))
+        .filter(seen::Column::State.eq(SeenState::Completed))
+        .filter(metadata::Column::Lot.is_in([MediaLot::Show, MediaLot::Podcast]))
+        .apply_if(input.end_date, |query, v| {
+            query.filter(seen::Column::FinishedOn.lte(v))
+        })
+        .apply_if(input.start_date, |query, v| {
+            query.filter(seen::Column::FinishedOn.gte(v))
+        })
+        .inner_join(metadata::Entity)
+        .select_only()
+        .column_as(
+            truncate_date(seen::Column::FinishedOn.into_expr().into()),
+            "day",
+        )
+        .column_as(distinct_series_count(MediaLot::Show), "show_count")
+        .column_as(distinct_series_count(MediaLot::Podcast), "podcast_count")
+        .group_by(day_alias)
+        .into_tuple::<(Option<NaiveDate>, i64, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|(day, show, podcast)| day.map(|d| (d, (show, podcast))))
+        .collect();
+    for item in items.iter_mut() {
+        let (show_count, podcast_count) = series_counts.get(&item.day).copied().unwrap_or_default();
+        item.show_count = show_count;
+        item.podcast_count = podcast_count;
+    }
+    if let (DailyUserActivitiesResponseGroupedBy::Day, Some(start), Some(end)) =
+        (grouped_by, input.start_date, input.end_date)
+    {
+        let mut by_day: HashMap<_, _> = items.into_iter().map(|i| (i.day, i)).collect();
+        items = date_range_buckets(start, end)
+            .into_iter()
+            .map(|day| {
+                by_day.remove(&day).unwrap_or(DailyUserActivityItem {
+                    day,
+                    ..Default::default()
+                })
+            })
+            .collect();
+    }
+    let total_count = items.iter().map(|i| i.total_count).sum();
+    let total_duration = items.iter().map(|i| i.total_duration).sum();
+    let item_count = items.len();
+    Ok(DailyUserActivitiesResponse {
+        items,
+        grouped_by,
+        item_count,
+        total_count,
+        total_duration,
+    })
+}
+
+/// Summarize what a user finished in a calendar year. Runtimes come from the
+/// specifics of the media, or from the seen entry when it was logged there.
+pub async fn user_yearly_summary(
+    user_id: &String,
+    year: i32,
+    db: &DatabaseConnection,
+) -> Result<UserYearlySummary> {
+    let (Some(start), Some(end)) = (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year, 12, 31),
+    ) else {
+        return Err(Error::new("Invalid year"));
+    };
+    let values = || {
+        [
+            user_id.to_owned().into(),
+            start.into(),
+            end.into(),
+            SeenState::Completed.into(),
+        ]
+    };
+    let lots = UserYearlySummaryLot::find_by_statement(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        r#"
+SELECT
+    "m"."lot",
+    COUNT(*) AS "seen_count",
+    COUNT(DISTINCT "m"."id") AS "metadata_count",
+    COALESCE(SUM("d"."minutes"), 0) AS "minutes",
+    COALESCE(SUM("d"."pages"), 0) AS "pages",
+    COUNT(*) FILTER (
+        WHERE "m"."lot" IN ('audio_book', 'book', 'movie', 'podcast', 'show')
+        AND "d"."minutes" IS NULL AND "d"."pages" IS NULL
+    ) AS "missing_data_count"
+FROM "seen" "s"
+JOIN "metadata" "m" ON "m"."id" = "s"."metadata_id"
+CROSS JOIN LATERAL (
+    SELECT
+        CASE "m"."lot"
+            WHEN 'movie' THEN COALESCE(
+                "s"."minutes_consumed", ("m"."movie_specifics"::JSONB ->> 'runtime')::INTEGER
+            )
+            WHEN 'audio_book' THEN COALESCE(
+                "s"."minutes_consumed", ("m"."audio_book_specifics"::JSONB ->> 'runtime')::INTEGER
+            )
+            WHEN 'show' THEN COALESCE("s"."minutes_consumed", (
+                SELECT ("e" ->> 'runtime')::INTEGER
+                FROM JSONB_ARRAY_ELEMENTS("m"."show_specifics"::JSONB -> 'seasons') "se",
+                JSONB_ARRAY_ELEMENTS("se" -> 'episodes') "e"
+                WHERE ("se" ->> 'season_number')::INTEGER
+                    = ("s"."show_extra_information"::JSONB ->> 'season')::INTEGER
+                AND ("e" ->> 'episode_number')::INTEGER
+                    = ("s"."show_extra_information"::JSONB ->> 'episode')::INTEGER
+                LIMIT 1
+            ))
+            WHEN 'podcast' THEN COALESCE("s"."minutes_consumed", (
+                SELECT ("e" ->> 'runtime')::INTEGER
+                FROM JSONB_ARRAY_ELEMENTS("m"."podcast_specifics"::JSONB -> 'episodes') "e"
+                WHERE ("e" ->> 'number')::INTEGER
+                    = ("s"."podcast_extra_information"::JSONB ->> 'episode')::INTEGER
+                LIMIT 1
+            ))
+        END AS "minutes",
+        CASE "m"."lot"
+            WHEN 'book' THEN COALESCE(
+                "s"."pages_read", ("m"."book_specifics"::JSONB ->> 'pages')::INTEGER
+            )
+        END AS "pages"
+) "d"
+WHERE "s"."user_id" = $1 AND "s"."finished_on" BETWEEN $2 AND $3 AND "s"."state" = $4
+GROUP BY "m"."lot"
+ORDER BY "m"."lot";
+        "#,
+        values(),
+    ))
+    .all(db)
+    .await?;
+    let top_genres = UserYearlySummaryGenre::find_by_statement(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        r#"
+SELECT "g"."name", COUNT(DISTINCT "s"."metadata_id") AS "count"
+FROM "seen" "s"
+JOIN "metadata_to_genre" "mg" ON "mg"."metadata_id" = "s"."metadata_id"
+JOIN "genre" "g" ON "g"."id" = "mg"."genre_id"
+WHERE "s"."user_id" = $1 AND "s"."finished_on" BETWEEN $2 AND $3 AND "s"."state" = $4
+GROUP BY "g"."name"
+ORDER BY "count" DESC, "g"."name"
+LIMIT 10;
+        "#,
+        values(),
+    ))
+    .all(db)
+    .await?;
+    // DEV: Days of a streak are consecutive, so subtracting their rank gives the same
+    // date for all of them
+    #[derive(Debug, FromQueryResult)]
+    struct LongestStreak {
+        days: i64,
+    }
+    let longest_streak_days = LongestStreak::find_by_statement(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        r#"
+SELECT COALESCE(MAX("length"), 0) AS "days" FROM (
+    SELECT COUNT(*) AS "length" FROM (
+        SELECT "day" - (ROW_NUMBER() OVER (ORDER BY "day"))::INTEGER AS "streak"
+        FROM (
+            SELECT DISTINCT "s"."finished_on" AS "day" FROM "seen" "s"
+            WHERE "s"."user_id" = $1 AND "s"."finished_on" BETWEEN $2 AND $3
+            AND "s"."state" = $4
+        ) "days"
+    ) "ranked"
+    GROUP BY "streak"
+) "streaks";
+        "#,
+        values(),
+    ))
+    .one(db)
+    .await?
+    .map(|s| s.days)
+    .unwrap_or_default();
+    Ok(UserYearlySummary {
+        year,
+        lots,
+        top_genres,
+        longest_streak_days,
+        calculated_at: Utc::now(),
+    })
+}
+
+// DEV: Summaries are brought up to date whenever a seen entry changes, the expiry only
+// removes the ones of users who stopped looking at them
+const USER_YEARLY_SUMMARY_EXPIRY_HOURS: i64 = 24 * 30;
+
+/// The cached yearly summary of a user, which is computed when it is not cached yet.
+pub async fn cached_user_yearly_summary(
+    user_id: &String,
+    year: i32,
+    ss: &Arc<SupportingService>,
+) -> Result<UserYearlySummary> {
+    let key = ApplicationCacheKey::UserYearlySummary {
+        user_id: user_id.to_owned(),
+        year,
+    };
+    if let Some(summary) = ss.cache_service.get_value(key).await? {
+        return Ok(summary);
+    }
+    regenerate_user_yearly_summary(user_id, year, ss).await
+}
+
+pub async fn regenerate_user_yearly_summary(
+    user_id: &String,
+    year: i32,
+    ss: &Arc<SupportingService>,
+) -> Result<UserYearlySummary> {
+    let summary = user_yearly_summary(user_id, year, &ss.db).await?;
+    ss.cache_service
+        .set_value_with_expiry(
+            ApplicationCacheKey::UserYearlySummary {
+                user_id: user_id.to_owned(),
+                year,
+            },
+            &summary,
+            USER_YEARLY_SUMMARY_EXPIRY_HOURS,
+        )
+        .await?;
+    Ok(summary)
+}
+
+/// Update the cached summary of a year after a seen entry finished in it changed.
+/// The summary is only computed again if it was cached before.
+pub async fn refresh_cached_user_yearly_summary(
+    user_id: &String,
+    year: i32,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    let key = ApplicationCacheKey::UserYearlySummary {
+        user_id: user_id.to_owned(),
+        year,
+    };
+    if ss.cache_service.get(key).await?.is_some() {
+        regenerate_user_yearly_summary(user_id, year, ss).await?;
+    }
+    Ok(())
+}
+
+pub async fn latest_user_summary(
+    user_id: &String,
+    db: &DatabaseConnection,
+) -> Result<DailyUserActivityItem> {
+    let ls = daily_user_activities(
+        user_id,
+        DailyUserActivitiesInput {
+            group_by: Some(DailyUserActivitiesResponseGroupedBy::Millennium),
+            ..Default::default()
+        },
+        db,
+    )
+    .await?;
+    Ok(ls.items.last().cloned().unwrap_or_default())
+}
+
+pub async fn user_recommendations(
+    user_id: &String,
+    ss: &Arc<SupportingService>,
+) -> Result<Vec<String>> {
+    let preferences = user_by_id(user_id, ss).await?.preferences;
+    let limit = preferences
+        .general
+        .dashboard
+        .into_iter()
+        .find(|d| d.section == DashboardElementLot::Recommendations)
+        .unwrap()
+        .num_elements;
+    let current_hour = Utc::now().hour();
+    let warning_tags = Some(preferences.general.content_warning_tags).filter(|t| !t.is_empty());
+    let recs = Metadata::find()
+        .filter(metadata::Column::IsRecommendation.eq(true))
+        .filter(recommendable_release_condition(get_current_date(
+            &ss.timezone,
+        )))
+        .apply_if(warning_tags, |query, v| {
+            query.filter(metadata_content_warning_condition(&v).not())
+        })
+        .order_by(
+            Expr::expr(Func::md5(
+                Expr::col(metadata::Column::Title)
+                    .concat(Expr::val(user_id))
+                    .concat(Expr::val(current_hour)),
+            )),
+            Order::Desc,
+        )
+        .limit(limit)
+        .all(&ss.db)
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect_vec();
+    Ok(recs)
+}
+
+/// Drop the cached dashboard sections of a user so that they are computed again
+/// the next time the dashboard is requested.
+pub async fn invalidate_user_dashboard_sections(
+    user_id: &String,
+    sections: &[UserDashboardSection],
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    for section in sections {
+        ss.cache_service
+            .delete(ApplicationCacheKey::UserDashboardSection {
+                section: *section,
+                user_id: user_id.to_owned(),
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// The dashboard section that lists the entities of this collection, if any.
+fn collection_dashboard_section(collection_name: &str) -> Option<UserDashboardSection> {
+    if collection_name == DefaultCollection::InProgress.to_string() {
+        Some(UserDashboardSection::InProgress)
+    } else if collection_name == DefaultCollection::Favorites.to_string() {
+        Some(UserDashboardSection::Favorites)
+    } else {
+        None
+    }
+}
+
+/// Drop the dashboard sections that list the entities of this collection, if any.
+async fn invalidate_collection_dashboard_sections(
+    user_id: &String,
+    collection_name: &str,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    let Some(section) = collection_dashboard_section(collection_name) else {
+        return Ok(());
+    };
+    invalidate_user_dashboard_sections(user_id, &[section], ss).await
+}
+
 pub async fn deploy_job_to_calculate_user_activities_and_summary(
     user_id: &String,
     calculate_from_beginning: bool,
@@ -811,3 +1898,701 @@ pub async fn deploy_job_to_re_evaluate_user_workouts(
         .await
         .unwrap();
 }
+
+const SAFETY_SNAPSHOTS_TO_KEEP: u64 = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SafetySnapshotContents {
+    seen: Vec<seen::Model>,
+    reviews: Vec<review::Model>,
+    #[serde(default)]
+    metadata: Vec<metadata::Model>,
+}
+
+// DEV: Snapshots used to be written to the temporary directory, they are only read
+// from there when they were created before the contents moved to the database
+fn legacy_safety_snapshot_path(snapshot_id: &str) -> PathBuf {
+    PathBuf::from(TEMP_DIR)
+        .join("safety-snapshots")
+        .join(format!("{}.json.gz", snapshot_id))
+}
+
+fn compress_safety_snapshot(contents: &SafetySnapshotContents) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    serde_json::to_writer(&mut encoder, contents)?;
+    encoder.finish()
+}
+
+fn read_safety_snapshot(contents: impl io::Read) -> io::Result<SafetySnapshotContents> {
+    Ok(serde_json::from_reader(GzDecoder::new(contents))?)
+}
+
+/// Insert the rows of a snapshot again. Rows whose id still exists are skipped.
+async fn insert_safety_snapshot_rows<C>(contents: SafetySnapshotContents, db: &C) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let metadata = contents
+        .metadata
+        .into_iter()
+        .map(|m| m.into_active_model())
+        .collect_vec();
+    // DEV: Generated columns can not be inserted, the database fills them again
+    let reviews = contents
+        .reviews
+        .into_iter()
+        .map(|r| {
+            let mut review = r.into_active_model();
+            review.entity_id = ActiveValue::NotSet;
+            review.entity_lot = ActiveValue::NotSet;
+            review
+        })
+        .collect_vec();
+    let seen = contents
+        .seen
+        .into_iter()
+        .map(|s| {
+            let last_updated_on = s.last_updated_on;
+            let mut seen = s.into_active_model();
+            seen.updated_at = ActiveValue::Set(vec![last_updated_on]);
+            seen.last_updated_on = ActiveValue::NotSet;
+            seen.num_times_updated = ActiveValue::NotSet;
+            seen.show_season_number = ActiveValue::NotSet;
+            seen.show_episode_number = ActiveValue::NotSet;
+            seen.podcast_episode_number = ActiveValue::NotSet;
+            seen
+        })
+        .collect_vec();
+    // DEV: Media that was deleted after the snapshot is created again first
+    if !metadata.is_empty() {
+        Metadata::insert_many(metadata)
+            .on_conflict(
+                OnConflict::column(metadata::Column::Id)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+    if !reviews.is_empty() {
+        Review::insert_many(reviews)
+            .on_conflict(
+                OnConflict::column(review::Column::Id)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+    if !seen.is_empty() {
+        Seen::insert_many(seen)
+            .on_conflict(OnConflict::column(seen::Column::Id).do_nothing().to_owned())
+            .exec_without_returning(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Save the seen and review rows that a destructive operation could remove so
+/// that they can be restored later. Pass no user to save the rows of everyone and
+/// no media to save the rows of every media item. The media rows themselves are
+/// saved too so that deleted media can be brought back. The operation must not go
+/// ahead if this fails.
+pub async fn create_safety_snapshot(
+    user_id: Option<&String>,
+    metadata_ids: &[String],
+    reason: String,
+    ss: &Arc<SupportingService>,
+) -> Result<String> {
+    let metadata_ids = Some(metadata_ids).filter(|ids| !ids.is_empty());
+    let seen = Seen::find()
+        .apply_if(user_id, |query, v| query.filter(seen::Column::UserId.eq(v)))
+        .apply_if(metadata_ids, |query, v| {
+            query.filter(seen::Column::MetadataId.is_in(v))
+        })
+        .all(&ss.db)
+        .await?;
+    let reviews = Review::find()
+        .apply_if(user_id, |query, v| {
+            query.filter(review::Column::UserId.eq(v))
+        })
+        .apply_if(metadata_ids, |query, v| {
+            query.filter(review::Column::MetadataId.is_in(v))
+        })
+        .all(&ss.db)
+        .await?;
+    let metadata = match metadata_ids {
+        Some(ids) => {
+            Metadata::find()
+                .filter(metadata::Column::Id.is_in(ids))
+                .all(&ss.db)
+                .await?
+        }
+        None => vec![],
+    };
+    let seen_count = seen.len().try_into().unwrap();
+    let review_count = reviews.len().try_into().unwrap();
+    let contents = compress_safety_snapshot(&SafetySnapshotContents {
+        seen,
+        reviews,
+        metadata,
+    })
+    .map_err(|e| Error::new(format!("Could not create a safety snapshot: {}", e)))?;
+    let snapshot = safety_snapshot::ActiveModel {
+        seen_count: ActiveValue::Set(seen_count),
+        review_count: ActiveValue::Set(review_count),
+        reason: ActiveValue::Set(reason),
+        created_on: ActiveValue::Set(Utc::now()),
+        user_id: ActiveValue::Set(user_id.cloned()),
+        contents: ActiveValue::Set(Some(contents)),
+        ..Default::default()
+    }
+    .insert(&ss.db)
+    .await?;
+    ryot_log!(debug, "Created safety snapshot with id = {}", snapshot.id);
+    let to_keep = SafetySnapshot::find()
+        .select_only()
+        .column(safety_snapshot::Column::Id)
+        .order_by_desc(safety_snapshot::Column::CreatedOn)
+        .limit(SAFETY_SNAPSHOTS_TO_KEEP)
+        .into_tuple::<String>()
+        .all(&ss.db)
+        .await?;
+    let expired = SafetySnapshot::find()
+        .select_only()
+        .column(safety_snapshot::Column::Id)
+        .filter(safety_snapshot::Column::Id.is_not_in(to_keep))
+        .into_tuple::<String>()
+        .all(&ss.db)
+        .await?;
+    for expired in expired {
+        remove_file(legacy_safety_snapshot_path(&expired)).ok();
+        SafetySnapshot::delete_by_id(expired).exec(&ss.db).await?;
+    }
+    Ok(snapshot.id)
+}
+
+pub async fn restore_safety_snapshot(
+    snapshot_id: String,
+    ss: &Arc<SupportingService>,
+) -> Result<bool> {
+    let snapshot = SafetySnapshot::find_by_id(&snapshot_id)
+        .one(&ss.db)
+        .await?
+        .ok_or_else(|| Error::new("Safety snapshot does not exist"))?;
+    let read_error =
+        |e: io::Error| Error::new(format!("Could not read the safety snapshot: {}", e));
+    let contents = match &snapshot.contents {
+        Some(contents) => read_safety_snapshot(contents.as_slice()).map_err(read_error)?,
+        None => {
+            let file = File::open(legacy_safety_snapshot_path(&snapshot.id)).map_err(read_error)?;
+            read_safety_snapshot(BufReader::new(file)).map_err(read_error)?
+        }
+    };
+    let txn = ss.db.begin().await?;
+    insert_safety_snapshot_rows(contents, &txn).await?;
+    let mut snapshot: safety_snapshot::ActiveModel = snapshot.into();
+    snapshot.restored_on = ActiveValue::Set(Some(Utc::now()));
+    snapshot.update(&txn).await?;
+    txn.commit().await?;
+    ryot_log!(debug, "Restored safety snapshot with id = {}", snapshot_id);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use enums::{IntegrationHealthState, IntegrationLot};
+    use media_models::{IntegrationProviderSpecifics, PublishYearRange};
+    use sea_orm::{MockDatabase, MockExecResult};
+
+    use super::*;
+
+    #[test]
+    fn episodes_and_series_are_counted_separately() {
+        let day = |d| Date::from_ymd_opt(2024, 10, d).unwrap();
+        let mut series_consumed = HashSet::new();
+        let mut activities: HashMap<Date, daily_user_activity::Model> = HashMap::new();
+        // DEV: Three episodes of one show and one of another on the first day, one
+        // more episode of the first show and two podcast episodes on the second
+        let seeded = [
+            (day(1), "show_1", MediaLot::Show),
+            (day(1), "show_1", MediaLot::Show),
+            (day(1), "show_1", MediaLot::Show),
+            (day(1), "show_2", MediaLot::Show),
+            (day(2), "show_1", MediaLot::Show),
+            (day(2), "podcast_1", MediaLot::Podcast),
+            (day(2), "podcast_1", MediaLot::Podcast),
+        ];
+        for (date, metadata_id, lot) in seeded {
+            let activity = activities
+                .entry(date)
+                .or_insert(daily_user_activity::Model {
+                    date,
+                    ..Default::default()
+                });
+            count_finished_media(activity, &mut series_consumed, metadata_id, lot, true);
+        }
+        let first = &activities[&day(1)];
+        assert_eq!((first.show_count, first.show_episode_count), (2, 4));
+        assert_eq!((first.podcast_count, first.podcast_episode_count), (0, 0));
+        let second = &activities[&day(2)];
+        assert_eq!((second.show_count, second.show_episode_count), (1, 1));
+        assert_eq!((second.podcast_count, second.podcast_episode_count), (1, 2));
+    }
+
+    fn media_server(
+        provider: IntegrationProvider,
+        specifics: IntegrationProviderSpecifics,
+    ) -> integration::Model {
+        integration::Model {
+            provider,
+            id: "int_1".to_owned(),
+            minimum_progress: None,
+            maximum_progress: None,
+            user_id: "usr_1".to_owned(),
+            lot: IntegrationLot::Sink,
+            sync_to_owned_collection: None,
+            default_collection: None,
+            is_disabled: None,
+            created_on: DateTimeUtc::default(),
+            last_triggered_on: None,
+            debug_mode_until: None,
+            expected_activity_days: None,
+            alerts_snoozed_until: None,
+            stale_alert_sent_on: None,
+            consecutive_failures: 0,
+            health_state: IntegrationHealthState::Healthy,
+            provider_specifics: Some(specifics),
+        }
+    }
+
+    fn item_mapping(server_id: Option<&str>) -> integration_item_mapping::Model {
+        integration_item_mapping::Model {
+            id: "iim_1".to_owned(),
+            integration_id: "int_1".to_owned(),
+            metadata_id: "met_1".to_owned(),
+            server_item_id: "a1b2c3".to_owned(),
+            server_id: server_id.map(String::from),
+            updated_on: DateTimeUtc::default(),
+        }
+    }
+
+    #[test]
+    fn jellyfin_links_need_the_web_base_url() {
+        let without_url = media_server(
+            IntegrationProvider::JellyfinSink,
+            IntegrationProviderSpecifics::default(),
+        );
+        assert_eq!(playback_link_url(&without_url, &item_mapping(None)), None);
+        let with_url = media_server(
+            IntegrationProvider::JellyfinSink,
+            IntegrationProviderSpecifics {
+                jellyfin_sink_web_base_url: Some("https://jellyfin.home/".to_owned()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            playback_link_url(&with_url, &item_mapping(None)).as_deref(),
+            Some("https://jellyfin.home/web/index.html#!/details?id=a1b2c3")
+        );
+    }
+
+    #[test]
+    fn plex_links_default_to_the_hosted_app() {
+        let plex = media_server(
+            IntegrationProvider::Plex,
+            IntegrationProviderSpecifics::default(),
+        );
+        assert_eq!(playback_link_url(&plex, &item_mapping(None)), None);
+        assert_eq!(
+            playback_link_url(&plex, &item_mapping(Some("srv"))).as_deref(),
+            Some("https://app.plex.tv/web/index.html#!/server/srv/details?key=%2Flibrary%2Fmetadata%2Fa1b2c3")
+        );
+        let local = media_server(
+            IntegrationProvider::Plex,
+            IntegrationProviderSpecifics {
+                plex_web_base_url: Some("http://plex.home:32400".to_owned()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            playback_link_url(&local, &item_mapping(Some("srv"))).as_deref(),
+            Some("http://plex.home:32400/web/index.html#!/server/srv/details?key=%2Flibrary%2Fmetadata%2Fa1b2c3")
+        );
+        let kodi = media_server(
+            IntegrationProvider::Kodi,
+            IntegrationProviderSpecifics::default(),
+        );
+        assert_eq!(playback_link_url(&kodi, &item_mapping(Some("srv"))), None);
+    }
+
+    #[test]
+    fn collection_changes_invalidate_their_dashboard_section() {
+        assert_eq!(
+            collection_dashboard_section(&DefaultCollection::InProgress.to_string()),
+            Some(UserDashboardSection::InProgress)
+        );
+        assert_eq!(
+            collection_dashboard_section(&DefaultCollection::Favorites.to_string()),
+            Some(UserDashboardSection::Favorites)
+        );
+        assert_eq!(
+            collection_dashboard_section(&DefaultCollection::Watchlist.to_string()),
+            None
+        );
+        assert_eq!(collection_dashboard_section("Books of 2024"), None);
+    }
+
+    #[test]
+    fn skipped_episodes_are_left_out_of_the_activities() {
+        let sql = seen_for_activities(&"user".to_owned(), DateTimeUtc::default())
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#""seen"."state" = 'completed'"#));
+        assert!(!sql.contains("skipped"));
+    }
+
+    #[test]
+    fn pages_read_prefer_the_chosen_edition() {
+        let book = BookSpecifics { pages: Some(300) };
+        let edition = |pages| SeenEdition {
+            pages,
+            name: Some("Paperback".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(book_pages_read(None, &book), Some(300));
+        assert_eq!(book_pages_read(Some(&edition(Some(412))), &book), Some(412));
+        assert_eq!(book_pages_read(Some(&edition(None)), &book), Some(300));
+        assert_eq!(book_pages_read(None, &BookSpecifics::default()), None);
+    }
+
+    #[test]
+    fn entries_without_an_episode_only_count_the_series() {
+        let mut series_consumed = HashSet::new();
+        let mut activity = daily_user_activity::Model::default();
+        count_finished_media(
+            &mut activity,
+            &mut series_consumed,
+            "show_1",
+            MediaLot::Show,
+            false,
+        );
+        count_finished_media(
+            &mut activity,
+            &mut series_consumed,
+            "movie_1",
+            MediaLot::Movie,
+            false,
+        );
+        count_finished_media(
+            &mut activity,
+            &mut series_consumed,
+            "movie_1",
+            MediaLot::Movie,
+            false,
+        );
+        assert_eq!((activity.show_count, activity.show_episode_count), (1, 0));
+        assert_eq!(activity.movie_count, 2);
+    }
+
+    #[test]
+    fn recommendations_keep_media_with_unknown_dates() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let sql = Metadata::find()
+            .select_only()
+            .column(metadata::Column::Id)
+            .filter(recommendable_release_condition(today))
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(
+            r#""metadata"."publish_date" IS NULL AND "metadata"."publish_year" IS NULL"#
+        ));
+        assert!(sql.contains(r#""metadata"."publish_date" <= '2024-06-01'"#));
+        assert!(!sql.contains("NOT"));
+    }
+
+    #[test]
+    fn heatmap_buckets_by_the_local_day_and_hour() {
+        let at = |day, hour, minute| Utc.with_ymd_and_hms(2024, 1, day, hour, minute, 0).unwrap();
+        let timezone = chrono_tz::America::New_York;
+        // DEV: Half an hour before and after midnight in New York, which is 05:00 UTC
+        let entries = || {
+            vec![
+                (at(1, 4, 30), NaiveDate::from_ymd_opt(2023, 12, 31)),
+                (at(1, 5, 30), NaiveDate::from_ymd_opt(2024, 1, 1)),
+                (at(1, 6, 0), None),
+            ]
+        };
+        let input = |year| ConsumptionHeatmapInput {
+            year,
+            lot: None,
+            include_unknown_time: None,
+        };
+        let heatmap = consumption_heatmap_counts(entries(), &input(None), &timezone);
+        assert_eq!(heatmap.counts[6][23], 1);
+        assert_eq!(heatmap.counts[0][0], 1);
+        assert_eq!(heatmap.counts[0][1], 1);
+        assert_eq!(heatmap.counts.iter().flatten().sum::<i64>(), 3);
+        let heatmap = consumption_heatmap_counts(entries(), &input(Some(2023)), &timezone);
+        assert_eq!(heatmap.counts.iter().flatten().sum::<i64>(), 1);
+        assert_eq!(heatmap.counts[6][23], 1);
+    }
+
+    #[test]
+    fn heatmap_counts_entries_without_a_time_only_when_asked() {
+        let updated_on = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        // DEV: Imported entries are logged long after the day they were finished
+        let entries = || vec![(updated_on, NaiveDate::from_ymd_opt(2023, 6, 1))];
+        let input = |include_unknown_time| ConsumptionHeatmapInput {
+            include_unknown_time,
+            lot: None,
+            year: None,
+        };
+        let heatmap =
+            consumption_heatmap_counts(entries(), &input(Some(true)), &chrono_tz::Tz::UTC);
+        assert_eq!(heatmap.unknown_time_count, Some(1));
+        assert_eq!(heatmap.counts.iter().flatten().sum::<i64>(), 0);
+        let heatmap = consumption_heatmap_counts(entries(), &input(None), &chrono_tz::Tz::UTC);
+        assert_eq!(heatmap.unknown_time_count, None);
+    }
+
+    fn snapshot_seen(id: &str) -> seen::Model {
+        seen::Model {
+            id: id.to_owned(),
+            progress: Decimal::ONE_HUNDRED,
+            started_on: None,
+            finished_on: NaiveDate::from_ymd_opt(2024, 6, 1),
+            user_id: "user".to_owned(),
+            metadata_id: "metadata".to_owned(),
+            state: SeenState::Completed,
+            provider_watched_on: None,
+            updated_at: vec![Utc::now()],
+            show_extra_information: None,
+            podcast_extra_information: None,
+            anime_extra_information: None,
+            manga_extra_information: None,
+            manual_time_spent: None,
+            edition: None,
+            pages_read: None,
+            minutes_consumed: None,
+            last_updated_on: Utc::now(),
+            num_times_updated: 1,
+            show_season_number: None,
+            show_episode_number: None,
+            podcast_episode_number: None,
+            review_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn snapshots_restore_the_rows_a_cleanup_removed() {
+        // DEV: The duplicate cleanup kept `seen_1` and removed `seen_2`, restoring
+        // inserts both and skips the one that still exists
+        let archived = compress_safety_snapshot(&SafetySnapshotContents {
+            seen: vec![snapshot_seen("seen_1"), snapshot_seen("seen_2")],
+            reviews: vec![],
+            metadata: vec![],
+        })
+        .unwrap();
+        let contents = read_safety_snapshot(archived.as_slice()).unwrap();
+        assert_eq!(
+            contents.seen.iter().map(|s| s.id.as_str()).collect_vec(),
+            ["seen_1", "seen_2"]
+        );
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        insert_safety_snapshot_rows(contents, &db).await.unwrap();
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 1);
+        let sql = &log[0].statements()[0].sql;
+        assert!(sql.starts_with(r#"INSERT INTO "seen""#));
+        assert!(sql.contains(r#""updated_at""#));
+        assert!(!sql.contains(r#""last_updated_on""#));
+        assert!(sql.ends_with(r#"ON CONFLICT ("id") DO NOTHING"#));
+    }
+
+    #[test]
+    fn corrupted_snapshots_can_not_be_read() {
+        let mut archived = compress_safety_snapshot(&SafetySnapshotContents {
+            seen: vec![snapshot_seen("seen_1")],
+            reviews: vec![],
+            metadata: vec![],
+        })
+        .unwrap();
+        archived.truncate(archived.len() / 2);
+        assert!(read_safety_snapshot(archived.as_slice()).is_err());
+    }
+
+    fn metadata_ids_where(condition: Condition) -> String {
+        Metadata::find()
+            .select_only()
+            .column(metadata::Column::Id)
+            .filter(condition)
+            .build(DatabaseBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn items_with_only_a_year_are_released_once_it_has_passed() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let released = metadata_ids_where(metadata_release_status_condition(
+            MediaReleaseStatus::Released,
+            today,
+        ));
+        assert!(released.contains(r#""metadata"."publish_date" <= '2024-06-01'"#));
+        assert!(released
+            .contains(r#""metadata"."publish_date" IS NULL AND "metadata"."publish_year" < 2024"#));
+        // DEV: An item from the current year without an exact date is not out yet
+        let unreleased = metadata_ids_where(metadata_release_status_condition(
+            MediaReleaseStatus::Unreleased,
+            today,
+        ));
+        assert!(unreleased.contains(r#""metadata"."publish_date" > '2024-06-01'"#));
+        assert!(unreleased.contains(
+            r#""metadata"."publish_date" IS NULL AND "metadata"."publish_year" >= 2024"#
+        ));
+        let unknown = metadata_ids_where(metadata_release_status_condition(
+            MediaReleaseStatus::Unknown,
+            today,
+        ));
+        assert!(unknown.ends_with(
+            r#"WHERE "metadata"."publish_date" IS NULL AND "metadata"."publish_year" IS NULL"#
+        ));
+    }
+
+    #[test]
+    fn decades_narrow_the_publish_year_range() {
+        let filter = |publish_year_range, decade| MediaFilter {
+            general: None,
+            collections: None,
+            publish_year_range,
+            decade,
+            release_status: None,
+            exclude_content_warnings: None,
+        };
+        let years = |filter: MediaFilter| {
+            let query = Metadata::find().select_only().column(metadata::Column::Id);
+            apply_publish_year_filter(query, &filter)
+                .build(DatabaseBackend::Postgres)
+                .to_string()
+        };
+        let sql = years(filter(None, Some(1995)));
+        assert!(sql.contains(
+            r#""metadata"."publish_year" >= 1990 AND "metadata"."publish_year" <= 1999"#
+        ));
+        let range = PublishYearRange {
+            start: Some(1985),
+            end: Some(1993),
+        };
+        let sql = years(filter(Some(range), Some(1990)));
+        assert!(sql.contains(
+            r#""metadata"."publish_year" >= 1990 AND "metadata"."publish_year" <= 1993"#
+        ));
+        let range = PublishYearRange {
+            start: None,
+            end: Some(2000),
+        };
+        let sql = years(filter(Some(range), None));
+        assert!(sql.ends_with(r#"WHERE "metadata"."publish_year" <= 2000"#));
+    }
+
+    #[test]
+    fn previous_source_uses_the_stored_names() {
+        assert_eq!(
+            previous_source_external_id(MediaLot::AudioBook, MediaSource::GoogleBooks, "abc"),
+            "audio_book:google_books:abc"
+        );
+        assert_eq!(
+            previous_source_external_id(MediaLot::Show, MediaSource::Tmdb, "tv:1399"),
+            "show:tmdb:tv:1399"
+        );
+    }
+
+    #[test]
+    fn render_review_text_matches_the_golden_file() {
+        assert_eq!(
+            render_review_text(include_str!("fixtures/review.md")).trim_end(),
+            include_str!("fixtures/review.html").trim_end()
+        );
+    }
+
+    #[test]
+    fn render_review_text_escapes_raw_html() {
+        for text in [
+            "<script>alert(1)</script>",
+            "Great <script>alert(1)</script> movie",
+            "<img src=x onerror=alert(1)>",
+            "Nice <img src=x onerror=alert(1)> cast",
+            "<a href=\"https://example.com\" onclick=\"alert(1)\">link</a>",
+        ] {
+            let html = render_review_text(text);
+            assert!(!html.contains("<script"), "{html}");
+            assert!(!html.contains("<img"), "{html}");
+            assert!(!html.contains("<a "), "{html}");
+            assert!(html.contains("&lt;"), "{html}");
+        }
+    }
+
+    #[test]
+    fn render_review_text_drops_dangerous_links() {
+        for text in [
+            "[click](javascript:alert(1))",
+            "[click](JavaScript:alert(1))",
+            "![image](javascript:alert(1))",
+            "<javascript:alert(1)>",
+            "[click](vbscript:msgbox(1))",
+            "[click](data:text/html;base64,PHNjcmlwdD4=)",
+        ] {
+            let html = render_review_text(text).to_lowercase();
+            assert!(!html.contains("href=\"javascript"), "{html}");
+            assert!(!html.contains("src=\"javascript"), "{html}");
+            assert!(!html.contains("href=\"vbscript"), "{html}");
+            assert!(!html.contains("href=\"data"), "{html}");
+        }
+        assert_eq!(
+            render_review_text("[site](https://example.com)"),
+            "<p><a href=\"https://example.com\">site</a></p>"
+        );
+    }
+
+    #[test]
+    fn collections_hold_media_and_people_side_by_side() {
+        let book = new_collection_entity(
+            "favorites".to_owned(),
+            "meta_1".to_owned(),
+            EntityLot::Metadata,
+            None,
+            1,
+        );
+        let author = new_collection_entity(
+            "favorites".to_owned(),
+            "person_1".to_owned(),
+            EntityLot::Person,
+            None,
+            2,
+        );
+        // DEV: The entity id and lot of the contents are generated from these columns
+        assert_eq!(
+            book.metadata_id,
+            ActiveValue::Set(Some("meta_1".to_owned()))
+        );
+        assert_eq!(book.person_id, ActiveValue::NotSet);
+        assert_eq!(
+            author.person_id,
+            ActiveValue::Set(Some("person_1".to_owned()))
+        );
+        assert_eq!(author.metadata_id, ActiveValue::NotSet);
+        let sql = |created: collection_to_entity::ActiveModel| {
+            CollectionToEntity::insert(created)
+                .build(DatabaseBackend::Postgres)
+                .to_string()
+        };
+        assert!(sql(book).contains(r#"("collection_id", "metadata_id", "information", "rank")"#));
+        assert!(sql(author).contains(r#"("collection_id", "person_id", "information", "rank")"#));
+    }
+}
@@ -1,31 +1,44 @@
-use std::{collections::HashMap, iter::zip, sync::Arc};
+use std::{collections::HashMap, iter::zip, sync::Arc, time::Instant};
 
 use anyhow::{bail, Result as AnyhowResult};
-use application_utils::get_current_date;
+use application_utils::{
+    get_current_date,
+    text::{description_preview, label_key, normalize_description, sort_key, sort_title},
+};
 use async_graphql::{Enum, Error, Result};
 use background::{ApplicationJob, CoreApplicationJob};
-use chrono::Utc;
+use chrono::{Datelike, Duration, Utc};
 use common_models::{
     ApplicationCacheKey, BackgroundJob, ChangeCollectionToEntityInput, DefaultCollection,
-    MediaStateChanged, StoredUrl, StringIdObject,
+    MediaStateChanged, StoredUrl, StringIdObject, UserDashboardSection,
+};
+use common_utils::{
+    ryot_log, upgrade_image_url, OUTBOUND_WEBHOOK_PAYLOAD_VERSION, SHOW_SPECIAL_SEASON_NAMES,
 };
-use common_utils::{ryot_log, SHOW_SPECIAL_SEASON_NAMES};
 use database_models::{
-    collection_to_entity, genre, metadata, metadata_group, metadata_to_genre, metadata_to_metadata,
-    metadata_to_person, monitored_entity, person,
+    collection, collection_to_entity,
+    functions::associate_user_with_entities,
+    genre, integration, metadata, metadata_external_id,
+    metadata_group::{self, MetadataGroupWithoutId},
+    metadata_to_genre, metadata_to_metadata, metadata_to_person, metadata_to_tag, monitored_entity,
+    outbound_webhook_delivery, person,
     prelude::{
-        Collection, CollectionToEntity, Exercise, Genre, Metadata, MetadataGroup, MetadataToGenre,
-        MetadataToMetadata, MetadataToPerson, MonitoredEntity, Person, Seen, UserToEntity, Workout,
+        Collection, CollectionToEntity, Exercise, Genre, Integration, Metadata, MetadataExternalId,
+        MetadataGroup, MetadataToGenre, MetadataToMetadata, MetadataToPerson, MetadataToTag,
+        MonitoredEntity, Person, Review, Seen, Tag, UserToEntity, Workout,
     },
-    queued_notification, review, seen, user_measurement, user_to_entity, workout,
+    queued_notification, review, seen, tag, user_measurement, user_to_entity, workout,
 };
 use database_utils::{
     add_entity_to_collection, admin_account_guard, create_or_update_collection,
-    deploy_job_to_re_evaluate_user_workouts, remove_entity_from_collection, user_by_id,
+    deploy_job_to_re_evaluate_user_workouts, invalidate_user_dashboard_sections,
+    metadata_by_external_id, previous_source_external_id, refresh_cached_user_yearly_summary,
+    remove_entity_from_collection, user_by_id,
 };
-use dependent_models::ImportResult;
+use dependent_models::{ImportResult, MetadataFinishedStatus};
 use enums::{
-    EntityLot, ExerciseLot, MediaLot, MediaSource, MetadataToMetadataRelation, SeenState,
+    EntityLot, ExerciseLot, ExternalIdType, IntegrationProvider, MediaLot, MediaSource,
+    MetadataToMetadataRelation, OutboundWebhookDeliveryStatus, OutboundWebhookEvent, SeenState,
     Visibility,
 };
 use file_storage_service::FileStorageService;
@@ -40,16 +53,19 @@ use importer_models::{ImportDetails, ImportFailStep, ImportFailedItem, ImportRes
 use itertools::Itertools;
 use media_models::{
     CommitMediaInput, CommitPersonInput, CreateOrUpdateCollectionInput, CreateOrUpdateReviewInput,
-    ImportOrExportItemRating, MetadataDetails, MetadataImage, PartialMetadata,
+    ImportOrExportItemRating, ImportOrExportMediaItem, ImportOrExportMediaItemSeen,
+    MetadataDetails, MetadataFieldChange, MetadataImage, MetadataRefreshProgress,
+    MetadataRefreshStage, OutboundWebhookMedia, OutboundWebhookPayload, PartialMetadata,
     PartialMetadataPerson, PartialMetadataWithoutId, ProgressUpdateError,
     ProgressUpdateErrorVariant, ProgressUpdateInput, ProgressUpdateResultUnion, ReviewPostedEvent,
-    SeenAnimeExtraInformation, SeenMangaExtraInformation, SeenPodcastExtraInformation,
-    SeenShowExtraInformation,
+    SeenAnimeExtraInformation, SeenChangesCursor, SeenMangaExtraInformation,
+    SeenPodcastExtraInformation, SeenShowExtraInformation, SwapMetadataSourceInput,
 };
 use nanoid::nanoid;
 use providers::{
     anilist::{AnilistAnimeService, AnilistMangaService},
     audible::AudibleService,
+    circuit_breaker::{provider_retry_at, CircuitBreakingProvider},
     google_books::GoogleBooksService,
     igdb::IgdbService,
     itunes::ITunesService,
@@ -57,6 +73,7 @@ use providers::{
     mal::{MalAnimeService, MalMangaService},
     manga_updates::MangaUpdatesService,
     openlibrary::OpenlibraryService,
+    progress::{page_progress, track_page_progress, untrack_page_progress},
     tmdb::{NonMediaTmdbService, TmdbMovieService, TmdbShowService},
     vndb::VndbService,
 };
@@ -67,13 +84,15 @@ use rust_decimal::{
 use rust_decimal_macros::dec;
 use sea_orm::{
     prelude::{DateTimeUtc, Expr},
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait,
-    QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    sea_query::{OnConflict, SimpleExpr},
+    ActiveEnum, ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait,
+    DatabaseConnection, DbErr, EntityTrait, Iterable, ModelTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, QueryTrait, Select, SqlErr, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use supporting_service::SupportingService;
-use traits::{MediaProvider, TraceOk};
-use user_models::{UserPreferences, UserReviewScale};
+use traits::{MediaProvider, TraceOk, Validate};
+use user_models::UserPreferences;
 use uuid::Uuid;
 
 pub type Provider = Box<(dyn MediaProvider + Send + Sync)>;
@@ -93,6 +112,35 @@ pub async fn first_metadata_image_as_url(
     }
 }
 
+/// Providers and users can point at the same image more than once, so only the first
+/// occurrence of every URL is kept for a media item.
+fn dedup_metadata_images(images: Vec<MetadataImage>) -> Vec<MetadataImage> {
+    images.into_iter().unique_by(|i| i.url.clone()).collect()
+}
+
+/// The images of a media item after it is refreshed from its provider.
+fn refreshed_metadata_images(
+    existing: Option<&Vec<MetadataImage>>,
+    details: &MetadataDetails,
+) -> Vec<MetadataImage> {
+    // DEV: Images uploaded by users are unknown to the provider, so they are kept
+    let mut images = existing
+        .into_iter()
+        .flatten()
+        .filter(|i| i.is_user_provided)
+        .cloned()
+        .collect_vec();
+    images.extend(details.url_images.iter().map(|i| MetadataImage {
+        url: StoredUrl::Url(upgrade_image_url(i.image.clone())),
+        ..Default::default()
+    }));
+    images.extend(details.s3_images.iter().map(|i| MetadataImage {
+        url: StoredUrl::S3(i.image.clone()),
+        ..Default::default()
+    }));
+    dedup_metadata_images(images)
+}
+
 pub async fn metadata_images_as_urls(
     value: &Option<Vec<MetadataImage>>,
     file_storage_service: &FileStorageService,
@@ -168,7 +216,37 @@ pub async fn get_metadata_provider(
         }
         MediaSource::Custom => return err(),
     };
-    Ok(service)
+    Ok(Box::new(CircuitBreakingProvider::new(
+        source,
+        &ss.config.media,
+        service,
+    )))
+}
+
+/// The best rating on the scale that each provider uses.
+fn provider_rating_scale(source: MediaSource) -> Option<Decimal> {
+    match source {
+        MediaSource::Audible | MediaSource::GoogleBooks => Some(dec!(5)),
+        MediaSource::Mal | MediaSource::MangaUpdates | MediaSource::Tmdb => Some(dec!(10)),
+        MediaSource::Anilist | MediaSource::Igdb | MediaSource::Listennotes | MediaSource::Vndb => {
+            Some(dec!(100))
+        }
+        MediaSource::Custom | MediaSource::Itunes | MediaSource::Openlibrary => None,
+    }
+}
+
+/// Ratings are stored out of 100 so that media from different providers can be
+/// sorted together. The scale is returned so that the original can be shown.
+pub fn normalize_provider_rating(
+    rating: Option<Decimal>,
+    source: MediaSource,
+) -> (Option<Decimal>, Option<Decimal>) {
+    match (rating, provider_rating_scale(source)) {
+        (Some(rating), Some(scale)) => {
+            (Some((rating * dec!(100) / scale).round_dp(2)), Some(scale))
+        }
+        _ => (None, None),
+    }
 }
 
 pub async fn details_from_provider(
@@ -182,61 +260,74 @@ pub async fn details_from_provider(
     Ok(results)
 }
 
+// DEV: Concurrent commits of the same media look up the same people, genres and
+// tags and can both try to create them. The unique constraints let only one of the
+// inserts through, the other one uses the row that won.
+async fn find_after_insert_conflict<E>(
+    error: DbErr,
+    find: Select<E>,
+    db: &DatabaseConnection,
+) -> Result<E::Model>
+where
+    E: EntityTrait,
+{
+    if !matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) {
+        return Err(Error::new(error.to_string()));
+    }
+    ryot_log!(
+        debug,
+        "Insert lost a race, using the existing row: {:?}",
+        error
+    );
+    find.one(db)
+        .await?
+        .ok_or_else(|| Error::new(error.to_string()))
+}
+
 pub async fn commit_person(
     input: CommitPersonInput,
     db: &DatabaseConnection,
 ) -> Result<StringIdObject> {
-    if let Some(p) = Person::find()
+    let find = Person::find()
         .filter(person::Column::Source.eq(input.source))
         .filter(person::Column::Identifier.eq(input.identifier.clone()))
         .apply_if(input.source_specifics.clone(), |query, v| {
             query.filter(person::Column::SourceSpecifics.eq(v))
-        })
-        .one(db)
-        .await?
-        .map(|p| StringIdObject { id: p.id })
-    {
-        Ok(p)
-    } else {
-        let person = person::ActiveModel {
-            identifier: ActiveValue::Set(input.identifier),
-            source: ActiveValue::Set(input.source),
-            source_specifics: ActiveValue::Set(input.source_specifics),
-            name: ActiveValue::Set(input.name),
-            is_partial: ActiveValue::Set(Some(true)),
-            ..Default::default()
-        };
-        let person = person.insert(db).await?;
-        Ok(StringIdObject { id: person.id })
+        });
+    if let Some(p) = find.clone().one(db).await? {
+        return Ok(StringIdObject { id: p.id });
     }
+    let person = person::ActiveModel {
+        identifier: ActiveValue::Set(input.identifier),
+        source: ActiveValue::Set(input.source),
+        source_specifics: ActiveValue::Set(input.source_specifics),
+        name: ActiveValue::Set(input.name),
+        is_partial: ActiveValue::Set(Some(true)),
+        ..Default::default()
+    };
+    let person = match person.insert(db).await {
+        Ok(p) => p,
+        Err(e) => find_after_insert_conflict(e, find, db).await?,
+    };
+    Ok(StringIdObject { id: person.id })
 }
 
-async fn associate_person_with_metadata(
-    metadata_id: &str,
-    person: PartialMetadataPerson,
-    index: usize,
-    db: &DatabaseConnection,
-) -> Result<()> {
-    let role = person.role.clone();
-    let db_person = commit_person(
-        CommitPersonInput {
-            identifier: person.identifier.clone(),
-            source: person.source,
-            source_specifics: person.source_specifics,
-            name: person.name,
-        },
-        db,
-    )
-    .await?;
-    let intermediate = metadata_to_person::ActiveModel {
-        metadata_id: ActiveValue::Set(metadata_id.to_owned()),
-        person_id: ActiveValue::Set(db_person.id),
-        role: ActiveValue::Set(role),
-        index: ActiveValue::Set(Some(index.try_into().unwrap())),
-        character: ActiveValue::Set(person.character),
+async fn genre_by_name(name: String, db: &DatabaseConnection) -> Result<genre::Model> {
+    let find = Genre::find().filter(genre::Column::Name.eq(&name));
+    let db_genre = match find.clone().one(db).await? {
+        Some(c) => c,
+        None => {
+            let c = genre::ActiveModel {
+                name: ActiveValue::Set(name),
+                ..Default::default()
+            };
+            match c.insert(db).await {
+                Ok(c) => c,
+                Err(e) => find_after_insert_conflict(e, find, db).await?,
+            }
+        }
     };
-    intermediate.insert(db).await.ok();
-    Ok(())
+    Ok(db_genre)
 }
 
 async fn associate_genre_with_metadata(
@@ -244,20 +335,7 @@ async fn associate_genre_with_metadata(
     metadata_id: &str,
     db: &DatabaseConnection,
 ) -> Result<()> {
-    let db_genre = if let Some(c) = Genre::find()
-        .filter(genre::Column::Name.eq(&name))
-        .one(db)
-        .await
-        .unwrap()
-    {
-        c
-    } else {
-        let c = genre::ActiveModel {
-            name: ActiveValue::Set(name),
-            ..Default::default()
-        };
-        c.insert(db).await.unwrap()
-    };
+    let db_genre = genre_by_name(name, db).await?;
     let intermediate = metadata_to_genre::ActiveModel {
         metadata_id: ActiveValue::Set(metadata_id.to_owned()),
         genre_id: ActiveValue::Set(db_genre.id),
@@ -266,23 +344,165 @@ async fn associate_genre_with_metadata(
     Ok(())
 }
 
+/// The tag with the same normalized name, which is created if it does not exist yet.
+async fn tag_by_name(name: &str, db: &DatabaseConnection) -> Result<tag::Model> {
+    let name = name.split_whitespace().collect_vec().join(" ");
+    let key = label_key(&name);
+    if key.is_empty() {
+        return Err(Error::new("The tag can not be empty"));
+    }
+    let find = Tag::find().filter(tag::Column::Key.eq(&key));
+    let db_tag = match find.clone().one(db).await? {
+        Some(t) => t,
+        None => {
+            let t = tag::ActiveModel {
+                name: ActiveValue::Set(name),
+                key: ActiveValue::Set(key),
+                created_on: ActiveValue::Set(Utc::now()),
+                is_hidden: ActiveValue::Set(false),
+                ..Default::default()
+            };
+            match t.insert(db).await {
+                Ok(t) => t,
+                Err(e) => find_after_insert_conflict(e, find, db).await?,
+            }
+        }
+    };
+    Ok(db_tag)
+}
+
+/// Add a tag to a media item, creating the tag if no tag with the same normalized
+/// name exists yet. Tags hidden by an admin are not added again. Adding a tag that
+/// the media already has does nothing.
+pub async fn associate_tag_with_metadata(
+    name: &str,
+    metadata_id: &str,
+    user_id: Option<&String>,
+    db: &DatabaseConnection,
+) -> Result<Option<tag::Model>> {
+    let db_tag = tag_by_name(name, db).await?;
+    if db_tag.is_hidden {
+        return Ok(None);
+    }
+    MetadataToTag::insert(metadata_to_tag::ActiveModel {
+        metadata_id: ActiveValue::Set(metadata_id.to_owned()),
+        tag_id: ActiveValue::Set(db_tag.id.clone()),
+        user_id: ActiveValue::Set(user_id.cloned()),
+        created_on: ActiveValue::Set(Utc::now()),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            metadata_to_tag::Column::MetadataId,
+            metadata_to_tag::Column::TagId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(Some(db_tag))
+}
+
+/// Record the ids by which other services know a media item. Ids that already
+/// belong to another item are left alone, so the first item to claim one keeps it.
+async fn associate_external_ids_with_metadata<C: ConnectionTrait>(
+    metadata: &metadata::Model,
+    db: &C,
+) -> Result<()> {
+    let mut ids = vec![];
+    match (metadata.source, metadata.lot) {
+        (MediaSource::Tmdb, MediaLot::Movie) => ids.push(ExternalIdType::TmdbMovie),
+        (MediaSource::Tmdb, MediaLot::Show) => ids.push(ExternalIdType::TmdbShow),
+        (MediaSource::Anilist, _) => ids.push(ExternalIdType::Anilist),
+        (MediaSource::Mal, MediaLot::Anime) => ids.push(ExternalIdType::MalAnime),
+        (MediaSource::Mal, MediaLot::Manga) => ids.push(ExternalIdType::MalManga),
+        (MediaSource::Igdb, _) => ids.push(ExternalIdType::Igdb),
+        _ => {}
+    }
+    let mut ids = ids
+        .into_iter()
+        .map(|id_type| (id_type, metadata.identifier.clone()))
+        .collect_vec();
+    if let Some(external) = &metadata.external_identifiers {
+        if let Some(imdb_id) = &external.imdb_id {
+            ids.push((ExternalIdType::Imdb, imdb_id.to_owned()));
+        }
+        if let Some(tvdb_id) = external.tvdb_id {
+            ids.push((ExternalIdType::Tvdb, tvdb_id.to_string()));
+        }
+        if let Some(steam_app_id) = external.steam_app_id {
+            ids.push((ExternalIdType::Steam, steam_app_id.to_string()));
+        }
+        match (external.mal_id, metadata.lot) {
+            (Some(mal_id), MediaLot::Anime) => {
+                ids.push((ExternalIdType::MalAnime, mal_id.to_string()))
+            }
+            (Some(mal_id), MediaLot::Manga) => {
+                ids.push((ExternalIdType::MalManga, mal_id.to_string()))
+            }
+            _ => {}
+        }
+    }
+    let ids = ids
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect_vec();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    MetadataExternalId::insert_many(ids.into_iter().map(|(id_type, value)| {
+        metadata_external_id::ActiveModel {
+            id_type: ActiveValue::Set(id_type),
+            value: ActiveValue::Set(value),
+            metadata_id: ActiveValue::Set(metadata.id.clone()),
+        }
+    }))
+    .on_conflict(
+        OnConflict::columns([
+            metadata_external_id::Column::IdType,
+            metadata_external_id::Column::Value,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+/// The title that a media is sorted by. An override set by an admin wins over the
+/// one computed from the title.
+pub fn metadata_sort_title(
+    title: &str,
+    romanized_title: Option<&str>,
+    sort_title_override: Option<&str>,
+    ss: &Arc<SupportingService>,
+) -> String {
+    match sort_title_override {
+        Some(sort_title_override) => sort_key(sort_title_override),
+        None => sort_title(
+            title,
+            romanized_title,
+            &ss.config.media.sort_title_languages,
+        ),
+    }
+}
+
 pub async fn create_partial_metadata(
     data: PartialMetadataWithoutId,
     db: &DatabaseConnection,
 ) -> Result<PartialMetadata> {
-    let mode = if let Some(c) = Metadata::find()
+    let find = Metadata::find()
         .filter(metadata::Column::Identifier.eq(&data.identifier))
         .filter(metadata::Column::Lot.eq(data.lot))
-        .filter(metadata::Column::Source.eq(data.source))
-        .one(db)
-        .await
-        .unwrap()
-    {
+        .filter(metadata::Column::Source.eq(data.source));
+    let mode = if let Some(c) = find.clone().one(db).await? {
         c
     } else {
         let image = data.image.clone().map(|i| {
             vec![MetadataImage {
                 url: StoredUrl::Url(i),
+                ..Default::default()
             }]
         });
         let c = metadata::ActiveModel {
@@ -295,7 +515,10 @@ pub async fn create_partial_metadata(
             is_recommendation: ActiveValue::Set(Some(true)),
             ..Default::default()
         };
-        c.insert(db).await?
+        match c.insert(db).await {
+            Ok(c) => c,
+            Err(e) => find_after_insert_conflict(e, find, db).await?,
+        }
     };
     let model = PartialMetadata {
         id: mode.id,
@@ -309,22 +532,6 @@ pub async fn create_partial_metadata(
     Ok(model)
 }
 
-async fn associate_suggestion_with_metadata(
-    data: PartialMetadataWithoutId,
-    metadata_id: &str,
-    db: &DatabaseConnection,
-) -> Result<()> {
-    let db_partial_metadata = create_partial_metadata(data, db).await?;
-    let intermediate = metadata_to_metadata::ActiveModel {
-        from_metadata_id: ActiveValue::Set(metadata_id.to_owned()),
-        to_metadata_id: ActiveValue::Set(db_partial_metadata.id),
-        relation: ActiveValue::Set(MetadataToMetadataRelation::Suggestion),
-        ..Default::default()
-    };
-    intermediate.insert(db).await.ok();
-    Ok(())
-}
-
 async fn deploy_associate_group_with_metadata_job(
     lot: MediaLot,
     source: MediaSource,
@@ -337,45 +544,274 @@ async fn deploy_associate_group_with_metadata_job(
     .await
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn change_metadata_associations(
+async fn metadata_person_ids(metadata_id: &String, db: &DatabaseConnection) -> Result<Vec<String>> {
+    let people = MetadataToPerson::find()
+        .select_only()
+        .column(metadata_to_person::Column::PersonId)
+        .filter(metadata_to_person::Column::MetadataId.eq(metadata_id))
+        .distinct()
+        .into_tuple::<String>()
+        .all(db)
+        .await?;
+    Ok(people)
+}
+
+/// The notifications for every user who has one of these people in any of their
+/// collections that the media item is a new work of theirs.
+async fn new_creator_work_notifications<C: ConnectionTrait>(
     metadata_id: &String,
-    lot: MediaLot,
-    source: MediaSource,
+    person_ids: Vec<String>,
+    db: &C,
+) -> Result<Vec<(String, (String, MediaStateChanged))>> {
+    let mut notifications = vec![];
+    if person_ids.is_empty() {
+        return Ok(notifications);
+    }
+    let followers = CollectionToEntity::find()
+        .select_only()
+        .column(collection::Column::UserId)
+        .column(collection_to_entity::Column::PersonId)
+        .inner_join(Collection)
+        .filter(collection_to_entity::Column::PersonId.is_in(person_ids))
+        .distinct()
+        .into_tuple::<(String, String)>()
+        .all(db)
+        .await?;
+    if followers.is_empty() {
+        return Ok(notifications);
+    }
+    let Some(metadata) = Metadata::find_by_id(metadata_id).one(db).await? else {
+        return Ok(notifications);
+    };
+    // DEV: Sorted so that the people are looked up in the same order every time
+    let followers = followers
+        .into_iter()
+        .into_group_map_by(|(_, person_id)| person_id.clone())
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0));
+    for (person_id, users) in followers {
+        let Some(person) = Person::find_by_id(&person_id).one(db).await? else {
+            continue;
+        };
+        for (user_id, _) in users {
+            notifications.push((
+                user_id,
+                (
+                    format!("{} has a new work: {}", person.name, metadata.title),
+                    MediaStateChanged::PersonMediaAssociated,
+                ),
+            ));
+        }
+    }
+    Ok(notifications)
+}
+
+/// Notify the followers of these people that the media item is a new work of theirs.
+async fn notify_users_about_new_creator_work(
+    metadata_id: &String,
+    person_ids: Vec<String>,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    let notifications = new_creator_work_notifications(metadata_id, person_ids, &ss.db).await?;
+    for (user_id, notification) in notifications {
+        queue_media_state_changed_notification_for_user(&user_id, &notification, ss)
+            .await
+            .trace_ok();
+    }
+    Ok(())
+}
+
+/// The people, genres, tags and suggestions of a media item. These are created
+/// before the media is linked to them, so that the links can be replaced in a
+/// transaction.
+#[derive(Default)]
+struct MetadataAssociations {
+    people: Vec<metadata_to_person::ActiveModel>,
+    genre_ids: Vec<String>,
+    tag_ids: Vec<String>,
+    suggestion_ids: Vec<String>,
+}
+
+async fn resolve_metadata_associations(
     genres: Vec<String>,
+    tags: Vec<String>,
     suggestions: Vec<PartialMetadataWithoutId>,
-    groups: Vec<String>,
     people: Vec<PartialMetadataPerson>,
     ss: &Arc<SupportingService>,
+) -> Result<MetadataAssociations> {
+    let mut associations = MetadataAssociations::default();
+    for (index, person) in people.into_iter().enumerate() {
+        let input = CommitPersonInput {
+            identifier: person.identifier,
+            source: person.source,
+            source_specifics: person.source_specifics,
+            name: person.name,
+        };
+        if let Ok(db_person) = commit_person(input, &ss.db).await {
+            associations.people.push(metadata_to_person::ActiveModel {
+                person_id: ActiveValue::Set(db_person.id),
+                role: ActiveValue::Set(person.role),
+                index: ActiveValue::Set(Some(index.try_into().unwrap())),
+                character: ActiveValue::Set(person.character),
+                ..Default::default()
+            });
+        }
+    }
+    for genre in genres.into_iter().unique() {
+        if let Ok(db_genre) = genre_by_name(genre, &ss.db).await {
+            associations.genre_ids.push(db_genre.id);
+        }
+    }
+    for tag in tags {
+        if let Ok(db_tag) = tag_by_name(&tag, &ss.db).await {
+            if !db_tag.is_hidden {
+                associations.tag_ids.push(db_tag.id);
+            }
+        }
+    }
+    // DEV: Providers return the most relevant suggestions first
+    for suggestion in suggestions
+        .into_iter()
+        .take(ss.config.media.max_suggestions)
+    {
+        if let Ok(partial) = create_partial_metadata(suggestion, &ss.db).await {
+            associations.suggestion_ids.push(partial.id);
+        }
+    }
+    Ok(associations)
+}
+
+/// Link a media item to exactly these people, genres, provider tags and suggestions.
+async fn replace_metadata_associations<C: ConnectionTrait>(
+    metadata_id: &str,
+    associations: MetadataAssociations,
+    db: &C,
 ) -> Result<()> {
     MetadataToPerson::delete_many()
         .filter(metadata_to_person::Column::MetadataId.eq(metadata_id))
-        .exec(&ss.db)
+        .exec(db)
         .await?;
     MetadataToGenre::delete_many()
         .filter(metadata_to_genre::Column::MetadataId.eq(metadata_id))
-        .exec(&ss.db)
+        .exec(db)
+        .await?;
+    // DEV: Tags added by users are kept when the provider data changes
+    MetadataToTag::delete_many()
+        .filter(metadata_to_tag::Column::MetadataId.eq(metadata_id))
+        .filter(metadata_to_tag::Column::UserId.is_null())
+        .exec(db)
         .await?;
     MetadataToMetadata::delete_many()
         .filter(metadata_to_metadata::Column::FromMetadataId.eq(metadata_id))
         .filter(metadata_to_metadata::Column::Relation.eq(MetadataToMetadataRelation::Suggestion))
-        .exec(&ss.db)
+        .exec(db)
+        .await?;
+    if !associations.people.is_empty() {
+        MetadataToPerson::insert_many(associations.people.into_iter().map(|mut p| {
+            p.metadata_id = ActiveValue::Set(metadata_id.to_owned());
+            p
+        }))
+        .on_conflict(
+            OnConflict::columns([
+                metadata_to_person::Column::MetadataId,
+                metadata_to_person::Column::PersonId,
+                metadata_to_person::Column::Role,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
         .await?;
-    for (index, creator) in people.into_iter().enumerate() {
-        associate_person_with_metadata(metadata_id, creator, index, &ss.db)
-            .await
-            .ok();
     }
-    for genre in genres {
-        associate_genre_with_metadata(genre, metadata_id, &ss.db)
-            .await
-            .ok();
+    if !associations.genre_ids.is_empty() {
+        MetadataToGenre::insert_many(associations.genre_ids.into_iter().map(|genre_id| {
+            metadata_to_genre::ActiveModel {
+                metadata_id: ActiveValue::Set(metadata_id.to_owned()),
+                genre_id: ActiveValue::Set(genre_id),
+            }
+        }))
+        .on_conflict(
+            OnConflict::columns([
+                metadata_to_genre::Column::MetadataId,
+                metadata_to_genre::Column::GenreId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
     }
-    for suggestion in suggestions {
-        associate_suggestion_with_metadata(suggestion, metadata_id, &ss.db)
-            .await
-            .ok();
+    if !associations.tag_ids.is_empty() {
+        MetadataToTag::insert_many(associations.tag_ids.into_iter().map(|tag_id| {
+            metadata_to_tag::ActiveModel {
+                metadata_id: ActiveValue::Set(metadata_id.to_owned()),
+                tag_id: ActiveValue::Set(tag_id),
+                user_id: ActiveValue::Set(None),
+                created_on: ActiveValue::Set(Utc::now()),
+            }
+        }))
+        .on_conflict(
+            OnConflict::columns([
+                metadata_to_tag::Column::MetadataId,
+                metadata_to_tag::Column::TagId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    }
+    if !associations.suggestion_ids.is_empty() {
+        MetadataToMetadata::insert_many(associations.suggestion_ids.into_iter().enumerate().map(
+            |(index, to_metadata_id)| metadata_to_metadata::ActiveModel {
+                from_metadata_id: ActiveValue::Set(metadata_id.to_owned()),
+                to_metadata_id: ActiveValue::Set(to_metadata_id),
+                relation: ActiveValue::Set(MetadataToMetadataRelation::Suggestion),
+                index: ActiveValue::Set(Some(index.try_into().unwrap())),
+                ..Default::default()
+            },
+        ))
+        .on_conflict(
+            OnConflict::columns([
+                metadata_to_metadata::Column::FromMetadataId,
+                metadata_to_metadata::Column::Relation,
+                metadata_to_metadata::Column::ToMetadataId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
     }
+    Ok(())
+}
+
+/// The people of a media item that were not linked to it before its links were
+/// replaced, only their followers hear about the media.
+fn newly_linked_people(people: Vec<String>, previous_people: &[String]) -> Vec<String> {
+    people
+        .into_iter()
+        .filter(|p| !previous_people.contains(p))
+        .collect_vec()
+}
+
+/// What happens once the links of a media item were replaced: followers of people
+/// who are new to the media are notified and its groups are associated.
+async fn after_metadata_associations_changed(
+    metadata_id: &String,
+    lot: MediaLot,
+    source: MediaSource,
+    previous_people: Vec<String>,
+    groups: Vec<String>,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    let new_people = newly_linked_people(
+        metadata_person_ids(metadata_id, &ss.db).await?,
+        &previous_people,
+    );
+    notify_users_about_new_creator_work(metadata_id, new_people, ss)
+        .await
+        .trace_ok();
     for group_identifier in groups {
         deploy_associate_group_with_metadata_job(lot, source, group_identifier, ss)
             .await
@@ -384,6 +820,24 @@ async fn change_metadata_associations(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
+async fn change_metadata_associations(
+    metadata_id: &String,
+    lot: MediaLot,
+    source: MediaSource,
+    genres: Vec<String>,
+    tags: Vec<String>,
+    suggestions: Vec<PartialMetadataWithoutId>,
+    groups: Vec<String>,
+    people: Vec<PartialMetadataPerson>,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    let previous_people = metadata_person_ids(metadata_id, &ss.db).await?;
+    let associations = resolve_metadata_associations(genres, tags, suggestions, people, ss).await?;
+    replace_metadata_associations(metadata_id, associations, &ss.db).await?;
+    after_metadata_associations_changed(metadata_id, lot, source, previous_people, groups, ss).await
+}
+
 pub async fn update_metadata(
     metadata_id: &String,
     force_update: bool,
@@ -391,9 +845,16 @@ pub async fn update_metadata(
 ) -> Result<Vec<(String, MediaStateChanged)>> {
     let metadata = Metadata::find_by_id(metadata_id)
         .one(&ss.db)
-        .await
-        .unwrap()
-        .unwrap();
+        .await?
+        .ok_or_else(|| Error::new("Metadata does not exist"))?;
+    if metadata.source == MediaSource::Custom {
+        ryot_log!(
+            debug,
+            "Metadata {:?} is custom, skipping update",
+            metadata_id
+        );
+        return Ok(vec![]);
+    }
     if !force_update {
         // check whether the metadata needs to be updated
         let provider = get_metadata_provider(metadata.lot, metadata.source, ss).await?;
@@ -406,6 +867,12 @@ pub async fn update_metadata(
                 "Metadata {:?} does not need to be updated",
                 metadata_id
             );
+            advance_metadata_refresh_progress(
+                metadata_id,
+                |p| p.stage = MetadataRefreshStage::Finished,
+                ss,
+            )
+            .await?;
             return Ok(vec![]);
         }
     }
@@ -415,19 +882,53 @@ pub async fn update_metadata(
         .col_expr(metadata::Column::IsPartial, Expr::value(false))
         .exec(&ss.db)
         .await?;
-    let maybe_details =
-        details_from_provider(metadata.lot, metadata.source, &metadata.identifier, ss).await;
-    let notifications = match maybe_details {
-        Ok(details) => {
-            let mut notifications = vec![];
-
-            let meta = Metadata::find_by_id(metadata_id)
-                .one(&ss.db)
-                .await
-                .unwrap()
-                .unwrap();
-
-            if let (Some(p1), Some(p2)) = (&meta.production_status, &details.production_status) {
+    advance_metadata_refresh_progress(
+        metadata_id,
+        |p| p.stage = MetadataRefreshStage::FetchingDetails,
+        ss,
+    )
+    .await?;
+    track_page_progress(metadata.source, &metadata.identifier);
+    let maybe_details = match details_from_provider(
+        metadata.lot,
+        metadata.source,
+        &metadata.identifier,
+        ss,
+    )
+    .await
+    {
+        Ok(mut details) if details.suggestions.is_empty() => {
+            let provider = get_metadata_provider(metadata.lot, metadata.source, ss).await?;
+            details.suggestions = provider
+                .metadata_suggestions(&metadata.identifier)
+                .await
+                .unwrap_or_default();
+            Ok(details)
+        }
+        details => details,
+    };
+    let pages = page_progress(metadata.source, &metadata.identifier).unwrap_or_default();
+    untrack_page_progress(metadata.source, &metadata.identifier);
+    let notifications = match maybe_details {
+        Ok(details) => {
+            advance_metadata_refresh_progress(
+                metadata_id,
+                |p| {
+                    p.stage = MetadataRefreshStage::SavingChanges;
+                    p.pages_fetched = Some(pages.fetched);
+                    p.total_pages = pages.total;
+                },
+                ss,
+            )
+            .await?;
+            let mut notifications = vec![];
+
+            let meta = Metadata::find_by_id(metadata_id)
+                .one(&ss.db)
+                .await?
+                .ok_or_else(|| Error::new("Metadata does not exist"))?;
+
+            if let (Some(p1), Some(p2)) = (&meta.production_status, &details.production_status) {
                 if p1 != p2 {
                     notifications.push((
                         format!("Status changed from {:#?} to {:#?}", p1, p2),
@@ -576,13 +1077,7 @@ pub async fn update_metadata(
                 .map(|n| (format!("{} for {:?}.", n.0, meta.title), n.1))
                 .collect_vec();
 
-            let mut images = vec![];
-            images.extend(details.url_images.into_iter().map(|i| MetadataImage {
-                url: StoredUrl::Url(i.image),
-            }));
-            images.extend(details.s3_images.into_iter().map(|i| MetadataImage {
-                url: StoredUrl::S3(i.image),
-            }));
+            let images = refreshed_metadata_images(meta.images.as_ref(), &details);
             let free_creators = if details.creators.is_empty() {
                 None
             } else {
@@ -594,14 +1089,34 @@ pub async fn update_metadata(
                 Some(details.watch_providers)
             };
 
+            let images_changed = meta.images.as_ref() != Some(&images);
+            let sort_title = metadata_sort_title(
+                &details.title,
+                details.romanized_title.as_deref(),
+                meta.sort_title_override.as_deref(),
+                ss,
+            );
+
             let mut meta: metadata::ActiveModel = meta.into();
             meta.last_updated_on = ActiveValue::Set(Utc::now());
+            meta.last_refreshed_on = ActiveValue::Set(Some(Utc::now()));
             meta.title = ActiveValue::Set(details.title);
+            meta.sort_title = ActiveValue::Set(Some(sort_title));
             meta.is_nsfw = ActiveValue::Set(details.is_nsfw);
             meta.is_partial = ActiveValue::Set(Some(false));
-            meta.provider_rating = ActiveValue::Set(details.provider_rating);
-            meta.description = ActiveValue::Set(details.description);
+            let (provider_rating, provider_rating_scale) =
+                normalize_provider_rating(details.provider_rating, details.source);
+            meta.provider_rating = ActiveValue::Set(provider_rating);
+            meta.provider_rating_scale = ActiveValue::Set(provider_rating_scale);
+            meta.provider_rating_count = ActiveValue::Set(details.provider_rating_count);
+            let description = details.description.and_then(|d| normalize_description(&d));
+            meta.description_preview =
+                ActiveValue::Set(description.as_deref().map(description_preview));
+            meta.description = ActiveValue::Set(description);
             meta.images = ActiveValue::Set(Some(images));
+            if images_changed {
+                meta.images_unreachable = ActiveValue::Set(None);
+            }
             meta.videos = ActiveValue::Set(Some(details.videos));
             meta.production_status = ActiveValue::Set(details.production_status);
             meta.original_language = ActiveValue::Set(details.original_language);
@@ -619,20 +1134,31 @@ pub async fn update_metadata(
             meta.video_game_specifics = ActiveValue::Set(details.video_game_specifics);
             meta.visual_novel_specifics = ActiveValue::Set(details.visual_novel_specifics);
             meta.external_identifiers = ActiveValue::Set(details.external_identifiers);
-            let metadata = meta.update(&ss.db).await.unwrap();
+            let metadata = meta.update(&ss.db).await?;
 
             change_metadata_associations(
                 &metadata.id,
                 metadata.lot,
                 metadata.source,
                 details.genres,
+                details.tags,
                 details.suggestions,
                 details.group_identifiers,
                 details.people,
                 ss,
             )
             .await?;
+            associate_external_ids_with_metadata(&metadata, &ss.db).await?;
             ryot_log!(debug, "Updated metadata for {:?}", metadata_id);
+            advance_metadata_refresh_progress(
+                metadata_id,
+                |p| {
+                    p.stage = MetadataRefreshStage::Finished;
+                    p.changes = notifications.iter().map(|n| n.0.clone()).collect();
+                },
+                ss,
+            )
+            .await?;
             notifications
         }
         Err(e) => {
@@ -642,12 +1168,77 @@ pub async fn update_metadata(
                 metadata_id,
                 e
             );
+            advance_metadata_refresh_progress(
+                metadata_id,
+                |p| {
+                    p.stage = MetadataRefreshStage::Failed;
+                    p.error = Some(e.message.clone());
+                },
+                ss,
+            )
+            .await?;
+            if let Some(retry_at) = provider_retry_at(metadata.source) {
+                reschedule_metadata_update(metadata_id, retry_at, ss).await?;
+            }
             vec![]
         }
     };
     Ok(notifications)
 }
 
+/// Put the metadata back in a partial state and record when another update should
+/// be queued. The periodic job picks it up from there, see
+/// `requeue_postponed_metadata_updates`.
+async fn reschedule_metadata_update(
+    metadata_id: &String,
+    retry_at: DateTimeUtc,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    ryot_log!(
+        debug,
+        "Rescheduling update for metadata = {:?} at {:?}",
+        metadata_id,
+        retry_at
+    );
+    Metadata::update_many()
+        .filter(metadata::Column::Id.eq(metadata_id))
+        .col_expr(metadata::Column::IsPartial, Expr::value(true))
+        .col_expr(metadata::Column::UpdateRetryAt, Expr::value(retry_at))
+        .exec(&ss.db)
+        .await?;
+    Ok(())
+}
+
+/// Queue the updates that were postponed by `reschedule_metadata_update` and
+/// are now due.
+pub async fn requeue_postponed_metadata_updates(ss: &Arc<SupportingService>) -> Result<()> {
+    let due = Metadata::find()
+        .select_only()
+        .column(metadata::Column::Id)
+        .filter(metadata::Column::UpdateRetryAt.lte(Utc::now()))
+        .into_tuple::<String>()
+        .all(&ss.db)
+        .await?;
+    for metadata_id in due {
+        let cleared = Metadata::update_many()
+            .filter(metadata::Column::Id.eq(&metadata_id))
+            .filter(metadata::Column::UpdateRetryAt.lte(Utc::now()))
+            .col_expr(
+                metadata::Column::UpdateRetryAt,
+                Expr::value(Option::<DateTimeUtc>::None),
+            )
+            .exec(&ss.db)
+            .await?;
+        if cleared.rows_affected != 1 {
+            continue;
+        }
+        ryot_log!(debug, "Requeueing postponed update for {:?}", metadata_id);
+        // DEV: Forced so that the retry is not skipped as already up to date.
+        deploy_update_metadata_job(&metadata_id, true, ss).await?;
+    }
+    Ok(())
+}
+
 pub async fn get_users_and_cte_monitoring_entity(
     entity_id: &String,
     entity_lot: EntityLot,
@@ -682,15 +1273,17 @@ pub async fn get_users_monitoring_entity(
 pub async fn queue_notifications_to_user_platforms(
     user_id: &String,
     msg: &str,
-    db: &DatabaseConnection,
+    ss: &Arc<SupportingService>,
 ) -> Result<bool> {
     let insert_data = queued_notification::ActiveModel {
         message: ActiveValue::Set(msg.to_owned()),
         user_id: ActiveValue::Set(user_id.to_owned()),
         ..Default::default()
     };
-    let notification = insert_data.insert(db).await?;
+    let notification = insert_data.insert(&ss.db).await?;
     ryot_log!(debug, "Queued notification with id = {}", notification.id);
+    invalidate_user_dashboard_sections(user_id, &[UserDashboardSection::PendingNotifications], ss)
+        .await?;
     Ok(true)
 }
 
@@ -702,7 +1295,7 @@ pub async fn queue_media_state_changed_notification_for_user(
     let (msg, change) = notification;
     let notification_preferences = user_by_id(user_id, ss).await?.preferences.notifications;
     if notification_preferences.enabled && notification_preferences.to_send.contains(change) {
-        queue_notifications_to_user_platforms(user_id, msg, &ss.db)
+        queue_notifications_to_user_platforms(user_id, msg, ss)
             .await
             .trace_ok();
     } else {
@@ -714,6 +1307,87 @@ pub async fn queue_media_state_changed_notification_for_user(
     Ok(())
 }
 
+/// Queue a delivery of the event to each enabled webhook integration of the user
+/// that is subscribed to it.
+/// Webhook integrations without an event selection receive every event.
+fn is_subscribed_to_webhook_event(
+    integration: &integration::Model,
+    event: OutboundWebhookEvent,
+) -> bool {
+    !integration.is_disabled.unwrap_or_default()
+        && integration
+            .provider_specifics
+            .as_ref()
+            .and_then(|s| s.webhook_events.as_ref())
+            .map_or(true, |events| events.contains(&event))
+}
+
+pub async fn queue_outbound_webhook_event(
+    user_id: &String,
+    event: OutboundWebhookEvent,
+    metadata_id: Option<&String>,
+    details: Option<serde_json::Value>,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    if user_by_id(user_id, ss)
+        .await?
+        .preferences
+        .general
+        .disable_integrations
+    {
+        return Ok(());
+    }
+    let integrations = Integration::find()
+        .filter(integration::Column::UserId.eq(user_id))
+        .filter(integration::Column::Provider.eq(IntegrationProvider::Webhook))
+        .all(&ss.db)
+        .await?
+        .into_iter()
+        .filter(|i| is_subscribed_to_webhook_event(i, event))
+        .collect_vec();
+    if integrations.is_empty() {
+        return Ok(());
+    }
+    let media = match metadata_id {
+        Some(metadata_id) => Metadata::find_by_id(metadata_id)
+            .one(&ss.db)
+            .await?
+            .map(|m| OutboundWebhookMedia {
+                id: m.id,
+                lot: m.lot,
+                title: m.title,
+                source: m.source,
+                identifier: m.identifier,
+            }),
+        None => None,
+    };
+    let payload = serde_json::to_string(&OutboundWebhookPayload {
+        event,
+        media,
+        details,
+        is_test: false,
+        timestamp: Utc::now(),
+        user_id: user_id.to_owned(),
+        version: OUTBOUND_WEBHOOK_PAYLOAD_VERSION,
+    })?;
+    for integration in integrations {
+        outbound_webhook_delivery::ActiveModel {
+            event: ActiveValue::Set(event),
+            payload: ActiveValue::Set(payload.clone()),
+            integration_id: ActiveValue::Set(integration.id.clone()),
+            status: ActiveValue::Set(OutboundWebhookDeliveryStatus::Queued),
+            ..Default::default()
+        }
+        .insert(&ss.db)
+        .await?;
+        ss.perform_core_application_job(CoreApplicationJob::ProcessOutboundWebhookDeliveries(
+            integration.id,
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
 pub async fn refresh_collection_to_entity_association(
     cte_id: &Uuid,
     db: &DatabaseConnection,
@@ -737,10 +1411,9 @@ pub async fn update_metadata_and_notify_users(
     metadata_id: &String,
     force_update: bool,
     ss: &Arc<SupportingService>,
-) -> Result<()> {
-    let notifications = update_metadata(metadata_id, force_update, ss)
-        .await
-        .unwrap();
+) -> Result<Vec<String>> {
+    let notifications = update_metadata(metadata_id, force_update, ss).await?;
+    let changes = notifications.iter().map(|n| n.0.clone()).collect_vec();
     if !notifications.is_empty() {
         let users_to_notify =
             get_users_and_cte_monitoring_entity(metadata_id, EntityLot::Metadata, &ss.db).await?;
@@ -749,13 +1422,24 @@ pub async fn update_metadata_and_notify_users(
                 queue_media_state_changed_notification_for_user(user_id, &notification, ss)
                     .await
                     .trace_ok();
+                if notification.1 == MediaStateChanged::MetadataEpisodeReleased {
+                    queue_outbound_webhook_event(
+                        user_id,
+                        OutboundWebhookEvent::NewEpisodeNotification,
+                        Some(metadata_id),
+                        Some(serde_json::json!({ "message": notification.0 })),
+                        ss,
+                    )
+                    .await
+                    .trace_ok();
+                }
                 refresh_collection_to_entity_association(cte_id, &ss.db)
                     .await
                     .trace_ok();
             }
         }
     }
-    Ok(())
+    Ok(changes)
 }
 
 pub async fn commit_metadata_internal(
@@ -765,19 +1449,32 @@ pub async fn commit_metadata_internal(
 ) -> Result<metadata::Model> {
     let mut images = vec![];
     images.extend(details.url_images.into_iter().map(|i| MetadataImage {
-        url: StoredUrl::Url(i.image),
+        url: StoredUrl::Url(upgrade_image_url(i.image)),
+        ..Default::default()
     }));
     images.extend(details.s3_images.into_iter().map(|i| MetadataImage {
         url: StoredUrl::S3(i.image),
+        ..Default::default()
     }));
+    let (provider_rating, provider_rating_scale) =
+        normalize_provider_rating(details.provider_rating, details.source);
+    let description = details.description.and_then(|d| normalize_description(&d));
+    let find = Metadata::find()
+        .filter(metadata::Column::Identifier.eq(&details.identifier))
+        .filter(metadata::Column::Lot.eq(details.lot))
+        .filter(metadata::Column::Source.eq(details.source));
+    let sort_title =
+        metadata_sort_title(&details.title, details.romanized_title.as_deref(), None, ss);
     let metadata = metadata::ActiveModel {
         lot: ActiveValue::Set(details.lot),
         source: ActiveValue::Set(details.source),
         title: ActiveValue::Set(details.title),
-        description: ActiveValue::Set(details.description),
+        sort_title: ActiveValue::Set(Some(sort_title)),
+        description_preview: ActiveValue::Set(description.as_deref().map(description_preview)),
+        description: ActiveValue::Set(description),
         publish_year: ActiveValue::Set(details.publish_year),
         publish_date: ActiveValue::Set(details.publish_date),
-        images: ActiveValue::Set(Some(images)),
+        images: ActiveValue::Set(Some(dedup_metadata_images(images))),
         videos: ActiveValue::Set(Some(details.videos)),
         identifier: ActiveValue::Set(details.identifier),
         audio_book_specifics: ActiveValue::Set(details.audio_book_specifics),
@@ -789,7 +1486,9 @@ pub async fn commit_metadata_internal(
         show_specifics: ActiveValue::Set(details.show_specifics),
         video_game_specifics: ActiveValue::Set(details.video_game_specifics),
         visual_novel_specifics: ActiveValue::Set(details.visual_novel_specifics),
-        provider_rating: ActiveValue::Set(details.provider_rating),
+        provider_rating: ActiveValue::Set(provider_rating),
+        provider_rating_scale: ActiveValue::Set(provider_rating_scale),
+        provider_rating_count: ActiveValue::Set(details.provider_rating_count),
         production_status: ActiveValue::Set(details.production_status),
         original_language: ActiveValue::Set(details.original_language),
         external_identifiers: ActiveValue::Set(details.external_identifiers),
@@ -807,33 +1506,130 @@ pub async fn commit_metadata_internal(
         }),
         ..Default::default()
     };
-    let metadata = metadata.insert(&ss.db).await?;
+    let metadata = match metadata.insert(&ss.db).await {
+        Ok(m) => m,
+        // DEV: The commit that created the row also writes the associations
+        Err(e) => return find_after_insert_conflict(e, find, &ss.db).await,
+    };
 
     change_metadata_associations(
         &metadata.id,
         metadata.lot,
         metadata.source,
         details.genres.clone(),
+        details.tags.clone(),
         details.suggestions.clone(),
         details.group_identifiers.clone(),
         details.people.clone(),
         ss,
     )
     .await?;
+    associate_external_ids_with_metadata(&metadata, &ss.db).await?;
     Ok(metadata)
 }
 
+/// Overwrite a custom media item with new details. Only the genres that were
+/// added or removed are linked or unlinked, and genres are reused by name.
+pub async fn update_custom_metadata_internal(
+    meta: metadata::Model,
+    details: MetadataDetails,
+    is_partial: bool,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    let images = dedup_metadata_images(
+        details
+            .s3_images
+            .into_iter()
+            .map(|i| MetadataImage {
+                url: StoredUrl::S3(i.image),
+                ..Default::default()
+            })
+            .collect_vec(),
+    );
+    let sort_title = metadata_sort_title(
+        &details.title,
+        details.romanized_title.as_deref(),
+        meta.sort_title_override.as_deref(),
+        ss,
+    );
+    let description = details.description.and_then(|d| normalize_description(&d));
+    let metadata_id = meta.id.clone();
+    let mut meta: metadata::ActiveModel = meta.into();
+    meta.last_updated_on = ActiveValue::Set(Utc::now());
+    meta.title = ActiveValue::Set(details.title);
+    meta.sort_title = ActiveValue::Set(Some(sort_title));
+    meta.description_preview = ActiveValue::Set(description.as_deref().map(description_preview));
+    meta.description = ActiveValue::Set(description);
+    meta.images = ActiveValue::Set(Some(images));
+    meta.images_unreachable = ActiveValue::Set(None);
+    meta.videos = ActiveValue::Set(Some(details.videos));
+    meta.is_nsfw = ActiveValue::Set(details.is_nsfw);
+    meta.is_partial = ActiveValue::Set(Some(is_partial));
+    meta.publish_year = ActiveValue::Set(details.publish_year);
+    meta.publish_date = ActiveValue::Set(details.publish_date);
+    meta.free_creators = ActiveValue::Set(if details.creators.is_empty() {
+        None
+    } else {
+        Some(details.creators)
+    });
+    meta.anime_specifics = ActiveValue::Set(details.anime_specifics);
+    meta.audio_book_specifics = ActiveValue::Set(details.audio_book_specifics);
+    meta.book_specifics = ActiveValue::Set(details.book_specifics);
+    meta.manga_specifics = ActiveValue::Set(details.manga_specifics);
+    meta.movie_specifics = ActiveValue::Set(details.movie_specifics);
+    meta.podcast_specifics = ActiveValue::Set(details.podcast_specifics);
+    meta.show_specifics = ActiveValue::Set(details.show_specifics);
+    meta.video_game_specifics = ActiveValue::Set(details.video_game_specifics);
+    meta.visual_novel_specifics = ActiveValue::Set(details.visual_novel_specifics);
+    meta.update(&ss.db).await?;
+
+    let existing_genres = Genre::find()
+        .inner_join(MetadataToGenre)
+        .filter(metadata_to_genre::Column::MetadataId.eq(&metadata_id))
+        .all(&ss.db)
+        .await?;
+    let removed_genres = existing_genres
+        .iter()
+        .filter(|g| !details.genres.contains(&g.name))
+        .map(|g| g.id.clone())
+        .collect_vec();
+    if !removed_genres.is_empty() {
+        MetadataToGenre::delete_many()
+            .filter(metadata_to_genre::Column::MetadataId.eq(&metadata_id))
+            .filter(metadata_to_genre::Column::GenreId.is_in(removed_genres))
+            .exec(&ss.db)
+            .await?;
+    }
+    for genre in details.genres.into_iter().unique() {
+        if !existing_genres.iter().any(|g| g.name == genre) {
+            associate_genre_with_metadata(genre, &metadata_id, &ss.db).await?;
+        }
+    }
+    Ok(())
+}
+
 pub async fn commit_metadata(
     input: CommitMediaInput,
     ss: &Arc<SupportingService>,
 ) -> Result<metadata::Model> {
-    let Some(m) = Metadata::find()
+    let existing = match Metadata::find()
         .filter(metadata::Column::Lot.eq(input.lot))
         .filter(metadata::Column::Source.eq(input.source))
         .filter(metadata::Column::Identifier.eq(input.identifier.clone()))
         .one(&ss.db)
         .await?
-    else {
+    {
+        Some(m) => Some(m),
+        None => {
+            metadata_by_external_id(
+                ExternalIdType::PreviousSource,
+                &previous_source_external_id(input.lot, input.source, &input.identifier),
+                &ss.db,
+            )
+            .await?
+        }
+    };
+    let Some(m) = existing else {
         let details = details_from_provider(input.lot, input.source, &input.identifier, ss).await?;
         let media = commit_metadata_internal(details, None, ss).await?;
         return Ok(media);
@@ -845,6 +1641,259 @@ pub async fn commit_metadata(
     Ok(m)
 }
 
+/// Replace the provider data of a media item with the data from another source.
+/// Everything that users attached to the item stays attached since the row is
+/// kept, and the old source is remembered so that integrations keep matching.
+pub async fn swap_metadata_source(
+    input: SwapMetadataSourceInput,
+    ss: &Arc<SupportingService>,
+) -> Result<Vec<MetadataFieldChange>> {
+    let meta = Metadata::find_by_id(&input.metadata_id)
+        .one(&ss.db)
+        .await?
+        .ok_or_else(|| Error::new("Metadata does not exist"))?;
+    if meta.source == input.new_source && meta.identifier == input.new_identifier {
+        return Err(Error::new("The media item already uses this source"));
+    }
+    let taken = Metadata::find()
+        .filter(metadata::Column::Lot.eq(meta.lot))
+        .filter(metadata::Column::Source.eq(input.new_source))
+        .filter(metadata::Column::Identifier.eq(&input.new_identifier))
+        .count(&ss.db)
+        .await?;
+    if taken > 0 {
+        return Err(Error::new(
+            "Another media item already uses this source, merge them instead",
+        ));
+    }
+    let details =
+        details_from_provider(meta.lot, input.new_source, &input.new_identifier, ss).await?;
+    check_swapped_lot(meta.lot, details.lot)?;
+    let sort_title = metadata_sort_title(
+        &details.title,
+        details.romanized_title.as_deref(),
+        meta.sort_title_override.as_deref(),
+        ss,
+    );
+    let swapped = swapped_metadata(&meta, &details, sort_title, Utc::now());
+    let changes = metadata_source_changes(&meta, &swapped);
+    if input.dry_run {
+        return Ok(changes);
+    }
+
+    ryot_log!(
+        debug,
+        "Swapping source of metadata {} to {:?} {}",
+        meta.id,
+        details.source,
+        details.identifier
+    );
+    let previous = previous_source_external_id(meta.lot, meta.source, &meta.identifier);
+    let previous_people = metadata_person_ids(&meta.id, &ss.db).await?;
+    let associations = resolve_metadata_associations(
+        details.genres,
+        details.tags,
+        details.suggestions,
+        details.people,
+        ss,
+    )
+    .await?;
+    let txn = ss.db.begin().await?;
+    let mut model: metadata::ActiveModel = meta.clone().into();
+    for column in metadata::Column::iter() {
+        let value = swapped.get(column);
+        if meta.get(column) != value {
+            model.set(column, value);
+        }
+    }
+    let updated = model.update(&txn).await?;
+    MetadataExternalId::insert(metadata_external_id::ActiveModel {
+        id_type: ActiveValue::Set(ExternalIdType::PreviousSource),
+        value: ActiveValue::Set(previous),
+        metadata_id: ActiveValue::Set(updated.id.clone()),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            metadata_external_id::Column::IdType,
+            metadata_external_id::Column::Value,
+        ])
+        .update_column(metadata_external_id::Column::MetadataId)
+        .to_owned(),
+    )
+    .exec_without_returning(&txn)
+    .await?;
+    replace_metadata_associations(&updated.id, associations, &txn).await?;
+    associate_external_ids_with_metadata(&updated, &txn).await?;
+    txn.commit().await?;
+
+    after_metadata_associations_changed(
+        &updated.id,
+        updated.lot,
+        updated.source,
+        previous_people,
+        details.group_identifiers,
+        ss,
+    )
+    .await?;
+    Ok(changes)
+}
+
+/// Sources can only be swapped for one that has the same type of media.
+fn check_swapped_lot(current: MediaLot, new: MediaLot) -> Result<()> {
+    if current != new {
+        return Err(Error::new(format!(
+            "The new source has a {:?} while the media item is a {:?}",
+            new, current
+        )));
+    }
+    Ok(())
+}
+
+/// The media item after its provider data is replaced with `details`. The id
+/// stays the same, so everything that users attached to it stays attached.
+/// Images uploaded by users and the sort title override are kept.
+fn swapped_metadata(
+    meta: &metadata::Model,
+    details: &MetadataDetails,
+    sort_title: String,
+    now: DateTimeUtc,
+) -> metadata::Model {
+    let description = details
+        .description
+        .as_deref()
+        .and_then(normalize_description);
+    let (provider_rating, provider_rating_scale) =
+        normalize_provider_rating(details.provider_rating, details.source);
+    metadata::Model {
+        source: details.source,
+        identifier: details.identifier.clone(),
+        last_updated_on: now,
+        title: details.title.clone(),
+        sort_title: Some(sort_title),
+        is_nsfw: details.is_nsfw,
+        is_partial: Some(false),
+        provider_rating,
+        provider_rating_scale,
+        provider_rating_count: details.provider_rating_count,
+        description_preview: description.as_deref().map(description_preview),
+        description,
+        images: Some(refreshed_metadata_images(meta.images.as_ref(), details)),
+        images_unreachable: None,
+        videos: Some(details.videos.clone()),
+        production_status: details.production_status.clone(),
+        original_language: details.original_language.clone(),
+        publish_year: details.publish_year,
+        publish_date: details.publish_date,
+        free_creators: Some(details.creators.clone()).filter(|c| !c.is_empty()),
+        watch_providers: Some(details.watch_providers.clone()).filter(|w| !w.is_empty()),
+        external_identifiers: details.external_identifiers.clone(),
+        anime_specifics: details.anime_specifics.clone(),
+        audio_book_specifics: details.audio_book_specifics.clone(),
+        book_specifics: details.book_specifics.clone(),
+        manga_specifics: details.manga_specifics.clone(),
+        movie_specifics: details.movie_specifics.clone(),
+        podcast_specifics: details.podcast_specifics.clone(),
+        show_specifics: details.show_specifics.clone(),
+        video_game_specifics: details.video_game_specifics.clone(),
+        visual_novel_specifics: details.visual_novel_specifics.clone(),
+        ..meta.clone()
+    }
+}
+
+/// The fields shown in the preview of a source swap that differ between the
+/// media item and its swapped version.
+fn metadata_source_changes(
+    before: &metadata::Model,
+    after: &metadata::Model,
+) -> Vec<MetadataFieldChange> {
+    fn json<T: Serialize>(value: &Option<T>) -> Option<String> {
+        value.as_ref().map(|v| serde_json::to_string(v).unwrap())
+    }
+    fn specifics(meta: &metadata::Model) -> Option<String> {
+        json(&meta.audio_book_specifics)
+            .or(json(&meta.anime_specifics))
+            .or(json(&meta.book_specifics))
+            .or(json(&meta.manga_specifics))
+            .or(json(&meta.movie_specifics))
+            .or(json(&meta.podcast_specifics))
+            .or(json(&meta.show_specifics))
+            .or(json(&meta.video_game_specifics))
+            .or(json(&meta.visual_novel_specifics))
+    }
+    let fields = [
+        (
+            "source",
+            Some(format!("{:?}", before.source)),
+            Some(format!("{:?}", after.source)),
+        ),
+        (
+            "identifier",
+            Some(before.identifier.clone()),
+            Some(after.identifier.clone()),
+        ),
+        (
+            "title",
+            Some(before.title.clone()),
+            Some(after.title.clone()),
+        ),
+        (
+            "description",
+            before.description.clone(),
+            after.description.clone(),
+        ),
+        (
+            "publish_year",
+            before.publish_year.map(|y| y.to_string()),
+            after.publish_year.map(|y| y.to_string()),
+        ),
+        (
+            "publish_date",
+            before.publish_date.map(|d| d.to_string()),
+            after.publish_date.map(|d| d.to_string()),
+        ),
+        (
+            "production_status",
+            before.production_status.clone(),
+            after.production_status.clone(),
+        ),
+        (
+            "original_language",
+            before.original_language.clone(),
+            after.original_language.clone(),
+        ),
+        (
+            "provider_rating",
+            before.provider_rating.map(|r| r.to_string()),
+            after.provider_rating.map(|r| r.to_string()),
+        ),
+        (
+            "is_nsfw",
+            before.is_nsfw.map(|n| n.to_string()),
+            after.is_nsfw.map(|n| n.to_string()),
+        ),
+        (
+            "images",
+            before.images.as_ref().map(|i| i.len().to_string()),
+            after.images.as_ref().map(|i| i.len().to_string()),
+        ),
+        (
+            "external_identifiers",
+            json(&before.external_identifiers),
+            json(&after.external_identifiers),
+        ),
+        ("specifics", specifics(before), specifics(after)),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| MetadataFieldChange {
+            field: field.to_owned(),
+            before,
+            after,
+        })
+        .collect()
+}
+
 pub async fn deploy_update_metadata_job(
     metadata_id: &String,
     force_update: bool,
@@ -858,6 +1907,90 @@ pub async fn deploy_update_metadata_job(
     Ok(true)
 }
 
+// DEV: Refreshes can take minutes for shows with a lot of seasons, so progress is kept
+// for a while after they finish to let clients that poll pick up the final state.
+const METADATA_REFRESH_PROGRESS_EXPIRY_HOURS: i64 = 1;
+
+/// Deploy a metadata update whose progress can be followed with
+/// `metadata_refresh_progress`.
+pub async fn deploy_tracked_update_metadata_job(
+    metadata_id: &String,
+    force_update: bool,
+    ss: &Arc<SupportingService>,
+) -> Result<bool> {
+    let progress = MetadataRefreshProgress {
+        stage: MetadataRefreshStage::Queued,
+        pages_fetched: None,
+        total_pages: None,
+        changes: vec![],
+        error: None,
+        updated_at: Utc::now(),
+    };
+    ss.cache_service
+        .set_value_with_expiry(
+            ApplicationCacheKey::MetadataRefreshProgress {
+                metadata_id: metadata_id.to_owned(),
+            },
+            &progress,
+            METADATA_REFRESH_PROGRESS_EXPIRY_HOURS,
+        )
+        .await?;
+    deploy_update_metadata_job(metadata_id, force_update, ss).await
+}
+
+/// Only refreshes that were deployed with `deploy_tracked_update_metadata_job` are
+/// recorded, background updates of the whole library are not.
+async fn advance_metadata_refresh_progress<F>(
+    metadata_id: &String,
+    update: F,
+    ss: &Arc<SupportingService>,
+) -> Result<()>
+where
+    F: FnOnce(&mut MetadataRefreshProgress),
+{
+    let key = ApplicationCacheKey::MetadataRefreshProgress {
+        metadata_id: metadata_id.to_owned(),
+    };
+    let Some(mut progress) = ss
+        .cache_service
+        .get_value::<MetadataRefreshProgress>(key.clone())
+        .await?
+    else {
+        return Ok(());
+    };
+    update(&mut progress);
+    progress.updated_at = Utc::now();
+    ss.cache_service
+        .set_value_with_expiry(key, &progress, METADATA_REFRESH_PROGRESS_EXPIRY_HOURS)
+        .await?;
+    Ok(())
+}
+
+pub async fn metadata_refresh_progress(
+    metadata_id: &String,
+    ss: &Arc<SupportingService>,
+) -> Result<Option<MetadataRefreshProgress>> {
+    let key = ApplicationCacheKey::MetadataRefreshProgress {
+        metadata_id: metadata_id.to_owned(),
+    };
+    let Some(mut progress) = ss
+        .cache_service
+        .get_value::<MetadataRefreshProgress>(key)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if progress.stage == MetadataRefreshStage::FetchingDetails {
+        if let Some(metadata) = Metadata::find_by_id(metadata_id).one(&ss.db).await? {
+            if let Some(pages) = page_progress(metadata.source, &metadata.identifier) {
+                progress.pages_fetched = Some(pages.fetched);
+                progress.total_pages = pages.total;
+            }
+        }
+    }
+    Ok(Some(progress))
+}
+
 pub async fn deploy_background_job(
     user_id: &String,
     job_name: BackgroundJob,
@@ -867,6 +2000,7 @@ pub async fn deploy_background_job(
         BackgroundJob::UpdateAllMetadata
         | BackgroundJob::UpdateAllExercises
         | BackgroundJob::RecalculateCalendarEvents
+        | BackgroundJob::RecalculateSortTitles
         | BackgroundJob::PerformBackgroundTasks => {
             admin_account_guard(user_id, ss).await?;
         }
@@ -894,6 +2028,10 @@ pub async fn deploy_background_job(
             ss.perform_application_job(ApplicationJob::RecalculateCalendarEvents)
                 .await?;
         }
+        BackgroundJob::RecalculateSortTitles => {
+            ss.perform_application_job(ApplicationJob::RecalculateSortTitles(true))
+                .await?;
+        }
         BackgroundJob::PerformBackgroundTasks => {
             ss.perform_application_job(ApplicationJob::PerformBackgroundTasks)
                 .await?;
@@ -919,15 +2057,48 @@ pub async fn deploy_background_job(
     Ok(true)
 }
 
+/// Reviews point at the entity through the column of its lot, so media, people
+/// and the other entities can all be reviewed.
+fn set_review_entity(review: &mut review::ActiveModel, entity_lot: EntityLot, entity_id: String) {
+    match entity_lot {
+        EntityLot::Metadata => review.metadata_id = ActiveValue::Set(Some(entity_id)),
+        EntityLot::Person => review.person_id = ActiveValue::Set(Some(entity_id)),
+        EntityLot::MetadataGroup => review.metadata_group_id = ActiveValue::Set(Some(entity_id)),
+        EntityLot::Collection => review.collection_id = ActiveValue::Set(Some(entity_id)),
+        EntityLot::Exercise => review.exercise_id = ActiveValue::Set(Some(entity_id)),
+        EntityLot::Workout | EntityLot::WorkoutTemplate => unreachable!(),
+    };
+}
+
 pub async fn post_review(
     user_id: &String,
     input: CreateOrUpdateReviewInput,
     ss: &Arc<SupportingService>,
 ) -> Result<StringIdObject> {
+    // DEV: Imports do not go through the resolvers, so the input is checked here too
+    input.validate()?;
     let preferences = user_by_id(user_id, ss).await?.preferences;
     if preferences.general.disable_reviews {
         return Err(Error::new("Reviews are disabled"));
     }
+    let maximum_rating = preferences.general.review_scale.maximum();
+    if input
+        .rating
+        .is_some_and(|r| r < Decimal::ZERO || r > maximum_rating)
+    {
+        return Err(Error::new(format!(
+            "Rating must be between 0 and {}",
+            maximum_rating
+        )));
+    }
+    if let Some(review_id) = &input.review_id {
+        let Some(review) = Review::find_by_id(review_id).one(&ss.db).await? else {
+            return Err(Error::new("This review does not exist"));
+        };
+        if &review.user_id != user_id {
+            return Err(Error::new("This review does not belong to you"));
+        }
+    }
     let show_ei = if let (Some(season), Some(episode)) =
         (input.show_season_number, input.show_episode_number)
     {
@@ -952,41 +2123,26 @@ pub async fn post_review(
         })
     };
 
-    if input.rating.is_none() && input.text.is_none() {
-        return Err(Error::new("At-least one of rating or review is required."));
-    }
-    let mut review_obj =
-        review::ActiveModel {
-            id: match input.review_id.clone() {
-                Some(i) => ActiveValue::Unchanged(i),
-                None => ActiveValue::NotSet,
-            },
-            rating: ActiveValue::Set(input.rating.map(
-                |r| match preferences.general.review_scale {
-                    UserReviewScale::OutOfFive => r * dec!(20),
-                    UserReviewScale::OutOfHundred | UserReviewScale::ThreePointSmiley => r,
-                },
-            )),
-            text: ActiveValue::Set(input.text),
-            user_id: ActiveValue::Set(user_id.to_owned()),
-            show_extra_information: ActiveValue::Set(show_ei),
-            anime_extra_information: ActiveValue::Set(anime_ei),
-            manga_extra_information: ActiveValue::Set(manga_ei),
-            podcast_extra_information: ActiveValue::Set(podcast_ei),
-            comments: ActiveValue::Set(vec![]),
-            ..Default::default()
-        };
-    let entity_id = input.entity_id.clone();
-    match input.entity_lot {
-        EntityLot::Metadata => review_obj.metadata_id = ActiveValue::Set(Some(entity_id)),
-        EntityLot::Person => review_obj.person_id = ActiveValue::Set(Some(entity_id)),
-        EntityLot::MetadataGroup => {
-            review_obj.metadata_group_id = ActiveValue::Set(Some(entity_id))
-        }
-        EntityLot::Collection => review_obj.collection_id = ActiveValue::Set(Some(entity_id)),
-        EntityLot::Exercise => review_obj.exercise_id = ActiveValue::Set(Some(entity_id)),
-        EntityLot::Workout | EntityLot::WorkoutTemplate => unreachable!(),
+    let mut review_obj = review::ActiveModel {
+        id: match input.review_id.clone() {
+            Some(i) => ActiveValue::Unchanged(i),
+            None => ActiveValue::NotSet,
+        },
+        rating: ActiveValue::Set(
+            input
+                .rating
+                .map(|r| preferences.general.review_scale.normalize(r)),
+        ),
+        text: ActiveValue::Set(input.text),
+        user_id: ActiveValue::Set(user_id.to_owned()),
+        show_extra_information: ActiveValue::Set(show_ei),
+        anime_extra_information: ActiveValue::Set(anime_ei),
+        manga_extra_information: ActiveValue::Set(manga_ei),
+        podcast_extra_information: ActiveValue::Set(podcast_ei),
+        comments: ActiveValue::Set(vec![]),
+        ..Default::default()
     };
+    set_review_entity(&mut review_obj, input.entity_lot, input.entity_id.clone());
     if let Some(s) = input.is_spoiler {
         review_obj.is_spoiler = ActiveValue::Set(s);
     }
@@ -997,6 +2153,23 @@ pub async fn post_review(
         review_obj.posted_on = ActiveValue::Set(d);
     }
     let insert = review_obj.save(&ss.db).await.unwrap();
+    if input.review_id.is_none() {
+        let metadata_id = insert.metadata_id.clone().unwrap();
+        queue_outbound_webhook_event(
+            insert.user_id.as_ref(),
+            OutboundWebhookEvent::ReviewPosted,
+            metadata_id.as_ref(),
+            Some(serde_json::json!({
+                "review_id": insert.id.as_ref(),
+                "entity_id": insert.entity_id.as_ref(),
+                "entity_lot": insert.entity_lot.as_ref(),
+                "rating": insert.rating.as_ref(),
+            })),
+            ss,
+        )
+        .await
+        .trace_ok();
+    }
     if insert.visibility.unwrap() == Visibility::Public {
         let entity_lot = insert.entity_lot.unwrap();
         let id = insert.entity_id.unwrap();
@@ -1032,129 +2205,232 @@ pub async fn post_review(
     })
 }
 
+/// Franchises grow over time, so the details of a stored group are refreshed on
+/// every commit.
+fn refreshed_metadata_group(
+    existing: metadata_group::Model,
+    details: MetadataGroupWithoutId,
+) -> metadata_group::ActiveModel {
+    let mut group: metadata_group::ActiveModel = existing.into();
+    group.is_partial = ActiveValue::Set(Some(false));
+    group.title = ActiveValue::Set(details.title);
+    group.parts = ActiveValue::Set(details.parts);
+    group.images = ActiveValue::Set(details.images);
+    group.description = ActiveValue::Set(details.description);
+    group
+}
+
 pub async fn commit_metadata_group_internal(
     identifier: &String,
     lot: MediaLot,
     source: MediaSource,
     ss: &Arc<SupportingService>,
 ) -> Result<(String, Vec<PartialMetadataWithoutId>)> {
-    let existing_group = MetadataGroup::find()
+    let find = MetadataGroup::find()
         .filter(metadata_group::Column::Identifier.eq(identifier))
         .filter(metadata_group::Column::Lot.eq(lot))
-        .filter(metadata_group::Column::Source.eq(source))
-        .one(&ss.db)
-        .await?;
+        .filter(metadata_group::Column::Source.eq(source));
+    let existing_group = find.clone().one(&ss.db).await?;
     let provider = get_metadata_provider(lot, source, ss).await?;
     let (group_details, associated_items) = provider.metadata_group_details(identifier).await?;
     let group_id = match existing_group {
-        Some(eg) => eg.id,
+        Some(eg) => {
+            let eg = refreshed_metadata_group(eg, group_details)
+                .update(&ss.db)
+                .await?;
+            eg.id
+        }
         None => {
             let mut db_group: metadata_group::ActiveModel =
                 group_details.into_model("".to_string(), None).into();
             db_group.id = ActiveValue::NotSet;
-            let new_group = db_group.insert(&ss.db).await?;
+            let new_group = match db_group.insert(&ss.db).await {
+                Ok(g) => g,
+                Err(e) => find_after_insert_conflict(e, find, &ss.db).await?,
+            };
             new_group.id
         }
     };
     Ok((group_id, associated_items))
 }
 
-async fn seen_history(
-    user_id: &String,
-    metadata_id: &String,
-    db: &DatabaseConnection,
-) -> Result<Vec<seen::Model>> {
-    let seen_items = Seen::find()
+/// The seen items of the user for the media, most recently updated first.
+pub fn seen_history_query(user_id: &String, metadata_id: &String) -> Select<Seen> {
+    Seen::find()
         .filter(seen::Column::UserId.eq(user_id))
         .filter(seen::Column::MetadataId.eq(metadata_id))
+        // DEV: Entries updated at the same instant are ordered by their ID so that
+        // the order is the same across calls and pages do not overlap
         .order_by_desc(seen::Column::LastUpdatedOn)
+        .order_by_desc(seen::Column::Id)
+}
+
+/// The seen items of the user that were updated after the cursor, least recently
+/// updated first.
+pub fn seen_changes_query(user_id: &String, after: Option<&SeenChangesCursor>) -> Select<Seen> {
+    Seen::find()
+        .filter(seen::Column::UserId.eq(user_id))
+        .apply_if(after, |query, cursor| {
+            query.filter(
+                Condition::any()
+                    .add(seen::Column::LastUpdatedOn.gt(cursor.last_updated_on))
+                    .add(
+                        Condition::all()
+                            .add(seen::Column::LastUpdatedOn.eq(cursor.last_updated_on))
+                            .add(seen::Column::Id.gt(&cursor.id)),
+                    ),
+            )
+        })
+        .order_by_asc(seen::Column::LastUpdatedOn)
+        .order_by_asc(seen::Column::Id)
+}
+
+pub fn seen_changes_cursor(seen: &seen::Model) -> SeenChangesCursor {
+    SeenChangesCursor {
+        id: seen.id.clone(),
+        last_updated_on: seen.last_updated_on,
+    }
+}
+
+pub async fn seen_history(
+    user_id: &String,
+    metadata_id: &String,
+    db: &DatabaseConnection,
+) -> Result<Vec<seen::Model>> {
+    let seen_items = seen_history_query(user_id, metadata_id)
         .all(db)
         .await
         .unwrap();
     Ok(seen_items)
 }
 
+/// Whether the user has finished the media and whether they have seen it at all.
+/// The seen history is aggregated in the database instead of being loaded.
 pub async fn is_metadata_finished_by_user(
     user_id: &String,
     metadata_id: &String,
     db: &DatabaseConnection,
-) -> Result<(bool, Vec<seen::Model>)> {
+) -> Result<MetadataFinishedStatus> {
     let metadata = Metadata::find_by_id(metadata_id)
         .one(db)
         .await
         .unwrap()
         .unwrap();
-    let seen_history = seen_history(user_id, metadata_id, db).await?;
-    let is_finished = if metadata.lot == MediaLot::Podcast
-        || metadata.lot == MediaLot::Show
-        || metadata.lot == MediaLot::Anime
-        || metadata.lot == MediaLot::Manga
-    {
-        // DEV: If all episodes have been seen the same number of times, the media can be
-        // considered finished.
-        let all_episodes = if let Some(s) = metadata.show_specifics {
-            s.seasons
-                .into_iter()
-                .filter(|s| !SHOW_SPECIAL_SEASON_NAMES.contains(&s.name.as_str()))
-                .flat_map(|s| {
-                    s.episodes
-                        .into_iter()
-                        .map(move |e| format!("{}-{}", s.season_number, e.episode_number))
-                })
-                .collect_vec()
-        } else if let Some(p) = metadata.podcast_specifics {
-            p.episodes
-                .into_iter()
-                .map(|e| format!("{}", e.number))
-                .collect_vec()
-        } else if let Some(e) = metadata.anime_specifics.and_then(|a| a.episodes) {
-            (1..e + 1).map(|e| format!("{}", e)).collect_vec()
-        } else if let Some(c) = metadata.manga_specifics.and_then(|m| m.chapters) {
-            let one = Decimal::one();
-            (0..c.to_u32().unwrap_or(0))
-                .map(|i| Decimal::from(i) + one)
-                .map(|d| d.to_string())
-                .collect_vec()
-        } else {
-            vec![]
-        };
-        if all_episodes.is_empty() {
-            return Ok((true, seen_history));
-        }
-        let mut bag =
-            HashMap::<String, i32>::from_iter(all_episodes.iter().cloned().map(|e| (e, 0)));
-        seen_history
-            .clone()
-            .into_iter()
-            .map(|h| {
-                if let Some(s) = h.show_extra_information {
-                    format!("{}-{}", s.season, s.episode)
-                } else if let Some(p) = h.podcast_extra_information {
-                    format!("{}", p.episode)
-                } else if let Some(a) = h.anime_extra_information.and_then(|a| a.episode) {
-                    format!("{}", a)
-                } else if let Some(m) = h.manga_extra_information.and_then(|m| m.chapter) {
-                    format!("{}", m)
-                } else {
-                    String::new()
-                }
-            })
-            .for_each(|ep| {
-                bag.entry(ep).and_modify(|c| *c += 1);
-            });
-        let values = bag.values().cloned().collect_vec();
+    let user_seen = Seen::find()
+        .select_only()
+        .filter(seen::Column::UserId.eq(user_id))
+        .filter(seen::Column::MetadataId.eq(metadata_id));
+    if !matches!(
+        metadata.lot,
+        MediaLot::Podcast | MediaLot::Show | MediaLot::Anime | MediaLot::Manga
+    ) {
+        let (seen_count, completed_count) = user_seen
+            .column_as(seen::Column::Id.count(), "seen_count")
+            .column_as(
+                Expr::cust_with_values(
+                    r#"COUNT(*) FILTER (WHERE "state" = $1)"#,
+                    [SeenState::Completed.to_value()],
+                ),
+                "completed_count",
+            )
+            .into_tuple::<(i64, i64)>()
+            .one(db)
+            .await?
+            .unwrap_or_default();
+        return Ok(MetadataFinishedStatus {
+            is_finished: completed_count > 0,
+            has_seen: seen_count > 0,
+        });
+    }
+    let all_episodes = metadata_episode_keys(metadata);
+    let times_seen = user_seen
+        .column_as(seen_episode_key(), "episode")
+        .column_as(seen::Column::Id.count(), "times_seen")
+        .group_by(seen_episode_key())
+        .into_tuple::<(String, i64)>()
+        .all(db)
+        .await?;
+    Ok(episodic_finished_status(all_episodes, times_seen))
+}
 
-        let min_value = values.iter().min();
-        let max_value = values.iter().max();
+/// Identifies the episode of a seen entry, it matches the keys returned by
+/// `metadata_episode_keys` once passed through `normalize_episode_key`.
+fn seen_episode_key() -> SimpleExpr {
+    Expr::cust(
+        r#"COALESCE("show_season_number" || '-' || "show_episode_number", "podcast_episode_number"::TEXT, "anime_extra_information" ->> 'episode', "manga_extra_information" ->> 'chapter', '')"#,
+    )
+}
 
-        match (min_value, max_value) {
-            (Some(min), Some(max)) => min == max && *min != 0,
-            _ => false,
-        }
+/// Manga chapters are stored with the scale they were logged with, so "2.0" and "2"
+/// are the same chapter.
+fn normalize_episode_key(key: String) -> String {
+    match Decimal::from_str_exact(&key) {
+        Ok(d) => d.normalize().to_string(),
+        Err(_) => key,
+    }
+}
+
+fn metadata_episode_keys(metadata: metadata::Model) -> Vec<String> {
+    if let Some(s) = metadata.show_specifics {
+        s.seasons
+            .into_iter()
+            .filter(|s| !SHOW_SPECIAL_SEASON_NAMES.contains(&s.name.as_str()))
+            .flat_map(|s| {
+                s.episodes
+                    .into_iter()
+                    .map(move |e| format!("{}-{}", s.season_number, e.episode_number))
+            })
+            .collect_vec()
+    } else if let Some(p) = metadata.podcast_specifics {
+        p.episodes
+            .into_iter()
+            .map(|e| format!("{}", e.number))
+            .collect_vec()
+    } else if let Some(e) = metadata.anime_specifics.and_then(|a| a.episodes) {
+        (1..e + 1).map(|e| format!("{}", e)).collect_vec()
+    } else if let Some(c) = metadata.manga_specifics.and_then(|m| m.chapters) {
+        let one = Decimal::one();
+        (0..c.to_u32().unwrap_or(0))
+            .map(|i| Decimal::from(i) + one)
+            .map(|d| d.to_string())
+            .collect_vec()
     } else {
-        seen_history.iter().any(|h| h.state == SeenState::Completed)
+        vec![]
+    }
+}
+
+// DEV: If all episodes have been seen the same number of times, the media can be
+// considered finished.
+fn episodic_finished_status(
+    all_episodes: Vec<String>,
+    times_seen: Vec<(String, i64)>,
+) -> MetadataFinishedStatus {
+    let has_seen = !times_seen.is_empty();
+    if all_episodes.is_empty() {
+        return MetadataFinishedStatus {
+            is_finished: true,
+            has_seen,
+        };
+    }
+    let mut bag = HashMap::<String, i64>::from_iter(
+        all_episodes
+            .into_iter()
+            .map(|e| (normalize_episode_key(e), 0)),
+    );
+    for (episode, count) in times_seen {
+        bag.entry(normalize_episode_key(episode))
+            .and_modify(|c| *c += count);
+    }
+    let min_value = bag.values().min();
+    let max_value = bag.values().max();
+    let is_finished = match (min_value, max_value) {
+        (Some(min), Some(max)) => min == max && *min != 0,
+        _ => false,
     };
-    Ok((is_finished, seen_history))
+    MetadataFinishedStatus {
+        is_finished,
+        has_seen,
+    }
 }
 
 pub async fn deploy_after_handle_media_seen_tasks(
@@ -1165,10 +2441,57 @@ pub async fn deploy_after_handle_media_seen_tasks(
         .await
 }
 
+fn is_tracked_by_episode(lot: MediaLot) -> bool {
+    matches!(
+        lot,
+        MediaLot::Podcast | MediaLot::Show | MediaLot::Anime | MediaLot::Manga
+    )
+}
+
+/// The default collections that a media is added to and removed from after it is
+/// seen, as `(to_add, to_remove)`. Media with episodes or chapters only move to
+/// Completed once `is_finished` says that all of them were seen. The changes do not
+/// depend on the collections the media is currently in.
+fn default_collection_changes(
+    state: SeenState,
+    lot: MediaLot,
+    is_finished: bool,
+) -> (Vec<DefaultCollection>, Vec<DefaultCollection>) {
+    let mut to_add = vec![];
+    let mut to_remove = vec![DefaultCollection::Watchlist];
+    match state {
+        SeenState::InProgress => {
+            to_add.extend([DefaultCollection::InProgress, DefaultCollection::Monitoring])
+        }
+        SeenState::Dropped | SeenState::OnAHold => to_remove.push(DefaultCollection::InProgress),
+        SeenState::Completed | SeenState::Skipped if is_tracked_by_episode(lot) => {
+            match is_finished {
+                true => {
+                    to_remove.push(DefaultCollection::InProgress);
+                    to_add.push(DefaultCollection::Completed);
+                }
+                false => {
+                    to_add.extend([DefaultCollection::InProgress, DefaultCollection::Monitoring])
+                }
+            }
+        }
+        SeenState::Completed | SeenState::Skipped => {
+            to_add.push(DefaultCollection::Completed);
+            to_remove.extend([DefaultCollection::InProgress, DefaultCollection::Monitoring]);
+        }
+    }
+    (to_add, to_remove)
+}
+
 pub async fn handle_after_media_seen_tasks(
     seen: seen::Model,
     ss: &Arc<SupportingService>,
 ) -> Result<()> {
+    if let Some(finished_on) = seen.finished_on {
+        refresh_cached_user_yearly_summary(&seen.user_id, finished_on.year(), ss)
+            .await
+            .trace_ok();
+    }
     let add_entity_to_collection = |collection_name: &str| {
         add_entity_to_collection(
             &seen.user_id,
@@ -1184,7 +2507,6 @@ pub async fn handle_after_media_seen_tasks(
     };
     let remove_entity_from_collection = |collection_name: &str| {
         remove_entity_from_collection(
-            &ss.db,
             &seen.user_id,
             ChangeCollectionToEntityInput {
                 creator_user_id: seen.user_id.clone(),
@@ -1193,59 +2515,110 @@ pub async fn handle_after_media_seen_tasks(
                 entity_lot: EntityLot::Metadata,
                 ..Default::default()
             },
+            ss,
         )
     };
-    remove_entity_from_collection(&DefaultCollection::Watchlist.to_string())
-        .await
-        .ok();
-    match seen.state {
-        SeenState::InProgress => {
-            for col in &[DefaultCollection::InProgress, DefaultCollection::Monitoring] {
-                add_entity_to_collection(&col.to_string()).await.ok();
-            }
-        }
-        SeenState::Dropped | SeenState::OnAHold => {
-            remove_entity_from_collection(&DefaultCollection::InProgress.to_string())
-                .await
-                .ok();
-        }
-        SeenState::Completed => {
-            let metadata = Metadata::find_by_id(&seen.metadata_id)
-                .one(&ss.db)
+    let metadata = Metadata::find_by_id(&seen.metadata_id)
+        .one(&ss.db)
+        .await?
+        .unwrap();
+    let is_finished = match seen.state {
+        SeenState::Completed | SeenState::Skipped if is_tracked_by_episode(metadata.lot) => {
+            is_metadata_finished_by_user(&seen.user_id, &seen.metadata_id, &ss.db)
                 .await?
-                .unwrap();
-            if metadata.lot == MediaLot::Podcast
-                || metadata.lot == MediaLot::Show
-                || metadata.lot == MediaLot::Anime
-                || metadata.lot == MediaLot::Manga
-            {
-                let (is_complete, _) =
-                    is_metadata_finished_by_user(&seen.user_id, &seen.metadata_id, &ss.db).await?;
-                if is_complete {
-                    remove_entity_from_collection(&DefaultCollection::InProgress.to_string())
-                        .await
-                        .ok();
-                    add_entity_to_collection(&DefaultCollection::Completed.to_string())
-                        .await
-                        .ok();
-                } else {
-                    for col in &[DefaultCollection::InProgress, DefaultCollection::Monitoring] {
-                        add_entity_to_collection(&col.to_string()).await.ok();
-                    }
-                }
-            } else {
-                add_entity_to_collection(&DefaultCollection::Completed.to_string())
-                    .await
-                    .ok();
-                for col in &[DefaultCollection::InProgress, DefaultCollection::Monitoring] {
-                    remove_entity_from_collection(&col.to_string()).await.ok();
-                }
-            };
+                .is_finished
         }
+        _ => false,
     };
+    let (to_add, to_remove) = default_collection_changes(seen.state, metadata.lot, is_finished);
+    for col in to_remove {
+        remove_entity_from_collection(&col.to_string()).await.ok();
+    }
+    for col in to_add {
+        add_entity_to_collection(&col.to_string()).await.ok();
+    }
     Ok(())
 }
 
+/// The progress of a media calculated from the number of pages read or minutes
+/// consumed, if either of them was given.
+async fn progress_from_amount_consumed(
+    input: &ProgressUpdateInput,
+    ss: &Arc<SupportingService>,
+) -> Result<Option<Decimal>> {
+    if input.pages_read.is_none() && input.minutes_consumed.is_none() {
+        return Ok(None);
+    }
+    let Some(meta) = Metadata::find_by_id(&input.metadata_id).one(&ss.db).await? else {
+        return Err(Error::new("Metadata with the given id does not exist"));
+    };
+    let (consumed, total) = match (input.pages_read, input.minutes_consumed) {
+        (Some(pages), _) => {
+            // DEV: The chosen edition is more accurate than the provider's page count
+            let total = input
+                .edition
+                .as_ref()
+                .and_then(|e| e.pages)
+                .or(meta.book_specifics.and_then(|b| b.pages))
+                .ok_or_else(|| Error::new("The number of pages of this media is not known"))?;
+            (pages, total)
+        }
+        (None, Some(minutes)) => {
+            let total = meta
+                .movie_specifics
+                .and_then(|m| m.runtime)
+                .or(meta.audio_book_specifics.and_then(|a| a.runtime))
+                .map(|r| r.minutes())
+                .ok_or_else(|| Error::new("The runtime of this media is not known"))?;
+            (minutes, total)
+        }
+        (None, None) => unreachable!(),
+    };
+    if total <= 0 {
+        return Err(Error::new("The length of this media is not known"));
+    }
+    let progress = Decimal::from(consumed) * dec!(100) / Decimal::from(total);
+    Ok(Some(progress.clamp(dec!(0), dec!(100)).round_dp(2)))
+}
+
+/// The entries that a progress update can apply to.
+// DEV: Shows and podcasts can have several episodes underway at the same time, for
+// example when two seasons are watched in parallel. Progress only applies to the
+// entry of the episode in the input.
+fn seen_in_progress_for_episode(
+    all_prev_seen: Vec<seen::Model>,
+    input: &ProgressUpdateInput,
+) -> Vec<seen::Model> {
+    all_prev_seen
+        .into_iter()
+        .filter(
+            |s| match (&s.show_extra_information, &s.podcast_extra_information) {
+                (Some(show), _) if input.show_episode_number.is_some() => {
+                    input.show_season_number == Some(show.season)
+                        && input.show_episode_number == Some(show.episode)
+                }
+                (_, Some(podcast)) if input.podcast_episode_number.is_some() => {
+                    input.podcast_episode_number == Some(podcast.episode)
+                }
+                _ => true,
+            },
+        )
+        .collect_vec()
+}
+
+/// Several episodes are underway and the input does not say which one to update.
+fn is_ambiguous_progress_update(
+    all_prev_seen: &[seen::Model],
+    input: &ProgressUpdateInput,
+) -> bool {
+    let episode_given =
+        input.show_episode_number.is_some() || input.podcast_episode_number.is_some();
+    let has_threads = all_prev_seen
+        .iter()
+        .any(|s| s.show_extra_information.is_some() || s.podcast_extra_information.is_some());
+    all_prev_seen.len() > 1 && has_threads && !episode_given
+}
+
 pub async fn progress_update(
     user_id: &String,
     // update only if media has not been consumed for this user in the last `n` duration
@@ -1271,6 +2644,18 @@ pub async fn progress_update(
         }));
     }
     ryot_log!(debug, "Input for progress_update = {:?}", input);
+    let mut input = input;
+    if input.progress.is_none() {
+        input.progress = progress_from_amount_consumed(&input, ss).await?;
+    }
+    if let Some(progress) = input.progress {
+        if progress < dec!(0) || progress > dec!(100) {
+            return Err(Error::new(format!(
+                "Progress must be between 0 and 100, got {}",
+                progress
+            )));
+        }
+    }
 
     let all_prev_seen = Seen::find()
         .filter(seen::Column::Progress.lt(100))
@@ -1278,9 +2663,11 @@ pub async fn progress_update(
         .filter(seen::Column::State.ne(SeenState::Dropped))
         .filter(seen::Column::MetadataId.eq(&input.metadata_id))
         .order_by_desc(seen::Column::LastUpdatedOn)
+        .order_by_desc(seen::Column::Id)
         .all(&ss.db)
         .await
         .unwrap();
+    let all_prev_seen = seen_in_progress_for_episode(all_prev_seen, &input);
     #[derive(Debug, Serialize, Deserialize, Enum, Clone, PartialEq, Eq, Copy)]
     enum ProgressUpdateAction {
         Update,
@@ -1325,10 +2712,41 @@ pub async fn progress_update(
     };
     let seen = match action {
         ProgressUpdateAction::Update => {
+            if is_ambiguous_progress_update(&all_prev_seen, &input) {
+                return Ok(ProgressUpdateResultUnion::Error(ProgressUpdateError {
+                    error: ProgressUpdateErrorVariant::AmbiguousSeenInProgress,
+                }));
+            }
             let prev_seen = all_prev_seen[0].clone();
             let progress = input.progress.unwrap();
             let watched_on = prev_seen.provider_watched_on.clone();
-            if prev_seen.progress == progress && watched_on == input.provider_watched_on {
+            if progress < prev_seen.progress && !input.allow_progress_decrease.unwrap_or_default() {
+                ryot_log!(
+                    debug,
+                    "Rejecting progress update from {} to {}",
+                    prev_seen.progress,
+                    progress
+                );
+                return Ok(ProgressUpdateResultUnion::Error(ProgressUpdateError {
+                    error: ProgressUpdateErrorVariant::RegressiveProgressUpdate,
+                }));
+            }
+            let is_unchanged = prev_seen.progress == progress
+                && watched_on == input.provider_watched_on
+                && input
+                    .edition
+                    .as_ref()
+                    .map_or(true, |e| prev_seen.edition.as_ref() == Some(e));
+            // DEV: Integrations can send the same event several times, so these are not
+            // counted as new updates
+            let dedupe_window = Duration::minutes(ss.config.server.progress_update_dedupe_minutes);
+            if is_unchanged && prev_seen.last_updated_on > Utc::now() - dedupe_window {
+                ryot_log!(debug, "Ignoring repeated progress update");
+                return Ok(ProgressUpdateResultUnion::Ok(StringIdObject {
+                    id: prev_seen.id,
+                }));
+            }
+            if is_unchanged {
                 ryot_log!(debug, "No progress update required");
                 return Ok(ProgressUpdateResultUnion::Error(ProgressUpdateError {
                     error: ProgressUpdateErrorVariant::UpdateWithoutProgressUpdate,
@@ -1345,6 +2763,15 @@ pub async fn progress_update(
             last_seen.updated_at = ActiveValue::Set(updated_at);
             last_seen.provider_watched_on =
                 ActiveValue::Set(input.provider_watched_on.or(watched_on));
+            if input.edition.is_some() {
+                last_seen.edition = ActiveValue::Set(input.edition);
+            }
+            if input.pages_read.is_some() {
+                last_seen.pages_read = ActiveValue::Set(input.pages_read);
+            }
+            if input.minutes_consumed.is_some() {
+                last_seen.minutes_consumed = ActiveValue::Set(input.minutes_consumed);
+            }
 
             // This is needed for manga as some of the apps will update in weird orders
             // For example with komga mihon will update out of order to the server
@@ -1364,10 +2791,28 @@ pub async fn progress_update(
                 .filter(seen::Column::UserId.eq(user_id))
                 .filter(seen::Column::MetadataId.eq(input.metadata_id))
                 .order_by_desc(seen::Column::LastUpdatedOn)
+                .order_by_desc(seen::Column::Id)
                 .one(&ss.db)
                 .await
                 .unwrap();
             match last_seen {
+                // DEV: Only something that is underway can be dropped, a finished entry
+                // stays in the history as it is
+                Some(ls)
+                    if new_state == SeenState::Dropped
+                        && !matches!(ls.state, SeenState::InProgress | SeenState::OnAHold) =>
+                {
+                    return err();
+                }
+                // DEV: Putting on hold and resuming keep using the same entry so that
+                // the progress and the started date are not lost
+                Some(ls)
+                    if (new_state == SeenState::OnAHold && ls.state != SeenState::InProgress)
+                        || (new_state == SeenState::InProgress
+                            && !matches!(ls.state, SeenState::OnAHold | SeenState::Dropped)) =>
+                {
+                    return err();
+                }
                 Some(ls) => {
                     let watched_on = ls.provider_watched_on.clone();
                     let mut updated_at = ls.updated_at.clone();
@@ -1457,6 +2902,9 @@ pub async fn progress_update(
                 finished_on: ActiveValue::Set(finished_on),
                 state: ActiveValue::Set(SeenState::InProgress),
                 provider_watched_on: ActiveValue::Set(input.provider_watched_on),
+                edition: ActiveValue::Set(input.edition),
+                pages_read: ActiveValue::Set(input.pages_read),
+                minutes_consumed: ActiveValue::Set(input.minutes_consumed),
                 show_extra_information: ActiveValue::Set(show_ei),
                 podcast_extra_information: ActiveValue::Set(podcast_ei),
                 anime_extra_information: ActiveValue::Set(anime_ei),
@@ -1491,10 +2939,10 @@ fn convert_review_into_input(
         ryot_log!(debug, "Skipping review since it has no content");
         return None;
     }
-    let rating = match preferences.general.review_scale {
-        UserReviewScale::OutOfFive => review.rating.map(|rating| rating / dec!(20)),
-        UserReviewScale::OutOfHundred | UserReviewScale::ThreePointSmiley => review.rating,
-    };
+    let review_scale = preferences.general.review_scale;
+    let rating = review
+        .rating
+        .map(|rating| (rating / review_scale.divisor()).min(review_scale.maximum()));
     let text = review.review.clone().and_then(|r| r.text);
     let is_spoiler = review.review.clone().map(|r| r.spoiler.unwrap_or(false));
     let date = review.review.clone().map(|r| r.date);
@@ -1907,6 +3355,153 @@ async fn create_collection_and_add_entity_to_it(
     Ok(())
 }
 
+// DEV: Metadata is committed and associated with the user in chunks of this size
+const IMPORT_CHUNK_SIZE: usize = 100;
+
+/// Whether a seen item was already recorded, which happens when the same file is
+/// imported more than once. Finished items are matched on the date they were
+/// finished and unfinished ones on the date they were started with the same
+/// progress. Items without either date can not be told apart and are always imported.
+async fn is_seen_already_imported(
+    user_id: &String,
+    metadata_id: &String,
+    seen: &ImportOrExportMediaItemSeen,
+    ss: &Arc<SupportingService>,
+) -> Result<bool> {
+    let progress = seen.progress.unwrap_or(dec!(100));
+    let query = Seen::find()
+        .filter(seen::Column::UserId.eq(user_id))
+        .filter(seen::Column::MetadataId.eq(metadata_id));
+    let query = match (seen.ended_on, seen.started_on) {
+        (Some(ended_on), _) if progress == dec!(100) => {
+            query.filter(seen::Column::FinishedOn.eq(ended_on))
+        }
+        (None, Some(started_on)) => query
+            .filter(seen::Column::FinishedOn.is_null())
+            .filter(seen::Column::StartedOn.eq(started_on))
+            .filter(seen::Column::Progress.eq(progress)),
+        _ => return Ok(false),
+    };
+    let existing = query.all(&ss.db).await?;
+    Ok(existing.into_iter().any(|s| {
+        s.show_extra_information.map(|i| (i.season, i.episode))
+            == seen.show_season_number.zip(seen.show_episode_number)
+            && s.podcast_extra_information.map(|i| i.episode) == seen.podcast_episode_number
+            && s.anime_extra_information.and_then(|i| i.episode) == seen.anime_episode_number
+            && s.manga_extra_information
+                .map_or((None, None), |i| (i.chapter, i.volume))
+                == (seen.manga_chapter_number, seen.manga_volume_number)
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn import_metadata_item_details(
+    user_id: &String,
+    respect_cache: bool,
+    idx: usize,
+    item: ImportOrExportMediaItem,
+    metadata: metadata::Model,
+    preferences: &UserPreferences,
+    failed_items: &mut Vec<ImportFailedItem>,
+    ss: &Arc<SupportingService>,
+) -> Result<()> {
+    for seen in item.seen_history.iter() {
+        if is_seen_already_imported(user_id, &metadata.id, seen, ss).await? {
+            ryot_log!(debug, "Skipping seen item since it was already imported");
+            continue;
+        }
+        let progress = if seen.progress.is_some() {
+            seen.progress
+        } else {
+            Some(dec!(100))
+        };
+        match progress_update(
+            user_id,
+            respect_cache,
+            ProgressUpdateInput {
+                metadata_id: metadata.id.clone(),
+                progress,
+                date: seen.ended_on,
+                show_season_number: seen.show_season_number,
+                show_episode_number: seen.show_episode_number,
+                podcast_episode_number: seen.podcast_episode_number,
+                anime_episode_number: seen.anime_episode_number,
+                manga_chapter_number: seen.manga_chapter_number,
+                manga_volume_number: seen.manga_volume_number,
+                provider_watched_on: seen.provider_watched_on.clone(),
+                edition: seen.edition.clone(),
+                pages_read: seen.pages_read,
+                minutes_consumed: seen.minutes_consumed,
+                change_state: None,
+                // DEV: The history is imported in order and can go back to an earlier point
+                allow_progress_decrease: Some(true),
+            },
+            ss,
+        )
+        .await
+        {
+            Ok(ProgressUpdateResultUnion::Ok(StringIdObject { id })) => {
+                // DEV: The original start date is kept instead of the date of the import
+                if let Some(started_on) = seen.started_on {
+                    Seen::update_many()
+                        .col_expr(seen::Column::StartedOn, Expr::value(started_on))
+                        .filter(seen::Column::Id.eq(id))
+                        .exec(&ss.db)
+                        .await?;
+                }
+            }
+            Ok(ProgressUpdateResultUnion::Error(e)) => failed_items.push(ImportFailedItem {
+                lot: Some(item.lot),
+                step: ImportFailStep::SeenHistoryConversion,
+                identifier: item.source_id.to_owned(),
+                error: Some(format!("Could not update progress: {:?}", e.error)),
+            }),
+            Err(e) => failed_items.push(ImportFailedItem {
+                lot: Some(item.lot),
+                step: ImportFailStep::SeenHistoryConversion,
+                identifier: item.source_id.to_owned(),
+                error: Some(e.message),
+            }),
+        };
+    }
+    for review in item.reviews.iter() {
+        if let Some(input) = convert_review_into_input(
+            review,
+            preferences,
+            metadata.id.clone(),
+            EntityLot::Metadata,
+        ) {
+            if let Err(e) = post_review(user_id, input, ss).await {
+                failed_items.push(ImportFailedItem {
+                    lot: Some(item.lot),
+                    step: ImportFailStep::ReviewConversion,
+                    identifier: item.source_id.to_owned(),
+                    error: Some(e.message),
+                });
+            };
+        }
+    }
+    for col in item.collections.into_iter() {
+        create_collection_and_add_entity_to_it(
+            ss,
+            user_id,
+            col,
+            metadata.id.clone(),
+            EntityLot::Metadata,
+        )
+        .await?;
+    }
+    ryot_log!(
+        debug,
+        "Imported item: {idx}, lot: {lot}, history count: {hist}, review count: {rev}",
+        idx = idx + 1,
+        lot = item.lot,
+        hist = item.seen_history.len(),
+        rev = item.reviews.len(),
+    );
+    Ok(())
+}
+
 pub async fn process_import(
     user_id: &String,
     respect_cache: bool,
@@ -1914,6 +3509,7 @@ pub async fn process_import(
     ss: &Arc<SupportingService>,
 ) -> Result<ImportResultResponse> {
     let mut import = import;
+    let started_at = Instant::now();
     let preferences = user_by_id(user_id, ss).await?.preferences;
     for m in import.metadata.iter_mut() {
         m.seen_history.sort_by(|a, b| {
@@ -1935,107 +3531,72 @@ pub async fn process_import(
         ryot_log!(debug, "Collection {} created", idx);
     }
 
-    for (idx, item) in import.metadata.into_iter().enumerate() {
-        let source_id = if item.source_id.is_empty() {
-            item.identifier.clone()
-        } else {
-            item.source_id.clone()
-        };
-        ryot_log!(debug, "Importing media with identifier = {:#?}", source_id);
-        let rev_length = item.reviews.len();
-        let identifier = item.identifier.clone();
-        let data = commit_metadata(
-            CommitMediaInput {
-                identifier,
-                lot: item.lot,
-                source: item.source,
-                force_update: None,
-            },
-            ss,
-        )
-        .await;
-        let metadata = match data {
-            Ok(r) => r,
-            Err(e) => {
-                ryot_log!(error, "{e:?}");
-                import.failed_items.push(ImportFailedItem {
-                    lot: Some(item.lot),
-                    step: ImportFailStep::MediaDetailsFromProvider,
-                    identifier: item.source_id.to_owned(),
-                    error: Some(e.message),
-                });
-                continue;
-            }
-        };
-        for seen in item.seen_history.iter() {
-            let progress = if seen.progress.is_some() {
-                seen.progress
+    let metadata_chunks = import
+        .metadata
+        .into_iter()
+        .enumerate()
+        .chunks(IMPORT_CHUNK_SIZE)
+        .into_iter()
+        .map(|c| c.collect_vec())
+        .collect_vec();
+    for chunk in metadata_chunks {
+        let mut committed = vec![];
+        for (idx, item) in chunk {
+            let source_id = if item.source_id.is_empty() {
+                item.identifier.clone()
             } else {
-                Some(dec!(100))
+                item.source_id.clone()
             };
-            if let Err(e) = progress_update(
-                user_id,
-                respect_cache,
-                ProgressUpdateInput {
-                    metadata_id: metadata.id.clone(),
-                    progress,
-                    date: seen.ended_on,
-                    show_season_number: seen.show_season_number,
-                    show_episode_number: seen.show_episode_number,
-                    podcast_episode_number: seen.podcast_episode_number,
-                    anime_episode_number: seen.anime_episode_number,
-                    manga_chapter_number: seen.manga_chapter_number,
-                    manga_volume_number: seen.manga_volume_number,
-                    provider_watched_on: seen.provider_watched_on.clone(),
-                    change_state: None,
+            ryot_log!(debug, "Importing media with identifier = {:#?}", source_id);
+            let data = commit_metadata(
+                CommitMediaInput {
+                    identifier: item.identifier.clone(),
+                    lot: item.lot,
+                    source: item.source,
+                    force_update: None,
                 },
                 ss,
             )
-            .await
-            {
-                import.failed_items.push(ImportFailedItem {
-                    lot: Some(item.lot),
-                    step: ImportFailStep::SeenHistoryConversion,
-                    identifier: item.source_id.to_owned(),
-                    error: Some(e.message),
-                });
-            };
-        }
-        for review in item.reviews.iter() {
-            if let Some(input) = convert_review_into_input(
-                review,
-                &preferences,
-                metadata.id.clone(),
-                EntityLot::Metadata,
-            ) {
-                if let Err(e) = post_review(user_id, input, ss).await {
+            .await;
+            match data {
+                Ok(metadata) => committed.push((idx, item, metadata)),
+                Err(e) => {
+                    ryot_log!(error, "{e:?}");
                     import.failed_items.push(ImportFailedItem {
                         lot: Some(item.lot),
-                        step: ImportFailStep::ReviewConversion,
+                        step: ImportFailStep::MediaDetailsFromProvider,
                         identifier: item.source_id.to_owned(),
                         error: Some(e.message),
                     });
-                };
+                }
             }
         }
-        for col in item.collections.into_iter() {
-            create_collection_and_add_entity_to_it(
-                ss,
+        // DEV: Creating the associations up front means that inserting the seen
+        // entries below only touches existing rows instead of racing to create them
+        let to_associate = committed
+            .iter()
+            .filter(|(_, item, _)| {
+                !item.seen_history.is_empty()
+                    || !item.reviews.is_empty()
+                    || !item.collections.is_empty()
+            })
+            .map(|(_, _, metadata)| metadata.id.clone())
+            .unique()
+            .collect_vec();
+        associate_user_with_entities(&ss.db, user_id, to_associate, EntityLot::Metadata).await?;
+        for (idx, item, metadata) in committed {
+            import_metadata_item_details(
                 user_id,
-                col,
-                metadata.id.clone(),
-                EntityLot::Metadata,
+                respect_cache,
+                idx,
+                item,
+                metadata,
+                &preferences,
+                &mut import.failed_items,
+                ss,
             )
             .await?;
         }
-        ryot_log!(
-            debug,
-            "Imported item: {idx}, lot: {lot}, history count: {hist}, review count: {rev}",
-            idx = idx + 1,
-            lot = item.lot,
-            hist = item.seen_history.len(),
-            rev = rev_length,
-        );
     }
     for (idx, item) in import.metadata_groups.into_iter().enumerate() {
         ryot_log!(
@@ -2190,9 +3751,18 @@ pub async fn process_import(
 
     // TODO: Allow importing exercises
 
+    let elapsed = started_at.elapsed().as_secs_f64();
+    ryot_log!(
+        info,
+        "Imported {} items in {:.2}s ({:.2} items/second)",
+        total,
+        elapsed,
+        total as f64 / elapsed.max(f64::EPSILON)
+    );
     let details = ImportResultResponse {
         import: ImportDetails { total },
         failed_items: import.failed_items,
+        warnings: import.warnings,
     };
 
     Ok(details)
@@ -2234,3 +3804,788 @@ pub fn db_workout_to_workout_input(user_workout: workout::Model) -> UserWorkoutI
             .collect(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Timelike;
+    use enums::{IntegrationHealthState, IntegrationLot};
+    use media_models::{
+        AnimeSpecifics, BookSpecifics, IntegrationProviderSpecifics, MangaSpecifics,
+        MetadataImageForMediaDetails, ShowEpisode, ShowSeason, ShowSpecifics,
+    };
+    use sea_orm::{DatabaseBackend, MockDatabase, Value};
+
+    use super::*;
+
+    // DEV: Tuples are read by position, so the keys only keep the columns in order
+    fn tuple_row(values: Vec<Value>) -> BTreeMap<String, Value> {
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect()
+    }
+
+    /// What `->>` returns for a field of a JSON column.
+    fn json_text(value: &serde_json::Value, field: &str) -> String {
+        match &value[field] {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    fn counts(keys: &[(&str, i64)]) -> Vec<(String, i64)> {
+        keys.iter().map(|(k, c)| (k.to_string(), *c)).collect()
+    }
+
+    #[test]
+    fn refreshed_groups_pick_up_new_parts() {
+        let existing = metadata_group::Model {
+            id: "group".to_owned(),
+            identifier: "86311".to_owned(),
+            lot: MediaLot::Movie,
+            source: MediaSource::Tmdb,
+            parts: 3,
+            title: "The Avengers Collection".to_owned(),
+            images: vec![],
+            display_images: vec![],
+            is_partial: Some(true),
+            description: None,
+        };
+        let details = MetadataGroupWithoutId {
+            identifier: "86311".to_owned(),
+            lot: MediaLot::Movie,
+            source: MediaSource::Tmdb,
+            parts: 4,
+            title: "The Avengers Collection".to_owned(),
+            images: vec![],
+            display_images: vec![],
+            description: Some("Earth's mightiest heroes.".to_owned()),
+        };
+        let refreshed = refreshed_metadata_group(existing, details);
+        assert_eq!(refreshed.id, ActiveValue::Unchanged("group".to_owned()));
+        assert_eq!(refreshed.parts, ActiveValue::Set(4));
+        assert_eq!(refreshed.is_partial, ActiveValue::Set(Some(false)));
+        assert_eq!(
+            refreshed.description,
+            ActiveValue::Set(Some("Earth's mightiest heroes.".to_owned()))
+        );
+    }
+
+    #[test]
+    fn seen_episode_keys_match_metadata_episode_keys() {
+        let manga = metadata::Model {
+            lot: MediaLot::Manga,
+            manga_specifics: Some(MangaSpecifics {
+                chapters: Some(dec!(3)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(metadata_episode_keys(manga), vec!["1", "2", "3"]);
+        // DEV: The keys of seen entries come from the stored JSON, so the chapter
+        // keeps the scale it was logged with
+        let chapter = serde_json::to_value(SeenMangaExtraInformation {
+            chapter: Some(dec!(2.0)),
+            volume: None,
+        })
+        .unwrap();
+        assert_eq!(json_text(&chapter, "chapter"), "2.0");
+        assert_eq!(normalize_episode_key(json_text(&chapter, "chapter")), "2");
+        let half = serde_json::to_value(SeenMangaExtraInformation {
+            chapter: Some(dec!(2.50)),
+            volume: None,
+        })
+        .unwrap();
+        assert_eq!(normalize_episode_key(json_text(&half, "chapter")), "2.5");
+        let episode =
+            serde_json::to_value(SeenAnimeExtraInformation { episode: Some(12) }).unwrap();
+        assert_eq!(json_text(&episode, "episode"), "12");
+        assert_eq!(normalize_episode_key("1-2".to_owned()), "1-2");
+    }
+
+    #[test]
+    fn metadata_episode_keys_skip_special_seasons() {
+        let episode = |episode_number| ShowEpisode {
+            episode_number,
+            ..Default::default()
+        };
+        let show = metadata::Model {
+            lot: MediaLot::Show,
+            show_specifics: Some(ShowSpecifics {
+                seasons: vec![
+                    ShowSeason {
+                        season_number: 0,
+                        name: "Specials".to_owned(),
+                        episodes: vec![episode(1)],
+                        ..Default::default()
+                    },
+                    ShowSeason {
+                        season_number: 1,
+                        name: "Season 1".to_owned(),
+                        episodes: vec![episode(1), episode(2)],
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(metadata_episode_keys(show), vec!["1-1", "1-2"]);
+    }
+
+    #[test]
+    fn episodic_finished_status_needs_every_episode_the_same_number_of_times() {
+        let all = || vec!["1".to_owned(), "2".to_owned(), "3".to_owned()];
+        assert_eq!(
+            episodic_finished_status(all(), vec![]),
+            MetadataFinishedStatus::default()
+        );
+        assert_eq!(
+            episodic_finished_status(all(), counts(&[("1", 1), ("3", 1)])),
+            MetadataFinishedStatus {
+                is_finished: false,
+                has_seen: true,
+            }
+        );
+        assert_eq!(
+            episodic_finished_status(all(), counts(&[("1", 2), ("2", 1), ("3", 1)])),
+            MetadataFinishedStatus {
+                is_finished: false,
+                has_seen: true,
+            }
+        );
+        assert_eq!(
+            episodic_finished_status(all(), counts(&[("1", 1), ("2.0", 1), ("3", 1), ("", 4)])),
+            MetadataFinishedStatus {
+                is_finished: true,
+                has_seen: true,
+            }
+        );
+        assert_eq!(
+            episodic_finished_status(vec![], vec![]),
+            MetadataFinishedStatus {
+                is_finished: true,
+                has_seen: false,
+            }
+        );
+    }
+
+    #[test]
+    fn only_completed_seen_items_finish_media_without_episodes() {
+        for state in SeenState::iter() {
+            assert_eq!(
+                state.to_value() == "completed",
+                state == SeenState::Completed,
+                "{:?}",
+                state
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn is_metadata_finished_by_user_uses_two_queries_for_media_without_episodes() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![metadata::Model {
+                lot: MediaLot::Movie,
+                ..Default::default()
+            }]])
+            .append_query_results([vec![tuple_row(vec![
+                Value::BigInt(Some(2)),
+                Value::BigInt(Some(0)),
+            ])]])
+            .into_connection();
+        let status = is_metadata_finished_by_user(&"u".to_owned(), &"m".to_owned(), &db)
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            MetadataFinishedStatus {
+                is_finished: false,
+                has_seen: true,
+            }
+        );
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 2);
+        let aggregate = format!("{:?}", log[1]);
+        assert!(aggregate.contains(r#"FILTER (WHERE \"state\" = $1)"#));
+        assert!(aggregate.contains(r#"String(Some("completed"))"#));
+    }
+
+    #[tokio::test]
+    async fn is_metadata_finished_by_user_uses_two_queries_for_episodic_media() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![metadata::Model {
+                lot: MediaLot::Anime,
+                anime_specifics: Some(AnimeSpecifics {
+                    episodes: Some(2),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]])
+            .append_query_results([vec![
+                tuple_row(vec![Value::from("1"), Value::BigInt(Some(1))]),
+                tuple_row(vec![Value::from("2"), Value::BigInt(Some(1))]),
+            ]])
+            .into_connection();
+        let status = is_metadata_finished_by_user(&"u".to_owned(), &"m".to_owned(), &db)
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            MetadataFinishedStatus {
+                is_finished: true,
+                has_seen: true,
+            }
+        );
+        assert_eq!(db.into_transaction_log().len(), 2);
+    }
+
+    fn provider_image(image: &str) -> MetadataImageForMediaDetails {
+        MetadataImageForMediaDetails {
+            image: image.to_owned(),
+        }
+    }
+
+    #[test]
+    fn refreshed_metadata_images_keep_user_provided_images() {
+        let uploaded = MetadataImage {
+            url: StoredUrl::S3("metadata-images/abc.png".to_owned()),
+            is_user_provided: true,
+        };
+        let existing = vec![
+            uploaded.clone(),
+            MetadataImage {
+                url: StoredUrl::Url("https://provider.example/old.png".to_owned()),
+                ..Default::default()
+            },
+        ];
+        let details = MetadataDetails {
+            url_images: vec![provider_image("https://provider.example/new.png")],
+            ..Default::default()
+        };
+        assert_eq!(
+            refreshed_metadata_images(Some(&existing), &details),
+            vec![
+                uploaded,
+                MetadataImage {
+                    url: StoredUrl::Url("https://provider.example/new.png".to_owned()),
+                    ..Default::default()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn refreshed_metadata_images_drop_provider_images_that_are_gone() {
+        let existing = vec![MetadataImage {
+            url: StoredUrl::S3("provider-cover.png".to_owned()),
+            ..Default::default()
+        }];
+        let details = MetadataDetails::default();
+        assert!(refreshed_metadata_images(Some(&existing), &details).is_empty());
+        assert!(refreshed_metadata_images(None, &details).is_empty());
+    }
+
+    #[test]
+    fn normalize_provider_rating_scales_to_100() {
+        assert_eq!(
+            normalize_provider_rating(Some(dec!(4.5)), MediaSource::Audible),
+            (Some(dec!(90)), Some(dec!(5)))
+        );
+        assert_eq!(
+            normalize_provider_rating(Some(dec!(7.123)), MediaSource::Tmdb),
+            (Some(dec!(71.23)), Some(dec!(10)))
+        );
+        assert_eq!(
+            normalize_provider_rating(Some(dec!(84)), MediaSource::Anilist),
+            (Some(dec!(84)), Some(dec!(100)))
+        );
+    }
+
+    #[test]
+    fn normalize_provider_rating_rounds_to_two_places() {
+        assert_eq!(
+            normalize_provider_rating(Some(dec!(3.333)), MediaSource::GoogleBooks),
+            (Some(dec!(66.66)), Some(dec!(5)))
+        );
+    }
+
+    #[test]
+    fn normalize_provider_rating_needs_a_rating_and_a_scale() {
+        assert_eq!(
+            normalize_provider_rating(None, MediaSource::Tmdb),
+            (None, None)
+        );
+        assert_eq!(
+            normalize_provider_rating(Some(dec!(8)), MediaSource::Openlibrary),
+            (None, None)
+        );
+        assert_eq!(
+            normalize_provider_rating(Some(dec!(8)), MediaSource::Custom),
+            (None, None)
+        );
+    }
+
+    fn collection_changes(
+        state: SeenState,
+        lot: MediaLot,
+        is_finished: bool,
+    ) -> (Vec<String>, Vec<String>) {
+        let names = |c: Vec<DefaultCollection>| c.iter().map(|c| c.to_string()).collect_vec();
+        let (to_add, to_remove) = default_collection_changes(state, lot, is_finished);
+        (names(to_add), names(to_remove))
+    }
+
+    #[test]
+    fn default_collection_changes_moves_started_media_to_in_progress() {
+        assert_eq!(
+            collection_changes(SeenState::InProgress, MediaLot::Movie, false),
+            (
+                vec!["In Progress".to_owned(), "Monitoring".to_owned()],
+                vec!["Watchlist".to_owned()]
+            )
+        );
+    }
+
+    #[test]
+    fn default_collection_changes_keeps_partially_watched_shows_in_progress() {
+        assert_eq!(
+            collection_changes(SeenState::Completed, MediaLot::Show, false),
+            (
+                vec!["In Progress".to_owned(), "Monitoring".to_owned()],
+                vec!["Watchlist".to_owned()]
+            )
+        );
+        assert_eq!(
+            collection_changes(SeenState::Completed, MediaLot::Show, true),
+            (
+                vec!["Completed".to_owned()],
+                vec!["Watchlist".to_owned(), "In Progress".to_owned()]
+            )
+        );
+    }
+
+    #[test]
+    fn default_collection_changes_completes_media_without_episodes() {
+        // DEV: `is_finished` is only computed for media with episodes
+        assert_eq!(
+            collection_changes(SeenState::Completed, MediaLot::Movie, false),
+            (
+                vec!["Completed".to_owned()],
+                vec![
+                    "Watchlist".to_owned(),
+                    "In Progress".to_owned(),
+                    "Monitoring".to_owned()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn default_collection_changes_do_not_need_existing_collections() {
+        // DEV: Media that was never in any collection gets the same changes and
+        // removing it from a collection it is not in is ignored by the caller
+        for state in [SeenState::Dropped, SeenState::OnAHold] {
+            assert_eq!(
+                collection_changes(state, MediaLot::Book, false),
+                (
+                    vec![],
+                    vec!["Watchlist".to_owned(), "In Progress".to_owned()]
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn seen_changes_query_orders_by_time_and_id() {
+        let cursor = SeenChangesCursor {
+            id: "seen_1".to_owned(),
+            last_updated_on: Utc::now(),
+        };
+        let sql = seen_changes_query(&"usr_1".to_owned(), Some(&cursor))
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#""seen"."last_updated_on" >"#));
+        assert!(sql.contains(r#""seen"."id" > 'seen_1'"#));
+        assert!(sql.ends_with(r#"ORDER BY "seen"."last_updated_on" ASC, "seen"."id" ASC"#));
+        let sql = seen_changes_query(&"usr_1".to_owned(), None)
+            .build(DatabaseBackend::Postgres)
+            .to_string();
+        assert!(!sql.contains(r#""seen"."id" >"#));
+    }
+
+    #[test]
+    fn seen_changes_scan_returns_same_second_rows_once() {
+        // DEV: Mirrors the filter and order of `seen_changes_query` for rows that
+        // were all updated within the same second
+        let second = Utc::now().with_nanosecond(0).unwrap();
+        let rows = (0..50)
+            .map(|i| SeenChangesCursor {
+                id: format!("seen_{:02}", (i * 37) % 50),
+                last_updated_on: second + Duration::milliseconds(i % 3 * 300),
+            })
+            .collect_vec();
+        let mut after: Option<SeenChangesCursor> = None;
+        let mut scanned = vec![];
+        loop {
+            let page = rows
+                .iter()
+                .filter(|r| after.as_ref().map_or(true, |a| *r > a))
+                .sorted()
+                .take(7)
+                .cloned()
+                .collect_vec();
+            if page.is_empty() {
+                break;
+            }
+            after = page.last().cloned();
+            scanned.extend(page);
+        }
+        assert_eq!(scanned.len(), rows.len());
+        assert_eq!(scanned, rows.into_iter().sorted().collect_vec());
+        assert!(scanned.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    fn show_thread(id: &str, season: i32, episode: i32) -> seen::Model {
+        seen::Model {
+            id: id.to_owned(),
+            progress: dec!(40),
+            started_on: None,
+            finished_on: None,
+            user_id: "user".to_owned(),
+            metadata_id: "show".to_owned(),
+            state: SeenState::InProgress,
+            provider_watched_on: None,
+            updated_at: vec![Utc::now()],
+            show_extra_information: Some(SeenShowExtraInformation { season, episode }),
+            podcast_extra_information: None,
+            anime_extra_information: None,
+            manga_extra_information: None,
+            manual_time_spent: None,
+            edition: None,
+            pages_read: None,
+            minutes_consumed: None,
+            last_updated_on: Utc::now(),
+            num_times_updated: 1,
+            show_season_number: Some(season),
+            show_episode_number: Some(episode),
+            podcast_episode_number: None,
+            review_id: None,
+        }
+    }
+
+    fn show_progress(season: Option<i32>, episode: Option<i32>) -> ProgressUpdateInput {
+        ProgressUpdateInput {
+            metadata_id: "show".to_owned(),
+            date: None,
+            progress: Some(dec!(80)),
+            change_state: None,
+            show_season_number: season,
+            show_episode_number: episode,
+            manga_volume_number: None,
+            anime_episode_number: None,
+            podcast_episode_number: None,
+            manga_chapter_number: None,
+            provider_watched_on: None,
+            edition: None,
+            pages_read: None,
+            minutes_consumed: None,
+            allow_progress_decrease: None,
+        }
+    }
+
+    #[test]
+    fn parallel_episodes_are_updated_independently() {
+        // DEV: A rewatch of season 1 and season 8 are underway at the same time
+        let threads = || vec![show_thread("rewatch", 1, 3), show_thread("latest", 8, 2)];
+        for (season, episode, expected) in [(1, 3, "rewatch"), (8, 2, "latest")] {
+            let input = show_progress(Some(season), Some(episode));
+            let matching = seen_in_progress_for_episode(threads(), &input);
+            assert_eq!(
+                matching.iter().map(|s| s.id.as_str()).collect_vec(),
+                [expected]
+            );
+            assert!(!is_ambiguous_progress_update(&matching, &input));
+        }
+        // DEV: An episode that is not underway starts a new entry
+        let input = show_progress(Some(8), Some(3));
+        assert!(seen_in_progress_for_episode(threads(), &input).is_empty());
+    }
+
+    #[test]
+    fn updates_without_an_episode_are_ambiguous_with_parallel_episodes() {
+        let input = show_progress(None, None);
+        let threads = seen_in_progress_for_episode(
+            vec![show_thread("rewatch", 1, 3), show_thread("latest", 8, 2)],
+            &input,
+        );
+        assert_eq!(threads.len(), 2);
+        assert!(is_ambiguous_progress_update(&threads, &input));
+        let single = vec![show_thread("latest", 8, 2)];
+        assert!(!is_ambiguous_progress_update(&single, &input));
+    }
+
+    #[tokio::test]
+    async fn genres_that_exist_are_not_created_again() {
+        let existing = genre::Model {
+            id: "gen_1".to_owned(),
+            name: "Drama".to_owned(),
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[existing.clone()]])
+            .into_connection();
+        let genre = genre_by_name("Drama".to_owned(), &db).await.unwrap();
+        assert_eq!(genre, existing);
+        assert_eq!(db.into_transaction_log().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn only_unique_violations_use_the_row_that_won() {
+        // DEV: The concurrent case needs a real unique index, here the insert fails
+        // for another reason and the error is returned without looking up again
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<genre::Model>::new()])
+            .append_query_errors([DbErr::Custom("connection reset".to_owned())])
+            .into_connection();
+        let error = genre_by_name("Drama".to_owned(), &db).await.unwrap_err();
+        assert!(error.message.contains("connection reset"));
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 2);
+        assert!(log[1].statements()[0]
+            .sql
+            .starts_with(r#"INSERT INTO "genre""#));
+    }
+
+    fn webhook_integration(
+        webhook_events: Option<Vec<OutboundWebhookEvent>>,
+    ) -> integration::Model {
+        integration::Model {
+            id: "integration".to_owned(),
+            minimum_progress: None,
+            maximum_progress: None,
+            user_id: "user".to_owned(),
+            lot: IntegrationLot::Push,
+            provider: IntegrationProvider::Webhook,
+            sync_to_owned_collection: None,
+            default_collection: None,
+            is_disabled: None,
+            created_on: Utc::now(),
+            last_triggered_on: None,
+            debug_mode_until: None,
+            expected_activity_days: None,
+            alerts_snoozed_until: None,
+            stale_alert_sent_on: None,
+            consecutive_failures: 0,
+            health_state: IntegrationHealthState::Healthy,
+            provider_specifics: Some(IntegrationProviderSpecifics {
+                webhook_url: Some("http://receiver.local/hook".to_owned()),
+                webhook_events,
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn webhooks_only_receive_the_selected_events() {
+        let selective = webhook_integration(Some(vec![OutboundWebhookEvent::SeenCompleted]));
+        assert!(is_subscribed_to_webhook_event(
+            &selective,
+            OutboundWebhookEvent::SeenCompleted
+        ));
+        assert!(!is_subscribed_to_webhook_event(
+            &selective,
+            OutboundWebhookEvent::ReviewPosted
+        ));
+        let everything = webhook_integration(None);
+        assert!(is_subscribed_to_webhook_event(
+            &everything,
+            OutboundWebhookEvent::ReviewPosted
+        ));
+        let mut disabled = webhook_integration(None);
+        disabled.is_disabled = Some(true);
+        assert!(!is_subscribed_to_webhook_event(
+            &disabled,
+            OutboundWebhookEvent::SeenCompleted
+        ));
+    }
+
+    fn garbage_book() -> metadata::Model {
+        metadata::Model {
+            id: "meta_1".to_owned(),
+            lot: MediaLot::Book,
+            source: MediaSource::GoogleBooks,
+            identifier: "abc".to_owned(),
+            title: "Hobbit, The (Pirated Edition)".to_owned(),
+            sort_title_override: Some("Hobbit".to_owned()),
+            publish_year: Some(1937),
+            images: Some(vec![MetadataImage {
+                url: StoredUrl::S3("metadata-images/cover.png".to_owned()),
+                is_user_provided: true,
+            }]),
+            book_specifics: Some(BookSpecifics { pages: None }),
+            ..Default::default()
+        }
+    }
+
+    fn openlibrary_book() -> MetadataDetails {
+        MetadataDetails {
+            lot: MediaLot::Book,
+            source: MediaSource::Openlibrary,
+            identifier: "OL262758W".to_owned(),
+            title: "The Hobbit".to_owned(),
+            publish_year: Some(1937),
+            book_specifics: Some(BookSpecifics { pages: Some(310) }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn swap_previews_only_the_fields_that_change() {
+        let meta = garbage_book();
+        let swapped = swapped_metadata(&meta, &openlibrary_book(), "Hobbit".to_owned(), Utc::now());
+        let changes = metadata_source_changes(&meta, &swapped);
+        assert_eq!(
+            changes.iter().map(|c| c.field.as_str()).collect_vec(),
+            ["source", "identifier", "title", "specifics"]
+        );
+        assert_eq!(changes[0].before.as_deref(), Some("GoogleBooks"));
+        assert_eq!(changes[0].after.as_deref(), Some("Openlibrary"));
+        assert_eq!(changes[2].after.as_deref(), Some("The Hobbit"));
+        assert_eq!(changes[3].after.as_deref(), Some(r#"{"pages":310}"#));
+    }
+
+    #[test]
+    fn swapping_keeps_the_row_that_user_data_is_attached_to() {
+        let meta = metadata::Model {
+            created_by_user_id: Some("user_1".to_owned()),
+            ..garbage_book()
+        };
+        let swapped = swapped_metadata(&meta, &openlibrary_book(), "Hobbit".to_owned(), Utc::now());
+        // DEV: Seen items, reviews and collections reference the id, which must not change
+        assert_eq!(swapped.id, meta.id);
+        assert_eq!(swapped.created_on, meta.created_on);
+        assert_eq!(swapped.created_by_user_id, meta.created_by_user_id);
+        assert_eq!(swapped.sort_title_override, meta.sort_title_override);
+        assert_eq!(swapped.images, meta.images);
+        assert_eq!(
+            (swapped.source, swapped.identifier.as_str()),
+            (MediaSource::Openlibrary, "OL262758W")
+        );
+        // DEV: Only the changed columns are written, the rest is left to other updates
+        let changed = metadata::Column::iter()
+            .filter(|c| meta.get(*c) != swapped.get(*c))
+            .map(|c| format!("{:?}", c))
+            .collect_vec();
+        assert_eq!(
+            changed,
+            [
+                "LastUpdatedOn",
+                "Title",
+                "SortTitle",
+                "Identifier",
+                "Source",
+                "IsPartial",
+                "Videos",
+                "BookSpecifics"
+            ]
+        );
+    }
+
+    #[test]
+    fn sources_with_another_type_of_media_are_rejected() {
+        assert!(check_swapped_lot(MediaLot::Book, MediaLot::Book).is_ok());
+        let error = check_swapped_lot(MediaLot::Book, MediaLot::AudioBook).unwrap_err();
+        assert_eq!(
+            error.message,
+            "The new source has a AudioBook while the media item is a Book"
+        );
+    }
+
+    #[test]
+    fn reviews_of_people_point_at_the_person() {
+        let mut review = review::ActiveModel::default();
+        set_review_entity(&mut review, EntityLot::Person, "person_1".to_owned());
+        assert_eq!(
+            review.person_id,
+            ActiveValue::Set(Some("person_1".to_owned()))
+        );
+        assert_eq!(review.metadata_id, ActiveValue::NotSet);
+    }
+
+    fn author(id: &str, name: &str) -> person::Model {
+        person::Model {
+            id: id.to_owned(),
+            identifier: id.to_owned(),
+            source: MediaSource::Openlibrary,
+            created_on: Default::default(),
+            last_updated_on: Default::default(),
+            name: name.to_owned(),
+            is_partial: None,
+            images: None,
+            display_images: vec![],
+            description: None,
+            gender: None,
+            birth_date: None,
+            death_date: None,
+            place: None,
+            website: None,
+            source_specifics: None,
+            state_changes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn followers_hear_about_new_works_of_people_they_collect() {
+        let already_linked = vec!["person_2".to_owned()];
+        let new_people = newly_linked_people(
+            vec!["person_1".to_owned(), "person_2".to_owned()],
+            &already_linked,
+        );
+        assert_eq!(new_people, ["person_1"]);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![
+                tuple_row(vec![Value::from("user_1"), Value::from("person_1")]),
+                tuple_row(vec![Value::from("user_2"), Value::from("person_1")]),
+            ]])
+            .append_query_results([vec![metadata::Model {
+                title: "The Silmarillion".to_owned(),
+                ..Default::default()
+            }]])
+            .append_query_results([vec![author("person_1", "J. R. R. Tolkien")]])
+            .into_connection();
+        let notifications = new_creator_work_notifications(&"meta_1".to_owned(), new_people, &db)
+            .await
+            .unwrap();
+        let message = "J. R. R. Tolkien has a new work: The Silmarillion".to_owned();
+        assert_eq!(
+            notifications,
+            [
+                (
+                    "user_1".to_owned(),
+                    (message.clone(), MediaStateChanged::PersonMediaAssociated)
+                ),
+                (
+                    "user_2".to_owned(),
+                    (message, MediaStateChanged::PersonMediaAssociated)
+                ),
+            ]
+        );
+        let log = db.into_transaction_log();
+        assert!(log[0].statements()[0]
+            .sql
+            .contains(r#"INNER JOIN "collection""#));
+    }
+
+    #[tokio::test]
+    async fn nobody_is_notified_when_no_people_are_new() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let notifications = new_creator_work_notifications(&"meta_1".to_owned(), vec![], &db)
+            .await
+            .unwrap();
+        assert!(notifications.is_empty());
+        assert!(db.into_transaction_log().is_empty());
+    }
+}
@@ -25,7 +25,7 @@ export interface ImportOrExportItemReview {
 	date: string | null;
 	/** Whether to mark the review as a spoiler. Defaults to false. */
 	spoiler: boolean | null;
-	/** Actual text for the review. */
+	/** Actual text for the review, in Markdown. */
 	text: string | null;
 	/**
 	 * The visibility set by the user.
@@ -118,6 +118,10 @@ export interface ImportOrExportMediaItemSeen {
 	manga_chapter_number: string | null;
 	/** If for a manga, the volume which was seen. */
 	manga_volume_number: number | null;
+	/** If for a movie or audiobook, the number of minutes consumed. */
+	minutes_consumed: number | null;
+	/** If for a book, the number of pages read. */
+	pages_read: number | null;
 	/** If for a podcast, the episode which was seen. */
 	podcast_episode_number: number | null;
 	/** The progress of media done. If none, it is considered as done. */
@@ -361,6 +365,8 @@ export interface CompleteExport {
 	media_groups: ImportOrExportMediaGroupItem[] | null;
 	/** Data about user's people. */
 	people: ImportOrExportPersonItem[] | null;
+	/** Version of the export format. Missing in exports made before it was added. */
+	version: number | null;
 	/** Data about user's workout templates. */
 	workout_templates: ImportOrExportWorkoutTemplateItem[] | null;
 	/** Data about user's workouts. */
@@ -382,6 +382,10 @@ export enum DashboardElementLot {
   Upcoming = 'UPCOMING'
 }
 
+export type DeployAnilistImportInput = {
+  username: Scalars['String']['input'];
+};
+
 export type DeployGenericCsvImportInput = {
   csvPath: Scalars['String']['input'];
 };
@@ -392,10 +396,12 @@ export type DeployIgdbImportInput = {
 };
 
 export type DeployImportJobInput = {
+  anilist?: InputMaybe<DeployAnilistImportInput>;
   genericCsv?: InputMaybe<DeployGenericCsvImportInput>;
   genericJson?: InputMaybe<DeployJsonImportInput>;
   igdb?: InputMaybe<DeployIgdbImportInput>;
   jellyfin?: InputMaybe<DeployUrlAndKeyAndUsernameImportInput>;
+  letterboxd?: InputMaybe<DeployLetterboxdImportInput>;
   mal?: InputMaybe<DeployMalImportInput>;
   movary?: InputMaybe<DeployMovaryImportInput>;
   source: ImportSource;
@@ -408,6 +414,14 @@ export type DeployJsonImportInput = {
   export: Scalars['String']['input'];
 };
 
+export type DeployLetterboxdImportInput = {
+  export?: InputMaybe<Scalars['String']['input']>;
+  ratings?: InputMaybe<Scalars['String']['input']>;
+  reviews?: InputMaybe<Scalars['String']['input']>;
+  watched?: InputMaybe<Scalars['String']['input']>;
+  watchlist?: InputMaybe<Scalars['String']['input']>;
+};
+
 export type DeployMalImportInput = {
   /** The anime export file path (uploaded via temporary upload). */
   animePath?: InputMaybe<Scalars['String']['input']>;
@@ -827,12 +841,14 @@ export type ImportResultResponse = {
 };
 
 export enum ImportSource {
+  Anilist = 'ANILIST',
   Audiobookshelf = 'AUDIOBOOKSHELF',
   GenericJson = 'GENERIC_JSON',
   Goodreads = 'GOODREADS',
   Igdb = 'IGDB',
   Imdb = 'IMDB',
   Jellyfin = 'JELLYFIN',
+  Letterboxd = 'LETTERBOXD',
   Mal = 'MAL',
   MediaTracker = 'MEDIA_TRACKER',
   Movary = 'MOVARY',
@@ -2481,6 +2497,7 @@ export type UserPreferences = {
 
 export enum UserReviewScale {
   OutOfFive = 'OUT_OF_FIVE',
+  OutOfTen = 'OUT_OF_TEN',
   OutOfHundred = 'OUT_OF_HUNDRED',
   ThreePointSmiley = 'THREE_POINT_SMILEY'
 }